@@ -262,6 +262,7 @@ async fn main() -> anyhow::Result<()> {
                 let user_id = profile.name.to_lowercase();
 
                 let settings = scheduler.settings().await;
+                let policy = scheduler.policy().await;
                 let timeout = Duration::from_secs(settings.queue_timeout_secs);
 
                 let start = Instant::now();
@@ -270,8 +271,10 @@ async fn main() -> anyhow::Result<()> {
                     .acquire_with_timeout(
                         model_id,
                         &user_id,
+                        None,
                         &db,
                         &settings,
+                        policy.as_ref(),
                         scheduler.queue(),
                         timeout,
                     )
@@ -403,9 +406,13 @@ async fn main() -> anyhow::Result<()> {
         // Recalculate priorities periodically (not every frame — DB queries)
         if last_priority_update.elapsed() > Duration::from_secs(1) {
             let settings = scheduler.settings().await;
+            let policy = scheduler.policy().await;
             for profile in &users {
                 let uid = profile.name.to_lowercase();
-                if let Ok(p) = fairness::calculate_user_priority(&db, &settings, &uid, 0.0).await {
+                if let Ok(p) =
+                    fairness::calculate_user_priority(&db, &settings, policy.as_ref(), &uid, 0.0, 0)
+                        .await
+                {
                     if let Some(s) = user_stats.get_mut(&profile.name) {
                         s.priority = p;
                     }
@@ -0,0 +1,6 @@
+-- Selectable fairness policy (weighted_usage | round_robin | token_bucket)
+-- plus token-bucket tuning. Defaults preserve the original behaviour.
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('fairness_policy',                'weighted_usage'),
+    ('fairness_bucket_capacity',       '50000.0'),
+    ('fairness_bucket_refill_per_sec', '100.0');
@@ -0,0 +1,17 @@
+-- Persisted queue entries. Rows are written when a request has to wait for a
+-- concurrency slot and resolved when it is served or times out. Rows still
+-- 'waiting' at startup were dropped by a restart and are marked 'interrupted'.
+CREATE TABLE IF NOT EXISTS queue_log (
+    request_id  TEXT PRIMARY KEY NOT NULL,
+    user_id     TEXT NOT NULL,
+    model_id    TEXT NOT NULL,
+    enqueued_at TEXT NOT NULL,
+    status      TEXT NOT NULL DEFAULT 'waiting'
+                CHECK (status IN ('waiting', 'served', 'timed_out', 'interrupted')),
+    wait_ms     INTEGER,
+    resolved_at TEXT
+);
+
+CREATE INDEX IF NOT EXISTS idx_queue_log_status ON queue_log(status);
+CREATE INDEX IF NOT EXISTS idx_queue_log_user ON queue_log(user_id, status);
+CREATE INDEX IF NOT EXISTS idx_queue_log_enqueued ON queue_log(enqueued_at);
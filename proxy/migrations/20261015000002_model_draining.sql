@@ -0,0 +1,3 @@
+-- Per-model drain flag: when set, new inference requests are rejected with
+-- `model_draining` while in-flight requests finish. Cleared on container stop.
+ALTER TABLE models ADD COLUMN draining INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,6 @@
+-- Bind OIDC login state to the client that started the flow. Both columns are
+-- salted SHA-256 hashes (NULL for rows created before this migration, which
+-- skip the check): the User-Agent, and the client's network prefix (/24 for
+-- IPv4, /64 for IPv6).
+ALTER TABLE oidc_auth_state ADD COLUMN user_agent_hash TEXT;
+ALTER TABLE oidc_auth_state ADD COLUMN network_hash TEXT;
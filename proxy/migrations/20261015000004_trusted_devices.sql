@@ -0,0 +1,21 @@
+-- "Remember this device": a long-lived refresh token bound to a named device
+-- record. Redeeming it mints a normal short-lived session (linked via
+-- sessions.device_id) and rotates the token.
+CREATE TABLE IF NOT EXISTS trusted_devices (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL REFERENCES users(id),
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    user_agent TEXT,
+    last_used_at TEXT,
+    expires_at TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_trusted_devices_user ON trusted_devices(user_id);
+CREATE INDEX IF NOT EXISTS idx_trusted_devices_expires ON trusted_devices(expires_at);
+
+ALTER TABLE sessions ADD COLUMN device_id TEXT REFERENCES trusted_devices(id);
+
+-- Whether the login that created this state asked to remember the device
+ALTER TABLE oidc_auth_state ADD COLUMN remember_device INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Optional cap on the number of requests a token may make over its lifetime
+-- (counted from usage_log). NULL = unlimited. Used by quickstart tokens.
+ALTER TABLE tokens ADD COLUMN request_quota INTEGER;
@@ -0,0 +1,4 @@
+-- Token exchange: short-lived tokens minted from a portal session for an
+-- embedded third-party UI. `exchange_client` names the requesting app; set
+-- only on exchanged tokens, which are hidden from the user's token list.
+ALTER TABLE tokens ADD COLUMN exchange_client TEXT;
@@ -0,0 +1,29 @@
+-- Persisted HuggingFace downloads so an interrupted pull can be resumed.
+-- `files` is the JSON file filter (NULL = whole repo); `backend_type` is
+-- NULL when the backend is picked from the primary file after download.
+CREATE TABLE IF NOT EXISTS downloads (
+    id TEXT PRIMARY KEY,
+    hf_repo TEXT NOT NULL,
+    files TEXT,
+    category_id TEXT,
+    backend_type TEXT,
+    status TEXT NOT NULL DEFAULT 'downloading',
+    error TEXT,
+    progress_bytes INTEGER NOT NULL DEFAULT 0,
+    total_bytes INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_downloads_status ON downloads(status);
+
+-- Per-file progress. `bytes_done` is the size of the `.part` file when the
+-- download last stopped; complete files are skipped on resume.
+CREATE TABLE IF NOT EXISTS download_files (
+    download_id TEXT NOT NULL REFERENCES downloads(id) ON DELETE CASCADE,
+    path TEXT NOT NULL,
+    size_bytes INTEGER,
+    bytes_done INTEGER NOT NULL DEFAULT 0,
+    complete INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (download_id, path)
+);
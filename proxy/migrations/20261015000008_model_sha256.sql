@@ -0,0 +1,4 @@
+-- SHA-256 of each downloaded file, verified against the repo's LFS hash.
+-- `models.sha256` is the primary file's digest for later integrity audits.
+ALTER TABLE models ADD COLUMN sha256 TEXT;
+ALTER TABLE download_files ADD COLUMN sha256 TEXT;
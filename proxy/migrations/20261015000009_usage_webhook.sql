@@ -0,0 +1,8 @@
+-- Hourly usage delta deliveries to USAGE_WEBHOOK_URL. `period_start` is the
+-- RFC 3339 start of the hour; `delivered_at` stays NULL until a POST succeeds.
+CREATE TABLE IF NOT EXISTS usage_webhook_deliveries (
+    period_start TEXT PRIMARY KEY,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    delivered_at TEXT
+);
@@ -0,0 +1,3 @@
+-- Per-model idle timeout in minutes before the container is stopped.
+-- NULL uses the `idle_unload_minutes` setting; 0 never unloads the model.
+ALTER TABLE models ADD COLUMN idle_unload_minutes INTEGER;
@@ -0,0 +1,23 @@
+-- Stored /v1 completion requests for admin replay (REQUEST_LOG=true).
+-- `response_body` is NULL for streamed responses. `replay_of` links a
+-- replayed request to the original it re-ran.
+CREATE TABLE IF NOT EXISTS request_log (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL,
+    token_id TEXT,
+    model_id TEXT NOT NULL,
+    category_id TEXT,
+    endpoint TEXT NOT NULL,
+    request_body TEXT NOT NULL,
+    response_status INTEGER NOT NULL,
+    response_body TEXT,
+    latency_ms INTEGER NOT NULL DEFAULT 0,
+    replay_of TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_request_log_created ON request_log(created_at);
+
+-- Usage from admin replays is attributed to the admin and marked, so it can
+-- be excluded from billing and quotas.
+ALTER TABLE usage_log ADD COLUMN replay_of TEXT;
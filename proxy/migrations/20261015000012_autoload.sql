@@ -0,0 +1,7 @@
+-- Lazy loading: a request for an unloaded model starts its container when
+-- autoload is enabled on the model or its category. The container is started
+-- with the parameters of its last manual start (JSON: gpu_type, gpu_layers,
+-- parallel); models that were never started have none and are not autoloaded.
+ALTER TABLE models ADD COLUMN autoload INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE models ADD COLUMN launch_params TEXT;
+ALTER TABLE model_categories ADD COLUMN autoload INTEGER NOT NULL DEFAULT 0;
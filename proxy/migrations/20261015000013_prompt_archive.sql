@@ -0,0 +1,28 @@
+-- Opt-in prompt/response archive (PROMPT_ARCHIVE=true). Bodies are stored
+-- once per SHA-256 in `archive_blobs`; entries reference them by hash.
+CREATE TABLE IF NOT EXISTS archive_blobs (
+    hash TEXT PRIMARY KEY NOT NULL,
+    content TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- `visibility` is 'private' (owner and admins) or 'shared' (all users).
+-- `response_hash` is NULL for streamed responses.
+CREATE TABLE IF NOT EXISTS prompt_archive (
+    id TEXT PRIMARY KEY NOT NULL,
+    user_id TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    endpoint TEXT NOT NULL,
+    prompt_hash TEXT NOT NULL REFERENCES archive_blobs(hash),
+    response_hash TEXT REFERENCES archive_blobs(hash),
+    params TEXT NOT NULL DEFAULT '{}',
+    seed INTEGER,
+    visibility TEXT NOT NULL DEFAULT 'private',
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_prompt_archive_user ON prompt_archive(user_id);
+CREATE INDEX IF NOT EXISTS idx_prompt_archive_prompt ON prompt_archive(prompt_hash);
+CREATE INDEX IF NOT EXISTS idx_prompt_archive_response ON prompt_archive(response_hash);
+CREATE INDEX IF NOT EXISTS idx_prompt_archive_created ON prompt_archive(created_at);
@@ -0,0 +1,3 @@
+-- Sampling seed sent by the client, for reproducing completions.
+ALTER TABLE usage_log ADD COLUMN seed INTEGER;
+ALTER TABLE request_log ADD COLUMN seed INTEGER;
@@ -0,0 +1,12 @@
+-- Preferred container launch settings per model. Start requests fall back to
+-- these for any field they omit. NULL fields use the built-in defaults.
+CREATE TABLE IF NOT EXISTS model_launch_profiles (
+    model_id TEXT PRIMARY KEY NOT NULL REFERENCES models(id) ON DELETE CASCADE,
+    backend_type TEXT,
+    gpu_type TEXT,
+    gpu_layers INTEGER,
+    context_size INTEGER,
+    parallel INTEGER,
+    updated_by TEXT,
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
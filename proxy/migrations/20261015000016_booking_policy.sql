@@ -0,0 +1,10 @@
+-- Reservation booking policy. Defaults preserve the original rules:
+-- 30-minute slots, 30-minute minimum, no other limits, admin approval.
+-- 0 disables the max duration, advance and pending limits.
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('reservation_slot_minutes',         '30'),
+    ('reservation_min_minutes',          '30'),
+    ('reservation_max_minutes',          '0'),
+    ('reservation_max_advance_days',     '0'),
+    ('reservation_max_pending_per_user', '0'),
+    ('reservation_auto_approve',         'false');
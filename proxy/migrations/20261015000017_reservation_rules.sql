@@ -0,0 +1,21 @@
+-- Auto-approval rules for reservation requests. A new, conflict-free request
+-- is approved by the first enabled rule whose conditions all hold; NULL
+-- conditions are not checked.
+CREATE TABLE reservation_rules (
+    id                   TEXT PRIMARY KEY NOT NULL,
+    name                 TEXT NOT NULL,
+    enabled              INTEGER NOT NULL DEFAULT 1,
+    -- Longest reservation the rule approves
+    max_duration_minutes INTEGER,
+    -- UTC hours [hours_start, hours_end) the whole reservation must fall in;
+    -- hours_start > hours_end wraps midnight (18 → 8 is "outside 8-18")
+    hours_start          INTEGER,
+    hours_end            INTEGER,
+    -- Comma-separated weekdays (0 = Monday) the whole reservation must fall on
+    weekdays             TEXT,
+    -- The user must have had no reservation rejected in this many days
+    good_standing_days   INTEGER,
+    created_by           TEXT REFERENCES users(id) ON DELETE SET NULL,
+    created_at           TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at           TEXT NOT NULL DEFAULT (datetime('now'))
+);
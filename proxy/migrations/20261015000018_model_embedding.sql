@@ -0,0 +1,3 @@
+-- Embedding models: served on /v1/embeddings, and llama.cpp containers for
+-- them start with --embeddings.
+ALTER TABLE models ADD COLUMN embedding INTEGER NOT NULL DEFAULT 0;
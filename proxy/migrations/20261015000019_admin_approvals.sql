@@ -0,0 +1,20 @@
+-- Two-person approval for high-impact admin actions. With
+-- TWO_PERSON_APPROVAL on, the action is recorded here and only runs once a
+-- second admin approves it before expires_at.
+CREATE TABLE admin_approvals (
+    id           TEXT PRIMARY KEY NOT NULL,
+    -- 'model.delete' or 'user.elevate'
+    action       TEXT NOT NULL,
+    resource_id  TEXT NOT NULL,
+    -- JSON options the action runs with (e.g. {"override": true})
+    params       TEXT NOT NULL DEFAULT '{}',
+    requested_by TEXT REFERENCES users(id) ON DELETE SET NULL,
+    -- pending → approved → executed | failed, or pending → rejected | expired
+    status       TEXT NOT NULL DEFAULT 'pending',
+    decided_by   TEXT REFERENCES users(id) ON DELETE SET NULL,
+    created_at   TEXT NOT NULL DEFAULT (datetime('now')),
+    expires_at   TEXT NOT NULL,
+    decided_at   TEXT
+);
+
+CREATE INDEX idx_admin_approvals_status ON admin_approvals(status, expires_at);
@@ -0,0 +1,42 @@
+-- Built-in vector store. Each user owns their collections; documents are
+-- split into chunks embedded with the collection's embedding model.
+CREATE TABLE vector_collections (
+    id          TEXT PRIMARY KEY NOT NULL,
+    user_id     TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name        TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    -- Model name resolved like the `model` field of /v1/embeddings
+    model       TEXT NOT NULL,
+    -- Embedding size, fixed by the first ingested document
+    dimensions  INTEGER,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE(user_id, name)
+);
+
+CREATE TABLE vector_documents (
+    id            TEXT PRIMARY KEY NOT NULL,
+    collection_id TEXT NOT NULL REFERENCES vector_collections(id) ON DELETE CASCADE,
+    name          TEXT NOT NULL,
+    -- Caller-supplied JSON object returned with query results
+    metadata      TEXT NOT NULL DEFAULT '{}',
+    chunk_count   INTEGER NOT NULL DEFAULT 0,
+    created_at    TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX idx_vector_documents_collection ON vector_documents(collection_id);
+
+CREATE TABLE vector_chunks (
+    id            INTEGER PRIMARY KEY AUTOINCREMENT,
+    collection_id TEXT NOT NULL REFERENCES vector_collections(id) ON DELETE CASCADE,
+    document_id   TEXT NOT NULL REFERENCES vector_documents(id) ON DELETE CASCADE,
+    chunk_index   INTEGER NOT NULL,
+    content       TEXT NOT NULL,
+    -- L2-normalised little-endian f32 vector
+    embedding     BLOB NOT NULL,
+    -- Random-hyperplane LSH signature used to narrow large searches
+    bucket        INTEGER NOT NULL
+);
+
+CREATE INDEX idx_vector_chunks_bucket ON vector_chunks(collection_id, bucket);
+CREATE INDEX idx_vector_chunks_document ON vector_chunks(document_id);
@@ -0,0 +1,3 @@
+-- GPU indices a backend container is pinned to, as a JSON array.
+-- NULL means the container may use every GPU.
+ALTER TABLE container_secrets ADD COLUMN devices TEXT;
@@ -0,0 +1,18 @@
+-- Admin-defined system prompt prefixes injected into chat completions.
+-- A policy with neither a category nor a token applies to every request;
+-- otherwise both set conditions must match.
+CREATE TABLE IF NOT EXISTS system_prompt_policies (
+    id TEXT PRIMARY KEY NOT NULL,
+    name TEXT NOT NULL,
+    prompt TEXT NOT NULL,
+    category_id TEXT REFERENCES model_categories(id) ON DELETE CASCADE,
+    token_id TEXT REFERENCES tokens(id) ON DELETE CASCADE,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_by TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Tokens excluded from every policy (e.g. evaluation harnesses that need
+-- the raw model).
+ALTER TABLE tokens ADD COLUMN system_prompt_opt_out INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,14 @@
+-- Before/after values of admin configuration updates (settings, IdPs,
+-- models, categories). Audit log events reference a row by `diff_id`.
+-- Secret values are redacted before they are stored.
+CREATE TABLE IF NOT EXISTS config_changes (
+    id TEXT PRIMARY KEY NOT NULL,
+    action TEXT NOT NULL,
+    actor TEXT NOT NULL,
+    resource TEXT,
+    -- JSON array of {"field", "before", "after"}
+    changes TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_config_changes_created ON config_changes(created_at);
@@ -0,0 +1,31 @@
+-- Overlapping reservation requests can join a waitlist. SQLite can't alter a
+-- CHECK constraint, so the table is rebuilt with the new status.
+CREATE TABLE reservations_new (
+    id           TEXT PRIMARY KEY NOT NULL,
+    user_id      TEXT NOT NULL REFERENCES users(id),
+    status       TEXT NOT NULL DEFAULT 'pending'
+                 CHECK(status IN ('pending','approved','active','completed','rejected','cancelled','waitlisted')),
+    start_time   TEXT NOT NULL,  -- ISO 8601, 30-min boundary
+    end_time     TEXT NOT NULL,  -- ISO 8601, 30-min boundary
+    reason       TEXT NOT NULL DEFAULT '',
+    admin_note   TEXT NOT NULL DEFAULT '',
+    approved_by  TEXT REFERENCES users(id),
+    created_at   TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at   TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+INSERT INTO reservations_new
+    (id, user_id, status, start_time, end_time, reason, admin_note, approved_by, created_at, updated_at)
+SELECT id, user_id, status, start_time, end_time, reason, admin_note, approved_by, created_at, updated_at
+FROM reservations;
+
+DROP TABLE reservations;
+ALTER TABLE reservations_new RENAME TO reservations;
+
+CREATE INDEX idx_reservations_user   ON reservations(user_id);
+CREATE INDEX idx_reservations_status ON reservations(status);
+CREATE INDEX idx_reservations_time   ON reservations(start_time, end_time);
+CREATE INDEX idx_reservations_active ON reservations(status, start_time, end_time)
+    WHERE status IN ('approved', 'active');
+CREATE INDEX idx_reservations_waitlist ON reservations(created_at)
+    WHERE status = 'waitlisted';
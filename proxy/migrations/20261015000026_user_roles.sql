@@ -0,0 +1,10 @@
+-- Scoped admin roles. Users with `is_admin` keep full access; a role grants
+-- one area of the admin API (see auth::rbac for what each one covers).
+CREATE TABLE IF NOT EXISTS user_roles (
+    user_id TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    role TEXT NOT NULL
+        CHECK(role IN ('model_admin', 'user_admin', 'reservation_admin', 'viewer')),
+    granted_by TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (user_id, role)
+);
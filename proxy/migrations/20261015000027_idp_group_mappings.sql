@@ -0,0 +1,5 @@
+-- OIDC group → role mapping. When `groups_claim` is set, every login reads
+-- that ID token claim and replaces the user's admin flag and roles with what
+-- `group_mappings` (JSON: group → ["admin" | role, ...]) grants.
+ALTER TABLE idp_configs ADD COLUMN groups_claim TEXT;
+ALTER TABLE idp_configs ADD COLUMN group_mappings TEXT NOT NULL DEFAULT '{}';
@@ -0,0 +1,17 @@
+-- Deleted models awaiting purge. `model_row` and `launch_profile` are JSON
+-- copies of the deleted rows, written back on restore. `trash_path` is NULL
+-- until the files have been moved (and stays NULL for models without files).
+CREATE TABLE IF NOT EXISTS model_trash (
+    id TEXT PRIMARY KEY NOT NULL,
+    model_id TEXT NOT NULL,
+    hf_repo TEXT NOT NULL,
+    model_row TEXT NOT NULL CHECK (json_valid(model_row)),
+    launch_profile TEXT,
+    trash_path TEXT,
+    size_bytes INTEGER NOT NULL DEFAULT 0,
+    deleted_by TEXT,
+    deleted_at TEXT NOT NULL DEFAULT (datetime('now')),
+    purge_after TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_model_trash_purge ON model_trash(purge_after);
@@ -0,0 +1,9 @@
+-- User deprovisioning. Inactive users can't sign in and their sessions and
+-- API tokens are rejected; deactivating also deletes the sessions and
+-- revokes the tokens. An IdP with a SCIM token can deactivate its users
+-- through /scim/v2 (the token is stored as a SHA-256 hash).
+ALTER TABLE users ADD COLUMN active INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE users ADD COLUMN deactivated_at TEXT;
+ALTER TABLE idp_configs ADD COLUMN scim_token_hash TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_idp_configs_scim_token
+    ON idp_configs(scim_token_hash) WHERE scim_token_hash IS NOT NULL;
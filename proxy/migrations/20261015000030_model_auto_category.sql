@@ -0,0 +1,12 @@
+-- Auto-categorization (AUTO_CATEGORIZE). A category can claim a detected
+-- model kind (`embedding`, `rerank`, `vision`, `code`, `reasoning`, `chat`).
+-- Models keep the GGUF architecture and Hugging Face pipeline tag seen at
+-- download, and in suggest mode the proposed category waiting for an admin.
+ALTER TABLE model_categories ADD COLUMN auto_kind TEXT;
+ALTER TABLE models ADD COLUMN architecture TEXT;
+ALTER TABLE models ADD COLUMN pipeline_tag TEXT;
+ALTER TABLE models ADD COLUMN suggested_category_id TEXT;
+ALTER TABLE models ADD COLUMN category_suggestion_reason TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_model_categories_auto_kind
+    ON model_categories(auto_kind) WHERE auto_kind IS NOT NULL;
@@ -0,0 +1,14 @@
+-- Admin-defined model names for the OpenAI API. A request for `alias`
+-- (case-insensitive) is routed to the model or category it points at, so
+-- clients that hard-code names like `gpt-4o` keep working when the model
+-- behind them changes. Exactly one of model_id / category_id is set.
+CREATE TABLE IF NOT EXISTS model_aliases (
+    id TEXT PRIMARY KEY NOT NULL,
+    alias TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    model_id TEXT REFERENCES models(id) ON DELETE CASCADE,
+    category_id TEXT REFERENCES model_categories(id) ON DELETE CASCADE,
+    created_by TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    CHECK ((model_id IS NULL) != (category_id IS NULL))
+);
@@ -0,0 +1,12 @@
+-- Per-category request logging: 'none', 'metadata' (who, what model, status,
+-- latency and token counts, no bodies) or 'full' (bodies too). NULL follows
+-- REQUEST_LOG ('full' when true, 'none' when false).
+ALTER TABLE model_categories ADD COLUMN log_level TEXT;
+
+-- What each entry captured. Metadata-only entries store an empty request
+-- body and can't be replayed.
+ALTER TABLE request_log ADD COLUMN log_level TEXT NOT NULL DEFAULT 'full';
+ALTER TABLE request_log ADD COLUMN input_tokens INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE request_log ADD COLUMN output_tokens INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX IF NOT EXISTS idx_request_log_user ON request_log(user_id);
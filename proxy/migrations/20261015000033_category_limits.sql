@@ -0,0 +1,4 @@
+-- Per-category concurrency gate limits, shared by all of a category's models.
+-- NULL means no limit beyond each model's own slots and the queue timeout.
+ALTER TABLE model_categories ADD COLUMN max_concurrent INTEGER;
+ALTER TABLE model_categories ADD COLUMN max_queue_depth INTEGER;
@@ -0,0 +1,13 @@
+-- Temporary full capture of a model's requests for debugging. While a trace
+-- is live (`remaining` > 0 and before `expires_at`), each completion request
+-- to the model is stored in request_log at 'full' level, with end-user
+-- identifiers stripped, whatever its category's log level says.
+CREATE TABLE IF NOT EXISTS model_traces (
+    model_id TEXT PRIMARY KEY NOT NULL REFERENCES models(id) ON DELETE CASCADE,
+    remaining INTEGER NOT NULL,
+    expires_at TEXT NOT NULL,
+    created_by TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+ALTER TABLE request_log ADD COLUMN traced INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Explicit queue priority per user on top of the fairness score: 'low',
+-- 'normal' or 'high' (e.g. instructors above students).
+ALTER TABLE users ADD COLUMN priority_tier TEXT NOT NULL DEFAULT 'normal';
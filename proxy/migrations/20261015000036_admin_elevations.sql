@@ -0,0 +1,18 @@
+-- Time-boxed admin rights. A user asks for admin for duration_minutes; once
+-- a full admin approves, their sessions count as admin until expires_at.
+CREATE TABLE admin_elevations (
+    id               TEXT PRIMARY KEY NOT NULL,
+    user_id          TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    reason           TEXT NOT NULL DEFAULT '',
+    duration_minutes INTEGER NOT NULL,
+    -- pending → active → expired | revoked, or
+    -- pending → rejected | withdrawn | expired
+    status           TEXT NOT NULL DEFAULT 'pending',
+    decided_by       TEXT REFERENCES users(id) ON DELETE SET NULL,
+    created_at       TEXT NOT NULL DEFAULT (datetime('now')),
+    -- While pending: when the request lapses. Once active: when admin ends.
+    expires_at       TEXT NOT NULL,
+    decided_at       TEXT
+);
+
+CREATE INDEX idx_admin_elevations_user ON admin_elevations(user_id, status, expires_at);
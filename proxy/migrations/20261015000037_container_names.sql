@@ -0,0 +1,4 @@
+-- Name of the container serving each model. A blue/green replace alternates
+-- between sovereign-<backend>-<id> and the same name with -green; NULL means
+-- the default name (rows from before this column).
+ALTER TABLE container_secrets ADD COLUMN container_name TEXT;
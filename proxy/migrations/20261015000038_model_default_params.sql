@@ -0,0 +1,6 @@
+-- Per-model sampling defaults (temperature, top_p, min_p, repeat_penalty)
+-- filled into /v1 completion requests that don't set them. See
+-- proxy/src/api/sampling_defaults.rs. '{}' means no defaults.
+ALTER TABLE models
+ADD COLUMN default_params TEXT NOT NULL DEFAULT '{}'
+CHECK (json_valid(default_params));
@@ -0,0 +1,6 @@
+-- What happens to a prompt longer than the model's context: 'reject' with
+-- context_length_exceeded, or 'truncate' the oldest chat messages. See
+-- proxy/src/api/context_guard.rs.
+ALTER TABLE models
+ADD COLUMN context_policy TEXT NOT NULL DEFAULT 'reject'
+CHECK (context_policy IN ('reject', 'truncate'));
@@ -0,0 +1,4 @@
+-- Per-category queue timeout and default parallel slots. NULL follows the
+-- global queue_timeout_secs setting and the built-in default of 1 slot.
+ALTER TABLE model_categories ADD COLUMN queue_timeout_secs INTEGER;
+ALTER TABLE model_categories ADD COLUMN default_parallel INTEGER;
@@ -0,0 +1,5 @@
+-- Global download bandwidth limit (MB/s, 0 = none) and the daily UTC window
+-- in which it is lifted ('' = no window, the limit always applies).
+INSERT OR IGNORE INTO settings (key, value) VALUES
+    ('download_max_mb_per_sec',    '0'),
+    ('download_full_speed_window', '');
@@ -0,0 +1,15 @@
+-- Feature flags for gradual rollout of proxy changes. A flag is on for a
+-- user when it is enabled and the user is listed in user_ids (a JSON array
+-- of user IDs) or falls inside rollout_percent. See
+-- proxy/src/feature_flags.rs.
+CREATE TABLE IF NOT EXISTS feature_flags (
+    name TEXT PRIMARY KEY NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    enabled INTEGER NOT NULL DEFAULT 0,
+    rollout_percent INTEGER NOT NULL DEFAULT 0
+        CHECK (rollout_percent BETWEEN 0 AND 100),
+    user_ids TEXT NOT NULL DEFAULT '[]' CHECK (json_valid(user_ids)),
+    created_by TEXT,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
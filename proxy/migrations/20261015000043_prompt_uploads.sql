@@ -0,0 +1,25 @@
+-- Chunked prompt uploads. Clients too large for one request body upload a
+-- prompt in PATCH chunks, then pass its id as `prompt_id` on
+-- /v1/chat/completions or /v1/completions. See
+-- proxy/src/api/prompt_uploads.rs.
+CREATE TABLE prompt_uploads (
+    id          TEXT PRIMARY KEY NOT NULL,
+    user_id     TEXT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    -- Bytes received so far; the next chunk must start here
+    size_bytes  INTEGER NOT NULL DEFAULT 0,
+    -- Set once the client marks the upload finished
+    completed_at TEXT,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    expires_at  TEXT NOT NULL
+);
+
+CREATE INDEX idx_prompt_uploads_user ON prompt_uploads(user_id);
+CREATE INDEX idx_prompt_uploads_expires ON prompt_uploads(expires_at);
+
+CREATE TABLE prompt_upload_chunks (
+    upload_id  TEXT NOT NULL REFERENCES prompt_uploads(id) ON DELETE CASCADE,
+    -- Byte offset of this chunk within the prompt
+    start_byte INTEGER NOT NULL,
+    data       BLOB NOT NULL,
+    PRIMARY KEY (upload_id, start_byte)
+);
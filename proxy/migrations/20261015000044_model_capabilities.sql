@@ -0,0 +1,5 @@
+-- Capabilities verified by probing a model's backend once it is healthy:
+-- JSON with the endpoints that answered, the context length the backend
+-- accepts and the embedding dimension. NULL until the first probe. See
+-- proxy/src/api/capabilities.rs.
+ALTER TABLE models ADD COLUMN capabilities TEXT;
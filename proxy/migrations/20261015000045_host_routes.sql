@@ -0,0 +1,11 @@
+-- Hostnames served besides API_HOSTNAME and CHAT_HOSTNAME. `target` is
+-- `api`, `chat`, `site:<dir under SITES_PATH>` or `redirect:<url>`. See
+-- proxy/src/host_routes.rs.
+CREATE TABLE IF NOT EXISTS host_routes (
+    hostname    TEXT PRIMARY KEY NOT NULL,
+    target      TEXT NOT NULL,
+    description TEXT NOT NULL DEFAULT '',
+    created_by  TEXT,
+    created_at  TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at  TEXT NOT NULL DEFAULT (datetime('now'))
+);
@@ -0,0 +1,4 @@
+-- When the model's directory was last uploaded to the S3 model store
+-- (MODEL_STORE_S3). Only stored models are evicted from the local cache.
+-- See proxy/src/storage/mod.rs.
+ALTER TABLE models ADD COLUMN stored_remote_at TEXT;
@@ -0,0 +1,6 @@
+-- Models whose files were deleted from disk after going unused for the
+-- `model_evict_unused_days` setting. The row stays so the model keeps its
+-- ID, category and aliases; downloading the repo again restores it. See
+-- proxy/src/storage/mod.rs.
+ALTER TABLE models ADD COLUMN downloaded INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE models ADD COLUMN evicted_at TEXT;
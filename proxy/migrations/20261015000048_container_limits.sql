@@ -0,0 +1,5 @@
+-- Per-model container resource limits in the launch profile. NULL leaves the
+-- resource unconstrained.
+ALTER TABLE model_launch_profiles ADD COLUMN cpu_limit REAL;
+ALTER TABLE model_launch_profiles ADD COLUMN memory_limit_mb INTEGER;
+ALTER TABLE model_launch_profiles ADD COLUMN pids_limit INTEGER;
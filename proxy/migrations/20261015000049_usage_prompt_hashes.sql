@@ -0,0 +1,5 @@
+-- Prompt hashes for the deduplication statistics (PROMPT_DEDUP_STATS).
+-- prompt_hash covers everything that decides the response; prompt_norm_hash
+-- only the prompt text, case- and whitespace-folded. NULL when not recorded.
+ALTER TABLE usage_log ADD COLUMN prompt_hash TEXT;
+ALTER TABLE usage_log ADD COLUMN prompt_norm_hash TEXT;
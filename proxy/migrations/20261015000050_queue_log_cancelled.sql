@@ -0,0 +1,24 @@
+-- Requests whose client disconnected while waiting are resolved as
+-- 'cancelled' instead of being left 'waiting' and later reported as
+-- interrupted by a restart. SQLite can't alter a CHECK constraint, so the
+-- table is rebuilt.
+CREATE TABLE queue_log_new (
+    request_id  TEXT PRIMARY KEY NOT NULL,
+    user_id     TEXT NOT NULL,
+    model_id    TEXT NOT NULL,
+    enqueued_at TEXT NOT NULL,
+    status      TEXT NOT NULL DEFAULT 'waiting'
+                CHECK (status IN ('waiting', 'served', 'timed_out', 'interrupted', 'cancelled')),
+    wait_ms     INTEGER,
+    resolved_at TEXT
+);
+
+INSERT INTO queue_log_new (request_id, user_id, model_id, enqueued_at, status, wait_ms, resolved_at)
+SELECT request_id, user_id, model_id, enqueued_at, status, wait_ms, resolved_at FROM queue_log;
+
+DROP TABLE queue_log;
+ALTER TABLE queue_log_new RENAME TO queue_log;
+
+CREATE INDEX IF NOT EXISTS idx_queue_log_status ON queue_log(status);
+CREATE INDEX IF NOT EXISTS idx_queue_log_user ON queue_log(user_id, status);
+CREATE INDEX IF NOT EXISTS idx_queue_log_enqueued ON queue_log(enqueued_at);
@@ -0,0 +1,493 @@
+//! Structured access log, separate from `tracing`.
+//!
+//! One JSON object per request: timestamp, method, route, user/token id,
+//! status, duration and request/response byte counts. Bodies and query strings
+//! are never read or recorded, so prompt content cannot end up in the log.
+//!
+//! Entries are written to a size-rotated file (`ACCESS_LOG_PATH`), POSTed in
+//! NDJSON batches to a webhook for SIEM ingestion (`ACCESS_LOG_WEBHOOK_URL`),
+//! or both. Logging never blocks a request: if the writer falls behind,
+//! entries are dropped and counted.
+
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use axum::body::{Body, Bytes, HttpBody};
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+use hyper::body::{Frame, SizeHint};
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::auth::{AuthUser, SessionAuth};
+use crate::config::AppConfig;
+
+/// Entries buffered between request handlers and the writer task.
+const CHANNEL_CAPACITY: usize = 4096;
+/// Max entries per webhook POST.
+const WEBHOOK_BATCH: usize = 100;
+/// Webhook flush interval for partial batches.
+const WEBHOOK_FLUSH: Duration = Duration::from_secs(5);
+
+/// One access log record.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccessLogEntry {
+    pub timestamp: String,
+    pub method: String,
+    /// Request path only — the query string is dropped.
+    pub route: String,
+    pub user_id: Option<String>,
+    pub token_id: Option<String>,
+    pub status: u16,
+    /// Time until the response body finished (or the client went away).
+    pub duration_ms: u64,
+    /// Request `content-length`, 0 when absent.
+    pub bytes_in: u64,
+    /// Response body bytes actually sent.
+    pub bytes_out: u64,
+}
+
+/// Handle used by the middleware to submit entries.
+#[derive(Clone)]
+pub struct AccessLog {
+    tx: mpsc::Sender<AccessLogEntry>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl AccessLog {
+    /// Start the writer task if a file path or webhook is configured.
+    pub async fn spawn(config: &AppConfig) -> Option<Self> {
+        if config.access_log_path.is_none() && config.access_log_webhook_url.is_none() {
+            return None;
+        }
+
+        let file = match &config.access_log_path {
+            Some(path) => match RotatingFile::open(
+                PathBuf::from(path),
+                config.access_log_max_bytes,
+                config.access_log_max_files,
+            )
+            .await
+            {
+                Ok(f) => Some(f),
+                Err(e) => {
+                    warn!(path = %path, error = %e, "Failed to open access log file");
+                    None
+                }
+            },
+            None => None,
+        };
+        let webhook = config.access_log_webhook_url.clone();
+        if file.is_none() && webhook.is_none() {
+            return None;
+        }
+
+        info!(
+            file = ?config.access_log_path,
+            webhook = webhook.is_some(),
+            "Access log enabled"
+        );
+
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(run_writer(rx, file, webhook));
+        Some(Self {
+            tx,
+            dropped: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    fn submit(&self, entry: AccessLogEntry) {
+        if self.tx.try_send(entry).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            if dropped.is_power_of_two() {
+                warn!(dropped, "Access log writer is behind; dropping entries");
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Middleware
+// ---------------------------------------------------------------------------
+
+/// Identity filled in by [`tag_identity`] once an auth middleware has run.
+#[derive(Debug, Clone, Default)]
+struct Identity {
+    user_id: Option<String>,
+    token_id: Option<String>,
+}
+
+/// Shared slot the outer access-log middleware reads after the inner auth
+/// layers have identified the caller.
+#[derive(Clone, Default)]
+pub struct IdentitySlot(Arc<Mutex<Identity>>);
+
+/// Outermost middleware: time the request and log it when the response body
+/// completes (so streaming durations and byte counts are accurate).
+pub async fn middleware(State(log): State<AccessLog>, mut req: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let timestamp = chrono::Utc::now().to_rfc3339();
+    let method = req.method().to_string();
+    let route = req.uri().path().to_string();
+    let bytes_in = req
+        .headers()
+        .get("content-length")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let slot = IdentitySlot::default();
+    req.extensions_mut().insert(slot.clone());
+
+    let response = next.run(req).await;
+    let status = response.status().as_u16();
+    let (parts, body) = response.into_parts();
+
+    let pending = PendingEntry {
+        log,
+        slot,
+        started,
+        timestamp,
+        method,
+        route,
+        status,
+        bytes_in,
+        bytes_out: 0,
+    };
+    Response::from_parts(
+        parts,
+        Body::new(LoggedBody {
+            inner: body,
+            pending,
+        }),
+    )
+}
+
+/// Inner middleware, layered inside an auth middleware: copy the
+/// authenticated user/token into the access log slot.
+pub async fn tag_identity(req: Request, next: Next) -> Response {
+    if let Some(slot) = req.extensions().get::<IdentitySlot>() {
+        let identity = if let Some(user) = req.extensions().get::<AuthUser>() {
+            Identity {
+                user_id: Some(user.user_id.clone()),
+                token_id: Some(user.token_id.clone()),
+            }
+        } else if let Some(session) = req.extensions().get::<SessionAuth>() {
+            Identity {
+                user_id: Some(session.user_id.clone()),
+                token_id: None,
+            }
+        } else {
+            Identity::default()
+        };
+        if let Ok(mut guard) = slot.0.lock() {
+            *guard = identity;
+        }
+    }
+    next.run(req).await
+}
+
+/// Entry under construction; submitted when dropped with its body.
+struct PendingEntry {
+    log: AccessLog,
+    slot: IdentitySlot,
+    started: Instant,
+    timestamp: String,
+    method: String,
+    route: String,
+    status: u16,
+    bytes_in: u64,
+    bytes_out: u64,
+}
+
+impl Drop for PendingEntry {
+    fn drop(&mut self) {
+        let identity = self
+            .slot
+            .0
+            .lock()
+            .map(|guard| guard.clone())
+            .unwrap_or_default();
+        self.log.submit(AccessLogEntry {
+            timestamp: std::mem::take(&mut self.timestamp),
+            method: std::mem::take(&mut self.method),
+            route: std::mem::take(&mut self.route),
+            user_id: identity.user_id,
+            token_id: identity.token_id,
+            status: self.status,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            bytes_in: self.bytes_in,
+            bytes_out: self.bytes_out,
+        });
+    }
+}
+
+/// Response body wrapper that counts bytes without touching their contents.
+struct LoggedBody {
+    inner: Body,
+    pending: PendingEntry,
+}
+
+impl HttpBody for LoggedBody {
+    type Data = Bytes;
+    type Error = axum::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let polled = Pin::new(&mut self.inner).poll_frame(cx);
+        if let Poll::Ready(Some(Ok(frame))) = &polled {
+            if let Some(data) = frame.data_ref() {
+                self.pending.bytes_out += data.len() as u64;
+            }
+        }
+        polled
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Writer
+// ---------------------------------------------------------------------------
+
+async fn run_writer(
+    mut rx: mpsc::Receiver<AccessLogEntry>,
+    mut file: Option<RotatingFile>,
+    webhook: Option<String>,
+) {
+    let client = reqwest::Client::new();
+    let mut batch: Vec<String> = Vec::new();
+    let mut flush = tokio::time::interval(WEBHOOK_FLUSH);
+    flush.tick().await; // first tick is immediate — skip it
+
+    loop {
+        tokio::select! {
+            entry = rx.recv() => {
+                let Some(entry) = entry else { break };
+                let line = match serde_json::to_string(&entry) {
+                    Ok(l) => l,
+                    Err(e) => {
+                        warn!(error = %e, "Failed to serialize access log entry");
+                        continue;
+                    }
+                };
+                if let Some(f) = file.as_mut() {
+                    if let Err(e) = f.write_line(&line).await {
+                        warn!(error = %e, "Failed to write access log");
+                    }
+                }
+                if let Some(url) = &webhook {
+                    batch.push(line);
+                    if batch.len() >= WEBHOOK_BATCH {
+                        post_batch(&client, url, &mut batch).await;
+                    }
+                }
+            }
+            _ = flush.tick() => {
+                if let Some(url) = &webhook {
+                    if !batch.is_empty() {
+                        post_batch(&client, url, &mut batch).await;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// POST a batch as NDJSON. Failed batches are dropped rather than retried so a
+/// dead SIEM endpoint can't grow memory without bound.
+async fn post_batch(client: &reqwest::Client, url: &str, batch: &mut Vec<String>) {
+    let mut body = batch.join("\n");
+    body.push('\n');
+    let count = batch.len();
+    batch.clear();
+
+    match client
+        .post(url)
+        .header("content-type", "application/x-ndjson")
+        .timeout(Duration::from_secs(10))
+        .body(body)
+        .send()
+        .await
+    {
+        Ok(resp) if resp.status().is_success() => {}
+        Ok(resp) => warn!(status = %resp.status(), count, "Access log webhook rejected batch"),
+        Err(e) => warn!(error = %e, count, "Access log webhook unreachable; batch dropped"),
+    }
+}
+
+/// Append-only file rotated by size: `access.log` → `access.log.1` → … →
+/// `access.log.<max_files>`, oldest deleted.
+struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: u32,
+    file: tokio::fs::File,
+    size: u64,
+}
+
+impl RotatingFile {
+    async fn open(path: PathBuf, max_bytes: u64, max_files: u32) -> std::io::Result<Self> {
+        let file = open_append(&path).await?;
+        let size = file.metadata().await?.len();
+        Ok(Self {
+            path,
+            max_bytes,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    async fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.max_bytes > 0 && self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate().await?;
+        }
+        self.file.write_all(line.as_bytes()).await?;
+        self.file.write_all(b"\n").await?;
+        self.size += len;
+        Ok(())
+    }
+
+    async fn rotate(&mut self) -> std::io::Result<()> {
+        self.file.flush().await?;
+        if self.max_files == 0 {
+            tokio::fs::remove_file(&self.path).await?;
+        } else {
+            for i in (1..self.max_files).rev() {
+                let from = numbered(&self.path, i);
+                if tokio::fs::try_exists(&from).await.unwrap_or(false) {
+                    tokio::fs::rename(&from, numbered(&self.path, i + 1)).await?;
+                }
+            }
+            tokio::fs::rename(&self.path, numbered(&self.path, 1)).await?;
+        }
+        self.file = open_append(&self.path).await?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+async fn open_append(path: &Path) -> std::io::Result<tokio::fs::File> {
+    tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+}
+
+fn numbered(path: &Path, n: u32) -> PathBuf {
+    let mut s = path.as_os_str().to_owned();
+    s.push(format!(".{n}"));
+    PathBuf::from(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::post;
+    use axum::{middleware as axum_middleware, Router};
+    use tower::ServiceExt;
+
+    fn test_log() -> (AccessLog, mpsc::Receiver<AccessLogEntry>) {
+        let (tx, rx) = mpsc::channel(16);
+        (
+            AccessLog {
+                tx,
+                dropped: Arc::new(AtomicU64::new(0)),
+            },
+            rx,
+        )
+    }
+
+    async fn fake_auth(mut req: Request, next: Next) -> Response {
+        req.extensions_mut().insert(SessionAuth {
+            user_id: "user-1".into(),
+            is_admin: false,
+            roles: Vec::new(),
+            email: None,
+            display_name: None,
+        });
+        next.run(req).await
+    }
+
+    #[tokio::test]
+    async fn logs_route_identity_and_sizes_without_query_or_body() {
+        let (log, mut rx) = test_log();
+        let app = Router::new()
+            .route("/v1/echo", post(|| async { "hello" }))
+            .layer(axum_middleware::from_fn(tag_identity))
+            .layer(axum_middleware::from_fn(fake_auth))
+            .layer(axum_middleware::from_fn_with_state(log, middleware));
+
+        let response = app
+            .oneshot(
+                axum::http::Request::builder()
+                    .method("POST")
+                    .uri("/v1/echo?secret=abc")
+                    .header("content-length", "17")
+                    .body(Body::from("{\"prompt\":\"hi!\"}\n"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"hello");
+
+        let entry = rx.recv().await.unwrap();
+        assert_eq!(entry.method, "POST");
+        assert_eq!(entry.route, "/v1/echo");
+        assert_eq!(entry.user_id.as_deref(), Some("user-1"));
+        assert_eq!(entry.token_id, None);
+        assert_eq!(entry.status, 200);
+        assert_eq!(entry.bytes_in, 17);
+        assert_eq!(entry.bytes_out, 5);
+
+        let json = serde_json::to_string(&entry).unwrap();
+        assert!(!json.contains("secret"));
+        assert!(!json.contains("prompt"));
+    }
+
+    #[tokio::test]
+    async fn rotates_when_size_exceeded() {
+        let dir = std::env::temp_dir().join(format!("access-log-{}", uuid::Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("access.log");
+
+        let mut file = RotatingFile::open(path.clone(), 20, 2).await.unwrap();
+        for line in [
+            "first-line-xxxxxx",
+            "second-line-xxxxx",
+            "third-line-xxxxxx",
+        ] {
+            file.write_line(line).await.unwrap();
+        }
+        file.file.flush().await.unwrap();
+
+        let current = tokio::fs::read_to_string(&path).await.unwrap();
+        let one = tokio::fs::read_to_string(numbered(&path, 1)).await.unwrap();
+        let two = tokio::fs::read_to_string(numbered(&path, 2)).await.unwrap();
+        assert_eq!(current, "third-line-xxxxxx\n");
+        assert_eq!(one, "second-line-xxxxx\n");
+        assert_eq!(two, "first-line-xxxxxx\n");
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
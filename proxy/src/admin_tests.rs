@@ -13,6 +13,155 @@
 //! - **delete_model_override_soft_deletes_tokens_and_succeeds** — same setup
 //!   with `?override=true` → 200, token soft-deleted (revoked+deleted_at),
 //!   pin nulled, model row gone.
+//! - **delete_model_dry_run_reports_without_deleting** — `?dry_run=true`
+//!   with an active pin and files on disk → 200 listing the token and files;
+//!   model, token and files untouched.
+//! - **deleted_model_restored_from_trash** — with files on disk the delete
+//!   moves them to the trash; restore puts the row and files back and spends
+//!   the entry.
+//!
+//! ## stop_container — POST /api/admin/containers/stop
+//!
+//! - **stop_container_dry_run_reports_plan** — `?dry_run=true` → 200 with
+//!   the container name and stop mode, no Docker call made.
+//! - **stop_container_dry_run_names_replacement** — after a blue/green
+//!   replace recorded the `-green` container, the plan names that one.
+//!
+//! ## replace_container — POST /api/admin/containers/{model_id}/replace
+//!
+//! - **replace_container_requires_loaded_model** — unknown model → 404, a
+//!   model that isn't loaded → 409, both before any Docker call.
+//!
+//! ## system drain — POST /api/admin/system/drain, /api/admin/system/resume
+//!
+//! - **system_drain_and_resume** — with nothing in flight the drain reports
+//!   `drained`; a second drain keeps the first one's start time and reason;
+//!   resume clears it.
+//!
+//! ## two-person approval — /api/admin/approvals
+//!
+//! - **model_delete_waits_for_second_admin** — with `TWO_PERSON_APPROVAL`
+//!   the delete returns 202 and leaves the model; the requester can't approve
+//!   it, a second admin's approval deletes it and marks the request executed.
+//! - **admin_grant_rejected_by_second_admin** — granting admin returns 202
+//!   without changing the user; after a rejection the request can no longer
+//!   be approved.
+//! - **user_admin_role_grant_waits_for_admin** — a `user_admin` granting
+//!   `user_admin` gets 202 and the roles are unchanged until a full admin
+//!   approves; a `viewer` grant applies straight away.
+//! - **demoted_admin_jwt_rejected** — after an admin's rights are revoked
+//!   the JWTs minted for them fail verification; other users' JWTs still
+//!   verify.
+//!
+//! ## launch profiles — GET/PUT /api/admin/models/{id}/launch-profile
+//!
+//! - **launch_profile_round_trips** — PUT then GET returns the saved fields,
+//!   resource limits included; a second PUT replaces the profile; a zero
+//!   `pids_limit` → 400.
+//! - **launch_profile_rejects_context_beyond_model** — context_size above the
+//!   model's context_length → 400, nothing saved.
+//!
+//! ## category gate defaults — /api/admin/categories
+//!
+//! - **category_queue_timeout_and_default_parallel** — `default_parallel: 0`
+//!   → 400; a category's `queue_timeout_secs` reaches the gate on create and
+//!   is cleared again by `null`.
+//!
+//! ## scoped roles — /api/admin/users/{id}/roles
+//!
+//! - **roles_limit_admin_routes** — a `model_admin` can change models and read
+//!   other areas, but not change users, roles or settings.
+//! - **user_roles_round_trip** — PUT replaces a user's roles and GET returns
+//!   them; unknown roles → 400; a `user_admin` can't change their own roles.
+//!
+//! ## identity providers — /api/admin/idps
+//!
+//! - **idp_group_mappings_validated_and_listed** — group mappings are stored
+//!   and listed as an object; unknown grants → 400; an empty `groups_claim`
+//!   turns group sync off.
+//! - **scim_deactivation_revokes_access** — a SCIM token issued to an IdP
+//!   finds its users by `userName`; patching `active: false` invalidates the
+//!   user's API token and session, reactivating doesn't restore the token,
+//!   other IdPs' users → 404 and an admin can't deactivate themselves.
+//!
+//! ## sessions — /api/admin/users/{id}/revoke-sessions
+//!
+//! - **revoke_sessions_logs_user_out** — deletes all of the user's sessions
+//!   and trusted devices but keeps their API tokens; unknown user → 404.
+//!
+//! ## auto-categorization — /api/admin/models/categorize
+//!
+//! - **auto_categorize_suggests_and_accepts** — a category claiming
+//!   `embedding` is suggested for an embedding model and matched by name for
+//!   a coder model; a model of unknown kind is left out; accepting assigns
+//!   the category, dismissing clears the suggestion; a second category can't
+//!   claim the same kind.
+//!
+//! ## bulk token revocation — /api/admin/tokens/revoke
+//!
+//! - **bulk_revoke_dry_run_then_revoke** — no filter or a bad date → 400; a
+//!   dry run lists the user's old token and revokes nothing; the real call
+//!   revokes only that token.
+//!
+//! ## model aliases — /api/admin/aliases
+//!
+//! - **model_alias_crud** — an alias needs exactly one known target, names
+//!   are unique regardless of case, an update can repoint it, and deleting
+//!   its model removes it.
+//!
+//! ## sampling defaults — /api/admin/models/{id}/default-params
+//!
+//! - **default_params_round_trip** — unknown model → 404, out-of-range values
+//!   → 400 and unknown keys → 422, nothing saved; a valid PUT shows in GET and
+//!   in the model list, and `{}` clears it.
+//!
+//! ## hygiene reports — /api/admin/hygiene
+//!
+//! - **hygiene_report_and_cleanup** — lists the old unused token, the
+//!   deactivated user's session, the unrequested old model and both empty
+//!   categories; each cleanup acts only on those, and a category with an
+//!   access mapping is skipped; `days=0` → 400.
+//!
+//! ## feature flags — /api/admin/feature-flags
+//!
+//! - **feature_flag_targeting_and_rollout** — a bad name or a rollout over
+//!   100 → 400; a targeted user sees the flag at 0%, everyone does at 100%,
+//!   nobody once it is disabled or deleted.
+//!
+//! ## signed download URLs — POST /api/user/signed-urls
+//!
+//! - **signed_url_downloads_without_session** — with no cookie the export is
+//!   401, the signed URL returns it, an edited signature → 403, and the URL
+//!   stops working once the user is deactivated.
+//!
+//! ## config export/import — /api/admin/export, /api/admin/import
+//!
+//! - **config_export_import_round_trip** — an export carries the category,
+//!   model, profile, alias and setting but not the plaintext IdP secret; a
+//!   dry run into a fresh instance reports creates and writes nothing, the
+//!   real import creates everything, a second import skips it all and
+//!   `on_conflict: "fail"` → 409; an unknown version → 400.
+//! - **config_import_skips_secrets_from_another_key** — an IdP secret
+//!   encrypted under another instance's key is imported empty with the IdP
+//!   disabled, and the import reports a warning.
+//!
+//! ## host routes — /api/admin/host-routes
+//!
+//! - **host_route_crud_updates_dispatch_cache** — a bad target, a missing
+//!   site directory → 400 and a built-in hostname → 409; a saved route is
+//!   listed and looked up by the dispatcher, and deleting it removes it.
+//!
+//! ## prompt deduplication — /api/admin/usage/dedup
+//!
+//! - **prompt_dedup_counts_repeats_per_model** — a repeated prompt counts as
+//!   a repeat and a TTL hit only within its own model, a case change only as
+//!   a near repeat, and unhashed usage is left out.
+//!
+//! ## operator issues — /api/admin/issues
+//!
+//! - **issues_report_stale_reservation_requests** — pending reservations
+//!   older than a day are reported together, newer ones are not, and the
+//!   list is ordered by severity.
 
 use std::sync::Arc;
 
@@ -23,13 +172,18 @@ use axum::Router;
 use serde_json::Value;
 use tower::ServiceExt;
 
-use crate::api::admin;
-use crate::auth::SessionAuth;
+use crate::api::{
+    admin, approvals, categorize, config_bundle, container_replace, conversations, feature_flags,
+    host_routes, hygiene, issues, model_aliases, model_trash, prompt_dedup, sampling_defaults,
+    token_revocation,
+};
+use crate::auth::rbac::Role;
+use crate::auth::{self, signed_url, SessionAuth};
 use crate::config::AppConfig;
 use crate::db::Database;
 use crate::docker::DockerManager;
-use crate::metrics::MetricsBroadcaster;
-use crate::scheduler::reservation::ReservationBroadcaster;
+use crate::events::EventBus;
+use crate::proxy::pool::{BackendClients, PoolSettings};
 use crate::scheduler::Scheduler;
 use crate::AppState;
 
@@ -50,9 +204,11 @@ fn test_config() -> AppConfig {
         model_path: "/tmp/test-models-admin-tests".to_string(),
         model_host_path: "/tmp/test-models-admin-tests".to_string(),
         ui_path: "/tmp/test-ui".to_string(),
+        sites_path: "/tmp/test-sites".to_string(),
         api_hostname: "localhost".to_string(),
         chat_hostname: "localhost".to_string(),
         cookie_domain: None,
+        session_redis_url: None,
         backend_network: "test-network".to_string(),
         acme_contact: None,
         acme_staging: false,
@@ -62,32 +218,128 @@ fn test_config() -> AppConfig {
         secure_cookies: false,
         db_encryption_key: None,
         db_encryption_key_old: None,
+        graceful_stop_timeout_secs: 120,
+        backend_pool_max_idle: 32,
+        backend_pool_idle_timeout_secs: 90,
+        backend_connect_timeout_secs: 5,
+        upstream_protocols: Default::default(),
+        sse_keepalive_secs: 15,
+        access_log_path: None,
+        access_log_max_bytes: 100 * 1024 * 1024,
+        access_log_max_files: 5,
+        access_log_webhook_url: None,
+        auth_rate_limit_per_minute: 0,
+        auth_rate_limit_burst: 10,
+        trust_forwarded_for: false,
+        trusted_proxy_hops: 1,
+        oidc_state_binding: "strict".to_string(),
+        trusted_device_ttl_days: 30,
+        jwt_access_tokens: false,
+        jwt_signing_key: None,
+        jwt_ttl_secs: 300,
+        token_cache_ttl_secs: 0,
+        token_cache_max_entries: 10_000,
+        model_name_normalize: true,
+        model_name_strip_prefixes: vec!["openai/".to_string()],
+        model_name_suggestions: true,
+        usage_webhook_url: None,
+        usage_webhook_secret: None,
+        usage_webhook_max_retries: 5,
+        event_webhook_url: None,
+        event_webhook_secret: None,
+        request_log: false,
+        autoload_timeout_secs: 300,
+        watchdog_interval_secs: 30,
+        watchdog_max_restarts: 3,
+        backend_warmup: false,
+        prompt_archive: false,
+        prompt_archive_retention_days: 90,
+        prompt_dedup_stats: false,
+        two_person_approval: false,
+        approval_window_minutes: 60,
+        sandbox_enabled: false,
+        sandbox_image: "python:3.12-alpine".to_string(),
+        sandbox_memory_mb: 256,
+        sandbox_cpus: 1.0,
+        sandbox_timeout_secs: 10,
+        sandbox_max_concurrent: 4,
+        backend_tls: false,
+        backend_tls_dir: "/config/backend-tls".to_string(),
+        backend_tls_host_path: "/config/backend-tls".to_string(),
+        host_memory_ceiling_percent: 0,
+        memory_critical_percent: 95,
+        memory_pressure_admission: false,
+        download_background_mb_per_sec: 0,
+        hf_download_connections: 1,
+        hf_download_chunk_mb: 64,
+        model_trash_hours: 24,
+        model_trash_max_gb: 200,
+        model_import_path: None,
+        model_store_s3: None,
+        s3_endpoint: None,
+        s3_region: "us-east-1".to_string(),
+        s3_access_key_id: None,
+        s3_secret_access_key: None,
+        model_cache_max_gb: 0,
+        gpu_probe: false,
+        auto_categorize: "off".to_string(),
+        request_log_retention_days: 30,
+        signed_url_key: None,
+        signed_url_max_ttl_secs: 3600,
     }
 }
 
 async fn test_app_state() -> Arc<AppState> {
+    test_app_state_with(test_config()).await
+}
+
+async fn test_app_state_with(config: AppConfig) -> Arc<AppState> {
     let db = Database::test_db().await;
+    let jwt = auth::jwt::JwtIssuer::from_config(&config);
     Arc::new(AppState {
-        config: test_config(),
+        backends: BackendClients::new(PoolSettings::from_config(&config)),
+        config,
+        sessions: crate::auth::sessions::Sessions::sqlite(&db),
         db,
         docker: DockerManager::test_dummy(),
         scheduler: Scheduler::new(),
-        metrics: MetricsBroadcaster::new(),
-        reservations: ReservationBroadcaster::new(),
+        events: EventBus::new(),
+        jwt,
+        token_cache: crate::auth::token_cache::TokenCache::new(std::time::Duration::ZERO, 0),
+        signed_urls: crate::auth::signed_url::UrlSigner::new(b"test".to_vec(), 3600),
+        tasks: crate::tasks::TaskScheduler::new(),
+        flags: crate::feature_flags::FeatureFlags::default(),
+        host_routes: crate::host_routes::HostRoutes::default(),
+        watchdog: crate::watchdog::Watchdog::default(),
+        warmups: crate::api::warmup::Warmups::default(),
+        download_progress: crate::api::hf::DownloadProgress::default(),
+        replacing: crate::api::container_replace::Replacing::default(),
+        effective_config: None,
+        storage: crate::storage::Storage::default(),
+        autoloads: crate::api::common::Autoloads::default(),
+        ui_integrity: crate::ui_integrity::UiIntegrity::default(),
     })
 }
 
 /// Build an admin-scoped router with a fake session-auth middleware that
 /// injects a `SessionAuth` carrying admin privileges.
 fn admin_router(state: Arc<AppState>, user_id: &str) -> Router {
+    session_router(state, user_id, true, &[])
+}
+
+/// Like `admin_router`, for a session with the given admin flag and roles.
+fn session_router(state: Arc<AppState>, user_id: &str, is_admin: bool, roles: &[Role]) -> Router {
     let user_id = user_id.to_string();
+    let roles = roles.to_vec();
     let auth_layer = middleware::from_fn(
         move |mut req: axum::extract::Request, next: axum::middleware::Next| {
             let user_id = user_id.clone();
+            let roles = roles.clone();
             async move {
                 req.extensions_mut().insert(SessionAuth {
                     user_id,
-                    is_admin: true,
+                    is_admin,
+                    roles,
                     email: None,
                     display_name: None,
                 });
@@ -97,7 +349,23 @@ fn admin_router(state: Arc<AppState>, user_id: &str) -> Router {
     );
 
     Router::new()
-        .nest("/admin", admin::routes(state))
+        .nest(
+            "/admin",
+            admin::routes(state.clone())
+                .merge(model_trash::admin_routes(state.clone()))
+                .merge(container_replace::admin_routes(state.clone()))
+                .merge(categorize::admin_routes(state.clone()))
+                .merge(model_aliases::admin_routes(state.clone()))
+                .merge(sampling_defaults::admin_routes(state.clone()))
+                .merge(token_revocation::admin_routes(state.clone()))
+                .merge(hygiene::admin_routes(state.clone()))
+                .merge(feature_flags::admin_routes(state.clone()))
+                .merge(config_bundle::admin_routes(state.clone()))
+                .merge(host_routes::admin_routes(state.clone()))
+                .merge(prompt_dedup::admin_routes(state.clone()))
+                .merge(issues::admin_routes(state.clone()))
+                .merge(approvals::admin_routes(state)),
+        )
         .layer(auth_layer)
 }
 
@@ -189,6 +457,28 @@ async fn json_delete(router: &Router, uri: &str) -> (StatusCode, Value) {
     (status, json)
 }
 
+async fn json_request(
+    router: &Router,
+    method: &str,
+    uri: &str,
+    body: Value,
+) -> (StatusCode, Value) {
+    let req = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+
+    let resp = router.clone().oneshot(req).await.unwrap();
+    let status = resp.status();
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+    (status, json)
+}
+
 async fn model_exists(pool: &sqlx::Pool<sqlx::Sqlite>, id: &str) -> bool {
     let row: Option<(String,)> = sqlx::query_as("SELECT id FROM models WHERE id = ?")
         .bind(id)
@@ -342,3 +632,1683 @@ async fn delete_model_override_soft_deletes_tokens_and_succeeds() {
     assert_eq!(revoked, 1, "token should be revoked");
     assert!(deleted_at.is_some(), "token should be soft-deleted");
 }
+
+#[tokio::test]
+async fn delete_model_dry_run_reports_without_deleting() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    insert_model(&state.db.pool, "model-dry", "owner/dry-GGUF").await;
+    insert_pinned_token(
+        &state.db.pool,
+        "tok-dry",
+        "user1",
+        "model-dry",
+        "live-token",
+        /* revoked */ false,
+        /* soft_deleted */ false,
+    )
+    .await;
+    let model_dir = format!("{}/owner--dry-GGUF", state.config.model_path);
+    std::fs::create_dir_all(&model_dir).unwrap();
+    std::fs::write(format!("{model_dir}/model.gguf"), b"12345").unwrap();
+
+    let router = admin_router(state.clone(), "admin1");
+    let (status, body) = json_delete(
+        &router,
+        "/admin/models/model-dry?override=true&dry_run=true",
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["dry_run"], true);
+    assert_eq!(body["revoked_tokens"][0]["id"], "tok-dry");
+    assert_eq!(body["cleared_pins"], 0);
+    assert!(body["container"].is_null(), "model isn't loaded");
+    assert_eq!(body["files"][0]["path"], "model.gguf");
+    assert_eq!(body["total_bytes"], 5);
+
+    // Nothing should have been mutated.
+    assert!(model_exists(&state.db.pool, "model-dry").await);
+    let (specific, revoked, deleted_at) = get_token_state(&state.db.pool, "tok-dry").await;
+    assert_eq!(specific.as_deref(), Some("model-dry"));
+    assert_eq!(revoked, 0);
+    assert!(deleted_at.is_none());
+    assert!(std::path::Path::new(&format!("{model_dir}/model.gguf")).exists());
+    std::fs::remove_dir_all(&model_dir).unwrap();
+}
+
+#[tokio::test]
+async fn stop_container_dry_run_reports_plan() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    insert_model(&state.db.pool, "model-stop", "owner/stop-GGUF").await;
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/containers/stop?dry_run=true",
+        serde_json::json!({ "model_id": "model-stop", "timeout_secs": 30 }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["dry_run"], true);
+    assert_eq!(body["container"], "sovereign-llamacpp-model-stop");
+    assert_eq!(body["mode"], "graceful");
+    assert_eq!(body["in_flight"], 0);
+    assert_eq!(body["drain_timeout_secs"], 30);
+}
+
+#[tokio::test]
+async fn stop_container_dry_run_names_replacement() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    insert_model(&state.db.pool, "model-green", "owner/green-GGUF").await;
+    sqlx::query("UPDATE models SET loaded = 1 WHERE id = 'model-green'")
+        .execute(&state.db.pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO container_secrets (model_id, container_uid, api_key, parallel_slots, container_name) VALUES ('model-green', 10001, 'key', 1, 'sovereign-llamacpp-model-green-green')",
+    )
+    .execute(&state.db.pool)
+    .await
+    .unwrap();
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/containers/stop?dry_run=true",
+        serde_json::json!({ "model_id": "model-green" }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["loaded"], true);
+    assert_eq!(body["container"], "sovereign-llamacpp-model-green-green");
+}
+
+#[tokio::test]
+async fn replace_container_requires_loaded_model() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    insert_model(&state.db.pool, "model-idle", "owner/idle-GGUF").await;
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, _) = json_request(
+        &router,
+        "POST",
+        "/admin/containers/no-such-model/replace",
+        serde_json::json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/containers/model-idle/replace",
+        serde_json::json!({ "parallel": 4 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body["error"].as_str().unwrap().contains("not loaded"));
+}
+
+#[tokio::test]
+async fn system_drain_and_resume() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/system/drain",
+        serde_json::json!({ "reason": "kernel upgrade", "timeout_secs": 0, "retry_after_secs": 120 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["drained"], true);
+    assert_eq!(body["drain"]["reason"], "kernel upgrade");
+    assert_eq!(body["drain"]["retry_after_secs"], 120);
+    let started_at = body["drain"]["started_at"].clone();
+
+    // A second drain keeps the original one
+    let (_, body) = json_request(
+        &router,
+        "POST",
+        "/admin/system/drain",
+        serde_json::json!({ "timeout_secs": 0 }),
+    )
+    .await;
+    assert_eq!(body["drain"]["started_at"], started_at);
+    assert_eq!(body["drain"]["reason"], "kernel upgrade");
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/system/resume",
+        serde_json::json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["was_draining"], true);
+    assert!(state.scheduler.drain().await.is_none());
+}
+
+fn approval_config() -> AppConfig {
+    AppConfig {
+        two_person_approval: true,
+        ..test_config()
+    }
+}
+
+#[tokio::test]
+async fn model_delete_waits_for_second_admin() {
+    let state = test_app_state_with(approval_config()).await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    ensure_test_user(&state.db.pool, "admin2").await;
+    insert_model(&state.db.pool, "model-2p", "owner/2p-GGUF").await;
+    let admin1 = admin_router(state.clone(), "admin1");
+    let admin2 = admin_router(state.clone(), "admin2");
+
+    let (status, body) = json_delete(&admin1, "/admin/models/model-2p").await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(body["status"], "pending_approval");
+    assert_eq!(body["approval"]["action"], "model.delete");
+    let approval_id = body["approval"]["id"].as_str().unwrap().to_string();
+    assert!(model_exists(&state.db.pool, "model-2p").await);
+
+    // Asking again returns the same pending request
+    let (status, body) = json_delete(&admin1, "/admin/models/model-2p").await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(body["approval"]["id"], approval_id.as_str());
+
+    let uri = format!("/admin/approvals/{approval_id}/approve");
+    let (status, _) = json_request(&admin1, "POST", &uri, Value::Null).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(model_exists(&state.db.pool, "model-2p").await);
+
+    let (status, body) = json_request(&admin2, "POST", &uri, Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "deleted");
+    assert!(!model_exists(&state.db.pool, "model-2p").await);
+
+    let (_, body) = json_request(&admin1, "GET", "/admin/approvals", Value::Null).await;
+    assert_eq!(body["approvals"][0]["status"], "executed");
+    assert_eq!(body["approvals"][0]["decided_by"], "admin2");
+}
+
+#[tokio::test]
+async fn admin_grant_rejected_by_second_admin() {
+    let state = test_app_state_with(approval_config()).await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    ensure_test_user(&state.db.pool, "admin2").await;
+    ensure_test_user(&state.db.pool, "user1").await;
+    let admin1 = admin_router(state.clone(), "admin1");
+    let admin2 = admin_router(state.clone(), "admin2");
+
+    let (status, body) = json_request(
+        &admin1,
+        "PUT",
+        "/admin/users/user1",
+        serde_json::json!({ "is_admin": true }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(body["approval"]["action"], "user.elevate");
+    let approval_id = body["approval"]["id"].as_str().unwrap().to_string();
+
+    let is_admin: bool = sqlx::query_scalar("SELECT is_admin FROM users WHERE id = 'user1'")
+        .fetch_one(&state.db.pool)
+        .await
+        .unwrap();
+    assert!(!is_admin);
+
+    let (status, _) = json_request(
+        &admin2,
+        "POST",
+        &format!("/admin/approvals/{approval_id}/reject"),
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = json_request(
+        &admin2,
+        "POST",
+        &format!("/admin/approvals/{approval_id}/approve"),
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let is_admin: bool = sqlx::query_scalar("SELECT is_admin FROM users WHERE id = 'user1'")
+        .fetch_one(&state.db.pool)
+        .await
+        .unwrap();
+    assert!(!is_admin);
+}
+
+#[tokio::test]
+async fn user_admin_role_grant_waits_for_admin() {
+    let state = test_app_state_with(approval_config()).await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    ensure_test_user(&state.db.pool, "useradm").await;
+    ensure_test_user(&state.db.pool, "user2").await;
+    let user_admin = session_router(state.clone(), "useradm", false, &[Role::UserAdmin]);
+    let admin1 = admin_router(state.clone(), "admin1");
+    let uri = "/admin/users/user2/roles";
+
+    let (status, body) = json_request(
+        &user_admin,
+        "PUT",
+        uri,
+        serde_json::json!({ "roles": ["user_admin", "viewer"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(body["status"], "pending_approval");
+    assert_eq!(body["approval"]["action"], "user.roles");
+    let approval_id = body["approval"]["id"].as_str().unwrap().to_string();
+
+    let (_, body) = json_request(&admin1, "GET", uri, Value::Null).await;
+    assert_eq!(body["roles"], serde_json::json!([]));
+
+    let (status, body) = json_request(
+        &admin1,
+        "POST",
+        &format!("/admin/approvals/{approval_id}/approve"),
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["roles"], serde_json::json!(["user_admin", "viewer"]));
+
+    // Roles that don't manage users need no approval
+    let (status, body) = json_request(
+        &user_admin,
+        "PUT",
+        "/admin/users/admin1/roles",
+        serde_json::json!({ "roles": ["viewer"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["roles"], serde_json::json!(["viewer"]));
+}
+
+#[tokio::test]
+async fn demoted_admin_jwt_rejected() {
+    let state = test_app_state_with(AppConfig {
+        jwt_access_tokens: true,
+        jwt_signing_key: Some("test-signing-key".to_string()),
+        ..test_config()
+    })
+    .await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    ensure_test_user(&state.db.pool, "admin2").await;
+    sqlx::query("UPDATE users SET is_admin = 1 WHERE id IN ('admin1', 'admin2')")
+        .execute(&state.db.pool)
+        .await
+        .unwrap();
+    let jwt = state.jwt.as_ref().unwrap();
+    let admin_jwt = |user_id: &str| {
+        jwt.issue(&auth::AuthUser {
+            user_id: user_id.to_string(),
+            token_id: format!("token-{user_id}"),
+            category_id: None,
+            specific_model_id: None,
+            is_admin: true,
+            is_internal: false,
+            via_jwt: false,
+        })
+    };
+    let demoted = admin_jwt("admin2");
+    let kept = admin_jwt("admin1");
+    assert!(jwt.verify(&demoted).unwrap().is_admin);
+
+    let admin1 = admin_router(state.clone(), "admin1");
+    let (status, _) = json_request(
+        &admin1,
+        "PUT",
+        "/admin/users/admin2",
+        serde_json::json!({ "is_admin": false }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    assert!(jwt.verify(&demoted).is_err());
+    assert!(jwt.verify(&kept).unwrap().is_admin);
+}
+
+#[tokio::test]
+async fn launch_profile_round_trips() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    insert_model(&state.db.pool, "model-profile", "owner/profile-GGUF").await;
+    let router = admin_router(state.clone(), "admin1");
+    let uri = "/admin/models/model-profile/launch-profile";
+
+    let (status, _) = json_request(&router, "GET", uri, Value::Null).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        uri,
+        serde_json::json!({
+            "gpu_type": "vulkan",
+            "gpu_layers": 40,
+            "parallel": 2,
+            "cpu_limit": 2.5,
+            "memory_limit_mb": 8192,
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = json_request(&router, "GET", uri, Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["profile"]["gpu_type"], "vulkan");
+    assert_eq!(body["profile"]["gpu_layers"], 40);
+    assert_eq!(body["profile"]["parallel"], 2);
+    assert_eq!(body["profile"]["cpu_limit"], 2.5);
+    assert_eq!(body["profile"]["memory_limit_mb"], 8192);
+    assert!(body["profile"]["pids_limit"].is_null());
+
+    // PUT replaces the whole profile
+    let (status, _) = json_request(&router, "PUT", uri, serde_json::json!({ "parallel": 4 })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = json_request(&router, "GET", uri, Value::Null).await;
+    assert_eq!(body["profile"]["parallel"], 4);
+    assert!(body["profile"]["gpu_type"].is_null());
+    assert!(body["profile"]["cpu_limit"].is_null());
+
+    let (status, _) =
+        json_request(&router, "PUT", uri, serde_json::json!({ "pids_limit": 0 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn launch_profile_rejects_context_beyond_model() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    insert_model(&state.db.pool, "model-ctx", "owner/ctx-GGUF").await;
+    sqlx::query("UPDATE models SET context_length = 8192 WHERE id = 'model-ctx'")
+        .execute(&state.db.pool)
+        .await
+        .unwrap();
+    let router = admin_router(state.clone(), "admin1");
+    let uri = "/admin/models/model-ctx/launch-profile";
+
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        uri,
+        serde_json::json!({ "context_size": 16384 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = json_request(&router, "GET", uri, Value::Null).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn category_queue_timeout_and_default_parallel() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, _) = json_request(
+        &router,
+        "POST",
+        "/admin/categories",
+        serde_json::json!({ "name": "large", "default_parallel": 0 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/categories",
+        serde_json::json!({ "name": "large", "queue_timeout_secs": 300, "default_parallel": 2 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = body["id"].as_str().unwrap().to_string();
+    let gate = state.scheduler.gate();
+    let default = std::time::Duration::from_secs(30);
+    assert_eq!(
+        gate.queue_timeout(Some(&id), default).await,
+        std::time::Duration::from_secs(300)
+    );
+
+    let (_, body) = json_request(&router, "GET", "/admin/categories", Value::Null).await;
+    assert_eq!(body["categories"][0]["queue_timeout_secs"], 300);
+    assert_eq!(body["categories"][0]["default_parallel"], 2);
+
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        &format!("/admin/categories/{id}"),
+        serde_json::json!({ "queue_timeout_secs": null }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(gate.queue_timeout(Some(&id), default).await, default);
+}
+
+#[tokio::test]
+async fn roles_limit_admin_routes() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "modeler").await;
+    ensure_test_user(&state.db.pool, "user2").await;
+    insert_model(&state.db.pool, "model-roles", "owner/roles-GGUF").await;
+    let router = session_router(state, "modeler", false, &[Role::ModelAdmin]);
+
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        "/admin/models/model-roles/launch-profile",
+        serde_json::json!({ "parallel": 2 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Other areas are readable but not writable
+    let (status, _) = json_request(&router, "GET", "/admin/users", Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        "/admin/users/user2/roles",
+        serde_json::json!({ "roles": ["viewer"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Full-admin areas are closed entirely
+    let (status, _) = json_request(&router, "GET", "/admin/settings", Value::Null).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        "/admin/users/modeler",
+        serde_json::json!({ "is_admin": true }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn user_roles_round_trip() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    ensure_test_user(&state.db.pool, "user2").await;
+    let router = admin_router(state.clone(), "admin1");
+    let uri = "/admin/users/user2/roles";
+
+    let (status, body) = json_request(
+        &router,
+        "PUT",
+        uri,
+        serde_json::json!({ "roles": ["viewer", "model_admin", "viewer"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["roles"], serde_json::json!(["model_admin", "viewer"]));
+
+    let (_, body) = json_request(&router, "GET", uri, Value::Null).await;
+    assert_eq!(body["roles"], serde_json::json!(["model_admin", "viewer"]));
+
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        uri,
+        serde_json::json!({ "roles": ["superuser"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let user_admin = session_router(state, "user2", false, &[Role::UserAdmin]);
+    let (status, _) = json_request(
+        &user_admin,
+        "PUT",
+        uri,
+        serde_json::json!({ "roles": ["user_admin", "model_admin"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn idp_group_mappings_validated_and_listed() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    let router = admin_router(state, "admin1");
+    let idp = serde_json::json!({
+        "name": "Keycloak",
+        "issuer": "https://kc.example/realms/se",
+        "client_id": "se",
+        "client_secret": "secret",
+        "groups_claim": "groups",
+        "group_mappings": { "se-admins": ["admin"], "ml": ["model_admin"] },
+    });
+
+    let (status, body) = json_request(&router, "POST", "/admin/idps", idp).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = body["id"].as_str().unwrap().to_string();
+    let uri = format!("/admin/idps/{id}");
+    let find = |body: &Value| {
+        body["idps"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i["id"] == id.as_str())
+            .cloned()
+            .unwrap()
+    };
+
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        &uri,
+        serde_json::json!({ "group_mappings": { "ml": ["root"] } }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, body) = json_request(&router, "GET", "/admin/idps", Value::Null).await;
+    let listed = find(&body);
+    assert_eq!(listed["groups_claim"], "groups");
+    assert_eq!(
+        listed["group_mappings"]["ml"],
+        serde_json::json!(["model_admin"])
+    );
+
+    // An empty claim turns sync off
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        &uri,
+        serde_json::json!({ "groups_claim": "" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = json_request(&router, "GET", "/admin/idps", Value::Null).await;
+    assert!(find(&body)["groups_claim"].is_null());
+}
+
+#[tokio::test]
+async fn deleted_model_restored_from_trash() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    insert_model(&state.db.pool, "model-trash", "owner/trash-GGUF").await;
+    let model_dir = format!("{}/owner--trash-GGUF", state.config.model_path);
+    std::fs::create_dir_all(&model_dir).unwrap();
+    std::fs::write(format!("{model_dir}/model.gguf"), b"12345").unwrap();
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, body) = json_delete(&router, "/admin/models/model-trash").await;
+    assert_eq!(status, StatusCode::OK);
+    let trash_id = body["trash_id"].as_str().unwrap().to_string();
+    assert!(!model_exists(&state.db.pool, "model-trash").await);
+    assert!(!std::path::Path::new(&model_dir).exists());
+
+    let (_, body) = json_request(&router, "GET", "/admin/models/trash", Value::Null).await;
+    let entry = body["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["id"] == trash_id.as_str())
+        .cloned()
+        .unwrap();
+    assert_eq!(entry["model_id"], "model-trash");
+    assert_eq!(entry["has_files"], true);
+    assert_eq!(entry["size_bytes"], 5);
+
+    let uri = format!("/admin/models/trash/{trash_id}/restore");
+    let (status, _) = json_request(&router, "POST", &uri, Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(model_exists(&state.db.pool, "model-trash").await);
+    assert!(std::path::Path::new(&format!("{model_dir}/model.gguf")).exists());
+
+    // The entry is spent
+    let (status, _) = json_request(&router, "POST", &uri, Value::Null).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    std::fs::remove_dir_all(&model_dir).unwrap();
+}
+
+#[tokio::test]
+async fn scim_deactivation_revokes_access() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    ensure_test_user(&state.db.pool, "leaver").await;
+    let api_token = crate::auth::tokens::create_token(&state.db, "leaver", "cli", None, None, None)
+        .await
+        .unwrap();
+    let session = crate::auth::sessions::create_session(&state.sessions, "leaver", None)
+        .await
+        .unwrap();
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/idps/test-idp/scim-token",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let scim_token = body["token"].as_str().unwrap().to_string();
+    let scim = Router::new().nest("/scim/v2", crate::auth::scim::routes(state.clone()));
+    let scim_request = |method: &str, uri: &str, token: &str, body: Value| {
+        let req = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("authorization", format!("Bearer {token}"))
+            .header("content-type", "application/scim+json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let scim = scim.clone();
+        async move {
+            let resp = scim.oneshot(req).await.unwrap();
+            let status = resp.status();
+            let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (
+                status,
+                serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null),
+            )
+        }
+    };
+
+    let (status, _) = scim_request("GET", "/scim/v2/Users", "se-scim-wrong", Value::Null).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = scim_request(
+        "GET",
+        "/scim/v2/Users?filter=userName%20eq%20%22leaver%40test.com%22",
+        &scim_token,
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["totalResults"], 1);
+    assert_eq!(body["Resources"][0]["externalId"], "leaver");
+
+    let (status, body) = scim_request(
+        "PATCH",
+        "/scim/v2/Users/leaver",
+        &scim_token,
+        serde_json::json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{ "op": "replace", "value": { "active": false } }],
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["active"], false);
+    assert!(crate::auth::tokens::validate_token(&state.db, &api_token)
+        .await
+        .is_err());
+    assert!(
+        crate::auth::sessions::validate_session(&state.sessions, &state.db, &session)
+            .await
+            .is_err()
+    );
+
+    // Reactivation doesn't bring the revoked token back
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        "/admin/users/leaver",
+        serde_json::json!({ "active": true }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(crate::auth::tokens::validate_token(&state.db, &api_token)
+        .await
+        .is_err());
+
+    // Users of other IdPs are out of reach
+    sqlx::query(
+        "INSERT INTO idp_configs (id, name, issuer, client_id, client_secret_enc) \
+         VALUES ('other-idp', 'other', 'https://other', 'client', 'secret')",
+    )
+    .execute(&state.db.pool)
+    .await
+    .unwrap();
+    sqlx::query("INSERT INTO users (id, idp_id, subject) VALUES ('outsider', 'other-idp', 'o')")
+        .execute(&state.db.pool)
+        .await
+        .unwrap();
+    let (status, _) = scim_request(
+        "DELETE",
+        "/scim/v2/Users/outsider",
+        &scim_token,
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Admins can't lock themselves out
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        "/admin/users/admin1",
+        serde_json::json!({ "active": false }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn revoke_sessions_logs_user_out() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    ensure_test_user(&state.db.pool, "roamer").await;
+    let api_token = crate::auth::tokens::create_token(&state.db, "roamer", "cli", None, None, None)
+        .await
+        .unwrap();
+    let laptop = crate::auth::sessions::create_session(&state.sessions, "roamer", None)
+        .await
+        .unwrap();
+    let phone = crate::auth::sessions::create_session(&state.sessions, "roamer", None)
+        .await
+        .unwrap();
+    let (_, device_token) = crate::auth::devices::create_device(&state.db, "roamer", None, 30)
+        .await
+        .unwrap();
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/users/roamer/revoke-sessions",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["revoked_sessions"], 2);
+    for session in [&laptop, &phone] {
+        assert!(
+            crate::auth::sessions::validate_session(&state.sessions, &state.db, session)
+                .await
+                .is_err()
+        );
+    }
+    assert!(crate::auth::devices::redeem(&state.db, &device_token)
+        .await
+        .is_err());
+    assert!(crate::auth::tokens::validate_token(&state.db, &api_token)
+        .await
+        .is_ok());
+
+    let (status, _) = json_request(
+        &router,
+        "POST",
+        "/admin/users/nobody/revoke-sessions",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+// ---------------------------------------------------------------------------
+// auto-categorization
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn auto_categorize_suggests_and_accepts() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    insert_model(&state.db.pool, "m-embed", "BAAI/bge-m3-GGUF").await;
+    insert_model(&state.db.pool, "m-coder", "Qwen/Qwen2.5-Coder-7B-GGUF").await;
+    insert_model(&state.db.pool, "m-mystery", "acme/mystery-GGUF").await;
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/categories",
+        serde_json::json!({ "name": "Vectors", "description": "RAG", "auto_kind": "embedding" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let vectors = body["id"].as_str().unwrap().to_string();
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/categories",
+        serde_json::json!({ "name": "Coding", "description": "Code assistants" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let coding = body["id"].as_str().unwrap().to_string();
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        &format!("/admin/categories/{coding}"),
+        serde_json::json!({ "auto_kind": "embedding" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/models/categorize",
+        serde_json::json!({ "apply": false }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let outcomes = body["models"].as_array().unwrap();
+    assert_eq!(outcomes.len(), 2);
+    let suggestions: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT id, suggested_category_id FROM models WHERE category_id IS NULL ORDER BY id",
+    )
+    .fetch_all(&state.db.pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        suggestions,
+        vec![
+            ("m-coder".to_string(), Some(coding.clone())),
+            ("m-embed".to_string(), Some(vectors.clone())),
+            ("m-mystery".to_string(), None),
+        ]
+    );
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/models/m-embed/category-suggestion",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["category_id"], vectors.as_str());
+    let (status, _) = json_request(
+        &router,
+        "DELETE",
+        "/admin/models/m-coder/category-suggestion",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let rows: Vec<(String, Option<String>, Option<String>)> =
+        sqlx::query_as("SELECT id, category_id, suggested_category_id FROM models ORDER BY id")
+            .fetch_all(&state.db.pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            ("m-coder".to_string(), None, None),
+            ("m-embed".to_string(), Some(vectors), None),
+            ("m-mystery".to_string(), None, None),
+        ]
+    );
+    let (status, _) = json_request(
+        &router,
+        "POST",
+        "/admin/models/m-coder/category-suggestion",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+// ---------------------------------------------------------------------------
+// bulk token revocation
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn bulk_revoke_dry_run_then_revoke() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    ensure_test_user(&state.db.pool, "alice").await;
+    ensure_test_user(&state.db.pool, "bob").await;
+    let mut tokens = Vec::new();
+    for (user, name) in [("alice", "old"), ("alice", "new"), ("bob", "old")] {
+        tokens.push(
+            crate::auth::tokens::create_token(&state.db, user, name, None, None, None)
+                .await
+                .unwrap(),
+        );
+    }
+    let [old, new, other] = <[String; 3]>::try_from(tokens).unwrap();
+    sqlx::query("UPDATE tokens SET created_at = '2026-01-01 00:00:00' WHERE name = 'old'")
+        .execute(&state.db.pool)
+        .await
+        .unwrap();
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, _) = json_request(
+        &router,
+        "POST",
+        "/admin/tokens/revoke",
+        serde_json::json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = json_request(
+        &router,
+        "POST",
+        "/admin/tokens/revoke",
+        serde_json::json!({ "created_before": "last spring" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let filter = serde_json::json!({ "user_id": "alice", "created_before": "2026-06-01" });
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/tokens/revoke?dry_run=true",
+        filter.clone(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["count"], 1);
+    assert_eq!(body["tokens"][0]["name"], "old");
+    assert_eq!(body["tokens"][0]["last_used_at"], Value::Null);
+    assert!(crate::auth::tokens::validate_token(&state.db, &old)
+        .await
+        .is_ok());
+
+    let (status, body) = json_request(&router, "POST", "/admin/tokens/revoke", filter).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["revoked"], 1);
+    assert!(crate::auth::tokens::validate_token(&state.db, &old)
+        .await
+        .is_err());
+    for token in [&new, &other] {
+        assert!(crate::auth::tokens::validate_token(&state.db, token)
+            .await
+            .is_ok());
+    }
+}
+
+// ---------------------------------------------------------------------------
+// model aliases
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn model_alias_crud() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    insert_model(&state.db.pool, "m1", "org/llama").await;
+    insert_model(&state.db.pool, "m2", "org/qwen").await;
+    let router = admin_router(state.clone(), "admin1");
+
+    for body in [
+        serde_json::json!({ "alias": "gpt-4o" }),
+        serde_json::json!({ "alias": "gpt-4o", "model_id": "m1", "category_id": "c" }),
+        serde_json::json!({ "alias": "gpt-4o", "model_id": "nope" }),
+        serde_json::json!({ "alias": " ", "model_id": "m1" }),
+    ] {
+        let (status, _) = json_request(&router, "POST", "/admin/aliases", body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/aliases",
+        serde_json::json!({ "alias": "gpt-4o", "model_id": "m1" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["target_name"], "org/llama");
+    let id = body["id"].as_str().unwrap().to_string();
+    let (status, _) = json_request(
+        &router,
+        "POST",
+        "/admin/aliases",
+        serde_json::json!({ "alias": "GPT-4O", "model_id": "m2" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, body) = json_request(
+        &router,
+        "PUT",
+        &format!("/admin/aliases/{id}"),
+        serde_json::json!({ "alias": "gpt-4o", "model_id": "m2" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["target_name"], "org/qwen");
+
+    sqlx::query("DELETE FROM models WHERE id = 'm2'")
+        .execute(&state.db.pool)
+        .await
+        .unwrap();
+    let (status, body) = json_request(&router, "GET", "/admin/aliases", Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["aliases"], serde_json::json!([]));
+    let (status, _) = json_request(
+        &router,
+        "DELETE",
+        &format!("/admin/aliases/{id}"),
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+// ---------------------------------------------------------------------------
+// sampling defaults
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn default_params_round_trip() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    insert_model(&state.db.pool, "m1", "org/llama").await;
+    let router = admin_router(state.clone(), "admin1");
+    let uri = "/admin/models/m1/default-params";
+
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        "/admin/models/nope/default-params",
+        serde_json::json!({ "temperature": 0.7 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = json_request(
+        &router,
+        "GET",
+        "/admin/models/nope/default-params",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = json_request(&router, "PUT", uri, serde_json::json!({ "top_p": 1.5 })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        uri,
+        serde_json::json!({ "temprature": 0.7 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let (status, body) = json_request(&router, "GET", uri, Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["default_params"], serde_json::json!({}));
+
+    let params = serde_json::json!({ "temperature": 0.7, "min_p": 0.05, "repeat_penalty": 1.1 });
+    let (status, _) = json_request(&router, "PUT", uri, params.clone()).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = json_request(&router, "GET", uri, Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["default_params"], params);
+    let (_, body) = json_request(&router, "GET", "/admin/models", Value::Null).await;
+    assert_eq!(body["models"][0]["default_params"], params);
+
+    let (status, _) = json_request(&router, "PUT", uri, serde_json::json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    let stored: String = sqlx::query_scalar("SELECT default_params FROM models WHERE id = 'm1'")
+        .fetch_one(&state.db.pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, "{}");
+}
+
+#[tokio::test]
+async fn hygiene_report_and_cleanup() {
+    let state = test_app_state().await;
+    let pool = &state.db.pool;
+    ensure_test_user(pool, "admin1").await;
+    ensure_test_user(pool, "alice").await;
+    ensure_test_user(pool, "leaver").await;
+    let stale = crate::auth::tokens::create_token(&state.db, "alice", "stale", None, None, None)
+        .await
+        .unwrap();
+    let fresh = crate::auth::tokens::create_token(&state.db, "alice", "fresh", None, None, None)
+        .await
+        .unwrap();
+    crate::auth::sessions::create_session(&state.sessions, "leaver", None)
+        .await
+        .unwrap();
+    insert_model(pool, "m-idle", "org/idle").await;
+    insert_model(pool, "m-used", "org/used").await;
+    insert_model(pool, "m-new", "org/new").await;
+    for sql in [
+        "UPDATE tokens SET created_at = '2026-01-01 00:00:00' WHERE name = 'stale'",
+        "UPDATE users SET active = 0, deactivated_at = datetime('now') WHERE id = 'leaver'",
+        "UPDATE models SET created_at = '2026-01-01 00:00:00' WHERE id IN ('m-idle', 'm-used')",
+        "INSERT INTO usage_log (id, user_id, model_id) VALUES ('u1', 'alice', 'm-used')",
+        "INSERT INTO model_categories (id, name, description) VALUES \
+         ('c-spare', 'Spare', ''), ('c-mapped', 'Mapped', ''), ('c-busy', 'Busy', '')",
+        "UPDATE models SET category_id = 'c-busy' WHERE id = 'm-used'",
+        "INSERT INTO idp_model_access (id, idp_id, group_claim, group_value, category_id) \
+         VALUES ('a1', 'test-idp', 'groups', 'staff', 'c-mapped')",
+    ] {
+        sqlx::query(sql).execute(pool).await.unwrap();
+    }
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, _) = json_request(&router, "GET", "/admin/hygiene?days=0", Value::Null).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = json_request(&router, "GET", "/admin/hygiene?days=30", Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    let names = |key: &str, field: &str| -> Vec<String> {
+        body[key]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v[field].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(names("unused_tokens", "name"), ["stale"]);
+    assert_eq!(names("stale_sessions", "user_id"), ["leaver"]);
+    assert_eq!(names("unused_models", "id"), ["m-idle"]);
+    assert_eq!(names("empty_categories", "name"), ["Mapped", "Spare"]);
+
+    let (_, body) = json_request(
+        &router,
+        "POST",
+        "/admin/hygiene/tokens/revoke?days=30",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(body["revoked"], 1);
+    assert!(crate::auth::tokens::validate_token(&state.db, &stale)
+        .await
+        .is_err());
+    assert!(crate::auth::tokens::validate_token(&state.db, &fresh)
+        .await
+        .is_ok());
+
+    let (_, body) = json_request(
+        &router,
+        "POST",
+        "/admin/hygiene/sessions/revoke",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(body["sessions"], 1);
+
+    let (_, body) = json_request(
+        &router,
+        "POST",
+        "/admin/hygiene/categories/delete",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(body["deleted"][0]["id"], "c-spare");
+    assert_eq!(body["skipped"][0]["id"], "c-mapped");
+
+    let (_, body) = json_request(
+        &router,
+        "POST",
+        "/admin/hygiene/models/delete?days=30",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(body["deleted"], 1);
+    let models: Vec<String> = sqlx::query_scalar("SELECT id FROM models ORDER BY id")
+        .fetch_all(pool)
+        .await
+        .unwrap();
+    assert_eq!(models, ["m-new", "m-used"]);
+
+    let (_, body) = json_request(&router, "GET", "/admin/hygiene?days=30", Value::Null).await;
+    for key in ["unused_tokens", "stale_sessions", "unused_models"] {
+        assert_eq!(body[key], serde_json::json!([]), "{key}");
+    }
+}
+
+#[tokio::test]
+async fn feature_flag_targeting_and_rollout() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        "/admin/feature-flags/New%20Parser",
+        serde_json::json!({ "enabled": true }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        "/admin/feature-flags/new_usage_parser",
+        serde_json::json!({ "enabled": true, "rollout_percent": 101 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = json_request(
+        &router,
+        "PUT",
+        "/admin/feature-flags/new_usage_parser",
+        serde_json::json!({ "enabled": true, "user_ids": ["alice"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["user_ids"][0], "alice");
+    assert!(state.flags.is_enabled("new_usage_parser", "alice").await);
+    assert!(!state.flags.is_enabled("new_usage_parser", "bob").await);
+
+    json_request(
+        &router,
+        "PUT",
+        "/admin/feature-flags/new_usage_parser",
+        serde_json::json!({ "enabled": true, "rollout_percent": 100 }),
+    )
+    .await;
+    assert!(state.flags.is_enabled("new_usage_parser", "bob").await);
+    let (_, body) = json_request(&router, "GET", "/admin/feature-flags", Value::Null).await;
+    assert_eq!(body["flags"][0]["rollout_percent"], 100);
+
+    json_request(
+        &router,
+        "PUT",
+        "/admin/feature-flags/new_usage_parser",
+        serde_json::json!({ "enabled": false, "rollout_percent": 100 }),
+    )
+    .await;
+    assert!(!state.flags.is_enabled("new_usage_parser", "bob").await);
+
+    let (status, _) = json_request(
+        &router,
+        "DELETE",
+        "/admin/feature-flags/new_usage_parser",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = json_request(
+        &router,
+        "DELETE",
+        "/admin/feature-flags/new_usage_parser",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn signed_url_downloads_without_session() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "user1").await;
+    sqlx::query(
+        "INSERT INTO conversations (id, user_id, title) VALUES ('conv1', 'user1', 'Notes')",
+    )
+    .execute(&state.db.pool)
+    .await
+    .unwrap();
+    // Real auth layers, no session cookie
+    let router = Router::new().nest(
+        "/api/user",
+        conversations::user_routes(state.clone())
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                auth::session_auth_middleware,
+            ))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                signed_url::middleware,
+            )),
+    );
+    let get = |uri: String| {
+        let router = router.clone();
+        async move {
+            let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            router.oneshot(req).await.unwrap().status()
+        }
+    };
+
+    let export = "/api/user/conversations/conv1/export?format=markdown";
+    assert_eq!(get(export.to_string()).await, StatusCode::UNAUTHORIZED);
+
+    let (signed, _) = state.signed_urls.sign(export, "user1", 60).unwrap();
+    assert_eq!(get(signed.clone()).await, StatusCode::OK);
+
+    let tampered = format!("{}00", signed);
+    assert_eq!(get(tampered).await, StatusCode::FORBIDDEN);
+
+    sqlx::query("UPDATE users SET active = 0 WHERE id = 'user1'")
+        .execute(&state.db.pool)
+        .await
+        .unwrap();
+    assert_eq!(get(signed).await, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn config_export_import_round_trip() {
+    let source = test_app_state().await;
+    ensure_test_user(&source.db.pool, "admin1").await;
+    sqlx::query(
+        "INSERT INTO model_categories (id, name, description) VALUES ('c1', 'coding', 'Code')",
+    )
+    .execute(&source.db.pool)
+    .await
+    .unwrap();
+    insert_model(&source.db.pool, "m1", "org/coder").await;
+    for sql in [
+        "UPDATE models SET category_id = 'c1', context_length = 8192 WHERE id = 'm1'",
+        "INSERT INTO model_launch_profiles (model_id, gpu_layers) VALUES ('m1', 40)",
+        "INSERT INTO model_aliases (id, alias, model_id) VALUES ('a1', 'gpt-4o', 'm1')",
+        "UPDATE settings SET value = '45' WHERE key = 'queue_timeout_secs'",
+    ] {
+        sqlx::query(sql).execute(&source.db.pool).await.unwrap();
+    }
+    let (status, bundle) = json_request(
+        &admin_router(source, "admin1"),
+        "GET",
+        "/admin/export",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(bundle["version"], 1);
+    assert_eq!(bundle["idp_secrets"], "omitted");
+    assert_eq!(bundle["models"][0]["context_length"], 8192);
+    assert!(bundle["models"][0].get("loaded").is_none());
+    let idp = bundle["idps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|i| i["id"] == "test-idp")
+        .unwrap();
+    assert!(idp["client_secret_enc"].is_null());
+
+    let target = test_app_state().await;
+    ensure_test_user(&target.db.pool, "admin2").await;
+    let router = admin_router(target.clone(), "admin2");
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/import",
+        serde_json::json!({ "bundle": bundle, "dry_run": true }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["sections"]["models"]["created"], 1);
+    assert_eq!(body["sections"]["launch_profiles"]["created"], 1);
+    let models: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM models")
+        .fetch_one(&target.db.pool)
+        .await
+        .unwrap();
+    assert_eq!(models, 0);
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/import",
+        serde_json::json!({ "bundle": bundle, "on_conflict": "overwrite" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["sections"]["categories"]["created"], 1);
+    assert_eq!(body["sections"]["aliases"]["created"], 1);
+    assert_eq!(
+        body["sections"]["settings"]["updated"],
+        bundle["settings"].as_array().unwrap().len()
+    );
+    let (category, loaded): (Option<String>, bool) =
+        sqlx::query_as("SELECT category_id, loaded FROM models WHERE id = 'm1'")
+            .fetch_one(&target.db.pool)
+            .await
+            .unwrap();
+    assert_eq!(category.as_deref(), Some("c1"));
+    assert!(!loaded);
+    let timeout: String =
+        sqlx::query_scalar("SELECT value FROM settings WHERE key = 'queue_timeout_secs'")
+            .fetch_one(&target.db.pool)
+            .await
+            .unwrap();
+    assert_eq!(timeout, "45");
+    // test-idp existed already, so its secret was kept
+    let secret: String =
+        sqlx::query_scalar("SELECT client_secret_enc FROM idp_configs WHERE id = 'test-idp'")
+            .fetch_one(&target.db.pool)
+            .await
+            .unwrap();
+    assert_eq!(secret, "secret");
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/import",
+        serde_json::json!({ "bundle": bundle }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["sections"]["models"]["skipped"], 1);
+    assert_eq!(body["sections"]["models"]["created"], 0);
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/import",
+        serde_json::json!({ "bundle": bundle, "on_conflict": "fail" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["conflicts"][0]["section"], "categories");
+
+    let (status, _) = json_request(
+        &router,
+        "POST",
+        "/admin/import",
+        serde_json::json!({ "bundle": { "version": 99 } }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn config_import_skips_secrets_from_another_key() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    let router = admin_router(state.clone(), "admin1");
+    let bundle = serde_json::json!({
+        "version": 1,
+        "idp_secrets": "encrypted",
+        "idp_key_fingerprint": crate::db::crypto::key_fingerprint("another-key"),
+        "idps": [{
+            "id": "idp2",
+            "name": "Other",
+            "issuer": "https://other",
+            "client_id": "client",
+            "client_secret_enc": "base64-from-another-instance",
+            "enabled": 1,
+        }],
+    });
+
+    let (status, body) = json_request(
+        &router,
+        "POST",
+        "/admin/import",
+        serde_json::json!({ "bundle": bundle }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["sections"]["idps"]["created"], 1);
+    assert_eq!(body["warnings"].as_array().unwrap().len(), 1);
+    let (secret, enabled): (String, bool) =
+        sqlx::query_as("SELECT client_secret_enc, enabled FROM idp_configs WHERE id = 'idp2'")
+            .fetch_one(&state.db.pool)
+            .await
+            .unwrap();
+    assert_eq!(secret, "");
+    assert!(!enabled);
+}
+
+#[tokio::test]
+async fn host_route_crud_updates_dispatch_cache() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    let router = admin_router(state.clone(), "admin1");
+
+    for (host, target) in [
+        ("docs.example.com", "ftp"),
+        ("docs.example.com", "site:missing-site"),
+        ("docs.example.com", "redirect:ftp://example.com"),
+        ("bad_host", "api"),
+    ] {
+        let (status, _) = json_request(
+            &router,
+            "PUT",
+            &format!("/admin/host-routes/{host}"),
+            serde_json::json!({ "target": target }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{host} {target}");
+    }
+    let (status, _) = json_request(
+        &router,
+        "PUT",
+        "/admin/host-routes/localhost",
+        serde_json::json!({ "target": "chat" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (status, body) = json_request(
+        &router,
+        "PUT",
+        "/admin/host-routes/Docs.Example.com",
+        serde_json::json!({ "target": "redirect:https://docs.example.org/", "description": "Docs" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["hostname"], "docs.example.com");
+    assert_eq!(body["target"], "redirect:https://docs.example.org");
+    assert_eq!(
+        state.host_routes.lookup("docs.example.com").await,
+        Some(crate::host_routes::HostTarget::Redirect(
+            "https://docs.example.org".into()
+        ))
+    );
+    let (_, body) = json_request(&router, "GET", "/admin/host-routes", Value::Null).await;
+    assert_eq!(body["builtin"][0]["target"], "api");
+    assert_eq!(body["routes"][0]["description"], "Docs");
+
+    let (status, _) = json_request(
+        &router,
+        "DELETE",
+        "/admin/host-routes/docs.example.com",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(state.host_routes.lookup("docs.example.com").await, None);
+    let (status, _) = json_request(
+        &router,
+        "DELETE",
+        "/admin/host-routes/docs.example.com",
+        Value::Null,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn prompt_dedup_counts_repeats_per_model() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    let pool = &state.db.pool;
+    let hello = prompt_dedup::hash_request(&serde_json::json!({
+        "model": "m", "messages": [{ "role": "user", "content": "Hello" }],
+    }))
+    .unwrap();
+    let shouted = prompt_dedup::hash_request(&serde_json::json!({
+        "model": "m", "messages": [{ "role": "user", "content": "HELLO" }],
+    }))
+    .unwrap();
+    for (id, model, hashes, ago) in [
+        ("d1", "m1", &hello, "-30 seconds"),
+        ("d2", "m1", &hello, "-10 seconds"),
+        ("d3", "m1", &shouted, "-5 seconds"),
+        ("d4", "m2", &hello, "-5 seconds"),
+    ] {
+        sqlx::query(
+            "INSERT INTO usage_log (id, user_id, model_id, input_tokens, output_tokens, \
+             prompt_hash, prompt_norm_hash, created_at) \
+             VALUES (?, 'admin1', ?, 100, 20, ?, ?, datetime('now', ?))",
+        )
+        .bind(id)
+        .bind(model)
+        .bind(&hashes.exact)
+        .bind(&hashes.normalized)
+        .bind(ago)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+    sqlx::query("INSERT INTO usage_log (id, user_id, model_id) VALUES ('d5', 'admin1', 'm1')")
+        .execute(pool)
+        .await
+        .unwrap();
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, body) =
+        json_request(&router, "GET", "/admin/usage/dedup?period=day", Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["requests"], 4);
+    assert_eq!(body["exact"]["repeats"], 1);
+    assert_eq!(body["exact"]["input_tokens_saved"], 100);
+    assert_eq!(body["exact"]["by_ttl"][0]["ttl_secs"], 60);
+    assert_eq!(body["exact"]["by_ttl"][0]["hits"], 1);
+    assert_eq!(body["near"]["repeats"], 2);
+    assert_eq!(body["by_model"][0]["model_id"], "m1");
+    assert_eq!(body["by_model"][0]["near_repeats"], 2);
+}
+
+#[tokio::test]
+async fn issues_report_stale_reservation_requests() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    for (id, created) in [("r1", "-3 days"), ("r2", "-2 days"), ("r3", "-1 hours")] {
+        sqlx::query(
+            "INSERT INTO reservations (id, user_id, status, start_time, end_time, created_at) \
+             VALUES (?, 'admin1', 'pending', '2099-01-01T10:00:00Z', '2099-01-01T11:00:00Z', \
+             datetime('now', ?))",
+        )
+        .bind(id)
+        .bind(created)
+        .execute(&state.db.pool)
+        .await
+        .unwrap();
+    }
+    let router = admin_router(state.clone(), "admin1");
+
+    let (status, body) = json_request(&router, "GET", "/admin/issues", Value::Null).await;
+    assert_eq!(status, StatusCode::OK);
+    let issues = body["issues"].as_array().unwrap();
+    let pending = issues
+        .iter()
+        .find(|i| i["kind"] == "reservations_pending")
+        .unwrap();
+    assert_eq!(pending["severity"], "info");
+    assert!(pending["title"]
+        .as_str()
+        .unwrap()
+        .starts_with("2 reservation requests waiting over 24 hours"));
+    assert_eq!(pending["link"], "/portal/admin/reservations");
+
+    let rank = |s: &Value| ["critical", "warning", "info"].iter().position(|r| s == r);
+    let ranks: Vec<_> = issues.iter().map(|i| rank(&i["severity"])).collect();
+    assert!(ranks.windows(2).all(|w| w[0] <= w[1]));
+    assert!(body["counts"]["info"].as_u64().unwrap() >= 1);
+}
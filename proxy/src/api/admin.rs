@@ -1,22 +1,65 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post, put};
-use axum::{Extension, Json, Router};
+use axum::{middleware, Extension, Json, Router};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use super::approvals;
+use super::audit;
+use super::categorize;
 use super::common;
+use super::context_guard;
+use super::elevation;
 use super::error;
-use crate::auth::SessionAuth;
-use crate::db::models::{IdpConfigPublic, User};
+use super::model_trash;
+use super::request_log;
+use super::usage_privacy::{self, UsageRow};
+use crate::auth::rbac::{self, Permission, Role};
+use crate::auth::{self, groups, scim, sessions, tokens, SessionAuth};
+use crate::db::models::{IdpConfigPublic, LaunchProfile, User};
+use crate::docker::limits::ResourceLimits;
 use crate::docker::runtime_overrides::ModelRuntimeOverrides;
+use crate::docker::StopMode;
+use crate::scheduler::fairness::PriorityTier;
+use crate::scheduler::settings::FairnessSettings;
+use crate::scheduler::SystemDrain;
 use crate::AppState;
 
+/// Snapshot queries for the config change sets recorded on update. The IdP
+/// secret is redacted by `audit::diff`; only whether it changed is kept.
+const IDP_SNAPSHOT: &str = "SELECT json_object('name', name, 'issuer', issuer, \
+     'client_id', client_id, 'client_secret', client_secret_enc, 'scopes', scopes, \
+     'enabled', enabled, 'groups_claim', groups_claim, \
+     'group_mappings', json(group_mappings)) FROM idp_configs WHERE id = ?";
+const CATEGORY_SNAPSHOT: &str = "SELECT json_object('name', name, 'description', description, \
+     'preferred_model_id', preferred_model_id, 'autoload', autoload, 'auto_kind', auto_kind, \
+     'log_level', log_level, 'max_concurrent', max_concurrent, \
+     'max_queue_depth', max_queue_depth, 'queue_timeout_secs', queue_timeout_secs, \
+     'default_parallel', default_parallel) FROM model_categories WHERE id = ?";
+const MODEL_SNAPSHOT: &str = "SELECT json_object('category_id', category_id, \
+     'backend_type', backend_type, 'idle_unload_minutes', idle_unload_minutes, \
+     'autoload', autoload, 'embedding', embedding, 'context_policy', context_policy, \
+     'runtime_overrides', json(runtime_overrides)) FROM models WHERE id = ?";
+
+/// Snapshot a record for diffing, or `null` if it can't be read.
+async fn snapshot_or_null(pool: &sqlx::SqlitePool, select: &str, id: &str) -> serde_json::Value {
+    match audit::snapshot(pool, select, id).await {
+        Ok(snapshot) => snapshot.unwrap_or_default(),
+        Err(e) => {
+            error!(error = %e, resource = %id, "Failed to snapshot record for change set");
+            serde_json::Value::Null
+        }
+    }
+}
+
 /// Row from `models` used by the estimate_vram handler.
 #[derive(sqlx::FromRow)]
 struct ModelMetadataRow {
@@ -34,10 +77,24 @@ struct ModelMetadataRow {
 }
 
 pub fn routes(state: Arc<AppState>) -> Router {
-    Router::new()
-        // IdP management
+    // IdP management and settings
+    let admin = Router::new()
         .route("/idps", get(list_idps).post(create_idp))
         .route("/idps/{id}", put(update_idp).delete(disable_idp))
+        .route(
+            "/idps/{id}/scim-token",
+            post(create_scim_token).delete(delete_scim_token),
+        )
+        .route("/settings", get(get_settings).put(update_settings))
+        .route("/system/config", get(effective_config))
+        // Granting full admin can't be delegated
+        .route("/users/{id}", put(update_user))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Admin,
+            rbac::require_permission,
+        ));
+
+    let models = Router::new()
         // Category management
         .route("/categories", get(list_categories).post(create_category))
         .route(
@@ -48,21 +105,53 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/models", get(list_models))
         .route("/models/register", post(register_model))
         .route("/models/{id}", put(update_model).delete(delete_model))
-        // User management
-        .route("/users", get(list_users))
-        .route("/users/{id}", put(update_user))
-        // System status
-        .route("/system", get(system_status))
+        .route("/models/{id}/draining", put(set_model_draining))
+        .route(
+            "/models/{id}/launch-profile",
+            get(get_launch_profile).put(put_launch_profile),
+        )
         // Containers
         .route("/containers", get(list_containers))
         .route("/containers/start", post(start_container))
         .route("/containers/stop", post(stop_container))
         .route("/containers/estimate", post(estimate_vram))
-        // Settings
-        .route("/settings", get(get_settings).put(update_settings))
+        .route("/system/gpu-probe", post(run_gpu_probe))
+        .route("/system/drain", post(drain_system))
+        .route("/system/resume", post(resume_system))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Models,
+            rbac::require_permission,
+        ));
+
+    // User management
+    let users = Router::new()
+        .route("/users", get(list_users))
+        .route("/users/{id}/roles", get(get_user_roles).put(put_user_roles))
+        .route("/users/{id}/revoke-sessions", post(revoke_user_sessions))
+        .route("/roles", get(list_roles))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Users,
+            rbac::require_permission,
+        ));
+
+    let view = Router::new()
+        // System status
+        .route("/system", get(system_status))
+        .route("/system/connections", get(connection_stats))
+        .route("/ui/manifest", get(ui_manifest))
         // Usage analytics
         .route("/usage", get(admin_usage))
         .route("/usage/timeline", get(admin_usage_timeline))
+        .route("/queue/history", get(queue_history))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::View,
+            rbac::require_permission,
+        ));
+
+    admin
+        .merge(models)
+        .merge(users)
+        .merge(view)
         .with_state(state)
 }
 
@@ -73,7 +162,7 @@ pub fn routes(state: Arc<AppState>) -> Router {
 /// GET /api/admin/idps — List all IdP configs.
 async fn list_idps(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     match sqlx::query_as::<_, IdpConfigPublic>(
-        "SELECT id, name, issuer, client_id, scopes, enabled, created_at FROM idp_configs",
+        "SELECT id, name, issuer, client_id, scopes, enabled, created_at, groups_claim, group_mappings, scim_token_hash IS NOT NULL AS scim_enabled FROM idp_configs",
     )
     .fetch_all(&state.db.pool)
     .await
@@ -90,6 +179,21 @@ struct CreateIdpRequest {
     client_id: String,
     client_secret: String,
     scopes: Option<String>,
+    groups_claim: Option<String>,
+    group_mappings: Option<serde_json::Value>,
+}
+
+/// Check `group_mappings` and return it as stored JSON text.
+fn group_mappings_text(value: &serde_json::Value) -> Result<String, Response> {
+    groups::parse_mappings(value)
+        .map(|_| value.to_string())
+        .map_err(|msg| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": msg })),
+            )
+                .into_response()
+        })
 }
 
 /// POST /api/admin/idps — Create a new IdP.
@@ -102,9 +206,20 @@ async fn create_idp(
         .or_else(|| error::validate_len("issuer", &req.issuer, error::MAX_URL))
         .or_else(|| error::validate_len("client_id", &req.client_id, error::MAX_NAME))
         .or_else(|| error::validate_len("client_secret", &req.client_secret, error::MAX_SECRET))
+        .or_else(|| {
+            req.groups_claim
+                .as_deref()
+                .and_then(|v| error::validate_len("groups_claim", v, error::MAX_NAME))
+        })
     {
         return r;
     }
+    let group_mappings = match req.group_mappings.as_ref().map(group_mappings_text) {
+        Some(Ok(text)) => text,
+        Some(Err(r)) => return r,
+        None => "{}".to_string(),
+    };
+    let groups_claim = req.groups_claim.filter(|c| !c.is_empty());
     let id = Uuid::new_v4().to_string();
     let scopes = req
         .scopes
@@ -118,7 +233,7 @@ async fn create_idp(
     };
 
     match sqlx::query(
-        "INSERT INTO idp_configs (id, name, issuer, client_id, client_secret_enc, scopes) VALUES (?, ?, ?, ?, ?, ?)",
+        "INSERT INTO idp_configs (id, name, issuer, client_id, client_secret_enc, scopes, groups_claim, group_mappings) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(&id)
     .bind(&req.name)
@@ -126,6 +241,8 @@ async fn create_idp(
     .bind(&req.client_id)
     .bind(&client_secret_enc)
     .bind(&scopes)
+    .bind(&groups_claim)
+    .bind(&group_mappings)
     .execute(&state.db.pool)
     .await
     {
@@ -149,6 +266,9 @@ struct UpdateIdpRequest {
     client_secret: Option<String>,
     scopes: Option<String>,
     enabled: Option<bool>,
+    /// An empty string turns group sync off.
+    groups_claim: Option<String>,
+    group_mappings: Option<serde_json::Value>,
 }
 
 /// PUT /api/admin/idps/:id — Update an IdP configuration.
@@ -177,6 +297,11 @@ async fn update_idp(
                 .as_deref()
                 .and_then(|v| error::validate_len("client_secret", v, error::MAX_SECRET))
         })
+        .or_else(|| {
+            req.groups_claim
+                .as_deref()
+                .and_then(|v| error::validate_len("groups_claim", v, error::MAX_NAME))
+        })
     {
         return r;
     }
@@ -200,6 +325,8 @@ async fn update_idp(
             .into_response();
     }
 
+    let before = snapshot_or_null(&state.db.pool, IDP_SNAPSHOT, &id).await;
+
     // Build dynamic update query
     let mut sets = Vec::new();
     let mut binds: Vec<String> = Vec::new();
@@ -232,6 +359,19 @@ async fn update_idp(
         sets.push("scopes = ?");
         binds.push(scopes.clone());
     }
+    if let Some(ref groups_claim) = req.groups_claim {
+        sets.push("groups_claim = NULLIF(?, '')");
+        binds.push(groups_claim.clone());
+    }
+    if let Some(ref group_mappings) = req.group_mappings {
+        match group_mappings_text(group_mappings) {
+            Ok(text) => {
+                sets.push("group_mappings = ?");
+                binds.push(text);
+            }
+            Err(r) => return r,
+        }
+    }
     if let Some(enabled) = req.enabled {
         sets.push("enabled = ?");
         enabled_val = Some(if enabled { 1 } else { 0 });
@@ -257,7 +397,17 @@ async fn update_idp(
 
     match query.execute(&state.db.pool).await {
         Ok(_) => {
-            info!(target: "audit", action = "idp.update", actor = %session.user_id, resource = %id, "Admin updated IdP");
+            let after = snapshot_or_null(&state.db.pool, IDP_SNAPSHOT, &id).await;
+            let diff_id = audit::record_diff(
+                &state.db.pool,
+                "idp.update",
+                &session.user_id,
+                Some(id.as_str()),
+                &before,
+                &after,
+            )
+            .await;
+            info!(target: "audit", action = "idp.update", actor = %session.user_id, resource = %id, diff_id = diff_id.as_deref().unwrap_or("-"), "Admin updated IdP");
             Json(serde_json::json!({ "status": "updated" })).into_response()
         }
         Err(e) => error::internal_error("update_idp", e),
@@ -291,6 +441,61 @@ async fn disable_idp(
     }
 }
 
+/// POST /api/admin/idps/:id/scim-token — Issue the IdP's SCIM token,
+/// replacing any previous one. The plaintext is only returned here.
+async fn create_scim_token(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let token = scim::generate_token();
+    match sqlx::query("UPDATE idp_configs SET scim_token_hash = ? WHERE id = ?")
+        .bind(tokens::hash_token(&token))
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "IdP not found" })),
+        )
+            .into_response(),
+        Ok(_) => {
+            info!(target: "audit", action = "idp.scim_token", actor = %session.user_id, resource = %id, "Admin issued SCIM token");
+            (
+                StatusCode::CREATED,
+                Json(serde_json::json!({ "token": token })),
+            )
+                .into_response()
+        }
+        Err(e) => error::internal_error("create_scim_token", e),
+    }
+}
+
+/// DELETE /api/admin/idps/:id/scim-token — Turn SCIM off for the IdP.
+async fn delete_scim_token(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match sqlx::query("UPDATE idp_configs SET scim_token_hash = NULL WHERE id = ?")
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "IdP not found" })),
+        )
+            .into_response(),
+        Ok(_) => {
+            info!(target: "audit", action = "idp.scim_token_delete", actor = %session.user_id, resource = %id, "Admin removed SCIM token");
+            Json(serde_json::json!({ "status": "deleted" })).into_response()
+        }
+        Err(e) => error::internal_error("delete_scim_token", e),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Category Management
 // ---------------------------------------------------------------------------
@@ -305,6 +510,106 @@ struct CreateCategoryRequest {
     name: String,
     description: Option<String>,
     preferred_model_id: Option<String>,
+    /// Model kind auto-categorization files into this category.
+    auto_kind: Option<String>,
+    /// Request log level; unset follows `REQUEST_LOG`.
+    log_level: Option<String>,
+    /// Requests in flight across the category's models; unset = no limit.
+    max_concurrent: Option<u32>,
+    /// Requests queued across the category's models; unset = no limit.
+    max_queue_depth: Option<u32>,
+    /// Seconds a request waits for a slot; unset follows `queue_timeout_secs`.
+    queue_timeout_secs: Option<u32>,
+    /// Parallel slots for the category's models when a start doesn't say.
+    default_parallel: Option<u32>,
+}
+
+/// A category's `max_concurrent` must let at least one request through.
+fn check_max_concurrent(max: u32) -> Option<Response> {
+    (max == 0).then(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "max_concurrent must be at least 1" })),
+        )
+            .into_response()
+    })
+}
+
+/// A category's `default_parallel` must be at least one slot.
+fn check_default_parallel(parallel: u32) -> Option<Response> {
+    (parallel == 0).then(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "default_parallel must be at least 1" })),
+        )
+            .into_response()
+    })
+}
+
+/// Push category limit changes to the concurrency gate.
+async fn reload_category_limits(state: &AppState) {
+    if let Err(e) = state.scheduler.reload_category_limits(&state.db).await {
+        error!(error = %e, "Failed to reload category limits");
+    }
+}
+
+/// A category's `log_level` must be `none`, `metadata` or `full`.
+fn check_log_level(level: &str) -> Option<Response> {
+    if request_log::LogLevel::parse(level).is_some() {
+        return None;
+    }
+    Some(
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "log_level must be one of: none, metadata, full"
+            })),
+        )
+            .into_response(),
+    )
+}
+
+/// A category's `auto_kind` must be a known kind that no other category takes.
+async fn check_auto_kind(
+    pool: &sqlx::SqlitePool,
+    kind: &str,
+    category_id: Option<&str>,
+) -> Option<Response> {
+    if categorize::ModelKind::parse(kind).is_none() {
+        let kinds: Vec<&str> = categorize::ModelKind::ALL
+            .iter()
+            .map(|k| k.as_str())
+            .collect();
+        return Some(
+            (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": format!("auto_kind must be one of: {}", kinds.join(", "))
+                })),
+            )
+                .into_response(),
+        );
+    }
+    let taken: Option<String> = match sqlx::query_scalar(
+        "SELECT name FROM model_categories WHERE auto_kind = ? AND id != COALESCE(?, '')",
+    )
+    .bind(kind)
+    .bind(category_id)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(row) => row,
+        Err(e) => return Some(error::internal_error("check_auto_kind", e)),
+    };
+    taken.map(|name| {
+        (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": format!("Category '{name}' already takes {kind} models")
+            })),
+        )
+            .into_response()
+    })
 }
 
 /// POST /api/admin/categories — Create a new model category.
@@ -320,20 +625,41 @@ async fn create_category(
     }) {
         return r;
     }
+    if let Some(kind) = &req.auto_kind {
+        if let Some(r) = check_auto_kind(&state.db.pool, kind, None).await {
+            return r;
+        }
+    }
+    if let Some(r) = req.log_level.as_deref().and_then(check_log_level) {
+        return r;
+    }
+    if let Some(r) = req.max_concurrent.and_then(check_max_concurrent) {
+        return r;
+    }
+    if let Some(r) = req.default_parallel.and_then(check_default_parallel) {
+        return r;
+    }
     let id = Uuid::new_v4().to_string();
     let desc = req.description.unwrap_or_default();
 
     match sqlx::query(
-        "INSERT INTO model_categories (id, name, description, preferred_model_id) VALUES (?, ?, ?, ?)",
+        "INSERT INTO model_categories (id, name, description, preferred_model_id, auto_kind, log_level, max_concurrent, max_queue_depth, queue_timeout_secs, default_parallel) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(&id)
     .bind(&req.name)
     .bind(&desc)
     .bind(&req.preferred_model_id)
+    .bind(&req.auto_kind)
+    .bind(&req.log_level)
+    .bind(req.max_concurrent)
+    .bind(req.max_queue_depth)
+    .bind(req.queue_timeout_secs)
+    .bind(req.default_parallel)
     .execute(&state.db.pool)
     .await
     {
         Ok(_) => {
+            reload_category_limits(&state).await;
             info!(target: "audit", action = "category.create", actor = %session.user_id, resource = %id, name = %req.name, "Admin created category");
             (
                 StatusCode::CREATED,
@@ -350,6 +676,26 @@ struct UpdateCategoryRequest {
     name: Option<String>,
     description: Option<String>,
     preferred_model_id: Option<String>,
+    /// Start unloaded models in this category when a request needs them.
+    autoload: Option<bool>,
+    /// Model kind auto-categorization files here; `null` clears it.
+    #[serde(default, deserialize_with = "present_or_null")]
+    auto_kind: Option<Option<String>>,
+    /// Request log level; `null` goes back to following `REQUEST_LOG`.
+    #[serde(default, deserialize_with = "present_or_null")]
+    log_level: Option<Option<String>>,
+    /// Category-wide in-flight limit; `null` removes it.
+    #[serde(default, deserialize_with = "present_or_null")]
+    max_concurrent: Option<Option<u32>>,
+    /// Category-wide queue limit; `null` removes it.
+    #[serde(default, deserialize_with = "present_or_null")]
+    max_queue_depth: Option<Option<u32>>,
+    /// Queue timeout for the category; `null` follows `queue_timeout_secs`.
+    #[serde(default, deserialize_with = "present_or_null")]
+    queue_timeout_secs: Option<Option<u32>>,
+    /// Default parallel slots for the category's models; `null` removes it.
+    #[serde(default, deserialize_with = "present_or_null")]
+    default_parallel: Option<Option<u32>>,
 }
 
 /// PUT /api/admin/categories/:id — Update a category.
@@ -386,6 +732,71 @@ async fn update_category(
         sets.push("preferred_model_id = ?");
         binds.push(preferred.clone());
     }
+    if let Some(autoload) = req.autoload {
+        // Stored as INTEGER; SQLite converts the bound text
+        sets.push("autoload = ?");
+        binds.push(i32::from(autoload).to_string());
+    }
+    match &req.auto_kind {
+        Some(Some(kind)) => {
+            if let Some(r) = check_auto_kind(&state.db.pool, kind, Some(&id)).await {
+                return r;
+            }
+            sets.push("auto_kind = ?");
+            binds.push(kind.clone());
+        }
+        Some(None) => sets.push("auto_kind = NULL"),
+        None => {}
+    }
+    match &req.log_level {
+        Some(Some(level)) => {
+            if let Some(r) = check_log_level(level) {
+                return r;
+            }
+            sets.push("log_level = ?");
+            binds.push(level.clone());
+        }
+        Some(None) => sets.push("log_level = NULL"),
+        None => {}
+    }
+    match req.max_concurrent {
+        Some(Some(max)) => {
+            if let Some(r) = check_max_concurrent(max) {
+                return r;
+            }
+            sets.push("max_concurrent = ?");
+            binds.push(max.to_string());
+        }
+        Some(None) => sets.push("max_concurrent = NULL"),
+        None => {}
+    }
+    match req.max_queue_depth {
+        Some(Some(max)) => {
+            sets.push("max_queue_depth = ?");
+            binds.push(max.to_string());
+        }
+        Some(None) => sets.push("max_queue_depth = NULL"),
+        None => {}
+    }
+    match req.queue_timeout_secs {
+        Some(Some(secs)) => {
+            sets.push("queue_timeout_secs = ?");
+            binds.push(secs.to_string());
+        }
+        Some(None) => sets.push("queue_timeout_secs = NULL"),
+        None => {}
+    }
+    match req.default_parallel {
+        Some(Some(parallel)) => {
+            if let Some(r) = check_default_parallel(parallel) {
+                return r;
+            }
+            sets.push("default_parallel = ?");
+            binds.push(parallel.to_string());
+        }
+        Some(None) => sets.push("default_parallel = NULL"),
+        None => {}
+    }
 
     if sets.is_empty() {
         return (
@@ -395,6 +806,7 @@ async fn update_category(
             .into_response();
     }
 
+    let before = snapshot_or_null(&state.db.pool, CATEGORY_SNAPSHOT, &id).await;
     let sql = format!(
         "UPDATE model_categories SET {} WHERE id = ?",
         sets.join(", ")
@@ -414,7 +826,18 @@ async fn update_category(
                 )
                     .into_response()
             } else {
-                info!(target: "audit", action = "category.update", actor = %session.user_id, resource = %id, "Admin updated category");
+                reload_category_limits(&state).await;
+                let after = snapshot_or_null(&state.db.pool, CATEGORY_SNAPSHOT, &id).await;
+                let diff_id = audit::record_diff(
+                    &state.db.pool,
+                    "category.update",
+                    &session.user_id,
+                    Some(id.as_str()),
+                    &before,
+                    &after,
+                )
+                .await;
+                info!(target: "audit", action = "category.update", actor = %session.user_id, resource = %id, diff_id = diff_id.as_deref().unwrap_or("-"), "Admin updated category");
                 Json(serde_json::json!({ "status": "updated" })).into_response()
             }
         }
@@ -428,6 +851,16 @@ async fn delete_category(
     Extension(session): Extension<SessionAuth>,
     Path(id): Path<String>,
 ) -> impl IntoResponse {
+    if let Err(e) = sqlx::query(
+        "UPDATE models SET suggested_category_id = NULL, category_suggestion_reason = NULL \
+         WHERE suggested_category_id = ?",
+    )
+    .bind(&id)
+    .execute(&state.db.pool)
+    .await
+    {
+        return error::internal_error("delete_category:suggestions", e);
+    }
     match sqlx::query("DELETE FROM model_categories WHERE id = ?")
         .bind(&id)
         .execute(&state.db.pool)
@@ -441,6 +874,7 @@ async fn delete_category(
                 )
                     .into_response()
             } else {
+                reload_category_limits(&state).await;
                 info!(target: "audit", action = "category.delete", actor = %session.user_id, resource = %id, "Admin deleted category");
                 Json(serde_json::json!({ "status": "deleted" })).into_response()
             }
@@ -483,9 +917,12 @@ async fn register_model(
     if let Some(r) = error::validate_hf_repo(&req.hf_repo) {
         return r;
     }
+    let backend_type = req.backend_type.as_deref().unwrap_or("llamacpp");
+    if let Some(r) = error::validate_backend_type(backend_type) {
+        return r;
+    }
     let id = Uuid::new_v4().to_string();
 
-    let backend_type = req.backend_type.as_deref().unwrap_or("llamacpp");
     // runtime_overrides defaults to '{}' via the column DEFAULT — don't bind it here.
     match sqlx::query(
         "INSERT INTO models (id, hf_repo, category_id, backend_type) VALUES (?, ?, ?, ?)",
@@ -516,6 +953,34 @@ struct UpdateModelRequest {
     /// `category_id` is updated (preserves the historical PUT semantics).
     #[serde(default)]
     runtime_overrides: Option<ModelRuntimeOverrides>,
+    /// Switch the serving backend (`llamacpp` or `vllm`). Only allowed while
+    /// the model is unloaded.
+    #[serde(default)]
+    backend_type: Option<String>,
+    /// Per-model idle timeout: minutes, `0` to never unload, or `null` to
+    /// follow the `idle_unload_minutes` setting. Absent leaves it unchanged.
+    #[serde(default, deserialize_with = "present_or_null")]
+    idle_unload_minutes: Option<Option<i64>>,
+    /// Start the container when a request arrives while the model is unloaded.
+    #[serde(default)]
+    autoload: Option<bool>,
+    /// Mark the model as an embedding model (served on `/v1/embeddings`).
+    /// Takes effect on the next container start.
+    #[serde(default)]
+    embedding: Option<bool>,
+    /// `reject` or `truncate` prompts longer than the model's context.
+    #[serde(default)]
+    context_policy: Option<String>,
+}
+
+/// Tell a field that is explicitly `null` (`Some(None)`) apart from one that
+/// is absent (`None`, via `#[serde(default)]`).
+fn present_or_null<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
 }
 
 /// PUT /api/admin/models/:id — Update model metadata.
@@ -544,6 +1009,121 @@ async fn update_model(
         None => None,
     };
 
+    let before = snapshot_or_null(&state.db.pool, MODEL_SNAPSHOT, &id).await;
+
+    if let Some(ref bt) = req.backend_type {
+        if let Some(r) = error::validate_backend_type(bt) {
+            return r;
+        }
+        match sqlx::query("UPDATE models SET backend_type = ? WHERE id = ? AND loaded = 0")
+            .bind(bt)
+            .bind(&id)
+            .execute(&state.db.pool)
+            .await
+        {
+            Ok(r) if r.rows_affected() == 0 => {
+                let exists = sqlx::query_as::<_, (i64,)>("SELECT 1 FROM models WHERE id = ?")
+                    .bind(&id)
+                    .fetch_optional(&state.db.pool)
+                    .await
+                    .ok()
+                    .flatten()
+                    .is_some();
+                return if exists {
+                    (
+                        StatusCode::CONFLICT,
+                        Json(serde_json::json!({ "error": "Stop the model before changing its backend_type" })),
+                    )
+                        .into_response()
+                } else {
+                    (
+                        StatusCode::NOT_FOUND,
+                        Json(serde_json::json!({ "error": "Model not found" })),
+                    )
+                        .into_response()
+                };
+            }
+            Ok(_) => {
+                info!(target: "audit", action = "model.backend_change", actor = %session.user_id, resource = %id, backend_type = %bt, "Admin changed model backend");
+            }
+            Err(e) => return error::internal_error("update_model:backend_type", e),
+        }
+    }
+
+    if let Some(minutes) = req.idle_unload_minutes {
+        if minutes.is_some_and(|m| m < 0) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "idle_unload_minutes must be a non-negative integer or null" })),
+            )
+                .into_response();
+        }
+        match sqlx::query("UPDATE models SET idle_unload_minutes = ? WHERE id = ?")
+            .bind(minutes)
+            .bind(&id)
+            .execute(&state.db.pool)
+            .await
+        {
+            Ok(r) if r.rows_affected() > 0 => {
+                info!(target: "audit", action = "model.idle_unload_change", actor = %session.user_id, resource = %id, idle_unload_minutes = ?minutes, "Admin changed model idle timeout");
+            }
+            Ok(_) => {} // Missing model is reported by the update below
+            Err(e) => return error::internal_error("update_model:idle_unload_minutes", e),
+        }
+    }
+
+    if let Some(autoload) = req.autoload {
+        match sqlx::query("UPDATE models SET autoload = ? WHERE id = ?")
+            .bind(autoload)
+            .bind(&id)
+            .execute(&state.db.pool)
+            .await
+        {
+            Ok(r) if r.rows_affected() > 0 => {
+                info!(target: "audit", action = "model.autoload_change", actor = %session.user_id, resource = %id, autoload, "Admin changed model autoload");
+            }
+            Ok(_) => {} // Missing model is reported by the update below
+            Err(e) => return error::internal_error("update_model:autoload", e),
+        }
+    }
+
+    if let Some(embedding) = req.embedding {
+        match sqlx::query("UPDATE models SET embedding = ? WHERE id = ?")
+            .bind(embedding)
+            .bind(&id)
+            .execute(&state.db.pool)
+            .await
+        {
+            Ok(r) if r.rows_affected() > 0 => {
+                info!(target: "audit", action = "model.embedding_change", actor = %session.user_id, resource = %id, embedding, "Admin changed model embedding flag");
+            }
+            Ok(_) => {} // Missing model is reported by the update below
+            Err(e) => return error::internal_error("update_model:embedding", e),
+        }
+    }
+
+    if let Some(policy) = &req.context_policy {
+        if context_guard::ContextPolicy::parse(policy).is_none() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": format!("context_policy must be one of: {}", context_guard::ContextPolicy::ALL.join(", ")) })),
+            )
+                .into_response();
+        }
+        match sqlx::query("UPDATE models SET context_policy = ? WHERE id = ?")
+            .bind(policy)
+            .bind(&id)
+            .execute(&state.db.pool)
+            .await
+        {
+            Ok(r) if r.rows_affected() > 0 => {
+                info!(target: "audit", action = "model.context_policy_change", actor = %session.user_id, resource = %id, context_policy = %policy, "Admin changed model context policy");
+            }
+            Ok(_) => {} // Missing model is reported by the update below
+            Err(e) => return error::internal_error("update_model:context_policy", e),
+        }
+    }
+
     let result = match &overrides_json {
         Some(json) => {
             sqlx::query("UPDATE models SET category_id = ?, runtime_overrides = ? WHERE id = ?")
@@ -571,12 +1151,33 @@ async fn update_model(
                 )
                     .into_response()
             } else {
+                // A category chosen by hand supersedes any suggestion
+                if req.category_id.is_some() {
+                    let _ = sqlx::query(
+                        "UPDATE models SET suggested_category_id = NULL, \
+                         category_suggestion_reason = NULL WHERE id = ?",
+                    )
+                    .bind(&id)
+                    .execute(&state.db.pool)
+                    .await;
+                }
+                let after = snapshot_or_null(&state.db.pool, MODEL_SNAPSHOT, &id).await;
+                let diff_id = audit::record_diff(
+                    &state.db.pool,
+                    "model.update",
+                    &session.user_id,
+                    Some(id.as_str()),
+                    &before,
+                    &after,
+                )
+                .await;
+                let diff_id = diff_id.as_deref().unwrap_or("-");
                 match &overrides_json {
                     Some(json) => {
-                        info!(target: "audit", action = "model.update", actor = %session.user_id, resource = %id, runtime_overrides = %json, "Admin updated model");
+                        info!(target: "audit", action = "model.update", actor = %session.user_id, resource = %id, runtime_overrides = %json, diff_id, "Admin updated model");
                     }
                     None => {
-                        info!(target: "audit", action = "model.update", actor = %session.user_id, resource = %id, "Admin updated model");
+                        info!(target: "audit", action = "model.update", actor = %session.user_id, resource = %id, diff_id, "Admin updated model");
                     }
                 }
                 Json(serde_json::json!({ "status": "updated" })).into_response()
@@ -586,54 +1187,283 @@ async fn update_model(
     }
 }
 
-/// Query parameters for `DELETE /api/admin/models/:id`.
-///
-/// `override=true` opts in to force-revoking any currently-active tokens that
-/// are pinned to this model (via `specific_model_id`). Without the override,
-/// an active pin causes the handler to return 409 with a list of blockers.
-#[derive(Deserialize, Default)]
-struct DeleteModelQuery {
-    #[serde(default, rename = "override")]
-    override_: bool,
-}
-
-/// Row returned by the blocking-tokens pre-check.
-#[derive(sqlx::FromRow, Serialize)]
-struct BlockingToken {
-    id: String,
-    name: String,
-    user_email: Option<String>,
-}
-
-/// DELETE /api/admin/models/:id — Delete a model.
-///
-/// Flow:
-/// 1. Look up the model (404 on miss).
-/// 2. Pre-check for active pins (`tokens.specific_model_id` where the token
-///    is neither revoked nor soft-deleted). If any are found and
-///    `override=true` is not set, return 409 with the blocker list — no side
-///    effects.
-/// 3. When `override=true`, soft-delete each blocking token (revoked=1,
-///    deleted_at=now) with audit logging.
-/// 4. Stop the backend container if loaded; run post-stop cleanup.
-/// 5. In a single DB transaction: NULL any remaining `specific_model_id`
-///    pins (covers just-overridden blockers and any pre-existing stale
-///    pins), defensively delete the `container_secrets` row, and delete the
-///    `models` row.
-/// 6. Remove files from disk only after the DB commit succeeds, so a
-///    failure never leaves an orphaned DB row or orphaned files on disk.
-async fn delete_model(
+/// GET /api/admin/models/:id/launch-profile — The model's saved launch settings.
+async fn get_launch_profile(
     State(state): State<Arc<AppState>>,
-    Extension(session): Extension<SessionAuth>,
     Path(id): Path<String>,
-    Query(params): Query<DeleteModelQuery>,
 ) -> impl IntoResponse {
-    // 1. Look up the model.
-    let model: Option<(String, String, bool, String)> =
-        match sqlx::query_as("SELECT id, hf_repo, loaded, backend_type FROM models WHERE id = ?")
+    match sqlx::query_as::<_, LaunchProfile>(
+        "SELECT backend_type, gpu_type, gpu_layers, context_size, parallel, cpu_limit, memory_limit_mb, pids_limit FROM model_launch_profiles WHERE model_id = ?",
+    )
+    .bind(&id)
+    .fetch_optional(&state.db.pool)
+    .await
+    {
+        Ok(Some(profile)) => {
+            Json(serde_json::json!({ "model_id": id, "profile": profile })).into_response()
+        }
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "No launch profile saved for this model" })),
+        )
+            .into_response(),
+        Err(e) => error::internal_error("get_launch_profile", e),
+    }
+}
+
+/// PUT /api/admin/models/:id/launch-profile — Save the model's preferred
+/// launch settings, replacing any previous profile.
+async fn put_launch_profile(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(profile): Json<LaunchProfile>,
+) -> impl IntoResponse {
+    if let Some(r) = profile
+        .backend_type
+        .as_deref()
+        .and_then(error::validate_backend_type)
+    {
+        return r;
+    }
+    if profile.context_size == Some(0) || profile.parallel == Some(0) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "context_size and parallel must be positive" })),
+        )
+            .into_response();
+    }
+    if let Err(msg) = common::profile_limits(&profile).validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": msg })),
+        )
+            .into_response();
+    }
+
+    let context_length: Option<(Option<i64>,)> =
+        match sqlx::query_as("SELECT context_length FROM models WHERE id = ?")
             .bind(&id)
             .fetch_optional(&state.db.pool)
             .await
+        {
+            Ok(row) => row,
+            Err(e) => return error::internal_error("put_launch_profile:lookup", e),
+        };
+    let Some((context_length,)) = context_length else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Model not found" })),
+        )
+            .into_response();
+    };
+    if let (Some(size), Some(max)) = (profile.context_size, context_length) {
+        if i64::from(size) > max {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": format!("context_size exceeds the model's context length ({max})") })),
+            )
+                .into_response();
+        }
+    }
+
+    match sqlx::query(
+        r#"
+        INSERT INTO model_launch_profiles (model_id, backend_type, gpu_type, gpu_layers, context_size, parallel, cpu_limit, memory_limit_mb, pids_limit, updated_by, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, datetime('now'))
+        ON CONFLICT(model_id) DO UPDATE SET
+            backend_type = excluded.backend_type,
+            gpu_type = excluded.gpu_type,
+            gpu_layers = excluded.gpu_layers,
+            context_size = excluded.context_size,
+            parallel = excluded.parallel,
+            cpu_limit = excluded.cpu_limit,
+            memory_limit_mb = excluded.memory_limit_mb,
+            pids_limit = excluded.pids_limit,
+            updated_by = excluded.updated_by,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(&id)
+    .bind(&profile.backend_type)
+    .bind(&profile.gpu_type)
+    .bind(profile.gpu_layers)
+    .bind(profile.context_size)
+    .bind(profile.parallel)
+    .bind(profile.cpu_limit)
+    .bind(profile.memory_limit_mb)
+    .bind(profile.pids_limit)
+    .bind(&session.user_id)
+    .execute(&state.db.pool)
+    .await
+    {
+        Ok(_) => {
+            info!(target: "audit", action = "model.launch_profile_update", actor = %session.user_id, resource = %id, profile = ?profile, "Admin saved model launch profile");
+            Json(serde_json::json!({ "model_id": id, "profile": profile })).into_response()
+        }
+        Err(e) => error::internal_error("put_launch_profile", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SetDrainingRequest {
+    draining: bool,
+}
+
+/// PUT /api/admin/models/:id/draining — Stop (or resume) accepting new requests
+/// for a model. In-flight requests are unaffected; the response reports how many
+/// are still running so the caller knows when it is safe to unload.
+async fn set_model_draining(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(req): Json<SetDrainingRequest>,
+) -> impl IntoResponse {
+    let result = sqlx::query("UPDATE models SET draining = ? WHERE id = ?")
+        .bind(req.draining)
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await;
+
+    match result {
+        Ok(r) if r.rows_affected() == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Model not found" })),
+        )
+            .into_response(),
+        Ok(_) => {
+            info!(target: "audit", action = "model.draining", actor = %session.user_id, resource = %id, draining = req.draining, "Admin changed model drain state");
+            let in_flight = state
+                .scheduler
+                .gate()
+                .status()
+                .await
+                .get(&id)
+                .map_or(0, |g| g.in_flight);
+            Json(serde_json::json!({
+                "draining": req.draining,
+                "in_flight": in_flight,
+            }))
+            .into_response()
+        }
+        Err(e) => error::internal_error("set_model_draining", e),
+    }
+}
+
+/// Query parameters for `DELETE /api/admin/models/:id`.
+///
+/// `override=true` opts in to force-revoking any currently-active tokens that
+/// are pinned to this model (via `specific_model_id`). Without the override,
+/// an active pin causes the handler to return 409 with a list of blockers.
+#[derive(Deserialize, Default)]
+struct DeleteModelQuery {
+    #[serde(default, rename = "override")]
+    override_: bool,
+    /// Report what would be removed without removing it.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// A file under a model directory, relative to it.
+#[derive(Serialize)]
+pub(crate) struct ModelFile {
+    pub(crate) path: String,
+    pub(crate) size_bytes: u64,
+}
+
+/// Every file under `dir`, sorted by relative path. A missing directory is
+/// empty.
+pub(crate) fn list_model_files(dir: &std::path::Path) -> Vec<ModelFile> {
+    fn walk(root: &std::path::Path, dir: &std::path::Path, out: &mut Vec<ModelFile>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            if meta.is_dir() {
+                walk(root, &path, out);
+            } else {
+                out.push(ModelFile {
+                    path: path
+                        .strip_prefix(root)
+                        .unwrap_or(&path)
+                        .to_string_lossy()
+                        .into_owned(),
+                    size_bytes: meta.len(),
+                });
+            }
+        }
+    }
+    let mut files = Vec::new();
+    walk(dir, dir, &mut files);
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+/// Row returned by the blocking-tokens pre-check.
+#[derive(sqlx::FromRow, Serialize)]
+struct BlockingToken {
+    id: String,
+    name: String,
+    user_email: Option<String>,
+}
+
+/// DELETE /api/admin/models/:id — Delete a model.
+///
+/// Flow:
+/// 1. Look up the model (404 on miss).
+/// 2. Pre-check for active pins (`tokens.specific_model_id` where the token
+///    is neither revoked nor soft-deleted). If any are found and
+///    `override=true` is not set, return 409 with the blocker list — no side
+///    effects.
+/// 3. When `override=true`, soft-delete each blocking token (revoked=1,
+///    deleted_at=now) with audit logging.
+/// 4. Stop the backend container if loaded; run post-stop cleanup.
+/// 5. In a single DB transaction: NULL any remaining `specific_model_id`
+///    pins (covers just-overridden blockers and any pre-existing stale
+///    pins), defensively delete the `container_secrets` row, and delete the
+///    `models` row.
+///    Unless `MODEL_TRASH_HOURS` is 0, the same transaction records a
+///    `model_trash` entry holding copies of the deleted rows.
+/// 6. Move the files to the trash (or remove them) only after the DB commit
+///    succeeds, so a failure never leaves an orphaned DB row or orphaned
+///    files on disk.
+async fn delete_model(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Query(params): Query<DeleteModelQuery>,
+) -> impl IntoResponse {
+    delete_model_core(
+        &state,
+        &session.user_id,
+        &id,
+        params.override_,
+        params.dry_run,
+        None,
+    )
+    .await
+}
+
+/// The body of `delete_model`. `approved_by` is set when a second admin
+/// approved the delete, which skips the two-person approval gate.
+pub(crate) async fn delete_model_core(
+    state: &Arc<AppState>,
+    actor: &str,
+    id: &str,
+    override_: bool,
+    dry_run: bool,
+    approved_by: Option<&str>,
+) -> Response {
+    // 1. Look up the model.
+    let model: Option<(String, String, bool, String)> =
+        match sqlx::query_as("SELECT id, hf_repo, loaded, backend_type FROM models WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&state.db.pool)
+            .await
         {
             Ok(row) => row,
             Err(e) => return error::internal_error("delete_model:lookup", e),
@@ -664,7 +1494,7 @@ async fn delete_model(
         Err(e) => return error::internal_error("delete_model:blockers", e),
     };
 
-    if !blockers.is_empty() && !params.override_ {
+    if !blockers.is_empty() && !override_ {
         // 409 with blocker list — no state mutated.
         return (
             StatusCode::CONFLICT,
@@ -679,6 +1509,55 @@ async fn delete_model(
             .into_response();
     }
 
+    let safe_repo = hf_repo.replace('/', "--");
+    let model_dir = format!("{}/{}", state.config.model_path, safe_repo);
+
+    if dry_run {
+        // Pins beyond the active blockers are stale and would be nulled.
+        let pins: i64 =
+            match sqlx::query_scalar("SELECT COUNT(*) FROM tokens WHERE specific_model_id = ?")
+                .bind(&model_id)
+                .fetch_one(&state.db.pool)
+                .await
+            {
+                Ok(n) => n,
+                Err(e) => return error::internal_error("delete_model:dry_run_pins", e),
+            };
+        let dir = std::path::PathBuf::from(&model_dir);
+        let files = tokio::task::spawn_blocking(move || list_model_files(&dir))
+            .await
+            .unwrap_or_default();
+        let total_bytes: u64 = files.iter().map(|f| f.size_bytes).sum();
+        let container = if loaded {
+            Some(common::serving_container_name(&state.db.pool, &model_id, &backend_type).await)
+        } else {
+            None
+        };
+        return Json(serde_json::json!({
+            "dry_run": true,
+            "model_id": model_id,
+            "hf_repo": hf_repo,
+            "container": container,
+            "revoked_tokens": blockers,
+            "cleared_pins": pins - blockers.len() as i64,
+            "model_dir": model_dir,
+            "files": files,
+            "total_bytes": total_bytes,
+        }))
+        .into_response();
+    }
+
+    if approved_by.is_none() && state.config.two_person_approval {
+        return approvals::request(
+            state,
+            actor,
+            approvals::MODEL_DELETE,
+            &model_id,
+            serde_json::json!({ "override": override_ }),
+        )
+        .await;
+    }
+
     // 3. Override path: soft-delete each blocker.
     for blocker in &blockers {
         if let Err(e) =
@@ -689,10 +1568,11 @@ async fn delete_model(
         {
             return error::internal_error("delete_model:soft_delete_token", e);
         }
+        auth::token_changed(state, &blocker.id);
         info!(
             target: "audit",
             action = "token.force_revoke",
-            actor = %session.user_id,
+            actor = %actor,
             resource = %blocker.id,
             reason = "model_delete_override",
             model_id = %model_id,
@@ -702,13 +1582,29 @@ async fn delete_model(
 
     // 4. Stop the running container if loaded.
     if loaded {
-        if let Err(e) = state.docker.stop_backend(&model_id, &backend_type).await {
+        let container =
+            common::serving_container_name(&state.db.pool, &model_id, &backend_type).await;
+        if let Err(e) = state
+            .docker
+            .stop_named(&container, &model_id, StopMode::Force)
+            .await
+        {
             error!(model = %model_id, error = %e, "Failed to stop container during model delete");
             // Continue — container may already be gone.
         }
-        common::post_stop_cleanup(&state, &model_id).await;
+        common::post_stop_cleanup(state, &model_id).await;
     }
 
+    // Copies of the rows for the trash, taken before anything is deleted
+    let snapshot = if state.config.model_trash_hours > 0 {
+        match model_trash::snapshot_model(&state.db.pool, &model_id).await {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => return error::internal_error("delete_model:snapshot", e),
+        }
+    } else {
+        None
+    };
+
     // 5. Transactional cleanup + DB delete.
     let mut tx = match state.db.pool.begin().await {
         Ok(tx) => tx,
@@ -746,37 +1642,66 @@ async fn delete_model(
         return error::internal_error("delete_model:db", e);
     }
 
+    let trash_id = match &snapshot {
+        Some(snapshot) => match model_trash::insert_entry(
+            &mut *tx,
+            snapshot,
+            &model_id,
+            &hf_repo,
+            actor,
+            state.config.model_trash_hours,
+        )
+        .await
+        {
+            Ok(id) => Some(id),
+            Err(e) => return error::internal_error("delete_model:trash", e),
+        },
+        None => None,
+    };
+
     if let Err(e) = tx.commit().await {
         return error::internal_error("delete_model:tx_commit", e);
     }
+    // Cached token scopes may still name the deleted model
+    state.token_cache.clear();
 
-    // 6. Remove files from disk — only after the DB commit succeeded.
-    let safe_repo = hf_repo.replace('/', "--");
-    let model_dir = format!("{}/{}", state.config.model_path, safe_repo);
+    // 6. Move files to the trash or remove them — only after the DB commit
+    // succeeded.
     if std::path::Path::new(&model_dir).exists() {
-        if let Err(e) = tokio::fs::remove_dir_all(&model_dir).await {
-            error!(path = %model_dir, error = %e, "Failed to delete model files after DB delete");
+        let result = match &trash_id {
+            Some(trash_id) => model_trash::move_files(state, trash_id, &model_dir).await,
+            None => tokio::fs::remove_dir_all(&model_dir)
+                .await
+                .map_err(Into::into),
+        };
+        if let Err(e) = result {
+            error!(path = %model_dir, error = %e, "Failed to remove model files after DB delete");
             // DB row is already gone; surface a distinct error code so
             // operators can tell this from the DB-failure case above.
             return error::internal_error("delete_model:files", e);
         }
-        info!(path = %model_dir, "Model files deleted");
+        if trash_id.is_none() {
+            info!(path = %model_dir, "Model files deleted");
+        }
     }
 
     info!(
         target: "audit",
         action = "model.delete",
-        actor = %session.user_id,
+        actor = %actor,
         resource = %model_id,
         hf_repo = %hf_repo,
-        overridden = params.override_,
+        overridden = override_,
+        approved_by = approved_by.unwrap_or("-"),
         revoked_tokens = blockers.len(),
+        trash_id = trash_id.as_deref().unwrap_or("-"),
         "Admin deleted model"
     );
 
     Json(serde_json::json!({
         "status": "deleted",
         "revoked_tokens": blockers.len(),
+        "trash_id": trash_id,
     }))
     .into_response()
 }
@@ -788,7 +1713,7 @@ async fn delete_model(
 /// GET /api/admin/users — List all users with usage stats.
 async fn list_users(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     match sqlx::query_as::<_, User>(
-        "SELECT id, idp_id, subject, email, display_name, is_admin, created_at FROM users",
+        "SELECT id, idp_id, subject, email, display_name, is_admin, active, priority_tier, created_at FROM users",
     )
     .fetch_all(&state.db.pool)
     .await
@@ -806,57 +1731,354 @@ async fn list_users(State(state): State<Arc<AppState>>) -> impl IntoResponse {
                 .await
                 .unwrap_or((0, 0));
 
-                let mut entry = serde_json::to_value(user).unwrap_or_default();
-                entry["usage_summary"] = serde_json::json!({
-                    "total_requests": usage.0,
-                    "total_tokens": usage.1,
-                });
-                data.push(entry);
-            }
+                let roles = user_roles(&state.db.pool, &user.id)
+                    .await
+                    .unwrap_or_default();
+
+                let mut entry = serde_json::to_value(user).unwrap_or_default();
+                entry["usage_summary"] = serde_json::json!({
+                    "total_requests": usage.0,
+                    "total_tokens": usage.1,
+                });
+                entry["roles"] = serde_json::json!(roles);
+                data.push(entry);
+            }
+
+            Json(serde_json::json!({ "users": data })).into_response()
+        }
+        Err(e) => error::internal_error("list_users", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateUserRequest {
+    is_admin: Option<bool>,
+    /// `false` deactivates the user, revoking their sessions and tokens.
+    active: Option<bool>,
+    /// Queue priority tier: `low`, `normal` or `high`.
+    priority_tier: Option<String>,
+}
+
+/// PUT /api/admin/users/:id — Update user (toggle admin, etc).
+async fn update_user(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateUserRequest>,
+) -> impl IntoResponse {
+    // Elevated admins can't turn their time-boxed rights into permanent ones
+    if req.is_admin == Some(true) {
+        match elevation::is_permanent_admin(&state.db.pool, &session.user_id).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return (
+                    StatusCode::FORBIDDEN,
+                    Json(serde_json::json!({ "error": "Only permanent admins can grant admin rights" })),
+                )
+                    .into_response();
+            }
+            Err(e) => return error::internal_error("update_user:actor", e),
+        }
+    }
+    if let Some(tier) = &req.priority_tier {
+        let Some(tier) = PriorityTier::parse(tier) else {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "priority_tier must be one of: low, normal, high" })),
+            )
+                .into_response();
+        };
+        match sqlx::query("UPDATE users SET priority_tier = ? WHERE id = ?")
+            .bind(tier.as_str())
+            .bind(&id)
+            .execute(&state.db.pool)
+            .await
+        {
+            Ok(result) if result.rows_affected() == 0 => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(serde_json::json!({ "error": "User not found" })),
+                )
+                    .into_response();
+            }
+            Ok(_) => {
+                info!(target: "audit", action = "user.priority_tier", actor = %session.user_id, resource = %id, tier = tier.as_str(), "Admin set user priority tier");
+            }
+            Err(e) => return error::internal_error("update_user:priority_tier", e),
+        }
+        if req.active.is_none() && req.is_admin.is_none() {
+            return Json(serde_json::json!({ "status": "updated" })).into_response();
+        }
+    }
+    if let Some(active) = req.active {
+        if !active && id == session.user_id {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "Cannot deactivate your own account" })),
+            )
+                .into_response();
+        }
+        let exists: Option<String> = match sqlx::query_scalar("SELECT id FROM users WHERE id = ?")
+            .bind(&id)
+            .fetch_optional(&state.db.pool)
+            .await
+        {
+            Ok(row) => row,
+            Err(e) => return error::internal_error("update_user:lookup", e),
+        };
+        if exists.is_none() {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "User not found" })),
+            )
+                .into_response();
+        }
+        if let Err(e) = scim::set_user_active(&state, &session.user_id, &id, active).await {
+            return error::internal_error("update_user:active", e);
+        }
+        if req.is_admin.is_none() {
+            return Json(serde_json::json!({ "status": "updated" })).into_response();
+        }
+    }
+    match req.is_admin {
+        Some(is_admin) => set_user_admin(&state, &session.user_id, &id, is_admin, None).await,
+        None => {
+            info!(target: "audit", action = "user.update", actor = %session.user_id, resource = %id, "Admin updated user");
+            Json(serde_json::json!({ "status": "updated" })).into_response()
+        }
+    }
+}
+
+/// Grant or revoke admin rights. Granting them needs a second admin's
+/// approval when two-person approval is on; `approved_by` is set once it has
+/// been given.
+pub(crate) async fn set_user_admin(
+    state: &Arc<AppState>,
+    actor: &str,
+    id: &str,
+    is_admin: bool,
+    approved_by: Option<&str>,
+) -> Response {
+    let current: Option<bool> = match sqlx::query_scalar("SELECT is_admin FROM users WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db.pool)
+        .await
+    {
+        Ok(row) => row,
+        Err(e) => return error::internal_error("update_user:lookup", e),
+    };
+    let Some(current) = current else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "User not found" })),
+        )
+            .into_response();
+    };
+
+    if is_admin && !current && approved_by.is_none() && state.config.two_person_approval {
+        return approvals::request(
+            state,
+            actor,
+            approvals::USER_ELEVATE,
+            id,
+            serde_json::json!({}),
+        )
+        .await;
+    }
+
+    if let Err(e) = sqlx::query("UPDATE users SET is_admin = ? WHERE id = ?")
+        .bind(is_admin)
+        .bind(id)
+        .execute(&state.db.pool)
+        .await
+    {
+        return error::internal_error("update_user", e);
+    }
+    // Cached tokens and JWTs carry the old admin flag
+    auth::user_changed(state, id);
+
+    info!(target: "audit", action = "user.update", actor = %actor, resource = %id, is_admin, approved_by = approved_by.unwrap_or("-"), "Admin updated user");
+    Json(serde_json::json!({ "status": "updated" })).into_response()
+}
+
+/// A user's scoped admin roles, by name.
+async fn user_roles(pool: &sqlx::SqlitePool, user_id: &str) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT role FROM user_roles WHERE user_id = ? ORDER BY role")
+        .bind(user_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// GET /api/admin/roles — The roles that can be assigned.
+async fn list_roles() -> impl IntoResponse {
+    let roles: Vec<serde_json::Value> = Role::ALL
+        .iter()
+        .map(|r| serde_json::json!({ "name": r, "description": r.description() }))
+        .collect();
+    Json(serde_json::json!({ "roles": roles }))
+}
+
+/// GET /api/admin/users/:id/roles — A user's scoped admin roles.
+async fn get_user_roles(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match user_roles(&state.db.pool, &id).await {
+        Ok(roles) => Json(serde_json::json!({ "user_id": id, "roles": roles })).into_response(),
+        Err(e) => error::internal_error("user_roles:get", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UserRolesRequest {
+    roles: Vec<String>,
+}
+
+/// POST /api/admin/users/:id/revoke-sessions — Log a user out everywhere.
+/// Their API tokens are left alone.
+async fn revoke_user_sessions(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let exists: Option<String> = match sqlx::query_scalar("SELECT id FROM users WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&state.db.pool)
+        .await
+    {
+        Ok(row) => row,
+        Err(e) => return error::internal_error("revoke_user_sessions:lookup", e),
+    };
+    if exists.is_none() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "User not found" })),
+        )
+            .into_response();
+    }
+    match sessions::revoke_user_sessions(&state.sessions, &state.db, &id).await {
+        Ok(revoked) => {
+            info!(target: "audit", action = "user.revoke_sessions", actor = %session.user_id, resource = %id, sessions = revoked, "Admin revoked user sessions");
+            Json(serde_json::json!({ "revoked_sessions": revoked })).into_response()
+        }
+        Err(e) => error::internal_error("revoke_user_sessions", e),
+    }
+}
+
+/// PUT /api/admin/users/:id/roles — Replace a user's scoped admin roles.
+async fn put_user_roles(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(req): Json<UserRolesRequest>,
+) -> impl IntoResponse {
+    let mut roles = Vec::with_capacity(req.roles.len());
+    for name in &req.roles {
+        match Role::parse(name) {
+            Some(role) if !roles.contains(&role) => roles.push(role),
+            Some(_) => {}
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({ "error": format!("Unknown role '{name}'") })),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    // Role admins can't widen their own access
+    if id == session.user_id && !session.is_admin {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({ "error": "You can't change your own roles" })),
+        )
+            .into_response();
+    }
+
+    set_user_roles(&state, &session.user_id, &id, &roles, None).await
+}
+
+/// Replace a user's scoped admin roles. Handing out a role that manages
+/// users (and so every other role) needs a second admin's approval when
+/// two-person approval is on; `approved_by` is set once it has been given.
+pub(crate) async fn set_user_roles(
+    state: &Arc<AppState>,
+    actor: &str,
+    id: &str,
+    roles: &[Role],
+    approved_by: Option<&str>,
+) -> Response {
+    let exists: Option<String> = match sqlx::query_scalar("SELECT id FROM users WHERE id = ?")
+        .bind(id)
+        .fetch_optional(&state.db.pool)
+        .await
+    {
+        Ok(row) => row,
+        Err(e) => return error::internal_error("user_roles:lookup", e),
+    };
+    if exists.is_none() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "User not found" })),
+        )
+            .into_response();
+    }
+
+    let before = match user_roles(&state.db.pool, id).await {
+        Ok(roles) => roles,
+        Err(e) => return error::internal_error("user_roles:before", e),
+    };
 
-            Json(serde_json::json!({ "users": data })).into_response()
-        }
-        Err(e) => error::internal_error("list_users", e),
+    let escalates = roles
+        .iter()
+        .any(|r| r.manages_users() && !before.iter().any(|b| b == r.as_str()));
+    if escalates && approved_by.is_none() && state.config.two_person_approval {
+        let names: Vec<&str> = roles.iter().map(Role::as_str).collect();
+        return approvals::request(
+            state,
+            actor,
+            approvals::ROLES_GRANT,
+            id,
+            serde_json::json!({ "roles": names }),
+        )
+        .await;
     }
-}
-
-#[derive(Debug, Deserialize)]
-struct UpdateUserRequest {
-    is_admin: Option<bool>,
-}
 
-/// PUT /api/admin/users/:id — Update user (toggle admin, etc).
-async fn update_user(
-    State(state): State<Arc<AppState>>,
-    Extension(session): Extension<SessionAuth>,
-    Path(id): Path<String>,
-    Json(req): Json<UpdateUserRequest>,
-) -> impl IntoResponse {
-    if let Some(is_admin) = req.is_admin {
-        let admin_val: i32 = if is_admin { 1 } else { 0 };
-        match sqlx::query("UPDATE users SET is_admin = ? WHERE id = ?")
-            .bind(admin_val)
-            .bind(&id)
-            .execute(&state.db.pool)
-            .await
-        {
-            Ok(result) => {
-                if result.rows_affected() == 0 {
-                    return (
-                        StatusCode::NOT_FOUND,
-                        Json(serde_json::json!({ "error": "User not found" })),
-                    )
-                        .into_response();
-                }
-            }
-            Err(e) => {
-                return error::internal_error("update_user", e);
-            }
+    let result: Result<(), sqlx::Error> = async {
+        let mut tx = state.db.pool.begin().await?;
+        sqlx::query("DELETE FROM user_roles WHERE user_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        for role in roles {
+            sqlx::query("INSERT INTO user_roles (user_id, role, granted_by) VALUES (?, ?, ?)")
+                .bind(id)
+                .bind(role.as_str())
+                .bind(actor)
+                .execute(&mut *tx)
+                .await?;
         }
+        tx.commit().await
+    }
+    .await;
+    if let Err(e) = result {
+        return error::internal_error("user_roles:put", e);
     }
 
-    info!(target: "audit", action = "user.update", actor = %session.user_id, resource = %id, "Admin updated user");
-    Json(serde_json::json!({ "status": "updated" })).into_response()
+    let mut after: Vec<&str> = roles.iter().map(Role::as_str).collect();
+    after.sort_unstable();
+    let diff_id = audit::record_diff(
+        &state.db.pool,
+        "user.roles",
+        actor,
+        Some(id),
+        &serde_json::json!({ "roles": before }),
+        &serde_json::json!({ "roles": after }),
+    )
+    .await;
+    info!(target: "audit", action = "user.roles", actor = %actor, resource = %id, roles = %after.join(","), approved_by = approved_by.unwrap_or("-"), diff_id = diff_id.as_deref().unwrap_or("-"), "Admin updated user roles");
+    Json(serde_json::json!({ "user_id": id, "roles": after })).into_response()
 }
 
 // ---------------------------------------------------------------------------
@@ -880,16 +2102,18 @@ async fn system_status(State(state): State<Arc<AppState>>) -> impl IntoResponse
     };
 
     // Per-container VRAM (best-effort)
-    let vram_map = state.docker.per_container_vram().await;
+    let devices = common::container_devices(&state.db.pool).await;
+    let vram_map = state.docker.per_container_vram(&devices).await;
 
     // Container health — list managed containers and check their state
     let containers = match state.docker.list_managed_containers().await {
-        Ok(containers) => common::extract_container_statuses(containers, &vram_map),
+        Ok(containers) => common::extract_container_statuses(containers, &vram_map, &state.warmups),
         Err(_) => vec![],
     };
 
     let queues = state.scheduler.get_queue_stats().await;
     let gates = state.scheduler.gate().status().await;
+    let category_gates = state.scheduler.gate().category_status().await;
 
     // GPU detection
     let gpu = state.docker.detect_gpu().await;
@@ -916,13 +2140,181 @@ async fn system_status(State(state): State<Arc<AppState>>) -> impl IntoResponse
         "containers": containers,
         "queues": queues,
         "gates": gates,
+        "category_gates": category_gates,
+        "arch": state.docker.arch,
         "gpu": gpu,
+        "gpu_probes": state.docker.gpu_probe_results().await,
         "gpu_memory": gpu_memory,
         "available_backends": available_backends,
+        "drain": state.scheduler.drain().await,
+        "model_eviction": state.storage.eviction_status(
+            &state.db,
+            state.scheduler.settings().await.model_evict_unused_days,
+        )
+        .await,
+        "watchdog": state.watchdog.status(&state.config),
+        "tasks": state.tasks.status(),
+    }))
+    .into_response()
+}
+
+/// POST /api/admin/system/gpu-probe — Re-run the GPU passthrough probes.
+async fn run_gpu_probe(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+) -> impl IntoResponse {
+    let probes = state.docker.probe_gpus().await;
+    info!(target: "audit", action = "system.gpu_probe", actor = %session.user_id, failed = probes.iter().filter(|p| !p.ok).count(), "Admin ran GPU probes");
+    Json(serde_json::json!({ "gpu_probes": probes }))
+}
+
+/// `Retry-After` sent to clients refused during a drain, unless the drain
+/// request sets one.
+const DEFAULT_DRAIN_RETRY_AFTER_SECS: u64 = 300;
+
+/// Body for `POST /api/admin/system/drain`.
+#[derive(Deserialize)]
+struct DrainRequest {
+    reason: Option<String>,
+    /// How long to wait for in-flight requests. Defaults to
+    /// `GRACEFUL_STOP_TIMEOUT_SECS`.
+    timeout_secs: Option<u64>,
+    /// Stop every loaded container once drained or timed out.
+    #[serde(default)]
+    stop_containers: bool,
+    retry_after_secs: Option<u64>,
+}
+
+/// POST /api/admin/system/drain — Stop accepting inference requests, wait for
+/// in-flight ones to finish, and optionally stop all containers.
+///
+/// Draining lasts until `POST /api/admin/system/resume` or a restart.
+async fn drain_system(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Json(req): Json<DrainRequest>,
+) -> impl IntoResponse {
+    let drain = state
+        .scheduler
+        .start_drain(SystemDrain {
+            started_at: Utc::now(),
+            started_by: session.user_id.clone(),
+            reason: req.reason.filter(|r| !r.trim().is_empty()),
+            retry_after_secs: req
+                .retry_after_secs
+                .unwrap_or(DEFAULT_DRAIN_RETRY_AFTER_SECS),
+        })
+        .await;
+    info!(target: "audit", action = "system.drain", actor = %session.user_id, reason = ?drain.reason, stop_containers = req.stop_containers, "Admin started system drain");
+
+    let timeout = Duration::from_secs(
+        req.timeout_secs
+            .unwrap_or(state.config.graceful_stop_timeout_secs),
+    );
+    let (in_flight, queued) = common::wait_for_idle(&state, timeout).await;
+    if in_flight > 0 || queued > 0 {
+        warn!(
+            in_flight,
+            queued, "System drain timed out with requests outstanding"
+        );
+    }
+
+    let mut stopped = Vec::new();
+    let mut stop_errors = Vec::new();
+    if req.stop_containers {
+        let loaded: Vec<(String, String)> =
+            match sqlx::query_as("SELECT id, backend_type FROM models WHERE loaded = 1")
+                .fetch_all(&state.db.pool)
+                .await
+            {
+                Ok(rows) => rows,
+                Err(e) => return error::internal_error("drain_system", e),
+            };
+        for (model_id, backend_type) in loaded {
+            // Already waited above, so don't wait again per model
+            match common::stop_container_core(
+                &state,
+                &model_id,
+                &backend_type,
+                StopMode::Graceful,
+                Duration::ZERO,
+            )
+            .await
+            {
+                Ok(outcome) => {
+                    info!(target: "audit", action = "container.stop", actor = %session.user_id, resource = %model_id, backend = %backend_type, mode = ?outcome.mode, abandoned_in_flight = outcome.abandoned_in_flight, "Container stopped for system drain");
+                    stopped.push(model_id);
+                }
+                Err(e) => {
+                    error!(model = %model_id, backend = %backend_type, error = %e, "Failed to stop container during drain");
+                    stop_errors.push(serde_json::json!({
+                        "model_id": model_id,
+                        "error": e.to_string(),
+                    }));
+                }
+            }
+        }
+    }
+
+    Json(serde_json::json!({
+        "draining": true,
+        "drain": drain,
+        "drained": in_flight == 0 && queued == 0,
+        "in_flight": in_flight,
+        "queued": queued,
+        "stopped": stopped,
+        "stop_errors": stop_errors,
+    }))
+    .into_response()
+}
+
+/// POST /api/admin/system/resume — End a system drain.
+async fn resume_system(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+) -> impl IntoResponse {
+    let ended = state.scheduler.end_drain().await;
+    if let Some(drain) = &ended {
+        let drained_secs = (Utc::now() - drain.started_at).num_seconds();
+        info!(target: "audit", action = "system.resume", actor = %session.user_id, drained_secs, "Admin ended system drain");
+    }
+    Json(serde_json::json!({
+        "draining": false,
+        "was_draining": ended.is_some(),
+    }))
+}
+
+/// GET /api/admin/system/connections — Upstream connection pool reuse per backend.
+async fn connection_stats(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "pool": {
+            "max_idle_per_host": state.config.backend_pool_max_idle,
+            "idle_timeout_secs": state.config.backend_pool_idle_timeout_secs,
+            "connect_timeout_secs": state.config.backend_connect_timeout_secs,
+        },
+        "backends": state.backends.stats().await,
     }))
     .into_response()
 }
 
+/// GET /api/admin/ui/manifest — Startup hashes of UI assets plus any on-disk changes since.
+async fn ui_manifest(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let changes = state.ui_integrity.changes();
+    Json(serde_json::json!({
+        "manifest": state.ui_integrity.manifest(),
+        "tampered": !changes.is_empty(),
+        "changes": changes,
+    }))
+    .into_response()
+}
+
+/// GET /api/admin/system/config — Settings the server started with, where
+/// each came from (environment, config profile, secret manager or default),
+/// secrets redacted.
+async fn effective_config(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(serde_json::json!(state.effective_config)).into_response()
+}
+
 // ---------------------------------------------------------------------------
 // Container Management (preserved from original)
 // ---------------------------------------------------------------------------
@@ -934,12 +2326,18 @@ async fn list_containers(State(state): State<Arc<AppState>>) -> impl IntoRespons
             let data: Vec<serde_json::Value> = containers
                 .into_iter()
                 .map(|c| {
+                    let limits = c
+                        .labels
+                        .as_ref()
+                        .map(ResourceLimits::from_labels)
+                        .unwrap_or_default();
                     serde_json::json!({
                         "id": c.id,
                         "names": c.names,
                         "state": c.state.map(|s| format!("{:?}", s).to_lowercase()),
                         "status": c.status,
                         "labels": c.labels,
+                        "limits": limits,
                     })
                 })
                 .collect();
@@ -955,7 +2353,14 @@ struct StartContainerRequest {
     backend_type: Option<String>,
     gpu_type: Option<String>,
     gpu_layers: Option<u32>,
+    context_size: Option<u32>,
     parallel: Option<u32>,
+    /// Pin the container to one GPU.
+    device_index: Option<u32>,
+    /// Pin the container to several GPUs.
+    device_list: Option<Vec<u32>>,
+    #[serde(flatten)]
+    limits: ResourceLimits,
 }
 
 /// POST /api/admin/containers/start — Start a backend container for a model.
@@ -964,17 +2369,37 @@ async fn start_container(
     Extension(session): Extension<SessionAuth>,
     Json(req): Json<StartContainerRequest>,
 ) -> impl IntoResponse {
+    let devices = match common::device_selection(req.device_index, req.device_list) {
+        Ok(devices) => devices,
+        Err(msg) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": msg })),
+            )
+                .into_response()
+        }
+    };
+    if let Err(msg) = req.limits.validate() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": msg })),
+        )
+            .into_response();
+    }
     let params = common::StartContainerParams {
         model_id: req.model_id,
         backend_type: req.backend_type,
         gpu_type: req.gpu_type,
         gpu_layers: req.gpu_layers,
+        context_size: req.context_size,
         parallel: req.parallel,
+        devices,
+        limits: req.limits,
     };
 
     match common::start_container_core(&state, &params).await {
         Ok((container_name, url)) => {
-            info!(target: "audit", action = "container.start", actor = %session.user_id, resource = %params.model_id, container = %container_name, "Admin started container");
+            info!(target: "audit", action = "container.start", actor = %session.user_id, resource = %params.model_id, container = %container_name, devices = ?params.devices, limits = ?params.limits, "Admin started container");
             Json(serde_json::json!({
                 "container": container_name,
                 "url": url,
@@ -1067,16 +2492,30 @@ async fn estimate_vram(
         parallel,
     });
 
-    let overhead_mb: u64 = 200; // CUDA context + misc overhead
+    let overhead_mb = RUNTIME_OVERHEAD_MB;
     let total_mb = model_weights_mb + kv_cache_mb + overhead_mb;
 
-    // Get current GPU memory — sum across all GPUs
+    // Current GPU memory, per device and summed
     let all_gpus = crate::docker::DockerManager::gpu_all_info().await;
     let gpu_total_mb: u64 = all_gpus.iter().map(|g| g.total_mb).sum();
     let gpu_used_mb: u64 = all_gpus.iter().map(|g| g.used_mb).sum();
     let gpu_free_mb: u64 = all_gpus.iter().map(|g| g.free_mb).sum();
 
-    let fits = gpu_total_mb > 0 && total_mb <= gpu_free_mb;
+    let placement = recommend_placement(total_mb, &all_gpus);
+    let fits = placement != Placement::DoesNotFit;
+    let devices: Vec<serde_json::Value> = all_gpus
+        .iter()
+        .map(|g| {
+            serde_json::json!({
+                "device_index": g.device_index,
+                "gpu_type": g.gpu_type,
+                "total_mb": g.total_mb,
+                "used_mb": g.used_mb,
+                "free_mb": g.free_mb,
+                "fits": total_mb <= g.free_mb,
+            })
+        })
+        .collect();
 
     Json(serde_json::json!({
         "model_weights_mb": model_weights_mb,
@@ -1087,33 +2526,116 @@ async fn estimate_vram(
         "gpu_used_mb": gpu_used_mb,
         "gpu_free_mb": gpu_free_mb,
         "fits": fits,
+        "devices": devices,
+        "placement": placement,
     }))
     .into_response()
 }
 
-#[derive(Debug, Deserialize)]
-struct StopContainerRequest {
-    model_id: String,
+/// Where a model of a given estimated size should run.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+enum Placement {
+    /// Fits on one GPU; the one with the most free memory is chosen.
+    SingleGpu { device_index: u32 },
+    /// Too big for any one GPU, but fits split across these (largest free
+    /// memory first). Each extra GPU needs its own runtime overhead.
+    TensorSplit { device_indices: Vec<u32> },
+    /// Doesn't fit even across every GPU, or no GPU was detected.
+    DoesNotFit,
+}
+
+/// Recommend a placement for a model needing `total_mb` (weights, KV cache
+/// and one runtime overhead) given each GPU's free memory.
+fn recommend_placement(total_mb: u64, gpus: &[crate::docker::GpuStats]) -> Placement {
+    let mut by_free: Vec<&crate::docker::GpuStats> = gpus.iter().collect();
+    by_free.sort_by(|a, b| {
+        b.free_mb
+            .cmp(&a.free_mb)
+            .then(a.device_index.cmp(&b.device_index))
+    });
+
+    match by_free.first() {
+        None => return Placement::DoesNotFit,
+        Some(best) if total_mb <= best.free_mb => {
+            return Placement::SingleGpu {
+                device_index: best.device_index,
+            }
+        }
+        Some(_) => {}
+    }
+
+    let mut free_mb = 0;
+    for (n, gpu) in by_free.iter().enumerate() {
+        free_mb += gpu.free_mb;
+        let needed = total_mb + RUNTIME_OVERHEAD_MB * n as u64;
+        if n > 0 && needed <= free_mb {
+            return Placement::TensorSplit {
+                device_indices: by_free[..=n].iter().map(|g| g.device_index).collect(),
+            };
+        }
+    }
+    Placement::DoesNotFit
 }
 
 /// POST /api/admin/containers/stop — Stop a backend container.
+///
+/// Graceful by default: drains in-flight requests (up to `timeout_secs`)
+/// before stopping. `force: true` stops immediately.
 async fn stop_container(
     State(state): State<Arc<AppState>>,
     Extension(session): Extension<SessionAuth>,
-    Json(req): Json<StopContainerRequest>,
+    Query(query): Query<common::DryRunQuery>,
+    Json(req): Json<common::StopContainerRequest>,
 ) -> impl IntoResponse {
     let backend_type = common::lookup_backend_type(&state.db.pool, &req.model_id).await;
+    let mode = req.mode();
 
-    info!(model = %req.model_id, backend = %backend_type, "Stopping container");
-    match state
-        .docker
-        .stop_backend(&req.model_id, &backend_type)
-        .await
+    if query.dry_run {
+        let loaded: Option<bool> =
+            match sqlx::query_scalar("SELECT loaded FROM models WHERE id = ?")
+                .bind(&req.model_id)
+                .fetch_optional(&state.db.pool)
+                .await
+            {
+                Ok(row) => row,
+                Err(e) => return error::internal_error("stop_container:dry_run", e),
+            };
+        let in_flight = common::gate_in_flight(&state, &req.model_id).await;
+        return Json(serde_json::json!({
+            "dry_run": true,
+            "model_id": req.model_id,
+            "container": common::serving_container_name(&state.db.pool, &req.model_id, &backend_type).await,
+            "loaded": loaded.unwrap_or(false),
+            "mode": mode,
+            "in_flight": in_flight,
+            "drain_timeout_secs": match mode {
+                StopMode::Graceful => Some(req.drain_timeout(&state.config).as_secs()),
+                StopMode::Force => None,
+            },
+        }))
+        .into_response();
+    }
+
+    info!(model = %req.model_id, backend = %backend_type, mode = ?mode, "Stopping container");
+    match common::stop_container_core(
+        &state,
+        &req.model_id,
+        &backend_type,
+        mode,
+        req.drain_timeout(&state.config),
+    )
+    .await
     {
-        Ok(_) => {
-            info!(target: "audit", action = "container.stop", actor = %session.user_id, resource = %req.model_id, backend = %backend_type, "Admin stopped container");
-            common::post_stop_cleanup(&state, &req.model_id).await;
-            Json(serde_json::json!({ "status": "stopped" })).into_response()
+        Ok(outcome) => {
+            info!(target: "audit", action = "container.stop", actor = %session.user_id, resource = %req.model_id, backend = %backend_type, mode = ?outcome.mode, abandoned_in_flight = outcome.abandoned_in_flight, "Admin stopped container");
+            Json(serde_json::json!({
+                "status": "stopped",
+                "mode": outcome.mode,
+                "drained": outcome.drained,
+                "abandoned_in_flight": outcome.abandoned_in_flight,
+            }))
+            .into_response()
         }
         Err(e) => {
             error!(model = %req.model_id, backend = %backend_type, error = %e, "Failed to stop container");
@@ -1126,35 +2648,74 @@ async fn stop_container(
 // Settings Management
 // ---------------------------------------------------------------------------
 
-/// GET /api/admin/settings — Return current fairness/queue settings.
-async fn get_settings(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let settings = state.scheduler.settings().await;
-    Json(serde_json::json!({
+fn settings_json(settings: &FairnessSettings) -> serde_json::Value {
+    serde_json::json!({
+        "fairness_policy": settings.policy.as_str(),
         "fairness_base_priority": settings.base_priority,
         "fairness_wait_weight": settings.wait_weight,
         "fairness_usage_weight": settings.usage_weight,
         "fairness_usage_scale": settings.usage_scale,
         "fairness_window_minutes": settings.window_minutes,
+        "fairness_bucket_capacity": settings.bucket_capacity,
+        "fairness_bucket_refill_per_sec": settings.bucket_refill_per_sec,
+        "fairness_tier_weight": settings.tier_weight,
         "queue_timeout_secs": settings.queue_timeout_secs,
-    }))
-    .into_response()
+        "idle_unload_minutes": settings.idle_unload_minutes,
+        "reservation_slot_minutes": settings.reservation_slot_minutes,
+        "reservation_min_minutes": settings.reservation_min_minutes,
+        "reservation_max_minutes": settings.reservation_max_minutes,
+        "reservation_max_advance_days": settings.reservation_max_advance_days,
+        "reservation_max_pending_per_user": settings.reservation_max_pending_per_user,
+        "reservation_auto_approve": settings.reservation_auto_approve,
+        "analytics_privacy": settings.analytics_privacy.as_str(),
+        "download_max_mb_per_sec": settings.download_max_mb_per_sec,
+        "download_full_speed_window": settings
+            .download_full_speed_window
+            .map(|w| w.to_string()),
+        "model_evict_unused_days": settings.model_evict_unused_days,
+    })
+}
+
+/// GET /api/admin/settings — Return current fairness/queue/booking settings.
+async fn get_settings(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let settings = state.scheduler.settings().await;
+    Json(settings_json(&settings)).into_response()
 }
 
-/// PUT /api/admin/settings — Partial update of fairness/queue settings.
+/// PUT /api/admin/settings — Partial update of fairness/queue/booking settings.
 async fn update_settings(
     State(state): State<Arc<AppState>>,
     Extension(session): Extension<SessionAuth>,
     Json(req): Json<HashMap<String, serde_json::Value>>,
 ) -> impl IntoResponse {
-    use crate::scheduler::settings::save_setting;
+    use crate::scheduler::settings::{
+        is_valid_slot_minutes, save_setting, AnalyticsPrivacy, DownloadWindow, FairnessPolicyKind,
+    };
+
+    let before = settings_json(&state.scheduler.settings().await);
 
     let valid_keys = [
+        "fairness_policy",
         "fairness_base_priority",
         "fairness_wait_weight",
         "fairness_usage_weight",
         "fairness_usage_scale",
         "fairness_window_minutes",
+        "fairness_bucket_capacity",
+        "fairness_bucket_refill_per_sec",
+        "fairness_tier_weight",
         "queue_timeout_secs",
+        "idle_unload_minutes",
+        "reservation_slot_minutes",
+        "reservation_min_minutes",
+        "reservation_max_minutes",
+        "reservation_max_advance_days",
+        "reservation_max_pending_per_user",
+        "reservation_auto_approve",
+        "analytics_privacy",
+        "download_max_mb_per_sec",
+        "download_full_speed_window",
+        "model_evict_unused_days",
     ];
 
     for (key, value) in &req {
@@ -1169,15 +2730,97 @@ async fn update_settings(
         let value_str = match value {
             serde_json::Value::Number(n) => n.to_string(),
             serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Bool(b) => b.to_string(),
+            // Clears the download window
+            serde_json::Value::Null if key == "download_full_speed_window" => String::new(),
             _ => {
                 return (
                     StatusCode::BAD_REQUEST,
-                    Json(serde_json::json!({ "error": format!("Invalid value for {key}: expected number or string") })),
+                    Json(serde_json::json!({ "error": format!("Invalid value for {key}: expected number, string or boolean") })),
                 )
                     .into_response();
             }
         };
 
+        if key == "idle_unload_minutes" && value_str.parse::<u64>().is_err() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "idle_unload_minutes must be a non-negative integer" })),
+            )
+                .into_response();
+        }
+
+        if key.starts_with("reservation_") {
+            let invalid = match key.as_str() {
+                "reservation_auto_approve" => value_str.parse::<bool>().err().map(|_| {
+                    "reservation_auto_approve must be true or false".to_string()
+                }),
+                "reservation_slot_minutes" => match value_str.parse::<u32>() {
+                    Ok(m) if is_valid_slot_minutes(m) => None,
+                    _ => Some(
+                        "reservation_slot_minutes must be a positive number of minutes that divides a day (e.g. 15, 30, 60)".to_string(),
+                    ),
+                },
+                _ => value_str
+                    .parse::<u32>()
+                    .err()
+                    .map(|_| format!("{key} must be a non-negative integer")),
+            };
+            if let Some(message) = invalid {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({ "error": message })),
+                )
+                    .into_response();
+            }
+        }
+
+        if key == "model_evict_unused_days" && value_str.parse::<u32>().is_err() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "model_evict_unused_days must be a non-negative integer" })),
+            )
+                .into_response();
+        }
+
+        if key == "download_max_mb_per_sec" && value_str.parse::<u64>().is_err() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "download_max_mb_per_sec must be a non-negative integer" })),
+            )
+                .into_response();
+        }
+
+        if key == "download_full_speed_window" && !value_str.is_empty() {
+            if let Err(e) = value_str.parse::<DownloadWindow>() {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({ "error": e })),
+                )
+                    .into_response();
+            }
+        }
+
+        if key == "analytics_privacy" {
+            if let Err(e) = value_str.parse::<AnalyticsPrivacy>() {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({ "error": e })),
+                )
+                    .into_response();
+            }
+        }
+
+        if key == "fairness_policy" {
+            if let Err(e) = value_str.parse::<FairnessPolicyKind>() {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({ "error": e })),
+                )
+                    .into_response();
+            }
+        }
+
         if let Err(e) = save_setting(&state.db, key, &value_str).await {
             return error::internal_error("update_settings:save", e);
         }
@@ -1188,19 +2831,21 @@ async fn update_settings(
         error!(error = %e, "Failed to reload settings after update");
     }
 
-    info!(target: "audit", action = "settings.update", actor = %session.user_id, keys = ?req.keys().collect::<Vec<_>>(), "Admin updated settings");
-
     // Return the updated settings
-    let settings = state.scheduler.settings().await;
-    Json(serde_json::json!({
-        "fairness_base_priority": settings.base_priority,
-        "fairness_wait_weight": settings.wait_weight,
-        "fairness_usage_weight": settings.usage_weight,
-        "fairness_usage_scale": settings.usage_scale,
-        "fairness_window_minutes": settings.window_minutes,
-        "queue_timeout_secs": settings.queue_timeout_secs,
-    }))
-    .into_response()
+    let after = settings_json(&state.scheduler.settings().await);
+    let diff_id = audit::record_diff(
+        &state.db.pool,
+        "settings.update",
+        &session.user_id,
+        None,
+        &before,
+        &after,
+    )
+    .await;
+
+    info!(target: "audit", action = "settings.update", actor = %session.user_id, keys = ?req.keys().collect::<Vec<_>>(), diff_id = diff_id.as_deref().unwrap_or("-"), "Admin updated settings");
+
+    Json(after).into_response()
 }
 
 // ---------------------------------------------------------------------------
@@ -1212,17 +2857,11 @@ struct AdminUsageQuery {
     period: Option<String>,
 }
 
-#[derive(Debug, Serialize, sqlx::FromRow)]
-struct AdminUsageByUser {
-    user_label: String,
-    requests: i64,
-    input_tokens: i64,
-    output_tokens: i64,
-}
-
 /// GET /api/admin/usage — Global usage statistics with per-user breakdown.
+/// Users are labelled per the `analytics_privacy` setting.
 async fn admin_usage(
     State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
     Query(params): Query<AdminUsageQuery>,
 ) -> impl IntoResponse {
     let period = params.period.unwrap_or_else(|| "day".to_string());
@@ -1238,9 +2877,10 @@ async fn admin_usage(
     .unwrap_or((0, 0, 0));
 
     // Per-user breakdown
-    let by_user = sqlx::query_as::<_, AdminUsageByUser>(
+    let by_user = sqlx::query_as::<_, UsageRow>(
         r#"
-        SELECT COALESCE(u.display_name, u.email, ul.user_id) as user_label,
+        SELECT ul.user_id,
+               COALESCE(u.display_name, u.email, ul.user_id) as user_label,
                COUNT(*) as requests,
                COALESCE(SUM(ul.input_tokens), 0) as input_tokens,
                COALESCE(SUM(ul.output_tokens), 0) as output_tokens
@@ -1255,6 +2895,12 @@ async fn admin_usage(
     .fetch_all(&state.db.pool)
     .await
     .unwrap_or_default();
+    let privacy =
+        usage_privacy::mode_for(&session, state.scheduler.settings().await.analytics_privacy);
+    let by_user = match usage_privacy::apply(&state.db.pool, privacy, by_user).await {
+        Ok(rows) => rows,
+        Err(e) => return error::internal_error("admin_usage:privacy", e),
+    };
 
     Json(serde_json::json!({
         "summary": {
@@ -1263,22 +2909,26 @@ async fn admin_usage(
             "total_output_tokens": summary.2,
             "period": period,
         },
+        "privacy": privacy.as_str(),
         "by_user": by_user,
     }))
     .into_response()
 }
 
-/// GET /api/admin/usage/timeline — Time-series usage grouped by user.
+/// GET /api/admin/usage/timeline — Time-series usage grouped by user,
+/// labelled like `/api/admin/usage`.
 async fn admin_usage_timeline(
     State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
     Query(params): Query<AdminUsageQuery>,
 ) -> impl IntoResponse {
     let period = params.period.unwrap_or_else(|| "day".to_string());
     let (interval, time_bucket) = common::period_to_interval_and_bucket(&period);
 
-    let timeline = sqlx::query_as::<_, (String, String, i64, i64, i64)>(&format!(
+    let timeline = sqlx::query_as::<_, UsageRow>(&format!(
         r#"
-            SELECT strftime('{}', ul.created_at) as ts,
+            SELECT strftime('{}', ul.created_at) as timestamp,
+                   ul.user_id,
                    COALESCE(u.display_name, u.email, ul.user_id) as user_label,
                    COUNT(*) as requests,
                    COALESCE(SUM(ul.input_tokens), 0) as input_tokens,
@@ -1286,8 +2936,8 @@ async fn admin_usage_timeline(
             FROM usage_log ul
             LEFT JOIN users u ON u.id = ul.user_id
             WHERE ul.created_at >= datetime('now', ?)
-            GROUP BY ts, ul.user_id
-            ORDER BY ts
+            GROUP BY timestamp, ul.user_id
+            ORDER BY timestamp
             "#,
         time_bucket
     ))
@@ -1295,28 +2945,39 @@ async fn admin_usage_timeline(
     .fetch_all(&state.db.pool)
     .await
     .unwrap_or_default();
-
-    let timeline_json: Vec<serde_json::Value> = timeline
-        .into_iter()
-        .map(
-            |(timestamp, user_label, requests, input_tokens, output_tokens)| {
-                serde_json::json!({
-                    "timestamp": timestamp,
-                    "user_label": user_label,
-                    "requests": requests,
-                    "input_tokens": input_tokens,
-                    "output_tokens": output_tokens,
-                })
-            },
-        )
-        .collect();
+    let privacy =
+        usage_privacy::mode_for(&session, state.scheduler.settings().await.analytics_privacy);
+    let timeline = match usage_privacy::apply(&state.db.pool, privacy, timeline).await {
+        Ok(rows) => rows,
+        Err(e) => return error::internal_error("admin_usage_timeline:privacy", e),
+    };
 
     Json(serde_json::json!({
-        "timeline": timeline_json,
+        "privacy": privacy.as_str(),
+        "timeline": timeline,
     }))
     .into_response()
 }
 
+/// GET /api/admin/queue/history — Persisted per-model queue wait statistics
+/// (survives restarts, unlike the live queue stats in the metrics stream).
+async fn queue_history(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AdminUsageQuery>,
+) -> impl IntoResponse {
+    let period = params.period.unwrap_or_else(|| "day".to_string());
+    let interval = common::period_to_interval(&period);
+
+    match crate::scheduler::queue_log::history_stats(&state.db, interval).await {
+        Ok(models) => Json(serde_json::json!({
+            "period": period,
+            "models": models,
+        }))
+        .into_response(),
+        Err(e) => error::internal_error("queue_history", e),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // KV cache estimation (extracted for testability)
 // ---------------------------------------------------------------------------
@@ -1346,6 +3007,34 @@ struct KvCacheParams {
     parallel: u64,
 }
 
+/// GPU memory a backend needs beyond weights and KV cache (compute context,
+/// scratch buffers).
+pub(crate) const RUNTIME_OVERHEAD_MB: u64 = 200;
+
+/// [`estimate_kv_cache_mb`] from GGUF header metadata, for models that
+/// aren't registered yet.
+pub(crate) fn estimate_kv_cache_mb_from_gguf(
+    meta: &super::hf::GgufMetadata,
+    context_size: u64,
+    parallel: u64,
+) -> u64 {
+    let (kv_bytes_per_token_global, kv_bytes_per_token_swa) =
+        super::hf::compute_kv_aggregates(meta);
+    estimate_kv_cache_mb(&KvCacheParams {
+        n_layers: meta.block_count.map(i64::from),
+        n_heads: meta.head_count.map(i64::from),
+        n_kv_heads: meta.head_count_kv.map(i64::from),
+        embedding_length: meta.embedding_length.map(i64::from),
+        key_length: meta.key_length.map(i64::from),
+        value_length: meta.value_length.map(i64::from),
+        kv_bytes_per_token_global,
+        kv_bytes_per_token_swa,
+        sliding_window: meta.sliding_window.map(i64::from),
+        context_size,
+        parallel,
+    })
+}
+
 /// Estimate KV cache size in MB.
 ///
 /// Two paths:
@@ -1407,6 +3096,53 @@ fn estimate_kv_cache_mb(p: &KvCacheParams) -> u64 {
 mod tests {
     use super::*;
 
+    // -- recommend_placement -------------------------------------------------
+
+    fn gpu(device_index: u32, free_mb: u64) -> crate::docker::GpuStats {
+        crate::docker::GpuStats {
+            gpu_type: "nvidia".to_string(),
+            device_index,
+            total_mb: 24_000,
+            used_mb: 24_000 - free_mb,
+            free_mb,
+            utilization_percent: None,
+        }
+    }
+
+    #[test]
+    fn placement_prefers_single_gpu_with_most_free_memory() {
+        let gpus = [gpu(0, 10_000), gpu(1, 20_000), gpu(2, 16_000)];
+        assert_eq!(
+            recommend_placement(15_000, &gpus),
+            Placement::SingleGpu { device_index: 1 }
+        );
+    }
+
+    #[test]
+    fn placement_splits_when_no_single_gpu_fits() {
+        let gpus = [gpu(0, 10_000), gpu(1, 20_000), gpu(2, 16_000)];
+        // 30 000 + 200 overhead for the second GPU fits in 20 000 + 16 000
+        assert_eq!(
+            recommend_placement(30_000, &gpus),
+            Placement::TensorSplit {
+                device_indices: vec![1, 2]
+            }
+        );
+        assert_eq!(
+            recommend_placement(45_500, &gpus),
+            Placement::TensorSplit {
+                device_indices: vec![1, 2, 0]
+            }
+        );
+    }
+
+    #[test]
+    fn placement_reports_when_nothing_fits() {
+        let gpus = [gpu(0, 10_000), gpu(1, 20_000)];
+        assert_eq!(recommend_placement(29_900, &gpus), Placement::DoesNotFit);
+        assert_eq!(recommend_placement(1, &[]), Placement::DoesNotFit);
+    }
+
     // -- estimate_kv_cache_mb -------------------------------------------------
 
     /// Helper to build KvCacheParams with common defaults (legacy path:
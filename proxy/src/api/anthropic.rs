@@ -9,14 +9,20 @@ use axum::routing::post;
 use axum::{Extension, Json, Router};
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
 use tokio::time::Instant;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use super::common;
+use super::prompt_dedup;
+use super::system_prompts;
 use crate::auth::tokens;
 use crate::auth::AuthUser;
+use crate::docker::vllm;
 use crate::proxy::streaming::proxy_to_backend;
-use crate::scheduler::usage;
+use crate::scheduler::gate::{GateError, QueueTicket};
+use crate::scheduler::{resolver, usage};
 use crate::AppState;
 
 // ---------------------------------------------------------------------------
@@ -650,6 +656,7 @@ fn transform_stream(
     openai_stream: impl futures::Stream<Item = Result<Bytes, reqwest::Error>> + Send + 'static,
     model: String,
     msg_id: String,
+    keepalive: Option<Duration>,
 ) -> (Body, Arc<tokio::sync::Mutex<(i64, i64)>>) {
     let usage_accumulator: Arc<tokio::sync::Mutex<(i64, i64)>> =
         Arc::new(tokio::sync::Mutex::new((0, 0)));
@@ -710,7 +717,23 @@ fn transform_stream(
 
         let mut pinned_stream = std::pin::pin!(openai_stream);
 
-        while let Some(chunk_result) = pinned_stream.next().await {
+        // Keep idle connections alive with pings until the backend's first chunk
+        let mut first_chunk = None;
+        if let Some(period) = keepalive {
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            first_chunk = loop {
+                tokio::select! {
+                    chunk = pinned_stream.next() => break Some(chunk),
+                    _ = ticker.tick() => send_event!("ping", &ping),
+                }
+            };
+        }
+
+        while let Some(chunk_result) = match first_chunk.take() {
+            Some(chunk) => chunk,
+            None => pinned_stream.next().await,
+        } {
             let chunk_bytes = match chunk_result {
                 Ok(b) => b,
                 Err(e) => {
@@ -963,7 +986,9 @@ fn transform_stream(
 
 /// POST /v1/messages -- Anthropic Messages API compatible endpoint.
 /// Translates to OpenAI format, proxies to the llama.cpp backend, and
-/// translates the response back to Anthropic format.
+/// translates the response back to Anthropic format. A streaming request
+/// that has to queue hears about its place while it waits (see
+/// [`common::with_queue_preamble`]).
 async fn messages(
     State(state): State<Arc<AppState>>,
     Extension(auth_user): Extension<AuthUser>,
@@ -981,6 +1006,23 @@ async fn messages(
         }
     };
 
+    if !parsed.stream {
+        return serve_messages(state, auth_user, parsed, None).await;
+    }
+    let (queued_tx, queued_rx) = oneshot::channel();
+    let scheduler = state.scheduler.clone();
+    let serve = serve_messages(state, auth_user, parsed, Some(queued_tx));
+    common::with_queue_preamble(&scheduler, queued_rx, serve, Some("error")).await
+}
+
+/// [`messages`] once the body is parsed, reporting on `queued` if the
+/// request has to wait for a slot.
+async fn serve_messages(
+    state: Arc<AppState>,
+    auth_user: AuthUser,
+    parsed: AnthropicRequest,
+    queued: Option<oneshot::Sender<QueueTicket>>,
+) -> Response<Body> {
     info!(
         model = %parsed.model,
         stream = parsed.stream,
@@ -1007,13 +1049,13 @@ async fn messages(
             return error_response(
                 StatusCode::NOT_FOUND,
                 "not_found_error",
-                format!("Model not found: {}", parsed.model),
+                resolver::not_found_message(&e, &parsed.model),
             );
         }
     };
 
-    // 3. Check model loaded status
-    if !model.loaded {
+    // 3. Check model loaded status (autoload models are started after step 4)
+    if !model.loaded && !common::autoload_enabled(&state.db.pool, &model.id).await {
         let msg = if model.hf_repo == parsed.model {
             format!("Model '{}' is not currently loaded", parsed.model)
         } else {
@@ -1031,6 +1073,54 @@ async fn messages(
         return error_response(StatusCode::SERVICE_UNAVAILABLE, "overloaded_error", msg);
     }
 
+    // `overloaded_error` alone doesn't say why; the header carries the
+    // `/v1` error code
+    if let Some(drain) = state.scheduler.drain().await {
+        let mut response = error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "overloaded_error",
+            "Server is draining for maintenance and not accepting new requests".to_string(),
+        );
+        response.headers_mut().insert(
+            axum::http::header::RETRY_AFTER,
+            axum::http::HeaderValue::from(drain.retry_after_secs),
+        );
+        response.headers_mut().insert(
+            "x-error-code",
+            axum::http::HeaderValue::from_static("server_draining"),
+        );
+        return response;
+    }
+
+    if model.draining {
+        let mut response = error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "overloaded_error",
+            format!(
+                "Model '{}' is draining for maintenance and not accepting new requests",
+                model.hf_repo
+            ),
+        );
+        response.headers_mut().insert(
+            axum::http::header::RETRY_AFTER,
+            axum::http::HeaderValue::from_static("60"),
+        );
+        response.headers_mut().insert(
+            "x-error-code",
+            axum::http::HeaderValue::from_static("model_draining"),
+        );
+        return response;
+    }
+
+    if common::admission_paused_for_memory(&state.config) {
+        warn!(model = %model.id, user = %auth_user.user_id, "Anthropic: request refused under host memory pressure");
+        return error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "overloaded_error",
+            "Server is under memory pressure. Please retry later.".to_string(),
+        );
+    }
+
     // 4. Check reservation
     if !auth_user.is_internal {
         if let Some(active) = state.scheduler.active_reservation().await {
@@ -1049,59 +1139,147 @@ async fn messages(
         }
     }
 
+    if !model.loaded {
+        info!(model_id = %model.id, user = %auth_user.user_id, "Anthropic: autoloading model");
+        match common::autoload_model(&state, &model.id).await {
+            Ok(()) => {}
+            Err(common::AutoloadError::Timeout) => {
+                return error_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "overloaded_error",
+                    format!(
+                        "Model '{}' is still loading. Please retry shortly.",
+                        model.hf_repo
+                    ),
+                );
+            }
+            Err(common::AutoloadError::Failed(reason)) => {
+                return error_response(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "api_error",
+                    format!("Model '{}' could not be loaded: {}", model.hf_repo, reason),
+                );
+            }
+        }
+    }
+
     // 5. Acquire concurrency gate slot
     let queue_start = Instant::now();
     let settings = state.scheduler.settings().await;
-    let timeout = Duration::from_secs(settings.queue_timeout_secs);
-    let _slot = match state
+    let policy = state.scheduler.policy().await;
+    let timeout = state
         .scheduler
         .gate()
-        .acquire_with_timeout(
+        .queue_timeout(
+            model.category_id.as_deref(),
+            Duration::from_secs(settings.queue_timeout_secs),
+        )
+        .await;
+    let estimated_wait_ms = state.scheduler.estimate_wait_ms(&model.id).await;
+    let slot = match state
+        .scheduler
+        .gate()
+        .acquire_reporting(
             &model.id,
+            model.category_id.as_deref(),
             &auth_user.user_id,
+            u32::try_from(parsed.max_tokens).ok(),
             &state.db,
             &settings,
+            policy.as_ref(),
             state.scheduler.queue(),
             timeout,
+            queued,
         )
         .await
     {
         Ok(slot) => slot,
-        Err(_) => {
+        Err(GateError::Timeout) => {
             warn!(
                 model = %model.id,
                 user = %auth_user.user_id,
                 "Request timed out in queue"
             );
-            return error_response(
+            let mut response = error_response(
                 StatusCode::TOO_MANY_REQUESTS,
                 "rate_limit_error",
                 "Server is busy. Please retry later.".to_string(),
             );
+            common::add_busy_headers(&mut response, &state.scheduler, &model.id).await;
+            return response;
+        }
+        Err(GateError::QueueFull) => {
+            warn!(
+                model = %model.id,
+                category = ?model.category_id,
+                user = %auth_user.user_id,
+                "Category queue full"
+            );
+            let mut response = error_response(
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate_limit_error",
+                "Too many requests are waiting for this model's category. Please retry later."
+                    .to_string(),
+            );
+            common::add_busy_headers(&mut response, &state.scheduler, &model.id).await;
+            return response;
         }
     };
     let queued_ms = queue_start.elapsed().as_millis() as i64;
 
-    // 6. Look up backend API key
-    let api_key: Option<String> =
-        sqlx::query_as::<_, (String,)>("SELECT api_key FROM container_secrets WHERE model_id = ?")
+    // 6. Look up the serving container and its API key. One row, so a
+    // blue/green replace can't pair the new container with the old key.
+    let secrets: Option<(String, Option<String>)> =
+        sqlx::query_as("SELECT api_key, container_name FROM container_secrets WHERE model_id = ?")
             .bind(&model.id)
             .fetch_optional(&state.db.pool)
             .await
             .ok()
-            .flatten()
-            .map(|(key,)| key);
+            .flatten();
+    let (api_key, container) = match secrets {
+        Some((key, name)) => (Some(key), name),
+        None => (None, None),
+    };
+    let container =
+        container.unwrap_or_else(|| crate::docker::container_name(&model.backend_type, &model.id));
 
     // 7. Translate request to OpenAI format
     let openai_body = translate_request(&parsed);
-    let openai_bytes = Bytes::from(serde_json::to_vec(&openai_body).unwrap());
+    let prompt_hashes = state
+        .config
+        .prompt_dedup_stats
+        .then(|| prompt_dedup::hash_request(&openai_body))
+        .flatten();
+    let mut openai_bytes = Bytes::from(serde_json::to_vec(&openai_body).unwrap());
+    openai_bytes = match system_prompts::apply(
+        &state.db.pool,
+        openai_bytes,
+        model.category_id.as_deref(),
+        &auth_user.user_id,
+        &auth_user.token_id,
+        &model.id,
+    )
+    .await
+    {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            error!(error = %e, model = %model.id, "Anthropic: failed to apply system prompt policies");
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "api_error",
+                "Internal server error".to_string(),
+            );
+        }
+    };
+    // vLLM only answers to the name it serves the model under
+    if model.backend_type == "vllm" {
+        openai_bytes = vllm::rewrite_model_field(openai_bytes, &model.id);
+    }
 
     // Backend URL
     let backend_url = format!(
         "{}/v1/chat/completions",
-        state
-            .docker
-            .backend_base_url(&model.id, &model.backend_type),
+        state.docker.backend_url(&container, &model.backend_type),
     );
 
     let requested_model = parsed.model.clone();
@@ -1135,13 +1313,15 @@ async fn messages(
 
     if !is_streaming {
         // 8. NON-STREAMING: proxy via proxy_to_backend, then transform response
-        let client = reqwest::Client::new();
         let result = proxy_to_backend(
-            &client,
+            &state.backends,
+            &model.id,
+            &model.backend_type,
             &backend_url,
             openai_bytes,
             false,
             api_key.as_deref(),
+            None,
         )
         .await;
 
@@ -1154,7 +1334,7 @@ async fn messages(
             .unwrap_or((0, 0));
 
         // Transform response body from OpenAI to Anthropic format
-        let response = if let Some(ref body_bytes) = result.body_bytes {
+        let mut response = if let Some(ref body_bytes) = result.body_bytes {
             match serde_json::from_slice::<OpenAIResponse>(body_bytes) {
                 Ok(openai_resp) => {
                     let anthropic_resp = translate_openai_response(&openai_resp, &requested_model);
@@ -1180,13 +1360,17 @@ async fn messages(
         };
 
         // 9. Log usage (fire and forget)
+        state
+            .scheduler
+            .record_usage(&auth_user.user_id, input_tokens + output_tokens)
+            .await;
         let db = state.db.clone();
         let model_id = model.id.clone();
         let category_id = model.category_id.clone();
 
         tokio::spawn(async move {
             let entry = usage::UsageEntry {
-                token_id: &log_token_id,
+                token_id: Some(&log_token_id),
                 user_id: &log_user_id,
                 model_id: &model_id,
                 category_id: category_id.as_deref(),
@@ -1194,25 +1378,36 @@ async fn messages(
                 output_tokens,
                 latency_ms,
                 queued_ms,
+                replay_of: None,
+                seed: None,
+                prompt_hash: prompt_hashes.as_ref().map(|h| h.exact.as_str()),
+                prompt_norm_hash: prompt_hashes.as_ref().map(|h| h.normalized.as_str()),
             };
             if let Err(e) = usage::log_usage(&db, &entry).await {
                 warn!(error = %e, "Failed to log usage");
             }
         });
 
+        common::add_queue_headers(&mut response, slot.queue_position(), estimated_wait_ms);
         response
     } else {
         // 10. STREAMING: make the reqwest call directly, transform SSE stream
-        let client = reqwest::Client::new();
-        let mut request = client
-            .post(&backend_url)
-            .header("content-type", "application/json");
+        let sent = state
+            .backends
+            .send(&model.id, &model.backend_type, |client| {
+                let mut request = client
+                    .post(&backend_url)
+                    .header("content-type", "application/json");
+
+                if let Some(ref key) = api_key {
+                    request = request.header("authorization", format!("Bearer {}", key));
+                }
 
-        if let Some(ref key) = api_key {
-            request = request.header("authorization", format!("Bearer {}", key));
-        }
+                request.body(openai_bytes.clone())
+            })
+            .await;
 
-        let backend_response = match request.body(openai_bytes).send().await {
+        let backend_response = match sent {
             Ok(resp) => resp,
             Err(e) => {
                 error!(error = %e, "Failed to connect to backend");
@@ -1241,10 +1436,17 @@ async fn messages(
         let msg_id = generate_message_id();
         let openai_stream = backend_response.bytes_stream();
 
-        let (body, usage_accumulator) = transform_stream(openai_stream, requested_model, msg_id);
+        let (body, usage_accumulator) = transform_stream(
+            openai_stream,
+            requested_model,
+            msg_id,
+            state.config.sse_keepalive(),
+        );
 
         // Log usage after stream completes
         let db = state.db.clone();
+        let scheduler = state.scheduler.clone();
+        let fairness_user_id = auth_user.user_id.clone();
         let model_id = model.id.clone();
         let category_id = model.category_id.clone();
         let start_time = start;
@@ -1261,8 +1463,11 @@ async fn messages(
                 if input_tokens > 0 || output_tokens > 0 || tokio::time::Instant::now() >= deadline
                 {
                     let latency_ms = start_time.elapsed().as_millis() as i64;
+                    scheduler
+                        .record_usage(&fairness_user_id, input_tokens + output_tokens)
+                        .await;
                     let entry = usage::UsageEntry {
-                        token_id: &log_token_id,
+                        token_id: Some(&log_token_id),
                         user_id: &log_user_id,
                         model_id: &model_id,
                         category_id: category_id.as_deref(),
@@ -1270,6 +1475,10 @@ async fn messages(
                         output_tokens,
                         latency_ms,
                         queued_ms,
+                        replay_of: None,
+                        seed: None,
+                        prompt_hash: prompt_hashes.as_ref().map(|h| h.exact.as_str()),
+                        prompt_norm_hash: prompt_hashes.as_ref().map(|h| h.normalized.as_str()),
                     };
                     if let Err(e) = usage::log_usage(&db, &entry).await {
                         warn!(error = %e, "Failed to log streaming usage");
@@ -1280,13 +1489,15 @@ async fn messages(
             }
         });
 
-        Response::builder()
+        let mut response = Response::builder()
             .status(StatusCode::OK)
             .header("content-type", "text/event-stream")
             .header("cache-control", "no-cache")
             .header("connection", "keep-alive")
             .body(body)
-            .unwrap()
+            .unwrap();
+        common::add_queue_headers(&mut response, slot.queue_position(), estimated_wait_ms);
+        response
     }
 }
 
@@ -1772,7 +1983,7 @@ mod tests {
 
         let msg_id = generate_message_id();
         let (body, usage_acc) =
-            transform_stream(resp.bytes_stream(), "llama3.1:8b".to_string(), msg_id);
+            transform_stream(resp.bytes_stream(), "llama3.1:8b".to_string(), msg_id, None);
 
         // Collect all SSE events from the body stream
         use http_body_util::BodyExt;
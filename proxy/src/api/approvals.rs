@@ -0,0 +1,332 @@
+//! Two-person approval for high-impact admin actions.
+//!
+//! With `TWO_PERSON_APPROVAL` on, deleting a model, granting admin rights or
+//! granting a role that manages users doesn't happen straight away: the request is recorded as pending and runs
+//! only once a different admin approves it within `APPROVAL_WINDOW_MINUTES`.
+//! Requests, decisions and the final action are all audit-logged.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+use super::admin;
+use super::error;
+use crate::auth::rbac::Role;
+use crate::auth::SessionAuth;
+use crate::AppState;
+
+/// `DELETE /api/admin/models/{id}`; params carry the `override` flag.
+pub const MODEL_DELETE: &str = "model.delete";
+/// `PUT /api/admin/users/{id}` with `is_admin: true`.
+pub const USER_ELEVATE: &str = "user.elevate";
+/// `PUT /api/admin/users/{id}/roles` adding `user_admin`; params carry the
+/// full role list.
+pub const ROLES_GRANT: &str = "user.roles";
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/approvals", get(list_approvals))
+        .route("/approvals/{id}/approve", post(approve))
+        .route("/approvals/{id}/reject", post(reject))
+        .with_state(state)
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Approval {
+    id: String,
+    action: String,
+    resource_id: String,
+    params: String,
+    requested_by: Option<String>,
+    status: String,
+    decided_by: Option<String>,
+    created_at: String,
+    expires_at: String,
+    decided_at: Option<String>,
+}
+
+fn approval_json(a: &Approval) -> serde_json::Value {
+    json!({
+        "id": a.id,
+        "action": a.action,
+        "resource_id": a.resource_id,
+        "params": serde_json::from_str::<serde_json::Value>(&a.params).unwrap_or(json!({})),
+        "requested_by": a.requested_by,
+        "status": a.status,
+        "decided_by": a.decided_by,
+        "created_at": a.created_at,
+        "expires_at": a.expires_at,
+        "decided_at": a.decided_at,
+    })
+}
+
+/// Mark pending requests whose window has passed as expired.
+async fn expire_stale(pool: &sqlx::SqlitePool) -> sqlx::Result<()> {
+    sqlx::query(
+        "UPDATE admin_approvals SET status = 'expired' \
+         WHERE status = 'pending' AND expires_at <= datetime('now')",
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn fetch_approval(pool: &sqlx::SqlitePool, id: &str) -> sqlx::Result<Option<Approval>> {
+    sqlx::query_as("SELECT * FROM admin_approvals WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// Hold `action` on `resource_id` until a second admin approves it. Returns
+/// 202 with the pending request; asking again while one is pending returns
+/// the existing request rather than opening another.
+pub(crate) async fn request(
+    state: &Arc<AppState>,
+    actor: &str,
+    action: &str,
+    resource_id: &str,
+    params: serde_json::Value,
+) -> Response {
+    let pool = &state.db.pool;
+    if let Err(e) = expire_stale(pool).await {
+        return error::internal_error("approvals:expire", e);
+    }
+
+    let existing: Option<Approval> = match sqlx::query_as(
+        "SELECT * FROM admin_approvals \
+         WHERE action = ? AND resource_id = ? AND status = 'pending'",
+    )
+    .bind(action)
+    .bind(resource_id)
+    .fetch_optional(pool)
+    .await
+    {
+        Ok(row) => row,
+        Err(e) => return error::internal_error("approvals:lookup", e),
+    };
+
+    let approval = match existing {
+        Some(approval) => approval,
+        None => {
+            let id = Uuid::new_v4().to_string();
+            if let Err(e) = sqlx::query(
+                "INSERT INTO admin_approvals (id, action, resource_id, params, requested_by, expires_at) \
+                 VALUES (?, ?, ?, ?, ?, datetime('now', ?))",
+            )
+            .bind(&id)
+            .bind(action)
+            .bind(resource_id)
+            .bind(params.to_string())
+            .bind(actor)
+            .bind(format!("+{} minutes", state.config.approval_window_minutes))
+            .execute(pool)
+            .await
+            {
+                return error::internal_error("approvals:create", e);
+            }
+            info!(target: "audit", action = "admin_approval.request", actor = %actor, resource = %id, approval_action = %action, subject = %resource_id, "Admin requested approval for a high-impact action");
+
+            match fetch_approval(pool, &id).await {
+                Ok(Some(approval)) => approval,
+                Ok(None) => {
+                    return error::internal_error(
+                        "approvals:create",
+                        "approval missing after insert",
+                    )
+                }
+                Err(e) => return error::internal_error("approvals:create", e),
+            }
+        }
+    };
+
+    (
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "status": "pending_approval",
+            "approval": approval_json(&approval),
+        })),
+    )
+        .into_response()
+}
+
+/// GET /api/admin/approvals — Approval requests, newest first.
+async fn list_approvals(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    if let Err(e) = expire_stale(&state.db.pool).await {
+        return error::internal_error("approvals:expire", e);
+    }
+    let approvals: Vec<Approval> = match sqlx::query_as(
+        "SELECT * FROM admin_approvals ORDER BY created_at DESC, id LIMIT 200",
+    )
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => return error::internal_error("approvals:list", e),
+    };
+    Json(json!({ "approvals": approvals.iter().map(approval_json).collect::<Vec<_>>() }))
+        .into_response()
+}
+
+/// Load a request that can still be decided, or the response explaining why
+/// it can't.
+async fn pending_approval(state: &AppState, id: &str) -> Result<Approval, Response> {
+    if let Err(e) = expire_stale(&state.db.pool).await {
+        return Err(error::internal_error("approvals:expire", e));
+    }
+    match fetch_approval(&state.db.pool, id).await {
+        Ok(Some(approval)) if approval.status == "pending" => Ok(approval),
+        Ok(Some(approval)) => Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": format!("Approval request is already {}", approval.status) })),
+        )
+            .into_response()),
+        Ok(None) => Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Approval request not found" })),
+        )
+            .into_response()),
+        Err(e) => Err(error::internal_error("approvals:fetch", e)),
+    }
+}
+
+/// Move a pending request to `status`. False if another admin decided it
+/// first.
+async fn decide(
+    pool: &sqlx::SqlitePool,
+    id: &str,
+    status: &str,
+    decided_by: &str,
+) -> sqlx::Result<bool> {
+    let result = sqlx::query(
+        "UPDATE admin_approvals SET status = ?, decided_by = ?, decided_at = datetime('now') \
+         WHERE id = ? AND status = 'pending'",
+    )
+    .bind(status)
+    .bind(decided_by)
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+fn already_decided() -> Response {
+    (
+        StatusCode::CONFLICT,
+        Json(json!({ "error": "Approval request was decided by another admin" })),
+    )
+        .into_response()
+}
+
+/// POST /api/admin/approvals/:id/approve — Approve and run a held action.
+///
+/// The approver must not be the admin who requested it. The response is the
+/// action's own response.
+async fn approve(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let approval = match pending_approval(&state, &id).await {
+        Ok(approval) => approval,
+        Err(r) => return r,
+    };
+    let requested_by = approval.requested_by.clone().unwrap_or_default();
+    if requested_by == session.user_id {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "A different admin must approve this request" })),
+        )
+            .into_response();
+    }
+
+    match decide(&state.db.pool, &id, "approved", &session.user_id).await {
+        Ok(true) => {}
+        Ok(false) => return already_decided(),
+        Err(e) => return error::internal_error("approvals:approve", e),
+    }
+    info!(target: "audit", action = "admin_approval.approve", actor = %session.user_id, resource = %id, approval_action = %approval.action, subject = %approval.resource_id, requested_by = %requested_by, "Admin approved a high-impact action");
+
+    let params: serde_json::Value = serde_json::from_str(&approval.params).unwrap_or(json!({}));
+    let approver = Some(session.user_id.as_str());
+    let response = match approval.action.as_str() {
+        MODEL_DELETE => {
+            let override_ = params["override"].as_bool().unwrap_or(false);
+            admin::delete_model_core(
+                &state,
+                &requested_by,
+                &approval.resource_id,
+                override_,
+                false,
+                approver,
+            )
+            .await
+        }
+        USER_ELEVATE => {
+            admin::set_user_admin(&state, &requested_by, &approval.resource_id, true, approver)
+                .await
+        }
+        ROLES_GRANT => {
+            let roles: Vec<Role> = params["roles"]
+                .as_array()
+                .map(|names| {
+                    names
+                        .iter()
+                        .filter_map(|n| n.as_str().and_then(Role::parse))
+                        .collect()
+                })
+                .unwrap_or_default();
+            admin::set_user_roles(
+                &state,
+                &requested_by,
+                &approval.resource_id,
+                &roles,
+                approver,
+            )
+            .await
+        }
+        other => error::internal_error("approvals:approve", format!("unknown action {other}")),
+    };
+
+    let outcome = if response.status().is_success() {
+        "executed"
+    } else {
+        "failed"
+    };
+    if let Err(e) = sqlx::query("UPDATE admin_approvals SET status = ? WHERE id = ?")
+        .bind(outcome)
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+    {
+        return error::internal_error("approvals:outcome", e);
+    }
+    response
+}
+
+/// POST /api/admin/approvals/:id/reject — Reject a held action. The
+/// requesting admin may reject their own request to withdraw it.
+async fn reject(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let approval = match pending_approval(&state, &id).await {
+        Ok(approval) => approval,
+        Err(r) => return r,
+    };
+    match decide(&state.db.pool, &id, "rejected", &session.user_id).await {
+        Ok(true) => {}
+        Ok(false) => return already_decided(),
+        Err(e) => return error::internal_error("approvals:reject", e),
+    }
+    info!(target: "audit", action = "admin_approval.reject", actor = %session.user_id, resource = %id, approval_action = %approval.action, subject = %approval.resource_id, "Admin rejected a high-impact action");
+    Json(json!({ "status": "rejected" })).into_response()
+}
@@ -0,0 +1,379 @@
+//! Content-addressed prompt/response archive (`PROMPT_ARCHIVE=true`).
+//!
+//! Completion bodies are stored once per SHA-256 in `archive_blobs`; each
+//! request adds a `prompt_archive` entry with the model, sampling parameters
+//! and seed. Entries are private to their owner unless shared, admins see
+//! everything, and entries older than `PROMPT_ARCHIVE_RETENTION_DAYS` are
+//! pruned by the hourly cleanup.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, put};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tracing::info;
+use uuid::Uuid;
+
+use super::error;
+use crate::auth::SessionAuth;
+use crate::db::Database;
+use crate::AppState;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 500;
+
+/// Request fields that carry content or routing rather than sampling
+/// parameters; everything else is kept as `params`.
+const NON_PARAM_FIELDS: &[&str] = &[
+    "model",
+    "messages",
+    "prompt",
+    "stream",
+    "stream_options",
+    "user",
+    "seed",
+];
+
+pub fn user_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/archive", get(search))
+        .route("/archive/blobs/{hash}", get(get_blob))
+        .route("/archive/{id}", put(set_visibility))
+        .with_state(state)
+}
+
+/// A completion to be archived.
+pub struct ArchiveEntry<'a> {
+    pub user_id: &'a str,
+    pub model_id: &'a str,
+    pub endpoint: &'a str,
+    pub request_body: &'a [u8],
+    /// `None` for streamed responses, which are not captured.
+    pub response_body: Option<&'a [u8]>,
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+/// Sampling parameters and seed from a request body.
+fn params_and_seed(request_body: &[u8]) -> (Value, Option<i64>) {
+    let Ok(Value::Object(mut obj)) = serde_json::from_slice::<Value>(request_body) else {
+        return (Value::Object(Default::default()), None);
+    };
+    let seed = obj.get("seed").and_then(Value::as_i64);
+    obj.retain(|k, _| !NON_PARAM_FIELDS.contains(&k.as_str()));
+    (Value::Object(obj), seed)
+}
+
+/// Store a body under its hash (once) and return the hash.
+async fn store_blob(db: &Database, content: &[u8]) -> Result<String> {
+    let hash = sha256_hex(content);
+    sqlx::query("INSERT OR IGNORE INTO archive_blobs (hash, content, size_bytes) VALUES (?, ?, ?)")
+        .bind(&hash)
+        .bind(String::from_utf8_lossy(content).into_owned())
+        .bind(content.len() as i64)
+        .execute(&db.pool)
+        .await
+        .context("Failed to store archive blob")?;
+    Ok(hash)
+}
+
+/// Archive a completion. Returns the new entry ID.
+pub async fn record(db: &Database, entry: &ArchiveEntry<'_>) -> Result<String> {
+    let id = Uuid::new_v4().to_string();
+    let (params, seed) = params_and_seed(entry.request_body);
+    let prompt_hash = store_blob(db, entry.request_body).await?;
+    let response_hash = match entry.response_body {
+        Some(body) => Some(store_blob(db, body).await?),
+        None => None,
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO prompt_archive (id, user_id, model_id, endpoint, prompt_hash,
+                                    response_hash, params, seed)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&id)
+    .bind(entry.user_id)
+    .bind(entry.model_id)
+    .bind(entry.endpoint)
+    .bind(&prompt_hash)
+    .bind(&response_hash)
+    .bind(params.to_string())
+    .bind(seed)
+    .execute(&db.pool)
+    .await
+    .context("Failed to insert prompt archive entry")?;
+
+    Ok(id)
+}
+
+/// Delete entries older than `days`, then blobs no entry references.
+/// Returns the number of entries deleted.
+pub async fn prune(db: &Database, days: i64) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM prompt_archive WHERE created_at < datetime('now', ?)")
+        .bind(format!("-{days} days"))
+        .execute(&db.pool)
+        .await?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM archive_blobs
+        WHERE hash NOT IN (SELECT prompt_hash FROM prompt_archive)
+          AND hash NOT IN (SELECT response_hash FROM prompt_archive WHERE response_hash IS NOT NULL)
+        "#,
+    )
+    .execute(&db.pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ArchiveListItem {
+    id: String,
+    user_id: String,
+    model_id: String,
+    endpoint: String,
+    prompt_hash: String,
+    response_hash: Option<String>,
+    #[serde(serialize_with = "serialize_json_text")]
+    params: String,
+    seed: Option<i64>,
+    visibility: String,
+    created_at: String,
+}
+
+/// Serialize a JSON TEXT column as a nested value.
+fn serialize_json_text<S: serde::Serializer>(text: &str, s: S) -> Result<S::Ok, S::Error> {
+    serde_json::from_str::<Value>(text)
+        .unwrap_or(Value::Null)
+        .serialize(s)
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchQuery {
+    /// Prompt or response hash, or a prefix of one.
+    hash: Option<String>,
+    model: Option<String>,
+    seed: Option<i64>,
+    /// Only the caller's own entries.
+    #[serde(default)]
+    mine: bool,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<i64>,
+}
+
+/// GET /api/user/archive — Search archived completions visible to the caller.
+async fn search(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Query(q): Query<SearchQuery>,
+) -> impl IntoResponse {
+    let hash_prefix = q.hash.as_deref().map(|h| format!("{}%", h.to_lowercase()));
+    let limit = q.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    match sqlx::query_as::<_, ArchiveListItem>(
+        r#"
+        SELECT id, user_id, model_id, endpoint, prompt_hash, response_hash, params, seed,
+               visibility, created_at
+        FROM prompt_archive
+        WHERE (user_id = ? OR (? AND visibility = 'shared') OR (? AND ?))
+          AND (? IS NULL OR prompt_hash LIKE ? OR response_hash LIKE ?)
+          AND (? IS NULL OR model_id = ?)
+          AND (? IS NULL OR seed = ?)
+          AND (? IS NULL OR created_at >= ?)
+          AND (? IS NULL OR created_at < ?)
+        ORDER BY created_at DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(&session.user_id)
+    .bind(!q.mine)
+    .bind(!q.mine)
+    .bind(session.is_admin)
+    .bind(&hash_prefix)
+    .bind(&hash_prefix)
+    .bind(&hash_prefix)
+    .bind(&q.model)
+    .bind(&q.model)
+    .bind(q.seed)
+    .bind(q.seed)
+    .bind(&q.since)
+    .bind(&q.since)
+    .bind(&q.until)
+    .bind(&q.until)
+    .bind(limit)
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(entries) => Json(serde_json::json!({ "entries": entries })).into_response(),
+        Err(e) => error::internal_error("archive_search", e),
+    }
+}
+
+/// GET /api/user/archive/blobs/:hash — Fetch an archived body by hash.
+/// Only blobs referenced by an entry the caller can see are returned.
+async fn get_blob(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(hash): Path<String>,
+) -> impl IntoResponse {
+    let blob: Option<(String, i64)> = match sqlx::query_as(
+        r#"
+        SELECT b.content, b.size_bytes
+        FROM archive_blobs b
+        WHERE b.hash = ?
+          AND EXISTS (
+            SELECT 1 FROM prompt_archive a
+            WHERE (a.prompt_hash = b.hash OR a.response_hash = b.hash)
+              AND (a.user_id = ? OR a.visibility = 'shared' OR ?)
+          )
+        "#,
+    )
+    .bind(&hash)
+    .bind(&session.user_id)
+    .bind(session.is_admin)
+    .fetch_optional(&state.db.pool)
+    .await
+    {
+        Ok(b) => b,
+        Err(e) => return error::internal_error("archive_blob", e),
+    };
+
+    match blob {
+        Some((content, size_bytes)) => {
+            // Bodies are JSON; fall back to the raw text if one isn't
+            let content = serde_json::from_str::<Value>(&content).unwrap_or(Value::String(content));
+            Json(serde_json::json!({
+                "hash": hash,
+                "size_bytes": size_bytes,
+                "content": content,
+            }))
+            .into_response()
+        }
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Blob not found" })),
+        )
+            .into_response(),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SetVisibilityRequest {
+    visibility: String,
+}
+
+/// PUT /api/user/archive/:id — Share an entry with all users or make it
+/// private again. Owners only.
+async fn set_visibility(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(req): Json<SetVisibilityRequest>,
+) -> impl IntoResponse {
+    if req.visibility != "private" && req.visibility != "shared" {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "visibility must be 'private' or 'shared'" })),
+        )
+            .into_response();
+    }
+
+    match sqlx::query("UPDATE prompt_archive SET visibility = ? WHERE id = ? AND user_id = ?")
+        .bind(&req.visibility)
+        .bind(&id)
+        .bind(&session.user_id)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Archive entry not found" })),
+        )
+            .into_response(),
+        Ok(_) => {
+            info!(target: "audit", action = "archive.visibility", actor = %session.user_id, resource = %id, visibility = %req.visibility, "User changed archive entry visibility");
+            Json(serde_json::json!({ "status": "updated" })).into_response()
+        }
+        Err(e) => error::internal_error("archive_set_visibility", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry<'a>(request: &'a [u8], response: Option<&'a [u8]>) -> ArchiveEntry<'a> {
+        ArchiveEntry {
+            user_id: "u1",
+            model_id: "m1",
+            endpoint: "/v1/chat/completions",
+            request_body: request,
+            response_body: response,
+        }
+    }
+
+    #[test]
+    fn params_exclude_content_and_capture_seed() {
+        let body = br#"{"model":"m","messages":[],"temperature":0.2,"seed":7,"stream":false}"#;
+        let (params, seed) = params_and_seed(body);
+        assert_eq!(seed, Some(7));
+        assert_eq!(params, serde_json::json!({"temperature": 0.2}));
+
+        let (params, seed) = params_and_seed(b"not json");
+        assert_eq!(params, serde_json::json!({}));
+        assert_eq!(seed, None);
+    }
+
+    #[tokio::test]
+    async fn identical_prompts_share_a_blob() {
+        let db = Database::test_db().await;
+        let prompt = br#"{"model":"m","messages":[{"role":"user","content":"hi"}]}"#;
+        record(&db, &entry(prompt, Some(b"{}"))).await.unwrap();
+        record(&db, &entry(prompt, None)).await.unwrap();
+
+        let (blobs,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM archive_blobs")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(blobs, 2);
+        let (hash,): (String,) = sqlx::query_as("SELECT DISTINCT prompt_hash FROM prompt_archive")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(hash, sha256_hex(prompt));
+    }
+
+    #[tokio::test]
+    async fn prune_removes_old_entries_and_orphaned_blobs() {
+        let db = Database::test_db().await;
+        let old = record(&db, &entry(b"{\"old\":1}", None)).await.unwrap();
+        record(&db, &entry(b"{\"new\":1}", None)).await.unwrap();
+        sqlx::query(
+            "UPDATE prompt_archive SET created_at = datetime('now', '-40 days') WHERE id = ?",
+        )
+        .bind(&old)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        assert_eq!(prune(&db, 30).await.unwrap(), 1);
+        let (blobs,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM archive_blobs")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(blobs, 1);
+    }
+}
@@ -0,0 +1,264 @@
+//! Structured change sets for admin configuration updates.
+//!
+//! Settings, IdP, model and category updates snapshot the record before and
+//! after the change and store the fields that differ in `config_changes`.
+//! The audit log event carries the row's `diff_id`, which admins resolve via
+//! `GET /api/admin/audit/{id}/diff`. Secret values are redacted before they
+//! are stored.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::error;
+use crate::AppState;
+
+/// Stored in place of a secret value.
+const REDACTED: &str = "[redacted]";
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/audit/{id}/diff", get(get_diff))
+        .with_state(state)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Value,
+    pub after: Value,
+}
+
+/// Whether a field holds a credential whose value must not be recorded.
+fn is_secret(field: &str) -> bool {
+    let field = field.to_ascii_lowercase();
+    ["secret", "password", "api_key", "private_key"]
+        .iter()
+        .any(|s| field.contains(s))
+}
+
+/// Fields of the `before` and `after` objects whose values differ, in key
+/// order. Secrets that changed are listed with both values redacted.
+pub fn diff(before: &Value, after: &Value) -> Vec<FieldChange> {
+    let empty = serde_json::Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+
+    let mut fields: Vec<&String> = before.keys().chain(after.keys()).collect();
+    fields.sort();
+    fields.dedup();
+
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            let old = before.get(field).unwrap_or(&Value::Null);
+            let new = after.get(field).unwrap_or(&Value::Null);
+            if old == new {
+                return None;
+            }
+            let redact = |v: &Value| {
+                if v.is_null() {
+                    Value::Null
+                } else {
+                    Value::String(REDACTED.to_string())
+                }
+            };
+            let (old, new) = if is_secret(field) {
+                (redact(old), redact(new))
+            } else {
+                (old.clone(), new.clone())
+            };
+            Some(FieldChange {
+                field: field.clone(),
+                before: old,
+                after: new,
+            })
+        })
+        .collect()
+}
+
+/// Store the change set for `action` and return its ID.
+pub async fn record(
+    pool: &sqlx::SqlitePool,
+    action: &str,
+    actor: &str,
+    resource: Option<&str>,
+    changes: &[FieldChange],
+) -> Result<String> {
+    let id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO config_changes (id, action, actor, resource, changes) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(action)
+    .bind(actor)
+    .bind(resource)
+    .bind(serde_json::to_string(changes)?)
+    .execute(pool)
+    .await
+    .context("Failed to record config change")?;
+    Ok(id)
+}
+
+/// Diff `before` against `after` and record it. A failure is logged rather
+/// than returned: the change itself has already been applied.
+pub async fn record_diff(
+    pool: &sqlx::SqlitePool,
+    action: &str,
+    actor: &str,
+    resource: Option<&str>,
+    before: &Value,
+    after: &Value,
+) -> Option<String> {
+    match record(pool, action, actor, resource, &diff(before, after)).await {
+        Ok(id) => Some(id),
+        Err(e) => {
+            warn!(error = %e, action, "Failed to record config change set");
+            None
+        }
+    }
+}
+
+/// Current state of a row as a JSON object, for diffing. `select` is a
+/// `SELECT json_object(...) FROM <table> WHERE id = ?` query.
+pub async fn snapshot(pool: &sqlx::SqlitePool, select: &str, id: &str) -> Result<Option<Value>> {
+    let row: Option<String> = sqlx::query_scalar(select)
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .context("Failed to snapshot record")?;
+    row.map(|json| serde_json::from_str(&json).context("Invalid snapshot JSON"))
+        .transpose()
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ConfigChange {
+    id: String,
+    action: String,
+    actor: String,
+    resource: Option<String>,
+    changes: String,
+    created_at: String,
+}
+
+/// GET /api/admin/audit/:id/diff — The change set behind an audit event's `diff_id`.
+async fn get_diff(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> impl IntoResponse {
+    let change: Option<ConfigChange> =
+        match sqlx::query_as("SELECT * FROM config_changes WHERE id = ?")
+            .bind(&id)
+            .fetch_optional(&state.db.pool)
+            .await
+        {
+            Ok(change) => change,
+            Err(e) => return error::internal_error("audit:diff", e),
+        };
+    let Some(change) = change else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Change set not found" })),
+        )
+            .into_response();
+    };
+
+    Json(json!({
+        "id": change.id,
+        "action": change.action,
+        "actor": change.actor,
+        "resource": change.resource,
+        "changes": serde_json::from_str::<Value>(&change.changes).unwrap_or(json!([])),
+        "created_at": change.created_at,
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn diff_lists_changed_fields_only() {
+        let changes = diff(
+            &json!({ "name": "a", "scopes": "openid", "enabled": 1 }),
+            &json!({ "name": "b", "scopes": "openid", "enabled": 0 }),
+        );
+        assert_eq!(
+            changes,
+            [
+                FieldChange {
+                    field: "enabled".into(),
+                    before: json!(1),
+                    after: json!(0),
+                },
+                FieldChange {
+                    field: "name".into(),
+                    before: json!("a"),
+                    after: json!("b"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_redacts_secrets() {
+        let changes = diff(
+            &json!({ "client_secret": "old", "api_key": null }),
+            &json!({ "client_secret": "new", "api_key": "k" }),
+        );
+        assert_eq!(changes[0].field, "api_key");
+        assert_eq!(changes[0].before, Value::Null);
+        assert_eq!(changes[0].after, json!(REDACTED));
+        assert_eq!(changes[1].before, json!(REDACTED));
+        assert_eq!(changes[1].after, json!(REDACTED));
+    }
+
+    #[tokio::test]
+    async fn snapshots_round_trip_through_record() {
+        let db = Database::test_db().await;
+        sqlx::query("INSERT INTO model_categories (id, name) VALUES ('c1', 'coding')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let select = "SELECT json_object('name', name, 'autoload', autoload) \
+                      FROM model_categories WHERE id = ?";
+        let before = snapshot(&db.pool, select, "c1").await.unwrap().unwrap();
+        sqlx::query("UPDATE model_categories SET autoload = 1 WHERE id = 'c1'")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let after = snapshot(&db.pool, select, "c1").await.unwrap().unwrap();
+        assert!(snapshot(&db.pool, select, "missing")
+            .await
+            .unwrap()
+            .is_none());
+
+        let id = record_diff(
+            &db.pool,
+            "category.update",
+            "admin",
+            Some("c1"),
+            &before,
+            &after,
+        )
+        .await
+        .unwrap();
+        let stored: String = sqlx::query_scalar("SELECT changes FROM config_changes WHERE id = ?")
+            .bind(&id)
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&stored).unwrap(),
+            json!([{ "field": "autoload", "before": 0, "after": 1 }])
+        );
+    }
+}
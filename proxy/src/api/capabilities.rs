@@ -0,0 +1,259 @@
+//! Model capability probing.
+//!
+//! Once a started container answers its health check, the proxy asks the
+//! backend what it actually serves: which of `/v1/models`, chat, text
+//! completions and embeddings answer, the context length it accepts (vLLM's
+//! `max_model_len`, llama.cpp's `n_ctx`) and, for embedding models, the
+//! vector dimension. The result is stored as JSON in `models.capabilities`
+//! and shown on `/v1/models` and the model lists, so clients see what was
+//! verified rather than what the GGUF metadata suggests. A failed probe
+//! leaves the previous result in place.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use super::common::{lookup_backend_type, serving_container_name};
+use crate::AppState;
+
+/// Pause between health checks while waiting to probe.
+const HEALTH_POLL: Duration = Duration::from_secs(2);
+
+/// Per-request timeout for probe requests. A one-token completion can be
+/// slow on a CPU backend that is still warming up.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What a probe found, stored in `models.capabilities`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    /// Endpoints that answered: `models`, `chat`, `completions`, `embeddings`.
+    pub endpoints: Vec<String>,
+    /// Context length the backend accepts.
+    pub max_context: Option<u64>,
+    /// Length of the vectors `/v1/embeddings` returns.
+    pub embedding_dim: Option<u64>,
+    pub probed_at: String,
+}
+
+/// Probe `model_id` in the background once its container is healthy, then
+/// warm its slots up if `BACKEND_WARMUP` is set.
+pub fn spawn_probe(state: Arc<AppState>, model_id: String) {
+    let warmup = state.config.backend_warmup;
+    if warmup {
+        state.warmups.pending(&model_id);
+    }
+    tokio::spawn(async move {
+        let backend = match connect_when_healthy(&state, &model_id).await {
+            Ok(backend) => backend,
+            Err(e) => {
+                warn!(model = %model_id, error = %e, "Model capability probe failed");
+                if warmup {
+                    state.warmups.failed(&model_id, &e.to_string());
+                }
+                return;
+            }
+        };
+        if let Err(e) = probe(&state, &model_id, &backend).await {
+            warn!(model = %model_id, error = %e, "Model capability probe failed");
+        }
+        if warmup {
+            super::warmup::run(&state.warmups, &backend, &model_id).await;
+        }
+    });
+}
+
+/// Wait for the model's container to pass its health check.
+async fn connect_when_healthy(state: &AppState, model_id: &str) -> Result<Backend> {
+    let backend_type = lookup_backend_type(&state.db.pool, model_id).await;
+    let container = serving_container_name(&state.db.pool, model_id, &backend_type).await;
+
+    let deadline =
+        tokio::time::Instant::now() + Duration::from_secs(state.config.autoload_timeout_secs);
+    while !matches!(
+        state
+            .docker
+            .check_health_at(&container, &backend_type)
+            .await,
+        Ok(true)
+    ) {
+        if tokio::time::Instant::now() >= deadline {
+            bail!("container {container} not healthy before timeout");
+        }
+        tokio::time::sleep(HEALTH_POLL).await;
+    }
+
+    let secrets: Option<(String, i64)> =
+        sqlx::query_as("SELECT api_key, parallel_slots FROM container_secrets WHERE model_id = ?")
+            .bind(model_id)
+            .fetch_optional(&state.db.pool)
+            .await?;
+    let embedding: bool = sqlx::query_scalar("SELECT embedding FROM models WHERE id = ?")
+        .bind(model_id)
+        .fetch_optional(&state.db.pool)
+        .await?
+        .unwrap_or(false);
+    let (api_key, slots) = secrets.unzip();
+
+    Ok(Backend {
+        client: state.docker.health_client()?,
+        base_url: state.docker.backend_url(&container, &backend_type),
+        api_key: api_key.filter(|k| !k.is_empty()),
+        backend_type,
+        embedding,
+        slots: slots
+            .and_then(|n| u32::try_from(n).ok())
+            .unwrap_or(1)
+            .max(1),
+    })
+}
+
+async fn probe(state: &AppState, model_id: &str, backend: &Backend) -> Result<()> {
+    let mut caps = Capabilities {
+        probed_at: chrono::Utc::now().to_rfc3339(),
+        ..Default::default()
+    };
+
+    if let Some(models) = backend.get("/v1/models").await {
+        caps.endpoints.push("models".into());
+        caps.max_context = max_model_len(&models, model_id);
+    }
+    if backend.backend_type == "llamacpp" {
+        if let Some(props) = backend.get("/props").await {
+            caps.max_context = llamacpp_n_ctx(&props).or(caps.max_context);
+        }
+    }
+    if backend.embedding {
+        let body = json!({ "model": model_id, "input": "ping" });
+        if let Some(resp) = backend.post("/v1/embeddings", &body).await {
+            caps.endpoints.push("embeddings".into());
+            caps.embedding_dim = embedding_dim(&resp);
+        }
+    } else {
+        let chat = json!({
+            "model": model_id,
+            "messages": [{ "role": "user", "content": "ping" }],
+            "max_tokens": 1,
+        });
+        if backend.post("/v1/chat/completions", &chat).await.is_some() {
+            caps.endpoints.push("chat".into());
+        }
+        let completion = json!({ "model": model_id, "prompt": "ping", "max_tokens": 1 });
+        if backend.post("/v1/completions", &completion).await.is_some() {
+            caps.endpoints.push("completions".into());
+        }
+    }
+
+    sqlx::query("UPDATE models SET capabilities = ? WHERE id = ?")
+        .bind(serde_json::to_string(&caps)?)
+        .bind(model_id)
+        .execute(&state.db.pool)
+        .await
+        .context("Failed to store model capabilities")?;
+    info!(model = %model_id, endpoints = ?caps.endpoints, max_context = ?caps.max_context, embedding_dim = ?caps.embedding_dim, "Probed model capabilities");
+    Ok(())
+}
+
+/// A backend container, reached the way health checks reach it.
+pub(super) struct Backend {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+    pub(super) backend_type: String,
+    pub(super) embedding: bool,
+    /// Parallel slots the container was started with.
+    pub(super) slots: u32,
+}
+
+impl Backend {
+    async fn get(&self, path: &str) -> Option<Value> {
+        self.send(self.client.get(format!("{}{path}", self.base_url)))
+            .await
+    }
+
+    pub(super) async fn post(&self, path: &str, body: &Value) -> Option<Value> {
+        self.send(
+            self.client
+                .post(format!("{}{path}", self.base_url))
+                .json(body),
+        )
+        .await
+    }
+
+    /// The JSON body of a successful response; `None` for anything else.
+    async fn send(&self, mut req: reqwest::RequestBuilder) -> Option<Value> {
+        if let Some(key) = &self.api_key {
+            req = req.bearer_auth(key);
+        }
+        let resp = req.timeout(PROBE_TIMEOUT).send().await.ok()?;
+        if !resp.status().is_success() {
+            return None;
+        }
+        resp.json().await.ok()
+    }
+}
+
+/// vLLM's `max_model_len` for `model_id` from a `/v1/models` response.
+fn max_model_len(models: &Value, model_id: &str) -> Option<u64> {
+    let data = models["data"].as_array()?;
+    data.iter()
+        .find(|m| m["id"] == model_id)
+        .or_else(|| data.first())?["max_model_len"]
+        .as_u64()
+}
+
+/// llama.cpp's per-slot context from a `/props` response. Older servers
+/// report it at the top level.
+fn llamacpp_n_ctx(props: &Value) -> Option<u64> {
+    props["default_generation_settings"]["n_ctx"]
+        .as_u64()
+        .or_else(|| props["n_ctx"].as_u64())
+}
+
+/// Vector length from a `/v1/embeddings` response.
+fn embedding_dim(resp: &Value) -> Option<u64> {
+    resp["data"][0]["embedding"]
+        .as_array()
+        .map(|v| v.len() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_vllm_max_model_len() {
+        let models = json!({
+            "data": [
+                { "id": "other", "max_model_len": 4096 },
+                { "id": "m1", "max_model_len": 32768 },
+            ]
+        });
+        assert_eq!(max_model_len(&models, "m1"), Some(32768));
+        assert_eq!(max_model_len(&models, "unknown"), Some(4096));
+        assert_eq!(max_model_len(&json!({ "data": [] }), "m1"), None);
+        // llama.cpp lists the model without a length
+        assert_eq!(
+            max_model_len(&json!({ "data": [{ "id": "m1" }] }), "m1"),
+            None
+        );
+    }
+
+    #[test]
+    fn reads_llamacpp_n_ctx() {
+        let props = json!({ "default_generation_settings": { "n_ctx": 8192 }, "total_slots": 2 });
+        assert_eq!(llamacpp_n_ctx(&props), Some(8192));
+        assert_eq!(llamacpp_n_ctx(&json!({ "n_ctx": 4096 })), Some(4096));
+        assert_eq!(llamacpp_n_ctx(&json!({})), None);
+    }
+
+    #[test]
+    fn reads_embedding_dim() {
+        let resp = json!({ "data": [{ "embedding": [0.1, 0.2, 0.3] }] });
+        assert_eq!(embedding_dim(&resp), Some(3));
+        assert_eq!(embedding_dim(&json!({ "data": [] })), None);
+    }
+}
@@ -0,0 +1,532 @@
+//! Model auto-categorization.
+//!
+//! A model's kind (embedding, reranker, vision, code, reasoning or chat) is
+//! guessed from how it has been used, its Hugging Face pipeline tag, its
+//! GGUF architecture and its name, in that order. The kind is mapped to the
+//! category that claims it (`auto_kind`), or else to one whose name says it
+//! (an "Embeddings" category for embedding models). `AUTO_CATEGORIZE`
+//! decides what happens to a new download with no category: the category is
+//! `suggest`ed for an admin to accept, `assign`ed straight away, or nothing
+//! happens (`off`). Admins can run the same over every uncategorized model.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{middleware, Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::SqlitePool;
+use tracing::info;
+
+use super::{audit, error};
+use crate::auth::rbac::{self, Permission};
+use crate::auth::SessionAuth;
+use crate::AppState;
+
+/// Requests a model must have served before its usage says anything.
+const MIN_USAGE_REQUESTS: i64 = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModelKind {
+    Embedding,
+    Rerank,
+    Vision,
+    Code,
+    Reasoning,
+    Chat,
+}
+
+impl ModelKind {
+    pub const ALL: [ModelKind; 6] = [
+        ModelKind::Embedding,
+        ModelKind::Rerank,
+        ModelKind::Vision,
+        ModelKind::Code,
+        ModelKind::Reasoning,
+        ModelKind::Chat,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelKind::Embedding => "embedding",
+            ModelKind::Rerank => "rerank",
+            ModelKind::Vision => "vision",
+            ModelKind::Code => "code",
+            ModelKind::Reasoning => "reasoning",
+            ModelKind::Chat => "chat",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == s)
+    }
+
+    /// Words that mark a category name as meant for this kind.
+    fn category_keywords(&self) -> &'static [&'static str] {
+        match self {
+            ModelKind::Embedding => &["embed"],
+            ModelKind::Rerank => &["rerank"],
+            ModelKind::Vision => &["vision", "multimodal"],
+            ModelKind::Code => &["code", "coding"],
+            ModelKind::Reasoning => &["reason", "thinking"],
+            ModelKind::Chat => &["chat", "general"],
+        }
+    }
+}
+
+/// What is known about a model.
+#[derive(Debug, Default)]
+pub struct Signals<'a> {
+    pub hf_repo: &'a str,
+    pub filename: Option<&'a str>,
+    pub pipeline_tag: Option<&'a str>,
+    /// GGUF `general.architecture`.
+    pub architecture: Option<&'a str>,
+    /// Requests served in the last 30 days, and how many produced output.
+    pub requests: i64,
+    pub requests_with_output: i64,
+}
+
+/// Guess a model's kind, with the reason.
+pub fn classify(signals: &Signals) -> Option<(ModelKind, String)> {
+    let name = match signals.filename {
+        Some(f) => format!("{} {}", signals.hf_repo, f),
+        None => signals.hf_repo.to_string(),
+    }
+    .to_lowercase();
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let has = |pred: &dyn Fn(&str) -> bool| words.iter().copied().find(|&w| pred(w));
+
+    // Rerankers look like embedding models in every other respect
+    if let Some(w) = has(&|w: &str| w.starts_with("rerank")) {
+        return Some((ModelKind::Rerank, format!("name contains \"{w}\"")));
+    }
+    if signals.requests >= MIN_USAGE_REQUESTS && signals.requests_with_output == 0 {
+        return Some((
+            ModelKind::Embedding,
+            format!(
+                "none of its {} requests in 30 days produced output tokens",
+                signals.requests
+            ),
+        ));
+    }
+    if let Some(tag) = signals.pipeline_tag {
+        let kind = match tag {
+            "feature-extraction" | "sentence-similarity" => Some(ModelKind::Embedding),
+            "text-ranking" => Some(ModelKind::Rerank),
+            "image-text-to-text"
+            | "visual-question-answering"
+            | "image-to-text"
+            | "video-text-to-text" => Some(ModelKind::Vision),
+            _ => None,
+        };
+        if let Some(kind) = kind {
+            return Some((kind, format!("pipeline tag {tag}")));
+        }
+    }
+    if let Some(arch) = signals.architecture {
+        let kind = if arch.contains("bert") || arch == "t5encoder" {
+            Some(ModelKind::Embedding)
+        } else if arch.ends_with("vl") || arch == "mllama" || arch == "llava" {
+            Some(ModelKind::Vision)
+        } else {
+            None
+        };
+        if let Some(kind) = kind {
+            return Some((kind, format!("GGUF architecture {arch}")));
+        }
+    }
+
+    let name_rules: [(ModelKind, &dyn Fn(&str) -> bool); 5] = [
+        (ModelKind::Embedding, &|w: &str| {
+            w.starts_with("embed") || matches!(w, "bge" | "e5" | "gte" | "minilm" | "mpnet")
+        }),
+        (ModelKind::Vision, &|w: &str| {
+            matches!(w, "vl" | "vision" | "llava" | "pixtral" | "mmproj")
+        }),
+        (ModelKind::Code, &|w: &str| {
+            matches!(w, "code" | "coder")
+                || [
+                    "starcoder",
+                    "codestral",
+                    "devstral",
+                    "codellama",
+                    "codegemma",
+                    "codeqwen",
+                ]
+                .iter()
+                .any(|p| w.starts_with(p))
+        }),
+        (ModelKind::Reasoning, &|w: &str| {
+            matches!(w, "r1" | "qwq" | "reasoning" | "thinking")
+        }),
+        (ModelKind::Chat, &|w: &str| {
+            matches!(w, "instruct" | "chat" | "it")
+        }),
+    ];
+    for (kind, rule) in name_rules {
+        if let Some(w) = has(rule) {
+            return Some((kind, format!("name contains \"{w}\"")));
+        }
+    }
+    if signals.pipeline_tag == Some("text-generation") {
+        return Some((ModelKind::Chat, "pipeline tag text-generation".to_string()));
+    }
+    None
+}
+
+/// A model's detected kind and the category it maps to.
+#[derive(Debug, Serialize)]
+pub struct Outcome {
+    pub model_id: String,
+    pub hf_repo: String,
+    pub kind: ModelKind,
+    pub reason: String,
+    /// `None` when no category claims the kind.
+    pub category_id: Option<String>,
+    pub category_name: Option<String>,
+    /// Assigned, rather than suggested.
+    pub applied: bool,
+}
+
+/// How `AUTO_CATEGORIZE` treats new downloads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Off,
+    Suggest,
+    Assign,
+}
+
+impl Mode {
+    pub fn from_config(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "off" | "false" => Mode::Off,
+            "assign" => Mode::Assign,
+            _ => Mode::Suggest,
+        }
+    }
+}
+
+/// The category for a kind: the one that claims it, or else the first
+/// whose name contains one of the kind's keywords.
+async fn category_for(pool: &SqlitePool, kind: ModelKind) -> Result<Option<(String, String)>> {
+    let claimed: Option<(String, String)> =
+        sqlx::query_as("SELECT id, name FROM model_categories WHERE auto_kind = ?")
+            .bind(kind.as_str())
+            .fetch_optional(pool)
+            .await?;
+    if claimed.is_some() {
+        return Ok(claimed);
+    }
+    let categories: Vec<(String, String)> =
+        sqlx::query_as("SELECT id, name FROM model_categories ORDER BY name")
+            .fetch_all(pool)
+            .await?;
+    Ok(categories.into_iter().find(|(_, name)| {
+        let name = name.to_lowercase();
+        kind.category_keywords().iter().any(|k| name.contains(k))
+    }))
+}
+
+/// Classify an uncategorized model and, if a category fits, assign it or
+/// store it as the model's suggestion. `None` if the model is categorized
+/// already or its kind can't be told.
+pub(crate) async fn categorize_model(
+    pool: &SqlitePool,
+    model_id: &str,
+    assign: bool,
+    actor: &str,
+) -> Result<Option<Outcome>> {
+    let row: Option<(String, Option<String>, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT hf_repo, filename, pipeline_tag, architecture FROM models \
+         WHERE id = ? AND category_id IS NULL",
+    )
+    .bind(model_id)
+    .fetch_optional(pool)
+    .await?;
+    let Some((hf_repo, filename, pipeline_tag, architecture)) = row else {
+        return Ok(None);
+    };
+    let (requests, requests_with_output): (i64, i64) = sqlx::query_as(
+        "SELECT COUNT(*), COALESCE(SUM(output_tokens > 0), 0) FROM usage_log \
+         WHERE model_id = ? AND created_at > datetime('now', '-30 days')",
+    )
+    .bind(model_id)
+    .fetch_one(pool)
+    .await?;
+
+    let signals = Signals {
+        hf_repo: &hf_repo,
+        filename: filename.as_deref(),
+        pipeline_tag: pipeline_tag.as_deref(),
+        architecture: architecture.as_deref(),
+        requests,
+        requests_with_output,
+    };
+    let Some((kind, reason)) = classify(&signals) else {
+        return Ok(None);
+    };
+    let category = category_for(pool, kind).await?;
+
+    let mut applied = false;
+    if let Some((category_id, _)) = &category {
+        if assign {
+            sqlx::query(
+                "UPDATE models SET category_id = ?, suggested_category_id = NULL, \
+                 category_suggestion_reason = NULL WHERE id = ? AND category_id IS NULL",
+            )
+            .bind(category_id)
+            .bind(model_id)
+            .execute(pool)
+            .await?;
+            audit::record_diff(
+                pool,
+                "model.categorize",
+                actor,
+                Some(model_id),
+                &json!({ "category_id": null }),
+                &json!({ "category_id": category_id }),
+            )
+            .await;
+            info!(target: "audit", action = "model.categorize", actor = %actor, resource = %model_id, category = %category_id, kind = kind.as_str(), reason = %reason, "Model categorized automatically");
+            applied = true;
+        } else {
+            sqlx::query(
+                "UPDATE models SET suggested_category_id = ?, category_suggestion_reason = ? \
+                 WHERE id = ?",
+            )
+            .bind(category_id)
+            .bind(&reason)
+            .bind(model_id)
+            .execute(pool)
+            .await?;
+        }
+    }
+    let (category_id, category_name) = category.unzip();
+    Ok(Some(Outcome {
+        model_id: model_id.to_string(),
+        hf_repo,
+        kind,
+        reason,
+        category_id,
+        category_name,
+        applied,
+    }))
+}
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/models/categorize", post(categorize_all))
+        .route(
+            "/models/{id}/category-suggestion",
+            post(accept_suggestion).delete(dismiss_suggestion),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Models,
+            rbac::require_permission,
+        ))
+        .with_state(state)
+}
+
+#[derive(Debug, Deserialize)]
+struct CategorizeRequest {
+    /// Assign the categories instead of suggesting them.
+    #[serde(default)]
+    apply: bool,
+}
+
+/// POST /api/admin/models/categorize — Categorize every uncategorized model.
+async fn categorize_all(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Json(req): Json<CategorizeRequest>,
+) -> Response {
+    let ids: Vec<String> = match sqlx::query_scalar(
+        "SELECT id FROM models WHERE category_id IS NULL ORDER BY hf_repo",
+    )
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(ids) => ids,
+        Err(e) => return error::internal_error("categorize:list", e),
+    };
+
+    let mut outcomes = Vec::new();
+    for id in &ids {
+        match categorize_model(&state.db.pool, id, req.apply, &session.user_id).await {
+            Ok(Some(outcome)) => outcomes.push(outcome),
+            Ok(None) => {}
+            Err(e) => return error::internal_error("categorize:model", e),
+        }
+    }
+    info!(target: "audit", action = "model.categorize_all", actor = %session.user_id, apply = req.apply, uncategorized = ids.len(), matched = outcomes.iter().filter(|o| o.category_id.is_some()).count(), "Admin ran model auto-categorization");
+    Json(json!({ "models": outcomes })).into_response()
+}
+
+/// POST /api/admin/models/:id/category-suggestion — Accept the suggestion.
+async fn accept_suggestion(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> Response {
+    let suggested: Option<Option<String>> =
+        match sqlx::query_scalar("SELECT suggested_category_id FROM models WHERE id = ?")
+            .bind(&id)
+            .fetch_optional(&state.db.pool)
+            .await
+        {
+            Ok(row) => row,
+            Err(e) => return error::internal_error("categorize:lookup", e),
+        };
+    let Some(suggested) = suggested else {
+        return not_found("Model not found");
+    };
+    let Some(category_id) = suggested else {
+        return not_found("No category suggestion for this model");
+    };
+
+    // The category may have been deleted since it was suggested
+    let result = sqlx::query(
+        "UPDATE models SET category_id = ?1, suggested_category_id = NULL, \
+         category_suggestion_reason = NULL \
+         WHERE id = ?2 AND EXISTS (SELECT 1 FROM model_categories WHERE id = ?1)",
+    )
+    .bind(&category_id)
+    .bind(&id)
+    .execute(&state.db.pool)
+    .await;
+    match result {
+        Ok(r) if r.rows_affected() == 0 => {
+            clear_suggestion(&state.db.pool, &id).await;
+            not_found("The suggested category no longer exists")
+        }
+        Ok(_) => {
+            let diff_id = audit::record_diff(
+                &state.db.pool,
+                "model.categorize",
+                &session.user_id,
+                Some(id.as_str()),
+                &json!({ "category_id": null }),
+                &json!({ "category_id": category_id }),
+            )
+            .await;
+            let diff_id = diff_id.as_deref().unwrap_or("-");
+            info!(target: "audit", action = "model.categorize", actor = %session.user_id, resource = %id, category = %category_id, diff_id, "Admin accepted category suggestion");
+            Json(json!({ "category_id": category_id })).into_response()
+        }
+        Err(e) => error::internal_error("categorize:accept", e),
+    }
+}
+
+/// DELETE /api/admin/models/:id/category-suggestion — Dismiss the suggestion.
+async fn dismiss_suggestion(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> Response {
+    match sqlx::query(
+        "UPDATE models SET suggested_category_id = NULL, category_suggestion_reason = NULL \
+         WHERE id = ? AND suggested_category_id IS NOT NULL",
+    )
+    .bind(&id)
+    .execute(&state.db.pool)
+    .await
+    {
+        Ok(r) if r.rows_affected() == 0 => not_found("No category suggestion for this model"),
+        Ok(_) => {
+            info!(target: "audit", action = "model.category_suggestion_dismiss", actor = %session.user_id, resource = %id, "Admin dismissed category suggestion");
+            Json(json!({ "status": "dismissed" })).into_response()
+        }
+        Err(e) => error::internal_error("categorize:dismiss", e),
+    }
+}
+
+async fn clear_suggestion(pool: &SqlitePool, model_id: &str) {
+    let _ = sqlx::query(
+        "UPDATE models SET suggested_category_id = NULL, category_suggestion_reason = NULL \
+         WHERE id = ?",
+    )
+    .bind(model_id)
+    .execute(pool)
+    .await;
+}
+
+fn not_found(msg: &str) -> Response {
+    (StatusCode::NOT_FOUND, Json(json!({ "error": msg }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(hf_repo: &str) -> Signals<'_> {
+        Signals {
+            hf_repo,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn names_decide_when_nothing_else_does() {
+        let kind = |repo| classify(&named(repo)).map(|(k, _)| k);
+        assert_eq!(
+            kind("Qwen/Qwen2.5-Coder-7B-Instruct-GGUF"),
+            Some(ModelKind::Code)
+        );
+        assert_eq!(kind("BAAI/bge-reranker-v2-m3"), Some(ModelKind::Rerank));
+        assert_eq!(
+            kind("nomic-ai/nomic-embed-text-v1.5-GGUF"),
+            Some(ModelKind::Embedding)
+        );
+        assert_eq!(kind("Qwen/Qwen2-VL-7B-Instruct"), Some(ModelKind::Vision));
+        assert_eq!(
+            kind("deepseek-ai/DeepSeek-R1-Distill-Qwen-7B"),
+            Some(ModelKind::Reasoning)
+        );
+        assert_eq!(kind("google/gemma-3-4b-it"), Some(ModelKind::Chat));
+        assert_eq!(kind("someone/mystery-model"), None);
+    }
+
+    #[test]
+    fn stronger_signals_win_over_the_name() {
+        let mut s = named("org/chat-model");
+        s.architecture = Some("nomic-bert");
+        assert_eq!(classify(&s).unwrap().0, ModelKind::Embedding);
+
+        let mut s = named("org/chat-model");
+        s.pipeline_tag = Some("image-text-to-text");
+        let (kind, reason) = classify(&s).unwrap();
+        assert_eq!(kind, ModelKind::Vision);
+        assert_eq!(reason, "pipeline tag image-text-to-text");
+
+        let mut s = named("org/chat-model");
+        s.requests = 50;
+        assert_eq!(classify(&s).unwrap().0, ModelKind::Embedding);
+        s.requests_with_output = 1;
+        assert_eq!(classify(&s).unwrap().0, ModelKind::Chat);
+    }
+
+    #[test]
+    fn little_usage_says_nothing() {
+        let mut s = named("org/mystery");
+        s.requests = MIN_USAGE_REQUESTS - 1;
+        assert!(classify(&s).is_none());
+        s.pipeline_tag = Some("text-generation");
+        assert_eq!(classify(&s).unwrap().0, ModelKind::Chat);
+    }
+
+    #[test]
+    fn mode_defaults_to_suggest() {
+        assert_eq!(Mode::from_config("assign"), Mode::Assign);
+        assert_eq!(Mode::from_config("OFF"), Mode::Off);
+        assert_eq!(Mode::from_config("bogus"), Mode::Suggest);
+    }
+}
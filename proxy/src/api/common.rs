@@ -2,21 +2,161 @@
 //! code duplication. Only genuinely repeated patterns live here — we do NOT
 //! over-abstract.
 
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
-use axum::http::StatusCode;
-use axum::response::IntoResponse;
+use axum::body::Body;
+use axum::http::{HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
 use axum::Json;
+use bytes::Bytes;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
-use tracing::error;
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use super::error;
-use crate::db::models::{Model, ModelCategory};
+use crate::config::AppConfig;
+use crate::db::models::{LaunchProfile, Model, ModelCategory};
+use crate::docker::limits::ResourceLimits;
 use crate::docker::runtime_overrides::ModelRuntimeOverrides;
-use crate::metrics::ContainerStatus;
+use crate::docker::{ContainerVram, StopMode};
+use crate::events::AppEvent;
+use crate::metrics::{ContainerStatus, HostMemoryInfo};
+use crate::scheduler::gate::QueueTicket;
+use crate::scheduler::Scheduler;
 use crate::AppState;
 
+// ---------------------------------------------------------------------------
+// Queue feedback (used by the OpenAI and Anthropic proxies)
+// ---------------------------------------------------------------------------
+
+/// Tell the client its request had to queue: `x-queue-position` is where it
+/// joined the queue and `x-estimated-wait-ms` the wait predicted at that
+/// point. Requests that got a slot straight away get neither header.
+pub fn add_queue_headers(
+    response: &mut Response,
+    queue_position: Option<usize>,
+    estimated_wait_ms: Option<i64>,
+) {
+    let Some(position) = queue_position else {
+        return;
+    };
+    let headers = response.headers_mut();
+    headers.insert("x-queue-position", HeaderValue::from(position));
+    if let Some(wait_ms) = estimated_wait_ms {
+        headers.insert("x-estimated-wait-ms", HeaderValue::from(wait_ms));
+    }
+}
+
+/// Queue feedback on a 429: the place a retry would take in the model's
+/// queue right now, and the wait predicted for it.
+pub async fn add_busy_headers(response: &mut Response, scheduler: &Scheduler, model_id: &str) {
+    let position = scheduler.queue().depth(model_id).await + 1;
+    let wait_ms = scheduler.estimate_wait_ms(model_id).await;
+    add_queue_headers(response, Some(position), wait_ms);
+}
+
+/// How often a queued streaming request is told where it stands.
+const QUEUE_STATUS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Serve a streaming request so the client hears about its place in the
+/// queue while it waits. `serve` produces the response and reports on
+/// `queued` if the request has to wait for a slot.
+///
+/// A request that never queues gets its response unchanged. One that does
+/// is answered at once: a 200 SSE stream with the queue headers, an SSE
+/// comment every [`QUEUE_STATUS_INTERVAL`] while it waits
+/// (`: queue {"position":2,"estimated_wait_ms":8000}`, which SSE clients
+/// skip), then the body of the real response. The status has been sent by
+/// then, so an error response becomes a final SSE event carrying its JSON
+/// body, named `error_event` if the API names its events.
+pub async fn with_queue_preamble<F>(
+    scheduler: &Scheduler,
+    queued: oneshot::Receiver<QueueTicket>,
+    serve: F,
+    error_event: Option<&'static str>,
+) -> Response
+where
+    F: Future<Output = Response> + Send + 'static,
+{
+    let mut serve = Box::pin(serve);
+    let ticket = tokio::select! {
+        response = &mut serve => return response,
+        Ok(ticket) = queued => ticket,
+    };
+    let scheduler = scheduler.clone();
+    let status = scheduler
+        .queue_status(&ticket.model_id, &ticket.request_id)
+        .await;
+
+    let (tx, rx) = mpsc::channel::<Result<Bytes, axum::Error>>(32);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(QUEUE_STATUS_INTERVAL);
+        let response = loop {
+            tokio::select! {
+                response = &mut serve => break response,
+                // The client went away: dropping `serve` leaves the queue
+                _ = tx.closed() => return,
+                _ = ticker.tick() => {
+                    // Nothing to report once the request has its slot
+                    let Some((position, wait_ms)) = scheduler
+                        .queue_status(&ticket.model_id, &ticket.request_id)
+                        .await
+                    else {
+                        continue;
+                    };
+                    let comment = format!(
+                        ": queue {}\n\n",
+                        serde_json::json!({ "position": position, "estimated_wait_ms": wait_ms })
+                    );
+                    if tx.send(Ok(Bytes::from(comment))).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        };
+
+        if response.status().is_success() {
+            let mut body = response.into_body().into_data_stream();
+            while let Some(chunk) = body.next().await {
+                if tx.send(chunk).await.is_err() {
+                    return;
+                }
+            }
+        } else {
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap_or_default();
+            let mut event = match error_event {
+                Some(name) => format!("event: {name}\ndata: "),
+                None => "data: ".to_string(),
+            };
+            event.push_str(&String::from_utf8_lossy(&body));
+            event.push_str("\n\n");
+            let _ = tx.send(Ok(Bytes::from(event))).await;
+        }
+    });
+
+    let mut response = Response::builder()
+        .status(StatusCode::OK)
+        .header("content-type", "text/event-stream")
+        .header("cache-control", "no-cache")
+        .body(Body::from_stream(
+            tokio_stream::wrappers::ReceiverStream::new(rx),
+        ))
+        .unwrap();
+    add_queue_headers(
+        &mut response,
+        status.map(|(position, _)| position),
+        status.and_then(|(_, wait_ms)| wait_ms),
+    );
+    response
+}
+
 // ---------------------------------------------------------------------------
 // Period → SQL interval mapping (used by usage & timeline endpoints)
 // ---------------------------------------------------------------------------
@@ -52,7 +192,7 @@ pub fn period_to_interval_and_bucket(period: &str) -> (&'static str, &'static st
 /// Fetch all model categories. Used by both admin and user list endpoints.
 pub async fn fetch_all_categories(pool: &SqlitePool) -> impl IntoResponse {
     match sqlx::query_as::<_, ModelCategory>(
-        "SELECT id, name, description, preferred_model_id, created_at FROM model_categories",
+        "SELECT id, name, description, preferred_model_id, created_at, autoload, auto_kind, log_level, max_concurrent, max_queue_depth, queue_timeout_secs, default_parallel FROM model_categories",
     )
     .fetch_all(pool)
     .await
@@ -65,7 +205,7 @@ pub async fn fetch_all_categories(pool: &SqlitePool) -> impl IntoResponse {
 /// Fetch all registered models. Used by both admin and user list endpoints.
 pub async fn fetch_all_models(pool: &SqlitePool) -> impl IntoResponse {
     match sqlx::query_as::<_, Model>(
-        "SELECT id, hf_repo, filename, size_bytes, category_id, loaded, backend_port, backend_type, last_used_at, created_at, context_length, n_layers, n_heads, n_kv_heads, embedding_length, key_length, value_length, sliding_window, kv_bytes_per_token_global, kv_bytes_per_token_swa, runtime_overrides FROM models",
+        "SELECT id, hf_repo, filename, size_bytes, category_id, loaded, backend_port, backend_type, last_used_at, created_at, context_length, n_layers, n_heads, n_kv_heads, embedding_length, key_length, value_length, sliding_window, kv_bytes_per_token_global, kv_bytes_per_token_swa, runtime_overrides, draining, sha256, idle_unload_minutes, autoload, embedding, architecture, pipeline_tag, suggested_category_id, category_suggestion_reason, default_params, context_policy, capabilities, downloaded, evicted_at FROM models",
     )
     .fetch_all(pool)
     .await
@@ -80,10 +220,12 @@ pub async fn fetch_all_models(pool: &SqlitePool) -> impl IntoResponse {
 // ---------------------------------------------------------------------------
 
 /// Extract container status info from a list of Docker container summaries.
-/// Merges per-container VRAM data from the provided map.
+/// Merges per-container VRAM data from the provided map and each model's
+/// latest warm-up.
 pub fn extract_container_statuses(
     containers: Vec<bollard::models::ContainerSummary>,
-    vram_map: &std::collections::HashMap<String, u64>,
+    vram_map: &std::collections::HashMap<String, ContainerVram>,
+    warmups: &super::warmup::Warmups,
 ) -> Vec<ContainerStatus> {
     containers
         .into_iter()
@@ -98,13 +240,15 @@ pub fn extract_container_statuses(
                 .cloned()
                 .unwrap_or_else(|| "llamacpp".to_string());
             let healthy = c.state == Some(bollard::models::ContainerSummaryStateEnum::RUNNING);
-            let vram_used_mb = vram_map.get(&model_id).copied();
+            let vram = vram_map.get(&model_id);
             ContainerStatus {
+                vram_used_mb: vram.map(|v| v.total_mb),
+                vram_by_device_mb: vram.map(|v| v.per_device_mb.clone()).unwrap_or_default(),
+                warmup: warmups.status(&model_id),
                 model_id,
                 backend_type,
                 healthy,
                 state: c.state.map(|s| format!("{:?}", s).to_lowercase()),
-                vram_used_mb,
             }
         })
         .collect()
@@ -120,7 +264,101 @@ pub struct StartContainerParams {
     pub backend_type: Option<String>,
     pub gpu_type: Option<String>,
     pub gpu_layers: Option<u32>,
+    pub context_size: Option<u32>,
     pub parallel: Option<u32>,
+    /// GPU indices to pin the container to; `None` uses every GPU.
+    pub devices: Option<Vec<u32>>,
+    /// Limits left out fall back to the launch profile's.
+    pub limits: ResourceLimits,
+}
+
+/// Combine the `device_index` / `device_list` request fields into the GPU
+/// indices to pin a container to. Giving both, an empty list or a repeated
+/// index is an error.
+pub fn device_selection(
+    device_index: Option<u32>,
+    device_list: Option<Vec<u32>>,
+) -> Result<Option<Vec<u32>>, &'static str> {
+    match (device_index, device_list) {
+        (Some(_), Some(_)) => Err("Give either device_index or device_list, not both"),
+        (Some(index), None) => Ok(Some(vec![index])),
+        (None, Some(list)) => {
+            if list.is_empty() {
+                return Err("device_list must not be empty");
+            }
+            let mut seen = std::collections::HashSet::new();
+            if !list.iter().all(|d| seen.insert(*d)) {
+                return Err("device_list contains a GPU more than once");
+            }
+            Ok(Some(list))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// GPU pinning of running backends from `container_secrets`: model_id →
+/// GPU indices. Containers that may use every GPU are left out.
+pub async fn container_devices(pool: &SqlitePool) -> std::collections::HashMap<String, Vec<u32>> {
+    let rows: Vec<(String, String)> = match sqlx::query_as(
+        "SELECT model_id, devices FROM container_secrets WHERE devices IS NOT NULL",
+    )
+    .fetch_all(pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            warn!(error = %e, "Failed to load container GPU assignments");
+            return Default::default();
+        }
+    };
+    rows.into_iter()
+        .filter_map(|(model_id, devices)| Some((model_id, serde_json::from_str(&devices).ok()?)))
+        .collect()
+}
+
+/// Launch parameters saved on every container start (`models.launch_params`)
+/// and reused when the model is autoloaded or restarted by the watchdog.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LaunchParams {
+    pub gpu_type: Option<String>,
+    pub gpu_layers: Option<u32>,
+    #[serde(default)]
+    pub context_size: Option<u32>,
+    pub parallel: Option<u32>,
+    #[serde(default)]
+    pub devices: Option<Vec<u32>>,
+    #[serde(default)]
+    pub limits: ResourceLimits,
+}
+
+impl LaunchParams {
+    /// Start parameters that launch `model_id` the way `launch_params` (as
+    /// stored) last did. Missing or unreadable parameters fall back to the
+    /// model's launch profile.
+    pub fn saved_start(model_id: &str, launch_params: Option<&str>) -> StartContainerParams {
+        let launch: LaunchParams = launch_params
+            .and_then(|p| serde_json::from_str(p).ok())
+            .unwrap_or_default();
+        StartContainerParams {
+            model_id: model_id.to_string(),
+            backend_type: None,
+            gpu_type: launch.gpu_type,
+            gpu_layers: launch.gpu_layers,
+            context_size: launch.context_size,
+            parallel: launch.parallel,
+            devices: launch.devices,
+            limits: launch.limits,
+        }
+    }
+}
+
+/// The resource limits saved in a launch profile.
+pub fn profile_limits(profile: &LaunchProfile) -> ResourceLimits {
+    ResourceLimits {
+        cpu_limit: profile.cpu_limit,
+        memory_limit_mb: profile.memory_limit_mb,
+        pids_limit: profile.pids_limit,
+    }
 }
 
 /// Row from `models` needed by the start-container flow.
@@ -129,24 +367,129 @@ pub struct ModelStartRow {
     pub id: String,
     pub hf_repo: String,
     pub filename: Option<String>,
+    pub size_bytes: Option<i64>,
     pub backend_type: String,
     pub context_length: Option<i64>,
+    pub embedding: bool,
     /// JSON blob; deserialized into [`ModelRuntimeOverrides`] in the start path.
     /// Stored as text per the `runtime_overrides` column.
     pub runtime_overrides: String,
 }
 
+/// Whether inference requests should be turned away because host memory
+/// pressure is critical (`MEMORY_PRESSURE_ADMISSION`).
+pub fn admission_paused_for_memory(config: &AppConfig) -> bool {
+    config.memory_pressure_admission
+        && HostMemoryInfo::read(config.memory_critical_percent).is_some_and(|m| m.critical)
+}
+
 /// Core container-start logic shared between admin and reservation handlers.
 ///
-/// On success, returns `Ok((container_name, backend_type_used))`.
+/// Fields the request leaves out fall back to the model's launch profile
+/// (`model_launch_profiles`), then to the built-in defaults. `parallel`
+/// falls back to the category's `default_parallel` before the default.
+///
+/// Once the container is healthy its capabilities are probed in the
+/// background (see `api::capabilities`). Publishes
+/// [`AppEvent::ModelLoaded`].
+///
+/// On success, returns `Ok((container_name, base_url))`.
 /// On failure, returns an `Err(axum::response::Response)` ready to send.
 pub async fn start_container_core(
     state: &Arc<AppState>,
     params: &StartContainerParams,
 ) -> Result<(String, String), axum::response::Response> {
+    let launched = launch_container(state, params, None).await?;
+
+    // Post-start bookkeeping: persist secrets, register gate, mark loaded
+    if let Err(e) = launched.record(&state.db.pool).await {
+        error!(model = %launched.model_id, error = %e, "Failed to record started container");
+    }
+    state
+        .scheduler
+        .gate()
+        .register(&launched.model_id, launched.parallel_slots)
+        .await;
+    super::capabilities::spawn_probe(state.clone(), launched.model_id.clone());
+    state.events.publish(launched.loaded_event());
+
+    let url = state
+        .docker
+        .backend_url(&launched.container_name, &launched.backend_type);
+    Ok((launched.container_name, url))
+}
+
+/// A backend container that has been started but not yet recorded as the
+/// one serving its model.
+#[derive(Debug)]
+pub struct LaunchedContainer {
+    pub model_id: String,
+    pub container_name: String,
+    pub backend_type: String,
+    pub uid: u32,
+    pub api_key: String,
+    pub parallel_slots: u32,
+    pub devices: Option<Vec<u32>>,
+    /// Serialized [`LaunchParams`], kept for autoload and replace.
+    pub launch_params: Option<String>,
+}
+
+impl LaunchedContainer {
+    /// Make this the container serving its model: store its name and
+    /// secrets, and mark the model loaded with the backend and launch
+    /// parameters actually used. One transaction, so requests never pair
+    /// one container's URL with another's API key.
+    pub async fn record(&self, pool: &SqlitePool) -> Result<(), sqlx::Error> {
+        let mut tx = pool.begin().await?;
+        sqlx::query(
+            "INSERT OR REPLACE INTO container_secrets (model_id, container_uid, api_key, parallel_slots, devices, container_name) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&self.model_id)
+        .bind(self.uid as i64)
+        .bind(&self.api_key)
+        .bind(self.parallel_slots as i64)
+        .bind(
+            self.devices
+                .as_ref()
+                .and_then(|d| serde_json::to_string(d).ok()),
+        )
+        .bind(&self.container_name)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "UPDATE models SET loaded = 1, backend_type = ?, launch_params = ? WHERE id = ?",
+        )
+        .bind(&self.backend_type)
+        .bind(&self.launch_params)
+        .bind(&self.model_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+
+    /// The event announcing that this container now serves its model.
+    pub fn loaded_event(&self) -> AppEvent {
+        AppEvent::ModelLoaded {
+            model_id: self.model_id.clone(),
+            container: self.container_name.clone(),
+            backend_type: self.backend_type.clone(),
+        }
+    }
+}
+
+/// Validate the launch parameters and start a backend container, named
+/// `container_name` or the model's default name. Nothing is recorded; see
+/// [`LaunchedContainer::record`]. Evicted models are refused, and with
+/// `MODEL_STORE_S3` missing model files are fetched first (see
+/// `crate::storage`).
+pub async fn launch_container(
+    state: &Arc<AppState>,
+    params: &StartContainerParams,
+    container_name: Option<String>,
+) -> Result<LaunchedContainer, axum::response::Response> {
     // Look up the model
     let model: Option<ModelStartRow> = sqlx::query_as(
-        "SELECT id, hf_repo, filename, backend_type, context_length, runtime_overrides FROM models WHERE id = ?",
+        "SELECT id, hf_repo, filename, size_bytes, backend_type, context_length, embedding, runtime_overrides FROM models WHERE id = ?",
     )
     .bind(&params.model_id)
     .fetch_optional(&state.db.pool)
@@ -157,8 +500,10 @@ pub async fn start_container_core(
         id: model_id,
         hf_repo,
         filename,
+        size_bytes,
         backend_type: db_backend_type,
         context_length: db_context_length,
+        embedding,
         runtime_overrides: runtime_overrides_json,
     } = model.ok_or_else(|| {
         (
@@ -168,8 +513,38 @@ pub async fn start_container_core(
             .into_response()
     })?;
 
-    let context_size = match db_context_length {
-        Some(v) => v as u32,
+    let profile: LaunchProfile = sqlx::query_as(
+        "SELECT backend_type, gpu_type, gpu_layers, context_size, parallel, cpu_limit, memory_limit_mb, pids_limit FROM model_launch_profiles WHERE model_id = ?",
+    )
+    .bind(&model_id)
+    .fetch_optional(&state.db.pool)
+    .await
+    .map_err(|e| error::internal_error("start_container:profile", e))?
+    .unwrap_or_default();
+    let limits = params.limits.or(profile_limits(&profile));
+    let gpu_type = params.gpu_type.clone().or(profile.gpu_type);
+    let gpu_layers = params.gpu_layers.or(profile.gpu_layers);
+    // The category's default applies to parallel slots only
+    let category_parallel: Option<i64> = sqlx::query_scalar(
+        "SELECT c.default_parallel FROM models m \
+         JOIN model_categories c ON c.id = m.category_id WHERE m.id = ?",
+    )
+    .bind(&model_id)
+    .fetch_optional(&state.db.pool)
+    .await
+    .map_err(|e| error::internal_error("start_container:category", e))?
+    .flatten();
+    let parallel = params
+        .parallel
+        .or(profile.parallel)
+        .or(category_parallel.and_then(|n| u32::try_from(n).ok()));
+
+    let context_size = match params
+        .context_size
+        .or(profile.context_size)
+        .or(db_context_length.map(|v| v as u32))
+    {
+        Some(v) => v,
         None => {
             return Err((
                 StatusCode::BAD_REQUEST,
@@ -179,7 +554,77 @@ pub async fn start_container_core(
         }
     };
 
-    let backend_type = params.backend_type.as_deref().unwrap_or(&db_backend_type);
+    let backend_type = params
+        .backend_type
+        .as_deref()
+        .or(profile.backend_type.as_deref())
+        .unwrap_or(&db_backend_type);
+
+    // Vulkan and vLLM images are amd64-only; refuse before allocating anything.
+    let arch = state.docker.arch;
+    let wants_vulkan = matches!(
+        crate::docker::llamacpp::GpuType::from_str(gpu_type.as_deref().unwrap_or("none")),
+        crate::docker::llamacpp::GpuType::Vulkan
+    );
+    let missing_image = if backend_type == "vllm" {
+        (!arch.has_vllm_image()).then_some("vLLM")
+    } else {
+        (wants_vulkan && !arch.has_vulkan_image()).then_some("llama.cpp Vulkan")
+    };
+    if let Some(image) = missing_image {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": format!("No {image} image for {} hosts — use the llama.cpp CPU backend", arch.as_str()) })),
+        )
+            .into_response());
+    }
+
+    if let Some(devices) = &params.devices {
+        let is_gpu_backend = backend_type == "vllm" || wants_vulkan;
+        if !is_gpu_backend {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "GPU selection needs a GPU backend (gpu_type vulkan or the vllm backend)" })),
+            )
+                .into_response());
+        }
+        let gpu_count = crate::docker::DockerManager::gpu_all_info().await.len();
+        if let Some(bad) = devices
+            .iter()
+            .find(|&&d| gpu_count > 0 && d as usize >= gpu_count)
+        {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": format!("GPU {bad} not found ({gpu_count} detected)") })),
+            )
+                .into_response());
+        }
+    }
+    let devices = params.devices.clone().unwrap_or_default();
+
+    // Make sure the files are on disk (fetching them from the S3 model
+    // store if needed); the guard keeps the directory from being evicted
+    // while the container starts
+    let _files_guard = match state
+        .storage
+        .prepare_launch(&state.db, &model_id, &hf_repo.replace('/', "--"))
+        .await
+    {
+        Ok(guard) => guard,
+        Err(crate::storage::PrepareError::Evicted) => {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(serde_json::json!({ "error": "Model files were evicted from disk after going unused — download the repo again to restore them" })),
+            )
+                .into_response());
+        }
+        Err(crate::storage::PrepareError::Failed(e)) => {
+            return Err(error::internal_error(
+                "start_container:model_files",
+                format!("{e:#}"),
+            ));
+        }
+    };
 
     // Allocate a collision-free UID and generate a per-container API key
     let uid = state
@@ -203,7 +648,28 @@ pub async fn start_container_core(
                 }
             };
 
-            let parallel = params.parallel.unwrap_or(1).max(1);
+            // CPU and GTT inference load the weights into host RAM
+            if state.config.host_memory_ceiling_percent > 0 {
+                let needed_mb = size_bytes.unwrap_or(0).max(0) as u64 / (1024 * 1024);
+                let ceiling = state.config.host_memory_ceiling_percent;
+                if let Some(memory) = HostMemoryInfo::read(state.config.memory_critical_percent)
+                    .filter(|m| m.exceeds_ceiling(needed_mb, ceiling))
+                {
+                    warn!(model = %model_id, needed_mb, used_mb = memory.used_mb, total_mb = memory.total_mb, "Refusing container start above host memory ceiling");
+                    return Err((
+                        StatusCode::CONFLICT,
+                        Json(serde_json::json!({
+                            "error": format!(
+                                "Starting this model needs about {needed_mb} MB, which would take host memory use past the {ceiling}% ceiling ({} of {} MB in use)",
+                                memory.used_mb, memory.total_mb
+                            ),
+                        })),
+                    )
+                        .into_response());
+                }
+            }
+
+            let parallel = parallel.unwrap_or(1).max(1);
             // A bad JSON blob in the DB shouldn't keep the model from starting —
             // fall back to defaults (i.e. no overrides) and carry on.
             let overrides = serde_json::from_str::<ModelRuntimeOverrides>(&runtime_overrides_json)
@@ -212,17 +678,40 @@ pub async fn start_container_core(
                 model_id: model_id.clone(),
                 gguf_path,
                 gpu_type: crate::docker::llamacpp::GpuType::from_str(
-                    params.gpu_type.as_deref().unwrap_or("none"),
+                    gpu_type.as_deref().unwrap_or("none"),
                 ),
-                gpu_layers: params.gpu_layers.unwrap_or(99),
+                gpu_layers: gpu_layers.unwrap_or(99),
                 context_size,
                 parallel,
+                embeddings: embedding,
+                devices: devices.clone(),
                 extra_args: overrides.to_cli_args(),
                 uid,
                 api_key: api_key.clone(),
+                container_name: container_name.clone(),
+                limits,
             };
             state.docker.start_llamacpp(&llamacpp_config).await
         }
+        "vllm" => {
+            // vLLM loads the whole repo directory (config, tokenizer, safetensors
+            // shards); llama-server runtime overrides don't apply.
+            let vllm_config = crate::docker::vllm::VllmConfig {
+                model_id: model_id.clone(),
+                model_dir: hf_repo.replace('/', "--"),
+                context_size,
+                parallel: parallel.unwrap_or(1).max(1),
+                // Shard across every pinned GPU
+                tensor_parallel_size: (devices.len() as u32).max(1),
+                devices: devices.clone(),
+                uid,
+                api_key: api_key.clone(),
+                container_name: container_name.clone(),
+                limits,
+                ..Default::default()
+            };
+            state.docker.start_vllm(&vllm_config).await
+        }
         other => {
             return Err((
                 StatusCode::BAD_REQUEST,
@@ -234,38 +723,28 @@ pub async fn start_container_core(
 
     match container_result {
         Ok(container_name) => {
-            // Post-start bookkeeping: persist secrets, register gate, mark loaded
-            let parallel_slots = params.parallel.unwrap_or(1).max(1);
-            if let Err(e) = sqlx::query(
-                "INSERT OR REPLACE INTO container_secrets (model_id, container_uid, api_key, parallel_slots) VALUES (?, ?, ?, ?)",
-            )
-            .bind(&model_id)
-            .bind(uid as i64)
-            .bind(&api_key)
-            .bind(parallel_slots as i64)
-            .execute(&state.db.pool)
-            .await
-            {
-                error!(model = %model_id, error = %e, "Failed to persist container secrets");
-            }
-
-            state
-                .scheduler
-                .gate()
-                .register(&model_id, parallel_slots)
-                .await;
-
-            let _ = sqlx::query("UPDATE models SET loaded = 1 WHERE id = ?")
-                .bind(&model_id)
-                .execute(&state.db.pool)
-                .await;
-
-            let url = state
-                .docker
-                .backend_base_url(&model_id, backend_type)
-                .to_string();
-
-            Ok((container_name, url))
+            // Record the backend actually used so routing and stop target
+            // the right container when the request overrode the stored type.
+            // Launch parameters are kept for autoload.
+            let launch_params = serde_json::to_string(&LaunchParams {
+                gpu_type,
+                gpu_layers,
+                context_size: params.context_size.or(profile.context_size),
+                parallel,
+                devices: params.devices.clone(),
+                limits,
+            })
+            .ok();
+            Ok(LaunchedContainer {
+                model_id,
+                container_name,
+                backend_type: backend_type.to_string(),
+                uid,
+                api_key,
+                parallel_slots: parallel.unwrap_or(1).max(1),
+                devices: params.devices.clone(),
+                launch_params,
+            })
         }
         Err(e) => {
             error!(model = %model_id, backend = %backend_type, error = ?e, "Failed to start container");
@@ -275,23 +754,362 @@ pub async fn start_container_core(
 }
 
 // ---------------------------------------------------------------------------
-// Container lifecycle: post-stop cleanup
+// Container lifecycle: stop
 // ---------------------------------------------------------------------------
 
-/// Shared cleanup after stopping a container: unregister gate, delete secrets,
-/// mark model as unloaded.
+/// Body shared by the admin and reservation container-stop endpoints.
+#[derive(Debug, Deserialize)]
+pub struct StopContainerRequest {
+    pub model_id: String,
+    /// Stop immediately, cutting off in-flight requests.
+    #[serde(default)]
+    pub force: bool,
+    /// Override `GRACEFUL_STOP_TIMEOUT_SECS` for this stop.
+    pub timeout_secs: Option<u64>,
+}
+
+impl StopContainerRequest {
+    pub fn mode(&self) -> StopMode {
+        StopMode::from_force(self.force)
+    }
+
+    pub fn drain_timeout(&self, config: &AppConfig) -> Duration {
+        Duration::from_secs(
+            self.timeout_secs
+                .unwrap_or(config.graceful_stop_timeout_secs),
+        )
+    }
+}
+
+/// `?dry_run=true` on a destructive admin endpoint: report what would be
+/// affected without changing anything.
+#[derive(Debug, Deserialize, Default)]
+pub struct DryRunQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Result of [`stop_container_core`].
+#[derive(Debug)]
+pub struct StopOutcome {
+    pub mode: StopMode,
+    /// True if no requests were in flight when the container was stopped.
+    pub drained: bool,
+    /// Requests still in flight when the container was stopped.
+    pub abandoned_in_flight: u32,
+}
+
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Stop a backend container and run [`post_stop_cleanup`].
+///
+/// Graceful mode marks the model draining so no new requests are routed to
+/// it, waits up to `drain_timeout` for in-flight generations to finish, then
+/// stops the container. Force mode stops it straight away.
+///
+/// If the stop fails, a drain flag set here is cleared again so the model
+/// keeps serving.
+pub async fn stop_container_core(
+    state: &Arc<AppState>,
+    model_id: &str,
+    backend_type: &str,
+    mode: StopMode,
+    drain_timeout: Duration,
+) -> anyhow::Result<StopOutcome> {
+    let mut marked_draining = false;
+    if mode == StopMode::Graceful {
+        marked_draining =
+            sqlx::query("UPDATE models SET draining = 1 WHERE id = ? AND draining = 0")
+                .bind(model_id)
+                .execute(&state.db.pool)
+                .await?
+                .rows_affected()
+                > 0;
+    }
+
+    let mut in_flight = gate_in_flight(state, model_id).await;
+    if mode == StopMode::Graceful {
+        let deadline = tokio::time::Instant::now() + drain_timeout;
+        while in_flight > 0 && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+            in_flight = gate_in_flight(state, model_id).await;
+        }
+    }
+
+    let container = serving_container_name(&state.db.pool, model_id, backend_type).await;
+    if let Err(e) = state.docker.stop_named(&container, model_id, mode).await {
+        if marked_draining {
+            let _ = sqlx::query("UPDATE models SET draining = 0 WHERE id = ?")
+                .bind(model_id)
+                .execute(&state.db.pool)
+                .await;
+        }
+        return Err(e);
+    }
+
+    post_stop_cleanup(state, model_id).await;
+
+    Ok(StopOutcome {
+        mode,
+        drained: in_flight == 0,
+        abandoned_in_flight: in_flight,
+    })
+}
+
+/// Wait up to `timeout` for every in-flight and queued inference request to
+/// finish. Returns the `(in_flight, queued)` counts left when it gave up, or
+/// zeros once drained.
+pub async fn wait_for_idle(state: &Arc<AppState>, timeout: Duration) -> (u32, usize) {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut outstanding = state.scheduler.outstanding_requests().await;
+    while outstanding != (0, 0) && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+        outstanding = state.scheduler.outstanding_requests().await;
+    }
+    outstanding
+}
+
+/// Requests currently holding a gate slot for `model_id`.
+pub async fn gate_in_flight(state: &Arc<AppState>, model_id: &str) -> u32 {
+    state
+        .scheduler
+        .gate()
+        .status()
+        .await
+        .get(model_id)
+        .map_or(0, |g| g.in_flight)
+}
+
+/// Shared cleanup after stopping a container: unregister gate, drop pooled
+/// connections, delete secrets, mark model as unloaded.
 pub async fn post_stop_cleanup(state: &Arc<AppState>, model_id: &str) {
     state.scheduler.gate().unregister(model_id).await;
+    state.backends.remove(model_id).await;
     let _ = sqlx::query("DELETE FROM container_secrets WHERE model_id = ?")
         .bind(model_id)
         .execute(&state.db.pool)
         .await;
-    let _ = sqlx::query("UPDATE models SET loaded = 0 WHERE id = ?")
+    let _ = sqlx::query("UPDATE models SET loaded = 0, draining = 0 WHERE id = ?")
         .bind(model_id)
         .execute(&state.db.pool)
         .await;
 }
 
+/// Idle timeout for a model: its own `idle_unload_minutes` if set, else the
+/// global setting. `None` means never unload.
+fn idle_timeout(model_minutes: Option<i64>, default_minutes: u64) -> Option<Duration> {
+    let minutes = model_minutes.map_or(default_minutes, |m| m.max(0) as u64);
+    (minutes > 0).then(|| Duration::from_secs(minutes * 60))
+}
+
+/// Stop loaded models that have had no requests for longer than their idle
+/// timeout, freeing their GPU memory. Idle time comes from the scheduler's
+/// concurrency gate (last request start or finish); models with requests in
+/// flight are never idle. Nothing is unloaded during an active reservation,
+/// whose holder manages containers themselves.
+pub async fn unload_idle_models(state: &Arc<AppState>) {
+    if state.scheduler.active_reservation().await.is_some() {
+        return;
+    }
+    let default_minutes = state.scheduler.settings().await.idle_unload_minutes;
+    let idle = state.scheduler.gate().idle_durations().await;
+    if idle.is_empty() {
+        return;
+    }
+
+    let loaded = match sqlx::query_as::<_, (String, String, Option<i64>)>(
+        "SELECT id, backend_type, idle_unload_minutes FROM models WHERE loaded = 1",
+    )
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            warn!(error = %e, "Failed to list loaded models for idle unload");
+            return;
+        }
+    };
+
+    for (model_id, backend_type, model_minutes) in loaded {
+        let Some(timeout) = idle_timeout(model_minutes, default_minutes) else {
+            continue;
+        };
+        let Some(idle_for) = idle.get(&model_id).filter(|d| **d >= timeout) else {
+            continue;
+        };
+
+        // Graceful: a request that slips in now is drained, not cut off
+        match stop_container_core(
+            state,
+            &model_id,
+            &backend_type,
+            StopMode::Graceful,
+            Duration::from_secs(state.config.graceful_stop_timeout_secs),
+        )
+        .await
+        {
+            Ok(_) => {
+                info!(target: "audit", action = "container.idle_unload", actor = "system", resource = %model_id, backend = %backend_type, idle_secs = idle_for.as_secs(), "Stopped idle container");
+            }
+            Err(e) => {
+                warn!(model = %model_id, error = %e, "Failed to stop idle container");
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Container lifecycle: autoload
+// ---------------------------------------------------------------------------
+
+/// One lock per model, held while an autoload starts its container and
+/// waits for it, so concurrent requests for an unloaded model start it once
+/// without holding up cold starts of other models. An entry is removed once
+/// nothing holds or waits for its lock.
+#[derive(Clone, Default)]
+pub struct Autoloads(
+    Arc<std::sync::Mutex<std::collections::HashMap<String, Arc<tokio::sync::Mutex<()>>>>>,
+);
+
+/// Holds a model's autoload lock; see [`Autoloads::lock`].
+pub struct AutoloadGuard {
+    guard: Option<tokio::sync::OwnedMutexGuard<()>>,
+    autoloads: Autoloads,
+    model_id: String,
+}
+
+impl Autoloads {
+    /// Wait until no other autoload of `model_id` runs.
+    pub async fn lock(&self, model_id: &str) -> AutoloadGuard {
+        let lock = self
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(model_id.to_string())
+            .or_default()
+            .clone();
+        AutoloadGuard {
+            guard: Some(lock.lock_owned().await),
+            autoloads: self.clone(),
+            model_id: model_id.to_string(),
+        }
+    }
+
+    /// Drop `model_id`'s entry if the map holds the only reference to it.
+    fn release(&self, model_id: &str) {
+        let mut locks = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if locks
+            .get(model_id)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            locks.remove(model_id);
+        }
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+impl Drop for AutoloadGuard {
+    fn drop(&mut self) {
+        self.guard.take();
+        self.autoloads.release(&self.model_id);
+    }
+}
+
+const AUTOLOAD_HEALTH_POLL: Duration = Duration::from_secs(2);
+
+/// Why an autoload did not leave the model ready to serve.
+#[derive(Debug)]
+pub enum AutoloadError {
+    /// The container did not pass its health check within `AUTOLOAD_TIMEOUT_SECS`.
+    /// It keeps loading; a retry may succeed.
+    Timeout,
+    /// The container could not be started.
+    Failed(String),
+}
+
+/// Whether a request may start this model's container: autoload is enabled
+/// on the model or its category, and the model has a launch profile or saved
+/// launch parameters (it was started manually at least once).
+pub async fn autoload_enabled(pool: &SqlitePool, model_id: &str) -> bool {
+    sqlx::query_as::<_, (bool,)>(
+        r#"
+        SELECT (m.autoload = 1 OR COALESCE(c.autoload, 0) = 1)
+           AND (m.launch_params IS NOT NULL
+                OR EXISTS (SELECT 1 FROM model_launch_profiles p WHERE p.model_id = m.id))
+        FROM models m
+        LEFT JOIN model_categories c ON c.id = m.category_id
+        WHERE m.id = ?
+        "#,
+    )
+    .bind(model_id)
+    .fetch_optional(pool)
+    .await
+    .ok()
+    .flatten()
+    .is_some_and(|(enabled,)| enabled)
+}
+
+/// Start an unloaded model's container with its saved launch parameters and
+/// wait until the backend answers its health check.
+///
+/// Requests that arrive while another autoload of the same model runs wait
+/// for it, then find the model loaded and only wait for health. Autoloads of
+/// different models run side by side.
+pub async fn autoload_model(state: &Arc<AppState>, model_id: &str) -> Result<(), AutoloadError> {
+    let _guard = state.autoloads.lock(model_id).await;
+
+    let row: Option<(bool, String, Option<String>)> =
+        sqlx::query_as("SELECT loaded, backend_type, launch_params FROM models WHERE id = ?")
+            .bind(model_id)
+            .fetch_optional(&state.db.pool)
+            .await
+            .map_err(|e| AutoloadError::Failed(e.to_string()))?;
+    let Some((loaded, mut backend_type, launch_params)) = row else {
+        return Err(AutoloadError::Failed("model not found".to_string()));
+    };
+
+    if !loaded {
+        let params = LaunchParams::saved_start(model_id, launch_params.as_deref());
+        match start_container_core(state, &params).await {
+            Ok((container_name, _)) => {
+                info!(target: "audit", action = "container.autoload", actor = "system", resource = %model_id, container = %container_name, "Started container on demand");
+            }
+            Err(response) => {
+                warn!(model = %model_id, status = %response.status(), "Autoload failed to start container");
+                return Err(AutoloadError::Failed(format!(
+                    "container start failed ({})",
+                    response.status()
+                )));
+            }
+        }
+        backend_type = lookup_backend_type(&state.db.pool, model_id).await;
+    }
+    let container = serving_container_name(&state.db.pool, model_id, &backend_type).await;
+
+    let deadline =
+        tokio::time::Instant::now() + Duration::from_secs(state.config.autoload_timeout_secs);
+    loop {
+        if matches!(
+            state
+                .docker
+                .check_health_at(&container, &backend_type)
+                .await,
+            Ok(true)
+        ) {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            warn!(model = %model_id, "Autoloaded container not healthy before timeout");
+            return Err(AutoloadError::Timeout);
+        }
+        tokio::time::sleep(AUTOLOAD_HEALTH_POLL).await;
+    }
+}
+
 /// Look up backend_type for a model, defaulting to "llamacpp" on any failure.
 pub async fn lookup_backend_type(pool: &SqlitePool, model_id: &str) -> String {
     match sqlx::query_as::<_, (String,)>("SELECT backend_type FROM models WHERE id = ?")
@@ -304,9 +1122,30 @@ pub async fn lookup_backend_type(pool: &SqlitePool, model_id: &str) -> String {
     }
 }
 
+/// Name of the container serving `model_id`: the one recorded in
+/// `container_secrets` (a blue/green replace may have switched to the
+/// `-green` twin), else the default name.
+pub async fn serving_container_name(
+    pool: &SqlitePool,
+    model_id: &str,
+    backend_type: &str,
+) -> String {
+    sqlx::query_as::<_, (Option<String>,)>(
+        "SELECT container_name FROM container_secrets WHERE model_id = ?",
+    )
+    .bind(model_id)
+    .fetch_optional(pool)
+    .await
+    .ok()
+    .flatten()
+    .and_then(|(name,)| name)
+    .unwrap_or_else(|| crate::docker::container_name(backend_type, model_id))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::warmup::Warmups;
     use bollard::models::{ContainerSummary, ContainerSummaryStateEnum};
     use std::collections::HashMap;
 
@@ -397,6 +1236,99 @@ mod tests {
         );
     }
 
+    // -----------------------------------------------------------------------
+    // idle_timeout
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn idle_timeout_model_override_wins() {
+        assert_eq!(idle_timeout(None, 0), None);
+        assert_eq!(idle_timeout(None, 10), Some(Duration::from_secs(600)));
+        assert_eq!(idle_timeout(Some(5), 10), Some(Duration::from_secs(300)));
+        assert_eq!(idle_timeout(Some(0), 10), None);
+        assert_eq!(idle_timeout(Some(-3), 10), None);
+    }
+
+    // -----------------------------------------------------------------------
+    // autoload_enabled
+    // -----------------------------------------------------------------------
+
+    #[tokio::test]
+    async fn autoload_enabled_by_model_or_category_with_launch_params() {
+        let db = crate::db::Database::test_db().await;
+        sqlx::query("INSERT INTO model_categories (id, name, autoload) VALUES ('cat', 'cat', 1)")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        for (id, category, autoload, params) in [
+            ("own", None, true, Some("{}")),
+            ("via-cat", Some("cat"), false, Some("{}")),
+            ("never-started", None, true, None),
+            ("off", None, false, Some("{}")),
+            ("profile-only", None, true, None),
+        ] {
+            sqlx::query(
+                "INSERT INTO models (id, hf_repo, category_id, autoload, launch_params) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(id)
+            .bind(category)
+            .bind(autoload)
+            .bind(params)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        }
+
+        sqlx::query(
+            "INSERT INTO model_launch_profiles (model_id, parallel) VALUES ('profile-only', 2)",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        assert!(autoload_enabled(&db.pool, "own").await);
+        assert!(autoload_enabled(&db.pool, "profile-only").await);
+        assert!(autoload_enabled(&db.pool, "via-cat").await);
+        assert!(!autoload_enabled(&db.pool, "never-started").await);
+        assert!(!autoload_enabled(&db.pool, "off").await);
+        assert!(!autoload_enabled(&db.pool, "missing").await);
+    }
+
+    // -----------------------------------------------------------------------
+    // autoload_model
+    // -----------------------------------------------------------------------
+
+    #[tokio::test]
+    async fn autoloads_of_different_models_run_side_by_side() {
+        let state =
+            crate::test_harness::test_app_state_with(crate::test_harness::test_config()).await;
+
+        // A cold start of "slow" is in progress
+        let cold_start = state.autoloads.lock("slow").await;
+
+        // Another model's autoload runs at the same time (and fails: it's not in the DB)
+        let other =
+            tokio::time::timeout(Duration::from_secs(5), autoload_model(&state, "other")).await;
+        assert!(matches!(other, Ok(Err(AutoloadError::Failed(_)))));
+
+        // A second autoload of the same model waits for the first
+        let same = tokio::spawn({
+            let state = state.clone();
+            async move { autoload_model(&state, "slow").await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!same.is_finished());
+
+        drop(cold_start);
+        let same = tokio::time::timeout(Duration::from_secs(5), same)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(same, Err(AutoloadError::Failed(_))));
+        assert_eq!(state.autoloads.len(), 0);
+    }
+
     // -----------------------------------------------------------------------
     // extract_container_statuses
     // -----------------------------------------------------------------------
@@ -414,7 +1346,7 @@ mod tests {
 
     #[test]
     fn extract_container_statuses_empty_input() {
-        let result = extract_container_statuses(vec![], &HashMap::new());
+        let result = extract_container_statuses(vec![], &HashMap::new(), &Warmups::default());
         assert!(result.is_empty());
     }
 
@@ -432,9 +1364,15 @@ mod tests {
         )];
 
         let mut vram = HashMap::new();
-        vram.insert("my-model".to_string(), 4096u64);
+        vram.insert(
+            "my-model".to_string(),
+            ContainerVram {
+                total_mb: 4096,
+                ..Default::default()
+            },
+        );
 
-        let statuses = extract_container_statuses(containers, &vram);
+        let statuses = extract_container_statuses(containers, &vram, &Warmups::default());
         assert_eq!(statuses.len(), 1);
         assert_eq!(statuses[0].model_id, "my-model");
         assert_eq!(statuses[0].backend_type, "vllm");
@@ -450,7 +1388,7 @@ mod tests {
             Some(ContainerSummaryStateEnum::EXITED),
         )];
 
-        let statuses = extract_container_statuses(containers, &HashMap::new());
+        let statuses = extract_container_statuses(containers, &HashMap::new(), &Warmups::default());
         assert_eq!(statuses.len(), 1);
         assert_eq!(statuses[0].model_id, ""); // default
         assert_eq!(statuses[0].backend_type, "llamacpp"); // default
@@ -462,7 +1400,7 @@ mod tests {
     #[test]
     fn extract_container_statuses_no_state() {
         let containers = vec![make_container(None, None)];
-        let statuses = extract_container_statuses(containers, &HashMap::new());
+        let statuses = extract_container_statuses(containers, &HashMap::new(), &Warmups::default());
         assert!(!statuses[0].healthy);
         assert!(statuses[0].state.is_none());
     }
@@ -481,13 +1419,58 @@ mod tests {
 
         // vram_map has a different model
         let mut vram = HashMap::new();
-        vram.insert("model-b".to_string(), 1024u64);
+        vram.insert(
+            "model-b".to_string(),
+            ContainerVram {
+                total_mb: 1024,
+                ..Default::default()
+            },
+        );
 
-        let statuses = extract_container_statuses(containers, &vram);
+        let statuses = extract_container_statuses(containers, &vram, &Warmups::default());
         assert_eq!(statuses[0].model_id, "model-a");
         assert_eq!(statuses[0].vram_used_mb, None);
     }
 
+    #[test]
+    fn extract_container_statuses_splits_vram_over_pinned_gpus() {
+        let mut labels = HashMap::new();
+        labels.insert("sovereign-engine.model-id".to_string(), "big".to_string());
+        let containers = vec![make_container(
+            Some(labels),
+            Some(ContainerSummaryStateEnum::RUNNING),
+        )];
+
+        let mut vram = HashMap::new();
+        vram.insert(
+            "big".to_string(),
+            ContainerVram {
+                total_mb: 9001,
+                per_device_mb: crate::docker::attribute_vram(9001, &[2, 0]),
+            },
+        );
+
+        let statuses = extract_container_statuses(containers, &vram, &Warmups::default());
+        assert_eq!(statuses[0].vram_used_mb, Some(9001));
+        assert_eq!(
+            statuses[0].vram_by_device_mb.iter().collect::<Vec<_>>(),
+            [(&0, &4500), (&2, &4501)]
+        );
+    }
+
+    #[test]
+    fn device_selection_combines_request_fields() {
+        assert_eq!(device_selection(None, None), Ok(None));
+        assert_eq!(device_selection(Some(1), None), Ok(Some(vec![1])));
+        assert_eq!(
+            device_selection(None, Some(vec![0, 2])),
+            Ok(Some(vec![0, 2]))
+        );
+        assert!(device_selection(Some(0), Some(vec![1])).is_err());
+        assert!(device_selection(None, Some(vec![])).is_err());
+        assert!(device_selection(None, Some(vec![1, 1])).is_err());
+    }
+
     #[test]
     fn extract_container_statuses_multiple_containers() {
         let mut labels1 = HashMap::new();
@@ -500,10 +1483,53 @@ mod tests {
             make_container(Some(labels2), Some(ContainerSummaryStateEnum::PAUSED)),
         ];
 
-        let statuses = extract_container_statuses(containers, &HashMap::new());
+        let statuses = extract_container_statuses(containers, &HashMap::new(), &Warmups::default());
         assert_eq!(statuses.len(), 2);
         assert!(statuses[0].healthy);
         assert!(!statuses[1].healthy);
         assert_eq!(statuses[1].state.as_deref(), Some("paused"));
     }
+
+    // -----------------------------------------------------------------------
+    // with_queue_preamble
+    // -----------------------------------------------------------------------
+
+    #[tokio::test]
+    async fn preamble_only_when_queued() {
+        let scheduler = Scheduler::new();
+
+        // Never queued: the response passes through untouched
+        let (tx, rx) = oneshot::channel::<QueueTicket>();
+        let serve = async move {
+            drop(tx);
+            StatusCode::NOT_FOUND.into_response()
+        };
+        let response = with_queue_preamble(&scheduler, rx, serve, None).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Queued, then failed: a 200 stream ending in the error as an event
+        let (tx, rx) = oneshot::channel();
+        let serve = async move {
+            let _ = tx.send(QueueTicket {
+                model_id: "m1".to_string(),
+                request_id: "r1".to_string(),
+            });
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(serde_json::json!({ "error": { "code": "queue_timeout" } })),
+            )
+                .into_response()
+        };
+        let response = with_queue_preamble(&scheduler, rx, serve, Some("error")).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            body,
+            "event: error\ndata: {\"error\":{\"code\":\"queue_timeout\"}}\n\n"
+        );
+    }
 }
@@ -0,0 +1,189 @@
+//! Capability descriptor for client libraries.
+//!
+//! `GET /v1/compat` lists the `/v1` endpoints this deployment serves, how
+//! they stream, the largest request body accepted, and which optional
+//! features are on, so SDKs can check up front instead of probing with
+//! requests that fail. Endpoints and features behind a setting
+//! (`JWT_ACCESS_TOKENS`, `SANDBOX_ENABLED`) are only listed when enabled.
+
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+
+use crate::config::AppConfig;
+use crate::AppState;
+
+/// Largest request body accepted on any route.
+pub const MAX_BODY_BYTES: usize = 10 * 1024 * 1024;
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/compat", get(compat))
+        .with_state(state)
+}
+
+#[derive(Debug, Serialize)]
+struct Compat {
+    version: &'static str,
+    endpoints: Vec<Endpoint>,
+    streaming: Streaming,
+    max_body_bytes: usize,
+    features: Features,
+}
+
+#[derive(Debug, Serialize)]
+struct Endpoint {
+    method: &'static str,
+    path: &'static str,
+    /// Whether `"stream": true` is honored.
+    streaming: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Streaming {
+    /// Server-sent events, OpenAI `data:` chunks ending in `data: [DONE]`.
+    openai: bool,
+    /// Server-sent events in Anthropic's `message_start` … `message_stop` form.
+    anthropic: bool,
+    /// Seconds between keep-alives while waiting for the first chunk; `None` = off.
+    keepalive_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Features {
+    /// `response_format` (`json_object`, `json_schema`) is passed to the backend.
+    json_schema: bool,
+    /// `tools` and `tool_choice`, including Anthropic tool use.
+    tools: bool,
+    /// `X-Deterministic: strict` with a `seed`.
+    strict_determinism: bool,
+    /// Models may drop the oldest messages to fit their context.
+    context_truncation: bool,
+    /// Large prompts can be uploaded in chunks and passed as `prompt_id`.
+    prompt_uploads: bool,
+    embeddings: bool,
+    vector_stores: bool,
+    code_execution: bool,
+    jwt_access_tokens: bool,
+}
+
+const fn endpoint(method: &'static str, path: &'static str, streaming: bool) -> Endpoint {
+    Endpoint {
+        method,
+        path,
+        streaming,
+    }
+}
+
+/// Capabilities for a deployment running with `config`.
+fn descriptor(config: &AppConfig) -> Compat {
+    descriptor_with(
+        config.sandbox_enabled,
+        config.jwt_access_tokens,
+        config.sse_keepalive().map(|d| d.as_secs()),
+    )
+}
+
+/// Capabilities given the settings that switch optional parts on.
+fn descriptor_with(
+    sandbox_enabled: bool,
+    jwt_access_tokens: bool,
+    keepalive_secs: Option<u64>,
+) -> Compat {
+    let mut endpoints = vec![
+        endpoint("GET", "/v1/compat", false),
+        endpoint("GET", "/v1/models", false),
+        endpoint("POST", "/v1/chat/completions", true),
+        endpoint("POST", "/v1/completions", true),
+        endpoint("POST", "/v1/embeddings", false),
+        endpoint("POST", "/v1/messages", true),
+        endpoint("POST", "/v1/prompts", false),
+        endpoint("GET", "/v1/prompts/{id}", false),
+        endpoint("PATCH", "/v1/prompts/{id}", false),
+        endpoint("DELETE", "/v1/prompts/{id}", false),
+        endpoint("POST", "/v1/prompts/{id}/complete", false),
+        endpoint("GET", "/v1/vector_stores", false),
+        endpoint("POST", "/v1/vector_stores", false),
+        endpoint("GET", "/v1/vector_stores/{id}", false),
+        endpoint("DELETE", "/v1/vector_stores/{id}", false),
+        endpoint("GET", "/v1/vector_stores/{id}/documents", false),
+        endpoint("POST", "/v1/vector_stores/{id}/documents", false),
+        endpoint("DELETE", "/v1/vector_stores/{id}/documents/{doc_id}", false),
+        endpoint("POST", "/v1/vector_stores/{id}/query", false),
+    ];
+    if sandbox_enabled {
+        endpoints.push(endpoint("POST", "/v1/tools/execute", false));
+    }
+    if jwt_access_tokens {
+        endpoints.push(endpoint("POST", "/v1/auth/jwt", false));
+    }
+
+    Compat {
+        version: env!("CARGO_PKG_VERSION"),
+        endpoints,
+        streaming: Streaming {
+            openai: true,
+            anthropic: true,
+            keepalive_secs,
+        },
+        max_body_bytes: MAX_BODY_BYTES,
+        features: Features {
+            json_schema: true,
+            tools: true,
+            strict_determinism: true,
+            context_truncation: true,
+            prompt_uploads: true,
+            embeddings: true,
+            vector_stores: true,
+            code_execution: sandbox_enabled,
+            jwt_access_tokens,
+        },
+    }
+}
+
+/// GET /v1/compat — What this deployment supports.
+async fn compat(State(state): State<Arc<AppState>>) -> Json<Compat> {
+    Json(descriptor(&state.config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(compat: &Compat) -> Vec<&str> {
+        compat.endpoints.iter().map(|e| e.path).collect()
+    }
+
+    #[test]
+    fn optional_endpoints_follow_config() {
+        let compat = descriptor_with(false, false, None);
+        assert!(!paths(&compat).contains(&"/v1/tools/execute"));
+        assert!(!paths(&compat).contains(&"/v1/auth/jwt"));
+        assert!(!compat.features.code_execution);
+
+        let compat = descriptor_with(true, true, None);
+        assert!(paths(&compat).contains(&"/v1/tools/execute"));
+        assert!(paths(&compat).contains(&"/v1/auth/jwt"));
+        assert!(compat.features.code_execution);
+        assert!(compat.features.jwt_access_tokens);
+    }
+
+    #[test]
+    fn reports_streaming_and_limits() {
+        let compat = descriptor_with(false, false, Some(15));
+        assert_eq!(compat.streaming.keepalive_secs, Some(15));
+        assert_eq!(compat.max_body_bytes, MAX_BODY_BYTES);
+
+        let chat = compat
+            .endpoints
+            .iter()
+            .find(|e| e.path == "/v1/chat/completions")
+            .unwrap();
+        assert!(chat.streaming);
+        let json = serde_json::to_value(&compat).unwrap();
+        assert_eq!(json["features"]["json_schema"], true);
+    }
+}
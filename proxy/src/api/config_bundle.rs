@@ -0,0 +1,542 @@
+//! Export and import of the system configuration.
+//!
+//! `GET /api/admin/export` returns one JSON bundle with the categories,
+//! model metadata, launch profiles, aliases, settings, identity providers and
+//! host routes, so a deployment can be copied to another instance or kept in version
+//! control. Model files are not included; an imported model shows up
+//! unloaded and needs its files downloaded or copied separately.
+//!
+//! IdP client secrets are exported as stored, encrypted with
+//! `DB_ENCRYPTION_KEY`, along with a fingerprint of the key, and only import
+//! on an instance whose key has the same fingerprint. Without a key they are
+//! stored in plain text, so they are left out of the bundle. An imported IdP
+//! whose secret is missing or can't be used is created disabled.
+//!
+//! `POST /api/admin/import` applies a bundle in one transaction. Rows are
+//! matched by primary key; `on_conflict` decides whether an existing row is
+//! skipped, overwritten, or fails the import, and `dry_run` reports what
+//! would change and rolls back.
+
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sqlx::SqliteConnection;
+use tracing::{info, warn};
+
+use super::error;
+use super::model_trash::{restore_insert, MODEL_COLUMNS, PROFILE_COLUMNS};
+use crate::auth::SessionAuth;
+use crate::db::crypto;
+use crate::AppState;
+
+/// Bundle format version, bumped on incompatible changes.
+const BUNDLE_VERSION: u64 = 1;
+
+/// A table in the bundle.
+struct Section {
+    /// Key in the bundle.
+    name: &'static str,
+    table: &'static str,
+    /// Primary key rows are matched on.
+    key: &'static str,
+    /// Another unique column, which may already be taken by a different row.
+    unique: Option<&'static str>,
+    columns: &'static [&'static str],
+}
+
+/// Sections in import order, so foreign keys resolve.
+const SECTIONS: &[Section] = &[
+    Section {
+        name: "categories",
+        table: "model_categories",
+        key: "id",
+        unique: Some("name"),
+        columns: &[
+            "id",
+            "name",
+            "description",
+            "preferred_model_id",
+            "created_at",
+            "autoload",
+            "auto_kind",
+            "log_level",
+            "max_concurrent",
+            "max_queue_depth",
+            "queue_timeout_secs",
+            "default_parallel",
+        ],
+    },
+    Section {
+        name: "models",
+        table: "models",
+        key: "id",
+        unique: None,
+        columns: MODEL_COLUMNS,
+    },
+    Section {
+        name: "launch_profiles",
+        table: "model_launch_profiles",
+        key: "model_id",
+        unique: None,
+        columns: PROFILE_COLUMNS,
+    },
+    Section {
+        name: "aliases",
+        table: "model_aliases",
+        key: "id",
+        unique: Some("alias"),
+        columns: &[
+            "id",
+            "alias",
+            "model_id",
+            "category_id",
+            "created_by",
+            "created_at",
+            "updated_at",
+        ],
+    },
+    Section {
+        name: "settings",
+        table: "settings",
+        key: "key",
+        unique: None,
+        columns: &["key", "value", "updated_at"],
+    },
+    Section {
+        name: "idps",
+        table: "idp_configs",
+        key: "id",
+        unique: None,
+        columns: &[
+            "id",
+            "name",
+            "issuer",
+            "client_id",
+            "client_secret_enc",
+            "scopes",
+            "enabled",
+            "created_at",
+            "groups_claim",
+            "group_mappings",
+        ],
+    },
+    Section {
+        name: "host_routes",
+        table: "host_routes",
+        key: "hostname",
+        unique: None,
+        columns: &[
+            "hostname",
+            "target",
+            "description",
+            "created_by",
+            "created_at",
+            "updated_at",
+        ],
+    },
+];
+
+/// IdP column holding the client secret.
+const SECRET_COLUMN: &str = "client_secret_enc";
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/export", get(export))
+        .route("/import", post(import))
+        .with_state(state)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum OnConflict {
+    /// Keep the existing row.
+    #[default]
+    Skip,
+    /// Replace the existing row's exported columns.
+    Overwrite,
+    /// Abort the whole import.
+    Fail,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportRequest {
+    bundle: Value,
+    #[serde(default)]
+    dry_run: bool,
+    #[serde(default)]
+    on_conflict: OnConflict,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct SectionCounts {
+    created: u64,
+    updated: u64,
+    skipped: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct Conflict {
+    section: &'static str,
+    key: String,
+    reason: String,
+}
+
+/// `SELECT json_object(...)` over every row of the section's table.
+fn export_select(section: &Section) -> String {
+    let fields: Vec<String> = section
+        .columns
+        .iter()
+        .map(|c| format!("'{c}', {c}"))
+        .collect();
+    format!(
+        "SELECT json_object({}) FROM {} ORDER BY {}",
+        fields.join(", "),
+        section.table,
+        section.key
+    )
+}
+
+/// `UPDATE` that writes `columns` from a row bound as `?1`.
+fn overwrite_update(section: &Section, columns: &[&str]) -> String {
+    let sets: Vec<String> = columns
+        .iter()
+        .filter(|c| **c != section.key)
+        .map(|c| format!("{c} = json_extract(?1, '$.{c}')"))
+        .collect();
+    format!(
+        "UPDATE {} SET {} WHERE {} = json_extract(?1, '$.{}')",
+        section.table,
+        sets.join(", "),
+        section.key,
+        section.key
+    )
+}
+
+/// GET /api/admin/export — The configuration bundle.
+async fn export(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+) -> Response {
+    let secrets_encrypted = state.config.db_encryption_key.is_some();
+    let mut bundle = Map::new();
+    bundle.insert("version".into(), json!(BUNDLE_VERSION));
+    bundle.insert("exported_at".into(), json!(chrono::Utc::now().to_rfc3339()));
+    bundle.insert(
+        "idp_secrets".into(),
+        json!(if secrets_encrypted {
+            "encrypted"
+        } else {
+            "omitted"
+        }),
+    );
+    if let Some(key) = &state.config.db_encryption_key {
+        bundle.insert(
+            "idp_key_fingerprint".into(),
+            json!(crypto::key_fingerprint(key)),
+        );
+    }
+
+    for section in SECTIONS {
+        let rows: Vec<String> = match sqlx::query_scalar(&export_select(section))
+            .fetch_all(&state.db.pool)
+            .await
+        {
+            Ok(rows) => rows,
+            Err(e) => return error::internal_error("config_export", e),
+        };
+        let mut rows: Vec<Value> = rows
+            .iter()
+            .filter_map(|row| serde_json::from_str(row).ok())
+            .collect();
+        if section.name == "idps" && !secrets_encrypted {
+            for row in &mut rows {
+                row[SECRET_COLUMN] = Value::Null;
+            }
+        }
+        bundle.insert(section.name.into(), Value::Array(rows));
+    }
+
+    info!(target: "audit", action = "config.export", actor = %session.user_id, "Admin exported system configuration");
+    Json(Value::Object(bundle)).into_response()
+}
+
+fn bad_request(message: impl Into<String>) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "error": message.into() })),
+    )
+        .into_response()
+}
+
+/// Outcome of importing a bundle, before commit or rollback.
+#[derive(Default)]
+struct ImportReport {
+    sections: Map<String, Value>,
+    conflicts: Vec<Conflict>,
+    warnings: Vec<String>,
+}
+
+/// Whether the bundle's IdP secrets were encrypted with `key`, judged by the
+/// fingerprint exported with them. A bundle without one can't be checked.
+fn secrets_match(bundle: &Map<String, Value>, key: Option<&str>) -> bool {
+    match (
+        bundle.get("idp_key_fingerprint").and_then(Value::as_str),
+        key,
+    ) {
+        (Some(fingerprint), Some(key)) => fingerprint == crypto::key_fingerprint(key),
+        _ => false,
+    }
+}
+
+/// Apply every section of `bundle`. IdP secrets are only written when
+/// `secrets_usable`. Errors are responses, and leave the transaction to be
+/// rolled back.
+async fn apply_bundle(
+    conn: &mut SqliteConnection,
+    bundle: &Map<String, Value>,
+    on_conflict: OnConflict,
+    secrets_usable: bool,
+) -> Result<ImportReport, Response> {
+    let mut report = ImportReport::default();
+    for section in SECTIONS {
+        let rows = match bundle.get(section.name) {
+            None | Some(Value::Null) => continue,
+            Some(Value::Array(rows)) => rows,
+            Some(_) => return Err(bad_request(format!("{} must be an array", section.name))),
+        };
+        let mut counts = SectionCounts::default();
+        for row in rows {
+            let Some(fields) = row.as_object() else {
+                return Err(bad_request(format!(
+                    "{} rows must be objects",
+                    section.name
+                )));
+            };
+            let Some(key) = fields.get(section.key).and_then(Value::as_str) else {
+                return Err(bad_request(format!(
+                    "{} row is missing {}",
+                    section.name, section.key
+                )));
+            };
+            let key = key.to_string();
+
+            if let Some(unique) = section.unique {
+                let taken: Option<String> = match sqlx::query_scalar(&format!(
+                    "SELECT {k} FROM {t} WHERE {unique} = ? AND {k} != ?",
+                    k = section.key,
+                    t = section.table,
+                ))
+                .bind(fields.get(unique).and_then(Value::as_str))
+                .bind(&key)
+                .fetch_optional(&mut *conn)
+                .await
+                {
+                    Ok(taken) => taken,
+                    Err(e) => return Err(error::internal_error("config_import:unique", e)),
+                };
+                if let Some(other) = taken {
+                    let conflict = Conflict {
+                        section: section.name,
+                        key,
+                        reason: format!("{unique} is already used by {other}"),
+                    };
+                    if on_conflict == OnConflict::Fail {
+                        return Err(conflict_response(conflict));
+                    }
+                    report.conflicts.push(conflict);
+                    counts.skipped += 1;
+                    continue;
+                }
+            }
+
+            let exists = match sqlx::query_scalar::<_, i64>(&format!(
+                "SELECT COUNT(*) FROM {} WHERE {} = ?",
+                section.table, section.key
+            ))
+            .bind(&key)
+            .fetch_one(&mut *conn)
+            .await
+            {
+                Ok(n) => n > 0,
+                Err(e) => return Err(error::internal_error("config_import:exists", e)),
+            };
+
+            // Only the columns the bundle carries, so an older bundle leaves
+            // newer columns at their defaults.
+            let mut row = row.clone();
+            let mut columns: Vec<&str> = section
+                .columns
+                .iter()
+                .copied()
+                .filter(|c| fields.contains_key(*c))
+                .collect();
+            let has_secret = fields.get(SECRET_COLUMN).is_some_and(|v| !v.is_null());
+            if section.name == "idps" && !(has_secret && secrets_usable) {
+                columns.retain(|c| *c != SECRET_COLUMN);
+                if !exists {
+                    row[SECRET_COLUMN] = json!("");
+                    row["enabled"] = json!(0);
+                    columns.push(SECRET_COLUMN);
+                    if !columns.contains(&"enabled") {
+                        columns.push("enabled");
+                    }
+                    report.warnings.push(if has_secret {
+                        format!("IdP {key}'s client secret wasn't encrypted with this instance's DB_ENCRYPTION_KEY and was created disabled")
+                    } else {
+                        format!("IdP {key} has no client secret in the bundle and was created disabled")
+                    });
+                }
+            }
+
+            let sql = if exists {
+                match on_conflict {
+                    OnConflict::Skip => {
+                        report.conflicts.push(Conflict {
+                            section: section.name,
+                            key,
+                            reason: "already exists".into(),
+                        });
+                        counts.skipped += 1;
+                        continue;
+                    }
+                    OnConflict::Fail => {
+                        return Err(conflict_response(Conflict {
+                            section: section.name,
+                            key,
+                            reason: "already exists".into(),
+                        }));
+                    }
+                    // Nothing but the key to write
+                    OnConflict::Overwrite if columns.len() < 2 => {
+                        counts.updated += 1;
+                        continue;
+                    }
+                    OnConflict::Overwrite => overwrite_update(section, &columns),
+                }
+            } else {
+                restore_insert(section.table, &columns)
+            };
+            if let Err(e) = sqlx::query(&sql)
+                .bind(row.to_string())
+                .execute(&mut *conn)
+                .await
+            {
+                return Err(bad_request(format!("{} {key}: {e}", section.name)));
+            }
+            if exists {
+                counts.updated += 1;
+            } else {
+                counts.created += 1;
+            }
+        }
+        report.sections.insert(section.name.into(), json!(counts));
+    }
+    Ok(report)
+}
+
+fn conflict_response(conflict: Conflict) -> Response {
+    (
+        StatusCode::CONFLICT,
+        Json(json!({
+            "error": format!("{} {}: {}", conflict.section, conflict.key, conflict.reason),
+            "conflicts": [conflict],
+        })),
+    )
+        .into_response()
+}
+
+/// POST /api/admin/import — Apply a configuration bundle.
+async fn import(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Json(req): Json<ImportRequest>,
+) -> Response {
+    let Some(bundle) = req.bundle.as_object() else {
+        return bad_request("bundle must be an object");
+    };
+    match bundle.get("version").and_then(Value::as_u64) {
+        Some(BUNDLE_VERSION) => {}
+        Some(v) => return bad_request(format!("Unsupported bundle version {v}")),
+        None => return bad_request("bundle.version is required"),
+    }
+
+    let mut tx = match state.db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return error::internal_error("config_import:begin", e),
+    };
+    let secrets_usable = secrets_match(bundle, state.config.db_encryption_key.as_deref());
+    let report = match apply_bundle(&mut tx, bundle, req.on_conflict, secrets_usable).await {
+        Ok(report) => report,
+        Err(response) => return response,
+    };
+
+    if req.dry_run {
+        if let Err(e) = tx.rollback().await {
+            return error::internal_error("config_import:rollback", e);
+        }
+    } else {
+        if let Err(e) = tx.commit().await {
+            return error::internal_error("config_import:commit", e);
+        }
+        if let Err(e) = state.scheduler.reload_settings(&state.db).await {
+            warn!(error = %e, "Failed to reload settings after config import");
+        }
+        if let Err(e) = state.scheduler.reload_category_limits(&state.db).await {
+            warn!(error = %e, "Failed to reload category limits after config import");
+        }
+        if let Err(e) = state.host_routes.reload(&state.db).await {
+            warn!(error = %e, "Failed to reload host routes after config import");
+        }
+        info!(target: "audit", action = "config.import", actor = %session.user_id, on_conflict = ?req.on_conflict, conflicts = report.conflicts.len(), "Admin imported system configuration");
+    }
+
+    Json(json!({
+        "dry_run": req.dry_run,
+        "on_conflict": req.on_conflict,
+        "sections": report.sections,
+        "conflicts": report.conflicts,
+        "warnings": report.warnings,
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_cover_their_keys() {
+        for section in SECTIONS {
+            assert!(section.columns.contains(&section.key), "{}", section.name);
+            if let Some(unique) = section.unique {
+                assert!(section.columns.contains(&unique), "{}", section.name);
+            }
+        }
+    }
+
+    #[test]
+    fn secrets_need_matching_key_fingerprint() {
+        let bundle = json!({ "idp_key_fingerprint": crypto::key_fingerprint("key-a") });
+        let bundle = bundle.as_object().unwrap();
+        assert!(secrets_match(bundle, Some("key-a")));
+        assert!(!secrets_match(bundle, Some("key-b")));
+        assert!(!secrets_match(bundle, None));
+        assert!(!secrets_match(&Map::new(), Some("key-a")));
+    }
+
+    #[test]
+    fn overwrite_leaves_key_alone() {
+        let settings = SECTIONS.iter().find(|s| s.name == "settings").unwrap();
+        assert_eq!(
+            overwrite_update(settings, &["key", "value"]),
+            "UPDATE settings SET value = json_extract(?1, '$.value') \
+             WHERE key = json_extract(?1, '$.key')"
+        );
+    }
+}
@@ -0,0 +1,140 @@
+//! Backend container logs.
+//!
+//! `GET /api/admin/containers/{model_id}/logs` sends the output of the
+//! container serving a model as server-sent events, one `stdout` or `stderr`
+//! event per log chunk. Without `follow` the stream ends after the existing
+//! output; with it, new output keeps arriving until the container stops or
+//! the client disconnects.
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{middleware, Extension, Json, Router};
+use futures::StreamExt;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use super::common;
+use super::error;
+use crate::auth::rbac::{self, Permission};
+use crate::auth::SessionAuth;
+use crate::AppState;
+
+/// Lines sent when `tail` is not given.
+const DEFAULT_TAIL: u32 = 100;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/containers/{model_id}/logs", get(container_logs))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Models,
+            rbac::require_permission,
+        ))
+        .with_state(state)
+}
+
+#[derive(Debug, Deserialize)]
+struct LogsQuery {
+    /// Keep the stream open for new output.
+    #[serde(default)]
+    follow: bool,
+    /// Number of lines from the end, or `all`.
+    tail: Option<String>,
+    /// Unix time; only output written after it is sent.
+    since: Option<i64>,
+}
+
+/// `Some(n)` for the last `n` lines, `None` for all of them.
+fn parse_tail(tail: Option<&str>) -> Result<Option<u32>, &'static str> {
+    match tail {
+        None => Ok(Some(DEFAULT_TAIL)),
+        Some("all") => Ok(None),
+        Some(n) => n
+            .parse()
+            .map(Some)
+            .map_err(|_| "tail must be a number of lines or \"all\""),
+    }
+}
+
+/// GET /api/admin/containers/{model_id}/logs — Stream a model's container
+/// output as SSE.
+async fn container_logs(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(model_id): Path<String>,
+    Query(query): Query<LogsQuery>,
+) -> Response {
+    let tail = match parse_tail(query.tail.as_deref()) {
+        Ok(tail) => tail,
+        Err(msg) => return json_error(StatusCode::BAD_REQUEST, msg),
+    };
+    if query.since.is_some_and(|t| t < 0) {
+        return json_error(StatusCode::BAD_REQUEST, "since must be a Unix timestamp");
+    }
+
+    let backend_type: Option<(String,)> =
+        match sqlx::query_as("SELECT backend_type FROM models WHERE id = ?")
+            .bind(&model_id)
+            .fetch_optional(&state.db.pool)
+            .await
+        {
+            Ok(row) => row,
+            Err(e) => return error::internal_error("container_logs:lookup", e),
+        };
+    let Some((backend_type,)) = backend_type else {
+        return json_error(StatusCode::NOT_FOUND, "Model not found");
+    };
+    let container = common::serving_container_name(&state.db.pool, &model_id, &backend_type).await;
+
+    let logs = match state
+        .docker
+        .container_logs(&container, query.follow, tail, query.since)
+        .await
+    {
+        Ok(Some(logs)) => logs,
+        Ok(None) => return json_error(StatusCode::NOT_FOUND, "Model has no container"),
+        Err(e) => return error::internal_error("container_logs:open", e),
+    };
+
+    info!(target: "audit", action = "container.logs_view", actor = %session.user_id, resource = %model_id, container = %container, follow = query.follow, "Admin viewed container logs");
+
+    // A read error ends the stream; the client sees it close
+    let events = logs
+        .take_while(move |line| {
+            if let Err(e) = line {
+                warn!(container = %container, error = %e, "Container log stream failed");
+            }
+            std::future::ready(line.is_ok())
+        })
+        .filter_map(|line| async move { line.ok() })
+        .map(|(stream, text)| {
+            Ok::<_, Infallible>(Event::default().event(stream.name()).data(text))
+        });
+
+    Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+fn json_error(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tail_defaults_and_parses() {
+        assert_eq!(parse_tail(None), Ok(Some(DEFAULT_TAIL)));
+        assert_eq!(parse_tail(Some("all")), Ok(None));
+        assert_eq!(parse_tail(Some("20")), Ok(Some(20)));
+        assert!(parse_tail(Some("-1")).is_err());
+        assert!(parse_tail(Some("lots")).is_err());
+    }
+}
@@ -0,0 +1,335 @@
+//! Zero-downtime container replace (blue/green).
+//!
+//! `POST /api/admin/containers/{model_id}/replace` starts a second container
+//! for a loaded model with new launch parameters, under the other of its two
+//! names (`sovereign-<backend>-<id>` and the same with `-green`). Once the new
+//! container passes its health check, one transaction points
+//! `container_secrets` and the model row at it, so requests that start after
+//! that go to the new container. The old container is stopped once every
+//! request that started before the switch has finished, or after the drain
+//! timeout.
+//!
+//! Both containers run side by side until then, so the host needs room (GPU
+//! memory in particular) for both. A replace keeps the model's backend;
+//! switching between llama.cpp and vLLM needs a stop and start.
+
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{middleware, Extension, Json, Router};
+use serde::Deserialize;
+use tracing::{error, info, warn};
+
+use super::common::{self, LaunchParams, StartContainerParams};
+use super::error;
+use crate::auth::rbac::{self, Permission};
+use crate::auth::SessionAuth;
+use crate::docker::limits::ResourceLimits;
+use crate::docker::StopMode;
+use crate::AppState;
+
+const HEALTH_POLL: Duration = Duration::from_secs(2);
+const DRAIN_POLL: Duration = Duration::from_millis(250);
+
+/// Models with a replace in progress, held in `AppState`.
+#[derive(Debug, Default)]
+pub struct Replacing(Mutex<BTreeSet<String>>);
+
+/// Marks a model as being replaced until dropped.
+struct ReplaceGuard<'a> {
+    replacing: &'a Replacing,
+    model_id: String,
+}
+
+impl<'a> ReplaceGuard<'a> {
+    fn acquire(replacing: &'a Replacing, model_id: &str) -> Option<Self> {
+        let mut models = replacing.0.lock().unwrap_or_else(|e| e.into_inner());
+        models.insert(model_id.to_string()).then(|| Self {
+            replacing,
+            model_id: model_id.to_string(),
+        })
+    }
+}
+
+impl Drop for ReplaceGuard<'_> {
+    fn drop(&mut self) {
+        self.replacing
+            .0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.model_id);
+    }
+}
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/containers/{model_id}/replace", post(replace_container))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Models,
+            rbac::require_permission,
+        ))
+        .with_state(state)
+}
+
+/// New launch parameters. Fields left out keep the running container's
+/// value, so `{}` restarts the model as it is.
+#[derive(Debug, Deserialize)]
+struct ReplaceContainerRequest {
+    gpu_type: Option<String>,
+    gpu_layers: Option<u32>,
+    context_size: Option<u32>,
+    parallel: Option<u32>,
+    /// Pin the new container to one GPU.
+    device_index: Option<u32>,
+    /// Pin the new container to several GPUs.
+    device_list: Option<Vec<u32>>,
+    #[serde(flatten)]
+    limits: ResourceLimits,
+    /// How long to wait for the new container's health check. Defaults to
+    /// `AUTOLOAD_TIMEOUT_SECS`.
+    health_timeout_secs: Option<u64>,
+    /// How long requests on the old container get to finish. Defaults to
+    /// `GRACEFUL_STOP_TIMEOUT_SECS`.
+    drain_timeout_secs: Option<u64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ServingRow {
+    loaded: bool,
+    draining: bool,
+    backend_type: String,
+    launch_params: Option<String>,
+    container_name: Option<String>,
+}
+
+/// POST /api/admin/containers/{model_id}/replace — Swap a loaded model's
+/// container for one with new launch parameters without dropping requests.
+async fn replace_container(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(model_id): Path<String>,
+    Json(req): Json<ReplaceContainerRequest>,
+) -> Response {
+    let devices = match common::device_selection(req.device_index, req.device_list) {
+        Ok(devices) => devices,
+        Err(msg) => return json_error(StatusCode::BAD_REQUEST, msg),
+    };
+    if let Err(msg) = req.limits.validate() {
+        return json_error(StatusCode::BAD_REQUEST, msg);
+    }
+
+    let Some(_guard) = ReplaceGuard::acquire(&state.replacing, &model_id) else {
+        return json_error(
+            StatusCode::CONFLICT,
+            "A replace is already in progress for this model",
+        );
+    };
+
+    let row: Option<ServingRow> = match sqlx::query_as(
+        r#"
+        SELECT m.loaded, m.draining, m.backend_type, m.launch_params, cs.container_name
+        FROM models m
+        LEFT JOIN container_secrets cs ON cs.model_id = m.id
+        WHERE m.id = ?
+        "#,
+    )
+    .bind(&model_id)
+    .fetch_optional(&state.db.pool)
+    .await
+    {
+        Ok(row) => row,
+        Err(e) => return error::internal_error("replace_container:lookup", e),
+    };
+    let Some(row) = row else {
+        return json_error(StatusCode::NOT_FOUND, "Model not found");
+    };
+    if !row.loaded {
+        return json_error(
+            StatusCode::CONFLICT,
+            "Model is not loaded — start its container instead",
+        );
+    }
+    if row.draining {
+        return json_error(StatusCode::CONFLICT, "Model is being stopped");
+    }
+
+    let backend_type = row.backend_type;
+    let old_name = row
+        .container_name
+        .unwrap_or_else(|| crate::docker::container_name(&backend_type, &model_id));
+    let new_name = crate::docker::replacement_container_name(&backend_type, &model_id, &old_name);
+
+    let current: LaunchParams = row
+        .launch_params
+        .as_deref()
+        .and_then(|p| serde_json::from_str(p).ok())
+        .unwrap_or_default();
+    let params = StartContainerParams {
+        model_id: model_id.clone(),
+        backend_type: Some(backend_type.clone()),
+        gpu_type: req.gpu_type.or(current.gpu_type),
+        gpu_layers: req.gpu_layers.or(current.gpu_layers),
+        context_size: req.context_size.or(current.context_size),
+        parallel: req.parallel.or(current.parallel),
+        devices: devices.or(current.devices),
+        limits: req.limits.or(current.limits),
+    };
+
+    // Left behind by a replace that died before cleaning up
+    if let Err(e) = state
+        .docker
+        .stop_named(&new_name, &model_id, StopMode::Force)
+        .await
+    {
+        return error::internal_error("replace_container:clear", e);
+    }
+
+    let launched = match common::launch_container(&state, &params, Some(new_name.clone())).await {
+        Ok(launched) => launched,
+        Err(response) => return response,
+    };
+
+    let health_timeout = Duration::from_secs(
+        req.health_timeout_secs
+            .unwrap_or(state.config.autoload_timeout_secs),
+    );
+    if !wait_for_health(&state, &new_name, &backend_type, health_timeout).await {
+        warn!(model = %model_id, container = %new_name, "Replacement container not healthy before timeout");
+        if let Err(e) = state
+            .docker
+            .stop_named(&new_name, &model_id, StopMode::Force)
+            .await
+        {
+            error!(model = %model_id, container = %new_name, error = %e, "Failed to remove unhealthy replacement container");
+        }
+        return json_error(
+            StatusCode::GATEWAY_TIMEOUT,
+            &format!(
+                "New container was not healthy within {}s; {old_name} keeps serving",
+                health_timeout.as_secs()
+            ),
+        );
+    }
+
+    // Switch over. The model may have been stopped while the new container
+    // loaded; don't bring it back behind the admin's back.
+    let still_loaded: Option<bool> =
+        sqlx::query_scalar("SELECT loaded = 1 AND draining = 0 FROM models WHERE id = ?")
+            .bind(&model_id)
+            .fetch_optional(&state.db.pool)
+            .await
+            .ok()
+            .flatten();
+    let switched = if still_loaded == Some(true) {
+        launched.record(&state.db.pool).await.map_err(Into::into)
+    } else {
+        Err(anyhow::anyhow!("model was stopped during the replace"))
+    };
+    if let Err(e) = switched {
+        let _ = state
+            .docker
+            .stop_named(&new_name, &model_id, StopMode::Force)
+            .await;
+        return error::internal_error("replace_container:switch", e);
+    }
+    let switched_at = tokio::time::Instant::now();
+    state
+        .scheduler
+        .gate()
+        .resize(&model_id, launched.parallel_slots)
+        .await;
+    // Pooled connections point at the old container
+    state.backends.remove(&model_id).await;
+    super::capabilities::spawn_probe(state.clone(), model_id.clone());
+    state.events.publish(launched.loaded_event());
+
+    // Requests that started before the switch are still on the old container
+    let drain_timeout = Duration::from_secs(
+        req.drain_timeout_secs
+            .unwrap_or(state.config.graceful_stop_timeout_secs),
+    );
+    let abandoned = loop {
+        let elapsed = switched_at.elapsed();
+        let remaining = state
+            .scheduler
+            .gate()
+            .in_flight_ages(&model_id)
+            .await
+            .map_or(0, |(_, ages)| {
+                ages.iter().filter(|age| **age >= elapsed).count()
+            });
+        if remaining == 0 || elapsed >= drain_timeout {
+            break remaining;
+        }
+        tokio::time::sleep(DRAIN_POLL).await;
+    };
+
+    let old_container_error = state
+        .docker
+        .stop_named(&old_name, &model_id, StopMode::Graceful)
+        .await
+        .err()
+        .map(|e| {
+            error!(model = %model_id, container = %old_name, error = %e, "Failed to remove replaced container");
+            e.to_string()
+        });
+
+    info!(target: "audit", action = "container.replace", actor = %session.user_id, resource = %model_id, container = %new_name, previous_container = %old_name, abandoned_in_flight = abandoned, "Admin replaced container");
+
+    Json(serde_json::json!({
+        "container": new_name,
+        "previous_container": old_name,
+        "url": state.docker.backend_url(&new_name, &backend_type),
+        "drained": abandoned == 0,
+        "abandoned_in_flight": abandoned,
+        "previous_container_error": old_container_error,
+    }))
+    .into_response()
+}
+
+/// Poll the named container's health check until it passes or `timeout`
+/// runs out.
+async fn wait_for_health(
+    state: &Arc<AppState>,
+    container: &str,
+    backend_type: &str,
+    timeout: Duration,
+) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if matches!(
+            state.docker.check_health_at(container, backend_type).await,
+            Ok(true)
+        ) {
+            return true;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return false;
+        }
+        tokio::time::sleep(HEALTH_POLL).await;
+    }
+}
+
+fn json_error(status: StatusCode, message: &str) -> Response {
+    (status, Json(serde_json::json!({ "error": message }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_guard_is_exclusive_per_model() {
+        let replacing = Replacing::default();
+        let first = ReplaceGuard::acquire(&replacing, "m1").unwrap();
+        assert!(ReplaceGuard::acquire(&replacing, "m1").is_none());
+        assert!(ReplaceGuard::acquire(&replacing, "m2").is_some());
+        drop(first);
+        assert!(ReplaceGuard::acquire(&replacing, "m1").is_some());
+    }
+}
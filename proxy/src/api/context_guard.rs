@@ -0,0 +1,365 @@
+//! Context-length guard for completions.
+//!
+//! Before a chat or text completion is queued, its prompt is estimated
+//! (about four characters per token) and compared with the context the
+//! model is served with: the container's `context_size` when it was started
+//! with one, else the GGUF `context_length`, else `model_max_length` from
+//! the stored tokenizer config. A prompt over the limit gets the OpenAI
+//! `context_length_exceeded` error instead of an opaque backend failure.
+//!
+//! A model's `context_policy` can be `truncate` instead: the oldest chat
+//! messages are dropped until the prompt fits, leaving room for the
+//! request's `max_tokens`. System and developer messages and the last
+//! message are kept, and a dropped assistant message takes its tool results
+//! with it.
+//!
+//! The estimate is deliberately rough, so the guard only catches prompts
+//! clearly over the limit; the backend still has the final say.
+
+use anyhow::{Context, Result};
+use axum::body::Bytes;
+use serde_json::Value;
+
+use super::common::LaunchParams;
+
+/// Tokenizer configs often carry a placeholder like `1e30` for "no limit".
+const MAX_PLAUSIBLE_CONTEXT: i64 = 10_000_000;
+
+/// What happens to a prompt over the model's context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContextPolicy {
+    /// Refuse with `context_length_exceeded`.
+    Reject,
+    /// Drop the oldest chat messages until it fits.
+    Truncate,
+}
+
+impl ContextPolicy {
+    pub const ALL: &'static [&'static str] = &["reject", "truncate"];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "reject" => Some(Self::Reject),
+            "truncate" => Some(Self::Truncate),
+            _ => None,
+        }
+    }
+}
+
+/// The context a model is served with, and what to do when a prompt
+/// doesn't fit.
+#[derive(Debug, Clone, Copy)]
+pub struct ContextLimit {
+    pub tokens: i64,
+    pub policy: ContextPolicy,
+}
+
+#[derive(sqlx::FromRow)]
+struct LimitRow {
+    context_length: Option<i64>,
+    launch_params: Option<String>,
+    tokenizer_max_length: Option<i64>,
+    context_policy: String,
+}
+
+/// The limit for `model_id`, or `None` if its context isn't known.
+pub async fn limit_for(pool: &sqlx::SqlitePool, model_id: &str) -> Result<Option<ContextLimit>> {
+    let row: Option<LimitRow> = sqlx::query_as(
+        "SELECT context_length, launch_params, context_policy, \
+         CASE WHEN json_valid(model_metadata) \
+              THEN CAST(json_extract(model_metadata, '$.model_max_length') AS INTEGER) \
+         END AS tokenizer_max_length \
+         FROM models WHERE id = ?",
+    )
+    .bind(model_id)
+    .fetch_optional(pool)
+    .await
+    .context("Failed to load model context limit")?;
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let launched = row
+        .launch_params
+        .as_deref()
+        .and_then(|p| serde_json::from_str::<LaunchParams>(p).ok())
+        .and_then(|p| p.context_size)
+        .map(i64::from);
+    let tokenizer = row
+        .tokenizer_max_length
+        .filter(|n| (1..=MAX_PLAUSIBLE_CONTEXT).contains(n));
+    let Some(tokens) = launched
+        .or(row.context_length)
+        .or(tokenizer)
+        .filter(|n| *n > 0)
+    else {
+        return Ok(None);
+    };
+    Ok(Some(ContextLimit {
+        tokens,
+        policy: ContextPolicy::parse(&row.context_policy).unwrap_or(ContextPolicy::Reject),
+    }))
+}
+
+/// Result of checking a request against a [`ContextLimit`].
+#[derive(Debug)]
+pub enum Checked {
+    /// The prompt fits, or couldn't be read; forward the body as it is.
+    Fits(Bytes),
+    /// The oldest `dropped` messages were removed to make it fit.
+    Truncated { body: Bytes, dropped: usize },
+    /// Over the limit, with the estimated prompt size.
+    Exceeded { prompt_tokens: i64 },
+}
+
+/// Check a completion request body against `limit`. `max_tokens` is the
+/// room truncation leaves for the reply.
+pub fn check(body: Bytes, limit: ContextLimit, max_tokens: Option<u32>) -> Checked {
+    let Ok(mut request) = serde_json::from_slice::<Value>(&body) else {
+        return Checked::Fits(body);
+    };
+    let prompt_tokens = estimate_tokens(&request);
+    // A reply budget the size of the whole context can't be honored anyway
+    let reserve = max_tokens
+        .map(i64::from)
+        .filter(|n| *n < limit.tokens)
+        .unwrap_or(0);
+
+    if limit.policy == ContextPolicy::Truncate && prompt_tokens + reserve > limit.tokens {
+        let dropped = drop_oldest(&mut request, limit.tokens - reserve);
+        if dropped > 0 {
+            let prompt_tokens = estimate_tokens(&request);
+            if prompt_tokens > limit.tokens {
+                return Checked::Exceeded { prompt_tokens };
+            }
+            return match serde_json::to_vec(&request) {
+                Ok(truncated) => Checked::Truncated {
+                    body: Bytes::from(truncated),
+                    dropped,
+                },
+                Err(_) => Checked::Exceeded { prompt_tokens },
+            };
+        }
+    }
+
+    if prompt_tokens > limit.tokens {
+        Checked::Exceeded { prompt_tokens }
+    } else {
+        Checked::Fits(body)
+    }
+}
+
+/// Rough prompt token count for a completion request: about four characters
+/// per token over the message and prompt text.
+pub fn estimate_tokens(request: &Value) -> i64 {
+    let messages: usize = request
+        .get("messages")
+        .and_then(Value::as_array)
+        .map_or(0, |messages| messages.iter().map(message_chars).sum());
+    let prompt = request.get("prompt").map_or(0, text_len);
+    (messages + prompt).div_ceil(4) as i64
+}
+
+fn message_chars(message: &Value) -> usize {
+    message.get("content").map_or(0, text_len)
+}
+
+fn text_len(value: &Value) -> usize {
+    match value {
+        Value::String(s) => s.chars().count(),
+        // Content parts (`[{"type":"text","text":...}]`) or prompt lists
+        Value::Array(items) => items
+            .iter()
+            .map(|item| item.get("text").map_or_else(|| text_len(item), text_len))
+            .sum(),
+        _ => 0,
+    }
+}
+
+fn role(message: &Value) -> Option<&str> {
+    message.get("role").and_then(Value::as_str)
+}
+
+/// Drop the oldest droppable chat messages until the estimate is within
+/// `target` tokens. Returns how many were dropped.
+fn drop_oldest(request: &mut Value, target: i64) -> usize {
+    let prompt_chars = request.get("prompt").map_or(0, text_len);
+    let Some(messages) = request.get_mut("messages").and_then(Value::as_array_mut) else {
+        return 0;
+    };
+    let mut chars = prompt_chars + messages.iter().map(message_chars).sum::<usize>();
+    let mut dropped = 0;
+    while (chars.div_ceil(4) as i64) > target {
+        // The last message is what the client wants answered
+        let droppable = messages.len().saturating_sub(1);
+        let Some(oldest) = messages[..droppable]
+            .iter()
+            .position(|m| !matches!(role(m), Some("system" | "developer")))
+        else {
+            break;
+        };
+        chars -= message_chars(&messages.remove(oldest));
+        dropped += 1;
+        // Tool results answer the assistant message just dropped
+        while oldest < messages.len().saturating_sub(1) && role(&messages[oldest]) == Some("tool") {
+            chars -= message_chars(&messages.remove(oldest));
+            dropped += 1;
+        }
+    }
+    dropped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn limit(tokens: i64, policy: ContextPolicy) -> ContextLimit {
+        ContextLimit { tokens, policy }
+    }
+
+    fn body(value: Value) -> Bytes {
+        Bytes::from(value.to_string())
+    }
+
+    /// A message of `tokens` estimated tokens.
+    fn msg(role: &str, tokens: usize) -> Value {
+        json!({ "role": role, "content": "x".repeat(tokens * 4) })
+    }
+
+    #[test]
+    fn prompt_within_limit_passes_unchanged() {
+        let request = body(json!({ "messages": [msg("user", 10)] }));
+        match check(request.clone(), limit(10, ContextPolicy::Reject), None) {
+            Checked::Fits(b) => assert_eq!(b, request),
+            other => panic!("expected Fits, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn over_limit_is_rejected() {
+        let request = body(json!({ "prompt": "x".repeat(44) }));
+        match check(request, limit(10, ContextPolicy::Reject), None) {
+            Checked::Exceeded { prompt_tokens } => assert_eq!(prompt_tokens, 11),
+            other => panic!("expected Exceeded, got {other:?}"),
+        }
+        // Truncation can't shorten a plain prompt
+        let request = body(json!({ "prompt": "x".repeat(44) }));
+        assert!(matches!(
+            check(request, limit(10, ContextPolicy::Truncate), None),
+            Checked::Exceeded { .. }
+        ));
+    }
+
+    #[test]
+    fn truncation_drops_oldest_and_keeps_system_and_last() {
+        let request = body(json!({
+            "messages": [
+                msg("system", 5),
+                msg("user", 10),
+                { "role": "assistant", "content": null, "tool_calls": [] },
+                msg("tool", 10),
+                msg("assistant", 10),
+                msg("user", 10),
+            ]
+        }));
+        let Checked::Truncated { body, dropped } =
+            check(request, limit(40, ContextPolicy::Truncate), Some(10))
+        else {
+            panic!("expected Truncated");
+        };
+        // 45 tokens + 10 reserved: the first user turn and the tool call with
+        // its result go
+        assert_eq!(dropped, 3);
+        let request: Value = serde_json::from_slice(&body).unwrap();
+        let roles: Vec<_> = request["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["system", "assistant", "user"]);
+    }
+
+    #[test]
+    fn truncation_never_drops_the_last_message() {
+        let request = body(json!({ "messages": [msg("system", 5), msg("user", 50)] }));
+        assert!(matches!(
+            check(request, limit(40, ContextPolicy::Truncate), None),
+            Checked::Exceeded { prompt_tokens: 55 }
+        ));
+    }
+
+    #[test]
+    fn reserve_is_ignored_when_it_fills_the_context() {
+        let request = body(json!({ "messages": [msg("user", 5), msg("user", 5)] }));
+        assert!(matches!(
+            check(request, limit(40, ContextPolicy::Truncate), Some(40)),
+            Checked::Fits(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn limit_prefers_launch_then_gguf_then_tokenizer() {
+        let db = crate::db::Database::test_db().await;
+        for (id, context_length, launch_params, metadata, policy) in [
+            (
+                "launched",
+                Some(32768),
+                Some(r#"{"context_size":8192}"#),
+                None,
+                "reject",
+            ),
+            ("gguf", Some(32768), Some("{}"), None, "truncate"),
+            (
+                "tokenizer",
+                None,
+                None,
+                Some(r#"{"model_max_length":4096}"#),
+                "reject",
+            ),
+            (
+                "placeholder",
+                None,
+                None,
+                Some(r#"{"model_max_length":1e30}"#),
+                "reject",
+            ),
+        ] {
+            sqlx::query(
+                "INSERT INTO models (id, hf_repo, context_length, launch_params, model_metadata, context_policy) \
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(id)
+            .bind(context_length)
+            .bind(launch_params)
+            .bind(metadata)
+            .bind(policy)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        }
+
+        let get = |id: &'static str| {
+            let pool = db.pool.clone();
+            async move { limit_for(&pool, id).await.unwrap() }
+        };
+        assert_eq!(get("launched").await.unwrap().tokens, 8192);
+        let gguf = get("gguf").await.unwrap();
+        assert_eq!(gguf.tokens, 32768);
+        assert_eq!(gguf.policy, ContextPolicy::Truncate);
+        assert_eq!(get("tokenizer").await.unwrap().tokens, 4096);
+        assert!(get("placeholder").await.is_none());
+        assert!(get("missing").await.is_none());
+    }
+
+    #[test]
+    fn unreadable_body_passes() {
+        let request = Bytes::from_static(b"not json");
+        assert!(matches!(
+            check(request, limit(1, ContextPolicy::Reject), None),
+            Checked::Fits(_)
+        ));
+    }
+}
@@ -0,0 +1,594 @@
+//! Server-side conversation storage for custom frontends.
+//!
+//! Each user has their own conversations: a title, the model used, and an
+//! ordered list of chat messages with the token counts the client reports.
+//! Conversations are private to their owner and are listed most recently
+//! updated first.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::SqlitePool;
+use tracing::info;
+use uuid::Uuid;
+
+use super::error;
+use crate::auth::SessionAuth;
+use crate::AppState;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 200;
+const MAX_TITLE_LEN: usize = 200;
+/// Most messages accepted in one create or append request.
+const MAX_MESSAGES_PER_REQUEST: usize = 500;
+/// Largest serialized content of a single message.
+const MAX_CONTENT_BYTES: usize = 1024 * 1024;
+const ROLES: &[&str] = &["system", "user", "assistant", "tool"];
+
+pub fn user_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/conversations",
+            get(list_conversations).post(create_conversation),
+        )
+        .route(
+            "/conversations/{id}",
+            get(get_conversation)
+                .put(update_conversation)
+                .delete(delete_conversation),
+        )
+        .route("/conversations/{id}/messages", post(append))
+        .route("/conversations/{id}/export", get(export))
+        .with_state(state)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Conversation {
+    id: String,
+    title: String,
+    model: Option<String>,
+    message_count: i64,
+    prompt_tokens: i64,
+    completion_tokens: i64,
+    created_at: String,
+    updated_at: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Message {
+    id: i64,
+    role: String,
+    #[serde(serialize_with = "serialize_json_text")]
+    content: String,
+    model: Option<String>,
+    prompt_tokens: i64,
+    completion_tokens: i64,
+    created_at: String,
+}
+
+/// Serialize a JSON TEXT column as a nested value.
+fn serialize_json_text<S: serde::Serializer>(text: &str, s: S) -> Result<S::Ok, S::Error> {
+    serde_json::from_str::<Value>(text)
+        .unwrap_or(Value::Null)
+        .serialize(s)
+}
+
+const CONVERSATION_SELECT: &str = r#"
+    SELECT c.id, c.title, c.model,
+           COUNT(m.id) AS message_count,
+           COALESCE(SUM(m.prompt_tokens), 0) AS prompt_tokens,
+           COALESCE(SUM(m.completion_tokens), 0) AS completion_tokens,
+           c.created_at, c.updated_at
+    FROM conversations c
+    LEFT JOIN conversation_messages m ON m.conversation_id = c.id
+"#;
+
+/// A message as sent by the client.
+#[derive(Debug, Deserialize)]
+struct NewMessage {
+    role: String,
+    /// A string, or an array of content parts.
+    content: Value,
+    model: Option<String>,
+    #[serde(default)]
+    prompt_tokens: i64,
+    #[serde(default)]
+    completion_tokens: i64,
+}
+
+/// Check a batch of messages, returning the first problem found.
+fn validate_messages(messages: &[NewMessage]) -> Result<(), String> {
+    if messages.len() > MAX_MESSAGES_PER_REQUEST {
+        return Err(format!(
+            "At most {MAX_MESSAGES_PER_REQUEST} messages per request"
+        ));
+    }
+    for (i, m) in messages.iter().enumerate() {
+        if !ROLES.contains(&m.role.as_str()) {
+            return Err(format!(
+                "messages[{i}].role must be one of {}",
+                ROLES.join(", ")
+            ));
+        }
+        if !m.content.is_string() && !m.content.is_array() {
+            return Err(format!(
+                "messages[{i}].content must be a string or an array"
+            ));
+        }
+        if m.content.to_string().len() > MAX_CONTENT_BYTES {
+            return Err(format!(
+                "messages[{i}].content exceeds {MAX_CONTENT_BYTES} bytes"
+            ));
+        }
+        if m.prompt_tokens < 0 || m.completion_tokens < 0 {
+            return Err(format!("messages[{i}] token counts must not be negative"));
+        }
+    }
+    Ok(())
+}
+
+fn bad_request(message: String) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": message }))).into_response()
+}
+
+fn not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "Conversation not found" })),
+    )
+        .into_response()
+}
+
+async fn fetch_conversation(
+    pool: &SqlitePool,
+    user_id: &str,
+    id: &str,
+) -> sqlx::Result<Option<Conversation>> {
+    let sql = format!("{CONVERSATION_SELECT} WHERE c.id = ? AND c.user_id = ? GROUP BY c.id");
+    sqlx::query_as(&sql)
+        .bind(id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+}
+
+async fn fetch_messages(pool: &SqlitePool, id: &str) -> sqlx::Result<Vec<Message>> {
+    sqlx::query_as(
+        "SELECT id, role, content, model, prompt_tokens, completion_tokens, created_at \
+         FROM conversation_messages WHERE conversation_id = ? ORDER BY id",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Append `messages` to conversation `id` and bump its `updated_at`. The
+/// conversation's model follows the last message that names one.
+async fn append_messages(pool: &SqlitePool, id: &str, messages: &[NewMessage]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    for m in messages {
+        sqlx::query(
+            "INSERT INTO conversation_messages \
+             (conversation_id, role, content, model, prompt_tokens, completion_tokens) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(&m.role)
+        .bind(m.content.to_string())
+        .bind(&m.model)
+        .bind(m.prompt_tokens)
+        .bind(m.completion_tokens)
+        .execute(&mut *tx)
+        .await?;
+    }
+    let model = messages.iter().rev().find_map(|m| m.model.as_deref());
+    sqlx::query(
+        "UPDATE conversations SET model = COALESCE(?, model), updated_at = datetime('now') \
+         WHERE id = ?",
+    )
+    .bind(model)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Conversation with its messages, as returned by GET and JSON export.
+async fn conversation_json(pool: &SqlitePool, user_id: &str, id: &str) -> Response {
+    let conversation = match fetch_conversation(pool, user_id, id).await {
+        Ok(Some(c)) => c,
+        Ok(None) => return not_found(),
+        Err(e) => return error::internal_error("conversation_get", e),
+    };
+    match fetch_messages(pool, id).await {
+        Ok(messages) => Json(json!({
+            "conversation": conversation,
+            "messages": messages,
+        }))
+        .into_response(),
+        Err(e) => error::internal_error("conversation_messages", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    /// Only conversations whose title contains this text.
+    q: Option<String>,
+}
+
+/// GET /api/user/conversations — The caller's conversations, most recently
+/// updated first.
+async fn list_conversations(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Query(q): Query<ListQuery>,
+) -> impl IntoResponse {
+    let limit = q.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    let offset = q.offset.unwrap_or(0).max(0);
+    let title_pattern = q.q.as_deref().map(|t| format!("%{t}%"));
+
+    // One extra row tells whether there is another page
+    let sql = format!(
+        "{CONVERSATION_SELECT} WHERE c.user_id = ? AND (? IS NULL OR c.title LIKE ?) \
+         GROUP BY c.id ORDER BY c.updated_at DESC, c.id LIMIT ? OFFSET ?"
+    );
+    match sqlx::query_as::<_, Conversation>(&sql)
+        .bind(&session.user_id)
+        .bind(&title_pattern)
+        .bind(&title_pattern)
+        .bind(limit + 1)
+        .bind(offset)
+        .fetch_all(&state.db.pool)
+        .await
+    {
+        Ok(mut conversations) => {
+            let has_more = conversations.len() as i64 > limit;
+            conversations.truncate(limit as usize);
+            Json(json!({
+                "conversations": conversations,
+                "limit": limit,
+                "offset": offset,
+                "has_more": has_more,
+            }))
+            .into_response()
+        }
+        Err(e) => error::internal_error("conversation_list", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateConversationRequest {
+    #[serde(default)]
+    title: String,
+    model: Option<String>,
+    #[serde(default)]
+    messages: Vec<NewMessage>,
+}
+
+/// POST /api/user/conversations — Start a conversation, optionally with
+/// its first messages.
+async fn create_conversation(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Json(req): Json<CreateConversationRequest>,
+) -> impl IntoResponse {
+    if let Some(r) = error::validate_len("title", &req.title, MAX_TITLE_LEN) {
+        return r;
+    }
+    if let Err(msg) = validate_messages(&req.messages) {
+        return bad_request(msg);
+    }
+
+    let id = Uuid::new_v4().to_string();
+    if let Err(e) =
+        sqlx::query("INSERT INTO conversations (id, user_id, title, model) VALUES (?, ?, ?, ?)")
+            .bind(&id)
+            .bind(&session.user_id)
+            .bind(req.title.trim())
+            .bind(&req.model)
+            .execute(&state.db.pool)
+            .await
+    {
+        return error::internal_error("conversation_create", e);
+    }
+    if !req.messages.is_empty() {
+        if let Err(e) = append_messages(&state.db.pool, &id, &req.messages).await {
+            return error::internal_error("conversation_create:messages", e);
+        }
+    }
+
+    info!(user = %session.user_id, conversation = %id, "Created conversation");
+    let mut response = conversation_json(&state.db.pool, &session.user_id, &id).await;
+    if response.status().is_success() {
+        *response.status_mut() = StatusCode::CREATED;
+    }
+    response
+}
+
+/// GET /api/user/conversations/:id — A conversation with all its messages.
+async fn get_conversation(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    conversation_json(&state.db.pool, &session.user_id, &id).await
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateConversationRequest {
+    title: Option<String>,
+    model: Option<String>,
+}
+
+/// PUT /api/user/conversations/:id — Rename a conversation or change its
+/// model.
+async fn update_conversation(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(req): Json<UpdateConversationRequest>,
+) -> impl IntoResponse {
+    if let Some(title) = &req.title {
+        if let Some(r) = error::validate_len("title", title, MAX_TITLE_LEN) {
+            return r;
+        }
+    }
+    match sqlx::query(
+        "UPDATE conversations SET title = COALESCE(?, title), model = COALESCE(?, model), \
+         updated_at = datetime('now') WHERE id = ? AND user_id = ?",
+    )
+    .bind(req.title.as_deref().map(str::trim))
+    .bind(&req.model)
+    .bind(&id)
+    .bind(&session.user_id)
+    .execute(&state.db.pool)
+    .await
+    {
+        Ok(r) if r.rows_affected() == 0 => not_found(),
+        Ok(_) => match fetch_conversation(&state.db.pool, &session.user_id, &id).await {
+            Ok(Some(c)) => Json(json!({ "conversation": c })).into_response(),
+            Ok(None) => not_found(),
+            Err(e) => error::internal_error("conversation_update", e),
+        },
+        Err(e) => error::internal_error("conversation_update", e),
+    }
+}
+
+/// DELETE /api/user/conversations/:id — Delete a conversation and its
+/// messages.
+async fn delete_conversation(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match sqlx::query("DELETE FROM conversations WHERE id = ? AND user_id = ?")
+        .bind(&id)
+        .bind(&session.user_id)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => not_found(),
+        Ok(_) => {
+            info!(user = %session.user_id, conversation = %id, "Deleted conversation");
+            Json(json!({ "status": "deleted" })).into_response()
+        }
+        Err(e) => error::internal_error("conversation_delete", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AppendRequest {
+    messages: Vec<NewMessage>,
+}
+
+/// POST /api/user/conversations/:id/messages — Append messages.
+async fn append(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(req): Json<AppendRequest>,
+) -> impl IntoResponse {
+    if req.messages.is_empty() {
+        return bad_request("messages must not be empty".to_string());
+    }
+    if let Err(msg) = validate_messages(&req.messages) {
+        return bad_request(msg);
+    }
+    match fetch_conversation(&state.db.pool, &session.user_id, &id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return not_found(),
+        Err(e) => return error::internal_error("conversation_append", e),
+    }
+    if let Err(e) = append_messages(&state.db.pool, &id, &req.messages).await {
+        return error::internal_error("conversation_append", e);
+    }
+    match fetch_conversation(&state.db.pool, &session.user_id, &id).await {
+        Ok(Some(c)) => Json(json!({ "conversation": c })).into_response(),
+        Ok(None) => not_found(),
+        Err(e) => error::internal_error("conversation_append", e),
+    }
+}
+
+/// Render a conversation as Markdown, one section per message.
+fn to_markdown(conversation: &Conversation, messages: &[Message]) -> String {
+    let title = if conversation.title.is_empty() {
+        "Untitled conversation"
+    } else {
+        &conversation.title
+    };
+    let mut out = format!("# {title}\n\n");
+    if let Some(model) = &conversation.model {
+        out.push_str(&format!("Model: {model}  \n"));
+    }
+    out.push_str(&format!(
+        "Created: {}  \nTokens: {} prompt, {} completion\n",
+        conversation.created_at, conversation.prompt_tokens, conversation.completion_tokens
+    ));
+    for m in messages {
+        let content = match serde_json::from_str::<Value>(&m.content) {
+            Ok(Value::String(text)) => text,
+            // Content parts: keep the text parts, note the rest
+            Ok(Value::Array(parts)) => parts
+                .iter()
+                .map(|p| match p["text"].as_str() {
+                    Some(text) => text.to_string(),
+                    None => format!("[{}]", p["type"].as_str().unwrap_or("attachment")),
+                })
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+            _ => m.content.clone(),
+        };
+        out.push_str(&format!("\n## {}\n\n{}\n", m.role, content));
+    }
+    out
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportQuery {
+    /// `json` (default) or `markdown`.
+    format: Option<String>,
+}
+
+/// GET /api/user/conversations/:id/export — Download a conversation as a
+/// JSON or Markdown file.
+async fn export(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Query(q): Query<ExportQuery>,
+) -> impl IntoResponse {
+    let format = q.format.as_deref().unwrap_or("json");
+    if format != "json" && format != "markdown" {
+        return bad_request("format must be 'json' or 'markdown'".to_string());
+    }
+    let conversation = match fetch_conversation(&state.db.pool, &session.user_id, &id).await {
+        Ok(Some(c)) => c,
+        Ok(None) => return not_found(),
+        Err(e) => return error::internal_error("conversation_export", e),
+    };
+    let messages = match fetch_messages(&state.db.pool, &id).await {
+        Ok(m) => m,
+        Err(e) => return error::internal_error("conversation_export", e),
+    };
+
+    let (content_type, extension, body) = if format == "markdown" {
+        (
+            "text/markdown; charset=utf-8",
+            "md",
+            to_markdown(&conversation, &messages),
+        )
+    } else {
+        let doc = json!({ "conversation": conversation, "messages": messages });
+        (
+            "application/json",
+            "json",
+            serde_json::to_string_pretty(&doc).unwrap_or_default(),
+        )
+    };
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"conversation-{id}.{extension}\""),
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    fn message(role: &str, content: Value, model: Option<&str>, tokens: (i64, i64)) -> NewMessage {
+        NewMessage {
+            role: role.to_string(),
+            content,
+            model: model.map(str::to_string),
+            prompt_tokens: tokens.0,
+            completion_tokens: tokens.1,
+        }
+    }
+
+    #[test]
+    fn messages_are_validated() {
+        let ok = [message("user", json!("hi"), None, (0, 0))];
+        assert!(validate_messages(&ok).is_ok());
+        let bad_role = [message("robot", json!("hi"), None, (0, 0))];
+        assert!(validate_messages(&bad_role).unwrap_err().contains("role"));
+        let bad_content = [message("user", json!(42), None, (0, 0))];
+        assert!(validate_messages(&bad_content)
+            .unwrap_err()
+            .contains("content"));
+        let negative = [message("assistant", json!("x"), None, (-1, 0))];
+        assert!(validate_messages(&negative).is_err());
+    }
+
+    #[tokio::test]
+    async fn appended_messages_update_totals_and_model() {
+        let db = Database::test_db().await;
+        sqlx::query(
+            "INSERT INTO idp_configs (id, name, issuer, client_id, client_secret_enc) \
+             VALUES ('idp', 'test', 'https://test', 'client', 'secret')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO users (id, idp_id, subject) VALUES ('u1', 'idp', 'u1')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO conversations (id, user_id, title) VALUES ('c1', 'u1', 'Plans')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        append_messages(
+            &db.pool,
+            "c1",
+            &[
+                message("user", json!("Hello"), None, (0, 0)),
+                message(
+                    "assistant",
+                    json!([{ "type": "text", "text": "Hi!" }, { "type": "image_url" }]),
+                    Some("llama-3"),
+                    (12, 3),
+                ),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let conversation = fetch_conversation(&db.pool, "u1", "c1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(conversation.message_count, 2);
+        assert_eq!(conversation.prompt_tokens, 12);
+        assert_eq!(conversation.completion_tokens, 3);
+        assert_eq!(conversation.model.as_deref(), Some("llama-3"));
+        assert!(fetch_conversation(&db.pool, "someone-else", "c1")
+            .await
+            .unwrap()
+            .is_none());
+
+        let messages = fetch_messages(&db.pool, "c1").await.unwrap();
+        let markdown = to_markdown(&conversation, &messages);
+        assert!(markdown.starts_with("# Plans\n"));
+        assert!(markdown.contains("## user\n\nHello\n"));
+        assert!(markdown.contains("## assistant\n\nHi!\n\n[image_url]\n"));
+    }
+}
@@ -0,0 +1,529 @@
+//! Time-boxed admin rights ("just-in-time" admin).
+//!
+//! Instead of a permanent `is_admin` bit, a user can ask for admin rights for
+//! a limited time (`POST /api/user/elevation`). Once a full admin approves,
+//! the user's sessions act as admin until `duration_minutes` have passed.
+//! Nothing has to run for the rights to end: sessions check the expiry on
+//! every request. Admins can end an elevation early and users can give theirs
+//! up. Requests nobody decides lapse after `APPROVAL_WINDOW_MINUTES`. Every
+//! step is audit-logged; ends by expiry are logged by the hourly cleanup.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+use super::error;
+use crate::auth::SessionAuth;
+use crate::db::Database;
+use crate::AppState;
+
+const DEFAULT_MINUTES: i64 = 240;
+const MAX_MINUTES: i64 = 12 * 60;
+
+pub fn user_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/elevation",
+            get(my_elevations)
+                .post(request_elevation)
+                .delete(end_my_elevation),
+        )
+        .with_state(state)
+}
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/elevations", get(list_elevations))
+        .route("/elevations/{id}", delete(revoke))
+        .route("/elevations/{id}/approve", post(approve))
+        .route("/elevations/{id}/reject", post(reject))
+        .with_state(state)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Elevation {
+    id: String,
+    user_id: String,
+    reason: String,
+    duration_minutes: i64,
+    status: String,
+    decided_by: Option<String>,
+    created_at: String,
+    expires_at: String,
+    decided_at: Option<String>,
+}
+
+/// Mark pending requests and active elevations whose time is up as expired,
+/// audit-logging each. Returns how many were expired.
+pub async fn expire(db: &Database) -> Result<u64> {
+    let due: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT id, user_id, status FROM admin_elevations \
+         WHERE status IN ('pending', 'active') AND expires_at <= datetime('now')",
+    )
+    .fetch_all(&db.pool)
+    .await?;
+
+    let mut expired = 0;
+    for (id, user_id, status) in due {
+        let result = sqlx::query(
+            "UPDATE admin_elevations SET status = 'expired' WHERE id = ? AND status = ?",
+        )
+        .bind(&id)
+        .bind(&status)
+        .execute(&db.pool)
+        .await?;
+        if result.rows_affected() > 0 {
+            expired += 1;
+            if status == "active" {
+                info!(target: "audit", action = "admin_elevation.expire", actor = "system", resource = %id, user = %user_id, "Time-boxed admin rights ended");
+            } else {
+                info!(target: "audit", action = "admin_elevation.lapse", actor = "system", resource = %id, user = %user_id, "Admin elevation request lapsed undecided");
+            }
+        }
+    }
+    Ok(expired)
+}
+
+async fn fetch(pool: &sqlx::SqlitePool, id: &str) -> sqlx::Result<Option<Elevation>> {
+    sqlx::query_as("SELECT * FROM admin_elevations WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// The user's pending request or live elevation, if any.
+async fn open_for_user(pool: &sqlx::SqlitePool, user_id: &str) -> sqlx::Result<Option<Elevation>> {
+    sqlx::query_as(
+        "SELECT * FROM admin_elevations \
+         WHERE user_id = ? AND status IN ('pending', 'active') AND expires_at > datetime('now')",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Whether `user_id` has the permanent admin flag, as opposed to admin
+/// rights from an elevation.
+pub(crate) async fn is_permanent_admin(
+    pool: &sqlx::SqlitePool,
+    user_id: &str,
+) -> sqlx::Result<bool> {
+    let is_admin: Option<bool> = sqlx::query_scalar("SELECT is_admin FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(is_admin.unwrap_or(false))
+}
+
+fn status_error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+// ---------------------------------------------------------------------------
+// User endpoints
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+struct ElevationRequest {
+    reason: String,
+    duration_minutes: Option<i64>,
+}
+
+/// POST /api/user/elevation — Ask for admin rights for a limited time.
+async fn request_elevation(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Json(req): Json<ElevationRequest>,
+) -> Response {
+    let minutes = req.duration_minutes.unwrap_or(DEFAULT_MINUTES);
+    if !(1..=MAX_MINUTES).contains(&minutes) {
+        return status_error(
+            StatusCode::BAD_REQUEST,
+            &format!("duration_minutes must be between 1 and {MAX_MINUTES}"),
+        );
+    }
+    if req.reason.trim().is_empty() {
+        return status_error(StatusCode::BAD_REQUEST, "reason is required");
+    }
+    if let Some(r) = error::validate_len("reason", &req.reason, error::MAX_DESCRIPTION) {
+        return r;
+    }
+
+    let pool = &state.db.pool;
+    let permanent = match is_permanent_admin(pool, &session.user_id).await {
+        Ok(permanent) => permanent,
+        Err(e) => return error::internal_error("request_elevation", e),
+    };
+    if permanent {
+        return status_error(StatusCode::BAD_REQUEST, "You already have admin rights");
+    }
+    match open_for_user(pool, &session.user_id).await {
+        Ok(Some(open)) => {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": format!("You already have an elevation that is {}", open.status),
+                    "elevation": open,
+                })),
+            )
+                .into_response();
+        }
+        Ok(None) => {}
+        Err(e) => return error::internal_error("request_elevation", e),
+    }
+
+    let id = Uuid::new_v4().to_string();
+    if let Err(e) = sqlx::query(
+        "INSERT INTO admin_elevations (id, user_id, reason, duration_minutes, expires_at) \
+         VALUES (?, ?, ?, ?, datetime('now', ?))",
+    )
+    .bind(&id)
+    .bind(&session.user_id)
+    .bind(req.reason.trim())
+    .bind(minutes)
+    .bind(format!("+{} minutes", state.config.approval_window_minutes))
+    .execute(pool)
+    .await
+    {
+        return error::internal_error("request_elevation", e);
+    }
+    info!(target: "audit", action = "admin_elevation.request", actor = %session.user_id, resource = %id, duration_minutes = minutes, "User requested time-boxed admin rights");
+
+    match fetch(pool, &id).await {
+        Ok(elevation) => {
+            (StatusCode::CREATED, Json(json!({ "elevation": elevation }))).into_response()
+        }
+        Err(e) => error::internal_error("request_elevation", e),
+    }
+}
+
+/// GET /api/user/elevation — The caller's elevation requests, newest first.
+async fn my_elevations(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+) -> Response {
+    if let Err(e) = expire(&state.db).await {
+        return error::internal_error("my_elevations", e);
+    }
+    match sqlx::query_as::<_, Elevation>(
+        "SELECT * FROM admin_elevations WHERE user_id = ? ORDER BY created_at DESC, id LIMIT 20",
+    )
+    .bind(&session.user_id)
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(elevations) => Json(json!({ "elevations": elevations })).into_response(),
+        Err(e) => error::internal_error("my_elevations", e),
+    }
+}
+
+/// DELETE /api/user/elevation — Withdraw a pending request, or give up live
+/// admin rights before they run out.
+async fn end_my_elevation(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+) -> Response {
+    let open = match open_for_user(&state.db.pool, &session.user_id).await {
+        Ok(Some(open)) => open,
+        Ok(None) => return status_error(StatusCode::NOT_FOUND, "No pending or active elevation"),
+        Err(e) => return error::internal_error("end_my_elevation", e),
+    };
+    let status = if open.status == "active" {
+        "revoked"
+    } else {
+        "withdrawn"
+    };
+    match end(
+        &state.db.pool,
+        &open.id,
+        &open.status,
+        status,
+        &session.user_id,
+    )
+    .await
+    {
+        Ok(true) => {
+            info!(target: "audit", action = "admin_elevation.withdraw", actor = %session.user_id, resource = %open.id, was = %open.status, "User ended their admin elevation");
+            Json(json!({ "status": status })).into_response()
+        }
+        Ok(false) => status_error(StatusCode::CONFLICT, "Elevation changed; try again"),
+        Err(e) => error::internal_error("end_my_elevation", e),
+    }
+}
+
+/// Move an elevation from `from` to a final `status`. False if it changed
+/// in the meantime.
+async fn end(
+    pool: &sqlx::SqlitePool,
+    id: &str,
+    from: &str,
+    status: &str,
+    decided_by: &str,
+) -> sqlx::Result<bool> {
+    let result = sqlx::query(
+        "UPDATE admin_elevations SET status = ?, decided_by = ?, decided_at = datetime('now') \
+         WHERE id = ? AND status = ?",
+    )
+    .bind(status)
+    .bind(decided_by)
+    .bind(id)
+    .bind(from)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+// ---------------------------------------------------------------------------
+// Admin endpoints
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Deserialize)]
+struct ListQuery {
+    status: Option<String>,
+}
+
+/// GET /api/admin/elevations?status=pending — Elevation requests, newest
+/// first.
+async fn list_elevations(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListQuery>,
+) -> Response {
+    if let Err(e) = expire(&state.db).await {
+        return error::internal_error("list_elevations", e);
+    }
+    match sqlx::query_as::<_, Elevation>(
+        "SELECT * FROM admin_elevations WHERE (?1 IS NULL OR status = ?1) \
+         ORDER BY created_at DESC, id LIMIT 200",
+    )
+    .bind(&query.status)
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(elevations) => Json(json!({ "elevations": elevations })).into_response(),
+        Err(e) => error::internal_error("list_elevations", e),
+    }
+}
+
+/// Load an elevation in `status` (after expiring stale ones), or the
+/// response explaining why it isn't.
+async fn elevation_in(state: &AppState, id: &str, status: &str) -> Result<Elevation, Response> {
+    if let Err(e) = expire(&state.db).await {
+        return Err(error::internal_error("elevations:expire", e));
+    }
+    match fetch(&state.db.pool, id).await {
+        Ok(Some(elevation)) if elevation.status == status => Ok(elevation),
+        Ok(Some(elevation)) => Err(status_error(
+            StatusCode::CONFLICT,
+            &format!("Elevation is {}", elevation.status),
+        )),
+        Ok(None) => Err(status_error(StatusCode::NOT_FOUND, "Elevation not found")),
+        Err(e) => Err(error::internal_error("elevations:fetch", e)),
+    }
+}
+
+/// POST /api/admin/elevations/:id/approve — Grant the requested admin time,
+/// starting now.
+///
+/// Only admins with the permanent flag can approve, so elevated users can't
+/// extend each other.
+async fn approve(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> Response {
+    let elevation = match elevation_in(&state, &id, "pending").await {
+        Ok(elevation) => elevation,
+        Err(r) => return r,
+    };
+    let permanent = match is_permanent_admin(&state.db.pool, &session.user_id).await {
+        Ok(permanent) => permanent,
+        Err(e) => return error::internal_error("approve_elevation", e),
+    };
+    if !permanent {
+        return status_error(
+            StatusCode::FORBIDDEN,
+            "Only permanent admins can approve elevations",
+        );
+    }
+
+    let result = sqlx::query(
+        "UPDATE admin_elevations SET status = 'active', decided_by = ?, \
+             decided_at = datetime('now'), expires_at = datetime('now', ?) \
+         WHERE id = ? AND status = 'pending'",
+    )
+    .bind(&session.user_id)
+    .bind(format!("+{} minutes", elevation.duration_minutes))
+    .bind(&id)
+    .execute(&state.db.pool)
+    .await;
+    match result {
+        Ok(r) if r.rows_affected() == 0 => {
+            return status_error(
+                StatusCode::CONFLICT,
+                "Elevation was decided by another admin",
+            )
+        }
+        Ok(_) => {}
+        Err(e) => return error::internal_error("approve_elevation", e),
+    }
+    info!(target: "audit", action = "admin_elevation.approve", actor = %session.user_id, resource = %id, user = %elevation.user_id, duration_minutes = elevation.duration_minutes, "Admin granted time-boxed admin rights");
+
+    match fetch(&state.db.pool, &id).await {
+        Ok(elevation) => Json(json!({ "elevation": elevation })).into_response(),
+        Err(e) => error::internal_error("approve_elevation", e),
+    }
+}
+
+/// POST /api/admin/elevations/:id/reject — Turn down a pending request.
+async fn reject(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> Response {
+    let elevation = match elevation_in(&state, &id, "pending").await {
+        Ok(elevation) => elevation,
+        Err(r) => return r,
+    };
+    match end(&state.db.pool, &id, "pending", "rejected", &session.user_id).await {
+        Ok(true) => {
+            info!(target: "audit", action = "admin_elevation.reject", actor = %session.user_id, resource = %id, user = %elevation.user_id, "Admin rejected an elevation request");
+            Json(json!({ "status": "rejected" })).into_response()
+        }
+        Ok(false) => status_error(
+            StatusCode::CONFLICT,
+            "Elevation was decided by another admin",
+        ),
+        Err(e) => error::internal_error("reject_elevation", e),
+    }
+}
+
+/// DELETE /api/admin/elevations/:id — End live admin rights early.
+async fn revoke(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> Response {
+    let elevation = match elevation_in(&state, &id, "active").await {
+        Ok(elevation) => elevation,
+        Err(r) => return r,
+    };
+    match end(&state.db.pool, &id, "active", "revoked", &session.user_id).await {
+        Ok(true) => {
+            info!(target: "audit", action = "admin_elevation.revoke", actor = %session.user_id, resource = %id, user = %elevation.user_id, "Admin ended time-boxed admin rights early");
+            Json(json!({ "status": "revoked" })).into_response()
+        }
+        Ok(false) => status_error(StatusCode::CONFLICT, "Elevation already ended"),
+        Err(e) => error::internal_error("revoke_elevation", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::sessions;
+
+    async fn user_with_session(db: &Database) -> String {
+        sqlx::query(
+            "INSERT INTO idp_configs (id, name, issuer, client_id, client_secret_enc) \
+             VALUES ('idp', 'test', 'https://test', 'client', 'secret')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO users (id, idp_id, subject) VALUES ('u1', 'idp', 'u1')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sessions::create_session(&sessions::Sessions::sqlite(db), "u1", None)
+            .await
+            .unwrap()
+    }
+
+    async fn insert(db: &Database, id: &str, status: &str, expires: &str) {
+        sqlx::query(
+            "INSERT INTO admin_elevations (id, user_id, duration_minutes, status, expires_at) \
+             VALUES (?, 'u1', 60, ?, datetime('now', ?))",
+        )
+        .bind(id)
+        .bind(status)
+        .bind(expires)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn active_elevation_makes_session_admin_until_expiry() {
+        let db = Database::test_db().await;
+        let token = user_with_session(&db).await;
+        let store = sessions::Sessions::sqlite(&db);
+        assert!(
+            !sessions::validate_session(&store, &db, &token)
+                .await
+                .unwrap()
+                .is_admin
+        );
+
+        insert(&db, "pending", "pending", "+1 hour").await;
+        assert!(
+            !sessions::validate_session(&store, &db, &token)
+                .await
+                .unwrap()
+                .is_admin
+        );
+
+        insert(&db, "live", "active", "+1 hour").await;
+        assert!(
+            sessions::validate_session(&store, &db, &token)
+                .await
+                .unwrap()
+                .is_admin
+        );
+
+        sqlx::query("UPDATE admin_elevations SET expires_at = datetime('now', '-1 second')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert!(
+            !sessions::validate_session(&store, &db, &token)
+                .await
+                .unwrap()
+                .is_admin
+        );
+    }
+
+    #[tokio::test]
+    async fn expire_marks_due_elevations() {
+        let db = Database::test_db().await;
+        user_with_session(&db).await;
+        insert(&db, "lapsed", "pending", "-1 minute").await;
+        insert(&db, "ended", "active", "-1 minute").await;
+        insert(&db, "live", "active", "+1 hour").await;
+        insert(&db, "rejected", "rejected", "-1 minute").await;
+
+        assert_eq!(expire(&db).await.unwrap(), 2);
+        let statuses: Vec<(String, String)> =
+            sqlx::query_as("SELECT id, status FROM admin_elevations ORDER BY id")
+                .fetch_all(&db.pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            statuses,
+            vec![
+                ("ended".to_string(), "expired".to_string()),
+                ("lapsed".to_string(), "expired".to_string()),
+                ("live".to_string(), "active".to_string()),
+                ("rejected".to_string(), "rejected".to_string()),
+            ]
+        );
+    }
+}
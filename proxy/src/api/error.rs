@@ -51,6 +51,26 @@ pub fn validate_hf_repo(value: &str) -> Option<Response> {
     None
 }
 
+/// Validate a `backend_type` against the backends the DockerManager can run.
+/// Returns `Some(Response)` with a 400 error if unknown, `None` if OK.
+pub fn validate_backend_type(value: &str) -> Option<Response> {
+    if crate::docker::BACKEND_TYPES.contains(&value) {
+        return None;
+    }
+    Some(
+        (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!(
+                    "Unknown backend_type '{value}' (expected one of: {})",
+                    crate::docker::BACKEND_TYPES.join(", ")
+                )
+            })),
+        )
+            .into_response(),
+    )
+}
+
 /// Return a generic 500 response, logging the real error server-side.
 pub fn internal_error(context: &str, err: impl std::fmt::Display) -> Response {
     error!(context = context, error = %err, "Internal error");
@@ -157,4 +177,20 @@ mod tests {
         // "a/b/c" passes the current validation.
         assert!(validate_hf_repo("a/b/c").is_none());
     }
+
+    // -----------------------------------------------------------------------
+    // validate_backend_type
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn validate_backend_type_known() {
+        assert!(validate_backend_type("llamacpp").is_none());
+        assert!(validate_backend_type("vllm").is_none());
+    }
+
+    #[test]
+    fn validate_backend_type_unknown_rejected() {
+        assert!(validate_backend_type("ollama").is_some());
+        assert!(validate_backend_type("").is_some());
+    }
 }
@@ -0,0 +1,156 @@
+//! Admin API for feature flags (see `crate::feature_flags`).
+//!
+//! `PUT /api/admin/feature-flags/{name}` creates or replaces a flag, so a
+//! rollout is stepped up by re-sending it with a higher `rollout_percent`.
+//! Every change reloads the in-memory cache handlers read from and is
+//! audit-logged.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{error, info};
+
+use super::error;
+use crate::auth::SessionAuth;
+use crate::feature_flags;
+use crate::AppState;
+
+/// Most users a flag can target by ID.
+const MAX_TARGET_USERS: usize = 1000;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/feature-flags", get(list_flags))
+        .route("/feature-flags/{name}", put(put_flag).delete(delete_flag))
+        .with_state(state)
+}
+
+#[derive(Debug, Deserialize)]
+struct FlagRequest {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    enabled: bool,
+    #[serde(default)]
+    rollout_percent: u8,
+    #[serde(default)]
+    user_ids: Vec<String>,
+}
+
+fn bad_request(message: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": message }))).into_response()
+}
+
+/// Flag names are what handlers check for, so keep them identifier-like.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Refresh the cache handlers read after a change.
+async fn reload_flags(state: &AppState) {
+    if let Err(e) = state.flags.reload(&state.db).await {
+        error!(error = %e, "Failed to reload feature flags");
+    }
+}
+
+/// GET /api/admin/feature-flags — List feature flags.
+async fn list_flags(State(state): State<Arc<AppState>>) -> Response {
+    match feature_flags::load_flags(&state.db).await {
+        Ok(flags) => Json(json!({ "flags": flags })).into_response(),
+        Err(e) => error::internal_error("feature_flags:list", e),
+    }
+}
+
+/// PUT /api/admin/feature-flags/:name — Create or replace a flag.
+async fn put_flag(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(name): Path<String>,
+    Json(req): Json<FlagRequest>,
+) -> Response {
+    if let Some(r) = error::validate_len("name", &name, error::MAX_NAME) {
+        return r;
+    }
+    if !valid_name(&name) {
+        return bad_request(
+            "Flag names may only contain lowercase letters, digits, '_', '-' and '.'",
+        );
+    }
+    if let Some(r) = error::validate_len("description", &req.description, error::MAX_DESCRIPTION) {
+        return r;
+    }
+    if req.rollout_percent > 100 {
+        return bad_request("rollout_percent must be between 0 and 100");
+    }
+    if req.user_ids.len() > MAX_TARGET_USERS {
+        return bad_request(&format!(
+            "A flag can target at most {MAX_TARGET_USERS} users"
+        ));
+    }
+
+    let user_ids = serde_json::to_string(&req.user_ids).unwrap_or_else(|_| "[]".to_string());
+    if let Err(e) = sqlx::query(
+        "INSERT INTO feature_flags (name, description, enabled, rollout_percent, user_ids, created_by) \
+         VALUES (?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(name) DO UPDATE SET description = excluded.description, \
+         enabled = excluded.enabled, rollout_percent = excluded.rollout_percent, \
+         user_ids = excluded.user_ids, updated_at = datetime('now')",
+    )
+    .bind(&name)
+    .bind(&req.description)
+    .bind(req.enabled)
+    .bind(req.rollout_percent)
+    .bind(&user_ids)
+    .bind(&session.user_id)
+    .execute(&state.db.pool)
+    .await
+    {
+        return error::internal_error("feature_flags:put", e);
+    }
+    reload_flags(&state).await;
+
+    info!(target: "audit", action = "feature_flag.update", actor = %session.user_id, resource = %name, enabled = req.enabled, rollout_percent = req.rollout_percent, targeted_users = req.user_ids.len(), "Admin updated feature flag");
+
+    match feature_flags::load_flags(&state.db).await {
+        Ok(flags) => match flags.into_iter().find(|f| f.name == name) {
+            Some(flag) => Json(flag).into_response(),
+            None => error::internal_error("feature_flags:put", "flag missing after upsert"),
+        },
+        Err(e) => error::internal_error("feature_flags:put", e),
+    }
+}
+
+/// DELETE /api/admin/feature-flags/:name — Remove a flag; checks for it
+/// are then off.
+async fn delete_flag(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(name): Path<String>,
+) -> Response {
+    match sqlx::query("DELETE FROM feature_flags WHERE name = ?")
+        .bind(&name)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Feature flag not found" })),
+        )
+            .into_response(),
+        Ok(_) => {
+            reload_flags(&state).await;
+            info!(target: "audit", action = "feature_flag.delete", actor = %session.user_id, resource = %name, "Admin deleted feature flag");
+            Json(json!({ "status": "deleted" })).into_response()
+        }
+        Err(e) => error::internal_error("feature_flags:delete", e),
+    }
+}
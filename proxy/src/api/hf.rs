@@ -1,17 +1,28 @@
-use std::collections::HashMap;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
 use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::IntoResponse;
 use axum::routing::{delete, get, post};
-use axum::{Json, Router};
+use axum::{middleware, Extension, Json, Router};
+use chrono::Utc;
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tokio::sync::RwLock;
+use tokio_stream::wrappers::BroadcastStream;
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use super::categorize;
+use crate::auth::rbac::{self, Permission};
+use crate::auth::SessionAuth;
+use crate::db::Database;
+use crate::events::{AppEvent, EventBus};
+use crate::scheduler::gate::ConcurrencyGate;
+use crate::scheduler::Scheduler;
 use crate::AppState;
 
 // ---------------------------------------------------------------------------
@@ -39,8 +50,137 @@ pub enum DownloadStatus {
     Cancelled,
 }
 
+impl DownloadStatus {
+    fn as_str(&self) -> &'static str {
+        match self {
+            DownloadStatus::Downloading => "downloading",
+            DownloadStatus::Complete => "complete",
+            DownloadStatus::Failed => "failed",
+            DownloadStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
 pub type Downloads = Arc<RwLock<HashMap<String, DownloadState>>>;
 
+/// Seconds between checks for download changes to push over SSE.
+const DOWNLOAD_PUSH_INTERVAL_SECS: u64 = 1;
+
+/// The fields whose change is pushed to download SSE clients.
+type DownloadFingerprint = (u64, u64, DownloadStatus, Option<String>);
+
+/// Spawn the background collector task watching `downloads`, which also
+/// keeps `progress` current. Every second it publishes an
+/// [`AppEvent::DownloadUpdated`] for each download whose progress or status
+/// changed, so a busy download pushes at most one event per interval.
+fn spawn_collector(events: EventBus, downloads: Downloads, progress: DownloadProgress) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(DOWNLOAD_PUSH_INTERVAL_SECS));
+        let mut seen = HashMap::new();
+
+        loop {
+            interval.tick().await;
+
+            let current = downloads.read().await;
+            let changed = changed_downloads(&current, &mut seen);
+            progress.update(&current, std::time::Instant::now());
+            drop(current);
+            for dl in changed {
+                events.publish(AppEvent::DownloadUpdated {
+                    download: download_json(&dl),
+                });
+            }
+        }
+    });
+}
+
+/// Downloads whose fingerprint differs from the one in `seen` (or that are
+/// new), updating `seen` to match.
+fn changed_downloads(
+    current: &HashMap<String, DownloadState>,
+    seen: &mut HashMap<String, DownloadFingerprint>,
+) -> Vec<DownloadState> {
+    let mut changed = Vec::new();
+    for dl in current.values() {
+        let fingerprint = (
+            dl.progress_bytes,
+            dl.total_bytes,
+            dl.status.clone(),
+            dl.error.clone(),
+        );
+        if seen.get(&dl.id) != Some(&fingerprint) {
+            seen.insert(dl.id.clone(), fingerprint);
+            changed.push(dl.clone());
+        }
+    }
+    changed
+}
+
+/// When each running download last received bytes, kept by the collector so
+/// stalled downloads can be reported.
+#[derive(Debug, Default)]
+struct ProgressTracker {
+    downloads: HashMap<String, (DownloadState, std::time::Instant)>,
+}
+
+impl ProgressTracker {
+    /// Record the running downloads as of `now`. A download that is new or
+    /// whose progress moved restarts its clock.
+    fn update(&mut self, current: &HashMap<String, DownloadState>, now: std::time::Instant) {
+        self.downloads.retain(|id, _| {
+            current
+                .get(id)
+                .is_some_and(|dl| dl.status == DownloadStatus::Downloading)
+        });
+        for dl in current
+            .values()
+            .filter(|dl| dl.status == DownloadStatus::Downloading)
+        {
+            match self.downloads.get_mut(&dl.id) {
+                Some((last, _)) if last.progress_bytes == dl.progress_bytes => *last = dl.clone(),
+                _ => {
+                    self.downloads.insert(dl.id.clone(), (dl.clone(), now));
+                }
+            }
+        }
+    }
+
+    fn stalled(
+        &self,
+        after: std::time::Duration,
+        now: std::time::Instant,
+    ) -> Vec<(DownloadState, std::time::Duration)> {
+        self.downloads
+            .values()
+            .map(|(dl, since)| (dl.clone(), now.saturating_duration_since(*since)))
+            .filter(|(_, stalled)| *stalled >= after)
+            .collect()
+    }
+}
+
+/// The download collector's [`ProgressTracker`], held in `AppState`.
+#[derive(Debug, Clone, Default)]
+pub struct DownloadProgress(Arc<Mutex<ProgressTracker>>);
+
+impl DownloadProgress {
+    fn update(&self, current: &HashMap<String, DownloadState>, now: std::time::Instant) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .update(current, now);
+    }
+
+    /// Running downloads that haven't received a byte for at least `after`,
+    /// with how long they have been stalled.
+    pub fn stalled(&self, after: std::time::Duration) -> Vec<(DownloadState, std::time::Duration)> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .stalled(after, std::time::Instant::now())
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Shared state wrapper — holds Downloads + a handle to AppState
 // ---------------------------------------------------------------------------
@@ -49,6 +189,7 @@ pub type Downloads = Arc<RwLock<HashMap<String, DownloadState>>>;
 pub struct HfState {
     pub app: Arc<AppState>,
     pub downloads: Downloads,
+    pub limiter: DownloadLimiter,
 }
 
 // ---------------------------------------------------------------------------
@@ -56,17 +197,37 @@ pub struct HfState {
 // ---------------------------------------------------------------------------
 
 pub fn routes(state: Arc<AppState>) -> Router {
+    let downloads: Downloads = Arc::new(RwLock::new(HashMap::new()));
+    spawn_collector(
+        state.events.clone(),
+        downloads.clone(),
+        state.download_progress.clone(),
+    );
     let hf_state = HfState {
         app: state,
-        downloads: Arc::new(RwLock::new(HashMap::new())),
+        downloads,
+        limiter: DownloadLimiter::default(),
     };
 
     Router::new()
         .route("/search", get(search_models))
         .route("/files", get(list_repo_files))
+        .route(
+            "/recommend",
+            get(recommend_quant).post(download_recommendation),
+        )
         .route("/download", post(start_download))
         .route("/downloads", get(list_downloads))
+        .route("/downloads/stream", get(download_events))
         .route("/downloads/{id}", delete(cancel_download))
+        .route("/downloads/{id}/resume", post(resume_download))
+        .route(
+            "/import",
+            post(import_models).layer(middleware::from_fn_with_state(
+                Permission::Models,
+                rbac::require_permission,
+            )),
+        )
         .with_state(hf_state)
 }
 
@@ -290,6 +451,277 @@ async fn list_repo_files(
     Json(serde_json::json!({ "files": file_list })).into_response()
 }
 
+// ---------------------------------------------------------------------------
+// GET /recommend?repo=<repo> and POST /recommend — quant choice by GPU memory
+// ---------------------------------------------------------------------------
+
+/// Quant names recognised in GGUF filenames, used to label candidates.
+/// Longer names come before their prefixes (`Q4_K_M` before `Q4_K`).
+const KNOWN_QUANTS: &[&str] = &[
+    "IQ1_S", "IQ1_M", "IQ2_XXS", "IQ2_XS", "IQ2_S", "IQ2_M", "IQ3_XXS", "IQ3_XS", "IQ3_S", "IQ3_M",
+    "IQ4_XS", "IQ4_NL", "Q2_K_L", "Q2_K", "Q3_K_S", "Q3_K_M", "Q3_K_L", "Q3_K_XL", "Q4_0", "Q4_1",
+    "Q4_K_S", "Q4_K_M", "Q4_K_L", "Q5_0", "Q5_1", "Q5_K_S", "Q5_K_M", "Q5_K_L", "Q6_K_L", "Q6_K",
+    "Q8_0", "BF16", "F16", "F32",
+];
+
+/// Bytes fetched from the start of a GGUF file to read its header metadata.
+const GGUF_HEADER_FETCH_BYTES: usize = 16 * 1024 * 1024;
+
+/// Share of total GPU memory a recommendation may plan to use.
+const RECOMMEND_MEMORY_FRACTION: f64 = 0.9;
+
+#[derive(Debug, Deserialize)]
+struct RecommendQuery {
+    repo: String,
+    /// Defaults to the model's trained context length (what a container
+    /// start uses), or 4096 if the header can't be read.
+    context_size: Option<u64>,
+    parallel: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RecommendDownloadRequest {
+    repo: String,
+    context_size: Option<u64>,
+    parallel: Option<u64>,
+    category_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct QuantCandidate {
+    quant: Option<String>,
+    /// All shards for split models.
+    files: Vec<String>,
+    size_bytes: u64,
+    /// Weights + KV cache + runtime overhead.
+    estimated_mb: u64,
+    fits: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Recommendation {
+    repo: String,
+    context_size: u64,
+    parallel: u64,
+    gpu_total_mb: u64,
+    budget_mb: u64,
+    kv_cache_mb: u64,
+    /// False when the GGUF header couldn't be read and KV cache is unaccounted.
+    kv_estimated: bool,
+    /// Smallest first.
+    candidates: Vec<QuantCandidate>,
+    /// Largest candidate that fits the budget.
+    recommended: Option<QuantCandidate>,
+}
+
+/// GET /api/user/hf/recommend — Recommend the GGUF file in a repo that best
+/// fits the host's GPU memory at the requested context size.
+async fn recommend_quant(
+    State(_state): State<HfState>,
+    Query(params): Query<RecommendQuery>,
+) -> impl IntoResponse {
+    match build_recommendation(&params.repo, params.context_size, params.parallel).await {
+        Ok(rec) => Json(rec).into_response(),
+        Err((status, msg)) => (status, Json(serde_json::json!({ "error": msg }))).into_response(),
+    }
+}
+
+/// POST /api/user/hf/recommend — Recommend as above and queue the download.
+async fn download_recommendation(
+    State(state): State<HfState>,
+    Json(req): Json<RecommendDownloadRequest>,
+) -> impl IntoResponse {
+    let rec = match build_recommendation(&req.repo, req.context_size, req.parallel).await {
+        Ok(rec) => rec,
+        Err((status, msg)) => {
+            return (status, Json(serde_json::json!({ "error": msg }))).into_response()
+        }
+    };
+    let Some(choice) = rec.recommended.clone() else {
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": format!(
+                    "No GGUF file in {} fits {} MB of GPU memory at context {}",
+                    rec.repo, rec.budget_mb, rec.context_size
+                ),
+                "recommendation": rec,
+            })),
+        )
+            .into_response();
+    };
+
+    match queue_download(
+        &state,
+        rec.repo.clone(),
+        Some(choice.files.clone()),
+        req.category_id,
+        None,
+    )
+    .await
+    {
+        Ok(download_id) => (
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({
+                "download_id": download_id,
+                "status": "started",
+                "recommended": choice,
+            })),
+        )
+            .into_response(),
+        Err((status, body)) => (status, Json(body)).into_response(),
+    }
+}
+
+async fn build_recommendation(
+    repo: &str,
+    context_size: Option<u64>,
+    parallel: Option<u64>,
+) -> Result<Recommendation, (StatusCode, String)> {
+    if super::error::validate_hf_repo(repo).is_some() || repo.len() > super::error::MAX_NAME {
+        return Err((StatusCode::BAD_REQUEST, "Invalid repo".to_string()));
+    }
+    let client = build_hf_client(&std::env::var("HF_TOKEN").ok())
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let files = list_downloadable_files(&client, repo, &None)
+        .await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, e))?;
+    let groups = gguf_groups(&files);
+    let Some(smallest) = groups.first() else {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("No GGUF files in {repo}"),
+        ));
+    };
+
+    let gpus = crate::docker::DockerManager::gpu_all_info().await;
+    let gpu_total_mb: u64 = gpus.iter().map(|g| g.total_mb).sum();
+    if gpu_total_mb == 0 {
+        return Err((
+            StatusCode::CONFLICT,
+            "No GPU detected — cannot size a recommendation".to_string(),
+        ));
+    }
+    let budget_mb = (gpu_total_mb as f64 * RECOMMEND_MEMORY_FRACTION) as u64;
+
+    // Architecture (and so KV cache size) is the same for every quant
+    let meta = fetch_gguf_header(&client, repo, &smallest.0[0]).await;
+    let context_size = context_size
+        .or_else(|| meta.as_ref()?.context_length.map(u64::from))
+        .unwrap_or(4096)
+        .max(1);
+    let parallel = parallel.unwrap_or(1).max(1);
+    let kv_cache_mb = meta.as_ref().map_or(0, |m| {
+        super::admin::estimate_kv_cache_mb_from_gguf(m, context_size, parallel)
+    });
+
+    let candidates: Vec<QuantCandidate> = groups
+        .into_iter()
+        .map(|(files, size_bytes)| {
+            let estimated_mb =
+                size_bytes / (1024 * 1024) + kv_cache_mb + super::admin::RUNTIME_OVERHEAD_MB;
+            QuantCandidate {
+                quant: quant_label(&files[0]),
+                files,
+                size_bytes,
+                estimated_mb,
+                fits: estimated_mb <= budget_mb,
+            }
+        })
+        .collect();
+    let recommended = candidates.iter().rev().find(|c| c.fits).cloned();
+
+    Ok(Recommendation {
+        repo: repo.to_string(),
+        context_size,
+        parallel,
+        gpu_total_mb,
+        budget_mb,
+        kv_cache_mb,
+        kv_estimated: meta.is_some(),
+        candidates,
+        recommended,
+    })
+}
+
+/// Group a repo's GGUF files into downloadable units (one file, or all shards
+/// of a split model) with their total size, smallest first. `mmproj` files
+/// are skipped.
+fn gguf_groups(files: &[HfFileEntry]) -> Vec<(Vec<String>, u64)> {
+    let mut groups: HashMap<String, (Vec<String>, u64)> = HashMap::new();
+    for f in files {
+        let lower = f.path.to_lowercase();
+        if !lower.ends_with(".gguf") || lower.contains("mmproj") {
+            continue;
+        }
+        let key = shard_prefix(&f.path).unwrap_or(&f.path).to_string();
+        let entry = groups.entry(key).or_default();
+        entry.0.push(f.path.clone());
+        entry.1 += f.size.unwrap_or(0);
+    }
+    let mut groups: Vec<(Vec<String>, u64)> = groups
+        .into_values()
+        .map(|(mut files, size)| {
+            files.sort();
+            (files, size)
+        })
+        .collect();
+    groups.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    groups
+}
+
+/// Quant name in a GGUF filename, if it is a known one.
+fn quant_label(path: &str) -> Option<String> {
+    KNOWN_QUANTS
+        .iter()
+        .find(|q| quant_matches(path, q))
+        .map(|q| q.to_string())
+}
+
+/// Read a remote GGUF file's header metadata from its first bytes.
+async fn fetch_gguf_header(
+    client: &reqwest::Client,
+    repo: &str,
+    path: &str,
+) -> Option<GgufMetadata> {
+    let url = format!("https://huggingface.co/{repo}/resolve/main/{path}");
+    let resp = client
+        .get(&url)
+        .header(
+            reqwest::header::RANGE,
+            format!("bytes=0-{}", GGUF_HEADER_FETCH_BYTES - 1),
+        )
+        .send()
+        .await
+        .ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+
+    let mut buf = Vec::with_capacity(GGUF_HEADER_FETCH_BYTES);
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        buf.extend_from_slice(&chunk.ok()?);
+        // Servers that ignore Range send the whole file — stop early
+        if buf.len() >= GGUF_HEADER_FETCH_BYTES {
+            break;
+        }
+    }
+
+    let tmp = std::env::temp_dir().join(format!("sovereign-gguf-header-{}.gguf", Uuid::new_v4()));
+    tokio::fs::write(&tmp, &buf).await.ok()?;
+    let meta = read_gguf_metadata(&tmp.to_string_lossy()).await;
+    let _ = tokio::fs::remove_file(&tmp).await;
+    match meta {
+        Ok(m) => Some(m),
+        Err(e) => {
+            warn!(repo = %repo, file = %path, error = %e, "Could not read GGUF header for recommendation");
+            None
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // POST /download
 // ---------------------------------------------------------------------------
@@ -313,6 +745,90 @@ async fn start_download(
     if let Some(r) = super::error::validate_hf_repo(&req.hf_repo) {
         return r;
     }
+    if let Some(r) = req
+        .backend_type
+        .as_deref()
+        .and_then(super::error::validate_backend_type)
+    {
+        return r;
+    }
+
+    match queue_download(
+        &state,
+        req.hf_repo,
+        req.files,
+        req.category_id,
+        req.backend_type,
+    )
+    .await
+    {
+        Ok(download_id) => (
+            StatusCode::ACCEPTED,
+            Json(serde_json::json!({
+                "download_id": download_id,
+                "status": "started",
+            })),
+        )
+            .into_response(),
+        Err((status, body)) => (status, Json(body)).into_response(),
+    }
+}
+
+/// Check disk space and duplicates, then record and start a background
+/// download. Returns the download ID, or a status plus `{"error": ...}` body.
+async fn queue_download(
+    state: &HfState,
+    hf_repo: String,
+    file_filter: Option<Vec<String>>,
+    category_id: Option<String>,
+    backend_type: Option<String>,
+) -> Result<String, (StatusCode, serde_json::Value)> {
+    check_download_admission(state, &hf_repo).await?;
+
+    let download_id = Uuid::new_v4().to_string();
+
+    // Persist the request so the download can be resumed after a restart
+    let files_json = file_filter
+        .as_ref()
+        .and_then(|f| serde_json::to_string(f).ok());
+    if let Err(e) = sqlx::query(
+        "INSERT INTO downloads (id, hf_repo, files, category_id, backend_type) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&download_id)
+    .bind(&hf_repo)
+    .bind(&files_json)
+    .bind(&category_id)
+    .bind(&backend_type)
+    .execute(&state.app.db.pool)
+    .await
+    {
+        error!(hf_repo = %hf_repo, "Failed to record download: {e}");
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            serde_json::json!({ "error": "Internal server error" }),
+        ));
+    }
+
+    spawn_download(
+        state,
+        download_id.clone(),
+        hf_repo,
+        file_filter,
+        category_id,
+        backend_type,
+        0,
+    )
+    .await;
+
+    Ok(download_id)
+}
+
+/// Refuse a new (or resumed) download when the disk is nearly full or the
+/// repo is already being downloaded.
+async fn check_download_admission(
+    state: &HfState,
+    hf_repo: &str,
+) -> Result<(), (StatusCode, serde_json::Value)> {
     // Check disk space before starting
     let model_path = &state.app.config.model_path;
     match get_disk_usage(model_path) {
@@ -323,16 +839,15 @@ async fn start_download(
                 0.0
             };
             if usage_pct >= 95.0 {
-                return (
+                return Err((
                     StatusCode::INSUFFICIENT_STORAGE,
-                    Json(serde_json::json!({
+                    serde_json::json!({
                         "error": format!(
                             "Disk usage at {:.1}% — downloads blocked above 95%",
                             usage_pct
                         )
-                    })),
-                )
-                    .into_response();
+                    }),
+                ));
             }
             if usage_pct >= 90.0 {
                 warn!(
@@ -351,32 +866,41 @@ async fn start_download(
     {
         let downloads = state.downloads.read().await;
         for dl in downloads.values() {
-            if dl.hf_repo == req.hf_repo && dl.status == DownloadStatus::Downloading {
-                return (
+            if dl.hf_repo == hf_repo && dl.status == DownloadStatus::Downloading {
+                return Err((
                     StatusCode::CONFLICT,
-                    Json(serde_json::json!({
-                        "error": format!("Download already in progress for {}", req.hf_repo),
+                    serde_json::json!({
+                        "error": format!("Download already in progress for {}", hf_repo),
                         "download_id": dl.id,
-                    })),
-                )
-                    .into_response();
+                    }),
+                ));
             }
         }
     }
 
-    let download_id = Uuid::new_v4().to_string();
+    Ok(())
+}
 
-    // Create initial download state
+/// Track a download in memory and run it in the background.
+/// `progress_bytes` is what a resumed download already has on disk.
+async fn spawn_download(
+    state: &HfState,
+    download_id: String,
+    hf_repo: String,
+    file_filter: Option<Vec<String>>,
+    category_id: Option<String>,
+    backend_type: Option<String>,
+    progress_bytes: u64,
+) {
     let dl_state = DownloadState {
         id: download_id.clone(),
-        hf_repo: req.hf_repo.clone(),
-        progress_bytes: 0,
+        hf_repo: hf_repo.clone(),
+        progress_bytes,
         total_bytes: 0,
         status: DownloadStatus::Downloading,
         error: None,
-        category_id: req.category_id.clone(),
-        backend_type: req
-            .backend_type
+        category_id: category_id.clone(),
+        backend_type: backend_type
             .clone()
             .unwrap_or_else(|| "llamacpp".to_string()),
     };
@@ -389,17 +913,14 @@ async fn start_download(
     // Spawn background download task
     let downloads = state.downloads.clone();
     let app_state = state.app.clone();
-    let hf_repo = req.hf_repo.clone();
-    let file_filter = req.files.clone();
-    let category_id = req.category_id.clone();
-    let backend_type = req.backend_type.clone();
-    let dl_id = download_id.clone();
+    let limiter = state.limiter.clone();
 
     tokio::spawn(async move {
         run_download(
             app_state,
             downloads,
-            dl_id,
+            limiter,
+            download_id,
             hf_repo,
             file_filter,
             category_id,
@@ -407,81 +928,615 @@ async fn start_download(
         )
         .await;
     });
-
-    (
-        StatusCode::ACCEPTED,
-        Json(serde_json::json!({
-            "download_id": download_id,
-            "status": "started",
-        })),
-    )
-        .into_response()
 }
 
 // ---------------------------------------------------------------------------
-// GET /downloads
+// POST /import — bulk download plan from a collection or curated list (admin)
 // ---------------------------------------------------------------------------
 
-async fn list_downloads(State(state): State<HfState>) -> impl IntoResponse {
-    let downloads = state.downloads.read().await;
-    let data: Vec<serde_json::Value> = downloads
-        .values()
-        .map(|dl| {
-            serde_json::json!({
-                "id": dl.id,
-                "hf_repo": dl.hf_repo,
-                "progress_bytes": dl.progress_bytes,
-                "total_bytes": dl.total_bytes,
-                "status": dl.status,
-                "error": dl.error,
-            })
-        })
-        .collect();
+/// Quant order used when an import doesn't specify one: best size/quality
+/// trade-off first.
+const DEFAULT_QUANT_PREFERENCE: &[&str] = &["Q4_K_M", "Q5_K_M", "Q4_K_S", "Q6_K", "Q8_0", "Q4_0"];
 
-    Json(serde_json::json!({ "downloads": data }))
+/// Upper bound on repos per import request.
+const MAX_IMPORT_ITEMS: usize = 50;
+
+#[derive(Debug, Deserialize)]
+struct ImportItem {
+    hf_repo: String,
+    /// Quant for this repo only, ahead of the request-level preference.
+    quant: Option<String>,
+    category_id: Option<String>,
 }
 
-// ---------------------------------------------------------------------------
-// DELETE /downloads/:id
-// ---------------------------------------------------------------------------
+#[derive(Debug, Deserialize)]
+struct ImportRequest {
+    /// e.g. `https://huggingface.co/collections/<owner>/<slug>`
+    collection_url: Option<String>,
+    /// Curated list; combined with the collection's repos if both are given.
+    #[serde(default)]
+    repos: Vec<ImportItem>,
+    /// Quant names in order of preference, matched against GGUF filenames.
+    quant_preference: Option<Vec<String>>,
+    /// Category for items that don't set their own.
+    category_id: Option<String>,
+    /// Resolve the plan without queuing downloads.
+    #[serde(default)]
+    dry_run: bool,
+}
 
-async fn cancel_download(
+#[derive(Debug, Serialize)]
+struct ImportItemResult {
+    hf_repo: String,
+    /// `queued`, `planned` (dry run), `skipped`, or `failed`.
+    status: &'static str,
+    files: Vec<String>,
+    download_id: Option<String>,
+    error: Option<String>,
+}
+
+impl ImportItemResult {
+    fn new(hf_repo: &str, status: &'static str) -> Self {
+        Self {
+            hf_repo: hf_repo.to_string(),
+            status,
+            files: Vec::new(),
+            download_id: None,
+            error: None,
+        }
+    }
+
+    fn with_error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+}
+
+/// POST /api/user/hf/import — Resolve a HuggingFace collection and/or a
+/// curated repo list to one GGUF file per repo and queue the downloads.
+async fn import_models(
     State(state): State<HfState>,
-    Path(id): Path<String>,
+    Extension(session): Extension<SessionAuth>,
+    Json(req): Json<ImportRequest>,
 ) -> impl IntoResponse {
-    let mut downloads = state.downloads.write().await;
+    let client = match build_hf_client(&std::env::var("HF_TOKEN").ok()) {
+        Ok(c) => c,
+        Err(e) => return super::error::internal_error("hf:build_http_client", e),
+    };
 
-    match downloads.get_mut(&id) {
-        Some(dl) => {
-            if dl.status == DownloadStatus::Downloading {
-                dl.status = DownloadStatus::Cancelled;
-                info!(download_id = %id, "Download cancelled");
-                Json(serde_json::json!({ "status": "cancelled" })).into_response()
-            } else {
-                (
-                    StatusCode::CONFLICT,
-                    Json(serde_json::json!({
-                        "error": format!("Download is not active (status: {:?})", dl.status)
-                    })),
+    let mut items = req.repos;
+    if let Some(ref url) = req.collection_url {
+        let Some(slug) = collection_slug(url) else {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": "collection_url must look like https://huggingface.co/collections/<owner>/<slug>"
+                })),
+            )
+                .into_response();
+        };
+        match fetch_collection_repos(&client, &slug).await {
+            Ok(repos) => items.extend(repos.into_iter().map(|hf_repo| ImportItem {
+                hf_repo,
+                quant: None,
+                category_id: None,
+            })),
+            Err(e) => {
+                return (
+                    StatusCode::BAD_GATEWAY,
+                    Json(serde_json::json!({ "error": e })),
                 )
-                    .into_response()
+                    .into_response();
             }
         }
-        None => (
-            StatusCode::NOT_FOUND,
-            Json(serde_json::json!({ "error": "Download not found" })),
-        )
-            .into_response(),
     }
-}
-
-// ---------------------------------------------------------------------------
-// Background download task — helper functions
-// ---------------------------------------------------------------------------
 
-/// Build an HTTP client with optional HuggingFace token authentication.
-fn build_hf_client(hf_token: &Option<String>) -> Result<reqwest::Client, String> {
-    let mut client_builder = reqwest::Client::builder().user_agent("sovereign-engine/0.1");
+    if items.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "Provide a collection_url or a non-empty repos list" })),
+        )
+            .into_response();
+    }
+    if items.len() > MAX_IMPORT_ITEMS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": format!("Import is limited to {MAX_IMPORT_ITEMS} repos ({} given)", items.len())
+            })),
+        )
+            .into_response();
+    }
+
+    let preference: Vec<String> = req.quant_preference.unwrap_or_else(|| {
+        DEFAULT_QUANT_PREFERENCE
+            .iter()
+            .map(|q| q.to_string())
+            .collect()
+    });
+
+    let mut seen = HashSet::new();
+    let mut results = Vec::with_capacity(items.len());
+    for item in items {
+        let result = if !seen.insert(item.hf_repo.clone()) {
+            ImportItemResult::new(&item.hf_repo, "skipped").with_error("Duplicate entry")
+        } else {
+            let category_id = item.category_id.clone().or(req.category_id.clone());
+            import_one(&state, &client, item, &preference, category_id, req.dry_run).await
+        };
+        results.push(result);
+    }
+
+    let queued = results.iter().filter(|r| r.status == "queued").count();
+    info!(
+        target: "audit",
+        action = "hf.import",
+        actor = %session.user_id,
+        items = results.len(),
+        queued = queued,
+        dry_run = req.dry_run,
+        "Admin imported HuggingFace models"
+    );
+
+    Json(serde_json::json!({ "items": results, "queued": queued })).into_response()
+}
+
+/// Plan (and unless `dry_run`, queue) the download for one import entry.
+async fn import_one(
+    state: &HfState,
+    client: &reqwest::Client,
+    item: ImportItem,
+    preference: &[String],
+    category_id: Option<String>,
+    dry_run: bool,
+) -> ImportItemResult {
+    let repo = item.hf_repo.as_str();
+    if super::error::validate_hf_repo(repo).is_some() || repo.len() > super::error::MAX_NAME {
+        return ImportItemResult::new(repo, "failed").with_error("Invalid hf_repo");
+    }
+
+    match sqlx::query_as::<_, (i64,)>("SELECT 1 FROM models WHERE hf_repo = ? LIMIT 1")
+        .bind(repo)
+        .fetch_optional(&state.app.db.pool)
+        .await
+    {
+        Ok(Some(_)) => {
+            return ImportItemResult::new(repo, "skipped").with_error("Already registered")
+        }
+        Ok(None) => {}
+        Err(e) => {
+            error!(hf_repo = %repo, error = %e, "Import: model lookup failed");
+            return ImportItemResult::new(repo, "failed").with_error("Internal error");
+        }
+    }
+
+    let files = match list_downloadable_files(client, repo, &None).await {
+        Ok(f) => f,
+        Err(e) => return ImportItemResult::new(repo, "failed").with_error(e),
+    };
+
+    let item_preference: Vec<String> = item.quant.into_iter().chain(preference.to_vec()).collect();
+    let Some(selected) = select_gguf(&files, &item_preference) else {
+        return ImportItemResult::new(repo, "failed").with_error(format!(
+            "No GGUF file matching quant preference {}",
+            item_preference.join(", ")
+        ));
+    };
+
+    let mut result = ImportItemResult::new(repo, if dry_run { "planned" } else { "queued" });
+    result.files = selected.clone();
+    if dry_run {
+        return result;
+    }
+
+    match queue_download(state, repo.to_string(), Some(selected), category_id, None).await {
+        Ok(download_id) => {
+            result.download_id = Some(download_id);
+            result
+        }
+        Err((_, body)) => {
+            result.status = "failed";
+            result.download_id = body["download_id"].as_str().map(str::to_string);
+            result.with_error(body["error"].as_str().unwrap_or("Failed to queue download"))
+        }
+    }
+}
+
+/// Extract `<owner>/<slug>` from a collection URL (or a bare slug).
+fn collection_slug(url: &str) -> Option<String> {
+    let path = url
+        .trim()
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .trim_start_matches("huggingface.co/")
+        .trim_start_matches("collections/");
+    let path = path.split(['?', '#']).next()?.trim_end_matches('/');
+    let (owner, slug) = path.split_once('/')?;
+    let safe = |s: &str| {
+        !s.is_empty()
+            && s.chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && s != ".."
+    };
+    (safe(owner) && safe(slug)).then(|| format!("{owner}/{slug}"))
+}
+
+#[derive(Debug, Deserialize)]
+struct HfCollection {
+    #[serde(default)]
+    items: Vec<HfCollectionItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HfCollectionItem {
+    #[serde(rename = "type")]
+    item_type: String,
+    id: String,
+}
+
+/// Model repos in a HuggingFace collection (datasets, spaces, and papers are skipped).
+async fn fetch_collection_repos(
+    client: &reqwest::Client,
+    slug: &str,
+) -> Result<Vec<String>, String> {
+    let url = format!("https://huggingface.co/api/collections/{slug}");
+    let resp = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("HuggingFace API request failed: {e}"))?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        return Err(format!(
+            "HuggingFace collections API returned {status}{}",
+            hf_http_error_hint(status)
+        ));
+    }
+    let collection: HfCollection = resp
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse collection: {e}"))?;
+    Ok(collection
+        .items
+        .into_iter()
+        .filter(|i| i.item_type == "model")
+        .map(|i| i.id)
+        .collect())
+}
+
+/// Pick the GGUF file for the first quant in `preference` that the repo has.
+/// Split models return every shard of the chosen quant. Vision projector
+/// (`mmproj`) files are never selected.
+fn select_gguf(files: &[HfFileEntry], preference: &[String]) -> Option<Vec<String>> {
+    let ggufs: Vec<&str> = files
+        .iter()
+        .map(|f| f.path.as_str())
+        .filter(|p| p.to_lowercase().ends_with(".gguf") && !p.to_lowercase().contains("mmproj"))
+        .collect();
+
+    for quant in preference {
+        let first = ggufs
+            .iter()
+            .filter(|p| quant_matches(p, quant))
+            .min()
+            .copied();
+        if let Some(path) = first {
+            return Some(match shard_prefix(path) {
+                Some(prefix) => {
+                    let mut shards: Vec<String> = ggufs
+                        .iter()
+                        .filter(|p| shard_prefix(p) == Some(prefix))
+                        .map(|p| p.to_string())
+                        .collect();
+                    shards.sort();
+                    shards
+                }
+                None => vec![path.to_string()],
+            });
+        }
+    }
+    None
+}
+
+/// Whether a GGUF path names `quant` as a whole token (so `Q4_0` doesn't
+/// match `Q4_0_4_4`), case-insensitively.
+fn quant_matches(path: &str, quant: &str) -> bool {
+    let path = path.to_lowercase();
+    let quant = quant.to_lowercase();
+    if quant.is_empty() {
+        return false;
+    }
+    let is_word = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    path.match_indices(&quant).any(|(i, _)| {
+        let before = path[..i].chars().next_back();
+        let after = path[i + quant.len()..].chars().next();
+        !before.is_some_and(|c| c.is_ascii_alphanumeric()) && !after.is_some_and(is_word)
+    })
+}
+
+/// Common prefix of a split GGUF shard (`model-Q4_K_M-00001-of-00003.gguf`
+/// → `model-Q4_K_M`), or `None` for a single-file model.
+pub(super) fn shard_prefix(path: &str) -> Option<&str> {
+    let stem = path.strip_suffix(".gguf")?;
+    let (head, total) = stem.rsplit_once("-of-")?;
+    let (prefix, part) = head.rsplit_once('-')?;
+    let digits = |s: &str| s.len() == 5 && s.chars().all(|c| c.is_ascii_digit());
+    (digits(part) && digits(total)).then_some(prefix)
+}
+
+// ---------------------------------------------------------------------------
+// GET /downloads
+// ---------------------------------------------------------------------------
+
+/// Persisted downloads listed alongside this process's own — the ones a
+/// restart interrupted and that can still be resumed.
+const MAX_PERSISTED_LISTED: i64 = 50;
+
+async fn list_downloads(State(state): State<HfState>) -> impl IntoResponse {
+    let persisted = sqlx::query_as::<_, (String, String, i64, i64, String, Option<String>)>(
+        "SELECT id, hf_repo, progress_bytes, total_bytes, status, error FROM downloads
+         WHERE status IN ('failed', 'cancelled') ORDER BY updated_at DESC LIMIT ?",
+    )
+    .bind(MAX_PERSISTED_LISTED)
+    .fetch_all(&state.app.db.pool)
+    .await
+    .unwrap_or_else(|e| {
+        warn!("Failed to list persisted downloads: {e}");
+        Vec::new()
+    });
+
+    let downloads = state.downloads.read().await;
+    let mut data: Vec<serde_json::Value> = downloads.values().map(download_json).collect();
+    data.extend(
+        persisted
+            .into_iter()
+            .filter(|(id, ..)| !downloads.contains_key(id))
+            .map(
+                |(id, hf_repo, progress_bytes, total_bytes, status, error)| {
+                    serde_json::json!({
+                        "id": id,
+                        "hf_repo": hf_repo,
+                        "progress_bytes": progress_bytes,
+                        "total_bytes": total_bytes,
+                        "status": status,
+                        "error": error,
+                        "resumable": true,
+                    })
+                },
+            ),
+    );
+
+    Json(serde_json::json!({ "downloads": data }))
+}
+
+fn download_json(dl: &DownloadState) -> serde_json::Value {
+    serde_json::json!({
+        "id": dl.id,
+        "hf_repo": dl.hf_repo,
+        "progress_bytes": dl.progress_bytes,
+        "total_bytes": dl.total_bytes,
+        "status": dl.status,
+        "error": dl.error,
+        "resumable": matches!(dl.status, DownloadStatus::Failed | DownloadStatus::Cancelled),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// GET /downloads/stream (SSE)
+// ---------------------------------------------------------------------------
+
+/// Push download changes instead of polling `GET /downloads`.
+///
+/// Sends each of this process's downloads as a `"download"` event on connect,
+/// then one event per download whenever its progress or status changes.
+/// Interrupted downloads from earlier runs are only listed by `GET /downloads`.
+async fn download_events(
+    State(state): State<HfState>,
+) -> Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>> {
+    // Subscribe before taking the snapshot so no change falls in between
+    let updates = BroadcastStream::new(state.app.events.subscribe()).filter_map(|r| async move {
+        match r {
+            Ok(AppEvent::DownloadUpdated { download }) => Some(download),
+            _ => None,
+        }
+    });
+    let current: Vec<serde_json::Value> = state
+        .downloads
+        .read()
+        .await
+        .values()
+        .map(download_json)
+        .collect();
+
+    let stream = futures::stream::iter(current)
+        .chain(updates)
+        .map(|download| {
+            Ok(Event::default()
+                .event("download")
+                .data(download.to_string()))
+        });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+// ---------------------------------------------------------------------------
+// DELETE /downloads/:id
+// ---------------------------------------------------------------------------
+
+async fn cancel_download(
+    State(state): State<HfState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let mut downloads = state.downloads.write().await;
+
+    match downloads.get_mut(&id) {
+        Some(dl) => {
+            if dl.status == DownloadStatus::Downloading {
+                dl.status = DownloadStatus::Cancelled;
+                info!(download_id = %id, "Download cancelled");
+                Json(serde_json::json!({ "status": "cancelled" })).into_response()
+            } else {
+                (
+                    StatusCode::CONFLICT,
+                    Json(serde_json::json!({
+                        "error": format!("Download is not active (status: {:?})", dl.status)
+                    })),
+                )
+                    .into_response()
+            }
+        }
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Download not found" })),
+        )
+            .into_response(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// POST /downloads/:id/resume
+// ---------------------------------------------------------------------------
+
+/// Restart a failed, cancelled or restart-interrupted download under the same
+/// ID. Completed files are skipped and partial files continue with HTTP Range
+/// requests.
+async fn resume_download(
+    State(state): State<HfState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if state
+        .downloads
+        .read()
+        .await
+        .get(&id)
+        .is_some_and(|dl| dl.status == DownloadStatus::Downloading)
+    {
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({ "error": "Download is already running" })),
+        )
+            .into_response();
+    }
+
+    let row = sqlx::query_as::<
+        _,
+        (
+            String,
+            Option<String>,
+            Option<String>,
+            Option<String>,
+            String,
+            i64,
+        ),
+    >(
+        "SELECT hf_repo, files, category_id, backend_type, status, progress_bytes
+         FROM downloads WHERE id = ?",
+    )
+    .bind(&id)
+    .fetch_optional(&state.app.db.pool)
+    .await;
+
+    let (hf_repo, files, category_id, backend_type, status, progress_bytes) = match row {
+        Ok(Some(r)) => r,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "Download not found" })),
+            )
+                .into_response()
+        }
+        Err(e) => return super::error::internal_error("resume_download", e),
+    };
+    if status == DownloadStatus::Complete.as_str() {
+        return (
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({ "error": "Download is already complete" })),
+        )
+            .into_response();
+    }
+
+    if let Err((status, body)) = check_download_admission(&state, &hf_repo).await {
+        return (status, Json(body)).into_response();
+    }
+
+    if let Err(e) = sqlx::query(
+        "UPDATE downloads SET status = 'downloading', error = NULL, updated_at = datetime('now')
+         WHERE id = ?",
+    )
+    .bind(&id)
+    .execute(&state.app.db.pool)
+    .await
+    {
+        return super::error::internal_error("resume_download", e);
+    }
+
+    let file_filter = files.and_then(|f| serde_json::from_str::<Vec<String>>(&f).ok());
+    info!(download_id = %id, hf_repo = %hf_repo, "Resuming download");
+    spawn_download(
+        &state,
+        id.clone(),
+        hf_repo,
+        file_filter,
+        category_id,
+        backend_type,
+        progress_bytes.max(0) as u64,
+    )
+    .await;
+
+    (
+        StatusCode::ACCEPTED,
+        Json(serde_json::json!({
+            "download_id": id,
+            "status": "resumed",
+        })),
+    )
+        .into_response()
+}
+
+/// Mark downloads that were running when the previous process exited as
+/// failed, so they show up as resumable. Must run at startup before any
+/// download can start. Returns the number of downloads recovered.
+pub async fn recover_interrupted_downloads(db: &Database) -> anyhow::Result<u64> {
+    let result = sqlx::query(
+        "UPDATE downloads
+         SET status = 'failed',
+             error = 'Interrupted by restart — resume to continue',
+             updated_at = datetime('now')
+         WHERE status = 'downloading'",
+    )
+    .execute(&db.pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Write a download's in-memory status and progress to the `downloads` table.
+async fn persist_download_state(db: &Database, downloads: &Downloads, download_id: &str) {
+    let Some(dl) = downloads.read().await.get(download_id).cloned() else {
+        return;
+    };
+    if let Err(e) = sqlx::query(
+        "UPDATE downloads SET status = ?, error = ?, progress_bytes = ?, total_bytes = ?,
+         updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(dl.status.as_str())
+    .bind(&dl.error)
+    .bind(dl.progress_bytes as i64)
+    .bind(dl.total_bytes as i64)
+    .bind(download_id)
+    .execute(&db.pool)
+    .await
+    {
+        warn!(download_id = %download_id, "Failed to persist download state: {e}");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Background download task — helper functions
+// ---------------------------------------------------------------------------
+
+/// Build an HTTP client with optional HuggingFace token authentication.
+fn build_hf_client(hf_token: &Option<String>) -> Result<reqwest::Client, String> {
+    let mut client_builder = reqwest::Client::builder().user_agent("sovereign-engine/0.1");
 
     if let Some(ref token) = hf_token {
         let mut headers = reqwest::header::HeaderMap::new();
@@ -615,16 +1670,21 @@ async fn validate_disk_space(
 
 /// Download all files to disk with progress tracking and cancellation support.
 /// Creates the destination directory and streams each file.
-/// Returns the total bytes downloaded on success, or Err(()) if the download
-/// was cancelled or an error occurred (reported via set_download_error).
+/// Returns the total bytes downloaded and each file's SHA-256 on success, or
+/// Err(()) if the download was cancelled or an error occurred (reported via
+/// set_download_error).
+#[allow(clippy::too_many_arguments)]
 async fn download_files_to_disk(
     client: &reqwest::Client,
+    db: &Database,
     downloads: &Downloads,
     download_id: &str,
     downloadable: &[HfFileEntry],
     dest_dir: &str,
     hf_repo: &str,
-) -> Result<u64, ()> {
+    chunks: ChunkSettings,
+    throttle: &mut DownloadThrottle,
+) -> Result<(u64, HashMap<String, String>), ()> {
     if let Err(e) = tokio::fs::create_dir_all(dest_dir).await {
         set_download_error(
             downloads,
@@ -635,7 +1695,20 @@ async fn download_files_to_disk(
         return Err(());
     }
 
+    // Per-file progress rows (kept from an earlier attempt when resuming)
+    for file in downloadable {
+        let _ = sqlx::query(
+            "INSERT OR IGNORE INTO download_files (download_id, path, size_bytes) VALUES (?, ?, ?)",
+        )
+        .bind(download_id)
+        .bind(&file.path)
+        .bind(file.size.map(|s| s as i64))
+        .execute(&db.pool)
+        .await;
+    }
+
     let mut total_downloaded: u64 = 0;
+    let mut digests = HashMap::new();
 
     for file in downloadable {
         // Check for cancellation between files
@@ -644,7 +1717,7 @@ async fn download_files_to_disk(
             return Err(());
         }
 
-        total_downloaded += download_single_file(
+        let result = download_single_file(
             client,
             downloads,
             download_id,
@@ -652,11 +1725,38 @@ async fn download_files_to_disk(
             dest_dir,
             hf_repo,
             total_downloaded,
+            chunks,
+            throttle,
         )
-        .await?;
+        .await;
+
+        let (bytes_done, sha256) = match result {
+            Ok((bytes, sha256)) => (bytes, Some(sha256)),
+            Err(()) => {
+                let part = format!("{}/{}.part", dest_dir, file.path);
+                (part_file_progress(&part, file.size).await, None)
+            }
+        };
+        let _ = sqlx::query(
+            "UPDATE download_files SET bytes_done = ?, complete = ?, sha256 = ?
+             WHERE download_id = ? AND path = ?",
+        )
+        .bind(bytes_done as i64)
+        .bind(sha256.is_some())
+        .bind(&sha256)
+        .bind(download_id)
+        .bind(&file.path)
+        .execute(&db.pool)
+        .await;
+
+        let Some(sha256) = sha256 else {
+            return Err(());
+        };
+        total_downloaded += bytes_done;
+        digests.insert(file.path.clone(), sha256);
     }
 
-    Ok(total_downloaded)
+    Ok((total_downloaded, digests))
 }
 
 /// Check whether a download has been cancelled.
@@ -679,8 +1779,168 @@ fn hf_http_error_hint(status: reqwest::StatusCode) -> &'static str {
     }
 }
 
+/// How often a throttled download rechecks whether inference is running.
+const THROTTLE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Token bucket shared by every download, holding them together to the
+/// `download_max_mb_per_sec` setting outside `download_full_speed_window`.
+#[derive(Clone, Default)]
+pub struct DownloadLimiter {
+    bucket: Arc<tokio::sync::Mutex<Option<LimiterBucket>>>,
+}
+
+/// Tokens (bytes) available and when they were last refilled. Tokens go
+/// negative when a write overdraws the bucket; the writer then waits the
+/// debt off.
+#[derive(Debug, Clone, Copy)]
+struct LimiterBucket {
+    tokens: f64,
+    refilled_at: std::time::Instant,
+}
+
+impl DownloadLimiter {
+    /// Take `bytes` from the bucket at `bytes_per_sec`, sleeping if it is
+    /// overdrawn. `None` means no limit and empties the bucket state, so a
+    /// later limit starts from a full bucket.
+    async fn take(&self, bytes: u64, bytes_per_sec: Option<u64>) {
+        let wait = {
+            let mut bucket = self.bucket.lock().await;
+            let Some(rate) = bytes_per_sec.filter(|&r| r > 0) else {
+                *bucket = None;
+                return;
+            };
+            let now = std::time::Instant::now();
+            let current = bucket.unwrap_or(LimiterBucket {
+                tokens: rate as f64,
+                refilled_at: now,
+            });
+            let (tokens, wait) = bucket_take(
+                current.tokens,
+                now.duration_since(current.refilled_at),
+                rate,
+                bytes,
+            );
+            *bucket = Some(LimiterBucket {
+                tokens,
+                refilled_at: now,
+            });
+            wait
+        };
+        if let Some(wait) = wait {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Refill a bucket holding at most one second of `rate` for `elapsed`, then
+/// take `bytes`. Returns the tokens left and how long to wait when that is
+/// below zero.
+fn bucket_take(
+    tokens: f64,
+    elapsed: std::time::Duration,
+    rate: u64,
+    bytes: u64,
+) -> (f64, Option<std::time::Duration>) {
+    let rate = rate as f64;
+    let tokens = (tokens + elapsed.as_secs_f64() * rate).min(rate) - bytes as f64;
+    let wait = (tokens < 0.0).then(|| std::time::Duration::from_secs_f64(-tokens / rate));
+    (tokens, wait)
+}
+
+/// Slows a download to `DOWNLOAD_BACKGROUND_MB_PER_SEC` while any model has
+/// requests in flight, so large downloads don't starve inference of disk
+/// bandwidth. Full speed resumes once every gate is idle. Writes also draw
+/// on the global `DownloadLimiter`.
+struct DownloadThrottle {
+    gate: ConcurrencyGate,
+    scheduler: Scheduler,
+    limiter: DownloadLimiter,
+    /// Global limit in effect as of the last check; `None` = full speed.
+    global_bytes_per_sec: Option<u64>,
+    bytes_per_sec: u64,
+    busy: bool,
+    checked_at: Option<std::time::Instant>,
+    /// Start of the current throttled stretch and the bytes written since.
+    window_start: std::time::Instant,
+    window_bytes: u64,
+}
+
+impl DownloadThrottle {
+    fn new(app_state: &AppState, limiter: DownloadLimiter) -> Self {
+        Self {
+            gate: app_state.scheduler.gate().clone(),
+            scheduler: app_state.scheduler.clone(),
+            limiter,
+            global_bytes_per_sec: None,
+            bytes_per_sec: app_state.config.download_background_mb_per_sec * 1024 * 1024,
+            busy: false,
+            checked_at: None,
+            window_start: std::time::Instant::now(),
+            window_bytes: 0,
+        }
+    }
+
+    /// Account for `bytes` just written, sleeping long enough to keep to the
+    /// global limit and to the background rate while inference is running.
+    async fn consume(&mut self, download_id: &str, bytes: u64) {
+        let recheck = self
+            .checked_at
+            .is_none_or(|t| t.elapsed() >= THROTTLE_CHECK_INTERVAL);
+        if recheck {
+            // The setting and the time of day both change what applies
+            let settings = self.scheduler.settings().await;
+            self.global_bytes_per_sec = settings.download_limit(Utc::now().time());
+            self.checked_at = Some(std::time::Instant::now());
+        }
+        self.limiter.take(bytes, self.global_bytes_per_sec).await;
+
+        if self.bytes_per_sec == 0 {
+            return;
+        }
+        if recheck {
+            let busy = self.gate.total_in_flight().await > 0;
+            if busy != self.busy {
+                if busy {
+                    info!(download_id = %download_id, bytes_per_sec = self.bytes_per_sec, "Inference active, throttling download");
+                } else {
+                    info!(download_id = %download_id, "System idle, download back to full speed");
+                }
+                self.busy = busy;
+                self.window_start = std::time::Instant::now();
+                self.window_bytes = 0;
+            }
+        }
+        if !self.busy {
+            return;
+        }
+
+        self.window_bytes += bytes;
+        if let Some(delay) = throttle_delay(
+            self.window_bytes,
+            self.bytes_per_sec,
+            self.window_start.elapsed(),
+        ) {
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// How long to pause so that `bytes` written over `elapsed` stays at or
+/// under `bytes_per_sec`. `None` when already under the rate.
+fn throttle_delay(
+    bytes: u64,
+    bytes_per_sec: u64,
+    elapsed: std::time::Duration,
+) -> Option<std::time::Duration> {
+    let due = std::time::Duration::from_secs_f64(bytes as f64 / bytes_per_sec as f64);
+    due.checked_sub(elapsed).filter(|d| !d.is_zero())
+}
+
 /// Stream an HTTP response body to a file on disk with progress tracking and
-/// cancellation support. Returns the number of bytes written.
+/// cancellation support. With `append`, bytes are added to the end of an
+/// existing file (a resumed range request). Every byte written is fed to
+/// `hasher`. Returns the number of bytes written.
+#[allow(clippy::too_many_arguments)]
 async fn stream_response_to_file(
     resp: reqwest::Response,
     file_dest: &str,
@@ -688,8 +1948,18 @@ async fn stream_response_to_file(
     downloads: &Downloads,
     download_id: &str,
     progress_offset: u64,
+    append: bool,
+    hasher: &mut Sha256,
+    throttle: &mut DownloadThrottle,
 ) -> Result<u64, ()> {
-    let mut out_file = match tokio::fs::File::create(file_dest).await {
+    let opened = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(file_dest)
+        .await;
+    let mut out_file = match opened {
         Ok(f) => f,
         Err(e) => {
             set_download_error(
@@ -723,13 +1993,17 @@ async fn stream_response_to_file(
                     .await;
                     return Err(());
                 }
+                hasher.update(&chunk);
 
                 file_downloaded += chunk.len() as u64;
 
-                let mut dls = downloads.write().await;
-                if let Some(dl) = dls.get_mut(download_id) {
-                    dl.progress_bytes = progress_offset + file_downloaded;
+                {
+                    let mut dls = downloads.write().await;
+                    if let Some(dl) = dls.get_mut(download_id) {
+                        dl.progress_bytes = progress_offset + file_downloaded;
+                    }
                 }
+                throttle.consume(download_id, chunk.len() as u64).await;
             }
             Err(e) => {
                 set_download_error(
@@ -748,7 +2022,13 @@ async fn stream_response_to_file(
 
 /// Download a single file from HuggingFace to disk with progress tracking.
 /// `progress_offset` is the cumulative bytes already downloaded (for progress reporting).
-/// Returns the number of bytes downloaded for this file.
+/// Data goes to `<file>.part` and is renamed when complete; an existing part
+/// file is continued with a Range request. The SHA-256 is computed while
+/// streaming and checked against the repo's LFS hash before the rename.
+/// Files large enough per `chunks` are fetched over several connections
+/// instead (see `download_file_chunked`).
+/// Returns the file's size on disk and its hex SHA-256.
+#[allow(clippy::too_many_arguments)]
 async fn download_single_file(
     client: &reqwest::Client,
     downloads: &Downloads,
@@ -757,7 +2037,9 @@ async fn download_single_file(
     dest_dir: &str,
     hf_repo: &str,
     progress_offset: u64,
-) -> Result<u64, ()> {
+    chunks: ChunkSettings,
+    throttle: &mut DownloadThrottle,
+) -> Result<(u64, String), ()> {
     // Reject path components that could escape the destination directory
     if file.path.contains("..") || file.path.starts_with('/') {
         set_download_error(
@@ -774,6 +2056,29 @@ async fn download_single_file(
         hf_repo, file.path
     );
     let file_dest = format!("{}/{}", dest_dir, file.path);
+    let part_dest = format!("{file_dest}.part");
+
+    // Already fetched by an earlier attempt of this download (and intact)
+    if let (Some(size), Ok(meta)) = (file.size, tokio::fs::metadata(&file_dest).await) {
+        if meta.len() == size {
+            if let Ok(sha256) = sha256_file(&file_dest).await {
+                if verify_checksum(file, &sha256).is_ok() {
+                    info!(file = %file.path, "File already downloaded, skipping");
+                    let mut dls = downloads.write().await;
+                    if let Some(dl) = dls.get_mut(download_id) {
+                        dl.progress_bytes = progress_offset + size;
+                    }
+                    return Ok((size, sha256));
+                }
+            }
+        }
+    }
+
+    // Continue a partial file, unless it's larger than the file should be
+    let resume_from = match tokio::fs::metadata(&part_dest).await {
+        Ok(meta) if file.size.is_none_or(|s| meta.len() <= s) => meta.len(),
+        _ => 0,
+    };
 
     // Create parent directory for nested files
     if let Some(parent) = std::path::Path::new(&file_dest).parent() {
@@ -788,42 +2093,533 @@ async fn download_single_file(
         }
     }
 
-    info!(file = %file.path, url = %file_url, "Downloading file");
+    // A part file from an earlier single-connection attempt is continued
+    // that way; one with a chunk sidecar is continued in chunks
+    let chunked = tokio::fs::try_exists(chunks_sidecar(&part_dest))
+        .await
+        .unwrap_or(false);
+    if let Some(size) = file
+        .size
+        .filter(|&size| chunked || (resume_from == 0 && chunks.splits(size)))
+    {
+        download_file_chunked(
+            client,
+            downloads,
+            download_id,
+            &file_url,
+            &file.path,
+            &part_dest,
+            size,
+            chunks,
+            progress_offset,
+            throttle,
+        )
+        .await?;
+        let sha256 = match sha256_file(&part_dest).await {
+            Ok(sha256) => sha256,
+            Err(e) => {
+                set_download_error(
+                    downloads,
+                    download_id,
+                    &format!("Failed to read {}: {e}", file.path),
+                )
+                .await;
+                return Err(());
+            }
+        };
+        finish_part_file(
+            downloads,
+            download_id,
+            file,
+            &part_dest,
+            &file_dest,
+            &sha256,
+        )
+        .await?;
+        return Ok((size, sha256));
+    }
+
+    info!(file = %file.path, url = %file_url, resume_from = resume_from, "Downloading file");
+
+    let mut req = client.get(&file_url);
+    if resume_from > 0 {
+        req = req.header(reqwest::header::RANGE, format!("bytes={resume_from}-"));
+    }
+    let resp = match req.send().await {
+        Ok(r) => r,
+        Err(e) => {
+            set_download_error(
+                downloads,
+                download_id,
+                &format!("Failed to download {}: {e}", file.path),
+            )
+            .await;
+            return Err(());
+        }
+    };
+
+    // Nothing left to fetch — the part file is already whole
+    let whole = resume_from > 0
+        && resp.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE
+        && file.size.is_none_or(|s| s == resume_from);
+
+    if !whole && !resp.status().is_success() {
+        let status = resp.status();
+        let hint = hf_http_error_hint(status);
+        set_download_error(
+            downloads,
+            download_id,
+            &format!("Download of {} returned HTTP {status}{hint}", file.path),
+        )
+        .await;
+        return Err(());
+    }
+
+    // A full 200 response means the server ignored the Range header
+    let kept = if whole || resp.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+        resume_from
+    } else {
+        0
+    };
+
+    // The digest covers the whole file, so hash what's already on disk first
+    let mut hasher = Sha256::new();
+    if kept > 0 {
+        if let Err(e) = hash_file_into(&part_dest, &mut hasher).await {
+            set_download_error(
+                downloads,
+                download_id,
+                &format!("Failed to read partial file {}: {e}", file.path),
+            )
+            .await;
+            return Err(());
+        }
+    }
+
+    let written = if whole {
+        0
+    } else {
+        stream_response_to_file(
+            resp,
+            &part_dest,
+            &file.path,
+            downloads,
+            download_id,
+            progress_offset + kept,
+            kept > 0,
+            &mut hasher,
+            throttle,
+        )
+        .await?
+    };
+    let file_size = kept + written;
+
+    let sha256 = hex::encode(hasher.finalize());
+    finish_part_file(
+        downloads,
+        download_id,
+        file,
+        &part_dest,
+        &file_dest,
+        &sha256,
+    )
+    .await?;
+    Ok((file_size, sha256))
+}
+
+/// Check a finished part file's SHA-256 and move it into place.
+async fn finish_part_file(
+    downloads: &Downloads,
+    download_id: &str,
+    file: &HfFileEntry,
+    part_dest: &str,
+    file_dest: &str,
+    sha256: &str,
+) -> Result<(), ()> {
+    if let Err(msg) = verify_checksum(file, sha256) {
+        // Corrupt data must not be resumed from
+        let _ = tokio::fs::remove_file(part_dest).await;
+        let _ = tokio::fs::remove_file(chunks_sidecar(part_dest)).await;
+        set_download_error(downloads, download_id, &msg).await;
+        return Err(());
+    }
+    let _ = tokio::fs::remove_file(chunks_sidecar(part_dest)).await;
+
+    if let Err(e) = tokio::fs::rename(part_dest, file_dest).await {
+        set_download_error(
+            downloads,
+            download_id,
+            &format!("Failed to finalize {}: {e}", file.path),
+        )
+        .await;
+        return Err(());
+    }
+    Ok(())
+}
+
+/// How files are split across connections (`HF_DOWNLOAD_CONNECTIONS`,
+/// `HF_DOWNLOAD_CHUNK_MB`).
+#[derive(Debug, Clone, Copy)]
+struct ChunkSettings {
+    connections: usize,
+    chunk_bytes: u64,
+}
+
+impl ChunkSettings {
+    fn new(config: &crate::config::AppConfig) -> Self {
+        Self {
+            connections: config.hf_download_connections.max(1),
+            chunk_bytes: config.hf_download_chunk_mb.max(1) * 1024 * 1024,
+        }
+    }
+
+    /// Whether a file of `size` bytes is worth fetching in parallel.
+    fn splits(&self, size: u64) -> bool {
+        self.connections > 1 && size >= 2 * self.chunk_bytes
+    }
+}
+
+/// Attempts per chunk before the download fails.
+const CHUNK_MAX_ATTEMPTS: u32 = 3;
+
+/// Pause before retrying a chunk, multiplied by the attempt number.
+const CHUNK_RETRY_DELAY: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Byte ranges `[start, end)` covering `size` bytes in `chunk_bytes` pieces.
+fn chunk_ranges(size: u64, chunk_bytes: u64) -> Vec<(u64, u64)> {
+    (0..size)
+        .step_by(chunk_bytes as usize)
+        .map(|start| (start, (start + chunk_bytes).min(size)))
+        .collect()
+}
+
+/// Sidecar of a parallel download's part file. The first line is the chunk
+/// size, each further line the index of a chunk that is fully written.
+fn chunks_sidecar(part_dest: &str) -> String {
+    format!("{part_dest}.chunks")
+}
+
+/// Chunk size and finished chunk indices from a sidecar's contents. A last
+/// line without its newline may be a torn write and is ignored.
+fn parse_chunks_sidecar(contents: &str) -> Option<(u64, HashSet<usize>)> {
+    let complete = &contents[..contents.rfind('\n')? + 1];
+    let mut lines = complete.lines();
+    let chunk_bytes = lines.next()?.trim().parse().ok().filter(|&n| n > 0)?;
+    let done = lines.filter_map(|l| l.trim().parse().ok()).collect();
+    Some((chunk_bytes, done))
+}
+
+/// Bytes of a part file that hold downloaded data. A parallel download's
+/// part file is preallocated, so only its finished chunks count.
+async fn part_file_progress(part_dest: &str, size: Option<u64>) -> u64 {
+    let sidecar = tokio::fs::read_to_string(chunks_sidecar(part_dest)).await;
+    match (sidecar.ok().as_deref().and_then(parse_chunks_sidecar), size) {
+        (Some((chunk_bytes, done)), Some(size)) => chunk_ranges(size, chunk_bytes)
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| done.contains(i))
+            .map(|(_, (start, end))| end - start)
+            .sum(),
+        _ => tokio::fs::metadata(part_dest).await.map_or(0, |m| m.len()),
+    }
+}
+
+/// Why a chunk stopped short.
+enum ChunkError {
+    Cancelled,
+    Failed(String),
+}
+
+/// Fetch `size` bytes into `part_dest` over up to `settings.connections`
+/// concurrent range requests, each writing at its own offset of the
+/// preallocated file. Finished chunks are recorded in the sidecar so a
+/// resumed download only fetches the rest. A chunk that fails is retried
+/// from where it stopped.
+#[allow(clippy::too_many_arguments)]
+async fn download_file_chunked(
+    client: &reqwest::Client,
+    downloads: &Downloads,
+    download_id: &str,
+    file_url: &str,
+    file_path: &str,
+    part_dest: &str,
+    size: u64,
+    settings: ChunkSettings,
+    progress_offset: u64,
+    throttle: &mut DownloadThrottle,
+) -> Result<(), ()> {
+    use tokio::io::AsyncWriteExt;
+
+    let sidecar = chunks_sidecar(part_dest);
+    let part_len = tokio::fs::metadata(part_dest).await.map_or(0, |m| m.len());
+    let previous = tokio::fs::read_to_string(&sidecar)
+        .await
+        .ok()
+        .as_deref()
+        .and_then(parse_chunks_sidecar)
+        .filter(|_| part_len == size);
+    let (chunk_bytes, done) = match previous {
+        Some(state) => state,
+        None => {
+            // Fresh start: preallocate the file and record the chunk size
+            let prepared = async {
+                let out = tokio::fs::File::create(part_dest).await?;
+                out.set_len(size).await?;
+                tokio::fs::write(&sidecar, format!("{}\n", settings.chunk_bytes)).await
+            }
+            .await;
+            if let Err(e) = prepared {
+                set_download_error(
+                    downloads,
+                    download_id,
+                    &format!("Failed to create file {part_dest}: {e}"),
+                )
+                .await;
+                return Err(());
+            }
+            (settings.chunk_bytes, HashSet::new())
+        }
+    };
+
+    let ranges = chunk_ranges(size, chunk_bytes);
+    let already: u64 = ranges
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| done.contains(i))
+        .map(|(_, (start, end))| end - start)
+        .sum();
+    let pending: Vec<(usize, (u64, u64))> = ranges
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| !done.contains(i))
+        .collect();
+    info!(
+        file = %file_path,
+        chunks = pending.len(),
+        connections = settings.connections,
+        "Downloading file in parallel chunks"
+    );
+
+    let written = std::sync::atomic::AtomicU64::new(already);
+    let throttle = tokio::sync::Mutex::new(throttle);
+    let mut chunks = futures::stream::iter(pending)
+        .map(|(index, range)| {
+            let (written, throttle) = (&written, &throttle);
+            async move {
+                let mut fetched = 0;
+                let mut attempt = 1;
+                loop {
+                    match download_chunk(
+                        client,
+                        downloads,
+                        download_id,
+                        file_url,
+                        part_dest,
+                        (range.0 + fetched, range.1),
+                        progress_offset,
+                        written,
+                        throttle,
+                    )
+                    .await
+                    {
+                        Ok(()) => return Ok(index),
+                        Err((n, ChunkError::Failed(msg))) if attempt < CHUNK_MAX_ATTEMPTS => {
+                            fetched += n;
+                            warn!(
+                                file = %file_path,
+                                chunk = index,
+                                attempt,
+                                error = %msg,
+                                "Chunk download failed, retrying"
+                            );
+                            tokio::time::sleep(CHUNK_RETRY_DELAY * attempt).await;
+                            attempt += 1;
+                        }
+                        Err((_, e)) => return Err(e),
+                    }
+                }
+            }
+        })
+        .buffer_unordered(settings.connections);
+
+    while let Some(result) = chunks.next().await {
+        match result {
+            Ok(index) => {
+                let recorded = async {
+                    let mut f = tokio::fs::OpenOptions::new()
+                        .append(true)
+                        .open(&sidecar)
+                        .await?;
+                    f.write_all(format!("{index}\n").as_bytes()).await
+                }
+                .await;
+                if let Err(e) = recorded {
+                    warn!(file = %file_path, "Failed to record finished chunk: {e}");
+                }
+            }
+            Err(ChunkError::Cancelled) => {
+                info!(download_id = %download_id, "Download cancelled during transfer");
+                drop(chunks);
+                let _ = tokio::fs::remove_file(part_dest).await;
+                let _ = tokio::fs::remove_file(&sidecar).await;
+                return Err(());
+            }
+            Err(ChunkError::Failed(msg)) => {
+                set_download_error(downloads, download_id, &format!("{file_path}: {msg}")).await;
+                return Err(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Fetch the byte range `[start, end)` into `part_dest` at offset `start`.
+/// On failure, also returns how many bytes of the range were written.
+#[allow(clippy::too_many_arguments)]
+async fn download_chunk(
+    client: &reqwest::Client,
+    downloads: &Downloads,
+    download_id: &str,
+    file_url: &str,
+    part_dest: &str,
+    (start, end): (u64, u64),
+    progress_offset: u64,
+    written: &std::sync::atomic::AtomicU64,
+    throttle: &tokio::sync::Mutex<&mut DownloadThrottle>,
+) -> Result<(), (u64, ChunkError)> {
+    use std::sync::atomic::Ordering;
+    use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+    let failed = |n, msg: String| Err((n, ChunkError::Failed(msg)));
+    let resp = match client
+        .get(file_url)
+        .header(reqwest::header::RANGE, format!("bytes={start}-{}", end - 1))
+        .send()
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => return failed(0, format!("request failed: {e}")),
+    };
+    if resp.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+        let status = resp.status();
+        let hint = hf_http_error_hint(status);
+        return failed(0, format!("range request returned HTTP {status}{hint}"));
+    }
+
+    let opened = async {
+        let mut out = tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(part_dest)
+            .await?;
+        out.seek(std::io::SeekFrom::Start(start)).await?;
+        Ok::<_, std::io::Error>(out)
+    }
+    .await;
+    let mut out = match opened {
+        Ok(f) => f,
+        Err(e) => return failed(0, format!("failed to open part file: {e}")),
+    };
+
+    let mut chunk_written: u64 = 0;
+    let mut stream = resp.bytes_stream();
+    while let Some(piece) = stream.next().await {
+        if is_download_cancelled(downloads, download_id).await {
+            return Err((chunk_written, ChunkError::Cancelled));
+        }
+        let piece = match piece {
+            Ok(p) => p,
+            Err(e) => return failed(chunk_written, format!("stream error: {e}")),
+        };
+        // Never write past the range, whatever the server sends
+        let take = piece.len().min((end - start - chunk_written) as usize);
+        if let Err(e) = out.write_all(&piece[..take]).await {
+            return failed(chunk_written, format!("write error: {e}"));
+        }
+        chunk_written += take as u64;
+
+        let total = written.fetch_add(take as u64, Ordering::Relaxed) + take as u64;
+        {
+            let mut dls = downloads.write().await;
+            if let Some(dl) = dls.get_mut(download_id) {
+                dl.progress_bytes = progress_offset + total;
+            }
+        }
+        throttle
+            .lock()
+            .await
+            .consume(download_id, take as u64)
+            .await;
+    }
+
+    if let Err(e) = out.flush().await {
+        return failed(chunk_written, format!("write error: {e}"));
+    }
+    if chunk_written < end - start {
+        return failed(
+            chunk_written,
+            format!(
+                "connection closed {} bytes short",
+                end - start - chunk_written
+            ),
+        );
+    }
+    Ok(())
+}
 
-    let resp = match client.get(&file_url).send().await {
-        Ok(r) => r,
-        Err(e) => {
-            set_download_error(
-                downloads,
-                download_id,
-                &format!("Failed to download {}: {e}", file.path),
-            )
-            .await;
-            return Err(());
+/// Compare a file's SHA-256 with the LFS hash the repo declares for it.
+/// Files stored outside LFS (configs, tokenizers) declare none and pass.
+fn verify_checksum(file: &HfFileEntry, sha256: &str) -> Result<(), String> {
+    match &file.lfs {
+        Some(lfs) if !lfs.oid.eq_ignore_ascii_case(sha256) => Err(format!(
+            "Checksum mismatch for {}: expected sha256 {}, got {}",
+            file.path, lfs.oid, sha256
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Feed a file's contents to `hasher`.
+async fn hash_file_into(path: &str, hasher: &mut Sha256) -> std::io::Result<()> {
+    use tokio::io::AsyncReadExt;
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut buf = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
         }
-    };
+        hasher.update(&buf[..n]);
+    }
+}
 
-    if !resp.status().is_success() {
-        let status = resp.status();
-        let hint = hf_http_error_hint(status);
-        set_download_error(
-            downloads,
-            download_id,
-            &format!("Download of {} returned HTTP {status}{hint}", file.path),
-        )
-        .await;
-        return Err(());
+/// Hex SHA-256 of a file on disk.
+async fn sha256_file(path: &str) -> std::io::Result<String> {
+    let mut hasher = Sha256::new();
+    hash_file_into(path, &mut hasher).await?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Backend for a download that didn't name one: llama.cpp serves GGUF,
+/// vLLM serves safetensors.
+pub(super) fn default_backend_type(primary_filename: Option<&str>) -> &'static str {
+    match primary_filename {
+        Some(f) if f.ends_with(".safetensors") => "vllm",
+        _ => "llamacpp",
     }
+}
 
-    stream_response_to_file(
-        resp,
-        &file_dest,
-        &file.path,
-        downloads,
-        download_id,
-        progress_offset,
-    )
-    .await
+/// Read the maximum context length from a downloaded HF `config.json`.
+/// Multimodal configs nest it under `text_config`.
+pub(super) async fn read_config_context_length(dest_dir: &str) -> Option<u32> {
+    let raw = tokio::fs::read(format!("{dest_dir}/config.json"))
+        .await
+        .ok()?;
+    let config: serde_json::Value = serde_json::from_slice(&raw).ok()?;
+    [&config, &config["text_config"]]
+        .into_iter()
+        .find_map(|c| c["max_position_embeddings"].as_u64())
+        .map(|v| v.min(u32::MAX as u64) as u32)
 }
 
 /// Detect the primary model file from a list of downloaded files.
@@ -841,6 +2637,17 @@ fn detect_primary_file(downloadable: &[HfFileEntry]) -> Option<String> {
             best_safetensors = Some((&file.path, sz));
         }
     }
+    // llama.cpp opens a split model through its first shard
+    if let Some(prefix) = best_gguf.and_then(|(path, _)| shard_prefix(path)) {
+        if let Some(first) = downloadable
+            .iter()
+            .map(|f| f.path.as_str())
+            .filter(|p| shard_prefix(p) == Some(prefix))
+            .min()
+        {
+            return Some(first.to_string());
+        }
+    }
     best_gguf
         .or(best_safetensors)
         .map(|(path, _)| path.to_string())
@@ -850,14 +2657,43 @@ fn detect_primary_file(downloadable: &[HfFileEntry]) -> Option<String> {
 // Background download task — orchestrator
 // ---------------------------------------------------------------------------
 
+#[allow(clippy::too_many_arguments)]
 async fn run_download(
     app_state: Arc<AppState>,
     downloads: Downloads,
+    limiter: DownloadLimiter,
     download_id: String,
     hf_repo: String,
     file_filter: Option<Vec<String>>,
     category_id: Option<String>,
     backend_type: Option<String>,
+) {
+    download_and_register(
+        &app_state,
+        &downloads,
+        limiter,
+        &download_id,
+        hf_repo,
+        file_filter,
+        category_id,
+        backend_type,
+    )
+    .await;
+
+    // Record how it ended so a failed or cancelled download can be resumed
+    persist_download_state(&app_state.db, &downloads, &download_id).await;
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn download_and_register(
+    app_state: &AppState,
+    downloads: &Downloads,
+    limiter: DownloadLimiter,
+    download_id: &str,
+    hf_repo: String,
+    file_filter: Option<Vec<String>>,
+    category_id: Option<String>,
+    backend_type: Option<String>,
 ) {
     info!(hf_repo = %hf_repo, download_id = %download_id, "Starting model download");
 
@@ -867,7 +2703,7 @@ async fn run_download(
     let client = match build_hf_client(&hf_token) {
         Ok(c) => c,
         Err(e) => {
-            set_download_error(&downloads, &download_id, &e).await;
+            set_download_error(downloads, download_id, &e).await;
             return;
         }
     };
@@ -876,22 +2712,28 @@ async fn run_download(
     let downloadable = match list_downloadable_files(&client, &hf_repo, &file_filter).await {
         Ok(files) => files,
         Err(e) => {
-            set_download_error(&downloads, &download_id, &e).await;
+            set_download_error(downloads, download_id, &e).await;
             return;
         }
     };
 
     // Step 4: Calculate total size and update download state
     let total_bytes: u64 = downloadable.iter().map(|f| f.size.unwrap_or(0)).sum();
-    {
+    // A resumed download already has part of this on disk
+    let already_on_disk = {
         let mut dls = downloads.write().await;
-        if let Some(dl) = dls.get_mut(&download_id) {
-            dl.total_bytes = total_bytes;
+        match dls.get_mut(download_id) {
+            Some(dl) => {
+                dl.total_bytes = total_bytes;
+                dl.progress_bytes.min(total_bytes)
+            }
+            None => 0,
         }
-    }
+    };
 
     // Step 5: Check disk space (including other in-flight downloads)
-    if validate_disk_space(&app_state, &downloads, &download_id, total_bytes)
+    let remaining = total_bytes - already_on_disk;
+    if validate_disk_space(app_state, downloads, download_id, remaining)
         .await
         .is_err()
     {
@@ -902,25 +2744,30 @@ async fn run_download(
     let safe_repo = hf_repo.replace('/', "--");
     let dest_dir = format!("{}/{}", app_state.config.model_path, safe_repo);
 
-    let total_downloaded = match download_files_to_disk(
+    let mut throttle = DownloadThrottle::new(app_state, limiter);
+    let (total_downloaded, digests) = match download_files_to_disk(
         &client,
-        &downloads,
-        &download_id,
+        &app_state.db,
+        downloads,
+        download_id,
         &downloadable,
         &dest_dir,
         &hf_repo,
+        ChunkSettings::new(&app_state.config),
+        &mut throttle,
     )
     .await
     {
-        Ok(bytes) => bytes,
+        Ok(result) => result,
         Err(()) => return,
     };
 
     // Step 8: Capture tokenizer metadata for future use (chat template detection, etc.)
     let model_metadata = fetch_tokenizer_config(&dest_dir, &hf_repo, &client).await;
+    let pipeline_tag = fetch_pipeline_tag(&client, &hf_repo).await;
 
     // Step 9: Extract architecture metadata from GGUF file
-    let gguf_meta = {
+    let mut gguf_meta = {
         let mut meta: Option<GgufMetadata> = None;
         for file in &downloadable {
             if file.path.ends_with(".gguf") {
@@ -947,6 +2794,10 @@ async fn run_download(
         }
         meta.unwrap_or_default()
     };
+    // Safetensors repos have no GGUF header — take the context length from config.json
+    if gguf_meta.context_length.is_none() {
+        gguf_meta.context_length = read_config_context_length(&dest_dir).await;
+    }
 
     // Step 10: Detect the primary model file
     let primary_filename = detect_primary_file(&downloadable);
@@ -963,13 +2814,65 @@ async fn run_download(
         );
     }
 
+    let size_bytes = total_downloaded as i64;
+
+    // Step 11a: Downloading an evicted model again restores its row, keeping
+    // its ID, category and aliases
+    let evicted: Option<String> = sqlx::query_scalar(
+        "SELECT id FROM models WHERE hf_repo = ? AND filename IS ? AND downloaded = 0",
+    )
+    .bind(&hf_repo)
+    .bind(&primary_filename)
+    .fetch_optional(&app_state.db.pool)
+    .await
+    .unwrap_or_default();
+    if let Some(model_id) = evicted {
+        let sha256 = primary_filename.as_ref().and_then(|f| digests.get(f));
+        if let Err(e) = sqlx::query(
+            "UPDATE models SET downloaded = 1, evicted_at = NULL, size_bytes = ?, \
+             sha256 = COALESCE(?, sha256) WHERE id = ?",
+        )
+        .bind(size_bytes)
+        .bind(sha256)
+        .bind(&model_id)
+        .execute(&app_state.db.pool)
+        .await
+        {
+            error!(hf_repo = %hf_repo, "Failed to restore evicted model: {e}");
+            set_download_error(
+                downloads,
+                download_id,
+                &format!("Download complete but restoring the model failed: {e}"),
+            )
+            .await;
+            return;
+        }
+        info!(hf_repo = %hf_repo, model_id = %model_id, size_bytes, "Evicted model downloaded again and restored");
+        app_state
+            .storage
+            .spawn_publish(app_state.db.clone(), model_id.clone(), safe_repo.clone());
+        let mut dls = downloads.write().await;
+        if let Some(dl) = dls.get_mut(download_id) {
+            dl.status = DownloadStatus::Complete;
+            dl.progress_bytes = total_downloaded;
+        }
+        app_state.events.publish(AppEvent::DownloadFinished {
+            hf_repo,
+            model_ids: vec![model_id],
+        });
+        return;
+    }
+
     // Step 11: Register model in DB
     let model_id = Uuid::new_v4().to_string();
-    let size_bytes = total_downloaded as i64;
-    let bt = backend_type.as_deref().unwrap_or("llamacpp");
+    let bt = backend_type
+        .as_deref()
+        .unwrap_or_else(|| default_backend_type(primary_filename.as_deref()));
     let (kv_bpt_global, kv_bpt_swa) = compute_kv_aggregates(&gguf_meta);
+    // Verified digest of the primary file, for later integrity audits
+    let sha256 = primary_filename.as_ref().and_then(|f| digests.get(f));
     match sqlx::query(
-        "INSERT INTO models (id, hf_repo, filename, size_bytes, category_id, backend_type, model_metadata, context_length, n_layers, n_heads, n_kv_heads, embedding_length, key_length, value_length, sliding_window, kv_bytes_per_token_global, kv_bytes_per_token_swa, runtime_overrides) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO models (id, hf_repo, filename, size_bytes, category_id, backend_type, model_metadata, context_length, n_layers, n_heads, n_kv_heads, embedding_length, key_length, value_length, sliding_window, kv_bytes_per_token_global, kv_bytes_per_token_swa, runtime_overrides, sha256, architecture, pipeline_tag) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(&model_id)
     .bind(&hf_repo)
@@ -989,6 +2892,9 @@ async fn run_download(
     .bind(kv_bpt_global)
     .bind(kv_bpt_swa)
     .bind(runtime_overrides_json)
+    .bind(sha256)
+    .bind(&gguf_meta.architecture)
+    .bind(&pipeline_tag)
     .execute(&app_state.db.pool)
     .await
     {
@@ -1003,8 +2909,8 @@ async fn run_download(
         Err(e) => {
             error!(hf_repo = %hf_repo, "Failed to register model in DB: {e}");
             set_download_error(
-                &downloads,
-                &download_id,
+                downloads,
+                download_id,
                 &format!("Download complete but DB registration failed: {e}"),
             )
             .await;
@@ -1012,12 +2918,45 @@ async fn run_download(
         }
     }
 
-    // Step 12: Mark download as complete
+    // Step 12: File it under a category if it came without one
+    let mode = categorize::Mode::from_config(&app_state.config.auto_categorize);
+    if category_id.is_none() && mode != categorize::Mode::Off {
+        match categorize::categorize_model(
+            &app_state.db.pool,
+            &model_id,
+            mode == categorize::Mode::Assign,
+            "auto-categorize",
+        )
+        .await
+        {
+            Ok(Some(outcome)) => info!(
+                hf_repo = %hf_repo,
+                kind = outcome.kind.as_str(),
+                category = ?outcome.category_name,
+                applied = outcome.applied,
+                reason = %outcome.reason,
+                "Detected model kind"
+            ),
+            Ok(None) => {}
+            Err(e) => warn!(hf_repo = %hf_repo, error = %e, "Auto-categorization failed"),
+        }
+    }
+
+    // Step 13: Upload to the S3 model store, if configured
+    app_state
+        .storage
+        .spawn_publish(app_state.db.clone(), model_id.clone(), safe_repo.clone());
+
+    // Step 14: Mark download as complete
     let mut dls = downloads.write().await;
-    if let Some(dl) = dls.get_mut(&download_id) {
+    if let Some(dl) = dls.get_mut(download_id) {
         dl.status = DownloadStatus::Complete;
         dl.progress_bytes = total_downloaded;
     }
+    app_state.events.publish(AppEvent::DownloadFinished {
+        hf_repo,
+        model_ids: vec![model_id],
+    });
 }
 
 // ---------------------------------------------------------------------------
@@ -1025,7 +2964,7 @@ async fn run_download(
 // ---------------------------------------------------------------------------
 
 /// Read tokenizer_config.json from a local directory, validating it as JSON.
-async fn try_local_tokenizer(dest_dir: &str) -> Option<String> {
+pub(super) async fn try_local_tokenizer(dest_dir: &str) -> Option<String> {
     let local_path = format!("{}/tokenizer_config.json", dest_dir);
     let contents = tokio::fs::read_to_string(&local_path).await.ok()?;
     serde_json::from_str::<serde_json::Value>(&contents).ok()?;
@@ -1075,6 +3014,21 @@ async fn try_base_model_tokenizer(
     Some((base_model, text))
 }
 
+/// The repo's pipeline tag (`text-generation`, `feature-extraction`, ...),
+/// used to categorize the model.
+async fn fetch_pipeline_tag(client: &reqwest::Client, hf_repo: &str) -> Option<String> {
+    let api_url = format!("https://huggingface.co/api/models/{}", hf_repo);
+    let resp = client.get(&api_url).send().await.ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    let model_info = resp.json::<serde_json::Value>().await.ok()?;
+    model_info
+        .get("pipeline_tag")
+        .and_then(|t| t.as_str())
+        .map(String::from)
+}
+
 /// Attempt to fetch tokenizer_config.json for a downloaded model.
 ///
 /// Strategy:
@@ -1126,6 +3080,15 @@ struct HfFileEntry {
     #[serde(rename = "rfilename", alias = "path")]
     path: String,
     size: Option<u64>,
+    /// Present for files stored in Git LFS (model weights).
+    #[serde(default)]
+    lfs: Option<HfLfsInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HfLfsInfo {
+    /// SHA-256 of the file contents, hex encoded.
+    oid: String,
 }
 
 async fn set_download_error(downloads: &Downloads, download_id: &str, error_msg: &str) {
@@ -1227,6 +3190,8 @@ fn format_bytes(bytes: u64) -> String {
 /// Architecture metadata extracted from a GGUF file header.
 #[derive(Debug, Clone, Default)]
 pub struct GgufMetadata {
+    /// `general.architecture` (`llama`, `qwen2vl`, `nomic-bert`, ...).
+    pub architecture: Option<String>,
     pub context_length: Option<u32>,
     pub block_count: Option<u32>, // n_layers
     pub embedding_length: Option<u32>,
@@ -1548,6 +3513,11 @@ pub async fn read_gguf_metadata(path: &str) -> Result<GgufMetadata, String> {
             .map_err(|e| format!("read type: {e}"))?;
         let vtype = u32::from_le_bytes(tb);
 
+        if key == "general.architecture" && vtype == 8 {
+            meta.architecture = Some(read_string(&mut f).await?);
+            continue;
+        }
+
         // head_count_kv may be an array (per-layer) in heterogeneous-attention
         // models like Gemma 4 — capture BOTH the max (for legacy estimator)
         // AND the full array (for SWA-aware estimator).
@@ -1756,6 +3726,7 @@ mod tests {
             file_type: "file".to_string(),
             path: path.to_string(),
             size: Some(size),
+            lfs: None,
         }
     }
 
@@ -1814,10 +3785,322 @@ mod tests {
             file_type: "file".to_string(),
             path: "model.gguf".to_string(),
             size: None,
+            lfs: None,
         }];
         assert_eq!(detect_primary_file(&files), Some("model.gguf".to_string()));
     }
 
+    // -- import helpers ------------------------------------------------------
+
+    fn entries(paths: &[&str]) -> Vec<HfFileEntry> {
+        paths
+            .iter()
+            .map(|p| HfFileEntry {
+                file_type: "file".to_string(),
+                path: p.to_string(),
+                size: Some(1),
+                lfs: None,
+            })
+            .collect()
+    }
+
+    fn prefs(q: &[&str]) -> Vec<String> {
+        q.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn collection_slug_accepts_urls_and_bare_slugs() {
+        assert_eq!(
+            collection_slug("https://huggingface.co/collections/org/coding-66f1a2b3c4d5/")
+                .as_deref(),
+            Some("org/coding-66f1a2b3c4d5")
+        );
+        assert_eq!(
+            collection_slug("org/coding-66f1?foo=bar").as_deref(),
+            Some("org/coding-66f1")
+        );
+        assert!(collection_slug("https://huggingface.co/collections/org").is_none());
+        assert!(collection_slug("org/../x").is_none());
+    }
+
+    #[test]
+    fn select_gguf_follows_preference_order() {
+        let files = entries(&[
+            "README.md",
+            "model.Q8_0.gguf",
+            "model.Q5_K_M.gguf",
+            "mmproj-model-Q4_K_M.gguf",
+        ]);
+        assert_eq!(
+            select_gguf(&files, &prefs(&["Q4_K_M", "Q5_K_M", "Q8_0"])),
+            Some(vec!["model.Q5_K_M.gguf".to_string()])
+        );
+        assert_eq!(select_gguf(&files, &prefs(&["IQ2_XS"])), None);
+    }
+
+    #[test]
+    fn select_gguf_returns_all_shards() {
+        let files = entries(&[
+            "Q4_K_M/model-Q4_K_M-00002-of-00002.gguf",
+            "Q4_K_M/model-Q4_K_M-00001-of-00002.gguf",
+            "Q8_0/model-Q8_0-00001-of-00002.gguf",
+        ]);
+        assert_eq!(
+            select_gguf(&files, &prefs(&["q4_k_m"])),
+            Some(vec![
+                "Q4_K_M/model-Q4_K_M-00001-of-00002.gguf".to_string(),
+                "Q4_K_M/model-Q4_K_M-00002-of-00002.gguf".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn quant_match_is_token_exact() {
+        assert!(quant_matches("model.Q4_0.gguf", "Q4_0"));
+        assert!(!quant_matches("model.Q4_0_4_4.gguf", "Q4_0"));
+        assert!(!quant_matches("model.IQ4_XS.gguf", "Q4_XS"));
+        assert!(quant_matches("model-q4_k_m.gguf", "Q4_K_M"));
+    }
+
+    #[test]
+    fn detect_primary_file_uses_first_shard() {
+        let mut files = entries(&[
+            "model-Q4_K_M-00001-of-00002.gguf",
+            "model-Q4_K_M-00002-of-00002.gguf",
+        ]);
+        files[1].size = Some(10);
+        assert_eq!(
+            detect_primary_file(&files),
+            Some("model-Q4_K_M-00001-of-00002.gguf".to_string())
+        );
+    }
+
+    // -- recommendation helpers ----------------------------------------------
+
+    #[test]
+    fn gguf_groups_merge_shards_and_sort_by_size() {
+        let mut files = entries(&[
+            "model.Q8_0.gguf",
+            "model.Q4_K_M.gguf",
+            "big/model-F16-00001-of-00002.gguf",
+            "big/model-F16-00002-of-00002.gguf",
+            "mmproj-F16.gguf",
+        ]);
+        for (f, size) in files.iter_mut().zip([800, 400, 700, 700, 50]) {
+            f.size = Some(size);
+        }
+        let groups = gguf_groups(&files);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0], (vec!["model.Q4_K_M.gguf".to_string()], 400));
+        assert_eq!(groups[1].0[0], "model.Q8_0.gguf");
+        assert_eq!(groups[2].0.len(), 2);
+        assert_eq!(groups[2].1, 1400);
+    }
+
+    #[test]
+    fn quant_label_prefers_longest_known_name() {
+        assert_eq!(quant_label("m.Q4_K_M.gguf").as_deref(), Some("Q4_K_M"));
+        assert_eq!(quant_label("m-IQ2_XXS.gguf").as_deref(), Some("IQ2_XXS"));
+        assert_eq!(quant_label("m-bf16.gguf").as_deref(), Some("BF16"));
+        assert_eq!(quant_label("model.gguf"), None);
+    }
+
+    // -- verify_checksum -----------------------------------------------------
+
+    #[test]
+    fn checksum_checked_only_for_lfs_files() {
+        let digest = hex::encode(Sha256::digest(b"weights"));
+        let mut file = make_file("model.gguf", 7);
+        assert!(verify_checksum(&file, "anything").is_ok());
+
+        file.lfs = Some(HfLfsInfo {
+            oid: digest.to_uppercase(),
+        });
+        assert!(verify_checksum(&file, &digest).is_ok());
+        let err = verify_checksum(&file, &"0".repeat(64)).unwrap_err();
+        assert!(err.contains("Checksum mismatch for model.gguf"));
+    }
+
+    #[tokio::test]
+    async fn sha256_file_matches_in_memory_digest() {
+        let path = std::env::temp_dir().join(format!("hf_sha_test_{}", Uuid::new_v4()));
+        let data = vec![7u8; 3 * 1024 * 1024 + 11]; // spans several read buffers
+        std::fs::write(&path, &data).unwrap();
+        let digest = sha256_file(&path.to_string_lossy()).await.unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(digest, hex::encode(Sha256::digest(&data)));
+    }
+
+    #[test]
+    fn tree_entry_parses_lfs_hash() {
+        let entry: HfFileEntry = serde_json::from_str(
+            r#"{"type":"file","oid":"abc","size":10,"lfs":{"oid":"deadbeef","size":10,"pointerSize":130},"path":"m.gguf"}"#,
+        )
+        .unwrap();
+        assert_eq!(entry.lfs.unwrap().oid, "deadbeef");
+    }
+
+    // -- download persistence ------------------------------------------------
+
+    #[tokio::test]
+    async fn interrupted_downloads_become_resumable_failures() {
+        let db = Database::test_db().await;
+        for (id, status) in [("a", "downloading"), ("b", "complete")] {
+            sqlx::query("INSERT INTO downloads (id, hf_repo, status) VALUES (?, 'org/m', ?)")
+                .bind(id)
+                .bind(status)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(recover_interrupted_downloads(&db).await.unwrap(), 1);
+        let (status, error): (String, Option<String>) =
+            sqlx::query_as("SELECT status, error FROM downloads WHERE id = 'a'")
+                .fetch_one(&db.pool)
+                .await
+                .unwrap();
+        assert_eq!(status, "failed");
+        assert!(error.unwrap().contains("resume"));
+        assert_eq!(recover_interrupted_downloads(&db).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn persist_download_state_writes_progress() {
+        let db = Database::test_db().await;
+        sqlx::query("INSERT INTO downloads (id, hf_repo) VALUES ('d1', 'org/m')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let downloads: Downloads = Arc::new(RwLock::new(HashMap::new()));
+        downloads.write().await.insert(
+            "d1".to_string(),
+            DownloadState {
+                id: "d1".to_string(),
+                hf_repo: "org/m".to_string(),
+                progress_bytes: 40,
+                total_bytes: 100,
+                status: DownloadStatus::Failed,
+                error: Some("Stream error".to_string()),
+                category_id: None,
+                backend_type: "llamacpp".to_string(),
+            },
+        );
+
+        persist_download_state(&db, &downloads, "d1").await;
+        let row: (String, i64, i64) = sqlx::query_as(
+            "SELECT status, progress_bytes, total_bytes FROM downloads WHERE id = 'd1'",
+        )
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(row, ("failed".to_string(), 40, 100));
+    }
+
+    #[test]
+    fn changed_downloads_reports_only_changes() {
+        let mut current = HashMap::new();
+        let mut dl = DownloadState {
+            id: "d1".to_string(),
+            hf_repo: "org/m".to_string(),
+            progress_bytes: 0,
+            total_bytes: 100,
+            status: DownloadStatus::Downloading,
+            error: None,
+            category_id: None,
+            backend_type: "llamacpp".to_string(),
+        };
+        current.insert("d1".to_string(), dl.clone());
+        let mut seen = HashMap::new();
+
+        assert_eq!(changed_downloads(&current, &mut seen).len(), 1);
+        assert!(changed_downloads(&current, &mut seen).is_empty());
+
+        dl.progress_bytes = 60;
+        current.insert("d1".to_string(), dl.clone());
+        let changed = changed_downloads(&current, &mut seen);
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].progress_bytes, 60);
+
+        dl.status = DownloadStatus::Complete;
+        current.insert("d1".to_string(), dl);
+        assert_eq!(
+            changed_downloads(&current, &mut seen)[0].status,
+            DownloadStatus::Complete
+        );
+    }
+
+    #[test]
+    fn progress_tracker_reports_stalled_downloads() {
+        let dl = DownloadState {
+            id: "d1".to_string(),
+            hf_repo: "org/m".to_string(),
+            progress_bytes: 10,
+            total_bytes: 100,
+            status: DownloadStatus::Downloading,
+            error: None,
+            category_id: None,
+            backend_type: "llamacpp".to_string(),
+        };
+        let minute = std::time::Duration::from_secs(60);
+        let start = std::time::Instant::now();
+        let mut current = HashMap::from([("d1".to_string(), dl.clone())]);
+        let mut tracker = ProgressTracker::default();
+
+        tracker.update(&current, start);
+        tracker.update(&current, start + minute);
+        assert!(tracker.stalled(2 * minute, start + minute).is_empty());
+        let stalled = tracker.stalled(2 * minute, start + 3 * minute);
+        assert_eq!(stalled.len(), 1);
+        assert_eq!(stalled[0].1, 3 * minute);
+
+        // Progress restarts the clock
+        current.get_mut("d1").unwrap().progress_bytes = 20;
+        tracker.update(&current, start + 3 * minute);
+        assert!(tracker.stalled(2 * minute, start + 4 * minute).is_empty());
+
+        // Finished downloads are forgotten
+        current.get_mut("d1").unwrap().status = DownloadStatus::Complete;
+        tracker.update(&current, start + 4 * minute);
+        assert!(tracker.stalled(minute, start + 10 * minute).is_empty());
+    }
+
+    // -- default_backend_type / read_config_context_length -------------------
+
+    #[test]
+    fn default_backend_follows_primary_file() {
+        assert_eq!(default_backend_type(Some("model.Q4_K_M.gguf")), "llamacpp");
+        assert_eq!(
+            default_backend_type(Some("model-00001-of-00002.safetensors")),
+            "vllm"
+        );
+        assert_eq!(default_backend_type(None), "llamacpp");
+    }
+
+    #[tokio::test]
+    async fn config_context_length_top_level_and_nested() {
+        let dir = std::env::temp_dir().join(format!("hf_config_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir_str = dir.to_str().unwrap();
+
+        std::fs::write(
+            dir.join("config.json"),
+            r#"{"max_position_embeddings": 32768}"#,
+        )
+        .unwrap();
+        assert_eq!(read_config_context_length(dir_str).await, Some(32768));
+
+        std::fs::write(
+            dir.join("config.json"),
+            r#"{"text_config": {"max_position_embeddings": 131072}}"#,
+        )
+        .unwrap();
+        assert_eq!(read_config_context_length(dir_str).await, Some(131072));
+
+        std::fs::remove_dir_all(&dir).ok();
+        assert_eq!(read_config_context_length(dir_str).await, None);
+    }
+
     // -- urlencoded ----------------------------------------------------------
 
     #[test]
@@ -1921,6 +4204,20 @@ mod tests {
         result.expect("Failed to parse GGUF")
     }
 
+    #[tokio::test]
+    async fn gguf_general_architecture() {
+        let arch = "nomic-bert";
+        let mut value = (arch.len() as u64).to_le_bytes().to_vec();
+        value.extend_from_slice(arch.as_bytes());
+        let data = build_gguf(&[
+            ("general.architecture", 8, value),
+            ("nomic-bert.block_count", 4, 12u32.to_le_bytes().to_vec()),
+        ]);
+        let meta = parse_gguf_bytes(&data).await;
+        assert_eq!(meta.architecture.as_deref(), Some("nomic-bert"));
+        assert_eq!(meta.block_count, Some(12));
+    }
+
     #[tokio::test]
     async fn gguf_scalar_head_count_kv() {
         let data = build_gguf(&[
@@ -2275,4 +4572,89 @@ mod tests {
         };
         assert_eq!(auto_runtime_overrides(&meta), "{}");
     }
+
+    #[test]
+    fn throttle_delay_holds_the_background_rate() {
+        use std::time::Duration;
+        let mb = 1024 * 1024;
+        // 4 MB at 2 MB/s is due at 2s; after 0.5s, wait out the rest
+        assert_eq!(
+            throttle_delay(4 * mb, 2 * mb, Duration::from_millis(500)),
+            Some(Duration::from_millis(1500))
+        );
+        // Already slower than the cap
+        assert_eq!(throttle_delay(mb, 2 * mb, Duration::from_secs(1)), None);
+    }
+
+    #[test]
+    fn bucket_take_waits_off_the_debt() {
+        use std::time::Duration;
+        let mb = 1024 * 1024;
+        // A full bucket covers one second of the rate without waiting
+        let (tokens, wait) = bucket_take(mb as f64, Duration::ZERO, mb, mb);
+        assert_eq!(tokens, 0.0);
+        assert_eq!(wait, None);
+        // Half a second later, another full second's worth is half due
+        let (tokens, wait) = bucket_take(tokens, Duration::from_millis(500), mb, mb);
+        assert_eq!(tokens, -(mb as f64) / 2.0);
+        assert_eq!(wait, Some(Duration::from_millis(500)));
+        // Idle time doesn't build up more than one second of burst
+        let (tokens, _) = bucket_take(0.0, Duration::from_secs(60), mb, 0);
+        assert_eq!(tokens, mb as f64);
+    }
+
+    #[test]
+    fn chunk_ranges_cover_the_file() {
+        assert_eq!(chunk_ranges(10, 4), vec![(0, 4), (4, 8), (8, 10)]);
+        assert_eq!(chunk_ranges(8, 4), vec![(0, 4), (4, 8)]);
+        assert!(chunk_ranges(0, 4).is_empty());
+    }
+
+    #[test]
+    fn chunk_settings_split_only_large_files() {
+        let mb = 1024 * 1024;
+        let settings = ChunkSettings {
+            connections: 4,
+            chunk_bytes: 64 * mb,
+        };
+        assert!(settings.splits(128 * mb));
+        assert!(!settings.splits(127 * mb));
+        let single = ChunkSettings {
+            connections: 1,
+            ..settings
+        };
+        assert!(!single.splits(1024 * mb));
+    }
+
+    #[test]
+    fn chunks_sidecar_parsing() {
+        let (chunk_bytes, done) = parse_chunks_sidecar("1024\n0\n2\n").unwrap();
+        assert_eq!(chunk_bytes, 1024);
+        assert_eq!(done, HashSet::from([0, 2]));
+        // A torn last line is ignored
+        let (_, done) = parse_chunks_sidecar("1024\n3\n1").unwrap();
+        assert_eq!(done, HashSet::from([3]));
+        assert!(parse_chunks_sidecar("").is_none());
+        assert!(parse_chunks_sidecar("0\n").is_none());
+    }
+
+    #[tokio::test]
+    async fn part_progress_counts_finished_chunks() {
+        let dir = std::env::temp_dir().join(format!("hf_chunks_test_{}", Uuid::new_v4()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let part = dir.join("model.gguf.part").to_string_lossy().into_owned();
+
+        // Preallocated, with chunks 0 and 2 of 4+4+2 bytes done
+        tokio::fs::write(&part, [0u8; 10]).await.unwrap();
+        tokio::fs::write(chunks_sidecar(&part), "4\n0\n2\n")
+            .await
+            .unwrap();
+        assert_eq!(part_file_progress(&part, Some(10)).await, 6);
+
+        // Without a sidecar the part file is a prefix of the file
+        tokio::fs::remove_file(chunks_sidecar(&part)).await.unwrap();
+        assert_eq!(part_file_progress(&part, Some(10)).await, 10);
+
+        let _ = tokio::fs::remove_dir_all(&dir).await;
+    }
 }
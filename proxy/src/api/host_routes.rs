@@ -0,0 +1,197 @@
+//! Admin API for host routes (see `crate::host_routes`).
+//!
+//! `PUT /api/admin/host-routes/{hostname}` creates or replaces a route and
+//! `DELETE` removes it. The built-in API and chat hostnames are listed but
+//! can't be changed here. Every change reloads the routing cache and is
+//! audit-logged.
+
+use std::path::Path as FsPath;
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{error, info};
+
+use super::error;
+use crate::auth::SessionAuth;
+use crate::host_routes::{self, HostTarget};
+use crate::AppState;
+
+/// Longest hostname DNS allows.
+const MAX_HOSTNAME: usize = 253;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/host-routes", get(list_routes))
+        .route(
+            "/host-routes/{hostname}",
+            put(put_route).delete(delete_route),
+        )
+        .with_state(state)
+}
+
+#[derive(Debug, Deserialize)]
+struct RouteRequest {
+    target: String,
+    #[serde(default)]
+    description: String,
+}
+
+fn bad_request(message: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": message }))).into_response()
+}
+
+fn valid_hostname(host: &str) -> bool {
+    !host.is_empty()
+        && host.len() <= MAX_HOSTNAME
+        && host
+            .split('.')
+            .all(|label| !label.is_empty() && !label.starts_with('-') && !label.ends_with('-'))
+        && host
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '.'))
+}
+
+fn is_builtin(state: &AppState, host: &str) -> bool {
+    host == host_routes::normalize_host(&state.config.api_hostname)
+        || host == host_routes::normalize_host(&state.config.chat_hostname)
+}
+
+/// Refresh the cache the dispatcher reads after a change.
+async fn reload_routes(state: &AppState) {
+    if let Err(e) = state.host_routes.reload(&state.db).await {
+        error!(error = %e, "Failed to reload host routes");
+    }
+}
+
+/// GET /api/admin/host-routes — The built-in hostnames and the routing table.
+async fn list_routes(State(state): State<Arc<AppState>>) -> Response {
+    match host_routes::load_routes(&state.db).await {
+        Ok(routes) => Json(json!({
+            "builtin": [
+                { "hostname": state.config.api_hostname, "target": "api" },
+                { "hostname": state.config.chat_hostname, "target": "chat" },
+            ],
+            "routes": routes,
+        }))
+        .into_response(),
+        Err(e) => error::internal_error("host_routes:list", e),
+    }
+}
+
+/// PUT /api/admin/host-routes/:hostname — Create or replace a route.
+async fn put_route(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(hostname): Path<String>,
+    Json(req): Json<RouteRequest>,
+) -> Response {
+    let hostname = hostname.to_ascii_lowercase();
+    if !valid_hostname(&hostname) {
+        return bad_request("Invalid hostname");
+    }
+    if is_builtin(&state, &hostname) {
+        return (
+            StatusCode::CONFLICT,
+            Json(
+                json!({ "error": "API_HOSTNAME and CHAT_HOSTNAME are configured by environment" }),
+            ),
+        )
+            .into_response();
+    }
+    if let Some(r) = error::validate_len("description", &req.description, error::MAX_DESCRIPTION) {
+        return r;
+    }
+    let target: HostTarget = match req.target.parse() {
+        Ok(target) => target,
+        Err(message) => return bad_request(&message),
+    };
+    if let HostTarget::Site(name) = &target {
+        if !FsPath::new(&state.config.sites_path).join(name).is_dir() {
+            return bad_request(&format!("No site directory {name} under SITES_PATH"));
+        }
+    }
+
+    let target = target.to_string();
+    if let Err(e) = sqlx::query(
+        "INSERT INTO host_routes (hostname, target, description, created_by) VALUES (?, ?, ?, ?) \
+         ON CONFLICT(hostname) DO UPDATE SET target = excluded.target, \
+         description = excluded.description, updated_at = datetime('now')",
+    )
+    .bind(&hostname)
+    .bind(&target)
+    .bind(&req.description)
+    .bind(&session.user_id)
+    .execute(&state.db.pool)
+    .await
+    {
+        return error::internal_error("host_routes:put", e);
+    }
+    reload_routes(&state).await;
+
+    info!(target: "audit", action = "host_route.update", actor = %session.user_id, resource = %hostname, host_target = %target, "Admin updated host route");
+
+    match host_routes::load_routes(&state.db).await {
+        Ok(routes) => match routes.into_iter().find(|r| r.hostname == hostname) {
+            Some(route) => Json(route).into_response(),
+            None => error::internal_error("host_routes:put", "route missing after upsert"),
+        },
+        Err(e) => error::internal_error("host_routes:put", e),
+    }
+}
+
+/// DELETE /api/admin/host-routes/:hostname — Remove a route; the host then
+/// gets 421 (or the combined router when both built-in hostnames match).
+async fn delete_route(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(hostname): Path<String>,
+) -> Response {
+    let hostname = hostname.to_ascii_lowercase();
+    match sqlx::query("DELETE FROM host_routes WHERE hostname = ?")
+        .bind(&hostname)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Host route not found" })),
+        )
+            .into_response(),
+        Ok(_) => {
+            reload_routes(&state).await;
+            info!(target: "audit", action = "host_route.delete", actor = %session.user_id, resource = %hostname, "Admin deleted host route");
+            Json(json!({ "status": "deleted" })).into_response()
+        }
+        Err(e) => error::internal_error("host_routes:delete", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hostname_validation() {
+        for host in ["status.example.com", "llm-1.acme.io", "localhost"] {
+            assert!(valid_hostname(host), "{host}");
+        }
+        for host in [
+            "",
+            "Status.example.com",
+            "a..b",
+            ".example.com",
+            "-bad.example.com",
+            "host:8080",
+            "under_score.example.com",
+        ] {
+            assert!(!valid_hostname(host), "{host}");
+        }
+        assert!(!valid_hostname(&"a".repeat(MAX_HOSTNAME + 1)));
+    }
+}
@@ -0,0 +1,326 @@
+//! Hygiene reports for long-running deployments (`/api/admin/hygiene`).
+//!
+//! Lists what has gone stale: API tokens unused for `days`, sessions and
+//! trusted devices still held by deactivated users, models nobody has
+//! requested since they were added more than `days` ago, and categories
+//! without models. Each list has a cleanup action that re-runs the same
+//! query and acts on everything it matches. Unused models go through the
+//! normal model delete, so they land in the trash and two-person approval
+//! still applies.
+
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+
+use super::admin;
+use super::error;
+use super::token_revocation::{self, MatchedToken, TokenFilter};
+use crate::auth::sessions::{self, SessionStore};
+use crate::auth::SessionAuth;
+use crate::AppState;
+
+/// Default idle period for tokens and models.
+const DEFAULT_DAYS: i64 = 90;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/hygiene", get(report))
+        .route("/hygiene/tokens/revoke", post(revoke_tokens))
+        .route("/hygiene/sessions/revoke", post(revoke_sessions))
+        .route("/hygiene/models/delete", post(delete_models))
+        .route("/hygiene/categories/delete", post(delete_categories))
+        .with_state(state)
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct HygieneQuery {
+    /// Tokens unused and models unrequested for this many days are stale.
+    days: Option<i64>,
+}
+
+impl HygieneQuery {
+    /// The `created_at` cutoff in the DB's `YYYY-MM-DD HH:MM:SS` form, or a
+    /// 400 if `days` isn't positive.
+    fn cutoff(&self) -> Result<String, Response> {
+        let days = self.days.unwrap_or(DEFAULT_DAYS);
+        if !(1..=36_500).contains(&days) {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "days must be between 1 and 36500" })),
+            )
+                .into_response());
+        }
+        Ok((Utc::now() - chrono::Duration::days(days))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string())
+    }
+}
+
+/// A deactivated user who still has sessions or trusted devices.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct StaleSessions {
+    user_id: String,
+    email: Option<String>,
+    deactivated_at: Option<String>,
+    /// Counted from the session store, which may not be SQLite.
+    #[sqlx(skip)]
+    sessions: u64,
+    devices: i64,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct UnusedModel {
+    id: String,
+    hf_repo: String,
+    category_id: Option<String>,
+    loaded: bool,
+    created_at: String,
+}
+
+/// A category with no models. It can only be deleted once no token or
+/// access mapping refers to it.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct EmptyCategory {
+    id: String,
+    name: String,
+    tokens: i64,
+    access_mappings: i64,
+}
+
+impl EmptyCategory {
+    fn deletable(&self) -> bool {
+        self.tokens == 0 && self.access_mappings == 0
+    }
+}
+
+/// Tokens created before `cutoff` and unused since.
+fn unused_token_filter(cutoff: String) -> TokenFilter {
+    TokenFilter {
+        created_before: Some(cutoff.clone()),
+        last_used_before: Some(cutoff),
+        ..Default::default()
+    }
+}
+
+async fn unused_tokens(
+    pool: &sqlx::SqlitePool,
+    cutoff: &str,
+) -> Result<Vec<MatchedToken>, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    token_revocation::matching_tokens(&mut *conn, &unused_token_filter(cutoff.to_string())).await
+}
+
+async fn stale_sessions(state: &AppState) -> anyhow::Result<Vec<StaleSessions>> {
+    let mut users: Vec<StaleSessions> = sqlx::query_as(
+        r#"
+        SELECT u.id AS user_id, u.email, u.deactivated_at,
+               (SELECT COUNT(*) FROM trusted_devices d WHERE d.user_id = u.id) AS devices
+        FROM users u
+        WHERE u.active = 0
+        ORDER BY u.deactivated_at
+        "#,
+    )
+    .fetch_all(&state.db.pool)
+    .await?;
+    for user in &mut users {
+        user.sessions = state.sessions.count_for_user(&user.user_id).await?;
+    }
+    users.retain(|u| u.sessions > 0 || u.devices > 0);
+    Ok(users)
+}
+
+async fn unused_models(
+    pool: &sqlx::SqlitePool,
+    cutoff: &str,
+) -> Result<Vec<UnusedModel>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT m.id, m.hf_repo, m.category_id, m.loaded, m.created_at FROM models m \
+         WHERE m.created_at < ? \
+           AND NOT EXISTS (SELECT 1 FROM usage_log l WHERE l.model_id = m.id) \
+         ORDER BY m.created_at",
+    )
+    .bind(cutoff)
+    .fetch_all(pool)
+    .await
+}
+
+async fn empty_categories(pool: &sqlx::SqlitePool) -> Result<Vec<EmptyCategory>, sqlx::Error> {
+    sqlx::query_as(
+        "SELECT c.id, c.name, \
+                (SELECT COUNT(*) FROM tokens t WHERE t.category_id = c.id) AS tokens, \
+                (SELECT COUNT(*) FROM idp_model_access a WHERE a.category_id = c.id) AS access_mappings \
+         FROM model_categories c \
+         WHERE NOT EXISTS (SELECT 1 FROM models m WHERE m.category_id = c.id) \
+         ORDER BY c.name",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+/// GET /api/admin/hygiene?days= — Report stale tokens, sessions, models and
+/// categories.
+async fn report(State(state): State<Arc<AppState>>, Query(query): Query<HygieneQuery>) -> Response {
+    let cutoff = match query.cutoff() {
+        Ok(cutoff) => cutoff,
+        Err(r) => return r,
+    };
+    match build_report(&state, &cutoff).await {
+        Ok(mut report) => {
+            report["days"] = json!(query.days.unwrap_or(DEFAULT_DAYS));
+            Json(report).into_response()
+        }
+        Err(e) => error::internal_error("hygiene:report", e),
+    }
+}
+
+async fn build_report(state: &AppState, cutoff: &str) -> anyhow::Result<serde_json::Value> {
+    let pool = &state.db.pool;
+    let tokens = unused_tokens(pool, cutoff).await?;
+    let sessions = stale_sessions(state).await?;
+    let models = unused_models(pool, cutoff).await?;
+    let categories = empty_categories(pool).await?;
+    Ok(json!({
+        "unused_tokens": tokens,
+        "stale_sessions": sessions,
+        "unused_models": models,
+        "empty_categories": categories,
+    }))
+}
+
+/// POST /api/admin/hygiene/tokens/revoke?days= — Revoke every token unused
+/// for `days`.
+async fn revoke_tokens(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Query(query): Query<HygieneQuery>,
+) -> Response {
+    let cutoff = match query.cutoff() {
+        Ok(cutoff) => cutoff,
+        Err(r) => return r,
+    };
+    match token_revocation::revoke_matching(&state, &unused_token_filter(cutoff.clone())).await {
+        Ok(tokens) => {
+            info!(target: "audit", action = "hygiene.revoke_tokens", actor = %session.user_id, unused_since = %cutoff, revoked = tokens.len(), "Admin revoked unused API tokens");
+            Json(json!({ "revoked": tokens.len(), "tokens": tokens })).into_response()
+        }
+        Err(e) => error::internal_error("hygiene:revoke_tokens", e),
+    }
+}
+
+/// POST /api/admin/hygiene/sessions/revoke — Drop the sessions and trusted
+/// devices of deactivated users.
+async fn revoke_sessions(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+) -> Response {
+    let users = match stale_sessions(&state).await {
+        Ok(users) => users,
+        Err(e) => return error::internal_error("hygiene:revoke_sessions", e),
+    };
+    let mut revoked = 0;
+    for user in &users {
+        match sessions::revoke_user_sessions(&state.sessions, &state.db, &user.user_id).await {
+            Ok(n) => revoked += n,
+            Err(e) => return error::internal_error("hygiene:revoke_sessions", e),
+        }
+        info!(target: "audit", action = "user.revoke_sessions", actor = %session.user_id, resource = %user.user_id, sessions = user.sessions, "Admin revoked sessions of deactivated user");
+    }
+    Json(json!({ "users": users.len(), "sessions": revoked })).into_response()
+}
+
+/// POST /api/admin/hygiene/models/delete?days= — Delete every model that
+/// hasn't been requested since it was added more than `days` ago.
+async fn delete_models(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Query(query): Query<HygieneQuery>,
+) -> Response {
+    let cutoff = match query.cutoff() {
+        Ok(cutoff) => cutoff,
+        Err(r) => return r,
+    };
+    let models = match unused_models(&state.db.pool, &cutoff).await {
+        Ok(models) => models,
+        Err(e) => return error::internal_error("hygiene:delete_models", e),
+    };
+    // Each delete is audited on its own; pinned models answer 409 and
+    // approval-gated deletes 202, and are reported as such
+    let mut results = Vec::with_capacity(models.len());
+    for model in &models {
+        let response =
+            admin::delete_model_core(&state, &session.user_id, &model.id, false, false, None).await;
+        results.push(json!({
+            "model_id": model.id,
+            "hf_repo": model.hf_repo,
+            "status": response.status().as_u16(),
+        }));
+    }
+    let deleted = results.iter().filter(|r| r["status"] == 200).count();
+    Json(json!({ "deleted": deleted, "results": results })).into_response()
+}
+
+/// POST /api/admin/hygiene/categories/delete — Delete every empty category
+/// that no token or access mapping refers to.
+async fn delete_categories(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+) -> Response {
+    let categories = match empty_categories(&state.db.pool).await {
+        Ok(categories) => categories,
+        Err(e) => return error::internal_error("hygiene:delete_categories", e),
+    };
+    let mut tx = match state.db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return error::internal_error("hygiene:delete_categories", e),
+    };
+    let (deletable, skipped): (Vec<_>, Vec<_>) =
+        categories.into_iter().partition(EmptyCategory::deletable);
+    for category in &deletable {
+        let result = async {
+            sqlx::query(
+                "UPDATE models SET suggested_category_id = NULL, category_suggestion_reason = NULL \
+                 WHERE suggested_category_id = ?",
+            )
+            .bind(&category.id)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query("DELETE FROM model_categories WHERE id = ?")
+                .bind(&category.id)
+                .execute(&mut *tx)
+                .await
+        }
+        .await;
+        if let Err(e) = result {
+            return error::internal_error("hygiene:delete_categories", e);
+        }
+    }
+    if let Err(e) = tx.commit().await {
+        return error::internal_error("hygiene:delete_categories", e);
+    }
+    for category in &deletable {
+        info!(target: "audit", action = "category.delete", actor = %session.user_id, resource = %category.id, name = %category.name, "Admin deleted empty category");
+    }
+    Json(json!({ "deleted": deletable, "skipped": skipped })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cutoff_rejects_non_positive_days() {
+        assert!(HygieneQuery { days: Some(0) }.cutoff().is_err());
+        assert!(HygieneQuery { days: Some(-5) }.cutoff().is_err());
+        let cutoff = HygieneQuery::default().cutoff().unwrap();
+        assert_eq!(cutoff.len(), "2026-01-01 00:00:00".len());
+        assert!(cutoff < Utc::now().format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+}
@@ -0,0 +1,480 @@
+//! Operator runbook (`GET /api/admin/issues`).
+//!
+//! Gathers the problems that currently need an operator into one list,
+//! most severe first, each with a suggested action and links to the portal
+//! page and API endpoint to handle it from: model storage over 90% full,
+//! crashed backend containers, models the watchdog keeps restarting or has
+//! given up on, certificates that have expired or expire within
+//! [`CERT_WARN_DAYS`], downloads that stopped making progress, and
+//! reservation requests left pending for over a day. Nothing is stored;
+//! every call re-checks.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{middleware, Json, Router};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use tracing::warn;
+
+use super::common;
+use super::error;
+use super::hf::{self, DiskUsage, DownloadState};
+use crate::auth::rbac::{self, Permission};
+use crate::docker::tls::{self, Certificate};
+use crate::metrics::ContainerStatus;
+use crate::watchdog::Alert;
+use crate::AppState;
+
+/// Model storage this full is a warning...
+const DISK_WARN: f64 = 0.90;
+/// ...and this full is critical.
+const DISK_CRITICAL: f64 = 0.97;
+
+/// Certificates expiring within this many days are reported.
+const CERT_WARN_DAYS: i64 = 14;
+
+/// Running downloads without progress for this long are stalled.
+const DOWNLOAD_STALL: Duration = Duration::from_secs(600);
+
+/// Pending reservations older than this are reported.
+const RESERVATION_PENDING_HOURS: i64 = 24;
+
+/// Watchdog restarts since the last stable run that count as repeated
+/// health failures.
+const REPEATED_RESTARTS: u32 = 2;
+
+/// Watchdog alerts are reported for this long, unless the model has been
+/// started again.
+const ALERT_HOURS: i64 = 24;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/issues", get(list_issues))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::View,
+            rbac::require_permission,
+        ))
+        .with_state(state)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Severity {
+    Critical,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Serialize)]
+struct Issue {
+    kind: &'static str,
+    severity: Severity,
+    /// The model, download, certificate or path it concerns.
+    resource: String,
+    title: String,
+    /// What to do about it.
+    action: String,
+    /// Portal page to handle it on.
+    link: &'static str,
+    /// API endpoint with the details.
+    api: Option<String>,
+}
+
+fn disk_issue(path: &str, disk: &DiskUsage) -> Option<Issue> {
+    if disk.total_bytes == 0 {
+        return None;
+    }
+    let used = disk.used_bytes as f64 / disk.total_bytes as f64;
+    let severity = if used >= DISK_CRITICAL {
+        Severity::Critical
+    } else if used > DISK_WARN {
+        Severity::Warning
+    } else {
+        return None;
+    };
+    Some(Issue {
+        kind: "disk_full",
+        severity,
+        resource: path.to_string(),
+        title: format!(
+            "Model storage is {:.0}% full ({:.1} GB free)",
+            used * 100.0,
+            disk.free_bytes as f64 / 1e9
+        ),
+        action: "Delete unused models or quantizations, empty the model trash, or set \
+                 model_evict_unused_days"
+            .to_string(),
+        link: "/portal/admin/models",
+        api: Some("/api/admin/system".to_string()),
+    })
+}
+
+fn logs_api(model_id: &str) -> Option<String> {
+    Some(format!("/api/admin/containers/{model_id}/logs?tail=200"))
+}
+
+/// Containers that exited or died. `restarting` are the models the watchdog
+/// is about to restart.
+fn container_issues(containers: &[ContainerStatus], restarting: &[String]) -> Vec<Issue> {
+    containers
+        .iter()
+        .filter(|c| matches!(c.state.as_deref(), Some("exited" | "dead")))
+        .map(|c| Issue {
+            kind: "container_crashed",
+            severity: Severity::Critical,
+            resource: c.model_id.clone(),
+            title: format!(
+                "Container for {} has {}",
+                c.model_id,
+                c.state.as_deref().unwrap_or_default()
+            ),
+            action: if restarting.contains(&c.model_id) {
+                "The watchdog will restart it; check its logs for the cause".to_string()
+            } else {
+                "Check its logs for the cause, then start the model again".to_string()
+            },
+            link: "/portal/admin/system",
+            api: logs_api(&c.model_id),
+        })
+        .collect()
+}
+
+/// Models the watchdog gave up on recently and that aren't loaded again,
+/// and models it has had to restart repeatedly.
+fn watchdog_issues(
+    alerts: &[Alert],
+    restarting: &[(String, u32)],
+    loaded: &[String],
+    now: DateTime<Utc>,
+) -> Vec<Issue> {
+    let mut issues: Vec<Issue> = Vec::new();
+    for alert in alerts {
+        let recent = now - alert.raised_at < chrono::Duration::hours(ALERT_HOURS);
+        if !recent
+            || loaded.contains(&alert.model_id)
+            || issues.iter().any(|i| i.resource == alert.model_id)
+        {
+            continue;
+        }
+        issues.push(Issue {
+            kind: "watchdog_gave_up",
+            severity: Severity::Critical,
+            resource: alert.model_id.clone(),
+            title: format!(
+                "{} kept crashing ({}) and was unloaded after {} restarts",
+                alert.model_id, alert.reason, alert.restarts
+            ),
+            action: "Check the model's launch profile (context size, parallel slots, memory \
+                     limit) against its GPU, then start it again"
+                .to_string(),
+            link: "/portal/admin/models",
+            api: Some("/api/admin/system".to_string()),
+        });
+    }
+    for (model_id, restarts) in restarting {
+        if *restarts >= REPEATED_RESTARTS {
+            issues.push(Issue {
+                kind: "repeated_health_failures",
+                severity: Severity::Warning,
+                resource: model_id.clone(),
+                title: format!("{model_id} has crashed and been restarted {restarts} times"),
+                action: "Check its logs; if it runs out of memory, lower its context size or \
+                         parallel slots"
+                    .to_string(),
+                link: "/portal/admin/system",
+                api: logs_api(model_id),
+            });
+        }
+    }
+    issues
+}
+
+fn cert_issue(cert: &Certificate, expires: DateTime<Utc>, now: DateTime<Utc>) -> Option<Issue> {
+    let days = (expires - now).num_days();
+    let (severity, title) = if expires <= now {
+        (
+            Severity::Critical,
+            format!(
+                "The {} expired on {}",
+                cert.name,
+                expires.format("%Y-%m-%d")
+            ),
+        )
+    } else if days < CERT_WARN_DAYS {
+        (
+            Severity::Warning,
+            format!(
+                "The {} expires in {days} days, on {}",
+                cert.name,
+                expires.format("%Y-%m-%d")
+            ),
+        )
+    } else {
+        return None;
+    };
+    Some(Issue {
+        kind: "cert_expiring",
+        severity,
+        resource: cert.path.display().to_string(),
+        title,
+        action: cert.renew.to_string(),
+        link: "/portal/admin/system",
+        api: None,
+    })
+}
+
+fn download_issue(dl: &DownloadState, stalled: Duration) -> Issue {
+    let progress = if dl.total_bytes > 0 {
+        format!(
+            ", {:.0}% done",
+            dl.progress_bytes as f64 * 100.0 / dl.total_bytes as f64
+        )
+    } else {
+        String::new()
+    };
+    Issue {
+        kind: "download_stalled",
+        severity: Severity::Warning,
+        resource: dl.id.clone(),
+        title: format!(
+            "Download of {} has made no progress for {} minutes{progress}",
+            dl.hf_repo,
+            stalled.as_secs() / 60
+        ),
+        action: "Cancel and resume it; if it stalls again, check the connection to Hugging Face \
+                 and HF_TOKEN"
+            .to_string(),
+        link: "/portal/admin/models",
+        api: Some("/api/user/hf/downloads".to_string()),
+    }
+}
+
+fn reservations_issue(count: i64, oldest: &str) -> Option<Issue> {
+    (count > 0).then(|| Issue {
+        kind: "reservations_pending",
+        severity: Severity::Info,
+        resource: "reservations".to_string(),
+        title: format!(
+            "{count} reservation request{} waiting over {RESERVATION_PENDING_HOURS} hours, \
+             the oldest since {oldest}",
+            if count == 1 { "" } else { "s" }
+        ),
+        action: "Approve or reject them, or add an auto-approval rule".to_string(),
+        link: "/portal/admin/reservations",
+        api: Some("/api/admin/reservations".to_string()),
+    })
+}
+
+/// The certificates to check: the HTTPS certificate when one is configured
+/// (ACME certificates renew themselves) and the backend TLS ones.
+fn certificates(state: &AppState) -> Vec<Certificate> {
+    let mut certs = Vec::new();
+    if let Some(path) = &state.config.tls_cert_path {
+        certs.push(Certificate {
+            name: "HTTPS certificate",
+            path: PathBuf::from(path),
+            renew: "Replace the certificate at TLS_CERT_PATH and restart the proxy",
+        });
+    }
+    if let Some(backend_tls) = &state.docker.backend_tls {
+        certs.extend(backend_tls.certificates());
+    }
+    certs
+}
+
+/// GET /api/admin/issues — Problems that need an operator, most severe
+/// first.
+async fn list_issues(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let now = Utc::now();
+    let mut issues = Vec::new();
+
+    match hf::get_disk_usage(&state.config.model_path) {
+        Ok(disk) => issues.extend(disk_issue(&state.config.model_path, &disk)),
+        Err(e) => warn!(error = %e, "Failed to read disk usage"),
+    }
+
+    let restarting = state.watchdog.restarting();
+    if let Ok(containers) = state.docker.list_managed_containers().await {
+        let statuses =
+            common::extract_container_statuses(containers, &HashMap::new(), &state.warmups);
+        let restarting: Vec<String> = restarting.iter().map(|(m, _)| m.clone()).collect();
+        issues.extend(container_issues(&statuses, &restarting));
+    }
+
+    let loaded: Vec<(String,)> = match sqlx::query_as("SELECT id FROM models WHERE loaded = 1")
+        .fetch_all(&state.db.pool)
+        .await
+    {
+        Ok(rows) => rows,
+        Err(e) => return error::internal_error("list_issues:models", e),
+    };
+    let loaded: Vec<String> = loaded.into_iter().map(|(id,)| id).collect();
+    issues.extend(watchdog_issues(
+        &state.watchdog.alerts(),
+        &restarting,
+        &loaded,
+        now,
+    ));
+
+    for cert in certificates(&state) {
+        match tls::cert_expiry(&cert.path).await {
+            Ok(expires) => issues.extend(cert_issue(&cert, expires, now)),
+            Err(e) => {
+                warn!(cert = %cert.path.display(), error = %e, "Failed to read certificate expiry")
+            }
+        }
+    }
+
+    issues.extend(
+        state
+            .download_progress
+            .stalled(DOWNLOAD_STALL)
+            .iter()
+            .map(|(dl, stalled)| download_issue(dl, *stalled)),
+    );
+
+    let (count, oldest): (i64, Option<String>) = match sqlx::query_as(
+        "SELECT COUNT(*), MIN(created_at) FROM reservations
+         WHERE status = 'pending' AND created_at < datetime('now', ?)",
+    )
+    .bind(format!("-{RESERVATION_PENDING_HOURS} hours"))
+    .fetch_one(&state.db.pool)
+    .await
+    {
+        Ok(row) => row,
+        Err(e) => return error::internal_error("list_issues:reservations", e),
+    };
+    issues.extend(reservations_issue(count, oldest.as_deref().unwrap_or("")));
+
+    // Stable, so each severity keeps the order above
+    issues.sort_by_key(|i| i.severity);
+    let count = |severity| issues.iter().filter(|i| i.severity == severity).count();
+
+    Json(json!({
+        "checked_at": now.to_rfc3339(),
+        "counts": {
+            "critical": count(Severity::Critical),
+            "warning": count(Severity::Warning),
+            "info": count(Severity::Info),
+        },
+        "issues": issues,
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn disk(used_percent: u64) -> DiskUsage {
+        DiskUsage {
+            total_bytes: 100_000_000_000,
+            used_bytes: used_percent * 1_000_000_000,
+            free_bytes: (100 - used_percent) * 1_000_000_000,
+        }
+    }
+
+    #[test]
+    fn disk_thresholds() {
+        assert!(disk_issue("/models", &disk(90)).is_none());
+        let warn = disk_issue("/models", &disk(93)).unwrap();
+        assert_eq!(warn.severity, Severity::Warning);
+        assert_eq!(warn.title, "Model storage is 93% full (7.0 GB free)");
+        assert_eq!(
+            disk_issue("/models", &disk(98)).unwrap().severity,
+            Severity::Critical
+        );
+        assert!(disk_issue(
+            "/models",
+            &DiskUsage {
+                total_bytes: 0,
+                used_bytes: 0,
+                free_bytes: 0,
+            }
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn cert_thresholds() {
+        let cert = Certificate {
+            name: "HTTPS certificate",
+            path: PathBuf::from("/certs/cert.pem"),
+            renew: "Renew it",
+        };
+        let now = Utc::now();
+        let days = chrono::Duration::days;
+        assert!(cert_issue(&cert, now + days(30), now).is_none());
+        let soon = cert_issue(&cert, now + days(5) + chrono::Duration::hours(1), now).unwrap();
+        assert_eq!(soon.severity, Severity::Warning);
+        assert!(soon.title.contains("expires in 5 days"));
+        assert_eq!(soon.resource, "/certs/cert.pem");
+        let expired = cert_issue(&cert, now - days(1), now).unwrap();
+        assert_eq!(expired.severity, Severity::Critical);
+    }
+
+    #[test]
+    fn watchdog_alerts_until_model_is_back() {
+        let now = Utc::now();
+        let alert = |model_id: &str, hours_ago| Alert {
+            model_id: model_id.to_string(),
+            reason: "OOM-killed".to_string(),
+            restarts: 3,
+            raised_at: now - chrono::Duration::hours(hours_ago),
+        };
+        let alerts = [
+            alert("m1", 1),
+            alert("m1", 2),
+            alert("m2", 1),
+            alert("m3", 48),
+        ];
+        let restarting = [("m4".to_string(), 1), ("m5".to_string(), 2)];
+        let issues = watchdog_issues(&alerts, &restarting, &["m2".to_string()], now);
+
+        let kinds: Vec<(&str, &str)> = issues
+            .iter()
+            .map(|i| (i.kind, i.resource.as_str()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("watchdog_gave_up", "m1"),
+                ("repeated_health_failures", "m5")
+            ]
+        );
+    }
+
+    #[test]
+    fn crashed_containers_only() {
+        let status = |model_id: &str, state: &str| ContainerStatus {
+            model_id: model_id.to_string(),
+            backend_type: "llamacpp".to_string(),
+            healthy: state == "running",
+            state: Some(state.to_string()),
+            vram_used_mb: None,
+            vram_by_device_mb: Default::default(),
+            warmup: None,
+        };
+        let containers = [
+            status("m1", "running"),
+            status("m2", "exited"),
+            status("m3", "dead"),
+        ];
+        let issues = container_issues(&containers, &["m3".to_string()]);
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].resource, "m2");
+        assert!(issues[0].action.starts_with("Check its logs"));
+        assert!(issues[1].action.starts_with("The watchdog"));
+        assert_eq!(
+            issues[1].api.as_deref(),
+            Some("/api/admin/containers/m3/logs?tail=200")
+        );
+    }
+}
@@ -1,28 +1,92 @@
 pub mod admin;
 pub mod anthropic;
+pub mod approvals;
+pub mod archive;
+pub mod audit;
+pub mod capabilities;
+pub mod categorize;
 pub mod common;
+pub mod compat;
+pub mod config_bundle;
+pub mod container_logs;
+pub mod container_replace;
+pub mod context_guard;
+pub mod conversations;
+pub mod elevation;
 pub mod error;
+pub mod feature_flags;
 pub mod hf;
+pub mod host_routes;
+pub mod hygiene;
+pub mod issues;
+pub mod model_aliases;
+pub mod model_files;
+pub mod model_import;
+pub mod model_trace;
+pub mod model_trash;
 pub mod openai;
+pub mod prompt_dedup;
+pub mod prompt_uploads;
+pub mod request_log;
 pub mod reservation;
+pub mod reservation_rules;
+pub mod sampling_defaults;
+pub mod system_prompts;
+pub mod token_revocation;
+pub mod tools;
+pub mod usage_privacy;
 pub mod user;
+pub mod vector_store;
+pub mod warmup;
 
 use std::sync::Arc;
 
 use axum::middleware;
 use axum::Router;
 
-use crate::auth::admin_only_middleware;
+use crate::auth::rbac::{self, Permission};
 use crate::AppState;
 
 pub fn routes(state: Arc<AppState>) -> Router {
+    // Admin routes set their own permission; these are for full admins only
+    let full_admin_routes = request_log::admin_routes(state.clone())
+        .merge(approvals::admin_routes(state.clone()))
+        .merge(elevation::admin_routes(state.clone()))
+        .merge(system_prompts::admin_routes(state.clone()))
+        .merge(audit::admin_routes(state.clone()))
+        .merge(hygiene::admin_routes(state.clone()))
+        .merge(model_trace::admin_routes(state.clone()))
+        .merge(feature_flags::admin_routes(state.clone()))
+        .merge(config_bundle::admin_routes(state.clone()))
+        .merge(host_routes::admin_routes(state.clone()))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Admin,
+            rbac::require_permission,
+        ));
+
     let admin_routes = admin::routes(state.clone())
+        .merge(model_trash::admin_routes(state.clone()))
+        .merge(container_replace::admin_routes(state.clone()))
+        .merge(container_logs::admin_routes(state.clone()))
+        .merge(prompt_dedup::admin_routes(state.clone()))
+        .merge(issues::admin_routes(state.clone()))
+        .merge(categorize::admin_routes(state.clone()))
+        .merge(model_aliases::admin_routes(state.clone()))
+        .merge(model_files::admin_routes(state.clone()))
+        .merge(model_import::admin_routes(state.clone()))
+        .merge(sampling_defaults::admin_routes(state.clone()))
+        .merge(token_revocation::admin_routes(state.clone()))
         .merge(reservation::admin_routes(state.clone()))
-        .layer(middleware::from_fn(admin_only_middleware));
+        .merge(reservation_rules::admin_routes(state.clone()))
+        .merge(full_admin_routes)
+        .layer(middleware::from_fn(rbac::admin_area_middleware));
 
     Router::new()
         .nest("/admin", admin_routes)
         .nest("/user", user::routes(state.clone()))
         .nest("/user", reservation::user_routes(state.clone()))
+        .nest("/user", archive::user_routes(state.clone()))
+        .nest("/user", conversations::user_routes(state.clone()))
+        .nest("/user", elevation::user_routes(state.clone()))
         .nest("/user/hf", hf::routes(state))
 }
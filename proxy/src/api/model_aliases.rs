@@ -0,0 +1,229 @@
+//! Model aliases for the OpenAI API.
+//!
+//! An alias is an admin-chosen model name (`gpt-4o`) that routes to a model
+//! or a category. The resolver checks aliases before real model names, so
+//! clients with hard-coded model names keep working when the model behind
+//! the alias is swapped. Aliases of a deleted model or category go with it.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::{middleware, Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+use super::error;
+use crate::auth::rbac::{self, Permission};
+use crate::auth::SessionAuth;
+use crate::AppState;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/aliases", get(list_aliases).post(create_alias))
+        .route("/aliases/{id}", put(update_alias).delete(delete_alias))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Models,
+            rbac::require_permission,
+        ))
+        .with_state(state)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ModelAlias {
+    pub id: String,
+    pub alias: String,
+    pub model_id: Option<String>,
+    pub category_id: Option<String>,
+    /// The target's hf_repo or category name, for display.
+    pub target_name: Option<String>,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+const ALIAS_SELECT: &str = "SELECT a.id, a.alias, a.model_id, a.category_id, \
+     COALESCE(m.hf_repo, c.name) AS target_name, a.created_by, a.created_at, a.updated_at \
+     FROM model_aliases a \
+     LEFT JOIN models m ON m.id = a.model_id \
+     LEFT JOIN model_categories c ON c.id = a.category_id";
+
+#[derive(Debug, Deserialize)]
+struct AliasRequest {
+    alias: String,
+    model_id: Option<String>,
+    category_id: Option<String>,
+}
+
+fn bad_request(message: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": message }))).into_response()
+}
+
+fn not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "Alias not found" })),
+    )
+        .into_response()
+}
+
+/// Validate an alias request into an error response, or `None` if it is
+/// usable. `id` is the alias being updated, which may keep its own name.
+async fn reject_invalid(
+    pool: &sqlx::SqlitePool,
+    req: &AliasRequest,
+    id: Option<&str>,
+) -> Option<Response> {
+    let alias = req.alias.trim();
+    if let Some(r) = error::validate_len("alias", alias, error::MAX_NAME) {
+        return Some(r);
+    }
+    if alias.is_empty() {
+        return Some(bad_request("alias is required"));
+    }
+    let (table, target) = match (&req.model_id, &req.category_id) {
+        (Some(model_id), None) => ("models", model_id),
+        (None, Some(category_id)) => ("model_categories", category_id),
+        _ => return Some(bad_request("Set exactly one of model_id and category_id")),
+    };
+    match sqlx::query_scalar::<_, i64>(&format!("SELECT COUNT(*) FROM {table} WHERE id = ?"))
+        .bind(target)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(0) if table == "models" => return Some(bad_request("Unknown model_id")),
+        Ok(0) => return Some(bad_request("Unknown category_id")),
+        Ok(_) => {}
+        Err(e) => return Some(error::internal_error("model_aliases:validate", e)),
+    }
+    match sqlx::query_scalar::<_, String>("SELECT id FROM model_aliases WHERE alias = ?")
+        .bind(alias)
+        .fetch_optional(pool)
+        .await
+    {
+        Ok(Some(existing)) if Some(existing.as_str()) != id => Some(
+            (
+                StatusCode::CONFLICT,
+                Json(json!({ "error": format!("Alias '{alias}' already exists") })),
+            )
+                .into_response(),
+        ),
+        Ok(_) => None,
+        Err(e) => Some(error::internal_error("model_aliases:validate", e)),
+    }
+}
+
+async fn fetch_alias(pool: &sqlx::SqlitePool, id: &str) -> sqlx::Result<Option<ModelAlias>> {
+    sqlx::query_as(&format!("{ALIAS_SELECT} WHERE a.id = ?"))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// GET /api/admin/aliases — List model aliases.
+async fn list_aliases(State(state): State<Arc<AppState>>) -> Response {
+    match sqlx::query_as::<_, ModelAlias>(&format!("{ALIAS_SELECT} ORDER BY a.alias"))
+        .fetch_all(&state.db.pool)
+        .await
+    {
+        Ok(aliases) => Json(json!({ "aliases": aliases })).into_response(),
+        Err(e) => error::internal_error("model_aliases:list", e),
+    }
+}
+
+/// POST /api/admin/aliases — Add a model alias.
+async fn create_alias(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Json(req): Json<AliasRequest>,
+) -> Response {
+    if let Some(r) = reject_invalid(&state.db.pool, &req, None).await {
+        return r;
+    }
+
+    let id = Uuid::new_v4().to_string();
+    if let Err(e) = sqlx::query(
+        "INSERT INTO model_aliases (id, alias, model_id, category_id, created_by) \
+         VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(req.alias.trim())
+    .bind(&req.model_id)
+    .bind(&req.category_id)
+    .bind(&session.user_id)
+    .execute(&state.db.pool)
+    .await
+    {
+        return error::internal_error("model_aliases:create", e);
+    }
+
+    info!(target: "audit", action = "model_alias.create", actor = %session.user_id, resource = %id, alias = %req.alias.trim(), model = ?req.model_id, category = ?req.category_id, "Admin created model alias");
+
+    match fetch_alias(&state.db.pool, &id).await {
+        Ok(Some(alias)) => (StatusCode::CREATED, Json(alias)).into_response(),
+        Ok(None) => error::internal_error("model_aliases:create", "alias missing after insert"),
+        Err(e) => error::internal_error("model_aliases:create", e),
+    }
+}
+
+/// PUT /api/admin/aliases/:id — Rename an alias or point it elsewhere.
+async fn update_alias(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(req): Json<AliasRequest>,
+) -> Response {
+    if let Some(r) = reject_invalid(&state.db.pool, &req, Some(&id)).await {
+        return r;
+    }
+
+    let updated = match sqlx::query(
+        "UPDATE model_aliases SET alias = ?, model_id = ?, category_id = ?, \
+         updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(req.alias.trim())
+    .bind(&req.model_id)
+    .bind(&req.category_id)
+    .bind(&id)
+    .execute(&state.db.pool)
+    .await
+    {
+        Ok(result) => result.rows_affected(),
+        Err(e) => return error::internal_error("model_aliases:update", e),
+    };
+    if updated == 0 {
+        return not_found();
+    }
+
+    info!(target: "audit", action = "model_alias.update", actor = %session.user_id, resource = %id, alias = %req.alias.trim(), model = ?req.model_id, category = ?req.category_id, "Admin updated model alias");
+
+    match fetch_alias(&state.db.pool, &id).await {
+        Ok(Some(alias)) => Json(alias).into_response(),
+        Ok(None) => not_found(),
+        Err(e) => error::internal_error("model_aliases:update", e),
+    }
+}
+
+/// DELETE /api/admin/aliases/:id — Remove a model alias.
+async fn delete_alias(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> Response {
+    match sqlx::query("DELETE FROM model_aliases WHERE id = ?")
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => not_found(),
+        Ok(_) => {
+            info!(target: "audit", action = "model_alias.delete", actor = %session.user_id, resource = %id, "Admin deleted model alias");
+            Json(json!({ "status": "deleted" })).into_response()
+        }
+        Err(e) => error::internal_error("model_aliases:delete", e),
+    }
+}
@@ -0,0 +1,250 @@
+//! Per-file pruning of downloaded model repos.
+//!
+//! A repo is often downloaded with several quantizations when only one is
+//! served. `GET /api/admin/models/{id}/files` lists what is on disk under
+//! the model's directory and `DELETE` removes chosen files, then sets
+//! `models.size_bytes` to what is left. The registered primary file of a
+//! loaded model (this one, or another model registered from the same repo)
+//! is refused, since the running backend has it open.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{middleware, Extension, Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use super::admin::{list_model_files, ModelFile};
+use super::error;
+use crate::auth::rbac::{self, Permission};
+use crate::auth::SessionAuth;
+use crate::AppState;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/models/{id}/files", get(list_files).delete(delete_files))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Models,
+            rbac::require_permission,
+        ))
+        .with_state(state)
+}
+
+#[derive(Debug, Deserialize)]
+struct DeleteFilesRequest {
+    /// Paths relative to the model directory, as listed by `GET`.
+    paths: Vec<String>,
+}
+
+fn not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "Model not found" })),
+    )
+        .into_response()
+}
+
+/// The model's repo, primary filename and on-disk directory.
+async fn lookup(
+    state: &AppState,
+    id: &str,
+) -> Result<Option<(String, Option<String>, PathBuf)>, sqlx::Error> {
+    let row: Option<(String, Option<String>)> =
+        sqlx::query_as("SELECT hf_repo, filename FROM models WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&state.db.pool)
+            .await?;
+    Ok(row.map(|(hf_repo, filename)| {
+        let dir = PathBuf::from(&state.config.model_path).join(hf_repo.replace('/', "--"));
+        (hf_repo, filename, dir)
+    }))
+}
+
+async fn files_in(dir: PathBuf) -> Vec<ModelFile> {
+    tokio::task::spawn_blocking(move || list_model_files(&dir))
+        .await
+        .unwrap_or_default()
+}
+
+/// Pick the listed files named by `requested`. Errors on a path that isn't
+/// on disk (which also rules out `..` and absolute paths) and on a file a
+/// loaded model is serving from.
+fn select_files<'a>(
+    on_disk: &'a [ModelFile],
+    requested: &[String],
+    in_use: &HashSet<String>,
+) -> Result<Vec<&'a ModelFile>, (StatusCode, String)> {
+    let mut selected: Vec<&ModelFile> = Vec::new();
+    for path in requested {
+        let Some(file) = on_disk.iter().find(|f| &f.path == path) else {
+            return Err((StatusCode::BAD_REQUEST, format!("No such file: {path}")));
+        };
+        if in_use.contains(path) {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("{path} is the primary file of a loaded model; stop it first"),
+            ));
+        }
+        if !selected.iter().any(|f| f.path == file.path) {
+            selected.push(file);
+        }
+    }
+    Ok(selected)
+}
+
+/// GET /api/admin/models/{id}/files — Files under the model's directory.
+async fn list_files(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    let (hf_repo, filename, dir) = match lookup(&state, &id).await {
+        Ok(Some(found)) => found,
+        Ok(None) => return not_found(),
+        Err(e) => return error::internal_error("list_model_files:lookup", e),
+    };
+    let files = files_in(dir).await;
+    let total_bytes: u64 = files.iter().map(|f| f.size_bytes).sum();
+    Json(json!({
+        "model_id": id,
+        "hf_repo": hf_repo,
+        "primary_filename": filename,
+        "files": files,
+        "total_bytes": total_bytes,
+    }))
+    .into_response()
+}
+
+/// DELETE /api/admin/models/{id}/files — Remove the listed files and update
+/// the model's size.
+async fn delete_files(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(req): Json<DeleteFilesRequest>,
+) -> Response {
+    if req.paths.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "paths must not be empty" })),
+        )
+            .into_response();
+    }
+    let (hf_repo, _, dir) = match lookup(&state, &id).await {
+        Ok(Some(found)) => found,
+        Ok(None) => return not_found(),
+        Err(e) => return error::internal_error("delete_model_files:lookup", e),
+    };
+
+    // Primary files of loaded models sharing this repo directory
+    let in_use: HashSet<String> = match sqlx::query_scalar::<_, String>(
+        "SELECT filename FROM models WHERE hf_repo = ? AND loaded = 1 AND filename IS NOT NULL",
+    )
+    .bind(&hf_repo)
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(rows) => rows.into_iter().collect(),
+        Err(e) => return error::internal_error("delete_model_files:in_use", e),
+    };
+
+    let on_disk = files_in(dir.clone()).await;
+    let selected = match select_files(&on_disk, &req.paths, &in_use) {
+        Ok(files) => files,
+        Err((status, message)) => {
+            return (status, Json(json!({ "error": message }))).into_response();
+        }
+    };
+
+    let mut deleted = Vec::new();
+    let mut failed = Vec::new();
+    let mut freed_bytes = 0u64;
+    for file in selected {
+        match tokio::fs::remove_file(dir.join(&file.path)).await {
+            Ok(()) => {
+                freed_bytes += file.size_bytes;
+                deleted.push(file.path.clone());
+            }
+            Err(e) => {
+                warn!(model = %id, file = %file.path, error = %e, "Failed to delete model file");
+                failed.push(file.path.clone());
+            }
+        }
+    }
+
+    let remaining = files_in(dir).await;
+    let size_bytes: u64 = remaining.iter().map(|f| f.size_bytes).sum();
+    if let Err(e) = sqlx::query("UPDATE models SET size_bytes = ? WHERE id = ?")
+        .bind(size_bytes as i64)
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+    {
+        return error::internal_error("delete_model_files:size", e);
+    }
+
+    info!(target: "audit", action = "model.files_delete", actor = %session.user_id, resource = %id, files = ?deleted, freed_bytes, "Admin deleted model files");
+
+    Json(json!({
+        "model_id": id,
+        "deleted": deleted,
+        "failed": failed,
+        "freed_bytes": freed_bytes,
+        "size_bytes": size_bytes,
+        "files": remaining,
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size_bytes: u64) -> ModelFile {
+        ModelFile {
+            path: path.to_string(),
+            size_bytes,
+        }
+    }
+
+    fn on_disk() -> Vec<ModelFile> {
+        vec![
+            file("model-Q4_K_M.gguf", 4),
+            file("model-Q8_0.gguf", 8),
+            file("README.md", 1),
+        ]
+    }
+
+    #[test]
+    fn selects_listed_files_once() {
+        let files = on_disk();
+        let requested = vec![
+            "model-Q8_0.gguf".to_string(),
+            "README.md".to_string(),
+            "model-Q8_0.gguf".to_string(),
+        ];
+        let selected = select_files(&files, &requested, &HashSet::new()).unwrap();
+        let paths: Vec<&str> = selected.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["model-Q8_0.gguf", "README.md"]);
+    }
+
+    #[test]
+    fn rejects_unknown_and_escaping_paths() {
+        let files = on_disk();
+        for path in ["missing.gguf", "../other/model.gguf", "/etc/passwd"] {
+            let err = select_files(&files, &[path.to_string()], &HashSet::new()).unwrap_err();
+            assert_eq!(err.0, StatusCode::BAD_REQUEST, "{path}");
+        }
+    }
+
+    #[test]
+    fn refuses_primary_file_of_loaded_model() {
+        let files = on_disk();
+        let in_use = HashSet::from(["model-Q4_K_M.gguf".to_string()]);
+        let err = select_files(&files, &["model-Q4_K_M.gguf".to_string()], &in_use).unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+        assert!(select_files(&files, &["model-Q8_0.gguf".to_string()], &in_use).is_ok());
+    }
+}
@@ -0,0 +1,485 @@
+//! Model import from the local filesystem.
+//!
+//! `POST /api/admin/models/import` registers a model that didn't come from
+//! HuggingFace. A path under `MODEL_PATH` is registered where it is: the
+//! directory directly under `MODEL_PATH` becomes the model's directory and
+//! its name the `hf_repo` (`org--name` → `org/name`), so container starts
+//! find the file the same way they do for downloads. A path under
+//! `MODEL_IMPORT_PATH` (a staging area such as a NAS mount) is copied into
+//! `MODEL_PATH/local--<name>` first and registered as `local/<name>`.
+//! GGUF headers are read for context and architecture metadata as for
+//! downloads; nothing is fetched from HuggingFace.
+
+use std::path::{Component, Path as FsPath, PathBuf};
+use std::sync::Arc;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{middleware, Extension, Json, Router};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::admin::{list_model_files, ModelFile};
+use super::{categorize, error, hf};
+use crate::auth::rbac::{self, Permission};
+use crate::auth::SessionAuth;
+use crate::AppState;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/models/import", post(import_model))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Models,
+            rbac::require_permission,
+        ))
+        .with_state(state)
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportRequest {
+    /// A model file or directory, absolute or relative to `MODEL_PATH`.
+    path: String,
+    /// Name for a copied model (`local/<name>`); defaults to the file stem
+    /// or directory name. Ignored for paths already under `MODEL_PATH`.
+    name: Option<String>,
+    category_id: Option<String>,
+    backend_type: Option<String>,
+}
+
+fn bad_request(message: impl Into<String>) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "error": message.into() })),
+    )
+        .into_response()
+}
+
+/// The `hf_repo` whose directory under `MODEL_PATH` is `dir`; the inverse
+/// of the `/` → `--` mapping used for downloads.
+fn repo_for_dir(dir: &str) -> String {
+    dir.replace("--", "/")
+}
+
+/// A name safe to use as a directory and repo name, or `None` if nothing
+/// usable is left.
+fn local_name(raw: &str) -> Option<String> {
+    let mut name = String::new();
+    for c in raw.trim().chars() {
+        let c = if c.is_ascii_alphanumeric() || matches!(c, '_' | '.') {
+            c
+        } else {
+            '-'
+        };
+        // `--` would read back as a `/` in the repo name
+        if !(c == '-' && name.ends_with('-')) {
+            name.push(c);
+        }
+    }
+    let name = name.trim_matches(|c| c == '-' || c == '.').to_string();
+    (!name.is_empty() && name.len() <= error::MAX_NAME).then_some(name)
+}
+
+/// The file to serve from a model directory: the largest GGUF (the first
+/// shard of a split model), else the largest safetensors file.
+fn primary_file(files: &[ModelFile]) -> Option<String> {
+    let largest = |ext: &str| {
+        files
+            .iter()
+            .filter(|f| f.path.ends_with(ext))
+            .max_by_key(|f| f.size_bytes)
+    };
+    if let Some(gguf) = largest(".gguf") {
+        if let Some(prefix) = hf::shard_prefix(&gguf.path) {
+            return files
+                .iter()
+                .map(|f| f.path.as_str())
+                .filter(|p| hf::shard_prefix(p) == Some(prefix))
+                .min()
+                .map(str::to_string);
+        }
+        return Some(gguf.path.clone());
+    }
+    largest(".safetensors").map(|f| f.path.clone())
+}
+
+/// `path` relative to `root` as `/`-joined normal components, or `None` if
+/// it isn't under `root`.
+fn relative_parts(root: &FsPath, path: &FsPath) -> Option<Vec<String>> {
+    let rel = path.strip_prefix(root).ok()?;
+    rel.components()
+        .map(|c| match c {
+            Component::Normal(s) => Some(s.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Where the model lives after import.
+struct Placement {
+    hf_repo: String,
+    /// Directory under `MODEL_PATH`.
+    dir: PathBuf,
+    /// Primary file, relative to `dir`.
+    filename: String,
+    copied: bool,
+}
+
+/// Register an existing directory or file under `MODEL_PATH` in place.
+fn place_in_model_path(
+    parts: &[String],
+    model_root: &FsPath,
+    is_dir: bool,
+) -> Result<Placement, Response> {
+    let Some((dir, rest)) = parts.split_first() else {
+        return Err(bad_request(
+            "path must be a model file or directory, not MODEL_PATH itself",
+        ));
+    };
+    if dir.starts_with('.') {
+        return Err(bad_request(
+            "Hidden directories under MODEL_PATH cannot be imported",
+        ));
+    }
+    let dir_path = model_root.join(dir);
+    let filename = if is_dir {
+        if !rest.is_empty() {
+            return Err(bad_request(
+                "Import a directory directly under MODEL_PATH, or a file inside one",
+            ));
+        }
+        primary_file(&list_model_files(&dir_path))
+            .ok_or_else(|| bad_request("No .gguf or .safetensors file in the directory"))?
+    } else {
+        if rest.is_empty() {
+            return Err(bad_request(
+                "Files directly in MODEL_PATH cannot be imported; move the file into a directory",
+            ));
+        }
+        rest.join("/")
+    };
+    Ok(Placement {
+        hf_repo: repo_for_dir(dir),
+        dir: dir_path,
+        filename,
+        copied: false,
+    })
+}
+
+/// Copy `source` (a file or directory) into `dest`, staging under a hidden
+/// directory so a failed copy never leaves a half-filled model directory.
+async fn copy_into_model_path(source: &FsPath, dest: &FsPath) -> std::io::Result<()> {
+    let root = dest.parent().unwrap_or(dest);
+    let staging = root.join(format!(".import-{}", Uuid::new_v4()));
+    let result = async {
+        tokio::fs::create_dir_all(&staging).await?;
+        if source.is_dir() {
+            let src = source.to_path_buf();
+            let files = tokio::task::spawn_blocking(move || list_model_files(&src))
+                .await
+                .unwrap_or_default();
+            for file in files {
+                let target = staging.join(&file.path);
+                if let Some(parent) = target.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::copy(source.join(&file.path), target).await?;
+            }
+        } else if let Some(name) = source.file_name() {
+            tokio::fs::copy(source, staging.join(name)).await?;
+        }
+        tokio::fs::rename(&staging, dest).await
+    }
+    .await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_dir_all(&staging).await;
+    }
+    result
+}
+
+/// POST /api/admin/models/import — Register a model from a local path.
+async fn import_model(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Json(req): Json<ImportRequest>,
+) -> Response {
+    if let Some(r) = error::validate_len("path", &req.path, 4096) {
+        return r;
+    }
+    if let Some(bt) = req.backend_type.as_deref() {
+        if let Some(r) = error::validate_backend_type(bt) {
+            return r;
+        }
+    }
+
+    let model_root = match tokio::fs::canonicalize(&state.config.model_path).await {
+        Ok(p) => p,
+        Err(e) => return error::internal_error("import_model:model_path", e),
+    };
+    let source = match tokio::fs::canonicalize(model_root.join(&req.path)).await {
+        Ok(p) => p,
+        Err(_) => return bad_request(format!("{} does not exist", req.path)),
+    };
+    let is_dir = source.is_dir();
+
+    let placement = if let Some(parts) = relative_parts(&model_root, &source) {
+        let root = model_root.clone();
+        match tokio::task::spawn_blocking(move || place_in_model_path(&parts, &root, is_dir)).await
+        {
+            Ok(Ok(p)) => p,
+            Ok(Err(r)) => return r,
+            Err(e) => return error::internal_error("import_model:place", e),
+        }
+    } else {
+        let staging_root = match &state.config.model_import_path {
+            Some(p) => tokio::fs::canonicalize(p).await.ok(),
+            None => None,
+        };
+        if !staging_root.is_some_and(|root| source.starts_with(root)) {
+            return bad_request("path must be under MODEL_PATH or MODEL_IMPORT_PATH");
+        }
+        let raw_name = match (&req.name, is_dir) {
+            (Some(name), _) => name.clone(),
+            (None, true) => source
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            (None, false) => source
+                .file_stem()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+        let Some(name) = local_name(&raw_name) else {
+            return bad_request("name must contain letters or digits");
+        };
+        let hf_repo = format!("local/{name}");
+        let dir = model_root.join(hf_repo.replace('/', "--"));
+        if dir.exists() {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({ "error": format!("{hf_repo} already exists under MODEL_PATH; choose another name") })),
+            )
+                .into_response();
+        }
+        if let Err(e) = copy_into_model_path(&source, &dir).await {
+            return error::internal_error("import_model:copy", e);
+        }
+        let listed = dir.clone();
+        let files = tokio::task::spawn_blocking(move || list_model_files(&listed))
+            .await
+            .unwrap_or_default();
+        let Some(filename) = primary_file(&files) else {
+            let _ = tokio::fs::remove_dir_all(&dir).await;
+            return bad_request("No .gguf or .safetensors file to import");
+        };
+        Placement {
+            hf_repo,
+            dir,
+            filename,
+            copied: true,
+        }
+    };
+
+    // Undo a copy when registration doesn't go through
+    let discard = |placement: &Placement| {
+        let dir = placement.dir.clone();
+        let copied = placement.copied;
+        async move {
+            if copied {
+                let _ = tokio::fs::remove_dir_all(dir).await;
+            }
+        }
+    };
+
+    let existing: Option<String> =
+        match sqlx::query_scalar("SELECT id FROM models WHERE hf_repo = ? AND filename = ?")
+            .bind(&placement.hf_repo)
+            .bind(&placement.filename)
+            .fetch_optional(&state.db.pool)
+            .await
+        {
+            Ok(row) => row,
+            Err(e) => return error::internal_error("import_model:existing", e),
+        };
+    if let Some(id) = existing {
+        discard(&placement).await;
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "This file is already registered", "model_id": id })),
+        )
+            .into_response();
+    }
+
+    let dir_str = placement.dir.to_string_lossy().into_owned();
+    let mut meta = hf::GgufMetadata::default();
+    if placement.filename.ends_with(".gguf") {
+        let path = placement.dir.join(&placement.filename);
+        match hf::read_gguf_metadata(&path.to_string_lossy()).await {
+            Ok(m) => meta = m,
+            Err(e) => {
+                discard(&placement).await;
+                return bad_request(format!(
+                    "{} is not a readable GGUF file: {e}",
+                    placement.filename
+                ));
+            }
+        }
+    }
+    if meta.context_length.is_none() {
+        meta.context_length = hf::read_config_context_length(&dir_str).await;
+    }
+    let model_metadata = hf::try_local_tokenizer(&dir_str).await;
+    let listed = placement.dir.clone();
+    let size_bytes: u64 = tokio::task::spawn_blocking(move || list_model_files(&listed))
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(|f| f.size_bytes)
+        .sum();
+    let backend_type = req
+        .backend_type
+        .as_deref()
+        .unwrap_or_else(|| hf::default_backend_type(Some(&placement.filename)));
+    let (kv_bpt_global, kv_bpt_swa) = hf::compute_kv_aggregates(&meta);
+
+    let model_id = Uuid::new_v4().to_string();
+    if let Err(e) = sqlx::query(
+        "INSERT INTO models (id, hf_repo, filename, size_bytes, category_id, backend_type, model_metadata, context_length, n_layers, n_heads, n_kv_heads, embedding_length, key_length, value_length, sliding_window, kv_bytes_per_token_global, kv_bytes_per_token_swa, runtime_overrides, architecture) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&model_id)
+    .bind(&placement.hf_repo)
+    .bind(&placement.filename)
+    .bind(size_bytes as i64)
+    .bind(&req.category_id)
+    .bind(backend_type)
+    .bind(&model_metadata)
+    .bind(meta.context_length.map(|v| v as i64))
+    .bind(meta.block_count.map(|v| v as i64))
+    .bind(meta.head_count.map(|v| v as i64))
+    .bind(meta.head_count_kv.map(|v| v as i64))
+    .bind(meta.embedding_length.map(|v| v as i64))
+    .bind(meta.key_length.map(|v| v as i64))
+    .bind(meta.value_length.map(|v| v as i64))
+    .bind(meta.sliding_window.map(|v| v as i64))
+    .bind(kv_bpt_global)
+    .bind(kv_bpt_swa)
+    .bind(hf::auto_runtime_overrides(&meta))
+    .bind(&meta.architecture)
+    .execute(&state.db.pool)
+    .await
+    {
+        discard(&placement).await;
+        return error::api_error(StatusCode::BAD_REQUEST, "import_model", e);
+    }
+
+    state.storage.spawn_publish(
+        state.db.clone(),
+        model_id.clone(),
+        placement.hf_repo.replace('/', "--"),
+    );
+
+    info!(target: "audit", action = "model.import", actor = %session.user_id, resource = %model_id, hf_repo = %placement.hf_repo, filename = %placement.filename, source = %source.display(), copied = placement.copied, "Admin imported model from local path");
+
+    let mode = categorize::Mode::from_config(&state.config.auto_categorize);
+    if req.category_id.is_none() && mode != categorize::Mode::Off {
+        if let Err(e) = categorize::categorize_model(
+            &state.db.pool,
+            &model_id,
+            mode == categorize::Mode::Assign,
+            "auto-categorize",
+        )
+        .await
+        {
+            warn!(model_id = %model_id, error = %e, "Auto-categorization failed");
+        }
+    }
+
+    (
+        StatusCode::CREATED,
+        Json(json!({
+            "id": model_id,
+            "hf_repo": placement.hf_repo,
+            "filename": placement.filename,
+            "size_bytes": size_bytes,
+            "backend_type": backend_type,
+            "context_length": meta.context_length,
+            "architecture": meta.architecture,
+            "copied": placement.copied,
+        })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, size_bytes: u64) -> ModelFile {
+        ModelFile {
+            path: path.to_string(),
+            size_bytes,
+        }
+    }
+
+    #[test]
+    fn repo_round_trips_with_download_dirs() {
+        for repo in ["unsloth/GLM-4.7-Flash-GGUF", "llama3", "local/my-model"] {
+            assert_eq!(repo_for_dir(&repo.replace('/', "--")), repo);
+        }
+    }
+
+    #[test]
+    fn local_name_sanitizes() {
+        assert_eq!(local_name("My Model (Q4)").as_deref(), Some("My-Model-Q4"));
+        assert_eq!(local_name(" qwen2.5-7b ").as_deref(), Some("qwen2.5-7b"));
+        assert_eq!(local_name("../.."), None);
+        assert_eq!(local_name(""), None);
+    }
+
+    #[test]
+    fn primary_file_prefers_gguf_and_first_shard() {
+        let files = [
+            file("README.md", 1),
+            file("model.safetensors", 100),
+            file("model-Q4_K_M.gguf", 40),
+            file("model-Q8_0.gguf", 80),
+        ];
+        assert_eq!(primary_file(&files).as_deref(), Some("model-Q8_0.gguf"));
+
+        let shards = [
+            file("big-Q4-00002-of-00002.gguf", 90),
+            file("big-Q4-00001-of-00002.gguf", 10),
+        ];
+        assert_eq!(
+            primary_file(&shards).as_deref(),
+            Some("big-Q4-00001-of-00002.gguf")
+        );
+
+        assert_eq!(
+            primary_file(&[file("model.safetensors", 1)]).as_deref(),
+            Some("model.safetensors")
+        );
+        assert_eq!(primary_file(&[file("README.md", 1)]), None);
+    }
+
+    #[test]
+    fn in_place_paths() {
+        let root = FsPath::new("/models");
+        let parts = |p: &str| relative_parts(root, FsPath::new(p)).unwrap();
+
+        let placed = place_in_model_path(&parts("/models/org--model/sub/m.gguf"), root, false)
+            .ok()
+            .unwrap();
+        assert_eq!(placed.hf_repo, "org/model");
+        assert_eq!(placed.dir, FsPath::new("/models/org--model"));
+        assert_eq!(placed.filename, "sub/m.gguf");
+
+        assert!(place_in_model_path(&parts("/models/m.gguf"), root, false).is_err());
+        assert!(place_in_model_path(&parts("/models/.trash/x/m.gguf"), root, false).is_err());
+        assert!(place_in_model_path(&parts("/models/a/b"), root, true).is_err());
+        assert!(relative_parts(root, FsPath::new("/srv/m.gguf")).is_none());
+    }
+}
@@ -0,0 +1,268 @@
+//! Request tracing for a single model (`/api/admin/models/{id}/trace`).
+//!
+//! Starting a trace captures the next `count` completion requests to the
+//! model in the request log at `full` level, whatever its category's log
+//! level or `REQUEST_LOG` say, until the samples run out or `ttl` passes.
+//! Samples are sanitized: end-user identifiers in the request body (`user`,
+//! `metadata`) are dropped before storing. Expired traces are removed by the
+//! hourly cleanup; the samples themselves follow `REQUEST_LOG_RETENTION_DAYS`.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::info;
+
+use super::error;
+use crate::auth::SessionAuth;
+use crate::db::Database;
+use crate::AppState;
+
+const DEFAULT_COUNT: i64 = 20;
+const MAX_COUNT: i64 = 1000;
+const DEFAULT_TTL_SECS: i64 = 3600;
+const MAX_TTL_SECS: i64 = 7 * 24 * 3600;
+
+/// Request body fields that identify the end user rather than the prompt.
+const IDENTIFYING_FIELDS: &[&str] = &["user", "metadata"];
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/models/{id}/trace",
+            post(start_trace).get(get_trace).delete(stop_trace),
+        )
+        .with_state(state)
+}
+
+/// A live trace.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Trace {
+    model_id: String,
+    remaining: i64,
+    expires_at: String,
+    created_by: Option<String>,
+    created_at: String,
+}
+
+/// Take one sample from the model's trace. Returns true if the request
+/// should be captured; false when no trace is live.
+pub async fn claim(pool: &sqlx::SqlitePool, model_id: &str) -> Result<bool> {
+    let result = sqlx::query(
+        "UPDATE model_traces SET remaining = remaining - 1 \
+         WHERE model_id = ? AND remaining > 0 AND expires_at > datetime('now')",
+    )
+    .bind(model_id)
+    .execute(pool)
+    .await
+    .context("Failed to claim trace sample")?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Strip end-user identifiers from a captured request body. Bodies that
+/// aren't a JSON object are kept as they are.
+pub fn sanitize(body: &[u8]) -> Vec<u8> {
+    let Ok(Value::Object(mut object)) = serde_json::from_slice::<Value>(body) else {
+        return body.to_vec();
+    };
+    for field in IDENTIFYING_FIELDS {
+        object.remove(*field);
+    }
+    serde_json::to_vec(&object).unwrap_or_else(|_| body.to_vec())
+}
+
+/// Delete traces that expired or ran out of samples.
+pub async fn prune(db: &Database) -> Result<u64> {
+    let result = sqlx::query(
+        "DELETE FROM model_traces WHERE remaining <= 0 OR expires_at <= datetime('now')",
+    )
+    .execute(&db.pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Parse a TTL like `90s`, `30m`, `1h` or `2d`; a bare number is seconds.
+fn parse_ttl(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let (number, unit) = match value.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&value[..i], c),
+        _ => (value, 's'),
+    };
+    let multiplier = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86_400,
+        _ => return None,
+    };
+    number.parse::<i64>().ok()?.checked_mul(multiplier)
+}
+
+#[derive(Debug, Deserialize)]
+struct TraceQuery {
+    count: Option<i64>,
+    ttl: Option<String>,
+}
+
+fn bad_request(message: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": message }))).into_response()
+}
+
+async fn fetch_trace(pool: &sqlx::SqlitePool, model_id: &str) -> sqlx::Result<Option<Trace>> {
+    sqlx::query_as(
+        "SELECT model_id, remaining, expires_at, created_by, created_at FROM model_traces \
+         WHERE model_id = ? AND remaining > 0 AND expires_at > datetime('now')",
+    )
+    .bind(model_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// POST /api/admin/models/{id}/trace?count=20&ttl=1h -- Capture the model's
+/// next `count` requests for up to `ttl`. Replaces a live trace.
+async fn start_trace(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Query(query): Query<TraceQuery>,
+) -> Response {
+    let count = query.count.unwrap_or(DEFAULT_COUNT);
+    if !(1..=MAX_COUNT).contains(&count) {
+        return bad_request(&format!("count must be between 1 and {MAX_COUNT}"));
+    }
+    let ttl_secs = match query.ttl.as_deref().map(parse_ttl) {
+        None => DEFAULT_TTL_SECS,
+        Some(Some(secs)) if (1..=MAX_TTL_SECS).contains(&secs) => secs,
+        Some(_) => return bad_request("ttl must be like 90s, 30m, 1h or 2d, and at most 7d"),
+    };
+
+    let exists: Option<String> = match sqlx::query_scalar("SELECT id FROM models WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&state.db.pool)
+        .await
+    {
+        Ok(row) => row,
+        Err(e) => return error::internal_error("start_trace", e),
+    };
+    if exists.is_none() {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Model not found" })),
+        )
+            .into_response();
+    }
+
+    if let Err(e) = sqlx::query(
+        "INSERT INTO model_traces (model_id, remaining, expires_at, created_by) \
+         VALUES (?, ?, datetime('now', ?), ?) \
+         ON CONFLICT(model_id) DO UPDATE SET remaining = excluded.remaining, \
+             expires_at = excluded.expires_at, created_by = excluded.created_by, \
+             created_at = datetime('now')",
+    )
+    .bind(&id)
+    .bind(count)
+    .bind(format!("+{ttl_secs} seconds"))
+    .bind(&session.user_id)
+    .execute(&state.db.pool)
+    .await
+    {
+        return error::internal_error("start_trace", e);
+    }
+
+    match fetch_trace(&state.db.pool, &id).await {
+        Ok(trace) => {
+            info!(target: "audit", action = "model.trace_start", actor = %session.user_id, resource = %id, count, ttl_secs, "Admin started model trace");
+            Json(json!({ "trace": trace })).into_response()
+        }
+        Err(e) => error::internal_error("start_trace", e),
+    }
+}
+
+/// GET /api/admin/models/{id}/trace -- The model's live trace, if any.
+async fn get_trace(State(state): State<Arc<AppState>>, Path(id): Path<String>) -> Response {
+    match fetch_trace(&state.db.pool, &id).await {
+        Ok(trace) => Json(json!({ "trace": trace })).into_response(),
+        Err(e) => error::internal_error("get_trace", e),
+    }
+}
+
+/// DELETE /api/admin/models/{id}/trace -- Stop capturing. Samples already
+/// taken stay in the request log.
+async fn stop_trace(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> Response {
+    match sqlx::query("DELETE FROM model_traces WHERE model_id = ?")
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "No trace for this model" })),
+        )
+            .into_response(),
+        Ok(_) => {
+            info!(target: "audit", action = "model.trace_stop", actor = %session.user_id, resource = %id, "Admin stopped model trace");
+            Json(json!({ "status": "stopped" })).into_response()
+        }
+        Err(e) => error::internal_error("stop_trace", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ttl_units() {
+        assert_eq!(parse_ttl("90"), Some(90));
+        assert_eq!(parse_ttl("90s"), Some(90));
+        assert_eq!(parse_ttl("30m"), Some(1800));
+        assert_eq!(parse_ttl("1h"), Some(3600));
+        assert_eq!(parse_ttl("2d"), Some(172_800));
+        assert_eq!(parse_ttl("1w"), None);
+        assert_eq!(parse_ttl("h"), None);
+        assert_eq!(parse_ttl(""), None);
+    }
+
+    #[test]
+    fn sanitize_drops_user_fields() {
+        let body =
+            br#"{"model":"m","messages":[],"user":"alice@example.com","metadata":{"k":"v"}}"#;
+        let clean: Value = serde_json::from_slice(&sanitize(body)).unwrap();
+        assert_eq!(clean, json!({"model": "m", "messages": []}));
+        assert_eq!(sanitize(b"not json"), b"not json");
+    }
+
+    #[tokio::test]
+    async fn claim_counts_down_and_expires() {
+        let db = Database::test_db().await;
+        sqlx::query("INSERT INTO models (id, hf_repo) VALUES ('m1', 'org/m1'), ('m2', 'org/m2')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO model_traces (model_id, remaining, expires_at) VALUES \
+             ('m1', 2, datetime('now', '+1 hour')), ('m2', 5, datetime('now', '-1 second'))",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        assert!(claim(&db.pool, "m1").await.unwrap());
+        assert!(claim(&db.pool, "m1").await.unwrap());
+        assert!(!claim(&db.pool, "m1").await.unwrap());
+        assert!(!claim(&db.pool, "m2").await.unwrap());
+        assert!(!claim(&db.pool, "m3").await.unwrap());
+
+        assert_eq!(prune(&db).await.unwrap(), 2);
+    }
+}
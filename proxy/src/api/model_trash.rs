@@ -0,0 +1,478 @@
+//! Model trash.
+//!
+//! Deleting a model moves its directory to `<MODEL_PATH>/.trash/<id>` and
+//! keeps JSON copies of its `models` and launch profile rows, so an admin can
+//! restore it instead of re-downloading. Entries older than
+//! `MODEL_TRASH_HOURS` are purged by the hourly cleanup, and
+//! `MODEL_TRASH_MAX_GB` caps the trash by purging the oldest entries first.
+//! Tokens revoked and pins cleared by the delete are not restored.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{middleware, Extension, Json, Router};
+use serde::Serialize;
+use serde_json::Value;
+use sqlx::SqliteConnection;
+use tracing::info;
+use uuid::Uuid;
+
+use super::{admin, audit, error};
+use crate::auth::rbac::{self, Permission};
+use crate::auth::SessionAuth;
+use crate::db::Database;
+use crate::AppState;
+
+/// Trash directory under `MODEL_PATH`.
+const TRASH_DIR: &str = ".trash";
+
+/// `models` columns kept in the trash and written back on restore. Runtime
+/// state (`loaded`, `backend_port`, `draining`, `last_used_at`) starts fresh.
+pub(super) const MODEL_COLUMNS: &[&str] = &[
+    "id",
+    "hf_repo",
+    "filename",
+    "size_bytes",
+    "category_id",
+    "backend_type",
+    "created_at",
+    "model_metadata",
+    "context_length",
+    "n_layers",
+    "n_heads",
+    "n_kv_heads",
+    "embedding_length",
+    "key_length",
+    "value_length",
+    "sliding_window",
+    "kv_bytes_per_token_global",
+    "kv_bytes_per_token_swa",
+    "runtime_overrides",
+    "sha256",
+    "idle_unload_minutes",
+    "autoload",
+    "launch_params",
+    "embedding",
+    "architecture",
+    "pipeline_tag",
+    "default_params",
+    "context_policy",
+];
+
+/// `model_launch_profiles` columns, which cascade away with the model.
+pub(super) const PROFILE_COLUMNS: &[&str] = &[
+    "model_id",
+    "backend_type",
+    "gpu_type",
+    "gpu_layers",
+    "context_size",
+    "parallel",
+    "cpu_limit",
+    "memory_limit_mb",
+    "pids_limit",
+    "updated_by",
+    "updated_at",
+];
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/models/trash", get(list_trash))
+        .route("/models/trash/{id}", delete(purge))
+        .route("/models/trash/{id}/restore", post(restore))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Models,
+            rbac::require_permission,
+        ))
+        .with_state(state)
+}
+
+/// `SELECT json_object(...)` over `columns`, for `audit::snapshot`.
+fn snapshot_select(table: &str, key: &str, columns: &[&str]) -> String {
+    let fields: Vec<String> = columns.iter().map(|c| format!("'{c}', {c}")).collect();
+    format!(
+        "SELECT json_object({}) FROM {table} WHERE {key} = ?",
+        fields.join(", ")
+    )
+}
+
+/// `INSERT` that writes `columns` back from a snapshot bound as `?1`.
+pub(super) fn restore_insert(table: &str, columns: &[&str]) -> String {
+    let values: Vec<String> = columns
+        .iter()
+        .map(|c| format!("json_extract(?1, '$.{c}')"))
+        .collect();
+    format!(
+        "INSERT INTO {table} ({}) SELECT {}",
+        columns.join(", "),
+        values.join(", ")
+    )
+}
+
+fn trash_root(model_path: &str) -> PathBuf {
+    PathBuf::from(model_path).join(TRASH_DIR)
+}
+
+/// Copies of a model's rows, taken before it is deleted.
+pub(crate) struct ModelSnapshot {
+    model_row: Value,
+    launch_profile: Option<Value>,
+}
+
+pub(crate) async fn snapshot_model(
+    pool: &sqlx::SqlitePool,
+    model_id: &str,
+) -> Result<ModelSnapshot> {
+    let model_row = audit::snapshot(
+        pool,
+        &snapshot_select("models", "id", MODEL_COLUMNS),
+        model_id,
+    )
+    .await?
+    .context("Model not found")?;
+    let launch_profile = audit::snapshot(
+        pool,
+        &snapshot_select("model_launch_profiles", "model_id", PROFILE_COLUMNS),
+        model_id,
+    )
+    .await?;
+    Ok(ModelSnapshot {
+        model_row,
+        launch_profile,
+    })
+}
+
+/// Record a trash entry inside the delete's transaction and return its id.
+/// The files are moved after the commit by [`move_files`].
+pub(crate) async fn insert_entry(
+    conn: &mut SqliteConnection,
+    snapshot: &ModelSnapshot,
+    model_id: &str,
+    hf_repo: &str,
+    actor: &str,
+    hours: u64,
+) -> Result<String, sqlx::Error> {
+    let id = Uuid::new_v4().to_string();
+    sqlx::query(
+        "INSERT INTO model_trash (id, model_id, hf_repo, model_row, launch_profile, deleted_by, purge_after) \
+         VALUES (?, ?, ?, ?, ?, ?, datetime('now', ?))",
+    )
+    .bind(&id)
+    .bind(model_id)
+    .bind(hf_repo)
+    .bind(snapshot.model_row.to_string())
+    .bind(snapshot.launch_profile.as_ref().map(Value::to_string))
+    .bind(actor)
+    .bind(format!("+{hours} hours"))
+    .execute(conn)
+    .await?;
+    Ok(id)
+}
+
+/// Move a deleted model's directory into the trash, then purge the oldest
+/// entries while the trash is over its cap. A model larger than the cap is
+/// deleted outright, along with its entry.
+pub(crate) async fn move_files(state: &AppState, entry_id: &str, model_dir: &str) -> Result<()> {
+    let dir = PathBuf::from(model_dir);
+    let size: u64 = tokio::task::spawn_blocking(move || {
+        admin::list_model_files(&dir)
+            .iter()
+            .map(|f| f.size_bytes)
+            .sum()
+    })
+    .await
+    .unwrap_or(0);
+    let max_bytes = state.config.model_trash_max_gb * 1024 * 1024 * 1024;
+
+    if max_bytes > 0 && size > max_bytes {
+        tokio::fs::remove_dir_all(model_dir).await?;
+        sqlx::query("DELETE FROM model_trash WHERE id = ?")
+            .bind(entry_id)
+            .execute(&state.db.pool)
+            .await?;
+        info!(path = %model_dir, size_bytes = size, "Model is larger than the trash cap — deleted outright");
+        return Ok(());
+    }
+
+    let root = trash_root(&state.config.model_path);
+    tokio::fs::create_dir_all(&root).await?;
+    let trash_path = root.join(entry_id);
+    tokio::fs::rename(model_dir, &trash_path)
+        .await
+        .with_context(|| format!("Failed to move {model_dir} to the trash"))?;
+    sqlx::query("UPDATE model_trash SET trash_path = ?, size_bytes = ? WHERE id = ?")
+        .bind(trash_path.to_string_lossy().as_ref())
+        .bind(size as i64)
+        .bind(entry_id)
+        .execute(&state.db.pool)
+        .await?;
+    info!(path = %model_dir, trash_id = %entry_id, size_bytes = size, "Model files moved to trash");
+
+    if max_bytes > 0 {
+        let purged = enforce_cap(&state.db, max_bytes).await?;
+        if purged > 0 {
+            info!(
+                purged,
+                "Purged oldest model trash entries to stay under the cap"
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Purge the oldest entries until the trash fits in `max_bytes`.
+async fn enforce_cap(db: &Database, max_bytes: u64) -> Result<u64> {
+    let entries: Vec<(String, Option<String>, i64)> = sqlx::query_as(
+        "SELECT id, trash_path, size_bytes FROM model_trash ORDER BY deleted_at DESC, rowid DESC",
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    let mut total = 0u64;
+    let mut purged = 0;
+    for (id, trash_path, size) in entries {
+        total += size.max(0) as u64;
+        if total > max_bytes {
+            purge_entry(db, &id, trash_path.as_deref()).await?;
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
+
+/// Delete an entry's files and row.
+async fn purge_entry(db: &Database, id: &str, trash_path: Option<&str>) -> Result<()> {
+    if let Some(path) = trash_path {
+        match tokio::fs::remove_dir_all(path).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("Failed to remove {path}")),
+        }
+    }
+    sqlx::query("DELETE FROM model_trash WHERE id = ?")
+        .bind(id)
+        .execute(&db.pool)
+        .await?;
+    Ok(())
+}
+
+/// Purge entries past their `purge_after`. Returns how many were purged.
+pub async fn purge_expired(db: &Database) -> Result<u64> {
+    let expired: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT id, trash_path FROM model_trash WHERE purge_after <= datetime('now')",
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    let mut purged = 0;
+    for (id, trash_path) in expired {
+        purge_entry(db, &id, trash_path.as_deref()).await?;
+        purged += 1;
+    }
+    Ok(purged)
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct TrashEntry {
+    id: String,
+    model_id: String,
+    hf_repo: String,
+    /// Whether files were moved to the trash (registered models have none).
+    has_files: bool,
+    size_bytes: i64,
+    deleted_by: Option<String>,
+    deleted_at: String,
+    purge_after: String,
+}
+
+/// GET /api/admin/models/trash — Deleted models that can still be restored.
+async fn list_trash(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match sqlx::query_as::<_, TrashEntry>(
+        "SELECT id, model_id, hf_repo, trash_path IS NOT NULL AS has_files, size_bytes, \
+         deleted_by, deleted_at, purge_after FROM model_trash ORDER BY deleted_at DESC",
+    )
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(entries) => Json(serde_json::json!({ "entries": entries })).into_response(),
+        Err(e) => error::internal_error("model_trash:list", e),
+    }
+}
+
+fn conflict(message: &str) -> Response {
+    (
+        StatusCode::CONFLICT,
+        Json(serde_json::json!({ "error": message })),
+    )
+        .into_response()
+}
+
+/// POST /api/admin/models/trash/:id/restore — Put a deleted model back.
+async fn restore(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> Response {
+    let entry: Option<(String, String, String, Option<String>, Option<String>)> =
+        match sqlx::query_as(
+            "SELECT model_id, hf_repo, model_row, launch_profile, trash_path \
+             FROM model_trash WHERE id = ?",
+        )
+        .bind(&id)
+        .fetch_optional(&state.db.pool)
+        .await
+        {
+            Ok(row) => row,
+            Err(e) => return error::internal_error("model_trash:lookup", e),
+        };
+    let Some((model_id, hf_repo, model_row, launch_profile, trash_path)) = entry else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Trash entry not found" })),
+        )
+            .into_response();
+    };
+
+    let exists: Option<String> = match sqlx::query_scalar("SELECT id FROM models WHERE id = ?")
+        .bind(&model_id)
+        .fetch_optional(&state.db.pool)
+        .await
+    {
+        Ok(row) => row,
+        Err(e) => return error::internal_error("model_trash:model_lookup", e),
+    };
+    if exists.is_some() {
+        return conflict("A model with this id exists again");
+    }
+    let model_dir = format!("{}/{}", state.config.model_path, hf_repo.replace('/', "--"));
+    if let Some(path) = &trash_path {
+        if std::path::Path::new(&model_dir).exists() {
+            return conflict("The model has been downloaded again");
+        }
+        if !std::path::Path::new(path).exists() {
+            return conflict("The model's files are missing from the trash");
+        }
+    }
+
+    let mut model_row: Value = match serde_json::from_str(&model_row) {
+        Ok(row) => row,
+        Err(e) => return error::internal_error("model_trash:parse", e),
+    };
+    // The category may have been deleted since
+    if let Some(category_id) = model_row["category_id"].as_str().map(str::to_string) {
+        match sqlx::query_scalar::<_, String>("SELECT id FROM model_categories WHERE id = ?")
+            .bind(&category_id)
+            .fetch_optional(&state.db.pool)
+            .await
+        {
+            Ok(Some(_)) => {}
+            Ok(None) => model_row["category_id"] = Value::Null,
+            Err(e) => return error::internal_error("model_trash:category", e),
+        }
+    }
+
+    // Entries trashed before these columns existed don't carry them
+    for (column, default) in [("default_params", "{}"), ("context_policy", "reject")] {
+        if matches!(model_row.get(column), None | Some(Value::Null)) {
+            model_row[column] = Value::String(default.to_string());
+        }
+    }
+
+    let mut tx = match state.db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return error::internal_error("model_trash:tx_begin", e),
+    };
+    let result: Result<(), sqlx::Error> = async {
+        sqlx::query(&restore_insert("models", MODEL_COLUMNS))
+            .bind(model_row.to_string())
+            .execute(&mut *tx)
+            .await?;
+        if let Some(profile) = &launch_profile {
+            sqlx::query(&restore_insert("model_launch_profiles", PROFILE_COLUMNS))
+                .bind(profile)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query("DELETE FROM model_trash WHERE id = ?")
+            .bind(&id)
+            .execute(&mut *tx)
+            .await?;
+        Ok(())
+    }
+    .await;
+    if let Err(e) = result {
+        return error::internal_error("model_trash:restore", e);
+    }
+
+    // Move the files back before committing, so a failure leaves the entry
+    if let Some(path) = &trash_path {
+        if let Err(e) = tokio::fs::rename(path, &model_dir).await {
+            return error::internal_error("model_trash:restore_files", e);
+        }
+    }
+    if let Err(e) = tx.commit().await {
+        if let Some(path) = &trash_path {
+            let _ = tokio::fs::rename(&model_dir, path).await;
+        }
+        return error::internal_error("model_trash:tx_commit", e);
+    }
+
+    info!(target: "audit", action = "model.restore", actor = %session.user_id, resource = %model_id, trash_id = %id, "Admin restored model from trash");
+    Json(serde_json::json!({ "status": "restored", "model_id": model_id })).into_response()
+}
+
+/// DELETE /api/admin/models/trash/:id — Purge an entry now.
+async fn purge(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> Response {
+    let entry: Option<(String, Option<String>)> =
+        match sqlx::query_as("SELECT model_id, trash_path FROM model_trash WHERE id = ?")
+            .bind(&id)
+            .fetch_optional(&state.db.pool)
+            .await
+        {
+            Ok(row) => row,
+            Err(e) => return error::internal_error("model_trash:lookup", e),
+        };
+    let Some((model_id, trash_path)) = entry else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Trash entry not found" })),
+        )
+            .into_response();
+    };
+
+    if let Err(e) = purge_entry(&state.db, &id, trash_path.as_deref()).await {
+        return error::internal_error("model_trash:purge", e);
+    }
+    info!(target: "audit", action = "model.purge", actor = %session.user_id, resource = %model_id, trash_id = %id, "Admin purged model from trash");
+    Json(serde_json::json!({ "status": "purged" })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_and_restore_cover_the_same_columns() {
+        let select = snapshot_select(
+            "model_launch_profiles",
+            "model_id",
+            &["model_id", "gpu_type"],
+        );
+        assert_eq!(
+            select,
+            "SELECT json_object('model_id', model_id, 'gpu_type', gpu_type) \
+             FROM model_launch_profiles WHERE model_id = ?"
+        );
+        assert_eq!(
+            restore_insert("model_launch_profiles", &["model_id", "gpu_type"]),
+            "INSERT INTO model_launch_profiles (model_id, gpu_type) \
+             SELECT json_extract(?1, '$.model_id'), json_extract(?1, '$.gpu_type')"
+        );
+    }
+}
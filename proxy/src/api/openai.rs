@@ -1,28 +1,49 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 
 use axum::body::{Body, Bytes};
 use axum::extract::State;
-use axum::http::{HeaderMap, Response, StatusCode};
+use axum::http::{HeaderMap, HeaderValue, Response, StatusCode};
 use axum::response::IntoResponse;
 use axum::routing::{get, post};
 use axum::{Extension, Json, Router};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
 use tokio::time::Instant;
 use tracing::{error, info, warn};
 
+use super::archive;
+use super::common;
+use super::context_guard::{self, Checked};
+use super::model_trace;
+use super::prompt_dedup;
+use super::request_log;
+use super::sampling_defaults;
+use super::system_prompts;
 use crate::auth::tokens;
 use crate::auth::AuthUser;
-use crate::proxy::streaming::proxy_to_backend;
-use crate::scheduler::usage;
+use crate::docker::vllm;
+use crate::proxy::streaming::{proxy_to_backend, StreamUsage};
+use crate::scheduler::gate::{GateError, QueueTicket};
+use crate::scheduler::{queue_log, resolver, usage};
 use crate::AppState;
 
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/chat/completions", post(chat_completions))
         .route("/completions", post(completions))
+        .route("/embeddings", post(embeddings))
         .route("/models", get(list_models))
-        .with_state(state)
+        .route("/queue", get(queued_requests))
+        .route("/queue/interrupted", get(queue_interrupted))
+        .route("/auth/jwt", post(issue_jwt))
+        .with_state(state.clone())
+        .merge(super::compat::routes(state.clone()))
+        .merge(super::prompt_uploads::routes(state.clone()))
+        .merge(super::tools::routes(state.clone()))
+        .merge(super::vector_store::routes(state))
 }
 
 #[derive(Debug, Deserialize)]
@@ -32,9 +53,30 @@ struct ChatCompletionRequest {
     stream: bool,
     /// OpenAI `user` field — Open WebUI populates this with the user's email.
     user: Option<String>,
+    max_tokens: Option<u32>,
+    /// Newer name for `max_tokens` on chat completions.
+    max_completion_tokens: Option<u32>,
+    /// Sampling seed, passed through to the backend and recorded in usage.
+    seed: Option<i64>,
     // All other fields are passed through to the backend
 }
 
+/// Backend path for embedding requests. llama.cpp serves its
+/// OpenAI-compatible embeddings here when started with `--embeddings`.
+const EMBEDDINGS_PATH: &str = "/v1/embeddings";
+
+/// Backend path for chat completions, the only requests that carry a
+/// system prompt.
+const CHAT_COMPLETIONS_PATH: &str = "/v1/chat/completions";
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingRequest {
+    model: String,
+    /// OpenAI `user` field — Open WebUI populates this with the user's email.
+    user: Option<String>,
+    // `input` and the other fields are passed through to the backend
+}
+
 /// Extract token usage from a non-streaming OpenAI response body.
 fn extract_usage_from_response(body: &[u8]) -> (i64, i64) {
     #[derive(Deserialize)]
@@ -62,8 +104,76 @@ fn extract_usage_from_response(body: &[u8]) -> (i64, i64) {
     }
 }
 
-/// Common logic for both chat and text completions: resolve model, proxy, log usage.
-async fn proxy_completion(
+/// Rough prompt token count for a completion request body.
+fn estimate_prompt_tokens(body: &[u8]) -> i64 {
+    serde_json::from_slice::<serde_json::Value>(body)
+        .map_or(0, |request| context_guard::estimate_tokens(&request))
+}
+
+/// Input and output tokens for a streamed response. Counts from the
+/// backend's usage frame win; otherwise the prompt is estimated and each
+/// output chunk counts as one token.
+fn streamed_token_counts(usage: StreamUsage, request_body: &[u8]) -> (i64, i64) {
+    let input = usage
+        .prompt_tokens
+        .unwrap_or_else(|| estimate_prompt_tokens(request_body));
+    let output = usage.completion_tokens.unwrap_or(usage.content_chunks);
+    (input, output)
+}
+
+/// Response header counting the messages dropped to fit the context.
+const CONTEXT_TRUNCATED_HEADER: &str = "x-context-truncated";
+
+/// Request header asking for strict determinism (`X-Deterministic: strict`).
+const DETERMINISTIC_HEADER: &str = "X-Deterministic";
+
+/// Whether the client asked for strict determinism.
+fn wants_strict_determinism(headers: &HeaderMap) -> bool {
+    headers
+        .get(DETERMINISTIC_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("strict"))
+}
+
+/// Why a strictly deterministic request can't be honored, as an OpenAI error
+/// `(code, message)`. Same seed and input only give the same output on
+/// llama-server with a single slot: with several slots, or on vLLM, requests
+/// are batched together and the batch changes the numerics.
+fn determinism_error(
+    seed: Option<i64>,
+    backend_type: &str,
+    parallel_slots: i64,
+) -> Option<(&'static str, String)> {
+    if seed.is_none() {
+        return Some((
+            "seed_required",
+            "Strict determinism requires a `seed` in the request body".to_string(),
+        ));
+    }
+    if backend_type != "llamacpp" {
+        return Some((
+            "determinism_unsupported",
+            format!("The {backend_type} backend batches requests and cannot guarantee deterministic output"),
+        ));
+    }
+    if parallel_slots > 1 {
+        return Some((
+            "determinism_unsupported",
+            format!("The model is running with {parallel_slots} parallel slots; strict determinism needs exactly 1"),
+        ));
+    }
+    None
+}
+
+/// Common logic for chat and text completions and embeddings: resolve model,
+/// proxy, log usage.
+///
+/// `replay_of` is set when an admin re-runs a logged request; usage is then
+/// recorded without a token and marked as a replay. A streaming request that
+/// has to queue hears about its place while it waits (see
+/// [`common::with_queue_preamble`]).
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn proxy_completion(
     state: Arc<AppState>,
     auth_user: AuthUser,
     body: Bytes,
@@ -71,6 +181,71 @@ async fn proxy_completion(
     is_streaming: bool,
     backend_path: &str,
     user_email_override: Option<&str>,
+    max_tokens: Option<u32>,
+    seed: Option<i64>,
+    strict_determinism: bool,
+    replay_of: Option<&str>,
+) -> Response<Body> {
+    if !is_streaming {
+        return serve_completion(
+            state,
+            auth_user,
+            body,
+            parsed_model,
+            is_streaming,
+            backend_path,
+            user_email_override,
+            max_tokens,
+            seed,
+            strict_determinism,
+            replay_of,
+            None,
+        )
+        .await;
+    }
+
+    let (queued_tx, queued_rx) = oneshot::channel();
+    let scheduler = state.scheduler.clone();
+    let parsed_model = parsed_model.to_string();
+    let backend_path = backend_path.to_string();
+    let user_email_override = user_email_override.map(str::to_string);
+    let replay_of = replay_of.map(str::to_string);
+    let serve = async move {
+        serve_completion(
+            state,
+            auth_user,
+            body,
+            &parsed_model,
+            is_streaming,
+            &backend_path,
+            user_email_override.as_deref(),
+            max_tokens,
+            seed,
+            strict_determinism,
+            replay_of.as_deref(),
+            Some(queued_tx),
+        )
+        .await
+    };
+    common::with_queue_preamble(&scheduler, queued_rx, serve, None).await
+}
+
+/// [`proxy_completion`], reporting on `queued` if the request has to wait
+/// for a slot.
+#[allow(clippy::too_many_arguments)]
+async fn serve_completion(
+    state: Arc<AppState>,
+    auth_user: AuthUser,
+    body: Bytes,
+    parsed_model: &str,
+    is_streaming: bool,
+    backend_path: &str,
+    user_email_override: Option<&str>,
+    max_tokens: Option<u32>,
+    seed: Option<i64>,
+    strict_determinism: bool,
+    replay_of: Option<&str>,
+    queued: Option<oneshot::Sender<QueueTicket>>,
 ) -> Response<Body> {
     let start = Instant::now();
 
@@ -92,7 +267,7 @@ async fn proxy_completion(
                 StatusCode::NOT_FOUND,
                 Json(serde_json::json!({
                     "error": {
-                        "message": format!("Model not found: {}", parsed_model),
+                        "message": resolver::not_found_message(&e, parsed_model),
                         "type": "invalid_request_error",
                         "code": "model_not_found"
                     }
@@ -102,7 +277,9 @@ async fn proxy_completion(
         }
     };
 
-    if !model.loaded {
+    // Autoload models are started further down, once the request is
+    // known to be allowed through
+    if !model.loaded && !common::autoload_enabled(&state.db.pool, &model.id).await {
         return (
             StatusCode::SERVICE_UNAVAILABLE,
             Json(serde_json::json!({
@@ -120,6 +297,76 @@ async fn proxy_completion(
             .into_response();
     }
 
+    let is_embedding = backend_path == EMBEDDINGS_PATH;
+    if is_embedding {
+        let embedding: bool = sqlx::query_scalar("SELECT embedding FROM models WHERE id = ?")
+            .bind(&model.id)
+            .fetch_one(&state.db.pool)
+            .await
+            .unwrap_or(false);
+        if !embedding {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": format!("Model '{}' is not an embedding model", model.hf_repo),
+                        "type": "invalid_request_error",
+                        "code": "model_not_embedding"
+                    }
+                })),
+            )
+                .into_response();
+        }
+    }
+
+    if let Some(drain) = state.scheduler.drain().await {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [("retry-after", drain.retry_after_secs.to_string())],
+            Json(serde_json::json!({
+                "error": {
+                    "message": "Server is draining for maintenance and not accepting new requests",
+                    "type": "server_error",
+                    "code": "server_draining",
+                    "draining_since": drain.started_at,
+                    "retry_after_secs": drain.retry_after_secs,
+                }
+            })),
+        )
+            .into_response();
+    }
+
+    if model.draining {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [("retry-after", "60")],
+            Json(serde_json::json!({
+                "error": {
+                    "message": format!("Model '{}' is draining for maintenance and not accepting new requests", model.hf_repo),
+                    "type": "server_error",
+                    "code": "model_draining"
+                }
+            })),
+        )
+            .into_response();
+    }
+
+    if common::admission_paused_for_memory(&state.config) {
+        warn!(model = %model.id, user = %auth_user.user_id, "Request refused under host memory pressure");
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [("retry-after", "30")],
+            Json(serde_json::json!({
+                "error": {
+                    "message": "Server is under memory pressure. Please retry later.",
+                    "type": "server_error",
+                    "code": "memory_pressure"
+                }
+            })),
+        )
+            .into_response();
+    }
+
     // If system is reserved, only the reservation holder may proceed.
     // Internal tokens (Open WebUI) are exempt — gated at the webui proxy level.
     if !auth_user.is_internal {
@@ -140,33 +387,151 @@ async fn proxy_completion(
         }
     }
 
+    if !model.loaded {
+        info!(model = %model.id, user = %auth_user.user_id, "Autoloading model for request");
+        match common::autoload_model(&state, &model.id).await {
+            Ok(()) => {}
+            Err(common::AutoloadError::Timeout) => {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    [("retry-after", "30")],
+                    Json(serde_json::json!({
+                        "error": {
+                            "message": format!("Model '{}' is still loading. Please retry shortly.", model.hf_repo),
+                            "type": "server_error",
+                            "code": "model_loading"
+                        }
+                    })),
+                )
+                    .into_response();
+            }
+            Err(common::AutoloadError::Failed(reason)) => {
+                return (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(serde_json::json!({
+                        "error": {
+                            "message": format!("Model '{}' could not be loaded: {}", model.hf_repo, reason),
+                            "type": "server_error",
+                            "code": "model_load_failed"
+                        }
+                    })),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    // Refuse (or shorten) prompts that can't fit the model's context before
+    // they take a slot. The log keeps the body as the client sent it.
+    let client_body = body.clone();
+    let (body, truncated) = if is_embedding {
+        (body, 0)
+    } else {
+        match context_guard::limit_for(&state.db.pool, &model.id).await {
+            Ok(Some(limit)) => match context_guard::check(body, limit, max_tokens) {
+                Checked::Fits(body) => (body, 0),
+                Checked::Truncated { body, dropped } => {
+                    info!(model = %model.id, user = %auth_user.user_id, dropped, "Dropped oldest messages to fit the context");
+                    (body, dropped)
+                }
+                Checked::Exceeded { prompt_tokens } => {
+                    let param = if backend_path == CHAT_COMPLETIONS_PATH {
+                        "messages"
+                    } else {
+                        "prompt"
+                    };
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(serde_json::json!({
+                            "error": {
+                                "message": format!(
+                                    "This model's maximum context length is {} tokens, but the request has about {} tokens of input. Shorten the {}.",
+                                    limit.tokens, prompt_tokens, param
+                                ),
+                                "type": "invalid_request_error",
+                                "param": param,
+                                "code": "context_length_exceeded"
+                            }
+                        })),
+                    )
+                        .into_response();
+                }
+            },
+            Ok(None) => (body, 0),
+            Err(e) => {
+                warn!(error = %e, model = %model.id, "Failed to load context limit");
+                (body, 0)
+            }
+        }
+    };
+
+    // A request strict determinism can't be honored for is refused before
+    // it waits for a slot
+    if strict_determinism {
+        let parallel_slots: i64 =
+            sqlx::query_scalar("SELECT parallel_slots FROM container_secrets WHERE model_id = ?")
+                .bind(&model.id)
+                .fetch_optional(&state.db.pool)
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or(1);
+        if let Some((code, message)) = determinism_error(seed, &model.backend_type, parallel_slots)
+        {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": message,
+                        "type": "invalid_request_error",
+                        "code": code
+                    }
+                })),
+            )
+                .into_response();
+        }
+    }
+
     // Acquire a concurrency slot (holds connection, times out with 429)
     let queue_start = Instant::now();
     let settings = state.scheduler.settings().await;
-    let timeout = Duration::from_secs(settings.queue_timeout_secs);
-    let _slot = match state
+    let policy = state.scheduler.policy().await;
+    let timeout = state
         .scheduler
         .gate()
-        .acquire_with_timeout(
+        .queue_timeout(
+            model.category_id.as_deref(),
+            Duration::from_secs(settings.queue_timeout_secs),
+        )
+        .await;
+    let estimated_wait_ms = state.scheduler.estimate_wait_ms(&model.id).await;
+    let slot = match state
+        .scheduler
+        .gate()
+        .acquire_reporting(
             &model.id,
+            model.category_id.as_deref(),
             &auth_user.user_id,
+            max_tokens,
             &state.db,
             &settings,
+            policy.as_ref(),
             state.scheduler.queue(),
             timeout,
+            queued,
         )
         .await
     {
         Ok(slot) => slot,
-        Err(_) => {
+        Err(GateError::Timeout) => {
             warn!(
                 model = %model.id,
                 user = %auth_user.user_id,
                 "Request timed out in queue"
             );
-            return (
+            let mut response = (
                 StatusCode::TOO_MANY_REQUESTS,
-                [("retry-after", settings.queue_timeout_secs.to_string())],
+                [("retry-after", timeout.as_secs().to_string())],
                 Json(serde_json::json!({
                     "error": {
                         "message": "Server is busy. Please retry later.",
@@ -176,47 +541,179 @@ async fn proxy_completion(
                 })),
             )
                 .into_response();
+            common::add_busy_headers(&mut response, &state.scheduler, &model.id).await;
+            return response;
+        }
+        Err(GateError::QueueFull) => {
+            warn!(
+                model = %model.id,
+                category = ?model.category_id,
+                user = %auth_user.user_id,
+                "Category queue full"
+            );
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                [("retry-after", timeout.as_secs().to_string())],
+                Json(serde_json::json!({
+                    "error": {
+                        "message": "Too many requests are waiting for this model's category. Please retry later.",
+                        "type": "server_error",
+                        "code": "category_queue_full"
+                    }
+                })),
+            )
+                .into_response();
+            common::add_busy_headers(&mut response, &state.scheduler, &model.id).await;
+            return response;
         }
     };
     let queued_ms = queue_start.elapsed().as_millis() as i64;
 
+    // Look up the serving container and its API key for backend
+    // authentication. One row, so a blue/green replace can't pair the new
+    // container with the old key.
+    let secrets: Option<(String, Option<String>)> =
+        sqlx::query_as("SELECT api_key, container_name FROM container_secrets WHERE model_id = ?")
+            .bind(&model.id)
+            .fetch_optional(&state.db.pool)
+            .await
+            .ok()
+            .flatten();
+    let api_key = secrets.as_ref().map(|(key, _)| key.clone());
+    let container = secrets
+        .as_ref()
+        .and_then(|(_, name)| name.clone())
+        .unwrap_or_else(|| crate::docker::container_name(&model.backend_type, &model.id));
+
     // Reach backend via container name on the internal Docker network
     let backend_url = format!(
         "{}{}",
-        state
-            .docker
-            .backend_base_url(&model.id, &model.backend_type),
+        state.docker.backend_url(&container, &model.backend_type),
         backend_path
     );
-    let client = reqwest::Client::new();
 
-    // Look up the per-container API key for backend authentication
-    let api_key: Option<String> =
-        sqlx::query_as::<_, (String,)>("SELECT api_key FROM container_secrets WHERE model_id = ?")
-            .bind(&model.id)
-            .fetch_optional(&state.db.pool)
+    // Keep the body as the client sent it, before any backend rewrite.
+    // Only completions are logged and archived, not embeddings. The model's
+    // category decides how much of the request log entry is kept.
+    let log_level = if is_embedding {
+        request_log::LogLevel::None
+    } else {
+        request_log::level_for(
+            &state.db.pool,
+            state.config.request_log,
+            model.category_id.as_deref(),
+        )
+        .await
+        .unwrap_or_else(|e| {
+            warn!(error = %e, model = %model.id, "Failed to look up request log level");
+            request_log::LogLevel::default_for(state.config.request_log)
+        })
+    };
+    // A live trace on the model captures the request in full regardless
+    let traced = !is_embedding
+        && replay_of.is_none()
+        && model_trace::claim(&state.db.pool, &model.id)
             .await
-            .ok()
-            .flatten()
-            .map(|(key,)| key);
+            .unwrap_or_else(|e| {
+                warn!(error = %e, model = %model.id, "Failed to check model trace");
+                false
+            });
+    let log_level = if traced {
+        request_log::LogLevel::Full
+    } else {
+        log_level
+    };
+    let archive = state.config.prompt_archive && replay_of.is_none() && !is_embedding;
+    let prompt_hashes = (state.config.prompt_dedup_stats && replay_of.is_none() && !is_embedding)
+        .then(|| prompt_dedup::hash_body(&client_body))
+        .flatten();
+    let logged_body =
+        (log_level == request_log::LogLevel::Full || archive).then(|| client_body.clone());
+
+    // Org-wide system prompt policies apply to chat completions only. The
+    // log and archive keep the client's own body; the injection is audited.
+    let body = if backend_path == CHAT_COMPLETIONS_PATH {
+        match system_prompts::apply(
+            &state.db.pool,
+            body,
+            model.category_id.as_deref(),
+            &auth_user.user_id,
+            &auth_user.token_id,
+            &model.id,
+        )
+        .await
+        {
+            Ok(body) => body,
+            Err(e) => {
+                error!(error = %e, model = %model.id, "Failed to apply system prompt policies");
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({
+                        "error": {
+                            "message": "Internal server error",
+                            "type": "server_error",
+                            "code": "system_prompt_policy_error"
+                        }
+                    })),
+                )
+                    .into_response();
+            }
+        }
+    } else {
+        body
+    };
+    // The model's sampling defaults fill in what the client left out
+    let body = if is_embedding {
+        body
+    } else {
+        match sampling_defaults::for_model(&state.db.pool, &model.id).await {
+            Ok(defaults) => defaults.apply(body, &model.backend_type),
+            Err(e) => {
+                warn!(error = %e, model = %model.id, "Failed to load sampling defaults");
+                body
+            }
+        }
+    };
+    // Kept for estimating prompt tokens if the stream carries no usage frame
+    let prompt_body = is_streaming.then(|| body.clone());
+
+    // vLLM only answers to the name it serves the model under
+    let body = if model.backend_type == "vllm" {
+        vllm::rewrite_model_field(body, &model.id)
+    } else {
+        body
+    };
 
     let result = proxy_to_backend(
-        &client,
+        &state.backends,
+        &model.id,
+        &model.backend_type,
         &backend_url,
         body,
         is_streaming,
         api_key.as_deref(),
+        state.config.sse_keepalive(),
     )
     .await;
 
     let latency_ms = start.elapsed().as_millis() as i64;
+    let response_status = result.response.status().as_u16();
+    let response_body = logged_body.as_ref().and(result.body_bytes.clone());
 
-    // Extract token usage from non-streaming responses
+    // Extract token usage from non-streaming responses; streamed usage is
+    // only known once the client body finishes
     let (input_tokens, output_tokens) = result
         .body_bytes
         .as_ref()
         .map(|b| extract_usage_from_response(b))
         .unwrap_or((0, 0));
+    let stream_usage = result.stream_usage;
+    if stream_usage.is_none() {
+        state
+            .scheduler
+            .record_usage(&auth_user.user_id, input_tokens + output_tokens)
+            .await;
+    }
 
     // Meta token resolution: if this is an internal token (Open WebUI) and the
     // request includes a `user` email, attribute usage to the actual user.
@@ -241,14 +738,73 @@ async fn proxy_completion(
     };
 
     let db = state.db.clone();
+    let scheduler = state.scheduler.clone();
+    let fairness_user_id = auth_user.user_id.clone();
     let token_id = log_token_id;
     let user_id = log_user_id;
     let model_id = model.id.clone();
     let category_id = model.category_id.clone();
+    let replay_of = replay_of.map(str::to_string);
+    let endpoint = backend_path.to_string();
 
     tokio::spawn(async move {
+        let (input_tokens, output_tokens) = match stream_usage {
+            Some(rx) => {
+                // Resolves when the stream ends or the client disconnects
+                let usage = rx.await.unwrap_or_default();
+                let counts = streamed_token_counts(usage, prompt_body.as_deref().unwrap_or(&[]));
+                scheduler
+                    .record_usage(&fairness_user_id, counts.0 + counts.1)
+                    .await;
+                counts
+            }
+            None => (input_tokens, output_tokens),
+        };
+
+        if log_level != request_log::LogLevel::None {
+            let sanitized = logged_body
+                .as_deref()
+                .filter(|_| traced)
+                .map(model_trace::sanitize);
+            let logged = request_log::LoggedRequest {
+                log_level,
+                user_id: &user_id,
+                token_id: (!token_id.is_empty()).then_some(token_id.as_str()),
+                model_id: &model_id,
+                category_id: category_id.as_deref(),
+                endpoint: &endpoint,
+                request_body: sanitized
+                    .as_deref()
+                    .or(logged_body.as_deref())
+                    .unwrap_or_default(),
+                response_status,
+                response_body: response_body.as_deref(),
+                latency_ms,
+                replay_of: replay_of.as_deref(),
+                seed,
+                input_tokens,
+                output_tokens,
+                traced,
+            };
+            if let Err(e) = request_log::record(&db, &logged).await {
+                warn!(error = %e, "Failed to log request");
+            }
+        }
+        if let Some(request_body) = logged_body.as_ref().filter(|_| archive) {
+            let entry = archive::ArchiveEntry {
+                user_id: &user_id,
+                model_id: &model_id,
+                endpoint: &endpoint,
+                request_body,
+                response_body: response_body.as_deref(),
+            };
+            if let Err(e) = archive::record(&db, &entry).await {
+                warn!(error = %e, "Failed to archive completion");
+            }
+        }
+
         let entry = usage::UsageEntry {
-            token_id: &token_id,
+            token_id: replay_of.is_none().then_some(token_id.as_str()),
             user_id: &user_id,
             model_id: &model_id,
             category_id: category_id.as_deref(),
@@ -256,13 +812,24 @@ async fn proxy_completion(
             output_tokens,
             latency_ms,
             queued_ms,
+            replay_of: replay_of.as_deref(),
+            seed,
+            prompt_hash: prompt_hashes.as_ref().map(|h| h.exact.as_str()),
+            prompt_norm_hash: prompt_hashes.as_ref().map(|h| h.normalized.as_str()),
         };
         if let Err(e) = usage::log_usage(&db, &entry).await {
             warn!(error = %e, "Failed to log usage");
         }
     });
 
-    result.response
+    let mut response = result.response;
+    common::add_queue_headers(&mut response, slot.queue_position(), estimated_wait_ms);
+    if truncated > 0 {
+        response
+            .headers_mut()
+            .insert(CONTEXT_TRUNCATED_HEADER, HeaderValue::from(truncated));
+    }
+    response
 }
 
 /// POST /v1/chat/completions -- OpenAI-compatible chat completion endpoint.
@@ -273,6 +840,10 @@ async fn chat_completions(
     headers: HeaderMap,
     body: Bytes,
 ) -> impl IntoResponse {
+    let body = match super::prompt_uploads::expand(&state, &auth_user, body, true).await {
+        Ok(b) => b,
+        Err(r) => return r,
+    };
     let parsed: ChatCompletionRequest = match serde_json::from_slice(&body) {
         Ok(r) => r,
         Err(e) => {
@@ -307,8 +878,12 @@ async fn chat_completions(
         body,
         &parsed.model,
         parsed.stream,
-        "/v1/chat/completions",
+        CHAT_COMPLETIONS_PATH,
         user_email,
+        parsed.max_completion_tokens.or(parsed.max_tokens),
+        parsed.seed,
+        wants_strict_determinism(&headers),
+        None,
     )
     .await
 }
@@ -320,6 +895,10 @@ async fn completions(
     headers: HeaderMap,
     body: Bytes,
 ) -> impl IntoResponse {
+    let body = match super::prompt_uploads::expand(&state, &auth_user, body, false).await {
+        Ok(b) => b,
+        Err(r) => return r,
+    };
     let parsed: ChatCompletionRequest = match serde_json::from_slice(&body) {
         Ok(r) => r,
         Err(e) => {
@@ -355,15 +934,150 @@ async fn completions(
         parsed.stream,
         "/v1/completions",
         user_email,
+        parsed.max_tokens,
+        parsed.seed,
+        wants_strict_determinism(&headers),
+        None,
     )
     .await
 }
 
+/// POST /v1/embeddings -- OpenAI-compatible embeddings endpoint. Takes the
+/// same reservation and concurrency-gate path as completions and records
+/// the prompt tokens as usage.
+async fn embeddings(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let parsed: EmbeddingRequest = match serde_json::from_slice(&body) {
+        Ok(r) => r,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": format!("Invalid request body: {}", e),
+                        "type": "invalid_request_error",
+                        "code": "invalid_body"
+                    }
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    info!(
+        model = %parsed.model,
+        user_id = %auth_user.user_id,
+        "Embedding request"
+    );
+
+    let header_email = headers
+        .get("X-OpenWebUI-User-Email")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+    let user_email = header_email.as_deref().or(parsed.user.as_deref());
+
+    proxy_completion(
+        state,
+        auth_user,
+        body,
+        &parsed.model,
+        false,
+        EMBEDDINGS_PATH,
+        user_email,
+        None,
+        None,
+        false,
+        None,
+    )
+    .await
+}
+
+/// Embed `inputs` with `model` through the same path as `/v1/embeddings`,
+/// so token scope, reservations, gating and usage all apply. Vectors come
+/// back in input order; an error is the response to hand to the caller.
+pub(crate) async fn embed_texts(
+    state: Arc<AppState>,
+    auth_user: AuthUser,
+    model: &str,
+    inputs: &[String],
+) -> Result<Vec<Vec<f32>>, Response<Body>> {
+    let body = serde_json::json!({ "model": model, "input": inputs });
+    let response = proxy_completion(
+        state,
+        auth_user,
+        Bytes::from(body.to_string()),
+        model,
+        false,
+        EMBEDDINGS_PATH,
+        None,
+        None,
+        None,
+        false,
+        None,
+    )
+    .await;
+    if !response.status().is_success() {
+        return Err(response);
+    }
+
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap_or_default();
+    let parsed: Option<Vec<(u64, Vec<f32>)>> = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .ok()
+        .and_then(|v| {
+            v["data"]
+                .as_array()?
+                .iter()
+                .map(|item| {
+                    let index = item["index"].as_u64()?;
+                    let embedding = serde_json::from_value(item["embedding"].clone()).ok()?;
+                    Some((index, embedding))
+                })
+                .collect()
+        });
+    match parsed {
+        Some(mut data) if data.len() == inputs.len() => {
+            data.sort_by_key(|(index, _)| *index);
+            Ok(data.into_iter().map(|(_, embedding)| embedding).collect())
+        }
+        _ => {
+            error!(model = %model, "Backend returned a malformed embeddings response");
+            Err((
+                StatusCode::BAD_GATEWAY,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": "Backend returned a malformed embeddings response",
+                        "type": "server_error",
+                        "code": "bad_embeddings"
+                    }
+                })),
+            )
+                .into_response())
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 struct ModelInfo {
     id: String,
     object: &'static str,
+    /// Unix time the model was registered.
+    created: i64,
     owned_by: &'static str,
+    /// True while an admin drains the model — new requests get `model_draining`.
+    draining: bool,
+    /// For a category alias, the model a request for it is routed to now.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolves_to: Option<String>,
+    /// What the (target) model's backend answered when probed after its
+    /// start; see `api::capabilities`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    capabilities: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Serialize)]
@@ -372,21 +1086,277 @@ struct ModelsResponse {
     data: Vec<ModelInfo>,
 }
 
-/// GET /v1/models -- List available models (OpenAI-compatible).
-async fn list_models(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let models: Vec<(String, String)> =
-        match sqlx::query_as("SELECT id, hf_repo FROM models WHERE loaded = 1")
-            .fetch_all(&state.db.pool)
+#[derive(Debug, sqlx::FromRow)]
+struct LoadedModelRow {
+    id: String,
+    hf_repo: String,
+    category_id: Option<String>,
+    category_name: Option<String>,
+    preferred_model_id: Option<String>,
+    draining: bool,
+    created_at: DateTime<Utc>,
+    capabilities: Option<String>,
+}
+
+impl LoadedModelRow {
+    fn capabilities(&self) -> Option<serde_json::Value> {
+        serde_json::from_str(self.capabilities.as_deref()?).ok()
+    }
+}
+
+/// The loaded models a token can reach, plus an alias per category with a
+/// servable model. `rows` must be ordered by most recent use, matching the
+/// resolver's fallback when a category's preferred model isn't available.
+fn model_listing(
+    rows: &[LoadedModelRow],
+    category_id: Option<&str>,
+    specific_model_id: Option<&str>,
+) -> Vec<ModelInfo> {
+    let visible: Vec<&LoadedModelRow> = rows
+        .iter()
+        .filter(|m| match (specific_model_id, category_id) {
+            (Some(id), _) => m.id == id,
+            (None, Some(cat)) => m.category_id.as_deref() == Some(cat),
+            (None, None) => true,
+        })
+        .collect();
+
+    let mut data: Vec<ModelInfo> = visible
+        .iter()
+        .map(|m| ModelInfo {
+            id: m.hf_repo.clone(),
+            object: "model",
+            created: m.created_at.timestamp(),
+            owned_by: "sovereign-engine",
+            draining: m.draining,
+            resolves_to: None,
+            capabilities: m.capabilities(),
+        })
+        .collect();
+    data.sort_by(|a, b| a.id.cmp(&b.id));
+
+    // A token pinned to one model can't pick a category
+    if specific_model_id.is_some() {
+        return data;
+    }
+
+    // Category name -> the model it resolves to: the preferred model when
+    // it's servable, otherwise the most recently used one
+    let mut targets: BTreeMap<&str, &LoadedModelRow> = BTreeMap::new();
+    for m in visible.iter().filter(|m| !m.draining) {
+        let Some(name) = m.category_name.as_deref() else {
+            continue;
+        };
+        let preferred = m.preferred_model_id.as_deref() == Some(m.id.as_str());
+        targets
+            .entry(name)
+            .and_modify(|target| {
+                if preferred {
+                    *target = m;
+                }
+            })
+            .or_insert(m);
+    }
+    for (name, target) in targets {
+        // Model names resolve before category names
+        if rows.iter().any(|m| m.hf_repo == name || m.id == name) {
+            continue;
+        }
+        data.push(ModelInfo {
+            id: name.to_string(),
+            object: "model",
+            created: target.created_at.timestamp(),
+            owned_by: "sovereign-engine",
+            draining: false,
+            resolves_to: Some(target.hf_repo.clone()),
+            capabilities: target.capabilities(),
+        });
+    }
+    data
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct AliasRow {
+    alias: String,
+    model_id: Option<String>,
+    category_name: Option<String>,
+}
+
+/// Admin-defined aliases (see `model_aliases`) whose target can serve now.
+/// A model alias resolves to the model; a category alias to whatever the
+/// category's entry in `data` resolves to. Aliases already listed under
+/// their own name are skipped.
+fn alias_listing(
+    data: &[ModelInfo],
+    rows: &[LoadedModelRow],
+    aliases: &[AliasRow],
+) -> Vec<ModelInfo> {
+    aliases
+        .iter()
+        .filter(|a| !data.iter().any(|m| m.id == a.alias))
+        .filter_map(|a| {
+            let (created, target, capabilities) = match (&a.model_id, &a.category_name) {
+                (Some(model_id), _) => {
+                    rows.iter()
+                        .find(|m| &m.id == model_id && !m.draining)
+                        .map(|m| {
+                            (
+                                m.created_at.timestamp(),
+                                m.hf_repo.clone(),
+                                m.capabilities(),
+                            )
+                        })?
+                }
+                (None, Some(name)) => data.iter().find(|m| &m.id == name).and_then(|m| {
+                    Some((m.created, m.resolves_to.clone()?, m.capabilities.clone()))
+                })?,
+                (None, None) => return None,
+            };
+            Some(ModelInfo {
+                id: a.alias.clone(),
+                object: "model",
+                created,
+                owned_by: "sovereign-engine",
+                draining: false,
+                resolves_to: Some(target),
+                capabilities,
+            })
+        })
+        .collect()
+}
+
+/// GET /v1/queue — The token owner's requests currently waiting for a slot,
+/// with their place in line and predicted remaining wait.
+async fn queued_requests(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let queued = state.scheduler.user_queue(&auth_user.user_id).await;
+    Json(serde_json::json!({ "queued": queued }))
+}
+
+/// GET /v1/queue/interrupted — The token owner's queued requests dropped by
+/// a proxy restart within the last day, so API clients can resubmit them.
+async fn queue_interrupted(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    match queue_log::interrupted_for_user(&state.db, &auth_user.user_id, "-1 day").await {
+        Ok(entries) => Json(serde_json::json!({ "interrupted": entries })).into_response(),
+        Err(e) => {
+            error!(error = %e, "Failed to list interrupted queue entries");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": "Failed to list interrupted requests",
+                        "type": "server_error"
+                    }
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// GET /v1/models -- List the loaded models, category aliases and
+/// admin-defined aliases the token can use (OpenAI-compatible).
+async fn list_models(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let rows: Vec<LoadedModelRow> = match sqlx::query_as(
+        "SELECT m.id, m.hf_repo, m.category_id, c.name AS category_name, c.preferred_model_id, \
+                m.draining, m.created_at, m.capabilities \
+         FROM models m LEFT JOIN model_categories c ON c.id = m.category_id \
+         WHERE m.loaded = 1 \
+         ORDER BY m.last_used_at DESC",
+    )
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(m) => m,
+        Err(e) => {
+            error!(error = %e, "Failed to query models");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({
+                    "error": {
+                        "message": "Failed to list models",
+                        "type": "server_error"
+                    }
+                })),
+            )
+                .into_response();
+        }
+    };
+
+    let mut data = model_listing(
+        &rows,
+        auth_user.category_id.as_deref(),
+        auth_user.specific_model_id.as_deref(),
+    );
+    // Scoped tokens never resolve through aliases
+    if auth_user.category_id.is_none() && auth_user.specific_model_id.is_none() {
+        match sqlx::query_as::<_, AliasRow>(
+            "SELECT a.alias, a.model_id, c.name AS category_name \
+             FROM model_aliases a LEFT JOIN model_categories c ON c.id = a.category_id \
+             ORDER BY a.alias",
+        )
+        .fetch_all(&state.db.pool)
+        .await
+        {
+            Ok(aliases) => {
+                let listed = alias_listing(&data, &rows, &aliases);
+                data.extend(listed);
+            }
+            Err(e) => error!(error = %e, "Failed to query model aliases"),
+        }
+    }
+
+    Json(ModelsResponse {
+        object: "list",
+        data,
+    })
+    .into_response()
+}
+
+/// POST /v1/auth/jwt -- Trade the presented opaque token for a short-lived JWT
+/// access token that is validated without a DB query.
+async fn issue_jwt(
+    State(state): State<Arc<AppState>>,
+    Extension(auth): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let Some(jwt) = &state.jwt else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "error": {
+                    "message": "JWT access tokens are not enabled",
+                    "type": "invalid_request_error",
+                    "code": "jwt_disabled"
+                }
+            })),
+        )
+            .into_response();
+    };
+
+    // A JWT must not mint its successor (revocation is tracked per opaque
+    // token), and quota-capped tokens need the DB check on every request
+    let quota: Option<(Option<i64>,)> =
+        match sqlx::query_as("SELECT request_quota FROM tokens WHERE id = ?")
+            .bind(&auth.token_id)
+            .fetch_optional(&state.db.pool)
             .await
         {
-            Ok(m) => m,
+            Ok(q) => q,
             Err(e) => {
-                error!(error = %e, "Failed to query models");
+                error!(error = %e, "Failed to look up token quota");
                 return (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     Json(serde_json::json!({
                         "error": {
-                            "message": "Failed to list models",
+                            "message": "Failed to issue JWT",
                             "type": "server_error"
                         }
                     })),
@@ -394,19 +1364,175 @@ async fn list_models(State(state): State<Arc<AppState>>) -> impl IntoResponse {
                     .into_response();
             }
         };
+    let capped = quota.is_some_and(|(q,)| q.is_some());
+    if auth.via_jwt || auth.is_internal || capped {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({
+                "error": {
+                    "message": "This token cannot be exchanged for a JWT",
+                    "type": "invalid_request_error",
+                    "code": "jwt_not_allowed"
+                }
+            })),
+        )
+            .into_response();
+    }
 
-    let data: Vec<ModelInfo> = models
-        .into_iter()
-        .map(|(_, hf_repo)| ModelInfo {
-            id: hf_repo,
-            object: "model",
-            owned_by: "sovereign-engine",
-        })
-        .collect();
+    info!(target: "audit", action = "token.jwt_issue", actor = %auth.user_id, resource = %auth.token_id, "JWT access token issued");
 
-    Json(ModelsResponse {
-        object: "list",
-        data,
-    })
+    Json(serde_json::json!({
+        "access_token": jwt.issue(&auth),
+        "token_type": "Bearer",
+        "expires_in": jwt.ttl_secs(),
+    }))
     .into_response()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn loaded(id: &str, category: Option<(&str, &str)>, preferred: Option<&str>) -> LoadedModelRow {
+        LoadedModelRow {
+            id: id.to_string(),
+            hf_repo: format!("org/{id}"),
+            category_id: category.map(|(cid, _)| cid.to_string()),
+            category_name: category.map(|(_, name)| name.to_string()),
+            preferred_model_id: preferred.map(str::to_string),
+            draining: false,
+            created_at: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            capabilities: None,
+        }
+    }
+
+    #[test]
+    fn listing_includes_category_aliases() {
+        let thinking = Some(("c1", "thinking"));
+        // Ordered by most recent use: m2 first
+        let rows = vec![
+            loaded("m2", thinking, Some("m1")),
+            loaded("m1", thinking, Some("m1")),
+            loaded("m3", None, None),
+        ];
+        let data = model_listing(&rows, None, None);
+        let ids: Vec<&str> = data.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["org/m1", "org/m2", "org/m3", "thinking"]);
+        // The preferred model wins over the most recently used one
+        assert_eq!(data[3].resolves_to.as_deref(), Some("org/m1"));
+        assert_eq!(data[0].created, 1_700_000_000);
+    }
+
+    #[test]
+    fn listing_carries_probed_capabilities() {
+        let mut m1 = loaded("m1", Some(("c1", "embed")), None);
+        m1.capabilities = Some(r#"{"endpoints":["embeddings"],"embedding_dim":1024}"#.into());
+        let rows = vec![m1, loaded("m2", None, None)];
+        let data = model_listing(&rows, None, None);
+        assert_eq!(
+            data[0].capabilities.as_ref().unwrap()["embedding_dim"],
+            1024
+        );
+        assert!(data[1].capabilities.is_none());
+        // The category alias shows its target's
+        assert_eq!(data[2].id, "embed");
+        assert_eq!(data[2].capabilities, data[0].capabilities);
+    }
+
+    #[test]
+    fn listing_respects_token_scope() {
+        let rows = vec![
+            loaded("m1", Some(("c1", "thinking")), None),
+            loaded("m2", Some(("c2", "fast")), None),
+        ];
+
+        let scoped = model_listing(&rows, Some("c2"), None);
+        let ids: Vec<&str> = scoped.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["org/m2", "fast"]);
+        assert_eq!(scoped[1].resolves_to.as_deref(), Some("org/m2"));
+
+        let pinned = model_listing(&rows, None, Some("m1"));
+        let ids: Vec<&str> = pinned.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, ["org/m1"]);
+    }
+
+    #[test]
+    fn listing_includes_servable_admin_aliases() {
+        let mut draining = loaded("m3", None, None);
+        draining.draining = true;
+        let rows = vec![loaded("m1", Some(("c1", "thinking")), None), draining];
+        let data = model_listing(&rows, None, None);
+        let alias = |alias: &str, model_id: Option<&str>, category: Option<&str>| AliasRow {
+            alias: alias.to_string(),
+            model_id: model_id.map(str::to_string),
+            category_name: category.map(str::to_string),
+        };
+        let aliases = vec![
+            alias("gpt-4o", Some("m1"), None),
+            alias("o1", None, Some("thinking")),
+            alias("org/m1", Some("m1"), None),
+            alias("slow", Some("m3"), None),
+            alias("gone", None, Some("empty")),
+        ];
+
+        let listed = alias_listing(&data, &rows, &aliases);
+        let ids: Vec<(&str, Option<&str>)> = listed
+            .iter()
+            .map(|m| (m.id.as_str(), m.resolves_to.as_deref()))
+            .collect();
+        assert_eq!(ids, [("gpt-4o", Some("org/m1")), ("o1", Some("org/m1"))]);
+    }
+
+    #[test]
+    fn prompt_estimate_covers_messages_and_prompts() {
+        let chat = br#"{"messages":[{"role":"user","content":"12345678"},
+            {"role":"user","content":[{"type":"text","text":"abcd"},{"type":"image_url","image_url":{"url":"x"}}]}]}"#;
+        assert_eq!(estimate_prompt_tokens(chat), 3);
+        assert_eq!(estimate_prompt_tokens(br#"{"prompt":["abc","de"]}"#), 2);
+        assert_eq!(estimate_prompt_tokens(b"not json"), 0);
+    }
+
+    #[test]
+    fn streamed_counts_prefer_backend_usage() {
+        let body = br#"{"messages":[{"role":"user","content":"12345678"}]}"#;
+        let reported = StreamUsage {
+            prompt_tokens: Some(20),
+            completion_tokens: Some(9),
+            content_chunks: 7,
+        };
+        assert_eq!(streamed_token_counts(reported, body), (20, 9));
+
+        let counted = StreamUsage {
+            content_chunks: 7,
+            ..Default::default()
+        };
+        assert_eq!(streamed_token_counts(counted, body), (2, 7));
+    }
+
+    #[test]
+    fn strict_determinism_header() {
+        let mut headers = HeaderMap::new();
+        assert!(!wants_strict_determinism(&headers));
+        headers.insert(DETERMINISTIC_HEADER, "Strict".parse().unwrap());
+        assert!(wants_strict_determinism(&headers));
+        headers.insert(DETERMINISTIC_HEADER, "best-effort".parse().unwrap());
+        assert!(!wants_strict_determinism(&headers));
+    }
+
+    #[test]
+    fn determinism_needs_seed_and_single_llamacpp_slot() {
+        assert!(determinism_error(Some(1), "llamacpp", 1).is_none());
+        assert_eq!(
+            determinism_error(None, "llamacpp", 1).unwrap().0,
+            "seed_required"
+        );
+        assert_eq!(
+            determinism_error(Some(1), "vllm", 1).unwrap().0,
+            "determinism_unsupported"
+        );
+        assert_eq!(
+            determinism_error(Some(1), "llamacpp", 4).unwrap().0,
+            "determinism_unsupported"
+        );
+    }
+}
@@ -0,0 +1,361 @@
+//! Repeated-prompt statistics (`PROMPT_DEDUP_STATS=true`).
+//!
+//! Each completion request records two SHA-256 hashes in `usage_log`:
+//! `prompt_hash` over the request body minus fields that don't change the
+//! response (`stream`, `stream_options`, `user`), which is what a response
+//! cache would be keyed on, and `prompt_norm_hash` over the prompt text
+//! alone, lowercased and with whitespace collapsed, to catch near-identical
+//! prompts. Only hashes are stored. `GET /api/admin/usage/dedup` reports how
+//! many requests repeated an earlier one for the same model, and the tokens
+//! a response cache with a given TTL would have saved.
+
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{middleware, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use super::common;
+use super::error;
+use crate::auth::rbac::{self, Permission};
+use crate::AppState;
+
+/// Request fields that don't affect the response.
+const IGNORED_FIELDS: &[&str] = &["stream", "stream_options", "user"];
+
+/// Cache TTLs the savings are estimated for, in seconds.
+const TTLS: &[i64] = &[60, 300, 3600, 86400];
+
+/// Models listed in `by_model`, most repeats first.
+const TOP_MODELS: usize = 20;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/usage/dedup", get(dedup_stats))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::View,
+            rbac::require_permission,
+        ))
+        .with_state(state)
+}
+
+/// Hashes of one completion request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptHashes {
+    /// Everything that decides the response.
+    pub exact: String,
+    /// The prompt text only, case- and whitespace-folded.
+    pub normalized: String,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// The hashes of an OpenAI-style completion request, or `None` if it has
+/// neither `messages` nor `prompt`.
+pub fn hash_request(request: &Value) -> Option<PromptHashes> {
+    let mut fields = request.as_object()?.clone();
+    let text = prompt_text(&fields)?;
+    for field in IGNORED_FIELDS {
+        fields.remove(*field);
+    }
+    let model = fields.get("model").and_then(Value::as_str).unwrap_or("");
+    let normalized = format!("{model}\n{}", normalize(&text));
+    // serde_json maps keep their keys sorted, so equal requests serialize
+    // identically whatever order the client sent
+    Some(PromptHashes {
+        exact: sha256_hex(Value::Object(fields).to_string().as_bytes()),
+        normalized: sha256_hex(normalized.as_bytes()),
+    })
+}
+
+/// [`hash_request`] for a raw request body.
+pub fn hash_body(body: &[u8]) -> Option<PromptHashes> {
+    hash_request(&serde_json::from_slice(body).ok()?)
+}
+
+/// The conversation as text: one `role: content` line per message, or the
+/// completion prompt.
+fn prompt_text(fields: &serde_json::Map<String, Value>) -> Option<String> {
+    if let Some(messages) = fields.get("messages").and_then(Value::as_array) {
+        let lines: Vec<String> = messages
+            .iter()
+            .map(|m| {
+                let role = m["role"].as_str().unwrap_or("");
+                format!("{role}: {}", content_text(&m["content"]))
+            })
+            .collect();
+        return Some(lines.join("\n"));
+    }
+    fields.get("prompt").map(content_text)
+}
+
+/// Text of a string, or of the text parts of an array.
+fn content_text(content: &Value) -> String {
+    match content {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter_map(|p| p.as_str().or_else(|| p["text"].as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+#[derive(Debug, Deserialize)]
+struct DedupQuery {
+    period: Option<String>,
+}
+
+/// A hashed request and the seconds since the previous request for the same
+/// model with the same hash (`None` for the first).
+#[derive(Debug, sqlx::FromRow)]
+struct HashedRequest {
+    model_id: String,
+    input_tokens: i64,
+    output_tokens: i64,
+    exact_gap_secs: Option<f64>,
+    norm_gap_secs: Option<f64>,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+struct TtlSavings {
+    ttl_secs: i64,
+    hits: i64,
+    hit_rate: f64,
+    input_tokens_saved: i64,
+    output_tokens_saved: i64,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+struct Repeats {
+    /// Requests that repeated an earlier one in the period.
+    repeats: i64,
+    repeat_rate: f64,
+    distinct: i64,
+    /// Tokens a cache that never expires would have saved.
+    input_tokens_saved: i64,
+    output_tokens_saved: i64,
+    by_ttl: Vec<TtlSavings>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct ModelRepeats {
+    model_id: String,
+    requests: i64,
+    repeats: i64,
+    near_repeats: i64,
+}
+
+fn rate(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+/// Repeat counts and savings for one of the two hashes. A request counts as
+/// a hit for a TTL when the previous identical request came at most that
+/// long before it, i.e. a cache whose entries are refreshed on every hit.
+fn repeats<'a>(requests: impl Iterator<Item = (&'a HashedRequest, Option<f64>)>) -> Repeats {
+    let mut out = Repeats {
+        by_ttl: TTLS
+            .iter()
+            .map(|&ttl_secs| TtlSavings {
+                ttl_secs,
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let mut total = 0;
+    for (req, gap) in requests {
+        total += 1;
+        let Some(gap) = gap else {
+            out.distinct += 1;
+            continue;
+        };
+        out.repeats += 1;
+        out.input_tokens_saved += req.input_tokens;
+        out.output_tokens_saved += req.output_tokens;
+        for ttl in out.by_ttl.iter_mut().filter(|t| gap <= t.ttl_secs as f64) {
+            ttl.hits += 1;
+            ttl.input_tokens_saved += req.input_tokens;
+            ttl.output_tokens_saved += req.output_tokens;
+        }
+    }
+    out.repeat_rate = rate(out.repeats, total);
+    for ttl in &mut out.by_ttl {
+        ttl.hit_rate = rate(ttl.hits, total);
+    }
+    out
+}
+
+fn by_model(requests: &[HashedRequest]) -> Vec<ModelRepeats> {
+    let mut models: Vec<ModelRepeats> = Vec::new();
+    for req in requests {
+        let entry = match models.iter_mut().find(|m| m.model_id == req.model_id) {
+            Some(entry) => entry,
+            None => {
+                models.push(ModelRepeats {
+                    model_id: req.model_id.clone(),
+                    requests: 0,
+                    repeats: 0,
+                    near_repeats: 0,
+                });
+                models.last_mut().expect("just pushed")
+            }
+        };
+        entry.requests += 1;
+        entry.repeats += i64::from(req.exact_gap_secs.is_some());
+        entry.near_repeats += i64::from(req.norm_gap_secs.is_some());
+    }
+    models.sort_by(|a, b| b.repeats.cmp(&a.repeats).then(b.requests.cmp(&a.requests)));
+    models.truncate(TOP_MODELS);
+    models
+}
+
+/// GET /api/admin/usage/dedup — How often the same prompt was sent again,
+/// and what a response cache would have saved.
+async fn dedup_stats(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<DedupQuery>,
+) -> impl IntoResponse {
+    let period = params.period.unwrap_or_else(|| "day".to_string());
+    let interval = common::period_to_interval(&period);
+
+    let requests: Vec<HashedRequest> = match sqlx::query_as(
+        r#"
+        SELECT model_id, input_tokens, output_tokens,
+               (julianday(created_at) - julianday(LAG(created_at) OVER (
+                   PARTITION BY model_id, prompt_hash ORDER BY created_at
+               ))) * 86400 AS exact_gap_secs,
+               (julianday(created_at) - julianday(LAG(created_at) OVER (
+                   PARTITION BY model_id, prompt_norm_hash ORDER BY created_at
+               ))) * 86400 AS norm_gap_secs
+        FROM usage_log
+        WHERE prompt_hash IS NOT NULL AND created_at >= datetime('now', ?)
+        "#,
+    )
+    .bind(interval)
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => return error::internal_error("dedup_stats", e),
+    };
+
+    Json(serde_json::json!({
+        "period": period,
+        "recording": state.config.prompt_dedup_stats,
+        "requests": requests.len(),
+        "exact": repeats(requests.iter().map(|r| (r, r.exact_gap_secs))),
+        "near": repeats(requests.iter().map(|r| (r, r.norm_gap_secs))),
+        "by_model": by_model(&requests),
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn exact_hash_ignores_delivery_fields() {
+        let a = hash_request(&json!({
+            "model": "m",
+            "messages": [{ "role": "user", "content": "Hello" }],
+            "temperature": 0.2,
+        }))
+        .unwrap();
+        let b = hash_request(&json!({
+            "temperature": 0.2,
+            "stream": true,
+            "user": "alice@example.com",
+            "messages": [{ "role": "user", "content": "Hello" }],
+            "model": "m",
+        }))
+        .unwrap();
+        assert_eq!(a, b);
+
+        let hotter = hash_request(&json!({
+            "model": "m",
+            "messages": [{ "role": "user", "content": "Hello" }],
+            "temperature": 0.9,
+        }))
+        .unwrap();
+        assert_ne!(hotter.exact, a.exact);
+        assert_eq!(hotter.normalized, a.normalized);
+    }
+
+    #[test]
+    fn normalized_hash_folds_case_and_whitespace() {
+        let a = hash_request(&json!({
+            "model": "m",
+            "messages": [{ "role": "user", "content": "What is  Rust?\n" }],
+        }))
+        .unwrap();
+        let b = hash_request(&json!({
+            "model": "m",
+            "messages": [{ "role": "user", "content": [{ "type": "text", "text": "what is rust?" }] }],
+        }))
+        .unwrap();
+        assert_ne!(a.exact, b.exact);
+        assert_eq!(a.normalized, b.normalized);
+
+        let completion = hash_request(&json!({ "model": "m", "prompt": "What is Rust?" })).unwrap();
+        assert_ne!(completion.normalized, a.normalized);
+        assert!(hash_request(&json!({ "model": "m", "input": "x" })).is_none());
+        assert!(hash_body(b"not json").is_none());
+    }
+
+    fn req(model_id: &str, gap: Option<f64>) -> HashedRequest {
+        HashedRequest {
+            model_id: model_id.into(),
+            input_tokens: 100,
+            output_tokens: 10,
+            exact_gap_secs: gap,
+            norm_gap_secs: gap,
+        }
+    }
+
+    #[test]
+    fn counts_hits_per_ttl() {
+        let requests = [
+            req("m1", None),
+            req("m1", Some(30.0)),
+            req("m1", Some(600.0)),
+            req("m2", None),
+        ];
+        let stats = repeats(requests.iter().map(|r| (r, r.exact_gap_secs)));
+        assert_eq!(stats.repeats, 2);
+        assert_eq!(stats.distinct, 2);
+        assert_eq!(stats.repeat_rate, 0.5);
+        assert_eq!(stats.input_tokens_saved, 200);
+
+        let hits: Vec<i64> = stats.by_ttl.iter().map(|t| t.hits).collect();
+        assert_eq!(hits, [1, 1, 2, 2]);
+        assert_eq!(stats.by_ttl[0].output_tokens_saved, 10);
+        assert_eq!(stats.by_ttl[0].hit_rate, 0.25);
+
+        let models = by_model(&requests);
+        assert_eq!(models[0].model_id, "m1");
+        assert_eq!(models[0].repeats, 2);
+        assert_eq!(models[1].requests, 1);
+    }
+}
@@ -0,0 +1,510 @@
+//! Chunked prompt uploads.
+//!
+//! Prompts near the request body limit are slow to send and fail as a whole
+//! when the connection drops. Instead a client creates a prompt with
+//! `POST /v1/prompts`, sends it in `PATCH /v1/prompts/{id}` chunks (each
+//! carrying its byte offset in `Upload-Offset`, so a failed chunk can be
+//! resent), marks it complete, and then passes `"prompt_id"` on
+//! `/v1/chat/completions` (appended as a final user message) or
+//! `/v1/completions` (used as `prompt`). Uploads belong to the token's user
+//! and expire an hour after creation.
+
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use serde_json::{json, Value};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::AuthUser;
+use crate::db::Database;
+use crate::AppState;
+
+/// Largest prompt an upload may grow to.
+const MAX_PROMPT_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Unexpired uploads one user may hold at once.
+const MAX_OPEN_UPLOADS: i64 = 16;
+
+/// How long an upload can be used after it is created.
+const UPLOAD_TTL: &str = "+60 minutes";
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/prompts", post(create_upload))
+        .route(
+            "/prompts/{id}",
+            get(get_upload).patch(append_chunk).delete(delete_upload),
+        )
+        .route("/prompts/{id}/complete", post(complete_upload))
+        .with_state(state)
+}
+
+fn upload_error(status: StatusCode, code: &str, message: String) -> Response {
+    let error_type = if status.is_server_error() {
+        "server_error"
+    } else {
+        "invalid_request_error"
+    };
+    (
+        status,
+        Json(json!({
+            "error": {
+                "message": message,
+                "type": error_type,
+                "code": code
+            }
+        })),
+    )
+        .into_response()
+}
+
+fn internal(context: &str, e: impl std::fmt::Display) -> Response {
+    error!(error = %e, "{context}");
+    upload_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "internal_error",
+        "Internal server error".to_string(),
+    )
+}
+
+fn not_found(id: &str) -> Response {
+    upload_error(
+        StatusCode::NOT_FOUND,
+        "prompt_not_found",
+        format!("Prompt '{id}' not found"),
+    )
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Upload {
+    id: String,
+    size_bytes: i64,
+    completed_at: Option<String>,
+    created_at: String,
+    expires_at: String,
+}
+
+fn upload_json(u: &Upload) -> Value {
+    json!({
+        "id": u.id,
+        "object": "prompt",
+        "bytes": u.size_bytes,
+        "complete": u.completed_at.is_some(),
+        "created_at": u.created_at,
+        "expires_at": u.expires_at,
+    })
+}
+
+/// The caller's unexpired upload `id`, if any.
+async fn owned_upload(
+    state: &AppState,
+    auth_user: &AuthUser,
+    id: &str,
+) -> Result<Upload, Response> {
+    match sqlx::query_as::<_, Upload>(
+        "SELECT id, size_bytes, completed_at, created_at, expires_at FROM prompt_uploads \
+         WHERE id = ? AND user_id = ? AND expires_at > datetime('now')",
+    )
+    .bind(id)
+    .bind(&auth_user.user_id)
+    .fetch_optional(&state.db.pool)
+    .await
+    {
+        Ok(Some(u)) => Ok(u),
+        Ok(None) => Err(not_found(id)),
+        Err(e) => Err(internal("Failed to load prompt upload", e)),
+    }
+}
+
+/// The uploaded bytes, in order.
+async fn upload_bytes(db: &Database, id: &str) -> sqlx::Result<Vec<u8>> {
+    let chunks: Vec<Vec<u8>> = sqlx::query_scalar(
+        "SELECT data FROM prompt_upload_chunks WHERE upload_id = ? ORDER BY start_byte",
+    )
+    .bind(id)
+    .fetch_all(&db.pool)
+    .await?;
+    Ok(chunks.concat())
+}
+
+/// POST /v1/prompts — Start an upload.
+async fn create_upload(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let open: i64 = match sqlx::query_scalar(
+        "SELECT COUNT(*) FROM prompt_uploads WHERE user_id = ? AND expires_at > datetime('now')",
+    )
+    .bind(&auth_user.user_id)
+    .fetch_one(&state.db.pool)
+    .await
+    {
+        Ok(n) => n,
+        Err(e) => return internal("Failed to count prompt uploads", e),
+    };
+    if open >= MAX_OPEN_UPLOADS {
+        return upload_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            "too_many_prompt_uploads",
+            format!("At most {MAX_OPEN_UPLOADS} prompt uploads can be open at once"),
+        );
+    }
+
+    let id = format!("prompt-{}", Uuid::new_v4());
+    if let Err(e) = sqlx::query(
+        "INSERT INTO prompt_uploads (id, user_id, expires_at) VALUES (?, ?, datetime('now', ?))",
+    )
+    .bind(&id)
+    .bind(&auth_user.user_id)
+    .bind(UPLOAD_TTL)
+    .execute(&state.db.pool)
+    .await
+    {
+        return internal("Failed to create prompt upload", e);
+    }
+    match owned_upload(&state, &auth_user, &id).await {
+        Ok(u) => (StatusCode::CREATED, Json(upload_json(&u))).into_response(),
+        Err(r) => r,
+    }
+}
+
+/// GET /v1/prompts/{id} — Upload progress.
+async fn get_upload(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match owned_upload(&state, &auth_user, &id).await {
+        Ok(u) => Json(upload_json(&u)).into_response(),
+        Err(r) => r,
+    }
+}
+
+/// PATCH /v1/prompts/{id} — Append the body at `Upload-Offset`, which must
+/// equal the bytes received so far.
+async fn append_chunk(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> impl IntoResponse {
+    let Some(offset) = headers
+        .get("upload-offset")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+    else {
+        return upload_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_upload_offset",
+            "Upload-Offset header with the chunk's byte offset is required".to_string(),
+        );
+    };
+    if body.is_empty() {
+        return upload_error(
+            StatusCode::BAD_REQUEST,
+            "empty_chunk",
+            "Chunk body must not be empty".to_string(),
+        );
+    }
+    let upload = match owned_upload(&state, &auth_user, &id).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    if upload.completed_at.is_some() {
+        return upload_error(
+            StatusCode::CONFLICT,
+            "prompt_complete",
+            "Prompt upload is already complete".to_string(),
+        );
+    }
+    if offset.saturating_add(body.len() as u64) > MAX_PROMPT_BYTES {
+        return upload_error(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "prompt_too_large",
+            format!("Prompts are limited to {MAX_PROMPT_BYTES} bytes"),
+        );
+    }
+
+    // The size check in the UPDATE makes concurrent appends at the same
+    // offset safe: only one advances the upload.
+    let appended: sqlx::Result<bool> = async {
+        let mut tx = state.db.pool.begin().await?;
+        let moved = sqlx::query(
+            "UPDATE prompt_uploads SET size_bytes = size_bytes + ? \
+             WHERE id = ? AND size_bytes = ? AND completed_at IS NULL",
+        )
+        .bind(body.len() as i64)
+        .bind(&id)
+        .bind(offset as i64)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if moved == 0 {
+            return Ok(false);
+        }
+        sqlx::query(
+            "INSERT INTO prompt_upload_chunks (upload_id, start_byte, data) VALUES (?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(offset as i64)
+        .bind(body.as_ref())
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+    .await;
+    match appended {
+        Ok(true) => {}
+        Ok(false) => {
+            return upload_error(
+                StatusCode::CONFLICT,
+                "upload_offset_mismatch",
+                format!(
+                    "Upload-Offset {offset} does not match the {} bytes received",
+                    upload.size_bytes
+                ),
+            );
+        }
+        Err(e) => return internal("Failed to store prompt chunk", e),
+    }
+    match owned_upload(&state, &auth_user, &id).await {
+        Ok(u) => Json(upload_json(&u)).into_response(),
+        Err(r) => r,
+    }
+}
+
+/// POST /v1/prompts/{id}/complete — Finish the upload. The prompt must be
+/// valid UTF-8.
+async fn complete_upload(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let upload = match owned_upload(&state, &auth_user, &id).await {
+        Ok(u) => u,
+        Err(r) => return r,
+    };
+    if upload.completed_at.is_none() {
+        let bytes = match upload_bytes(&state.db, &id).await {
+            Ok(b) => b,
+            Err(e) => return internal("Failed to load prompt chunks", e),
+        };
+        if bytes.is_empty() || std::str::from_utf8(&bytes).is_err() {
+            return upload_error(
+                StatusCode::BAD_REQUEST,
+                "invalid_prompt",
+                "Uploaded prompt must be non-empty UTF-8 text".to_string(),
+            );
+        }
+        if let Err(e) = sqlx::query(
+            "UPDATE prompt_uploads SET completed_at = datetime('now') \
+             WHERE id = ? AND completed_at IS NULL",
+        )
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+        {
+            return internal("Failed to complete prompt upload", e);
+        }
+        info!(user_id = %auth_user.user_id, prompt_id = %id, bytes = bytes.len(), "Prompt upload completed");
+    }
+    match owned_upload(&state, &auth_user, &id).await {
+        Ok(u) => Json(upload_json(&u)).into_response(),
+        Err(r) => r,
+    }
+}
+
+/// DELETE /v1/prompts/{id} — Discard an upload.
+async fn delete_upload(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match sqlx::query("DELETE FROM prompt_uploads WHERE id = ? AND user_id = ?")
+        .bind(&id)
+        .bind(&auth_user.user_id)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => not_found(&id),
+        Ok(_) => {
+            Json(json!({ "id": id, "object": "prompt.deleted", "deleted": true })).into_response()
+        }
+        Err(e) => internal("Failed to delete prompt upload", e),
+    }
+}
+
+/// Put `text` into a completion request: a trailing user message for chat
+/// (`chat = true`), or `prompt` for text completions.
+fn splice(
+    obj: &mut serde_json::Map<String, Value>,
+    text: String,
+    chat: bool,
+) -> Result<(), String> {
+    if chat {
+        let messages = obj
+            .entry("messages")
+            .or_insert_with(|| Value::Array(Vec::new()));
+        let Some(messages) = messages.as_array_mut() else {
+            return Err("messages must be an array".to_string());
+        };
+        messages.push(json!({ "role": "user", "content": text }));
+    } else {
+        if obj.contains_key("prompt") {
+            return Err("prompt and prompt_id cannot both be set".to_string());
+        }
+        obj.insert("prompt".to_string(), Value::String(text));
+    }
+    Ok(())
+}
+
+/// Replace a `prompt_id` in a completion request body with the uploaded
+/// prompt. Bodies without one are returned unchanged.
+pub async fn expand(
+    state: &AppState,
+    auth_user: &AuthUser,
+    body: Bytes,
+    chat: bool,
+) -> Result<Bytes, Response> {
+    const KEY: &[u8] = b"\"prompt_id\"";
+    if !body.windows(KEY.len()).any(|w| w == KEY) {
+        return Ok(body);
+    }
+    let Ok(mut value) = serde_json::from_slice::<Value>(&body) else {
+        return Ok(body);
+    };
+    let Some(obj) = value.as_object_mut() else {
+        return Ok(body);
+    };
+    let Some(prompt_id) = obj.remove("prompt_id") else {
+        return Ok(body);
+    };
+    let Some(prompt_id) = prompt_id.as_str() else {
+        return Err(upload_error(
+            StatusCode::BAD_REQUEST,
+            "invalid_prompt_id",
+            "prompt_id must be a string".to_string(),
+        ));
+    };
+
+    let upload = owned_upload(state, auth_user, prompt_id).await?;
+    if upload.completed_at.is_none() {
+        return Err(upload_error(
+            StatusCode::BAD_REQUEST,
+            "prompt_incomplete",
+            format!("Prompt '{prompt_id}' has not been completed"),
+        ));
+    }
+    let bytes = upload_bytes(&state.db, prompt_id)
+        .await
+        .map_err(|e| internal("Failed to load prompt chunks", e))?;
+    let text = String::from_utf8(bytes).map_err(|e| internal("Stored prompt is not UTF-8", e))?;
+
+    splice(obj, text, chat)
+        .map_err(|message| upload_error(StatusCode::BAD_REQUEST, "invalid_prompt_id", message))?;
+    serde_json::to_vec(&value)
+        .map(Bytes::from)
+        .map_err(|e| internal("Failed to encode request", e))
+}
+
+/// Delete expired uploads. Returns how many were removed.
+pub async fn prune(db: &Database) -> sqlx::Result<u64> {
+    sqlx::query("DELETE FROM prompt_uploads WHERE expires_at < datetime('now')")
+        .execute(&db.pool)
+        .await
+        .map(|r| r.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(value: Value) -> serde_json::Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn chat_gets_trailing_user_message() {
+        let mut obj = object(json!({
+            "model": "m",
+            "messages": [{ "role": "system", "content": "Summarise." }],
+        }));
+        splice(&mut obj, "long text".to_string(), true).unwrap();
+        assert_eq!(
+            obj["messages"],
+            json!([
+                { "role": "system", "content": "Summarise." },
+                { "role": "user", "content": "long text" },
+            ])
+        );
+
+        let mut obj = object(json!({ "model": "m" }));
+        splice(&mut obj, "only".to_string(), true).unwrap();
+        assert_eq!(
+            obj["messages"],
+            json!([{ "role": "user", "content": "only" }])
+        );
+    }
+
+    #[test]
+    fn completions_get_prompt() {
+        let mut obj = object(json!({ "model": "m" }));
+        splice(&mut obj, "long text".to_string(), false).unwrap();
+        assert_eq!(obj["prompt"], "long text");
+
+        let mut obj = object(json!({ "model": "m", "prompt": "x" }));
+        assert!(splice(&mut obj, "long text".to_string(), false).is_err());
+    }
+
+    #[test]
+    fn non_array_messages_rejected() {
+        let mut obj = object(json!({ "messages": "hi" }));
+        assert!(splice(&mut obj, "long text".to_string(), true).is_err());
+    }
+
+    #[tokio::test]
+    async fn prune_removes_expired_uploads_and_chunks() {
+        let db = Database::test_db().await;
+        sqlx::query(
+            "INSERT INTO idp_configs (id, name, issuer, client_id, client_secret_enc) \
+             VALUES ('idp', 'test', 'https://test', 'client', 'secret')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO users (id, idp_id, subject) VALUES ('u1', 'idp', 'u1')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        for (id, expires) in [("old", "-1 minutes"), ("new", "+60 minutes")] {
+            sqlx::query(
+                "INSERT INTO prompt_uploads (id, user_id, size_bytes, expires_at) \
+                 VALUES (?, 'u1', 2, datetime('now', ?))",
+            )
+            .bind(id)
+            .bind(expires)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO prompt_upload_chunks (upload_id, start_byte, data) VALUES (?, 0, X'6869')",
+            )
+            .bind(id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(prune(&db).await.unwrap(), 1);
+        assert!(upload_bytes(&db, "old").await.unwrap().is_empty());
+        assert_eq!(upload_bytes(&db, "new").await.unwrap(), b"hi");
+    }
+}
@@ -0,0 +1,713 @@
+//! Stored completion requests and admin replay.
+//!
+//! Each model category has a logging level: `none`, `metadata` (who asked
+//! which model, status, latency and token counts) or `full`, which also keeps
+//! the body the client sent and, for non-streaming calls, the backend's
+//! response. Categories without a level, and uncategorized models, follow
+//! `REQUEST_LOG`. Admins can search and purge the log, and re-run a fully
+//! logged request against the same or a different model to compare outputs
+//! after a model or config change. Entries older than
+//! `REQUEST_LOG_RETENTION_DAYS` are deleted by the hourly cleanup.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, post};
+use axum::{Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::time::Instant;
+use tracing::info;
+use uuid::Uuid;
+
+use super::common::DryRunQuery;
+use super::error;
+use super::openai;
+use crate::auth::{AuthUser, SessionAuth};
+use crate::db::Database;
+use crate::AppState;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/requests", get(list_requests).delete(purge_requests))
+        .route("/requests/{id}", get(get_request))
+        .route("/requests/{id}/replay", post(replay_request))
+        .with_state(state)
+}
+
+/// How much of a request is logged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    None,
+    Metadata,
+    Full,
+}
+
+impl LogLevel {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "none" => Some(LogLevel::None),
+            "metadata" => Some(LogLevel::Metadata),
+            "full" => Some(LogLevel::Full),
+            _ => None,
+        }
+    }
+
+    /// The level for categories without their own: `REQUEST_LOG` on or off.
+    pub fn default_for(request_log: bool) -> Self {
+        if request_log {
+            LogLevel::Full
+        } else {
+            LogLevel::None
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::None => "none",
+            LogLevel::Metadata => "metadata",
+            LogLevel::Full => "full",
+        }
+    }
+}
+
+/// The logging level for a request to a model in `category_id`: the
+/// category's own level, else `full` with `REQUEST_LOG` and `none` without.
+pub async fn level_for(
+    pool: &sqlx::SqlitePool,
+    request_log: bool,
+    category_id: Option<&str>,
+) -> Result<LogLevel> {
+    let default = LogLevel::default_for(request_log);
+    let Some(category_id) = category_id else {
+        return Ok(default);
+    };
+    let level: Option<Option<String>> =
+        sqlx::query_scalar("SELECT log_level FROM model_categories WHERE id = ?")
+            .bind(category_id)
+            .fetch_optional(pool)
+            .await
+            .context("Failed to look up category log level")?;
+    Ok(level
+        .flatten()
+        .as_deref()
+        .and_then(LogLevel::parse)
+        .unwrap_or(default))
+}
+
+/// Delete entries older than `days`.
+pub async fn prune(db: &Database, days: i64) -> Result<u64> {
+    let result = sqlx::query("DELETE FROM request_log WHERE created_at < datetime('now', ?)")
+        .bind(format!("-{days} days"))
+        .execute(&db.pool)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+/// A completion request to be stored in `request_log`. With
+/// [`LogLevel::Metadata`] the bodies are dropped.
+pub struct LoggedRequest<'a> {
+    pub log_level: LogLevel,
+    pub user_id: &'a str,
+    pub token_id: Option<&'a str>,
+    pub model_id: &'a str,
+    pub category_id: Option<&'a str>,
+    /// Proxy path the request came in on, e.g. `/v1/chat/completions`.
+    pub endpoint: &'a str,
+    pub request_body: &'a [u8],
+    pub response_status: u16,
+    /// `None` for streamed responses, which are not captured.
+    pub response_body: Option<&'a [u8]>,
+    pub latency_ms: i64,
+    pub replay_of: Option<&'a str>,
+    pub seed: Option<i64>,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    /// Captured as a model trace sample (see `model_trace`).
+    pub traced: bool,
+}
+
+/// Store a completion request. Returns the new `request_log` ID.
+pub async fn record(db: &Database, entry: &LoggedRequest<'_>) -> Result<String> {
+    let id = Uuid::new_v4().to_string();
+    let full = entry.log_level == LogLevel::Full;
+    let request_body = if full {
+        String::from_utf8_lossy(entry.request_body).into_owned()
+    } else {
+        String::new()
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO request_log (id, user_id, token_id, model_id, category_id, endpoint,
+                                 request_body, response_status, response_body, latency_ms,
+                                 replay_of, seed, log_level, input_tokens, output_tokens,
+                                 traced)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(&id)
+    .bind(entry.user_id)
+    .bind(entry.token_id)
+    .bind(entry.model_id)
+    .bind(entry.category_id)
+    .bind(entry.endpoint)
+    .bind(request_body)
+    .bind(entry.response_status as i64)
+    .bind(
+        entry
+            .response_body
+            .filter(|_| full)
+            .map(|b| String::from_utf8_lossy(b).into_owned()),
+    )
+    .bind(entry.latency_ms)
+    .bind(entry.replay_of)
+    .bind(entry.seed)
+    .bind(entry.log_level.as_str())
+    .bind(entry.input_tokens)
+    .bind(entry.output_tokens)
+    .bind(entry.traced)
+    .execute(&db.pool)
+    .await
+    .context("Failed to insert request log entry")?;
+
+    Ok(id)
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct StoredRequest {
+    model_id: String,
+    endpoint: String,
+    request_body: String,
+    response_status: i64,
+    response_body: Option<String>,
+    latency_ms: i64,
+    log_level: String,
+}
+
+/// A log entry as listed, without bodies.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct LogEntry {
+    id: String,
+    user_id: String,
+    token_id: Option<String>,
+    model_id: String,
+    category_id: Option<String>,
+    endpoint: String,
+    response_status: i64,
+    latency_ms: i64,
+    input_tokens: i64,
+    output_tokens: i64,
+    log_level: String,
+    traced: bool,
+    replay_of: Option<String>,
+    seed: Option<i64>,
+    created_at: String,
+}
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+const ENTRY_COLUMNS: &str = "id, user_id, token_id, model_id, category_id, endpoint, \
+     response_status, latency_ms, input_tokens, output_tokens, log_level, traced, replay_of, \
+     seed, created_at";
+
+/// Filters shared by listing and purging. Times are compared with
+/// `created_at`, so use its `YYYY-MM-DD HH:MM:SS` form or a plain date.
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct LogFilter {
+    user_id: Option<String>,
+    model_id: Option<String>,
+    category_id: Option<String>,
+    since: Option<String>,
+    before: Option<String>,
+    /// Listing only.
+    #[serde(skip_serializing)]
+    limit: Option<i64>,
+}
+
+const FILTER_SQL: &str = "(?1 IS NULL OR user_id = ?1) \
+     AND (?2 IS NULL OR model_id = ?2) \
+     AND (?3 IS NULL OR category_id = ?3) \
+     AND (?4 IS NULL OR created_at >= ?4) \
+     AND (?5 IS NULL OR created_at < ?5)";
+
+/// GET /api/admin/requests -- Search the request log, newest first. Bodies
+/// are left out; fetch an entry to see them.
+async fn list_requests(
+    State(state): State<Arc<AppState>>,
+    Query(f): Query<LogFilter>,
+) -> impl IntoResponse {
+    let limit = f.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    match sqlx::query_as::<_, LogEntry>(&format!(
+        "SELECT {ENTRY_COLUMNS} FROM request_log WHERE {FILTER_SQL} \
+         ORDER BY created_at DESC LIMIT ?6"
+    ))
+    .bind(&f.user_id)
+    .bind(&f.model_id)
+    .bind(&f.category_id)
+    .bind(&f.since)
+    .bind(&f.before)
+    .bind(limit)
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(entries) => Json(json!({ "entries": entries })).into_response(),
+        Err(e) => error::internal_error("list_requests", e),
+    }
+}
+
+/// GET /api/admin/requests/{id} -- One log entry with its bodies. Reading
+/// captured prompts is audit-logged.
+async fn get_request(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let entry: Option<LogEntry> = match sqlx::query_as(&format!(
+        "SELECT {ENTRY_COLUMNS} FROM request_log WHERE id = ?"
+    ))
+    .bind(&id)
+    .fetch_optional(&state.db.pool)
+    .await
+    {
+        Ok(entry) => entry,
+        Err(e) => return error::internal_error("get_request", e),
+    };
+    let Some(entry) = entry else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Logged request not found"})),
+        )
+            .into_response();
+    };
+    let bodies: (String, Option<String>) =
+        match sqlx::query_as("SELECT request_body, response_body FROM request_log WHERE id = ?")
+            .bind(&id)
+            .fetch_one(&state.db.pool)
+            .await
+        {
+            Ok(bodies) => bodies,
+            Err(e) => return error::internal_error("get_request", e),
+        };
+
+    if entry.log_level == LogLevel::Full.as_str() {
+        info!(target: "audit", action = "request_log.view", actor = %session.user_id, resource = %id, user = %entry.user_id, "Admin viewed logged request");
+    }
+    let parse = |body: &str| serde_json::from_str::<Value>(body).ok();
+    let request_body = Some(bodies.0.as_str())
+        .filter(|b| !b.is_empty())
+        .and_then(parse);
+    let response_body = bodies.1.as_deref().and_then(parse);
+    Json(json!({
+        "entry": entry,
+        "request_body": request_body,
+        "response_body": response_body,
+    }))
+    .into_response()
+}
+
+/// DELETE /api/admin/requests -- Purge log entries matching the filters.
+/// At least one filter is required; `before` alone purges by age.
+/// `?dry_run=true` counts the entries that would go without deleting them.
+async fn purge_requests(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Query(filter): Query<LogFilter>,
+    Query(dry): Query<DryRunQuery>,
+) -> impl IntoResponse {
+    if filter.user_id.is_none()
+        && filter.model_id.is_none()
+        && filter.category_id.is_none()
+        && filter.since.is_none()
+        && filter.before.is_none()
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "At least one filter is required"})),
+        )
+            .into_response();
+    }
+    if dry.dry_run {
+        return match sqlx::query_scalar::<_, i64>(&format!(
+            "SELECT COUNT(*) FROM request_log WHERE {FILTER_SQL}"
+        ))
+        .bind(&filter.user_id)
+        .bind(&filter.model_id)
+        .bind(&filter.category_id)
+        .bind(&filter.since)
+        .bind(&filter.before)
+        .fetch_one(&state.db.pool)
+        .await
+        {
+            Ok(count) => Json(json!({ "dry_run": true, "deleted": count })).into_response(),
+            Err(e) => error::internal_error("purge_requests:dry_run", e),
+        };
+    }
+    match sqlx::query(&format!("DELETE FROM request_log WHERE {FILTER_SQL}"))
+        .bind(&filter.user_id)
+        .bind(&filter.model_id)
+        .bind(&filter.category_id)
+        .bind(&filter.since)
+        .bind(&filter.before)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(result) => {
+            let filter_json = serde_json::to_string(&filter).unwrap_or_default();
+            info!(target: "audit", action = "request_log.purge", actor = %session.user_id, filter = %filter_json, deleted = result.rows_affected(), "Admin purged request log");
+            Json(json!({ "deleted": result.rows_affected() })).into_response()
+        }
+        Err(e) => error::internal_error("purge_requests", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReplayQuery {
+    /// Model name to replay against; defaults to the model that served the
+    /// original request.
+    model: Option<String>,
+}
+
+/// The parts of a completion response worth comparing side by side.
+fn summarize(status: u16, latency_ms: i64, body: Option<&Value>) -> Value {
+    let choice = body.and_then(|b| b.pointer("/choices/0"));
+    let content = choice.and_then(|c| {
+        c.pointer("/message/content")
+            .or_else(|| c.get("text"))
+            .and_then(Value::as_str)
+    });
+    json!({
+        "model": body.and_then(|b| b.get("model")),
+        "status": status,
+        "latency_ms": latency_ms,
+        "content": content,
+        "finish_reason": choice.and_then(|c| c.get("finish_reason")),
+        "usage": body.and_then(|b| b.get("usage")),
+        "response": body,
+    })
+}
+
+/// POST /api/admin/requests/{id}/replay?model= -- Re-run a logged completion
+/// request and return the original and replayed results side by side.
+///
+/// The replay is always non-streaming. Its usage is attributed to the admin,
+/// carries no token, and is marked with `replay_of`.
+async fn replay_request(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Query(query): Query<ReplayQuery>,
+) -> impl IntoResponse {
+    let stored: Option<StoredRequest> = match sqlx::query_as(
+        "SELECT model_id, endpoint, request_body, response_status, response_body, latency_ms,
+                log_level
+         FROM request_log WHERE id = ?",
+    )
+    .bind(&id)
+    .fetch_optional(&state.db.pool)
+    .await
+    {
+        Ok(r) => r,
+        Err(e) => return error::internal_error("replay_request", e),
+    };
+    let Some(stored) = stored else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Logged request not found"})),
+        )
+            .into_response();
+    };
+
+    if stored.log_level != LogLevel::Full.as_str() {
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(
+                json!({"error": "Only metadata was logged for this request; it can't be replayed"}),
+            ),
+        )
+            .into_response();
+    }
+
+    let mut body: Value = match serde_json::from_str(&stored.request_body) {
+        Ok(Value::Object(obj)) => Value::Object(obj),
+        _ => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({"error": "Logged request body is not a JSON object"})),
+            )
+                .into_response();
+        }
+    };
+    // Replays are compared as whole responses, never streamed
+    body["stream"] = Value::Bool(false);
+    if let Some(obj) = body.as_object_mut() {
+        obj.remove("stream_options");
+    }
+    // Without an explicit target, pin the replay to the original model
+    let (model_name, specific_model_id) = match &query.model {
+        Some(m) => {
+            body["model"] = Value::String(m.clone());
+            (m.clone(), None)
+        }
+        None => (
+            body.get("model")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            Some(stored.model_id.clone()),
+        ),
+    };
+    let max_tokens = body
+        .get("max_completion_tokens")
+        .or_else(|| body.get("max_tokens"))
+        .and_then(Value::as_u64)
+        .map(|n| n as u32);
+
+    let seed = body.get("seed").and_then(Value::as_i64);
+
+    let auth_user = AuthUser {
+        user_id: session.user_id.clone(),
+        token_id: String::new(),
+        category_id: None,
+        specific_model_id,
+        is_admin: true,
+        is_internal: false,
+        via_jwt: false,
+    };
+
+    info!(target: "audit", action = "request.replay", actor = %session.user_id, resource = %id, model = %model_name, "Admin replayed logged request");
+
+    let start = Instant::now();
+    let response = openai::proxy_completion(
+        state,
+        auth_user,
+        Bytes::from(body.to_string()),
+        &model_name,
+        false,
+        &stored.endpoint,
+        None,
+        max_tokens,
+        seed,
+        false,
+        Some(&id),
+    )
+    .await;
+    let replay_status = response.status().as_u16();
+    let replay_bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap_or_default();
+    let latency_ms = start.elapsed().as_millis() as i64;
+
+    let original_body: Option<Value> = stored
+        .response_body
+        .as_deref()
+        .and_then(|b| serde_json::from_str(b).ok());
+    let replay_body: Option<Value> = serde_json::from_slice(&replay_bytes).ok();
+
+    let original = summarize(
+        stored.response_status as u16,
+        stored.latency_ms,
+        original_body.as_ref(),
+    );
+    let replay = summarize(replay_status, latency_ms, replay_body.as_ref());
+    let identical = original_body.is_some()
+        && original["status"] == replay["status"]
+        && original["content"] == replay["content"];
+
+    Json(json!({
+        "request_id": id,
+        "endpoint": stored.endpoint,
+        "original": original,
+        "replay": replay,
+        "identical": identical,
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarize_chat_response() {
+        let body = json!({
+            "model": "m",
+            "choices": [{"message": {"role": "assistant", "content": "hi"}, "finish_reason": "stop"}],
+            "usage": {"prompt_tokens": 3, "completion_tokens": 1}
+        });
+        let s = summarize(200, 12, Some(&body));
+        assert_eq!(s["content"], "hi");
+        assert_eq!(s["finish_reason"], "stop");
+        assert_eq!(s["usage"]["completion_tokens"], 1);
+        assert_eq!(s["latency_ms"], 12);
+    }
+
+    #[test]
+    fn summarize_text_completion_and_missing_body() {
+        let body = json!({"choices": [{"text": "abc", "finish_reason": "length"}]});
+        assert_eq!(summarize(200, 0, Some(&body))["content"], "abc");
+
+        let s = summarize(200, 0, None);
+        assert!(s["content"].is_null());
+        assert!(s["response"].is_null());
+    }
+
+    #[tokio::test]
+    async fn record_stores_request_and_response() {
+        let db = Database::test_db().await;
+        let id = record(
+            &db,
+            &LoggedRequest {
+                user_id: "u1",
+                token_id: Some("t1"),
+                model_id: "m1",
+                category_id: None,
+                endpoint: "/v1/chat/completions",
+                request_body: br#"{"model":"m","messages":[]}"#,
+                response_status: 200,
+                response_body: None,
+                latency_ms: 42,
+                replay_of: None,
+                seed: Some(7),
+                log_level: LogLevel::Full,
+                input_tokens: 3,
+                output_tokens: 0,
+                traced: false,
+            },
+        )
+        .await
+        .unwrap();
+
+        let row: StoredRequest = sqlx::query_as(
+            "SELECT model_id, endpoint, request_body, response_status, response_body, latency_ms,
+                    log_level
+             FROM request_log WHERE id = ?",
+        )
+        .bind(&id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(row.model_id, "m1");
+        assert_eq!(row.endpoint, "/v1/chat/completions");
+        assert_eq!(row.response_status, 200);
+        assert!(row.response_body.is_none());
+        assert_eq!(row.latency_ms, 42);
+    }
+
+    #[tokio::test]
+    async fn metadata_level_drops_bodies() {
+        let db = Database::test_db().await;
+        let id = record(
+            &db,
+            &LoggedRequest {
+                user_id: "u1",
+                token_id: None,
+                model_id: "m1",
+                category_id: Some("c1"),
+                endpoint: "/v1/chat/completions",
+                request_body: br#"{"messages":[{"role":"user","content":"secret"}]}"#,
+                response_status: 200,
+                response_body: Some(br#"{"choices":[]}"#),
+                latency_ms: 5,
+                replay_of: None,
+                seed: None,
+                log_level: LogLevel::Metadata,
+                input_tokens: 12,
+                output_tokens: 4,
+                traced: false,
+            },
+        )
+        .await
+        .unwrap();
+
+        let (request_body, response_body, log_level, output_tokens): (
+            String,
+            Option<String>,
+            String,
+            i64,
+        ) = sqlx::query_as(
+            "SELECT request_body, response_body, log_level, output_tokens FROM request_log WHERE id = ?",
+        )
+        .bind(&id)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(request_body, "");
+        assert!(response_body.is_none());
+        assert_eq!(log_level, "metadata");
+        assert_eq!(output_tokens, 4);
+    }
+
+    #[tokio::test]
+    async fn purge_dry_run_counts_without_deleting() {
+        let harness = crate::test_harness::Harness::new().await;
+        let db = &harness.state.db;
+        for user_id in ["u1", "u1", "u2"] {
+            record(
+                db,
+                &LoggedRequest {
+                    user_id,
+                    token_id: None,
+                    model_id: "m1",
+                    category_id: None,
+                    endpoint: "/v1/chat/completions",
+                    request_body: b"{}",
+                    response_status: 200,
+                    response_body: None,
+                    latency_ms: 1,
+                    replay_of: None,
+                    seed: None,
+                    log_level: LogLevel::Metadata,
+                    input_tokens: 0,
+                    output_tokens: 0,
+                    traced: false,
+                },
+            )
+            .await
+            .unwrap();
+        }
+        let admin = harness.admin("admin1").await;
+        let count = move || async move {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM request_log")
+                .fetch_one(&db.pool)
+                .await
+                .unwrap()
+        };
+
+        let (status, body) = admin
+            .delete("/api/admin/requests?user_id=u1&dry_run=true")
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "dry_run": true, "deleted": 2 }));
+        assert_eq!(count().await, 3);
+
+        let (status, body) = admin.delete("/api/admin/requests?user_id=u1").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["deleted"], 2);
+        assert_eq!(count().await, 1);
+    }
+
+    #[tokio::test]
+    async fn category_level_overrides_global_default() {
+        let db = Database::test_db().await;
+        sqlx::query(
+            "INSERT INTO model_categories (id, name, description, log_level) VALUES \
+             ('quiet', 'quiet', '', 'none'), ('plain', 'plain', '', NULL)",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        let level = |request_log, category| level_for(&db.pool, request_log, category);
+        assert_eq!(level(true, Some("quiet")).await.unwrap(), LogLevel::None);
+        assert_eq!(level(true, Some("plain")).await.unwrap(), LogLevel::Full);
+        assert_eq!(level(false, Some("plain")).await.unwrap(), LogLevel::None);
+        assert_eq!(level(false, None).await.unwrap(), LogLevel::None);
+        assert_eq!(level(true, Some("missing")).await.unwrap(), LogLevel::Full);
+    }
+}
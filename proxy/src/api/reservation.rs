@@ -1,10 +1,10 @@
 use std::sync::Arc;
 
-use axum::extract::{Path, State};
+use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::IntoResponse;
 use axum::routing::{delete, get, post};
-use axum::{Extension, Json, Router};
+use axum::{middleware, Extension, Json, Router};
 use chrono::{NaiveDateTime, Utc};
 use serde::Deserialize;
 use tracing::{error, info};
@@ -12,8 +12,12 @@ use uuid::Uuid;
 
 use super::common;
 use super::error;
+use super::reservation_rules;
+use crate::auth::rbac::{self, Permission};
 use crate::auth::SessionAuth;
+use crate::events::AppEvent;
 use crate::scheduler::reservation::{ActiveReservation, Reservation, ReservationWithUser};
+use crate::scheduler::settings::FairnessSettings;
 use crate::AppState;
 
 // ---------------------------------------------------------------------------
@@ -38,11 +42,16 @@ pub fn user_routes(state: Arc<AppState>) -> Router {
 pub fn admin_routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/reservations", get(admin_list))
+        .route("/reservations/heatmap", get(heatmap))
         .route("/reservations/{id}/approve", post(approve))
         .route("/reservations/{id}/reject", post(reject))
         .route("/reservations/{id}/activate", post(force_activate))
         .route("/reservations/{id}/deactivate", post(force_deactivate))
         .route("/reservations/{id}", delete(admin_delete))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Reservations,
+            rbac::require_permission,
+        ))
         .with_state(state)
 }
 
@@ -55,6 +64,9 @@ struct CreateReservationRequest {
     start_time: String,
     end_time: String,
     reason: Option<String>,
+    /// Join the waitlist instead of failing when the slot is taken.
+    #[serde(default)]
+    waitlist: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -68,6 +80,7 @@ struct ContainerRequest {
     backend_type: Option<String>,
     gpu_type: Option<String>,
     gpu_layers: Option<u32>,
+    context_size: Option<u32>,
     parallel: Option<u32>,
 }
 
@@ -75,15 +88,201 @@ struct ContainerRequest {
 // Validation Helpers
 // ---------------------------------------------------------------------------
 
+const ISO_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
 fn parse_iso_time(s: &str) -> Option<NaiveDateTime> {
-    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").ok()
+    NaiveDateTime::parse_from_str(s, ISO_FORMAT).ok()
+}
+
+use chrono::{Datelike, Timelike};
+
+// ---------------------------------------------------------------------------
+// Conflict Suggestions
+// ---------------------------------------------------------------------------
+
+/// Free slots offered with a 409 on reservation create.
+const SUGGESTION_COUNT: usize = 3;
+/// How far either side of the requested start to look for free slots.
+const SUGGESTION_HORIZON_DAYS: i64 = 14;
+
+/// First `slot_minutes` boundary (counted from midnight) strictly after `now`.
+fn next_boundary(now: NaiveDateTime, slot_minutes: u32) -> NaiveDateTime {
+    let slot = slot_minutes.max(1);
+    let minute_of_day = now.hour() * 60 + now.minute();
+    let floored = now
+        .date()
+        .and_hms_opt(0, 0, 0)
+        .map(|midnight| {
+            midnight + chrono::Duration::minutes(i64::from(minute_of_day - minute_of_day % slot))
+        })
+        .unwrap_or(now);
+    floored + chrono::Duration::minutes(i64::from(slot))
+}
+
+/// The `count` free windows of `duration` whose starts are nearest to
+/// `requested_start`, on `step`s from it and within `earliest..=latest`.
+/// `busy` holds approved/active windows. Returned in start order.
+fn nearest_free_slots(
+    busy: &[(NaiveDateTime, NaiveDateTime)],
+    requested_start: NaiveDateTime,
+    duration: chrono::Duration,
+    step: chrono::Duration,
+    earliest: NaiveDateTime,
+    latest: NaiveDateTime,
+    count: usize,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let is_free = |start: NaiveDateTime| {
+        let end = start + duration;
+        start >= earliest
+            && start <= latest
+            && busy
+                .iter()
+                .all(|(b_start, b_end)| start >= *b_end || end <= *b_start)
+    };
+
+    let mut slots = Vec::new();
+    let steps =
+        chrono::Duration::days(SUGGESTION_HORIZON_DAYS).num_minutes() / step.num_minutes().max(1);
+    for k in 1..=steps as i32 {
+        for start in [requested_start - step * k, requested_start + step * k] {
+            if slots.len() < count && is_free(start) {
+                slots.push((start, start + duration));
+            }
+        }
+        if slots.len() >= count {
+            break;
+        }
+    }
+    slots.sort();
+    slots
 }
 
-fn is_30min_boundary(dt: &NaiveDateTime) -> bool {
-    (dt.minute() == 0 || dt.minute() == 30) && dt.second() == 0
+/// Nearest free slots for a conflicting request, from the approved/active
+/// calendar and within the booking policy. Empty if the calendar can't be read.
+async fn suggest_free_slots(
+    pool: &sqlx::SqlitePool,
+    settings: &FairnessSettings,
+    requested_start: NaiveDateTime,
+    duration: chrono::Duration,
+    now: NaiveDateTime,
+) -> Vec<(NaiveDateTime, NaiveDateTime)> {
+    let horizon = chrono::Duration::days(SUGGESTION_HORIZON_DAYS);
+    let window_start = (requested_start - horizon).format(ISO_FORMAT).to_string();
+    let window_end = (requested_start + horizon + duration)
+        .format(ISO_FORMAT)
+        .to_string();
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        "SELECT start_time, end_time FROM reservations \
+         WHERE status IN ('approved', 'active') \
+         AND start_time < ? AND end_time > ?",
+    )
+    .bind(&window_end)
+    .bind(&window_start)
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
+
+    let busy: Vec<(NaiveDateTime, NaiveDateTime)> = rows
+        .iter()
+        .filter_map(|(s, e)| Some((parse_iso_time(s)?, parse_iso_time(e)?)))
+        .collect();
+    let latest = match settings.reservation_max_advance_days {
+        0 => NaiveDateTime::MAX,
+        days => now + chrono::Duration::days(i64::from(days)),
+    };
+    nearest_free_slots(
+        &busy,
+        requested_start,
+        duration,
+        chrono::Duration::minutes(i64::from(settings.reservation_slot_minutes.max(1))),
+        next_boundary(now, settings.reservation_slot_minutes),
+        latest,
+        SUGGESTION_COUNT,
+    )
+}
+
+// ---------------------------------------------------------------------------
+// Waitlist
+// ---------------------------------------------------------------------------
+
+/// Promote waitlisted requests whose window has come free, oldest request
+/// first. A window is free once no approved, active or pending reservation
+/// overlaps it, so a promoted request holds its place ahead of later ones.
+/// Promoted requests become `approved` when the booking policy or a rule
+/// would approve them, `pending` otherwise. Returns the promoted IDs.
+pub(crate) async fn promote_waitlist(
+    pool: &sqlx::SqlitePool,
+    settings: &FairnessSettings,
+) -> anyhow::Result<Vec<String>> {
+    let now = Utc::now().naive_utc().format(ISO_FORMAT).to_string();
+    let waitlisted: Vec<(String, String, String, String)> = sqlx::query_as(
+        "SELECT id, user_id, start_time, end_time FROM reservations \
+         WHERE status = 'waitlisted' AND start_time > ? \
+         ORDER BY created_at, id",
+    )
+    .bind(&now)
+    .fetch_all(pool)
+    .await?;
+
+    let mut promoted = Vec::new();
+    for (id, user_id, start_time, end_time) in waitlisted {
+        let blocked: Option<(String,)> = sqlx::query_as(
+            "SELECT id FROM reservations \
+             WHERE id != ? AND status IN ('approved', 'active', 'pending') \
+             AND start_time < ? AND end_time > ? \
+             LIMIT 1",
+        )
+        .bind(&id)
+        .bind(&end_time)
+        .bind(&start_time)
+        .fetch_optional(pool)
+        .await?;
+        if blocked.is_some() {
+            continue;
+        }
+
+        let rule = match (
+            settings.reservation_auto_approve,
+            parse_iso_time(&start_time),
+            parse_iso_time(&end_time),
+        ) {
+            (false, Some(start), Some(end)) => {
+                reservation_rules::matching_rule(pool, &user_id, start, end).await?
+            }
+            _ => None,
+        };
+        let approved = settings.reservation_auto_approve || rule.is_some();
+        let status = if approved { "approved" } else { "pending" };
+        let admin_note = rule
+            .as_ref()
+            .map(|r| format!("Auto-approved by rule '{}'", r.name))
+            .unwrap_or_default();
+
+        let result = sqlx::query(
+            "UPDATE reservations SET status = ?, admin_note = ?, updated_at = datetime('now') \
+             WHERE id = ? AND status = 'waitlisted'",
+        )
+        .bind(status)
+        .bind(&admin_note)
+        .bind(&id)
+        .execute(pool)
+        .await?;
+        if result.rows_affected() > 0 {
+            info!(target: "audit", action = "reservation.waitlist_promote", actor = "system", resource = %id, user = %user_id, status, "Waitlisted reservation promoted");
+            promoted.push(id);
+        }
+    }
+    Ok(promoted)
 }
 
-use chrono::Timelike;
+/// Run `promote_waitlist` after a reservation frees its slot. Failures are
+/// logged: the triggering change has already been applied.
+async fn promote_waitlist_logged(state: &AppState) {
+    let settings = state.scheduler.settings().await;
+    if let Err(e) = promote_waitlist(&state.db.pool, &settings).await {
+        error!(error = %e, "Failed to promote waitlisted reservations");
+    }
+}
 
 // ---------------------------------------------------------------------------
 // User Handlers
@@ -115,13 +314,6 @@ async fn create(
         }
     };
 
-    if !is_30min_boundary(&start) || !is_30min_boundary(&end) {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({ "error": "Times must be on 30-minute boundaries (minute 0 or 30, seconds 0)" })),
-        ).into_response();
-    }
-
     if end <= start {
         return (
             StatusCode::BAD_REQUEST,
@@ -131,40 +323,87 @@ async fn create(
     }
 
     let duration = end - start;
-    if duration < chrono::Duration::minutes(30) {
+    let now = Utc::now().naive_utc();
+    if start <= now {
         return (
             StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({ "error": "Minimum reservation duration is 30 minutes" })),
+            Json(serde_json::json!({ "error": "start_time must be in the future" })),
         )
             .into_response();
     }
-    let now = Utc::now().naive_utc();
-    if start <= now {
+
+    let settings = state.scheduler.settings().await;
+    if let Err(message) = settings.check_booking(start, end, now) {
         return (
             StatusCode::BAD_REQUEST,
-            Json(serde_json::json!({ "error": "start_time must be in the future" })),
+            Json(serde_json::json!({ "error": message })),
         )
             .into_response();
     }
 
+    if settings.reservation_max_pending_per_user > 0 {
+        let pending: i64 = match sqlx::query_scalar(
+            "SELECT COUNT(*) FROM reservations WHERE user_id = ? AND status IN ('pending', 'waitlisted')",
+        )
+        .bind(&session.user_id)
+        .fetch_one(&state.db.pool)
+        .await
+        {
+            Ok(n) => n,
+            Err(e) => return error::internal_error("reservation:create_pending", e),
+        };
+        if pending >= i64::from(settings.reservation_max_pending_per_user) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({
+                    "error": format!(
+                        "You already have {pending} pending or waitlisted reservation(s); the limit is {}",
+                        settings.reservation_max_pending_per_user
+                    )
+                })),
+            )
+                .into_response();
+        }
+    }
+
     // Check for overlap with approved/active reservations
-    let overlap: Option<(String,)> = sqlx::query_as(
-        "SELECT id FROM reservations \
+    let conflicts: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT id, start_time, end_time FROM reservations \
          WHERE status IN ('approved', 'active') \
          AND start_time < ? AND end_time > ? \
-         LIMIT 1",
+         ORDER BY start_time",
     )
     .bind(&req.end_time)
     .bind(&req.start_time)
-    .fetch_optional(&state.db.pool)
+    .fetch_all(&state.db.pool)
     .await
-    .unwrap_or(None);
+    .unwrap_or_default();
 
-    if overlap.is_some() {
+    let waitlisted = !conflicts.is_empty() && req.waitlist;
+    if !conflicts.is_empty() && !waitlisted {
+        let suggestions = suggest_free_slots(&state.db.pool, &settings, start, duration, now).await;
         return (
             StatusCode::CONFLICT,
-            Json(serde_json::json!({ "error": "Time slot overlaps with an existing approved or active reservation" })),
-        ).into_response();
+            Json(serde_json::json!({
+                "error": "Time slot overlaps with an existing approved or active reservation",
+                "conflicts": conflicts
+                    .iter()
+                    .map(|(id, start_time, end_time)| serde_json::json!({
+                        "id": id,
+                        "start_time": start_time,
+                        "end_time": end_time,
+                    }))
+                    .collect::<Vec<_>>(),
+                "suggestions": suggestions
+                    .iter()
+                    .map(|(s, e)| serde_json::json!({
+                        "start_time": s.format(ISO_FORMAT).to_string(),
+                        "end_time": e.format(ISO_FORMAT).to_string(),
+                    }))
+                    .collect::<Vec<_>>(),
+            })),
+        )
+            .into_response();
     }
 
     let id = Uuid::new_v4().to_string();
@@ -174,24 +413,61 @@ async fn create(
         return r;
     }
 
+    // Conflict-free requests skip admin review when the policy or a rule allows it
+    let rule = if settings.reservation_auto_approve || waitlisted {
+        None
+    } else {
+        match reservation_rules::matching_rule(&state.db.pool, &session.user_id, start, end).await {
+            Ok(rule) => rule,
+            Err(e) => return error::internal_error("reservation:create_rules", e),
+        }
+    };
+    let auto_approved = !waitlisted && (settings.reservation_auto_approve || rule.is_some());
+    let status = if waitlisted {
+        "waitlisted"
+    } else if auto_approved {
+        "approved"
+    } else {
+        "pending"
+    };
+    let admin_note = rule
+        .as_ref()
+        .map(|r| format!("Auto-approved by rule '{}'", r.name))
+        .unwrap_or_default();
+
     match sqlx::query(
-        "INSERT INTO reservations (id, user_id, start_time, end_time, reason) VALUES (?, ?, ?, ?, ?)",
+        "INSERT INTO reservations (id, user_id, status, start_time, end_time, reason, admin_note) VALUES (?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(&id)
     .bind(&session.user_id)
+    .bind(status)
     .bind(&req.start_time)
     .bind(&req.end_time)
     .bind(&reason)
+    .bind(&admin_note)
     .execute(&state.db.pool)
     .await
     {
         Ok(_) => {
             info!(target: "audit", action = "reservation.create", actor = %session.user_id, resource = %id, "User created reservation request");
-            state.reservations.notify();
-            (
-                StatusCode::CREATED,
-                Json(serde_json::json!({ "id": id, "status": "pending" })),
-            ).into_response()
+            match &rule {
+                Some(rule) => {
+                    info!(target: "audit", action = "reservation.auto_approve", actor = %session.user_id, resource = %id, rule = %rule.id, rule_name = %rule.name, "Reservation auto-approved by rule");
+                }
+                None if auto_approved => {
+                    info!(target: "audit", action = "reservation.auto_approve", actor = %session.user_id, resource = %id, "Reservation auto-approved by booking policy");
+                }
+                None if waitlisted => {
+                    info!(target: "audit", action = "reservation.waitlist", actor = %session.user_id, resource = %id, "Reservation request joined the waitlist");
+                }
+                None => {}
+            }
+            state.events.publish(AppEvent::ReservationChanged);
+            let mut body = serde_json::json!({ "id": id, "status": status });
+            if waitlisted {
+                body["blocked_by"] = conflicts.iter().map(|(id, _, _)| id.clone()).collect();
+            }
+            (StatusCode::CREATED, Json(body)).into_response()
         }
         Err(e) => error::internal_error("reservation:create", e),
     }
@@ -215,7 +491,7 @@ async fn list_own(
     }
 }
 
-/// POST /api/user/reservations/:id/cancel — Cancel own pending/approved reservation.
+/// POST /api/user/reservations/:id/cancel — Cancel own pending/approved/waitlisted reservation.
 async fn cancel_own(
     State(state): State<Arc<AppState>>,
     Extension(session): Extension<SessionAuth>,
@@ -223,7 +499,7 @@ async fn cancel_own(
 ) -> impl IntoResponse {
     match sqlx::query(
         "UPDATE reservations SET status = 'cancelled', updated_at = datetime('now') \
-         WHERE id = ? AND user_id = ? AND status IN ('pending', 'approved')",
+         WHERE id = ? AND user_id = ? AND status IN ('pending', 'approved', 'waitlisted')",
     )
     .bind(&id)
     .bind(&session.user_id)
@@ -241,7 +517,8 @@ async fn cancel_own(
                     .into_response()
             } else {
                 info!(target: "audit", action = "reservation.cancel", actor = %session.user_id, resource = %id, "User cancelled reservation");
-                state.reservations.notify();
+                promote_waitlist_logged(&state).await;
+                state.events.publish(AppEvent::ReservationChanged);
                 Json(serde_json::json!({ "status": "cancelled" })).into_response()
             }
         }
@@ -304,7 +581,10 @@ async fn user_start_container(
         backend_type: req.backend_type,
         gpu_type: req.gpu_type,
         gpu_layers: req.gpu_layers,
+        context_size: req.context_size,
         parallel: req.parallel,
+        devices: None,
+        limits: Default::default(),
     };
 
     match common::start_container_core(&state, &params).await {
@@ -324,7 +604,7 @@ async fn user_start_container(
 async fn user_stop_container(
     State(state): State<Arc<AppState>>,
     Extension(session): Extension<SessionAuth>,
-    Json(req): Json<ContainerRequest>,
+    Json(req): Json<common::StopContainerRequest>,
 ) -> impl IntoResponse {
     // Verify caller holds the active reservation
     let active = match state.scheduler.active_reservation().await {
@@ -340,15 +620,24 @@ async fn user_stop_container(
 
     let backend_type = common::lookup_backend_type(&state.db.pool, &req.model_id).await;
 
-    match state
-        .docker
-        .stop_backend(&req.model_id, &backend_type)
-        .await
+    match common::stop_container_core(
+        &state,
+        &req.model_id,
+        &backend_type,
+        req.mode(),
+        req.drain_timeout(&state.config),
+    )
+    .await
     {
-        Ok(_) => {
-            info!(target: "audit", action = "reservation.container.stop", actor = %session.user_id, reservation = %active.reservation_id, resource = %req.model_id, "Reservation holder stopped container");
-            common::post_stop_cleanup(&state, &req.model_id).await;
-            Json(serde_json::json!({ "status": "stopped" })).into_response()
+        Ok(outcome) => {
+            info!(target: "audit", action = "reservation.container.stop", actor = %session.user_id, reservation = %active.reservation_id, resource = %req.model_id, mode = ?outcome.mode, abandoned_in_flight = outcome.abandoned_in_flight, "Reservation holder stopped container");
+            Json(serde_json::json!({
+                "status": "stopped",
+                "mode": outcome.mode,
+                "drained": outcome.drained,
+                "abandoned_in_flight": outcome.abandoned_in_flight,
+            }))
+            .into_response()
         }
         Err(e) => {
             error!(model = %req.model_id, error = %e, "Failed to stop container");
@@ -377,6 +666,139 @@ async fn admin_list(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     }
 }
 
+#[derive(Debug, Deserialize)]
+struct HeatmapQuery {
+    /// Look-back window as `<days>d`, e.g. `30d` (default).
+    window: Option<String>,
+}
+
+/// Longest heatmap look-back.
+const HEATMAP_MAX_DAYS: i64 = 365;
+
+/// Parse a `<days>d` window, e.g. `30d`. `None` if malformed or out of range.
+fn parse_window_days(window: &str) -> Option<i64> {
+    let days: i64 = window.strip_suffix('d')?.parse().ok()?;
+    (1..=HEATMAP_MAX_DAYS).contains(&days).then_some(days)
+}
+
+/// Reserved hours per (weekday from Monday, hour) cell, counting only the
+/// parts of each window that fall within `from..to`.
+fn reserved_hours_grid(
+    windows: &[(NaiveDateTime, NaiveDateTime)],
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> [[f64; 24]; 7] {
+    let mut grid = [[0.0; 24]; 7];
+    for &(start, end) in windows {
+        // Walk hour by hour, crediting each hour cell with the overlap
+        let mut t = start.max(from);
+        let end = end.min(to);
+        while t < end {
+            let hour_start = t
+                .with_minute(0)
+                .and_then(|h| h.with_second(0))
+                .and_then(|h| h.with_nanosecond(0))
+                .unwrap_or(t);
+            let segment_end = (hour_start + chrono::Duration::hours(1)).min(end);
+            let weekday = t.weekday().num_days_from_monday() as usize;
+            grid[weekday][t.hour() as usize] += (segment_end - t).num_seconds() as f64 / 3600.0;
+            t = segment_end;
+        }
+    }
+    grid
+}
+
+/// GET /api/admin/reservations/heatmap?window=30d — Reserved hours and
+/// inference load per weekday and hour (UTC) over the window.
+async fn heatmap(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<HeatmapQuery>,
+) -> impl IntoResponse {
+    let Some(days) = parse_window_days(query.window.as_deref().unwrap_or("30d")) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": format!("window must be <days>d, between 1d and {HEATMAP_MAX_DAYS}d") })),
+        )
+            .into_response();
+    };
+    let to = Utc::now().naive_utc();
+    let from = to - chrono::Duration::days(days);
+    let from_iso = from.format(ISO_FORMAT).to_string();
+    let to_iso = to.format(ISO_FORMAT).to_string();
+
+    // Everything that held (or holds) the system during the window
+    let rows: Vec<(String, String)> = match sqlx::query_as(
+        "SELECT start_time, end_time FROM reservations \
+         WHERE status IN ('approved', 'active', 'completed') \
+         AND start_time < ? AND end_time > ?",
+    )
+    .bind(&to_iso)
+    .bind(&from_iso)
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => return error::internal_error("reservation:heatmap", e),
+    };
+    let windows: Vec<(NaiveDateTime, NaiveDateTime)> = rows
+        .iter()
+        .filter_map(|(s, e)| Some((parse_iso_time(s)?, parse_iso_time(e)?)))
+        .collect();
+    let reserved = reserved_hours_grid(&windows, from, to);
+
+    // usage_log timestamps are `YYYY-MM-DD HH:MM:SS`; strftime('%w') counts from Sunday
+    let load: Vec<(i64, i64, i64, i64, f64)> = match sqlx::query_as(
+        "SELECT (CAST(strftime('%w', created_at) AS INTEGER) + 6) % 7 AS weekday, \
+                CAST(strftime('%H', created_at) AS INTEGER) AS hour, \
+                COUNT(*), \
+                COALESCE(SUM(input_tokens + output_tokens), 0), \
+                COALESCE(AVG(queued_ms), 0.0) \
+         FROM usage_log \
+         WHERE created_at >= datetime('now', ?) \
+         GROUP BY weekday, hour",
+    )
+    .bind(format!("-{days} days"))
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(rows) => rows,
+        Err(e) => return error::internal_error("reservation:heatmap_load", e),
+    };
+    let mut load_grid = [[(0i64, 0i64, 0.0f64); 24]; 7];
+    for (weekday, hour, requests, tokens, avg_queued_ms) in load {
+        if let Some(cell) = load_grid
+            .get_mut(weekday as usize)
+            .and_then(|row| row.get_mut(hour as usize))
+        {
+            *cell = (requests, tokens, avg_queued_ms);
+        }
+    }
+
+    let mut cells = Vec::with_capacity(7 * 24);
+    for weekday in 0..7 {
+        for hour in 0..24 {
+            let (requests, tokens, avg_queued_ms) = load_grid[weekday][hour];
+            cells.push(serde_json::json!({
+                "weekday": weekday,
+                "hour": hour,
+                "reserved_hours": reserved[weekday][hour],
+                "requests": requests,
+                "tokens": tokens,
+                "avg_queued_ms": avg_queued_ms.round() as i64,
+            }));
+        }
+    }
+
+    Json(serde_json::json!({
+        "window_days": days,
+        "from": from_iso,
+        "to": to_iso,
+        "timezone": "UTC",
+        "cells": cells,
+    }))
+    .into_response()
+}
+
 /// POST /api/admin/reservations/:id/approve — Approve a pending reservation.
 async fn approve(
     State(state): State<Arc<AppState>>,
@@ -423,6 +845,18 @@ async fn approve(
             .unwrap_or(None);
 
     if let Some((start, end)) = times {
+        // Requests made under an older, looser policy must still fit the current one
+        if let (Some(start), Some(end)) = (parse_iso_time(&start), parse_iso_time(&end)) {
+            let settings = state.scheduler.settings().await;
+            if let Err(message) = settings.check_booking(start, end, Utc::now().naive_utc()) {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(serde_json::json!({ "error": format!("Reservation violates the booking policy: {message}") })),
+                )
+                    .into_response();
+            }
+        }
+
         let overlap: Option<(String,)> = sqlx::query_as(
             "SELECT id FROM reservations \
              WHERE id != ? AND status IN ('approved', 'active') \
@@ -455,7 +889,7 @@ async fn approve(
     {
         Ok(_) => {
             info!(target: "audit", action = "reservation.approve", actor = %session.user_id, resource = %id, "Admin approved reservation");
-            state.reservations.notify();
+            state.events.publish(AppEvent::ReservationChanged);
             Json(serde_json::json!({ "status": "approved" })).into_response()
         }
         Err(e) => error::internal_error("reservation:approve", e),
@@ -490,7 +924,8 @@ async fn reject(
                 ).into_response()
             } else {
                 info!(target: "audit", action = "reservation.reject", actor = %session.user_id, resource = %id, "Admin rejected reservation");
-                state.reservations.notify();
+                promote_waitlist_logged(&state).await;
+                state.events.publish(AppEvent::ReservationChanged);
                 Json(serde_json::json!({ "status": "rejected" })).into_response()
             }
         }
@@ -556,7 +991,7 @@ async fn force_activate(
         .await;
 
     info!(target: "audit", action = "reservation.force_activate", actor = %session.user_id, resource = %res_id, "Admin force-activated reservation");
-    state.reservations.notify();
+    state.events.publish(AppEvent::ReservationChanged);
     Json(serde_json::json!({ "status": "active" })).into_response()
 }
 
@@ -583,7 +1018,8 @@ async fn force_deactivate(
                 // Clear in-memory cache
                 state.scheduler.set_active_reservation(None).await;
                 info!(target: "audit", action = "reservation.deactivate", actor = %session.user_id, resource = %id, "Admin force-deactivated reservation");
-                state.reservations.notify();
+                promote_waitlist_logged(&state).await;
+                state.events.publish(AppEvent::ReservationChanged);
                 Json(serde_json::json!({ "status": "completed" })).into_response()
             }
         }
@@ -627,7 +1063,8 @@ async fn admin_delete(
                     .into_response()
             } else {
                 info!(target: "audit", action = "reservation.delete", actor = %session.user_id, resource = %id, "Admin deleted reservation");
-                state.reservations.notify();
+                promote_waitlist_logged(&state).await;
+                state.events.publish(AppEvent::ReservationChanged);
                 Json(serde_json::json!({ "status": "deleted" })).into_response()
             }
         }
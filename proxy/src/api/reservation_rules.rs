@@ -0,0 +1,460 @@
+//! Reservation auto-approval rules.
+//!
+//! Admins define rules such as "approve slots of at most 2h outside business
+//! hours for users with no recent rejections". A new, conflict-free
+//! reservation request is approved by the first enabled rule (oldest first)
+//! whose conditions all hold. Rule changes and every approval they make are
+//! audit-logged.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, put};
+use axum::{middleware, Extension, Json, Router};
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+use super::error;
+use crate::auth::rbac::{self, Permission};
+use crate::auth::SessionAuth;
+use crate::AppState;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/reservations/rules", get(list_rules).post(create_rule))
+        .route(
+            "/reservations/rules/{id}",
+            put(update_rule).delete(delete_rule),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Reservations,
+            rbac::require_permission,
+        ))
+        .with_state(state)
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ReservationRule {
+    pub id: String,
+    pub name: String,
+    pub enabled: bool,
+    pub max_duration_minutes: Option<i64>,
+    pub hours_start: Option<i64>,
+    pub hours_end: Option<i64>,
+    /// Comma-separated weekdays, 0 = Monday.
+    pub weekdays: Option<String>,
+    pub good_standing_days: Option<i64>,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl ReservationRule {
+    fn weekday_list(&self) -> Option<Vec<u32>> {
+        self.weekdays.as_deref().map(|days| {
+            days.split(',')
+                .filter_map(|d| d.trim().parse().ok())
+                .collect()
+        })
+    }
+
+    fn hour_allowed(&self, hour: u32) -> bool {
+        let (Some(start), Some(end)) = (self.hours_start, self.hours_end) else {
+            return true;
+        };
+        let hour = i64::from(hour);
+        match start.cmp(&end) {
+            std::cmp::Ordering::Less => (start..end).contains(&hour),
+            std::cmp::Ordering::Greater => hour >= start || hour < end,
+            std::cmp::Ordering::Equal => true,
+        }
+    }
+
+    /// Whether the reservation window satisfies the rule's duration, hour
+    /// and weekday conditions. Good standing needs the DB and is checked
+    /// separately.
+    fn window_matches(&self, start: NaiveDateTime, end: NaiveDateTime) -> bool {
+        if let Some(max) = self.max_duration_minutes {
+            if (end - start).num_minutes() > max {
+                return false;
+            }
+        }
+
+        let weekdays = self.weekday_list();
+        // Every hour the reservation touches must be allowed
+        let mut t = start;
+        while t < end {
+            let weekday = t.weekday().num_days_from_monday();
+            if !self.hour_allowed(t.hour())
+                || weekdays
+                    .as_ref()
+                    .is_some_and(|days| !days.contains(&weekday))
+            {
+                return false;
+            }
+            t = t
+                .with_minute(0)
+                .and_then(|h| h.with_second(0))
+                .and_then(|h| h.with_nanosecond(0))
+                .unwrap_or(t)
+                + chrono::Duration::hours(1);
+        }
+        true
+    }
+}
+
+/// The first enabled rule that approves `user_id` reserving `start..end`.
+pub async fn matching_rule(
+    pool: &sqlx::SqlitePool,
+    user_id: &str,
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+) -> Result<Option<ReservationRule>> {
+    let rules: Vec<ReservationRule> =
+        sqlx::query_as("SELECT * FROM reservation_rules WHERE enabled = 1 ORDER BY created_at, id")
+            .fetch_all(pool)
+            .await
+            .context("Failed to load reservation rules")?;
+
+    for rule in rules {
+        if !rule.window_matches(start, end) {
+            continue;
+        }
+        if let Some(days) = rule.good_standing_days {
+            let rejected: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM reservations \
+                 WHERE user_id = ? AND status = 'rejected' \
+                 AND updated_at >= datetime('now', ?)",
+            )
+            .bind(user_id)
+            .bind(format!("-{days} days"))
+            .fetch_one(pool)
+            .await
+            .context("Failed to check reservation standing")?;
+            if rejected > 0 {
+                continue;
+            }
+        }
+        return Ok(Some(rule));
+    }
+    Ok(None)
+}
+
+fn rule_json(rule: &ReservationRule) -> serde_json::Value {
+    json!({
+        "id": rule.id,
+        "name": rule.name,
+        "enabled": rule.enabled,
+        "max_duration_minutes": rule.max_duration_minutes,
+        "hours_start": rule.hours_start,
+        "hours_end": rule.hours_end,
+        "weekdays": rule.weekday_list(),
+        "good_standing_days": rule.good_standing_days,
+        "created_by": rule.created_by,
+        "created_at": rule.created_at,
+        "updated_at": rule.updated_at,
+    })
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RuleRequest {
+    name: String,
+    enabled: Option<bool>,
+    max_duration_minutes: Option<i64>,
+    hours_start: Option<i64>,
+    hours_end: Option<i64>,
+    /// 0 = Monday … 6 = Sunday.
+    weekdays: Option<Vec<u32>>,
+    good_standing_days: Option<i64>,
+}
+
+impl RuleRequest {
+    /// Validate the conditions. Returns an error message if invalid.
+    fn validate(&self) -> Option<String> {
+        if self.name.trim().is_empty() {
+            return Some("name is required".to_string());
+        }
+        if self.max_duration_minutes.is_some_and(|m| m <= 0) {
+            return Some("max_duration_minutes must be positive".to_string());
+        }
+        match (self.hours_start, self.hours_end) {
+            (None, None) => {}
+            (Some(start), Some(end)) if (0..24).contains(&start) && (0..24).contains(&end) => {}
+            _ => {
+                return Some(
+                    "hours_start and hours_end must be set together, each 0-23".to_string(),
+                )
+            }
+        }
+        if let Some(days) = &self.weekdays {
+            if days.is_empty() || days.iter().any(|d| *d > 6) {
+                return Some(
+                    "weekdays must list at least one day, 0 (Monday) to 6 (Sunday)".to_string(),
+                );
+            }
+        }
+        if self.good_standing_days.is_some_and(|d| d <= 0) {
+            return Some("good_standing_days must be positive".to_string());
+        }
+        None
+    }
+
+    fn weekdays_text(&self) -> Option<String> {
+        self.weekdays.as_ref().map(|days| {
+            days.iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+    }
+}
+
+/// Validate a rule request into a 400 response, or `None` if it is usable.
+fn reject_invalid(req: &RuleRequest) -> Option<axum::response::Response> {
+    if let Some(r) = error::validate_len("name", &req.name, error::MAX_NAME) {
+        return Some(r);
+    }
+    req.validate()
+        .map(|message| (StatusCode::BAD_REQUEST, Json(json!({ "error": message }))).into_response())
+}
+
+async fn fetch_rule(pool: &sqlx::SqlitePool, id: &str) -> sqlx::Result<Option<ReservationRule>> {
+    sqlx::query_as("SELECT * FROM reservation_rules WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+/// GET /api/admin/reservations/rules — List auto-approval rules in evaluation order.
+async fn list_rules(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let rules: Vec<ReservationRule> =
+        match sqlx::query_as("SELECT * FROM reservation_rules ORDER BY created_at, id")
+            .fetch_all(&state.db.pool)
+            .await
+        {
+            Ok(rules) => rules,
+            Err(e) => return error::internal_error("reservation_rules:list", e),
+        };
+    Json(json!({ "rules": rules.iter().map(rule_json).collect::<Vec<_>>() })).into_response()
+}
+
+/// POST /api/admin/reservations/rules — Add an auto-approval rule.
+async fn create_rule(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Json(req): Json<RuleRequest>,
+) -> impl IntoResponse {
+    if let Some(r) = reject_invalid(&req) {
+        return r;
+    }
+
+    let id = Uuid::new_v4().to_string();
+    if let Err(e) = sqlx::query(
+        "INSERT INTO reservation_rules (id, name, enabled, max_duration_minutes, hours_start, \
+         hours_end, weekdays, good_standing_days, created_by) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(req.name.trim())
+    .bind(req.enabled.unwrap_or(true))
+    .bind(req.max_duration_minutes)
+    .bind(req.hours_start)
+    .bind(req.hours_end)
+    .bind(req.weekdays_text())
+    .bind(req.good_standing_days)
+    .bind(&session.user_id)
+    .execute(&state.db.pool)
+    .await
+    {
+        return error::internal_error("reservation_rules:create", e);
+    }
+
+    info!(target: "audit", action = "reservation_rule.create", actor = %session.user_id, resource = %id, name = %req.name.trim(), "Admin created reservation auto-approval rule");
+
+    match fetch_rule(&state.db.pool, &id).await {
+        Ok(Some(rule)) => (StatusCode::CREATED, Json(rule_json(&rule))).into_response(),
+        Ok(None) => error::internal_error("reservation_rules:create", "rule missing after insert"),
+        Err(e) => error::internal_error("reservation_rules:create", e),
+    }
+}
+
+/// PUT /api/admin/reservations/rules/:id — Replace an auto-approval rule.
+async fn update_rule(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(req): Json<RuleRequest>,
+) -> impl IntoResponse {
+    if let Some(r) = reject_invalid(&req) {
+        return r;
+    }
+
+    let updated = match sqlx::query(
+        "UPDATE reservation_rules SET name = ?, enabled = ?, max_duration_minutes = ?, \
+         hours_start = ?, hours_end = ?, weekdays = ?, good_standing_days = ?, \
+         updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(req.name.trim())
+    .bind(req.enabled.unwrap_or(true))
+    .bind(req.max_duration_minutes)
+    .bind(req.hours_start)
+    .bind(req.hours_end)
+    .bind(req.weekdays_text())
+    .bind(req.good_standing_days)
+    .bind(&id)
+    .execute(&state.db.pool)
+    .await
+    {
+        Ok(result) => result.rows_affected(),
+        Err(e) => return error::internal_error("reservation_rules:update", e),
+    };
+    if updated == 0 {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Rule not found" })),
+        )
+            .into_response();
+    }
+
+    info!(target: "audit", action = "reservation_rule.update", actor = %session.user_id, resource = %id, name = %req.name.trim(), enabled = req.enabled.unwrap_or(true), "Admin updated reservation auto-approval rule");
+
+    match fetch_rule(&state.db.pool, &id).await {
+        Ok(Some(rule)) => Json(rule_json(&rule)).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Rule not found" })),
+        )
+            .into_response(),
+        Err(e) => error::internal_error("reservation_rules:update", e),
+    }
+}
+
+/// DELETE /api/admin/reservations/rules/:id — Remove an auto-approval rule.
+async fn delete_rule(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match sqlx::query("DELETE FROM reservation_rules WHERE id = ?")
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Rule not found" })),
+        )
+            .into_response(),
+        Ok(_) => {
+            info!(target: "audit", action = "reservation_rule.delete", actor = %session.user_id, resource = %id, "Admin deleted reservation auto-approval rule");
+            Json(json!({ "status": "deleted" })).into_response()
+        }
+        Err(e) => error::internal_error("reservation_rules:delete", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap()
+    }
+
+    fn rule() -> ReservationRule {
+        ReservationRule {
+            id: "r".into(),
+            name: "r".into(),
+            enabled: true,
+            max_duration_minutes: None,
+            hours_start: None,
+            hours_end: None,
+            weekdays: None,
+            good_standing_days: None,
+            created_by: None,
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn empty_rule_matches_anything() {
+        assert!(rule().window_matches(at("2026-03-02T09:00:00"), at("2026-03-04T17:00:00")));
+    }
+
+    #[test]
+    fn outside_business_hours_wraps_midnight() {
+        // 2026-03-02 is a Monday
+        let r = ReservationRule {
+            max_duration_minutes: Some(120),
+            hours_start: Some(18),
+            hours_end: Some(8),
+            ..rule()
+        };
+        assert!(r.window_matches(at("2026-03-02T18:00:00"), at("2026-03-02T20:00:00")));
+        assert!(r.window_matches(at("2026-03-02T23:30:00"), at("2026-03-03T01:30:00")));
+        assert!(r.window_matches(at("2026-03-03T06:30:00"), at("2026-03-03T08:00:00")));
+        // Runs into business hours
+        assert!(!r.window_matches(at("2026-03-03T07:00:00"), at("2026-03-03T09:00:00")));
+        // Too long
+        assert!(!r.window_matches(at("2026-03-02T18:00:00"), at("2026-03-02T20:30:00")));
+    }
+
+    #[test]
+    fn weekdays_cover_whole_window() {
+        let r = ReservationRule {
+            weekdays: Some("5,6".into()),
+            ..rule()
+        };
+        // Saturday into Sunday
+        assert!(r.window_matches(at("2026-03-07T22:00:00"), at("2026-03-08T02:00:00")));
+        // Sunday into Monday
+        assert!(!r.window_matches(at("2026-03-08T23:00:00"), at("2026-03-09T01:00:00")));
+    }
+
+    #[test]
+    fn request_validation() {
+        let valid = RuleRequest {
+            name: "nights".into(),
+            enabled: None,
+            max_duration_minutes: Some(120),
+            hours_start: Some(18),
+            hours_end: Some(8),
+            weekdays: Some(vec![0, 4]),
+            good_standing_days: Some(90),
+        };
+        assert!(valid.validate().is_none());
+        assert_eq!(valid.weekdays_text().as_deref(), Some("0,4"));
+
+        for invalid in [
+            RuleRequest {
+                hours_end: None,
+                ..valid.clone()
+            },
+            RuleRequest {
+                hours_start: Some(24),
+                ..valid.clone()
+            },
+            RuleRequest {
+                weekdays: Some(vec![7]),
+                ..valid.clone()
+            },
+            RuleRequest {
+                max_duration_minutes: Some(0),
+                ..valid.clone()
+            },
+            RuleRequest {
+                name: " ".into(),
+                ..valid.clone()
+            },
+        ] {
+            assert!(invalid.validate().is_some(), "{invalid:?}");
+        }
+    }
+}
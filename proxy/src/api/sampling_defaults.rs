@@ -0,0 +1,302 @@
+//! Per-model sampling defaults.
+//!
+//! Admins set `temperature`, `top_p`, `min_p` and `repeat_penalty` for a
+//! model (`models.default_params`). The OpenAI proxy fills them into chat
+//! and text completion requests for that model before forwarding. A field
+//! the client sends, even as `null`, is left as the client sent it.
+//!
+//! llama.cpp reads `repeat_penalty`; vLLM calls the same knob
+//! `repetition_penalty`, so that is the name a vLLM backend gets, and a
+//! client sending either name counts as having set it.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{middleware, Extension, Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::info;
+
+use super::error;
+use crate::auth::rbac::{self, Permission};
+use crate::auth::SessionAuth;
+use crate::AppState;
+
+const MAX_TEMPERATURE: f64 = 2.0;
+const MAX_REPEAT_PENALTY: f64 = 2.0;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/models/{id}/default-params",
+            get(get_default_params).put(put_default_params),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Models,
+            rbac::require_permission,
+        ))
+        .with_state(state)
+}
+
+/// Sampling parameters filled into requests that don't set them. Unset
+/// fields are left to the client and the backend's own defaults. Unknown
+/// keys are rejected so a typo doesn't silently no-op.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SamplingDefaults {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_p: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_penalty: Option<f64>,
+}
+
+impl SamplingDefaults {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Range checks. Returns `Err(reason)` on the first failure.
+    pub fn validate(&self) -> Result<(), String> {
+        check_range("temperature", self.temperature, 0.0, MAX_TEMPERATURE)?;
+        check_range("top_p", self.top_p, 0.0, 1.0)?;
+        check_range("min_p", self.min_p, 0.0, 1.0)?;
+        check_range(
+            "repeat_penalty",
+            self.repeat_penalty,
+            0.0,
+            MAX_REPEAT_PENALTY,
+        )?;
+        if self.repeat_penalty == Some(0.0) {
+            return Err("repeat_penalty must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    /// Fill the defaults into a completion request body for a model served
+    /// by `backend_type`. Bodies that aren't a JSON object are passed
+    /// through unchanged.
+    pub fn apply(&self, body: Bytes, backend_type: &str) -> Bytes {
+        if self.is_empty() {
+            return body;
+        }
+        let Ok(mut value) = serde_json::from_slice::<Value>(&body) else {
+            return body;
+        };
+        let Some(obj) = value.as_object_mut() else {
+            return body;
+        };
+
+        let penalty_key = if backend_type == "vllm" {
+            "repetition_penalty"
+        } else {
+            "repeat_penalty"
+        };
+        let fields: [(&str, Option<f64>, &[&str]); 4] = [
+            ("temperature", self.temperature, &["temperature"]),
+            ("top_p", self.top_p, &["top_p"]),
+            ("min_p", self.min_p, &["min_p"]),
+            (
+                penalty_key,
+                self.repeat_penalty,
+                &["repeat_penalty", "repetition_penalty"],
+            ),
+        ];
+        let mut changed = false;
+        for (key, default, client_keys) in fields {
+            let Some(number) = default.and_then(serde_json::Number::from_f64) else {
+                continue;
+            };
+            if client_keys.iter().any(|k| obj.contains_key(*k)) {
+                continue;
+            }
+            obj.insert(key.to_string(), Value::Number(number));
+            changed = true;
+        }
+        if !changed {
+            return body;
+        }
+        serde_json::to_vec(&value).map(Bytes::from).unwrap_or(body)
+    }
+}
+
+fn check_range(name: &str, value: Option<f64>, min: f64, max: f64) -> Result<(), String> {
+    match value {
+        Some(v) if !(min..=max).contains(&v) => {
+            Err(format!("{name} must be between {min} and {max}"))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The defaults saved for `model_id`. Empty if the model has none or
+/// doesn't exist.
+pub async fn for_model(pool: &sqlx::SqlitePool, model_id: &str) -> Result<SamplingDefaults> {
+    let stored: Option<String> =
+        sqlx::query_scalar("SELECT default_params FROM models WHERE id = ?")
+            .bind(model_id)
+            .fetch_optional(pool)
+            .await
+            .context("Failed to load model default params")?;
+    match stored {
+        Some(json) => serde_json::from_str(&json).context("Invalid stored default params"),
+        None => Ok(SamplingDefaults::default()),
+    }
+}
+
+fn not_found() -> Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "Model not found" })),
+    )
+        .into_response()
+}
+
+/// GET /api/admin/models/{id}/default-params — The model's sampling defaults.
+async fn get_default_params(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    let exists: Option<i64> = match sqlx::query_scalar("SELECT 1 FROM models WHERE id = ?")
+        .bind(&id)
+        .fetch_optional(&state.db.pool)
+        .await
+    {
+        Ok(row) => row,
+        Err(e) => return error::internal_error("get_default_params:lookup", e),
+    };
+    if exists.is_none() {
+        return not_found();
+    }
+    match for_model(&state.db.pool, &id).await {
+        Ok(params) => Json(json!({ "model_id": id, "default_params": params })).into_response(),
+        Err(e) => error::internal_error("get_default_params", e),
+    }
+}
+
+/// PUT /api/admin/models/{id}/default-params — Replace the model's sampling
+/// defaults. `{}` clears them.
+async fn put_default_params(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(params): Json<SamplingDefaults>,
+) -> Response {
+    if let Err(reason) = params.validate() {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": reason }))).into_response();
+    }
+    let stored = match serde_json::to_string(&params) {
+        Ok(s) => s,
+        Err(e) => return error::internal_error("put_default_params:serialize", e),
+    };
+
+    match sqlx::query("UPDATE models SET default_params = ? WHERE id = ?")
+        .bind(&stored)
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => not_found(),
+        Ok(_) => {
+            info!(target: "audit", action = "model.default_params_update", actor = %session.user_id, resource = %id, default_params = %stored, "Admin set model sampling defaults");
+            Json(json!({ "model_id": id, "default_params": params })).into_response()
+        }
+        Err(e) => error::internal_error("put_default_params", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> SamplingDefaults {
+        SamplingDefaults {
+            temperature: Some(0.7),
+            top_p: Some(0.9),
+            min_p: None,
+            repeat_penalty: Some(1.1),
+        }
+    }
+
+    fn apply(body: Value, backend_type: &str) -> Value {
+        let out = defaults().apply(Bytes::from(body.to_string()), backend_type);
+        serde_json::from_slice(&out).unwrap()
+    }
+
+    #[test]
+    fn fills_missing_fields() {
+        let out = apply(json!({ "model": "m", "messages": [] }), "llamacpp");
+        assert_eq!(out["temperature"], 0.7);
+        assert_eq!(out["top_p"], 0.9);
+        assert_eq!(out["repeat_penalty"], 1.1);
+        assert!(out.get("min_p").is_none());
+        assert_eq!(out["model"], "m");
+    }
+
+    #[test]
+    fn client_values_win() {
+        let out = apply(
+            json!({ "temperature": 0.0, "top_p": null, "repetition_penalty": 1.3 }),
+            "llamacpp",
+        );
+        assert_eq!(out["temperature"], 0.0);
+        assert!(out["top_p"].is_null());
+        assert_eq!(out["repetition_penalty"], 1.3);
+        assert!(out.get("repeat_penalty").is_none());
+    }
+
+    #[test]
+    fn vllm_gets_repetition_penalty() {
+        let out = apply(json!({}), "vllm");
+        assert_eq!(out["repetition_penalty"], 1.1);
+        assert!(out.get("repeat_penalty").is_none());
+    }
+
+    #[test]
+    fn non_object_body_passes_through() {
+        let body = Bytes::from_static(b"not json");
+        assert_eq!(defaults().apply(body.clone(), "llamacpp"), body);
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range() {
+        assert!(defaults().validate().is_ok());
+        let bad = [
+            SamplingDefaults {
+                temperature: Some(2.5),
+                ..Default::default()
+            },
+            SamplingDefaults {
+                top_p: Some(-0.1),
+                ..Default::default()
+            },
+            SamplingDefaults {
+                min_p: Some(f64::NAN),
+                ..Default::default()
+            },
+            SamplingDefaults {
+                repeat_penalty: Some(0.0),
+                ..Default::default()
+            },
+        ];
+        for params in bad {
+            assert!(params.validate().is_err(), "{params:?}");
+        }
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(serde_json::from_str::<SamplingDefaults>(r#"{"temprature": 0.5}"#).is_err());
+        let empty: SamplingDefaults = serde_json::from_str("{}").unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(serde_json::to_string(&empty).unwrap(), "{}");
+    }
+}
@@ -0,0 +1,482 @@
+//! Org-wide system prompt policies.
+//!
+//! Admins define prompt prefixes (disclaimers, data-handling rules) that the
+//! chat proxy prepends to the system message of every chat completion whose
+//! model category and/or token match the policy. Several matching policies
+//! are applied oldest first. Tokens flagged with `system_prompt_opt_out` are
+//! left untouched. Policy changes and every injection are audit-logged.
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::{get, put};
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::info;
+use uuid::Uuid;
+
+use super::error;
+use crate::auth::SessionAuth;
+use crate::AppState;
+
+/// Longest prompt a single policy may carry.
+const MAX_PROMPT: usize = 16 * 1024;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/system-prompts", get(list_policies).post(create_policy))
+        .route(
+            "/system-prompts/{id}",
+            put(update_policy).delete(delete_policy),
+        )
+        .route("/tokens/{id}/system-prompt-opt-out", put(set_token_opt_out))
+        .with_state(state)
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SystemPromptPolicy {
+    pub id: String,
+    pub name: String,
+    pub prompt: String,
+    pub category_id: Option<String>,
+    pub token_id: Option<String>,
+    pub enabled: bool,
+    pub created_by: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Enabled policies that apply to a request for a model in `category_id`
+/// made with `token_id`, oldest first. Empty if the token has opted out.
+pub async fn applicable_policies(
+    pool: &sqlx::SqlitePool,
+    category_id: Option<&str>,
+    token_id: &str,
+) -> Result<Vec<SystemPromptPolicy>> {
+    sqlx::query_as(
+        "SELECT p.* FROM system_prompt_policies p \
+         WHERE p.enabled = 1 \
+         AND (p.category_id IS NULL OR p.category_id = ?) \
+         AND (p.token_id IS NULL OR p.token_id = ?) \
+         AND NOT EXISTS (SELECT 1 FROM tokens t WHERE t.id = ? AND t.system_prompt_opt_out = 1) \
+         ORDER BY p.created_at, p.id",
+    )
+    .bind(category_id)
+    .bind(token_id)
+    .bind(token_id)
+    .fetch_all(pool)
+    .await
+    .context("Failed to load system prompt policies")
+}
+
+/// The prefix the policies add, in application order.
+pub fn combined_prompt(policies: &[SystemPromptPolicy]) -> String {
+    policies
+        .iter()
+        .map(|p| p.prompt.trim())
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Prepend `prefix` to the leading system message of a chat completion
+/// body, adding one if the conversation has none. Returns `None` when the
+/// body has no `messages` array, in which case it is forwarded unchanged.
+pub fn inject(body: &[u8], prefix: &str) -> Option<Bytes> {
+    let mut value: Value = serde_json::from_slice(body).ok()?;
+    let messages = value.get_mut("messages")?.as_array_mut()?;
+
+    let leading_system = messages.first_mut().filter(|m| {
+        matches!(
+            m.get("role").and_then(Value::as_str),
+            Some("system" | "developer")
+        )
+    });
+    match leading_system.and_then(|m| m.get_mut("content")) {
+        Some(Value::String(content)) => {
+            *content = format!("{prefix}\n\n{content}");
+        }
+        Some(Value::Array(parts)) => {
+            parts.insert(0, json!({ "type": "text", "text": prefix }));
+        }
+        _ => {
+            messages.insert(0, json!({ "role": "system", "content": prefix }));
+        }
+    }
+
+    serde_json::to_vec(&value).ok().map(Bytes::from)
+}
+
+/// Apply the policies matching a chat completion to its OpenAI-format
+/// `body`, audit-logging the injection. The body is returned unchanged when
+/// no policy applies.
+pub async fn apply(
+    pool: &sqlx::SqlitePool,
+    body: Bytes,
+    category_id: Option<&str>,
+    user_id: &str,
+    token_id: &str,
+    model_id: &str,
+) -> Result<Bytes> {
+    let policies = applicable_policies(pool, category_id, token_id).await?;
+    let prefix = combined_prompt(&policies);
+    if prefix.is_empty() {
+        return Ok(body);
+    }
+    let Some(injected) = inject(&body, &prefix) else {
+        return Ok(body);
+    };
+
+    let policy_ids = policies
+        .iter()
+        .map(|p| p.id.as_str())
+        .collect::<Vec<_>>()
+        .join(",");
+    info!(target: "audit", action = "system_prompt.inject", actor = %user_id, resource = %token_id, model = %model_id, policies = %policy_ids, "System prompt policies applied to chat completion");
+    Ok(injected)
+}
+
+fn policy_json(policy: &SystemPromptPolicy) -> Value {
+    json!({
+        "id": policy.id,
+        "name": policy.name,
+        "prompt": policy.prompt,
+        "category_id": policy.category_id,
+        "token_id": policy.token_id,
+        "enabled": policy.enabled,
+        "created_by": policy.created_by,
+        "created_at": policy.created_at,
+        "updated_at": policy.updated_at,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct PolicyRequest {
+    name: String,
+    prompt: String,
+    enabled: Option<bool>,
+    category_id: Option<String>,
+    token_id: Option<String>,
+}
+
+fn bad_request(message: &str) -> axum::response::Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": message }))).into_response()
+}
+
+/// Validate a policy request into a 400 response, or `None` if it is usable.
+async fn reject_invalid(
+    pool: &sqlx::SqlitePool,
+    req: &PolicyRequest,
+) -> Option<axum::response::Response> {
+    if let Some(r) = error::validate_len("name", &req.name, error::MAX_NAME) {
+        return Some(r);
+    }
+    if let Some(r) = error::validate_len("prompt", &req.prompt, MAX_PROMPT) {
+        return Some(r);
+    }
+    if req.name.trim().is_empty() {
+        return Some(bad_request("name is required"));
+    }
+    if req.prompt.trim().is_empty() {
+        return Some(bad_request("prompt is required"));
+    }
+    if let Some(category_id) = &req.category_id {
+        match sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM model_categories WHERE id = ?")
+            .bind(category_id)
+            .fetch_one(pool)
+            .await
+        {
+            Ok(0) => return Some(bad_request("Unknown category_id")),
+            Ok(_) => {}
+            Err(e) => return Some(error::internal_error("system_prompts:validate", e)),
+        }
+    }
+    if let Some(token_id) = &req.token_id {
+        match sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM tokens WHERE id = ?")
+            .bind(token_id)
+            .fetch_one(pool)
+            .await
+        {
+            Ok(0) => return Some(bad_request("Unknown token_id")),
+            Ok(_) => {}
+            Err(e) => return Some(error::internal_error("system_prompts:validate", e)),
+        }
+    }
+    None
+}
+
+async fn fetch_policy(
+    pool: &sqlx::SqlitePool,
+    id: &str,
+) -> sqlx::Result<Option<SystemPromptPolicy>> {
+    sqlx::query_as("SELECT * FROM system_prompt_policies WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+fn not_found() -> axum::response::Response {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({ "error": "Policy not found" })),
+    )
+        .into_response()
+}
+
+/// GET /api/admin/system-prompts — List system prompt policies in application order.
+async fn list_policies(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let policies: Vec<SystemPromptPolicy> =
+        match sqlx::query_as("SELECT * FROM system_prompt_policies ORDER BY created_at, id")
+            .fetch_all(&state.db.pool)
+            .await
+        {
+            Ok(policies) => policies,
+            Err(e) => return error::internal_error("system_prompts:list", e),
+        };
+    Json(json!({ "policies": policies.iter().map(policy_json).collect::<Vec<_>>() }))
+        .into_response()
+}
+
+/// POST /api/admin/system-prompts — Add a system prompt policy.
+async fn create_policy(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Json(req): Json<PolicyRequest>,
+) -> impl IntoResponse {
+    if let Some(r) = reject_invalid(&state.db.pool, &req).await {
+        return r;
+    }
+
+    let id = Uuid::new_v4().to_string();
+    if let Err(e) = sqlx::query(
+        "INSERT INTO system_prompt_policies (id, name, prompt, category_id, token_id, enabled, \
+         created_by) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(req.name.trim())
+    .bind(req.prompt.trim())
+    .bind(&req.category_id)
+    .bind(&req.token_id)
+    .bind(req.enabled.unwrap_or(true))
+    .bind(&session.user_id)
+    .execute(&state.db.pool)
+    .await
+    {
+        return error::internal_error("system_prompts:create", e);
+    }
+
+    info!(target: "audit", action = "system_prompt_policy.create", actor = %session.user_id, resource = %id, name = %req.name.trim(), category = ?req.category_id, token = ?req.token_id, "Admin created system prompt policy");
+
+    match fetch_policy(&state.db.pool, &id).await {
+        Ok(Some(policy)) => (StatusCode::CREATED, Json(policy_json(&policy))).into_response(),
+        Ok(None) => error::internal_error("system_prompts:create", "policy missing after insert"),
+        Err(e) => error::internal_error("system_prompts:create", e),
+    }
+}
+
+/// PUT /api/admin/system-prompts/:id — Replace a system prompt policy.
+async fn update_policy(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(req): Json<PolicyRequest>,
+) -> impl IntoResponse {
+    if let Some(r) = reject_invalid(&state.db.pool, &req).await {
+        return r;
+    }
+
+    let updated = match sqlx::query(
+        "UPDATE system_prompt_policies SET name = ?, prompt = ?, category_id = ?, token_id = ?, \
+         enabled = ?, updated_at = datetime('now') WHERE id = ?",
+    )
+    .bind(req.name.trim())
+    .bind(req.prompt.trim())
+    .bind(&req.category_id)
+    .bind(&req.token_id)
+    .bind(req.enabled.unwrap_or(true))
+    .bind(&id)
+    .execute(&state.db.pool)
+    .await
+    {
+        Ok(result) => result.rows_affected(),
+        Err(e) => return error::internal_error("system_prompts:update", e),
+    };
+    if updated == 0 {
+        return not_found();
+    }
+
+    info!(target: "audit", action = "system_prompt_policy.update", actor = %session.user_id, resource = %id, name = %req.name.trim(), enabled = req.enabled.unwrap_or(true), category = ?req.category_id, token = ?req.token_id, "Admin updated system prompt policy");
+
+    match fetch_policy(&state.db.pool, &id).await {
+        Ok(Some(policy)) => Json(policy_json(&policy)).into_response(),
+        Ok(None) => not_found(),
+        Err(e) => error::internal_error("system_prompts:update", e),
+    }
+}
+
+/// DELETE /api/admin/system-prompts/:id — Remove a system prompt policy.
+async fn delete_policy(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match sqlx::query("DELETE FROM system_prompt_policies WHERE id = ?")
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => not_found(),
+        Ok(_) => {
+            info!(target: "audit", action = "system_prompt_policy.delete", actor = %session.user_id, resource = %id, "Admin deleted system prompt policy");
+            Json(json!({ "status": "deleted" })).into_response()
+        }
+        Err(e) => error::internal_error("system_prompts:delete", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OptOutRequest {
+    opt_out: bool,
+}
+
+/// PUT /api/admin/tokens/:id/system-prompt-opt-out — Exempt a token from
+/// every system prompt policy, or revoke the exemption.
+async fn set_token_opt_out(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(id): Path<String>,
+    Json(req): Json<OptOutRequest>,
+) -> impl IntoResponse {
+    match sqlx::query("UPDATE tokens SET system_prompt_opt_out = ? WHERE id = ?")
+        .bind(req.opt_out)
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Token not found" })),
+        )
+            .into_response(),
+        Ok(_) => {
+            info!(target: "audit", action = "token.system_prompt_opt_out", actor = %session.user_id, resource = %id, opt_out = req.opt_out, "Admin changed token system prompt opt-out");
+            Json(json!({ "id": id, "system_prompt_opt_out": req.opt_out })).into_response()
+        }
+        Err(e) => error::internal_error("system_prompts:opt_out", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    fn injected(body: Value, prefix: &str) -> Value {
+        let bytes = inject(&serde_json::to_vec(&body).unwrap(), prefix).unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[test]
+    fn prefixes_existing_system_message() {
+        let out = injected(
+            json!({
+                "model": "m",
+                "messages": [
+                    { "role": "system", "content": "Be brief." },
+                    { "role": "user", "content": "hi" }
+                ]
+            }),
+            "Do not share PII.",
+        );
+        assert_eq!(out["messages"].as_array().unwrap().len(), 2);
+        assert_eq!(
+            out["messages"][0]["content"],
+            "Do not share PII.\n\nBe brief."
+        );
+        assert_eq!(out["model"], "m");
+    }
+
+    #[test]
+    fn adds_system_message_when_missing() {
+        let out = injected(
+            json!({ "messages": [{ "role": "user", "content": "hi" }] }),
+            "Disclaimer",
+        );
+        assert_eq!(
+            out["messages"][0],
+            json!({ "role": "system", "content": "Disclaimer" })
+        );
+        assert_eq!(out["messages"][1]["role"], "user");
+    }
+
+    #[test]
+    fn prefixes_content_parts() {
+        let out = injected(
+            json!({ "messages": [{
+                "role": "system",
+                "content": [{ "type": "text", "text": "Be brief." }]
+            }] }),
+            "Disclaimer",
+        );
+        assert_eq!(
+            out["messages"][0]["content"],
+            json!([
+                { "type": "text", "text": "Disclaimer" },
+                { "type": "text", "text": "Be brief." }
+            ])
+        );
+    }
+
+    #[test]
+    fn leaves_bodies_without_messages_alone() {
+        assert!(inject(br#"{"prompt":"hi"}"#, "x").is_none());
+        assert!(inject(b"not json", "x").is_none());
+    }
+
+    #[tokio::test]
+    async fn policies_match_category_and_token_unless_opted_out() {
+        let db = Database::test_db().await;
+        for sql in [
+            "INSERT INTO idp_configs (id, name, issuer, client_id, client_secret_enc) \
+             VALUES ('idp', 'test', 'https://test', 'client', 'secret')",
+            "INSERT INTO users (id, idp_id, subject) VALUES ('u1', 'idp', 'u1')",
+            "INSERT INTO model_categories (id, name) VALUES ('legal', 'legal')",
+            "INSERT INTO tokens (id, user_id, name, token_hash) VALUES ('t1', 'u1', 'a', 'h1')",
+            "INSERT INTO tokens (id, user_id, name, token_hash) VALUES ('t2', 'u1', 'b', 'h2')",
+            "INSERT INTO system_prompt_policies (id, name, prompt, created_at) \
+             VALUES ('all', 'all', 'Global', '2026-01-01 00:00:00')",
+            "INSERT INTO system_prompt_policies (id, name, prompt, category_id, created_at) \
+             VALUES ('cat', 'cat', 'Legal', 'legal', '2026-01-02 00:00:00')",
+            "INSERT INTO system_prompt_policies (id, name, prompt, token_id, created_at) \
+             VALUES ('tok', 'tok', 'Token', 't2', '2026-01-03 00:00:00')",
+            "INSERT INTO system_prompt_policies (id, name, prompt, enabled) \
+             VALUES ('off', 'off', 'Disabled', 0)",
+        ] {
+            sqlx::query(sql).execute(&db.pool).await.unwrap();
+        }
+
+        let ids = |policies: Vec<SystemPromptPolicy>| {
+            policies.into_iter().map(|p| p.id).collect::<Vec<_>>()
+        };
+        let t1 = applicable_policies(&db.pool, None, "t1").await.unwrap();
+        assert_eq!(ids(t1), ["all"]);
+        let t2 = applicable_policies(&db.pool, Some("legal"), "t2")
+            .await
+            .unwrap();
+        assert_eq!(combined_prompt(&t2), "Global\n\nLegal\n\nToken");
+
+        sqlx::query("UPDATE tokens SET system_prompt_opt_out = 1 WHERE id = 't2'")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let opted_out = applicable_policies(&db.pool, Some("legal"), "t2")
+            .await
+            .unwrap();
+        assert!(opted_out.is_empty());
+    }
+}
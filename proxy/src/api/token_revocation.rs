@@ -0,0 +1,247 @@
+//! Bulk API token revocation (`POST /api/admin/tokens/revoke`).
+//!
+//! For a suspected key leak: every active token matching the filters (user,
+//! creation time, last use, category or model scope) is revoked in one
+//! transaction. `?dry_run=true` lists the matches without touching them, so
+//! the blast radius can be checked first. Internal and meta tokens are never
+//! matched; they aren't handed to clients.
+
+use std::sync::Arc;
+
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{middleware, Extension, Json, Router};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::SqliteConnection;
+use tracing::info;
+
+use super::error;
+use crate::auth::rbac::{self, Permission};
+use crate::auth::{self, SessionAuth};
+use crate::AppState;
+
+pub fn admin_routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/tokens/revoke", post(revoke_tokens))
+        .route_layer(middleware::from_fn_with_state(
+            Permission::Users,
+            rbac::require_permission,
+        ))
+        .with_state(state)
+}
+
+/// Which tokens to revoke. Filters combine with AND; at least one is needed.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TokenFilter {
+    pub user_id: Option<String>,
+    /// Tokens created before this time (RFC 3339 or `YYYY-MM-DD`).
+    pub created_before: Option<String>,
+    /// Tokens not used since this time, including tokens never used.
+    pub last_used_before: Option<String>,
+    /// Tokens scoped to this category.
+    pub category_id: Option<String>,
+    /// Tokens pinned to this model.
+    pub model_id: Option<String>,
+}
+
+impl TokenFilter {
+    fn is_empty(&self) -> bool {
+        self.user_id.is_none()
+            && self.created_before.is_none()
+            && self.last_used_before.is_none()
+            && self.category_id.is_none()
+            && self.model_id.is_none()
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RevokeQuery {
+    /// List the matching tokens without revoking them.
+    #[serde(default)]
+    dry_run: bool,
+}
+
+/// An active token matched by a filter.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct MatchedToken {
+    pub id: String,
+    pub name: String,
+    pub user_id: String,
+    pub user_email: Option<String>,
+    pub category_id: Option<String>,
+    pub specific_model_id: Option<String>,
+    pub created_at: String,
+    /// Latest request in the usage log; `None` if never used.
+    pub last_used_at: Option<String>,
+}
+
+/// Normalize a filter time to the `YYYY-MM-DD HH:MM:SS` UTC form the DB
+/// stores. `None` if it can't be parsed.
+fn parse_time(value: &str) -> Option<String> {
+    const DB_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+    if let Ok(t) = DateTime::parse_from_rfc3339(value) {
+        return Some(t.with_timezone(&Utc).format(DB_FORMAT).to_string());
+    }
+    if let Ok(t) = NaiveDateTime::parse_from_str(value, DB_FORMAT) {
+        return Some(t.format(DB_FORMAT).to_string());
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .map(|d| format!("{} 00:00:00", d.format("%Y-%m-%d")))
+}
+
+/// Active tokens matching the filter, oldest first. Times must already be
+/// normalized with [`parse_time`].
+pub(crate) async fn matching_tokens(
+    conn: &mut SqliteConnection,
+    filter: &TokenFilter,
+) -> Result<Vec<MatchedToken>, sqlx::Error> {
+    sqlx::query_as::<_, MatchedToken>(
+        r#"
+        SELECT * FROM (
+            SELECT t.id, t.name, t.user_id, u.email AS user_email, t.category_id,
+                   t.specific_model_id, t.created_at,
+                   (SELECT MAX(l.created_at) FROM usage_log l WHERE l.token_id = t.id) AS last_used_at
+            FROM tokens t
+            LEFT JOIN users u ON u.id = t.user_id
+            WHERE t.revoked = 0 AND t.deleted_at IS NULL AND t.internal = 0 AND t.meta = 0
+        )
+        WHERE (?1 IS NULL OR user_id = ?1)
+          AND (?2 IS NULL OR created_at < ?2)
+          AND (?3 IS NULL OR last_used_at IS NULL OR last_used_at < ?3)
+          AND (?4 IS NULL OR category_id = ?4)
+          AND (?5 IS NULL OR specific_model_id = ?5)
+        ORDER BY created_at
+        "#,
+    )
+    .bind(&filter.user_id)
+    .bind(&filter.created_before)
+    .bind(&filter.last_used_before)
+    .bind(&filter.category_id)
+    .bind(&filter.model_id)
+    .fetch_all(conn)
+    .await
+}
+
+/// POST /api/admin/tokens/revoke — Revoke every active token matching the
+/// filters, or with `?dry_run=true` list them.
+async fn revoke_tokens(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Query(query): Query<RevokeQuery>,
+    Json(mut filter): Json<TokenFilter>,
+) -> Response {
+    if filter.is_empty() {
+        return bad_request("At least one filter is required");
+    }
+    for (field, value) in [
+        ("created_before", &mut filter.created_before),
+        ("last_used_before", &mut filter.last_used_before),
+    ] {
+        if let Some(raw) = value.as_deref() {
+            match parse_time(raw) {
+                Some(normalized) => *value = Some(normalized),
+                None => {
+                    return bad_request(&format!(
+                        "{field} must be an RFC 3339 time or a YYYY-MM-DD date"
+                    ))
+                }
+            }
+        }
+    }
+
+    if query.dry_run {
+        let mut conn = match state.db.pool.acquire().await {
+            Ok(conn) => conn,
+            Err(e) => return error::internal_error("revoke_tokens:dry_run", e),
+        };
+        return match matching_tokens(&mut *conn, &filter).await {
+            Ok(tokens) => Json(json!({
+                "dry_run": true,
+                "count": tokens.len(),
+                "tokens": tokens,
+            }))
+            .into_response(),
+            Err(e) => error::internal_error("revoke_tokens:dry_run", e),
+        };
+    }
+
+    let tokens = match revoke_matching(&state, &filter).await {
+        Ok(tokens) => tokens,
+        Err(e) => return error::internal_error("revoke_tokens", e),
+    };
+
+    let filter_json = serde_json::to_string(&filter).unwrap_or_default();
+    info!(target: "audit", action = "token.bulk_revoke", actor = %session.user_id, filter = %filter_json, revoked = tokens.len(), "Admin bulk-revoked API tokens");
+    Json(json!({
+        "revoked": tokens.len(),
+        "tokens": tokens,
+    }))
+    .into_response()
+}
+
+/// Revoke every active token matching the filter in one transaction and
+/// return them.
+pub(crate) async fn revoke_matching(
+    state: &AppState,
+    filter: &TokenFilter,
+) -> Result<Vec<MatchedToken>, sqlx::Error> {
+    let mut tx = state.db.pool.begin().await?;
+    let tokens = matching_tokens(&mut *tx, filter).await?;
+    for token in &tokens {
+        sqlx::query("UPDATE tokens SET revoked = 1 WHERE id = ?")
+            .bind(&token.id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    for token in &tokens {
+        auth::token_changed(state, &token.id);
+    }
+    Ok(tokens)
+}
+
+fn bad_request(msg: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": msg }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_time_normalizes_to_db_format() {
+        assert_eq!(
+            parse_time("2026-10-01T12:30:00+02:00").as_deref(),
+            Some("2026-10-01 10:30:00")
+        );
+        assert_eq!(
+            parse_time("2026-10-01T12:30:00Z").as_deref(),
+            Some("2026-10-01 12:30:00")
+        );
+        assert_eq!(
+            parse_time("2026-10-01 12:30:00").as_deref(),
+            Some("2026-10-01 12:30:00")
+        );
+        assert_eq!(
+            parse_time("2026-10-01").as_deref(),
+            Some("2026-10-01 00:00:00")
+        );
+        assert!(parse_time("yesterday").is_none());
+        assert!(parse_time("2026-13-01").is_none());
+    }
+
+    #[test]
+    fn empty_filter_detected() {
+        assert!(TokenFilter::default().is_empty());
+        let filter = TokenFilter {
+            model_id: Some("m".to_string()),
+            ..Default::default()
+        };
+        assert!(!filter.is_empty());
+    }
+}
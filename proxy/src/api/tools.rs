@@ -0,0 +1,234 @@
+//! Code-execution tool backend for agents.
+//!
+//! `POST /v1/tools/execute` runs a snippet in a throwaway sandbox container
+//! (see `docker::sandbox`): no network, no capabilities, read-only root
+//! filesystem, and CPU, memory, process and time limits. It is off unless
+//! `SANDBOX_ENABLED=true`, and at most `SANDBOX_MAX_CONCURRENT` runs happen
+//! at once.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use tokio::sync::Semaphore;
+use tracing::{error, info};
+
+use crate::auth::AuthUser;
+use crate::docker::sandbox::SandboxLimits;
+use crate::AppState;
+
+/// Largest snippet accepted. It is passed as a command-line argument, which
+/// Linux caps at 128 KiB.
+const MAX_CODE_BYTES: usize = 64 * 1024;
+
+#[derive(Clone)]
+struct ToolsState {
+    app: Arc<AppState>,
+    /// One permit per sandbox run allowed at once.
+    slots: Arc<Semaphore>,
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    let slots = Arc::new(Semaphore::new(state.config.sandbox_max_concurrent));
+    Router::new()
+        .route("/tools/execute", post(execute))
+        .with_state(ToolsState { app: state, slots })
+}
+
+#[derive(Debug, Deserialize)]
+struct ExecuteRequest {
+    #[serde(default = "default_language")]
+    language: String,
+    code: String,
+    /// Lower the time limit for this run; capped at `SANDBOX_TIMEOUT_SECS`.
+    timeout_secs: Option<u64>,
+}
+
+fn default_language() -> String {
+    "python".to_string()
+}
+
+impl ExecuteRequest {
+    /// The command to run and the time limit, or an error code and message.
+    fn plan(
+        &self,
+        max_timeout_secs: u64,
+    ) -> Result<(Vec<String>, Duration), (&'static str, String)> {
+        if self.language != "python" {
+            return Err((
+                "unsupported_language",
+                format!(
+                    "Language '{}' is not supported; use 'python'",
+                    self.language
+                ),
+            ));
+        }
+        if self.code.trim().is_empty() {
+            return Err(("invalid_code", "code is required".to_string()));
+        }
+        if self.code.len() > MAX_CODE_BYTES {
+            return Err((
+                "code_too_large",
+                format!("code must be at most {MAX_CODE_BYTES} bytes"),
+            ));
+        }
+        if self.timeout_secs == Some(0) {
+            return Err((
+                "invalid_timeout",
+                "timeout_secs must be positive".to_string(),
+            ));
+        }
+        let timeout = self
+            .timeout_secs
+            .map_or(max_timeout_secs, |t| t.min(max_timeout_secs));
+        // -I: ignore PYTHON* env vars and the user site directory
+        let cmd = vec![
+            "python3".to_string(),
+            "-I".to_string(),
+            "-c".to_string(),
+            self.code.clone(),
+        ];
+        Ok((cmd, Duration::from_secs(timeout)))
+    }
+}
+
+fn tool_error(status: StatusCode, error_type: &str, code: &str, message: String) -> Response {
+    (
+        status,
+        Json(serde_json::json!({
+            "error": {
+                "message": message,
+                "type": error_type,
+                "code": code
+            }
+        })),
+    )
+        .into_response()
+}
+
+/// POST /v1/tools/execute — Run a code snippet in a sandbox container.
+async fn execute(
+    State(state): State<ToolsState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<ExecuteRequest>,
+) -> impl IntoResponse {
+    let config = &state.app.config;
+    if !config.sandbox_enabled {
+        return tool_error(
+            StatusCode::NOT_FOUND,
+            "invalid_request_error",
+            "sandbox_disabled",
+            "Code execution is not enabled on this server".to_string(),
+        );
+    }
+
+    let (cmd, timeout) = match req.plan(config.sandbox_timeout_secs) {
+        Ok(plan) => plan,
+        Err((code, message)) => {
+            return tool_error(
+                StatusCode::BAD_REQUEST,
+                "invalid_request_error",
+                code,
+                message,
+            )
+        }
+    };
+
+    let Ok(_permit) = state.slots.try_acquire() else {
+        return tool_error(
+            StatusCode::TOO_MANY_REQUESTS,
+            "rate_limit_error",
+            "sandbox_busy",
+            "All sandboxes are busy, retry shortly".to_string(),
+        );
+    };
+
+    let limits = SandboxLimits {
+        image: config.sandbox_image.clone(),
+        memory_mb: config.sandbox_memory_mb,
+        cpus: config.sandbox_cpus,
+        timeout,
+    };
+    let output = match state.app.docker.run_sandboxed(&limits, cmd).await {
+        Ok(output) => output,
+        Err(e) => {
+            error!(user = %auth_user.user_id, error = %e, "Sandbox run failed");
+            return tool_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "server_error",
+                "sandbox_failed",
+                "Failed to run the sandbox".to_string(),
+            );
+        }
+    };
+
+    info!(
+        target: "audit",
+        action = "sandbox.execute",
+        actor = %auth_user.user_id,
+        resource = %auth_user.token_id,
+        language = %req.language,
+        code_bytes = req.code.len(),
+        exit_code = ?output.exit_code,
+        timed_out = output.timed_out,
+        duration_ms = output.duration.as_millis() as u64,
+        "Ran code in sandbox"
+    );
+
+    Json(serde_json::json!({
+        "object": "tool.execution",
+        "language": req.language,
+        "exit_code": output.exit_code,
+        "stdout": output.stdout,
+        "stderr": output.stderr,
+        "timed_out": output.timed_out,
+        "truncated": output.truncated,
+        "duration_ms": output.duration.as_millis() as u64,
+    }))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(language: &str, code: &str, timeout_secs: Option<u64>) -> ExecuteRequest {
+        ExecuteRequest {
+            language: language.to_string(),
+            code: code.to_string(),
+            timeout_secs,
+        }
+    }
+
+    #[test]
+    fn plan_runs_python_in_isolated_mode() {
+        let (cmd, timeout) = request("python", "print(1)", None).plan(10).unwrap();
+        assert_eq!(cmd, ["python3", "-I", "-c", "print(1)"]);
+        assert_eq!(timeout, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn plan_caps_requested_timeout() {
+        let (_, timeout) = request("python", "1", Some(3)).plan(10).unwrap();
+        assert_eq!(timeout, Duration::from_secs(3));
+        let (_, timeout) = request("python", "1", Some(60)).plan(10).unwrap();
+        assert_eq!(timeout, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn plan_rejects_bad_requests() {
+        let code = |r: ExecuteRequest| r.plan(10).unwrap_err().0;
+        assert_eq!(code(request("bash", "ls", None)), "unsupported_language");
+        assert_eq!(code(request("python", "  ", None)), "invalid_code");
+        assert_eq!(
+            code(request("python", &"x".repeat(MAX_CODE_BYTES + 1), None)),
+            "code_too_large"
+        );
+        assert_eq!(code(request("python", "1", Some(0))), "invalid_timeout");
+    }
+}
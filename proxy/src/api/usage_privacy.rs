@@ -0,0 +1,209 @@
+//! User labelling for the admin usage analytics.
+//!
+//! The `analytics_privacy` setting decides what `/api/admin/usage` and
+//! `/api/admin/usage/timeline` show in place of a user's name: `off` keeps
+//! names, `pseudonymize` gives each user a stable `user-xxxxxxxx` label and
+//! `bucket` merges users into bands by request count. Pseudonyms are an HMAC
+//! of the user ID under a random per-install salt kept in `settings`, so they
+//! can't be matched to users by hashing the IDs from the user list. Holders
+//! of the `usage_auditor` role always see names.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use rand::RngExt;
+use serde::Serialize;
+use sha2::Sha256;
+
+use crate::auth::rbac::Role;
+use crate::auth::SessionAuth;
+use crate::scheduler::settings::AnalyticsPrivacy;
+
+/// `settings` key of the pseudonym salt. Not exposed by the settings API.
+const SALT_KEY: &str = "analytics_pseudonym_salt";
+
+/// One user's usage, or one band's in bucket mode.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub(crate) struct UsageRow {
+    /// Time bucket (timeline only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    pub timestamp: Option<String>,
+    #[serde(skip)]
+    pub user_id: String,
+    pub user_label: String,
+    pub requests: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    /// Users merged into the row (bucket mode only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    pub users: Option<i64>,
+}
+
+/// The privacy mode that applies to `session`: `Off` for usage auditors.
+pub(crate) fn mode_for(session: &SessionAuth, setting: AnalyticsPrivacy) -> AnalyticsPrivacy {
+    if session.roles.contains(&Role::UsageAuditor) {
+        AnalyticsPrivacy::Off
+    } else {
+        setting
+    }
+}
+
+/// Relabel `rows` for `mode`. Row order is kept, except that bucket mode
+/// returns one row per band (per timestamp), busiest band first.
+pub(crate) async fn apply(
+    pool: &sqlx::SqlitePool,
+    mode: AnalyticsPrivacy,
+    rows: Vec<UsageRow>,
+) -> Result<Vec<UsageRow>> {
+    match mode {
+        AnalyticsPrivacy::Off => Ok(rows),
+        AnalyticsPrivacy::Pseudonymize => {
+            let salt = salt(pool).await?;
+            Ok(rows
+                .into_iter()
+                .map(|row| UsageRow {
+                    user_label: pseudonym(&salt, &row.user_id),
+                    ..row
+                })
+                .collect())
+        }
+        AnalyticsPrivacy::Bucket => Ok(bucket(rows)),
+    }
+}
+
+/// The install's pseudonym salt, created on first use.
+async fn salt(pool: &sqlx::SqlitePool) -> Result<String> {
+    let bytes: [u8; 32] = rand::rng().random();
+    sqlx::query(
+        "INSERT OR IGNORE INTO settings (key, value, updated_at) VALUES (?, ?, datetime('now'))",
+    )
+    .bind(SALT_KEY)
+    .bind(hex::encode(bytes))
+    .execute(pool)
+    .await?;
+    Ok(
+        sqlx::query_scalar("SELECT value FROM settings WHERE key = ?")
+            .bind(SALT_KEY)
+            .fetch_one(pool)
+            .await?,
+    )
+}
+
+fn pseudonym(salt: &str, user_id: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(salt.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(user_id.as_bytes());
+    let digest = hex::encode(mac.finalize().into_bytes());
+    format!("user-{}", &digest[..8])
+}
+
+/// The band a user with `requests` requests falls in.
+fn band(requests: i64) -> (u8, &'static str) {
+    if requests <= 10 {
+        (0, "1-10 requests")
+    } else if requests <= 100 {
+        (1, "11-100 requests")
+    } else if requests <= 1000 {
+        (2, "101-1000 requests")
+    } else {
+        (3, "over 1000 requests")
+    }
+}
+
+fn bucket(rows: Vec<UsageRow>) -> Vec<UsageRow> {
+    let mut bands: BTreeMap<(Option<String>, std::cmp::Reverse<u8>), UsageRow> = BTreeMap::new();
+    for row in rows {
+        let (rank, label) = band(row.requests);
+        let merged = bands
+            .entry((row.timestamp.clone(), std::cmp::Reverse(rank)))
+            .or_insert_with(|| UsageRow {
+                timestamp: row.timestamp.clone(),
+                user_id: String::new(),
+                user_label: label.to_string(),
+                requests: 0,
+                input_tokens: 0,
+                output_tokens: 0,
+                users: Some(0),
+            });
+        merged.requests += row.requests;
+        merged.input_tokens += row.input_tokens;
+        merged.output_tokens += row.output_tokens;
+        merged.users = merged.users.map(|n| n + 1);
+    }
+    bands.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    fn row(timestamp: Option<&str>, user_id: &str, requests: i64) -> UsageRow {
+        UsageRow {
+            timestamp: timestamp.map(str::to_string),
+            user_id: user_id.to_string(),
+            user_label: format!("{user_id}@example.com"),
+            requests,
+            input_tokens: requests * 10,
+            output_tokens: requests,
+            users: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn pseudonyms_are_stable_and_hide_names() {
+        let db = Database::test_db().await;
+        let rows = vec![row(None, "u1", 5), row(None, "u2", 3)];
+        let first = apply(&db.pool, AnalyticsPrivacy::Pseudonymize, rows.clone())
+            .await
+            .unwrap();
+        let again = apply(&db.pool, AnalyticsPrivacy::Pseudonymize, rows)
+            .await
+            .unwrap();
+        assert!(first[0].user_label.starts_with("user-"));
+        assert_ne!(first[0].user_label, first[1].user_label);
+        assert_eq!(first[0].user_label, again[0].user_label);
+        assert!(!first.iter().any(|r| r.user_label.contains("example.com")));
+    }
+
+    #[test]
+    fn bucket_merges_users_per_band_and_timestamp() {
+        let rows = vec![
+            row(Some("t1"), "u1", 5),
+            row(Some("t1"), "u2", 8),
+            row(Some("t1"), "u3", 500),
+            row(Some("t2"), "u1", 2),
+        ];
+        let bands = bucket(rows);
+        assert_eq!(bands.len(), 3);
+        assert_eq!(bands[0].timestamp.as_deref(), Some("t1"));
+        assert_eq!(bands[0].user_label, "101-1000 requests");
+        assert_eq!(bands[1].user_label, "1-10 requests");
+        assert_eq!(bands[1].requests, 13);
+        assert_eq!(bands[1].users, Some(2));
+        assert_eq!(bands[2].timestamp.as_deref(), Some("t2"));
+    }
+
+    #[test]
+    fn usage_auditors_see_names() {
+        let mut session = SessionAuth {
+            user_id: "a1".into(),
+            is_admin: true,
+            roles: vec![],
+            email: None,
+            display_name: None,
+        };
+        assert_eq!(
+            mode_for(&session, AnalyticsPrivacy::Bucket),
+            AnalyticsPrivacy::Bucket
+        );
+        session.roles.push(Role::UsageAuditor);
+        assert_eq!(
+            mode_for(&session, AnalyticsPrivacy::Bucket),
+            AnalyticsPrivacy::Off
+        );
+    }
+}
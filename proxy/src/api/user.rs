@@ -4,31 +4,39 @@ use axum::extract::{Path, Query, State};
 use axum::http::StatusCode;
 use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::IntoResponse;
-use axum::routing::{delete, get, post};
+use axum::routing::{delete, get, post, put};
 use axum::{Extension, Json, Router};
-use futures::stream::{self, StreamExt};
+use futures::StreamExt;
 use serde::Deserialize;
 use tokio_stream::wrappers::BroadcastStream;
 use tracing::info;
 
 use super::common;
 use super::error;
-use crate::auth::tokens;
-use crate::auth::SessionAuth;
+use crate::auth::{self, devices, tokens, SessionAuth};
 use crate::db::models::TokenListItem;
+use crate::events::AppEvent;
+use crate::scheduler::queue_log;
 use crate::AppState;
 
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/tokens", get(list_tokens).post(create_token))
+        .route("/tokens/quickstart", post(quickstart_token))
+        .route("/tokens/exchange", post(exchange_token))
         .route("/tokens/{id}", delete(delete_token))
         .route("/tokens/{id}/revoke", post(revoke_token))
+        .route("/devices", get(list_devices))
+        .route("/devices/{id}", put(rename_device).delete(revoke_device))
         .route("/usage", get(usage_stats))
         .route("/usage/timeline", get(usage_timeline))
         .route("/categories", get(list_categories))
         .route("/models", get(list_models))
         .route("/disk", get(disk_usage))
+        .route("/queue", get(queued_requests))
+        .route("/queue/interrupted", get(interrupted_requests))
         .route("/events", get(unified_events))
+        .route("/signed-urls", post(sign_url))
         .with_state(state)
 }
 
@@ -42,7 +50,7 @@ async fn list_tokens(
     Extension(session): Extension<SessionAuth>,
 ) -> impl IntoResponse {
     match sqlx::query_as::<_, TokenListItem>(
-        "SELECT t.id, t.name, t.category_id, mc.name AS category_name, t.specific_model_id, t.expires_at, t.revoked, t.created_at FROM tokens t LEFT JOIN model_categories mc ON mc.id = t.category_id WHERE t.user_id = ? AND t.internal = 0 AND t.meta = 0 AND t.deleted_at IS NULL",
+        "SELECT t.id, t.name, t.category_id, mc.name AS category_name, t.specific_model_id, t.expires_at, t.revoked, t.request_quota, t.created_at FROM tokens t LEFT JOIN model_categories mc ON mc.id = t.category_id WHERE t.user_id = ? AND t.internal = 0 AND t.meta = 0 AND t.exchange_client IS NULL AND t.deleted_at IS NULL",
     )
     .bind(&session.user_id)
     .fetch_all(&state.db.pool)
@@ -96,6 +104,126 @@ async fn create_token(
     }
 }
 
+/// POST /api/user/tokens/quickstart — Mint a short-lived, request-capped token
+/// plus copy-paste snippets for a first API call.
+async fn quickstart_token(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+) -> impl IntoResponse {
+    let models: Vec<(String,)> = match sqlx::query_as(
+        "SELECT hf_repo FROM models WHERE loaded = 1 AND draining = 0 ORDER BY last_used_at DESC",
+    )
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(m) => m,
+        Err(e) => return error::internal_error("quickstart_token:models", e),
+    };
+    let models: Vec<String> = models.into_iter().map(|(m,)| m).collect();
+
+    let (token, expires_at) =
+        match tokens::create_quickstart_token(&state.db, &session.user_id).await {
+            Ok(t) => t,
+            Err(e) => return error::internal_error("quickstart_token", e),
+        };
+
+    info!(target: "audit", action = "token.quickstart", actor = %session.user_id, "User created quickstart API token");
+
+    let base_url = format!("{}/v1", state.config.api_external_url());
+    (
+        StatusCode::CREATED,
+        Json(serde_json::json!({
+            "token": token,
+            "name": "Quickstart",
+            "expires_at": expires_at,
+            "request_quota": tokens::QUICKSTART_REQUEST_QUOTA,
+            "quickstart": quickstart_snippets(&base_url, &token, &models),
+            "warning": "Save this token — it cannot be shown again."
+        })),
+    )
+        .into_response()
+}
+
+/// Ready-to-run snippets for the first loaded model (or a placeholder when
+/// none is loaded yet).
+fn quickstart_snippets(base_url: &str, token: &str, models: &[String]) -> serde_json::Value {
+    let model = models.first().map(String::as_str).unwrap_or("<model>");
+    let body = serde_json::json!({
+        "model": model,
+        "messages": [{ "role": "user", "content": "Hello!" }],
+    });
+    let curl = format!(
+        "curl {base_url}/chat/completions \\\n  -H \"Authorization: Bearer {token}\" \\\n  -H \"Content-Type: application/json\" \\\n  -d '{body}'"
+    );
+    let python = format!(
+        "from openai import OpenAI\n\nclient = OpenAI(base_url=\"{base_url}\", api_key=\"{token}\")\nresponse = client.chat.completions.create(\n    model=\"{model}\",\n    messages=[{{\"role\": \"user\", \"content\": \"Hello!\"}}],\n)\nprint(response.choices[0].message.content)\n"
+    );
+    serde_json::json!({
+        "base_url": base_url,
+        "models": models,
+        "curl": curl,
+        "python": python,
+    })
+}
+
+/// RFC 8693 token type for an access token.
+const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+#[derive(Debug, Deserialize)]
+struct ExchangeTokenRequest {
+    /// Name of the embedded app requesting the token (recorded for audit).
+    client: String,
+    category_id: Option<String>,
+    specific_model_id: Option<String>,
+    expires_in: Option<i64>,
+}
+
+/// POST /api/user/tokens/exchange — Exchange the caller's portal session for a
+/// short-lived access token an embedded UI can use against `/v1`.
+///
+/// The response follows the OAuth 2.0 token exchange shape (RFC 8693), so
+/// standard client libraries can consume it.
+async fn exchange_token(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Json(req): Json<ExchangeTokenRequest>,
+) -> impl IntoResponse {
+    let client = req.client.trim();
+    if client.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "client must not be empty" })),
+        )
+            .into_response();
+    }
+    if let Some(r) = error::validate_len("client", client, error::MAX_NAME) {
+        return r;
+    }
+
+    match tokens::create_exchange_token(
+        &state.db,
+        &session.user_id,
+        client,
+        req.category_id.as_deref(),
+        req.specific_model_id.as_deref(),
+        req.expires_in,
+    )
+    .await
+    {
+        Ok((token, expires_in)) => {
+            info!(target: "audit", action = "token.exchange", actor = %session.user_id, client = %client, expires_in, "Session exchanged for embedded-client token");
+            Json(serde_json::json!({
+                "access_token": token,
+                "issued_token_type": ACCESS_TOKEN_TYPE,
+                "token_type": "Bearer",
+                "expires_in": expires_in,
+            }))
+            .into_response()
+        }
+        Err(e) => error::internal_error("exchange_token", e),
+    }
+}
+
 /// POST /api/user/tokens/:id/revoke — Revoke a token.
 async fn revoke_token(
     State(state): State<Arc<AppState>>,
@@ -104,6 +232,7 @@ async fn revoke_token(
 ) -> impl IntoResponse {
     match tokens::revoke_token(&state.db, &token_id, &session.user_id).await {
         Ok(()) => {
+            auth::token_changed(&state, &token_id);
             info!(target: "audit", action = "token.revoke", actor = %session.user_id, resource = %token_id, "User revoked API token");
             Json(serde_json::json!({ "status": "revoked" })).into_response()
         }
@@ -138,6 +267,7 @@ async fn delete_token(
 
     match result {
         Ok(r) if r.rows_affected() > 0 => {
+            auth::token_changed(&state, &token_id);
             info!(target: "audit", action = "token.delete", actor = %session.user_id, resource = %token_id, "User deleted API token");
             Json(serde_json::json!({ "status": "deleted" })).into_response()
         }
@@ -150,6 +280,75 @@ async fn delete_token(
     }
 }
 
+// ---------------------------------------------------------------------------
+// Trusted Devices
+// ---------------------------------------------------------------------------
+
+/// GET /api/user/devices — List the user's remembered devices.
+async fn list_devices(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+) -> impl IntoResponse {
+    match devices::list_devices(&state.db, &session.user_id).await {
+        Ok(list) => Json(serde_json::json!({ "devices": list })).into_response(),
+        Err(e) => error::internal_error("list_devices", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RenameDeviceRequest {
+    name: String,
+}
+
+/// PUT /api/user/devices/:id — Rename a remembered device.
+async fn rename_device(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(device_id): Path<String>,
+    Json(req): Json<RenameDeviceRequest>,
+) -> impl IntoResponse {
+    let name = req.name.trim();
+    if name.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "name must not be empty" })),
+        )
+            .into_response();
+    }
+    if let Some(r) = error::validate_len("name", name, error::MAX_NAME) {
+        return r;
+    }
+    match devices::rename_device(&state.db, &session.user_id, &device_id, name).await {
+        Ok(true) => Json(serde_json::json!({ "status": "renamed" })).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Device not found" })),
+        )
+            .into_response(),
+        Err(e) => error::internal_error("rename_device", e),
+    }
+}
+
+/// DELETE /api/user/devices/:id — Forget a device and end its sessions.
+async fn revoke_device(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Path(device_id): Path<String>,
+) -> impl IntoResponse {
+    match devices::revoke_device(&state.db, &state.sessions, &session.user_id, &device_id).await {
+        Ok(true) => {
+            info!(target: "audit", action = "device.revoke", actor = %session.user_id, resource = %device_id, "User revoked trusted device");
+            Json(serde_json::json!({ "status": "revoked" })).into_response()
+        }
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Device not found" })),
+        )
+            .into_response(),
+        Err(e) => error::internal_error("revoke_device", e),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Usage Stats
 // ---------------------------------------------------------------------------
@@ -386,48 +585,119 @@ async fn disk_usage(State(state): State<Arc<AppState>>) -> impl IntoResponse {
 }
 
 // ---------------------------------------------------------------------------
-// Unified SSE Stream (replaces per-concern SSE endpoints)
+// Queue history
 // ---------------------------------------------------------------------------
 
-/// Merged SSE item from either the metrics or reservations broadcast channel.
-enum UnifiedEvent {
-    Metrics(Box<crate::metrics::MetricsSnapshot>),
-    ReservationsChanged,
+/// GET /api/user/queue — The caller's requests currently waiting for a
+/// slot, with their place in line and predicted wait.
+async fn queued_requests(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+) -> impl IntoResponse {
+    let queued = state.scheduler.user_queue(&session.user_id).await;
+    Json(serde_json::json!({ "queued": queued }))
 }
 
-/// GET /api/user/events — Single SSE stream merging metrics + reservation signals.
+/// GET /api/user/queue/interrupted — The caller's queued requests that were
+/// dropped by a proxy restart within the last day, so clients can resubmit.
+async fn interrupted_requests(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+) -> impl IntoResponse {
+    match queue_log::interrupted_for_user(&state.db, &session.user_id, "-1 day").await {
+        Ok(entries) => Json(serde_json::json!({ "interrupted": entries })).into_response(),
+        Err(e) => error::internal_error("interrupted_requests", e),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Signed Download URLs
+// ---------------------------------------------------------------------------
+
+const DEFAULT_SIGNED_URL_TTL_SECS: i64 = 300;
+
+#[derive(Deserialize)]
+struct SignUrlRequest {
+    /// Path and query of the download, e.g.
+    /// `/api/user/conversations/:id/export?format=markdown`.
+    path: String,
+    ttl_secs: Option<i64>,
+}
+
+/// POST /api/user/signed-urls — Sign a download URL that works without a
+/// session until it expires (see `auth::signed_url`).
+async fn sign_url(
+    State(state): State<Arc<AppState>>,
+    Extension(session): Extension<SessionAuth>,
+    Json(req): Json<SignUrlRequest>,
+) -> impl IntoResponse {
+    let ttl_secs = req.ttl_secs.unwrap_or(DEFAULT_SIGNED_URL_TTL_SECS);
+    match state
+        .signed_urls
+        .sign(&req.path, &session.user_id, ttl_secs)
+    {
+        Ok((path, expires)) => {
+            info!(target: "audit", action = "signed_url.issue", actor = %session.user_id, resource = %req.path, expires, "User signed a download URL");
+            Json(serde_json::json!({
+                "url": format!("{}{path}", state.config.api_external_url()),
+                "path": path,
+                "expires_at": chrono::DateTime::from_timestamp(expires, 0),
+            }))
+            .into_response()
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": e.to_string() })),
+        )
+            .into_response(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Unified SSE Stream (replaces per-concern SSE endpoints)
+// ---------------------------------------------------------------------------
+
+/// GET /api/user/events — Single SSE stream of metrics and bus events.
 ///
 /// Admins receive the full MetricsSnapshot as the `"metrics"` event.
 /// Non-admin users receive only `gpu_memory`, `active_reservation`, and `timestamp`.
 /// Reservation changes are sent as a data-less `"reservations_changed"` event.
+/// Admins also receive the other bus notifications (`model_loaded`,
+/// `download_finished`, `container_crashed`) with the event as JSON data.
+/// Download progress has its own stream at `/api/user/hf/downloads/stream`.
 async fn unified_events(
     State(state): State<Arc<AppState>>,
     Extension(session): Extension<SessionAuth>,
 ) -> Sse<impl futures::Stream<Item = Result<Event, std::convert::Infallible>>> {
     let is_admin = session.is_admin;
 
-    let metrics_stream = BroadcastStream::new(state.metrics.subscribe())
-        .filter_map(|r| async { r.ok().map(|s| UnifiedEvent::Metrics(Box::new(s))) });
-
-    let reservation_stream = BroadcastStream::new(state.reservations.subscribe())
-        .filter_map(|r| async { r.ok().map(|_| UnifiedEvent::ReservationsChanged) });
+    let events = BroadcastStream::new(state.events.subscribe()).filter_map(move |r| async move {
+        r.ok().filter(|e| match e {
+            AppEvent::Metrics { .. } | AppEvent::ReservationChanged => true,
+            AppEvent::DownloadUpdated { .. } => false,
+            _ => is_admin,
+        })
+    });
 
-    let merged = stream::select(metrics_stream, reservation_stream).map(move |item| {
-        Ok(match item {
-            UnifiedEvent::Metrics(snapshot) => {
+    let merged = events.map(move |event| {
+        Ok(match event {
+            AppEvent::Metrics { snapshot } => {
                 let data = if is_admin {
-                    serde_json::to_string(&snapshot).unwrap_or_default()
+                    snapshot.to_string()
                 } else {
-                    serde_json::to_string(&serde_json::json!({
-                        "gpu_memory": snapshot.gpu_memory,
-                        "active_reservation": snapshot.active_reservation,
-                        "timestamp": snapshot.timestamp,
-                    }))
-                    .unwrap_or_default()
+                    serde_json::json!({
+                        "gpu_memory": snapshot["gpu_memory"],
+                        "active_reservation": snapshot["active_reservation"],
+                        "timestamp": snapshot["timestamp"],
+                    })
+                    .to_string()
                 };
                 Event::default().event("metrics").data(data)
             }
-            UnifiedEvent::ReservationsChanged => Event::default().event("reservations_changed"),
+            AppEvent::ReservationChanged => Event::default().event("reservations_changed"),
+            event => Event::default()
+                .event(event.name())
+                .data(serde_json::to_string(&event).unwrap_or_default()),
         })
     });
 
@@ -0,0 +1,602 @@
+//! Built-in vector store for retrieval-augmented generation.
+//!
+//! Collections belong to the token's user and are invisible to everyone
+//! else. Documents are split into overlapping chunks and embedded with the
+//! collection's embedding model through the normal `/v1/embeddings` path, so
+//! token scope, reservations and usage accounting all apply. Storage and
+//! search live in `crate::vector_store`.
+
+use std::sync::Arc;
+
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Extension, Json, Router};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use super::openai;
+use crate::auth::AuthUser;
+use crate::vector_store as store;
+use crate::AppState;
+
+/// Largest document accepted in one ingest request.
+const MAX_DOCUMENT_BYTES: usize = 2 * 1024 * 1024;
+
+/// Most chunks one document may produce.
+const MAX_CHUNKS_PER_DOCUMENT: usize = 4000;
+
+/// Chunks sent to the embedding model per request.
+const EMBED_BATCH: usize = 64;
+
+const MAX_TOP_K: usize = 50;
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/vector_stores",
+            get(list_collections).post(create_collection),
+        )
+        .route(
+            "/vector_stores/{id}",
+            get(get_collection).delete(delete_collection),
+        )
+        .route(
+            "/vector_stores/{id}/documents",
+            get(list_documents).post(ingest_document),
+        )
+        .route(
+            "/vector_stores/{id}/documents/{doc_id}",
+            delete(delete_document),
+        )
+        .route("/vector_stores/{id}/query", post(query))
+        .with_state(state)
+}
+
+fn store_error(status: StatusCode, error_type: &str, code: &str, message: String) -> Response {
+    (
+        status,
+        Json(json!({
+            "error": {
+                "message": message,
+                "type": error_type,
+                "code": code
+            }
+        })),
+    )
+        .into_response()
+}
+
+fn invalid(code: &str, message: String) -> Response {
+    store_error(
+        StatusCode::BAD_REQUEST,
+        "invalid_request_error",
+        code,
+        message,
+    )
+}
+
+fn internal(context: &str, e: impl std::fmt::Display) -> Response {
+    error!(error = %e, "{context}");
+    store_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "server_error",
+        "internal_error",
+        "Internal server error".to_string(),
+    )
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Collection {
+    id: String,
+    name: String,
+    description: String,
+    model: String,
+    dimensions: Option<i64>,
+    document_count: i64,
+    chunk_count: i64,
+    created_at: String,
+    updated_at: String,
+}
+
+const COLLECTION_SELECT: &str = "SELECT c.id, c.name, c.description, c.model, c.dimensions, \
+     (SELECT COUNT(*) FROM vector_documents d WHERE d.collection_id = c.id) AS document_count, \
+     (SELECT COUNT(*) FROM vector_chunks k WHERE k.collection_id = c.id) AS chunk_count, \
+     c.created_at, c.updated_at \
+     FROM vector_collections c";
+
+fn collection_json(c: &Collection) -> Value {
+    json!({
+        "id": c.id,
+        "object": "vector_store",
+        "name": c.name,
+        "description": c.description,
+        "model": c.model,
+        "dimensions": c.dimensions,
+        "document_count": c.document_count,
+        "chunk_count": c.chunk_count,
+        "created_at": c.created_at,
+        "updated_at": c.updated_at,
+    })
+}
+
+/// The caller's collection `id`, or a 404 whether it is missing or belongs
+/// to someone else.
+async fn owned_collection(
+    state: &AppState,
+    auth_user: &AuthUser,
+    id: &str,
+) -> Result<Collection, Response> {
+    let sql = format!("{COLLECTION_SELECT} WHERE c.id = ? AND c.user_id = ?");
+    match sqlx::query_as::<_, Collection>(&sql)
+        .bind(id)
+        .bind(&auth_user.user_id)
+        .fetch_optional(&state.db.pool)
+        .await
+    {
+        Ok(Some(c)) => Ok(c),
+        Ok(None) => Err(store_error(
+            StatusCode::NOT_FOUND,
+            "invalid_request_error",
+            "vector_store_not_found",
+            format!("Vector store '{id}' not found"),
+        )),
+        Err(e) => Err(internal("Failed to load vector store", e)),
+    }
+}
+
+/// GET /v1/vector_stores — The caller's collections.
+async fn list_collections(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    let sql = format!("{COLLECTION_SELECT} WHERE c.user_id = ? ORDER BY c.name");
+    match sqlx::query_as::<_, Collection>(&sql)
+        .bind(&auth_user.user_id)
+        .fetch_all(&state.db.pool)
+        .await
+    {
+        Ok(rows) => Json(json!({
+            "object": "list",
+            "data": rows.iter().map(collection_json).collect::<Vec<_>>(),
+        }))
+        .into_response(),
+        Err(e) => internal("Failed to list vector stores", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateCollectionRequest {
+    name: String,
+    #[serde(default)]
+    description: String,
+    /// Embedding model, named as in `/v1/embeddings`.
+    model: String,
+}
+
+/// POST /v1/vector_stores — Create a collection.
+async fn create_collection(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(req): Json<CreateCollectionRequest>,
+) -> impl IntoResponse {
+    let name = req.name.trim();
+    if name.is_empty() || name.len() > 128 {
+        return invalid("invalid_name", "name must be 1-128 characters".to_string());
+    }
+    if req.model.trim().is_empty() {
+        return invalid("invalid_model", "model is required".to_string());
+    }
+
+    let taken: Option<String> = match sqlx::query_scalar(
+        "SELECT id FROM vector_collections WHERE user_id = ? AND name = ?",
+    )
+    .bind(&auth_user.user_id)
+    .bind(name)
+    .fetch_optional(&state.db.pool)
+    .await
+    {
+        Ok(id) => id,
+        Err(e) => return internal("Failed to check vector store name", e),
+    };
+    if taken.is_some() {
+        return store_error(
+            StatusCode::CONFLICT,
+            "invalid_request_error",
+            "vector_store_exists",
+            format!("A vector store named '{name}' already exists"),
+        );
+    }
+
+    let id = Uuid::new_v4().to_string();
+    if let Err(e) = sqlx::query(
+        "INSERT INTO vector_collections (id, user_id, name, description, model) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(&auth_user.user_id)
+    .bind(name)
+    .bind(&req.description)
+    .bind(req.model.trim())
+    .execute(&state.db.pool)
+    .await
+    {
+        return internal("Failed to create vector store", e);
+    }
+    info!(target: "audit", action = "vector_store.create", actor = %auth_user.user_id, resource = %id, name = %name, "User created vector store");
+
+    match owned_collection(&state, &auth_user, &id).await {
+        Ok(c) => (StatusCode::CREATED, Json(collection_json(&c))).into_response(),
+        Err(r) => r,
+    }
+}
+
+/// GET /v1/vector_stores/:id — One collection.
+async fn get_collection(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match owned_collection(&state, &auth_user, &id).await {
+        Ok(c) => Json(collection_json(&c)).into_response(),
+        Err(r) => r,
+    }
+}
+
+/// DELETE /v1/vector_stores/:id — Delete a collection with its documents.
+async fn delete_collection(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(r) = owned_collection(&state, &auth_user, &id).await {
+        return r;
+    }
+    if let Err(e) = sqlx::query("DELETE FROM vector_collections WHERE id = ?")
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+    {
+        return internal("Failed to delete vector store", e);
+    }
+    info!(target: "audit", action = "vector_store.delete", actor = %auth_user.user_id, resource = %id, "User deleted vector store");
+    Json(json!({ "id": id, "object": "vector_store.deleted", "deleted": true })).into_response()
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Document {
+    id: String,
+    name: String,
+    metadata: String,
+    chunk_count: i64,
+    created_at: String,
+}
+
+fn document_json(d: &Document) -> Value {
+    json!({
+        "id": d.id,
+        "object": "vector_store.document",
+        "name": d.name,
+        "metadata": serde_json::from_str::<Value>(&d.metadata).unwrap_or(json!({})),
+        "chunk_count": d.chunk_count,
+        "created_at": d.created_at,
+    })
+}
+
+/// GET /v1/vector_stores/:id/documents — Documents in a collection.
+async fn list_documents(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    if let Err(r) = owned_collection(&state, &auth_user, &id).await {
+        return r;
+    }
+    match sqlx::query_as::<_, Document>(
+        "SELECT id, name, metadata, chunk_count, created_at FROM vector_documents \
+         WHERE collection_id = ? ORDER BY created_at DESC, id",
+    )
+    .bind(&id)
+    .fetch_all(&state.db.pool)
+    .await
+    {
+        Ok(rows) => Json(json!({
+            "object": "list",
+            "data": rows.iter().map(document_json).collect::<Vec<_>>(),
+        }))
+        .into_response(),
+        Err(e) => internal("Failed to list documents", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IngestRequest {
+    name: String,
+    text: String,
+    /// JSON object stored with the document and returned by queries.
+    metadata: Option<Value>,
+    chunk_size: Option<usize>,
+    chunk_overlap: Option<usize>,
+}
+
+impl IngestRequest {
+    /// The document's chunks, or an error code and message.
+    fn chunks(&self) -> Result<Vec<String>, (&'static str, String)> {
+        if self.name.trim().is_empty() {
+            return Err(("invalid_name", "name is required".to_string()));
+        }
+        if self.text.len() > MAX_DOCUMENT_BYTES {
+            return Err((
+                "document_too_large",
+                format!("text must be at most {MAX_DOCUMENT_BYTES} bytes"),
+            ));
+        }
+        if matches!(&self.metadata, Some(m) if !m.is_object()) {
+            return Err((
+                "invalid_metadata",
+                "metadata must be a JSON object".to_string(),
+            ));
+        }
+        let size = self.chunk_size.unwrap_or(1000);
+        if !(100..=8000).contains(&size) {
+            return Err((
+                "invalid_chunk_size",
+                "chunk_size must be between 100 and 8000".to_string(),
+            ));
+        }
+        let overlap = self.chunk_overlap.unwrap_or(200);
+        if overlap > size / 2 {
+            return Err((
+                "invalid_chunk_overlap",
+                "chunk_overlap must be at most half of chunk_size".to_string(),
+            ));
+        }
+
+        let chunks = store::chunk_text(&self.text, size, overlap);
+        if chunks.is_empty() {
+            return Err(("invalid_text", "text is empty".to_string()));
+        }
+        if chunks.len() > MAX_CHUNKS_PER_DOCUMENT {
+            return Err((
+                "too_many_chunks",
+                format!(
+                    "Document splits into {} chunks; the limit is {MAX_CHUNKS_PER_DOCUMENT}",
+                    chunks.len()
+                ),
+            ));
+        }
+        Ok(chunks)
+    }
+}
+
+/// Embed `inputs` with the collection's model and normalise the vectors.
+/// All of them must have the collection's dimensions, once it has any.
+async fn embed_normalised(
+    state: &Arc<AppState>,
+    auth_user: &AuthUser,
+    collection: &Collection,
+    inputs: &[String],
+) -> Result<Vec<Vec<f32>>, Response> {
+    let mut vectors = Vec::with_capacity(inputs.len());
+    for batch in inputs.chunks(EMBED_BATCH) {
+        let embedded =
+            openai::embed_texts(state.clone(), auth_user.clone(), &collection.model, batch).await?;
+        vectors.extend(embedded);
+    }
+
+    let expected = collection
+        .dimensions
+        .map(|d| d as usize)
+        .or_else(|| vectors.first().map(Vec::len));
+    if let Some(bad) = vectors.iter().find(|v| Some(v.len()) != expected) {
+        return Err(store_error(
+            StatusCode::CONFLICT,
+            "invalid_request_error",
+            "dimension_mismatch",
+            format!(
+                "Model '{}' returned {}-dimensional embeddings; this vector store holds {}",
+                collection.model,
+                bad.len(),
+                expected.unwrap_or_default()
+            ),
+        ));
+    }
+    vectors.iter_mut().for_each(|v| store::normalize(v));
+    Ok(vectors)
+}
+
+/// POST /v1/vector_stores/:id/documents — Chunk, embed and store a document.
+async fn ingest_document(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<String>,
+    Json(req): Json<IngestRequest>,
+) -> impl IntoResponse {
+    let collection = match owned_collection(&state, &auth_user, &id).await {
+        Ok(c) => c,
+        Err(r) => return r,
+    };
+    let chunks = match req.chunks() {
+        Ok(chunks) => chunks,
+        Err((code, message)) => return invalid(code, message),
+    };
+    let vectors = match embed_normalised(&state, &auth_user, &collection, &chunks).await {
+        Ok(v) => v,
+        Err(r) => return r,
+    };
+
+    let doc_id = Uuid::new_v4().to_string();
+    let metadata = req.metadata.clone().unwrap_or(json!({}));
+    let stored: sqlx::Result<()> = async {
+        let mut tx = state.db.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO vector_documents (id, collection_id, name, metadata, chunk_count) \
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&doc_id)
+        .bind(&id)
+        .bind(req.name.trim())
+        .bind(metadata.to_string())
+        .bind(chunks.len() as i64)
+        .execute(&mut *tx)
+        .await?;
+        for (i, (content, vector)) in chunks.iter().zip(&vectors).enumerate() {
+            sqlx::query(
+                "INSERT INTO vector_chunks (collection_id, document_id, chunk_index, content, embedding, bucket) \
+                 VALUES (?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&id)
+            .bind(&doc_id)
+            .bind(i as i64)
+            .bind(content)
+            .bind(store::encode(vector))
+            .bind(store::signature(vector))
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query(
+            "UPDATE vector_collections SET dimensions = COALESCE(dimensions, ?), \
+             updated_at = datetime('now') WHERE id = ?",
+        )
+        .bind(vectors[0].len() as i64)
+        .bind(&id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await
+    }
+    .await;
+    if let Err(e) = stored {
+        return internal("Failed to store document", e);
+    }
+
+    info!(
+        collection = %id,
+        document = %doc_id,
+        chunks = chunks.len(),
+        user_id = %auth_user.user_id,
+        "Ingested document into vector store"
+    );
+
+    let document = Document {
+        id: doc_id,
+        name: req.name.trim().to_string(),
+        metadata: metadata.to_string(),
+        chunk_count: chunks.len() as i64,
+        created_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    };
+    (StatusCode::CREATED, Json(document_json(&document))).into_response()
+}
+
+/// DELETE /v1/vector_stores/:id/documents/:doc_id — Remove a document and
+/// its chunks.
+async fn delete_document(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((id, doc_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    if let Err(r) = owned_collection(&state, &auth_user, &id).await {
+        return r;
+    }
+    match sqlx::query("DELETE FROM vector_documents WHERE id = ? AND collection_id = ?")
+        .bind(&doc_id)
+        .bind(&id)
+        .execute(&state.db.pool)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => store_error(
+            StatusCode::NOT_FOUND,
+            "invalid_request_error",
+            "document_not_found",
+            format!("Document '{doc_id}' not found"),
+        ),
+        Ok(_) => Json(
+            json!({ "id": doc_id, "object": "vector_store.document.deleted", "deleted": true }),
+        )
+        .into_response(),
+        Err(e) => internal("Failed to delete document", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryRequest {
+    query: String,
+    top_k: Option<usize>,
+}
+
+/// POST /v1/vector_stores/:id/query — The chunks most similar to `query`.
+async fn query(
+    State(state): State<Arc<AppState>>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<String>,
+    Json(req): Json<QueryRequest>,
+) -> impl IntoResponse {
+    let collection = match owned_collection(&state, &auth_user, &id).await {
+        Ok(c) => c,
+        Err(r) => return r,
+    };
+    if req.query.trim().is_empty() {
+        return invalid("invalid_query", "query is required".to_string());
+    }
+    let top_k = req.top_k.unwrap_or(5);
+    if !(1..=MAX_TOP_K).contains(&top_k) {
+        return invalid(
+            "invalid_top_k",
+            format!("top_k must be between 1 and {MAX_TOP_K}"),
+        );
+    }
+    if collection.chunk_count == 0 {
+        return Json(json!({ "object": "list", "data": [] })).into_response();
+    }
+
+    let vector = match embed_normalised(&state, &auth_user, &collection, &[req.query]).await {
+        Ok(mut v) => v.remove(0),
+        Err(r) => return r,
+    };
+    match store::search(&state.db.pool, &id, &vector, top_k).await {
+        Ok(hits) => Json(json!({ "object": "list", "data": hits })).into_response(),
+        Err(e) => internal("Vector search failed", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(text: &str, chunk_size: Option<usize>, overlap: Option<usize>) -> IngestRequest {
+        IngestRequest {
+            name: "notes.txt".to_string(),
+            text: text.to_string(),
+            metadata: None,
+            chunk_size,
+            chunk_overlap: overlap,
+        }
+    }
+
+    #[test]
+    fn ingest_splits_with_defaults() {
+        let text = "word ".repeat(500);
+        let chunks = request(&text, None, None).chunks().unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks.iter().all(|c| c.len() <= 1000));
+    }
+
+    #[test]
+    fn ingest_rejects_bad_requests() {
+        let code = |r: IngestRequest| r.chunks().unwrap_err().0;
+        assert_eq!(code(request("  ", None, None)), "invalid_text");
+        assert_eq!(code(request("x", Some(10), None)), "invalid_chunk_size");
+        assert_eq!(
+            code(request("x", Some(200), Some(150))),
+            "invalid_chunk_overlap"
+        );
+        assert_eq!(
+            code(request(&"x".repeat(MAX_DOCUMENT_BYTES + 1), None, None)),
+            "document_too_large"
+        );
+        let mut r = request("x", None, None);
+        r.metadata = Some(json!(["not", "an", "object"]));
+        assert_eq!(code(r), "invalid_metadata");
+    }
+}
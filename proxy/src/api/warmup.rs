@@ -0,0 +1,189 @@
+//! Backend slot warm-up.
+//!
+//! A llama.cpp slot's first request is noticeably slower than later ones.
+//! With `BACKEND_WARMUP=true`, once a started container is healthy and has
+//! been probed (see [`super::capabilities`]), every slot gets a tiny request
+//! of its own, pinned to it with `id_slot`, so users don't pay that cost.
+//! vLLM has no slots and gets a single request. The outcome is shown per
+//! container in the admin system status and `metrics` events.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use super::capabilities::Backend;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WarmupState {
+    /// Waiting for the container to become healthy.
+    Pending,
+    Warming,
+    Warm,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WarmupStatus {
+    pub state: WarmupState,
+    /// Slots sent a warm-up request; 0 until warming starts.
+    pub slots: u32,
+    /// Slots whose warm-up request succeeded.
+    pub slots_warmed: u32,
+    pub duration_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl WarmupStatus {
+    fn new(state: WarmupState) -> Self {
+        Self {
+            state,
+            slots: 0,
+            slots_warmed: 0,
+            duration_ms: None,
+            error: None,
+        }
+    }
+}
+
+/// Latest warm-up per model, held in `AppState`. An entry outlives its
+/// container, but only containers that exist are shown.
+#[derive(Debug, Clone, Default)]
+pub struct Warmups(Arc<Mutex<HashMap<String, WarmupStatus>>>);
+
+impl Warmups {
+    fn set(&self, model_id: &str, status: WarmupStatus) {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(model_id.to_string(), status);
+    }
+
+    /// The model's latest warm-up, if one was started since the proxy started.
+    pub fn status(&self, model_id: &str) -> Option<WarmupStatus> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(model_id)
+            .cloned()
+    }
+
+    /// A container was started; warm-up follows once it is healthy.
+    pub fn pending(&self, model_id: &str) {
+        self.set(model_id, WarmupStatus::new(WarmupState::Pending));
+    }
+
+    /// The container never became healthy.
+    pub fn failed(&self, model_id: &str, error: &str) {
+        self.set(
+            model_id,
+            WarmupStatus {
+                error: Some(error.to_string()),
+                ..WarmupStatus::new(WarmupState::Failed)
+            },
+        );
+    }
+}
+
+/// The request that warms up `slot`, or any slot when `None`.
+fn warmup_request(model_id: &str, embedding: bool, slot: Option<u32>) -> (&'static str, Value) {
+    let (path, mut body) = if embedding {
+        (
+            "/v1/embeddings",
+            json!({ "model": model_id, "input": "warm-up" }),
+        )
+    } else {
+        (
+            "/v1/chat/completions",
+            json!({
+                "model": model_id,
+                "messages": [{ "role": "user", "content": "Hi" }],
+                "max_tokens": 4,
+            }),
+        )
+    };
+    if let Some(slot) = slot {
+        body["id_slot"] = slot.into();
+    }
+    (path, body)
+}
+
+/// Send every slot its warm-up request, all at once, and record the result.
+pub(super) async fn run(warmups: &Warmups, backend: &Backend, model_id: &str) {
+    let slots: Vec<Option<u32>> = if backend.backend_type == "llamacpp" {
+        (0..backend.slots).map(Some).collect()
+    } else {
+        vec![None]
+    };
+    let total = slots.len() as u32;
+    warmups.set(
+        model_id,
+        WarmupStatus {
+            slots: total,
+            ..WarmupStatus::new(WarmupState::Warming)
+        },
+    );
+
+    let started = Instant::now();
+    let results = futures::future::join_all(slots.into_iter().map(|slot| {
+        let (path, body) = warmup_request(model_id, backend.embedding, slot);
+        async move { backend.post(path, &body).await.is_some() }
+    }))
+    .await;
+    let warmed = results.iter().filter(|ok| **ok).count() as u32;
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    let status = if warmed == total {
+        info!(model = %model_id, slots = total, duration_ms, "Warmed up backend slots");
+        WarmupStatus {
+            state: WarmupState::Warm,
+            slots: total,
+            slots_warmed: warmed,
+            duration_ms: Some(duration_ms),
+            error: None,
+        }
+    } else {
+        warn!(model = %model_id, slots = total, warmed, duration_ms, "Backend warm-up failed for some slots");
+        WarmupStatus {
+            state: WarmupState::Failed,
+            slots: total,
+            slots_warmed: warmed,
+            duration_ms: Some(duration_ms),
+            error: Some(format!("{} of {total} slots failed", total - warmed)),
+        }
+    };
+    warmups.set(model_id, status);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pins_requests_to_slots() {
+        let (path, body) = warmup_request("m1", false, Some(2));
+        assert_eq!(path, "/v1/chat/completions");
+        assert_eq!(body["id_slot"], 2);
+        assert_eq!(body["max_tokens"], 4);
+
+        let (path, body) = warmup_request("m1", true, None);
+        assert_eq!(path, "/v1/embeddings");
+        assert!(body.get("id_slot").is_none());
+    }
+
+    #[test]
+    fn records_latest_status() {
+        let warmups = Warmups::default();
+        warmups.pending("m1");
+        assert_eq!(warmups.status("m1").unwrap().state, WarmupState::Pending);
+        warmups.failed("m1", "not healthy");
+        let latest = warmups.status("m1").unwrap();
+        assert_eq!(latest.state, WarmupState::Failed);
+        assert_eq!(latest.error.as_deref(), Some("not healthy"));
+        assert!(warmups.status("m2").is_none());
+    }
+}
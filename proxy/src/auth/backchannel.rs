@@ -0,0 +1,295 @@
+//! OIDC Back-Channel Logout (`POST /auth/backchannel-logout`).
+//!
+//! When a user signs out at the IdP (or is disabled there), the IdP posts a
+//! signed logout token here. The token's signature is checked against the
+//! IdP's published keys, then every session and trusted device of the user
+//! it names is deleted. Sessions aren't tied to an IdP session, so a token
+//! must carry `sub`; `sid`-only tokens are rejected. Logging out is
+//! idempotent, so replayed tokens aren't tracked.
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Form, Json};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use openidconnect::core::{CoreJwsSigningAlgorithm, CoreProviderMetadata};
+use openidconnect::{IssuerUrl, JsonWebKey};
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::auth::{groups, sessions};
+use crate::AppState;
+
+/// The `events` member that marks a JWT as a logout token.
+const LOGOUT_EVENT: &str = "http://schemas.openid.net/event/backchannel-logout";
+
+/// Logout tokens issued longer ago than this are refused.
+const MAX_TOKEN_AGE_SECS: i64 = 600;
+
+/// Clock skew allowed between us and the IdP.
+const CLOCK_SKEW_SECS: i64 = 60;
+
+#[derive(Deserialize)]
+pub(super) struct LogoutForm {
+    logout_token: String,
+}
+
+#[derive(Deserialize)]
+struct JoseHeader {
+    alg: String,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+/// POST /auth/backchannel-logout — Revoke the sessions of the user named by
+/// an IdP's logout token.
+pub(super) async fn logout(
+    State(state): State<Arc<AppState>>,
+    Form(form): Form<LogoutForm>,
+) -> Response {
+    match revoke_from_token(&state, &form.logout_token).await {
+        Ok(()) => (
+            StatusCode::OK,
+            [(header::CACHE_CONTROL, "no-store")],
+            Json(serde_json::json!({ "status": "logged_out" })),
+        )
+            .into_response(),
+        Err(e) => {
+            warn!(target: "audit", action = "auth.backchannel_logout_rejected", error = %e, "Back-channel logout token rejected");
+            (
+                StatusCode::BAD_REQUEST,
+                [(header::CACHE_CONTROL, "no-store")],
+                Json(serde_json::json!({ "error": "invalid_request", "error_description": e.to_string() })),
+            )
+                .into_response()
+        }
+    }
+}
+
+async fn revoke_from_token(state: &AppState, token: &str) -> Result<()> {
+    let mut parts = token.split('.');
+    let (Some(header_b64), Some(payload_b64), Some(signature_b64), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        bail!("Logout token is not a signed JWT");
+    };
+    let header: JoseHeader = serde_json::from_slice(
+        &URL_SAFE_NO_PAD
+            .decode(header_b64)
+            .context("Malformed token header")?,
+    )
+    .context("Malformed token header")?;
+    let payload = groups::token_payload(token).context("Malformed token payload")?;
+
+    // Which IdP? Only decides whose keys to check the signature against.
+    let issuer = payload
+        .get("iss")
+        .and_then(Value::as_str)
+        .context("Logout token has no iss")?;
+    let (idp_id, idp_issuer, client_id) = find_idp(state, issuer)
+        .await?
+        .ok_or_else(|| anyhow!("No enabled IdP with issuer {issuer}"))?;
+
+    let signature = URL_SAFE_NO_PAD
+        .decode(signature_b64)
+        .context("Malformed token signature")?;
+    verify_signature(
+        &idp_issuer,
+        &header,
+        format!("{header_b64}.{payload_b64}").as_bytes(),
+        &signature,
+    )
+    .await?;
+
+    let subject = check_claims(
+        &payload,
+        &idp_issuer,
+        &client_id,
+        chrono::Utc::now().timestamp(),
+    )
+    .map_err(|e| anyhow!(e))?;
+
+    let user_id: Option<String> =
+        sqlx::query_scalar("SELECT id FROM users WHERE idp_id = ? AND subject = ?")
+            .bind(&idp_id)
+            .bind(&subject)
+            .fetch_optional(&state.db.pool)
+            .await?;
+    // A user who never logged in has nothing to revoke
+    let Some(user_id) = user_id else {
+        return Ok(());
+    };
+    let revoked = sessions::revoke_user_sessions(&state.sessions, &state.db, &user_id).await?;
+    info!(target: "audit", action = "user.revoke_sessions", actor = %format!("idp:{idp_id}"), resource = %user_id, sessions = revoked, "IdP back-channel logout");
+    Ok(())
+}
+
+/// The enabled IdP with this issuer: `(id, issuer, client_id)`. Trailing
+/// slashes are ignored, as IdPs aren't consistent about them.
+async fn find_idp(state: &AppState, issuer: &str) -> Result<Option<(String, String, String)>> {
+    let idps: Vec<(String, String, String)> =
+        sqlx::query_as("SELECT id, issuer, client_id FROM idp_configs WHERE enabled = 1")
+            .fetch_all(&state.db.pool)
+            .await?;
+    Ok(idps
+        .into_iter()
+        .find(|(_, iss, _)| iss.trim_end_matches('/') == issuer.trim_end_matches('/')))
+}
+
+/// Check the signature with the IdP's published keys. Only asymmetric
+/// algorithms are accepted: the client secret is no proof the IdP sent it.
+async fn verify_signature(
+    issuer: &str,
+    header: &JoseHeader,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<()> {
+    if header.alg == "none" || header.alg.starts_with("HS") {
+        bail!(
+            "Logout token must be signed with the IdP's key, not {}",
+            header.alg
+        );
+    }
+    let alg: CoreJwsSigningAlgorithm = serde_json::from_value(Value::String(header.alg.clone()))
+        .map_err(|_| anyhow!("Unsupported signing algorithm {}", header.alg))?;
+
+    let issuer_url = IssuerUrl::new(issuer.to_string()).context("Invalid issuer URL")?;
+    let metadata =
+        CoreProviderMetadata::discover_async(issuer_url, &super::oidc::build_http_client())
+            .await
+            .context("OIDC discovery failed")?;
+
+    let verified = metadata
+        .jwks()
+        .keys()
+        .iter()
+        .filter(|key| match (&header.kid, key.key_id()) {
+            (Some(kid), Some(key_id)) => kid.as_str() == key_id.as_str(),
+            _ => true,
+        })
+        .any(|key| key.verify_signature(&alg, message, signature).is_ok());
+    if !verified {
+        bail!("Logout token signature doesn't match the IdP's keys");
+    }
+    Ok(())
+}
+
+/// Validate a logout token's claims and return its subject.
+fn check_claims(
+    payload: &Value,
+    issuer: &str,
+    client_id: &str,
+    now: i64,
+) -> Result<String, &'static str> {
+    let iss = payload.get("iss").and_then(Value::as_str).unwrap_or("");
+    if iss.trim_end_matches('/') != issuer.trim_end_matches('/') {
+        return Err("Wrong issuer");
+    }
+    let audience = groups::claim_values(payload, "aud").unwrap_or_default();
+    if !audience.iter().any(|aud| aud == client_id) {
+        return Err("Logout token is not for this client");
+    }
+    let iat = payload
+        .get("iat")
+        .and_then(Value::as_i64)
+        .ok_or("Logout token has no iat")?;
+    if iat > now + CLOCK_SKEW_SECS || iat < now - MAX_TOKEN_AGE_SECS {
+        return Err("Logout token is too old or from the future");
+    }
+    if let Some(exp) = payload.get("exp").and_then(Value::as_i64) {
+        if exp < now - CLOCK_SKEW_SECS {
+            return Err("Logout token has expired");
+        }
+    }
+    if !payload
+        .get("events")
+        .and_then(|events| events.get(LOGOUT_EVENT))
+        .is_some_and(Value::is_object)
+    {
+        return Err("Not a logout token");
+    }
+    // A nonce would make it an ID token, which must never be accepted here
+    if payload.get("nonce").is_some() {
+        return Err("Logout token must not contain a nonce");
+    }
+    match payload.get("sub").and_then(Value::as_str) {
+        Some(sub) if !sub.is_empty() => Ok(sub.to_string()),
+        _ => Err("Logout token has no sub; sid-only logout isn't supported"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const NOW: i64 = 1_800_000_000;
+
+    fn token() -> Value {
+        json!({
+            "iss": "https://idp.example.com/realms/main/",
+            "aud": ["sovereign", "other"],
+            "iat": NOW - 5,
+            "exp": NOW + 120,
+            "jti": "abc",
+            "sub": "user-1",
+            "sid": "s-1",
+            "events": { LOGOUT_EVENT: {} },
+        })
+    }
+
+    fn check(payload: &Value) -> Result<String, &'static str> {
+        check_claims(
+            payload,
+            "https://idp.example.com/realms/main",
+            "sovereign",
+            NOW,
+        )
+    }
+
+    #[test]
+    fn accepts_valid_token() {
+        assert_eq!(check(&token()).unwrap(), "user-1");
+        let mut single_aud = token();
+        single_aud["aud"] = json!("sovereign");
+        assert!(check(&single_aud).is_ok());
+    }
+
+    #[test]
+    fn rejects_wrong_issuer_or_audience() {
+        let mut t = token();
+        t["iss"] = json!("https://evil.example.com");
+        assert!(check(&t).is_err());
+        let mut t = token();
+        t["aud"] = json!("someone-else");
+        assert!(check(&t).is_err());
+    }
+
+    #[test]
+    fn rejects_stale_or_expired() {
+        let mut t = token();
+        t["iat"] = json!(NOW - MAX_TOKEN_AGE_SECS - 1);
+        assert!(check(&t).is_err());
+        let mut t = token();
+        t["exp"] = json!(NOW - CLOCK_SKEW_SECS - 1);
+        assert!(check(&t).is_err());
+    }
+
+    #[test]
+    fn rejects_id_tokens_and_sid_only() {
+        let mut t = token();
+        t["events"] = json!({});
+        assert!(check(&t).is_err());
+        let mut t = token();
+        t["nonce"] = json!("n");
+        assert!(check(&t).is_err());
+        let mut t = token();
+        t.as_object_mut().unwrap().remove("sub");
+        assert!(check(&t).is_err());
+    }
+}
@@ -82,9 +82,11 @@ mod tests {
             model_path: "/models".into(),
             model_host_path: "/models".into(),
             ui_path: "/app/ui".into(),
+            sites_path: "/config/sites".into(),
             api_hostname: "localhost".into(),
             chat_hostname: "localhost".into(),
             cookie_domain: None,
+            session_redis_url: None,
             backend_network: "sovereign-internal".into(),
             acme_contact: None,
             acme_staging: false,
@@ -94,6 +96,74 @@ mod tests {
             secure_cookies: true,
             db_encryption_key: None,
             db_encryption_key_old: None,
+            graceful_stop_timeout_secs: 120,
+            backend_pool_max_idle: 32,
+            backend_pool_idle_timeout_secs: 90,
+            backend_connect_timeout_secs: 5,
+            upstream_protocols: Default::default(),
+            sse_keepalive_secs: 15,
+            access_log_path: None,
+            access_log_max_bytes: 100 * 1024 * 1024,
+            access_log_max_files: 5,
+            access_log_webhook_url: None,
+            auth_rate_limit_per_minute: 0,
+            auth_rate_limit_burst: 10,
+            trust_forwarded_for: false,
+            trusted_proxy_hops: 1,
+            oidc_state_binding: "strict".to_string(),
+            trusted_device_ttl_days: 30,
+            jwt_access_tokens: false,
+            jwt_signing_key: None,
+            jwt_ttl_secs: 300,
+            token_cache_ttl_secs: 0,
+            token_cache_max_entries: 10_000,
+            model_name_normalize: true,
+            model_name_strip_prefixes: vec!["openai/".to_string()],
+            model_name_suggestions: true,
+            usage_webhook_url: None,
+            usage_webhook_secret: None,
+            usage_webhook_max_retries: 5,
+            event_webhook_url: None,
+            event_webhook_secret: None,
+            request_log: false,
+            autoload_timeout_secs: 300,
+            watchdog_interval_secs: 30,
+            watchdog_max_restarts: 3,
+            backend_warmup: false,
+            prompt_archive: false,
+            prompt_archive_retention_days: 90,
+            prompt_dedup_stats: false,
+            two_person_approval: false,
+            approval_window_minutes: 60,
+            sandbox_enabled: false,
+            sandbox_image: "python:3.12-alpine".to_string(),
+            sandbox_memory_mb: 256,
+            sandbox_cpus: 1.0,
+            sandbox_timeout_secs: 10,
+            sandbox_max_concurrent: 4,
+            backend_tls: false,
+            backend_tls_dir: "/config/backend-tls".to_string(),
+            backend_tls_host_path: "/config/backend-tls".to_string(),
+            host_memory_ceiling_percent: 0,
+            memory_critical_percent: 95,
+            memory_pressure_admission: false,
+            download_background_mb_per_sec: 0,
+            hf_download_connections: 1,
+            hf_download_chunk_mb: 64,
+            model_trash_hours: 24,
+            model_trash_max_gb: 200,
+            model_import_path: None,
+            model_store_s3: None,
+            s3_endpoint: None,
+            s3_region: "us-east-1".into(),
+            s3_access_key_id: None,
+            s3_secret_access_key: None,
+            model_cache_max_gb: 0,
+            gpu_probe: false,
+            auto_categorize: "off".to_string(),
+            request_log_retention_days: 30,
+            signed_url_key: None,
+            signed_url_max_ttl_secs: 3600,
         }
     }
 
@@ -0,0 +1,353 @@
+//! Trusted devices ("remember this device").
+//!
+//! A remembered device holds a long-lived refresh token in the `se_device`
+//! cookie (scoped to `/auth`). Redeeming it mints an ordinary 24h session and
+//! rotates the refresh token, so a copied cookie stops working as soon as
+//! either copy is used. Users can name and revoke their devices; revoking one
+//! also ends its sessions.
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use super::sessions::{generate_session_token, hash_session, SessionStore, Sessions};
+use crate::db::Database;
+
+const DEVICE_COOKIE_NAME: &str = "se_device";
+
+/// A remembered device as shown to its owner.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct TrustedDevice {
+    pub id: String,
+    pub name: String,
+    pub user_agent: Option<String>,
+    pub last_used_at: Option<String>,
+    pub expires_at: String,
+    pub created_at: String,
+}
+
+/// Result of redeeming a device refresh token.
+#[derive(Debug)]
+pub struct DeviceRefresh {
+    pub device_id: String,
+    pub user_id: String,
+    /// Replacement refresh token — the presented one is no longer valid.
+    pub token: String,
+}
+
+/// Register a device for a user. Returns `(device_id, refresh_token)`.
+pub async fn create_device(
+    db: &Database,
+    user_id: &str,
+    user_agent: Option<&str>,
+    ttl_days: i64,
+) -> Result<(String, String)> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let token = generate_session_token();
+    let name = default_device_name(user_agent.unwrap_or(""));
+
+    sqlx::query(
+        "INSERT INTO trusted_devices (id, user_id, name, token_hash, user_agent, last_used_at, expires_at) VALUES (?, ?, ?, ?, ?, datetime('now'), datetime('now', '+' || ? || ' days'))",
+    )
+    .bind(&id)
+    .bind(user_id)
+    .bind(&name)
+    .bind(hash_session(&token))
+    .bind(user_agent)
+    .bind(ttl_days)
+    .execute(&db.pool)
+    .await
+    .context("Failed to create trusted device")?;
+
+    Ok((id, token))
+}
+
+/// Redeem a refresh token: validate it, rotate it, and bump `last_used_at`.
+/// The expiry is not extended — a device must log in again when it lapses.
+pub async fn redeem(db: &Database, token: &str) -> Result<DeviceRefresh> {
+    let row: Option<(String, String)> = sqlx::query_as(
+        "SELECT id, user_id FROM trusted_devices WHERE token_hash = ? AND expires_at > datetime('now')",
+    )
+    .bind(hash_session(token))
+    .fetch_optional(&db.pool)
+    .await
+    .context("Failed to query trusted device")?;
+
+    let Some((device_id, user_id)) = row else {
+        bail!("Invalid or expired device token");
+    };
+
+    let new_token = generate_session_token();
+    // Compare-and-swap on the old hash so two concurrent redemptions of the
+    // same token can't both succeed
+    let result = sqlx::query(
+        "UPDATE trusted_devices SET token_hash = ?, last_used_at = datetime('now') WHERE id = ? AND token_hash = ?",
+    )
+    .bind(hash_session(&new_token))
+    .bind(&device_id)
+    .bind(hash_session(token))
+    .execute(&db.pool)
+    .await
+    .context("Failed to rotate device token")?;
+    if result.rows_affected() == 0 {
+        bail!("Device token already redeemed");
+    }
+
+    Ok(DeviceRefresh {
+        device_id,
+        user_id,
+        token: new_token,
+    })
+}
+
+/// List a user's unexpired devices, most recently used first.
+pub async fn list_devices(db: &Database, user_id: &str) -> Result<Vec<TrustedDevice>> {
+    sqlx::query_as::<_, TrustedDevice>(
+        "SELECT id, name, user_agent, last_used_at, expires_at, created_at FROM trusted_devices WHERE user_id = ? AND expires_at > datetime('now') ORDER BY last_used_at DESC",
+    )
+    .bind(user_id)
+    .fetch_all(&db.pool)
+    .await
+    .context("Failed to list trusted devices")
+}
+
+/// Rename a device. Returns false if it doesn't exist or isn't the user's.
+pub async fn rename_device(db: &Database, user_id: &str, id: &str, name: &str) -> Result<bool> {
+    let result = sqlx::query("UPDATE trusted_devices SET name = ? WHERE id = ? AND user_id = ?")
+        .bind(name)
+        .bind(id)
+        .bind(user_id)
+        .execute(&db.pool)
+        .await
+        .context("Failed to rename trusted device")?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Forget a device and end every session it minted. Returns false if it
+/// doesn't exist or isn't the user's.
+pub async fn revoke_device(
+    db: &Database,
+    sessions: &Sessions,
+    user_id: &str,
+    id: &str,
+) -> Result<bool> {
+    sessions.delete_for_device(user_id, id).await?;
+    let result = sqlx::query("DELETE FROM trusted_devices WHERE id = ? AND user_id = ?")
+        .bind(id)
+        .bind(user_id)
+        .execute(&db.pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Forget the device holding `token` (logout on a remembered device).
+pub async fn revoke_by_token(db: &Database, sessions: &Sessions, token: &str) -> Result<()> {
+    let row: Option<(String, String)> =
+        sqlx::query_as("SELECT id, user_id FROM trusted_devices WHERE token_hash = ?")
+            .bind(hash_session(token))
+            .fetch_optional(&db.pool)
+            .await?;
+    if let Some((id, user_id)) = row {
+        revoke_device(db, sessions, &user_id, &id).await?;
+    }
+    Ok(())
+}
+
+/// Clean up expired devices. Their sessions are unlinked, not deleted — they
+/// expire on their own.
+pub async fn cleanup_expired(db: &Database, sessions: &Sessions) -> Result<u64> {
+    let expired: Vec<(String, String)> = sqlx::query_as(
+        "SELECT id, user_id FROM trusted_devices WHERE expires_at < datetime('now')",
+    )
+    .fetch_all(&db.pool)
+    .await
+    .context("Failed to list expired devices")?;
+    for (id, user_id) in &expired {
+        sessions
+            .unlink_device(user_id, id)
+            .await
+            .context("Failed to unlink expired devices")?;
+    }
+    let result = sqlx::query("DELETE FROM trusted_devices WHERE expires_at < datetime('now')")
+        .execute(&db.pool)
+        .await
+        .context("Failed to clean up trusted devices")?;
+    Ok(result.rows_affected())
+}
+
+/// Find the device refresh token in a Cookie header.
+pub fn token_from_cookies(cookie_header: &str) -> Option<&str> {
+    let prefix = format!("{DEVICE_COOKIE_NAME}=");
+    cookie_header
+        .split(';')
+        .find_map(|c| c.trim().strip_prefix(&prefix))
+        .filter(|t| !t.is_empty())
+}
+
+/// Build a Set-Cookie header value for the device cookie. Scoped to `/auth`
+/// so the long-lived token is never sent with API or proxied chat requests.
+pub fn build_cookie(
+    token: &str,
+    max_age: i64,
+    secure: bool,
+    cookie_domain: Option<&str>,
+) -> String {
+    let secure_flag = if secure { "; Secure" } else { "" };
+    let domain_attr = match cookie_domain {
+        Some(d) => format!("; Domain={d}"),
+        None => String::new(),
+    };
+    format!(
+        "{}={}; Path=/auth; HttpOnly; SameSite=Lax; Max-Age={}{}{}",
+        DEVICE_COOKIE_NAME, token, max_age, secure_flag, domain_attr
+    )
+}
+
+/// Build a Set-Cookie header value that clears the device cookie.
+pub fn clear_cookie(secure: bool, cookie_domain: Option<&str>) -> String {
+    build_cookie("", 0, secure, cookie_domain)
+}
+
+/// Human-readable default name from a User-Agent, e.g. "Firefox on Linux".
+pub fn default_device_name(user_agent: &str) -> String {
+    // Order matters: Edge and Opera UAs also contain "Chrome", Chrome's
+    // contains "Safari"
+    let browser = [
+        ("Edg/", "Edge"),
+        ("OPR/", "Opera"),
+        ("Firefox/", "Firefox"),
+        ("Chrome/", "Chrome"),
+        ("Safari/", "Safari"),
+    ]
+    .iter()
+    .find(|(needle, _)| user_agent.contains(needle))
+    .map(|(_, name)| *name);
+
+    let os = [
+        ("Android", "Android"),
+        ("iPhone", "iOS"),
+        ("iPad", "iPadOS"),
+        ("Windows", "Windows"),
+        ("Mac OS X", "macOS"),
+        ("CrOS", "ChromeOS"),
+        ("Linux", "Linux"),
+    ]
+    .iter()
+    .find(|(needle, _)| user_agent.contains(needle))
+    .map(|(_, name)| *name);
+
+    match (browser, os) {
+        (Some(b), Some(o)) => format!("{b} on {o}"),
+        (Some(b), None) => b.to_string(),
+        (None, Some(o)) => o.to_string(),
+        (None, None) => "Unknown device".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::sessions;
+
+    async fn insert_user(db: &Database, id: &str) {
+        sqlx::query(
+            "INSERT OR IGNORE INTO idp_configs (id, name, issuer, client_id, client_secret_enc)
+             VALUES ('test-idp', 'test', 'https://test', 'client', 'secret')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO users (id, idp_id, subject) VALUES (?, 'test-idp', ?)")
+            .bind(id)
+            .bind(id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn default_names() {
+        let firefox = "Mozilla/5.0 (X11; Linux x86_64; rv:128.0) Gecko/20100101 Firefox/128.0";
+        let edge = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36 Edg/126.0.0.0";
+        let safari = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Mobile/15E148 Safari/604.1";
+        assert_eq!(default_device_name(firefox), "Firefox on Linux");
+        assert_eq!(default_device_name(edge), "Edge on Windows");
+        assert_eq!(default_device_name(safari), "Safari on iOS");
+        assert_eq!(default_device_name(""), "Unknown device");
+    }
+
+    #[test]
+    fn device_cookie_is_scoped_to_auth() {
+        let cookie = build_cookie("tok", 3600, true, None);
+        assert!(cookie.starts_with("se_device=tok;"));
+        assert!(cookie.contains("Path=/auth;"));
+        assert!(cookie.contains("HttpOnly"));
+        assert!(clear_cookie(false, None).contains("Max-Age=0"));
+    }
+
+    #[test]
+    fn finds_token_among_cookies() {
+        assert_eq!(
+            token_from_cookies("se_session=abc; se_device=xyz"),
+            Some("xyz")
+        );
+        assert_eq!(token_from_cookies("se_session=abc"), None);
+        assert_eq!(token_from_cookies("se_device="), None);
+    }
+
+    #[tokio::test]
+    async fn redeem_rotates_token() {
+        let db = Database::test_db().await;
+        insert_user(&db, "u1").await;
+        let (id, token) = create_device(&db, "u1", Some("Firefox/1"), 30)
+            .await
+            .unwrap();
+
+        let refreshed = redeem(&db, &token).await.unwrap();
+        assert_eq!(refreshed.device_id, id);
+        assert_eq!(refreshed.user_id, "u1");
+        assert_ne!(refreshed.token, token);
+
+        // The old token is spent; the new one works
+        assert!(redeem(&db, &token).await.is_err());
+        assert!(redeem(&db, &refreshed.token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn expired_device_cannot_be_redeemed() {
+        let db = Database::test_db().await;
+        insert_user(&db, "u1").await;
+        let (id, token) = create_device(&db, "u1", None, -1).await.unwrap();
+        assert!(redeem(&db, &token).await.is_err());
+
+        // Sessions the device minted outlive it
+        let sessions = Sessions::sqlite(&db);
+        let session = sessions::create_session(&sessions, "u1", Some(&id))
+            .await
+            .unwrap();
+        assert_eq!(cleanup_expired(&db, &sessions).await.unwrap(), 1);
+        assert!(sessions::validate_session(&sessions, &db, &session)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn rename_and_revoke_are_owner_only() {
+        let db = Database::test_db().await;
+        insert_user(&db, "u1").await;
+        insert_user(&db, "u2").await;
+        let (id, token) = create_device(&db, "u1", None, 30).await.unwrap();
+
+        assert!(!rename_device(&db, "u2", &id, "mine now").await.unwrap());
+        assert!(rename_device(&db, "u1", &id, "Work laptop").await.unwrap());
+        assert_eq!(
+            list_devices(&db, "u1").await.unwrap()[0].name,
+            "Work laptop"
+        );
+
+        let sessions = Sessions::sqlite(&db);
+        assert!(!revoke_device(&db, &sessions, "u2", &id).await.unwrap());
+        assert!(revoke_device(&db, &sessions, "u1", &id).await.unwrap());
+        assert!(list_devices(&db, "u1").await.unwrap().is_empty());
+        assert!(redeem(&db, &token).await.is_err());
+    }
+}
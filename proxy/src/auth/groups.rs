@@ -0,0 +1,329 @@
+//! OIDC group → role mapping.
+//!
+//! An IdP with a `groups_claim` reads that claim from the ID token at every
+//! login and maps its values through `group_mappings` (a JSON object of
+//! group → list of `admin` or role names). The result replaces the user's
+//! `is_admin` flag and roles, so removing someone from a group revokes the
+//! access it gave at their next login. IdPs without a claim leave both to
+//! the admin API.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use serde_json::Value;
+use tracing::{info, warn};
+
+use super::jwt::JwtIssuer;
+use super::rbac::Role;
+use super::token_cache::TokenCache;
+use crate::api::audit;
+use crate::db::Database;
+
+/// Mapping target that grants full admin rather than a scoped role.
+pub const ADMIN: &str = "admin";
+
+/// Parse and check `group_mappings`: every value must be a list of `admin`
+/// or role names.
+pub fn parse_mappings(value: &Value) -> Result<HashMap<String, Vec<String>>, String> {
+    let Some(object) = value.as_object() else {
+        return Err("group_mappings must be an object of group → grants".into());
+    };
+    let mut mappings = HashMap::with_capacity(object.len());
+    for (group, grants) in object {
+        let Some(list) = grants.as_array() else {
+            return Err(format!("Grants for group '{group}' must be a list"));
+        };
+        let mut names = Vec::with_capacity(list.len());
+        for grant in list {
+            match grant.as_str() {
+                Some(name) if name == ADMIN || Role::parse(name).is_some() => {
+                    names.push(name.to_string())
+                }
+                _ => return Err(format!("Unknown grant {grant} for group '{group}'")),
+            }
+        }
+        mappings.insert(group.clone(), names);
+    }
+    Ok(mappings)
+}
+
+/// Decode the payload of a compact JWT. Only call this on a token whose
+/// signature has already been verified.
+pub fn token_payload(jwt: &str) -> Option<Value> {
+    let payload = jwt.split('.').nth(1)?;
+    let bytes = URL_SAFE_NO_PAD.decode(payload).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Values of `claim` in a token payload. A dotted name walks nested objects
+/// (`realm_access.roles`); a single string counts as a one-item list.
+/// `None` when the claim is absent.
+pub fn claim_values(payload: &Value, claim: &str) -> Option<Vec<String>> {
+    let value = claim
+        .split('.')
+        .try_fold(payload, |value, key| value.get(key))?;
+    match value {
+        Value::String(s) => Some(vec![s.clone()]),
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// What a user's groups grant.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Grants {
+    pub is_admin: bool,
+    pub roles: Vec<Role>,
+}
+
+/// Resolve `groups` through `mappings`. Keycloak sends full group paths
+/// (`/admins`), so a mapping for `admins` matches `/admins` too.
+pub fn resolve(groups: &[String], mappings: &HashMap<String, Vec<String>>) -> Grants {
+    let mut grants = Grants::default();
+    for group in groups {
+        let names = mappings
+            .get(group)
+            .or_else(|| mappings.get(group.trim_start_matches('/')));
+        for name in names.into_iter().flatten() {
+            if name == ADMIN {
+                grants.is_admin = true;
+            } else if let Some(role) = Role::parse(name) {
+                if !grants.roles.contains(&role) {
+                    grants.roles.push(role);
+                }
+            }
+        }
+    }
+    grants.roles.sort_by_key(|r| r.as_str());
+    grants
+}
+
+/// Replace a user's admin flag and roles with `grants`. Changes are recorded
+/// as a `user.group_sync` change set with the IdP as the actor, and the
+/// user's cached tokens and JWTs are dropped so API calls see the new admin
+/// flag.
+pub async fn sync_user(
+    db: &Database,
+    token_cache: &TokenCache,
+    jwt: Option<&JwtIssuer>,
+    idp_id: &str,
+    user_id: &str,
+    grants: &Grants,
+) -> Result<()> {
+    let before_admin: bool = sqlx::query_scalar("SELECT is_admin FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_one(&db.pool)
+        .await?;
+    let before_roles: Vec<String> =
+        sqlx::query_scalar("SELECT role FROM user_roles WHERE user_id = ? ORDER BY role")
+            .bind(user_id)
+            .fetch_all(&db.pool)
+            .await?;
+    let after_roles: Vec<&str> = grants.roles.iter().map(Role::as_str).collect();
+    if before_admin == grants.is_admin && before_roles == after_roles {
+        return Ok(());
+    }
+
+    let actor = format!("idp:{idp_id}");
+    let mut tx = db.pool.begin().await?;
+    sqlx::query("UPDATE users SET is_admin = ? WHERE id = ?")
+        .bind(grants.is_admin)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM user_roles WHERE user_id = ?")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+    for role in &after_roles {
+        sqlx::query("INSERT INTO user_roles (user_id, role, granted_by) VALUES (?, ?, ?)")
+            .bind(user_id)
+            .bind(*role)
+            .bind(&actor)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    token_cache.invalidate_user(user_id);
+    if let Some(jwt) = jwt {
+        jwt.revoke_user(user_id);
+    }
+
+    let diff_id = audit::record_diff(
+        &db.pool,
+        "user.group_sync",
+        &actor,
+        Some(user_id),
+        &serde_json::json!({ "is_admin": before_admin, "roles": before_roles }),
+        &serde_json::json!({ "is_admin": grants.is_admin, "roles": after_roles }),
+    )
+    .await;
+    info!(target: "audit", action = "user.group_sync", actor = %actor, resource = %user_id, is_admin = grants.is_admin, roles = %after_roles.join(","), diff_id = diff_id.as_deref().unwrap_or("-"), "IdP groups changed user access");
+    Ok(())
+}
+
+/// Sync a user from a verified ID token if the IdP maps groups. A missing
+/// claim counts as no groups, so access is revoked rather than kept.
+#[allow(clippy::too_many_arguments)]
+pub async fn sync_from_token(
+    db: &Database,
+    token_cache: &TokenCache,
+    jwt: Option<&JwtIssuer>,
+    idp_id: &str,
+    groups_claim: Option<&str>,
+    group_mappings: &str,
+    user_id: &str,
+    id_token: &str,
+) -> Result<()> {
+    let Some(claim) = groups_claim.filter(|c| !c.is_empty()) else {
+        return Ok(());
+    };
+    let mappings = serde_json::from_str::<Value>(group_mappings)
+        .map_err(|e| e.to_string())
+        .and_then(|v| parse_mappings(&v))
+        .map_err(|e| anyhow::anyhow!("Invalid group_mappings for IdP {idp_id}: {e}"))?;
+
+    let groups = token_payload(id_token)
+        .and_then(|payload| claim_values(&payload, claim))
+        .unwrap_or_else(|| {
+            warn!(idp = %idp_id, claim, user_id, "Groups claim missing from ID token");
+            Vec::new()
+        });
+    sync_user(
+        db,
+        token_cache,
+        jwt,
+        idp_id,
+        user_id,
+        &resolve(&groups, &mappings),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mappings() -> HashMap<String, Vec<String>> {
+        parse_mappings(&serde_json::json!({
+            "se-admins": ["admin"],
+            "ml-team": ["model_admin", "viewer"],
+            "ops": ["viewer", "reservation_admin"],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn rejects_unknown_grants() {
+        assert!(parse_mappings(&serde_json::json!({ "g": ["root"] })).is_err());
+        assert!(parse_mappings(&serde_json::json!({ "g": "admin" })).is_err());
+        assert!(parse_mappings(&serde_json::json!(["admin"])).is_err());
+    }
+
+    #[test]
+    fn reads_nested_and_single_claims() {
+        let payload = serde_json::json!({
+            "groups": ["/se-admins", "other"],
+            "realm_access": { "roles": ["ml-team"] },
+            "team": "ops",
+        });
+        assert_eq!(
+            claim_values(&payload, "groups").unwrap(),
+            ["/se-admins", "other"]
+        );
+        assert_eq!(
+            claim_values(&payload, "realm_access.roles").unwrap(),
+            ["ml-team"]
+        );
+        assert_eq!(claim_values(&payload, "team").unwrap(), ["ops"]);
+        assert!(claim_values(&payload, "missing").is_none());
+    }
+
+    #[test]
+    fn decodes_token_payload() {
+        let body = URL_SAFE_NO_PAD.encode(br#"{"groups":["ops"]}"#);
+        let payload = token_payload(&format!("e30.{body}.sig")).unwrap();
+        assert_eq!(payload["groups"][0], "ops");
+        assert!(token_payload("not-a-jwt").is_none());
+    }
+
+    #[test]
+    fn resolves_groups_to_grants() {
+        let grants = resolve(&["/se-admins".into(), "ml-team".into()], &mappings());
+        assert!(grants.is_admin);
+        assert_eq!(grants.roles, [Role::ModelAdmin, Role::Viewer]);
+
+        let grants = resolve(&["ops".into(), "ml-team".into()], &mappings());
+        assert!(!grants.is_admin);
+        assert_eq!(
+            grants.roles,
+            [Role::ModelAdmin, Role::ReservationAdmin, Role::Viewer]
+        );
+
+        assert_eq!(resolve(&["nobody".into()], &mappings()), Grants::default());
+    }
+
+    #[tokio::test]
+    async fn sync_revokes_access_removed_from_groups() {
+        let db = Database::test_db().await;
+        sqlx::query(
+            "INSERT INTO idp_configs (id, name, issuer, client_id, client_secret_enc)
+             VALUES ('kc', 'Keycloak', 'https://kc', 'client', 'secret')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO users (id, idp_id, subject, is_admin) VALUES ('u1', 'kc', 'u1', 1)",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        let granted = Grants {
+            is_admin: true,
+            roles: vec![Role::Viewer],
+        };
+        let cache = TokenCache::new(std::time::Duration::from_secs(60), 16);
+        sync_user(&db, &cache, None, "kc", "u1", &granted)
+            .await
+            .unwrap();
+        cache.insert(
+            "hash1",
+            crate::auth::AuthUser {
+                user_id: "u1".into(),
+                token_id: "t1".into(),
+                category_id: None,
+                specific_model_id: None,
+                is_admin: true,
+                is_internal: false,
+                via_jwt: false,
+            },
+            None,
+        );
+        assert!(cache.get("hash1").is_some());
+        sync_user(&db, &cache, None, "kc", "u1", &Grants::default())
+            .await
+            .unwrap();
+        // The cached token no longer carries the admin flag
+        assert!(cache.get("hash1").is_none());
+
+        let is_admin: bool = sqlx::query_scalar("SELECT is_admin FROM users WHERE id = 'u1'")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        let roles: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM user_roles WHERE user_id = 'u1'")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert!(!is_admin);
+        assert_eq!(roles, 0);
+    }
+}
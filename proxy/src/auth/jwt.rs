@@ -0,0 +1,328 @@
+//! Short-lived JWT access tokens validated without a DB query.
+//!
+//! Opaque `se-` tokens remain the long-lived credential. A holder can trade
+//! one for an HS256 JWT (`POST /v1/auth/jwt`) that carries the same identity
+//! and scope and expires after `JWT_TTL_SECS`. The bearer middleware checks
+//! JWTs locally; revoking or deleting the source opaque token adds its id to
+//! an in-memory revocation list, kept until every JWT minted from it has
+//! expired. Changing a user's admin flag or roles does the same for every
+//! JWT minted for that user so far, since the claims carry the old rights.
+//! The lists are per-process, so with several replicas a revoked token's
+//! JWTs stay valid on the others for at most one TTL.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine as _;
+use hmac::{Hmac, Mac};
+use rand::RngExt;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tracing::warn;
+
+use crate::auth::AuthUser;
+use crate::config::AppConfig;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Base64url of `{"alg":"HS256","typ":"JWT"}`.
+const HEADER: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    /// User id.
+    sub: String,
+    /// Opaque token the JWT was minted from (usage attribution + revocation).
+    tid: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cat: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mdl: Option<String>,
+    #[serde(default)]
+    adm: bool,
+    iat: i64,
+    exp: i64,
+}
+
+/// Issues and validates JWT access tokens. Cheap to clone.
+#[derive(Clone)]
+pub struct JwtIssuer {
+    key: Arc<Vec<u8>>,
+    ttl_secs: i64,
+    /// Revoked source token id → unix time after which no JWT from it is valid.
+    revoked: Arc<RwLock<HashMap<String, i64>>>,
+    /// User id → unix time of the last change to their rights; their JWTs
+    /// minted at or before it are invalid.
+    revoked_users: Arc<RwLock<HashMap<String, i64>>>,
+}
+
+impl JwtIssuer {
+    /// Build from config; `None` unless `JWT_ACCESS_TOKENS` is enabled.
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        if !config.jwt_access_tokens {
+            return None;
+        }
+        let key = match &config.jwt_signing_key {
+            Some(k) => k.as_bytes().to_vec(),
+            None => {
+                warn!("JWT_SIGNING_KEY not set — using a random key; JWTs won't survive a restart or work across replicas");
+                let bytes: [u8; 32] = rand::rng().random();
+                bytes.to_vec()
+            }
+        };
+        Some(Self::new(key, config.jwt_ttl_secs as i64))
+    }
+
+    pub fn new(key: Vec<u8>, ttl_secs: i64) -> Self {
+        Self {
+            key: Arc::new(key),
+            ttl_secs: ttl_secs.max(1),
+            revoked: Arc::new(RwLock::new(HashMap::new())),
+            revoked_users: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn ttl_secs(&self) -> i64 {
+        self.ttl_secs
+    }
+
+    /// Mint a JWT carrying `user`'s identity and scope.
+    pub fn issue(&self, user: &AuthUser) -> String {
+        let now = chrono::Utc::now().timestamp();
+        let claims = Claims {
+            sub: user.user_id.clone(),
+            tid: user.token_id.clone(),
+            cat: user.category_id.clone(),
+            mdl: user.specific_model_id.clone(),
+            adm: user.is_admin,
+            iat: now,
+            exp: now + self.ttl_secs,
+        };
+        // Serializing a struct of strings/ints cannot fail
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap_or_default());
+        let signing_input = format!("{HEADER}.{payload}");
+        let signature = URL_SAFE_NO_PAD.encode(self.sign(&signing_input));
+        format!("{signing_input}.{signature}")
+    }
+
+    /// Verify signature, expiry, and revocation. No DB access.
+    pub fn verify(&self, token: &str) -> Result<AuthUser> {
+        let (signing_input, signature) = token.rsplit_once('.').context("Malformed JWT")?;
+        let (header, payload) = signing_input.split_once('.').context("Malformed JWT")?;
+        if header != HEADER {
+            bail!("Unsupported JWT header");
+        }
+
+        let signature = URL_SAFE_NO_PAD
+            .decode(signature)
+            .context("Malformed JWT signature")?;
+        let mut mac = self.mac();
+        mac.update(signing_input.as_bytes());
+        mac.verify_slice(&signature)
+            .map_err(|_| anyhow::anyhow!("Invalid JWT signature"))?;
+
+        let claims: Claims = serde_json::from_slice(
+            &URL_SAFE_NO_PAD
+                .decode(payload)
+                .context("Malformed JWT payload")?,
+        )
+        .context("Malformed JWT claims")?;
+
+        let now = chrono::Utc::now().timestamp();
+        if claims.exp <= now {
+            bail!("JWT has expired");
+        }
+        if self.is_revoked(&claims.tid, now) {
+            bail!("JWT source token has been revoked");
+        }
+        if self.is_user_revoked(&claims.sub, claims.iat) {
+            bail!("JWT predates a change to the user's rights");
+        }
+
+        Ok(AuthUser {
+            user_id: claims.sub,
+            token_id: claims.tid,
+            category_id: claims.cat,
+            specific_model_id: claims.mdl,
+            is_admin: claims.adm,
+            is_internal: false,
+            via_jwt: true,
+        })
+    }
+
+    /// Invalidate every JWT minted from `token_id`.
+    pub fn revoke(&self, token_id: &str) {
+        let now = chrono::Utc::now().timestamp();
+        let mut revoked = self.revoked.write().unwrap_or_else(|e| e.into_inner());
+        // Any JWT from this token expires within one TTL, after which the entry is moot
+        revoked.retain(|_, until| *until > now);
+        revoked.insert(token_id.to_string(), now + self.ttl_secs);
+    }
+
+    /// Invalidate every JWT minted for `user_id` so far, e.g. after they lost
+    /// admin rights. Ones minted in the same second are caught too; the user
+    /// can trade their opaque token for a fresh JWT right after.
+    pub fn revoke_user(&self, user_id: &str) {
+        let now = chrono::Utc::now().timestamp();
+        let mut revoked = self
+            .revoked_users
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        // JWTs minted before a change expire within one TTL of it
+        revoked.retain(|_, at| *at + self.ttl_secs > now);
+        revoked.insert(user_id.to_string(), now);
+    }
+
+    fn is_user_revoked(&self, user_id: &str, issued_at: i64) -> bool {
+        self.revoked_users
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(user_id)
+            .is_some_and(|at| issued_at <= *at)
+    }
+
+    fn is_revoked(&self, token_id: &str, now: i64) -> bool {
+        self.revoked
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(token_id)
+            .is_some_and(|until| *until > now)
+    }
+
+    fn mac(&self) -> HmacSha256 {
+        HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length")
+    }
+
+    fn sign(&self, signing_input: &str) -> Vec<u8> {
+        let mut mac = self.mac();
+        mac.update(signing_input.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+}
+
+/// Cheap check for whether a bearer credential is a JWT rather than an opaque token.
+pub fn looks_like_jwt(token: &str) -> bool {
+    token.starts_with("eyJ") && token.matches('.').count() == 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> AuthUser {
+        AuthUser {
+            user_id: "u1".into(),
+            token_id: "t1".into(),
+            category_id: Some("cat".into()),
+            specific_model_id: None,
+            is_admin: false,
+            is_internal: false,
+            via_jwt: false,
+        }
+    }
+
+    #[test]
+    fn header_constant_matches_encoding() {
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(br#"{"alg":"HS256","typ":"JWT"}"#),
+            HEADER
+        );
+    }
+
+    #[test]
+    fn round_trip_preserves_identity_and_scope() {
+        let issuer = JwtIssuer::new(b"secret".to_vec(), 300);
+        let jwt = issuer.issue(&user());
+        assert!(looks_like_jwt(&jwt));
+
+        let auth = issuer.verify(&jwt).unwrap();
+        assert_eq!(auth.user_id, "u1");
+        assert_eq!(auth.token_id, "t1");
+        assert_eq!(auth.category_id.as_deref(), Some("cat"));
+        assert!(auth.via_jwt);
+    }
+
+    #[test]
+    fn rejects_tampered_or_foreign_tokens() {
+        let issuer = JwtIssuer::new(b"secret".to_vec(), 300);
+        let other = JwtIssuer::new(b"other".to_vec(), 300);
+        let jwt = issuer.issue(&user());
+
+        assert!(other.verify(&jwt).is_err());
+
+        // Escalated claims under the original signature
+        let forged_payload = URL_SAFE_NO_PAD
+            .encode(br#"{"sub":"admin","tid":"t1","adm":true,"iat":0,"exp":99999999999}"#);
+        let sig = jwt.rsplit_once('.').unwrap().1;
+        let forged = format!("{HEADER}.{forged_payload}.{sig}");
+        assert!(issuer.verify(&forged).is_err());
+    }
+
+    #[test]
+    fn rejects_expired() {
+        let issuer = JwtIssuer::new(b"secret".to_vec(), 300);
+        let claims = Claims {
+            sub: "u1".into(),
+            tid: "t1".into(),
+            cat: None,
+            mdl: None,
+            adm: false,
+            iat: 0,
+            exp: 1,
+        };
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+        let input = format!("{HEADER}.{payload}");
+        let jwt = format!("{input}.{}", URL_SAFE_NO_PAD.encode(issuer.sign(&input)));
+        assert!(issuer
+            .verify(&jwt)
+            .unwrap_err()
+            .to_string()
+            .contains("expired"));
+    }
+
+    #[test]
+    fn revocation_applies_to_source_token() {
+        let issuer = JwtIssuer::new(b"secret".to_vec(), 300);
+        let jwt = issuer.issue(&user());
+        issuer.revoke("other-token");
+        assert!(issuer.verify(&jwt).is_ok());
+        issuer.revoke("t1");
+        assert!(issuer.verify(&jwt).is_err());
+    }
+
+    #[test]
+    fn user_revocation_rejects_earlier_jwts() {
+        let issuer = JwtIssuer::new(b"secret".to_vec(), 300);
+        let jwt = issuer.issue(&AuthUser {
+            is_admin: true,
+            ..user()
+        });
+        issuer.revoke_user("u2");
+        assert!(issuer.verify(&jwt).is_ok());
+        issuer.revoke_user("u1");
+        assert!(issuer.verify(&jwt).is_err());
+
+        // Minted after the change
+        let now = chrono::Utc::now().timestamp();
+        let claims = Claims {
+            sub: "u1".into(),
+            tid: "t1".into(),
+            cat: None,
+            mdl: None,
+            adm: false,
+            iat: now + 1,
+            exp: now + 300,
+        };
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&claims).unwrap());
+        let input = format!("{HEADER}.{payload}");
+        let later = format!("{input}.{}", URL_SAFE_NO_PAD.encode(issuer.sign(&input)));
+        assert!(!issuer.verify(&later).unwrap().is_admin);
+    }
+
+    #[test]
+    fn opaque_tokens_are_not_jwts() {
+        assert!(!looks_like_jwt("se-123e4567-e89b-12d3-a456-426614174000"));
+    }
+}
@@ -1,11 +1,22 @@
+pub mod backchannel;
 pub mod bootstrap;
+pub mod devices;
+pub mod groups;
+pub mod jwt;
 pub mod oidc;
+pub mod rate_limit;
+pub mod rbac;
+pub mod scim;
+mod session_redis;
 pub mod sessions;
+pub mod signed_url;
+pub mod token_cache;
 pub mod tokens;
 
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
-use axum::extract::{Request, State};
+use axum::extract::{ConnectInfo, Request, State};
 use axum::http::StatusCode;
 use axum::middleware::Next;
 use axum::response::{IntoResponse, Response};
@@ -16,6 +27,34 @@ use crate::config::AppConfig;
 use crate::db::Database;
 use crate::AppState;
 
+/// Resolve the client IP: the socket peer, or with `forwarded_hops` trusted
+/// proxies, the `X-Forwarded-For` entry that many places from the right. Each
+/// proxy appends the address it saw, so anything further left is whatever the
+/// client sent. A list shorter than the hop count yields its left-most entry.
+/// `None` without connection info (e.g. in-process tests).
+pub(crate) fn client_ip(
+    headers: &axum::http::HeaderMap,
+    extensions: &axum::http::Extensions,
+    forwarded_hops: usize,
+) -> Option<IpAddr> {
+    if forwarded_hops > 0 {
+        let forwarded = headers
+            .get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| {
+                let entries: Vec<&str> = v.split(',').map(str::trim).collect();
+                let idx = entries.len().saturating_sub(forwarded_hops);
+                entries[idx].parse().ok()
+            });
+        if forwarded.is_some() {
+            return forwarded;
+        }
+    }
+    extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+}
+
 /// Try to authenticate via Basic auth (bootstrap credentials).
 /// Returns SessionAuth if valid bootstrap credentials are present, None otherwise.
 pub(crate) async fn try_bootstrap_auth(
@@ -36,6 +75,7 @@ pub(crate) async fn try_bootstrap_auth(
     Some(SessionAuth {
         user_id,
         is_admin: true,
+        roles: Vec::new(),
         email: None,
         display_name: Some(user.to_string()),
     })
@@ -51,10 +91,10 @@ pub struct AuthUser {
     pub token_id: String,
     pub category_id: Option<String>,
     pub specific_model_id: Option<String>,
-    #[allow(dead_code)] // populated from DB; will be consumed by authorization middleware
     pub is_admin: bool,
-    #[allow(dead_code)] // populated from DB; will be consumed by authorization middleware
     pub is_internal: bool,
+    /// Authenticated with a JWT access token rather than the opaque token.
+    pub via_jwt: bool,
 }
 
 /// Authenticated session user (from cookie).
@@ -62,10 +102,30 @@ pub struct AuthUser {
 pub struct SessionAuth {
     pub user_id: String,
     pub is_admin: bool,
+    /// Scoped admin roles (see `rbac`); empty for full admins and most users.
+    pub roles: Vec<rbac::Role>,
     pub email: Option<String>,
     pub display_name: Option<String>,
 }
 
+/// Forget cached validation state for a token that was revoked, deleted, or
+/// re-scoped, including any JWTs minted from it.
+pub(crate) fn token_changed(state: &AppState, token_id: &str) {
+    state.token_cache.invalidate_token(token_id);
+    if let Some(jwt) = &state.jwt {
+        jwt.revoke(token_id);
+    }
+}
+
+/// Forget cached validation state for a user whose admin flag or roles
+/// changed, including any JWTs minted for them.
+pub(crate) fn user_changed(state: &AppState, user_id: &str) {
+    state.token_cache.invalidate_user(user_id);
+    if let Some(jwt) = &state.jwt {
+        jwt.revoke_user(user_id);
+    }
+}
+
 /// Middleware: validate Bearer token or x-api-key on /v1/* API requests.
 pub async fn bearer_auth_middleware(
     State(state): State<Arc<AppState>>,
@@ -81,9 +141,22 @@ pub async fn bearer_auth_middleware(
         .or_else(|| req.headers().get("x-api-key").and_then(|v| v.to_str().ok()))
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    let auth_user = tokens::validate_token(&state.db, token)
+    // JWTs are validated locally, without a DB query
+    if let Some(jwt) = state.jwt.as_ref().filter(|_| jwt::looks_like_jwt(token)) {
+        let auth_user = jwt.verify(token).map_err(|_| StatusCode::UNAUTHORIZED)?;
+        req.extensions_mut().insert(auth_user);
+        return Ok(next.run(req).await);
+    }
+
+    let auth_user = tokens::validate_token_cached(&state.db, &state.token_cache, token)
         .await
-        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+        .map_err(|e| {
+            if e.to_string().contains("quota exhausted") {
+                StatusCode::TOO_MANY_REQUESTS
+            } else {
+                StatusCode::UNAUTHORIZED
+            }
+        })?;
 
     req.extensions_mut().insert(auth_user);
     Ok(next.run(req).await)
@@ -106,10 +179,11 @@ pub(crate) fn extract_session_tokens(cookie_header: &str) -> impl Iterator<Item
 /// Tries each matching `se_session` cookie until one validates.
 pub(crate) async fn validate_any_session(
     cookie_header: &str,
+    sessions: &sessions::Sessions,
     db: &Database,
 ) -> Option<sessions::SessionUser> {
     for token in extract_session_tokens(cookie_header) {
-        if let Ok(user) = sessions::validate_session(db, token).await {
+        if let Ok(user) = sessions::validate_session(sessions, db, token).await {
             return Some(user);
         }
     }
@@ -122,6 +196,11 @@ pub async fn session_auth_middleware(
     mut req: Request,
     next: Next,
 ) -> Result<Response, Response> {
+    // Already authenticated by a signed URL (see `signed_url::middleware`)
+    if req.extensions().get::<SessionAuth>().is_some() {
+        return Ok(next.run(req).await);
+    }
+
     // Try bootstrap auth from header first
     if let Some(auth) = try_bootstrap_auth(req.headers(), &state.config, &state.db).await {
         req.extensions_mut().insert(auth);
@@ -135,7 +214,7 @@ pub async fn session_auth_middleware(
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
 
-    let session_user = validate_any_session(cookie_header, &state.db)
+    let session_user = validate_any_session(cookie_header, &state.sessions, &state.db)
         .await
         .ok_or_else(|| {
             (
@@ -148,6 +227,7 @@ pub async fn session_auth_middleware(
     req.extensions_mut().insert(SessionAuth {
         user_id: session_user.user_id,
         is_admin: session_user.is_admin,
+        roles: rbac::Role::parse_list(&session_user.roles),
         email: session_user.email,
         display_name: session_user.display_name,
     });
@@ -180,13 +260,14 @@ pub async fn session_auth_redirect_middleware(
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
 
-    let session_user = validate_any_session(cookie_header, &state.db)
+    let session_user = validate_any_session(cookie_header, &state.sessions, &state.db)
         .await
         .ok_or_else(|| unauth_response(&req, &portal_url))?;
 
     req.extensions_mut().insert(SessionAuth {
         user_id: session_user.user_id,
         is_admin: session_user.is_admin,
+        roles: rbac::Role::parse_list(&session_user.roles),
         email: session_user.email,
         display_name: session_user.display_name,
     });
@@ -213,24 +294,3 @@ fn unauth_response(req: &Request, portal_url: &str) -> Response {
             .into_response()
     }
 }
-
-/// Middleware: require admin role (must be chained after session_auth_middleware).
-pub async fn admin_only_middleware(req: Request, next: Next) -> Result<Response, Response> {
-    let session = req.extensions().get::<SessionAuth>().ok_or_else(|| {
-        (
-            StatusCode::UNAUTHORIZED,
-            Json(serde_json::json!({ "error": "Authentication required" })),
-        )
-            .into_response()
-    })?;
-
-    if !session.is_admin {
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(serde_json::json!({ "error": "Admin access required" })),
-        )
-            .into_response());
-    }
-
-    Ok(next.run(req).await)
-}
@@ -1,9 +1,10 @@
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
-use axum::extract::{Query, State};
+use axum::extract::{Query, Request, State};
 use axum::http::StatusCode;
-use axum::response::{IntoResponse, Redirect, Response};
+use axum::response::{AppendHeaders, IntoResponse, Redirect, Response};
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use openidconnect::core::{
@@ -15,9 +16,10 @@ use openidconnect::{
     EndpointNotSet, EndpointSet, IssuerUrl, Nonce, PkceCodeChallenge, PkceCodeVerifier,
     RedirectUrl, Scope, TokenResponse,
 };
-use tracing::{error, info};
+use sha2::{Digest, Sha256};
+use tracing::{error, info, warn};
 
-use crate::auth::sessions;
+use crate::auth::{client_ip, devices, groups, rbac, sessions};
 use crate::db::models::IdpConfig;
 use crate::db::Database;
 use crate::AppState;
@@ -43,7 +45,9 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/callback", get(callback))
         .route("/providers", get(list_providers))
         .route("/me", get(me))
+        .route("/refresh", post(refresh))
         .route("/logout", post(logout))
+        .route("/backchannel-logout", post(super::backchannel::logout))
         .with_state(state)
 }
 
@@ -76,10 +80,17 @@ async fn list_providers(State(state): State<Arc<AppState>>) -> impl IntoResponse
 #[derive(serde::Deserialize)]
 struct LoginQuery {
     idp: String,
+    /// Remember this device (see `auth::devices`)
+    #[serde(default)]
+    remember: bool,
 }
 
-/// GET /auth/login?idp=<id> — Initiate OIDC authorization redirect.
-async fn login(State(state): State<Arc<AppState>>, Query(query): Query<LoginQuery>) -> Response {
+/// GET /auth/login?idp=<id>&remember=<bool> — Initiate OIDC authorization redirect.
+async fn login(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LoginQuery>,
+    req: Request,
+) -> Response {
     let idp = match load_idp(&state.db, &query.idp).await {
         Ok(idp) => idp,
         Err(e) => {
@@ -127,13 +138,19 @@ async fn login(State(state): State<Arc<AppState>>, Query(query): Query<LoginQuer
 
     let (auth_url, csrf_token, nonce) = auth_request.url();
 
-    // Store CSRF, nonce, and PKCE verifier in DB for callback validation
+    // Recorded regardless of OIDC_STATE_BINDING so a mode change applies to
+    // logins already in flight
+    let fingerprint = ClientFingerprint::new(csrf_token.secret(), &req, &state);
+
+    // Store CSRF, nonce, PKCE verifier, and client fingerprint for callback validation
     if let Err(e) = store_auth_state(
         &state.db,
         csrf_token.secret(),
         nonce.secret(),
         &query.idp,
         pkce_verifier.secret(),
+        &fingerprint,
+        query.remember && state.config.trusted_device_ttl_days > 0,
     )
     .await
     {
@@ -158,6 +175,7 @@ struct CallbackQuery {
 async fn callback(
     State(state): State<Arc<AppState>>,
     Query(query): Query<CallbackQuery>,
+    req: Request,
 ) -> Response {
     // Look up the stored auth state
     let auth_state = match load_auth_state(&state.db, &query.state).await {
@@ -172,6 +190,27 @@ async fn callback(
         }
     };
 
+    // The state must be redeemed by the client that requested it
+    let binding = StateBinding::from_config(&state.config.oidc_state_binding);
+    let fingerprint = ClientFingerprint::new(&query.state, &req, &state);
+    if !fingerprint.matches(&auth_state, binding) {
+        let _ = delete_auth_state(&state.db, &query.state).await;
+        warn!(
+            target: "audit",
+            action = "auth.state_mismatch",
+            idp = %auth_state.idp_id,
+            binding = ?binding,
+            "OIDC callback from a different client than the login — state discarded"
+        );
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "Login must be completed from the browser that started it"
+            })),
+        )
+            .into_response();
+    }
+
     let idp = match load_idp(&state.db, &auth_state.idp_id).await {
         Ok(idp) => idp,
         Err(e) => {
@@ -286,10 +325,90 @@ async fn callback(
         }
     };
 
+    // Users deprovisioned through SCIM or by an admin can't sign back in
+    match sqlx::query_scalar::<_, bool>("SELECT active FROM users WHERE id = ?")
+        .bind(&user_id)
+        .fetch_one(&state.db.pool)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            warn!(user_id = %user_id, "Login refused for deactivated user");
+            return (
+                StatusCode::FORBIDDEN,
+                Json(serde_json::json!({ "error": "Account is deactivated" })),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            error!(error = %e, "Failed to check user status");
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({ "error": "Failed to create user record" })),
+            )
+                .into_response();
+        }
+    }
+
+    // Groups-mapped IdPs own the user's admin flag and roles
+    if let Err(e) = groups::sync_from_token(
+        &state.db,
+        &state.token_cache,
+        state.jwt.as_ref(),
+        &idp.id,
+        idp.groups_claim.as_deref(),
+        &idp.group_mappings,
+        &user_id,
+        &id_token.to_string(),
+    )
+    .await
+    {
+        error!(error = %e, "Failed to sync user groups");
+        return (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "error": "Failed to apply group mappings" })),
+        )
+            .into_response();
+    }
+
     info!(user_id = %user_id, subject = %subject, "OIDC login successful");
 
+    // Remember the device if asked — failure only costs the user a future login
+    let device = if auth_state.remember_device && state.config.trusted_device_ttl_days > 0 {
+        let user_agent = req
+            .headers()
+            .get("user-agent")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        match devices::create_device(
+            &state.db,
+            &user_id,
+            user_agent.as_deref(),
+            state.config.trusted_device_ttl_days,
+        )
+        .await
+        {
+            Ok((device_id, token)) => {
+                info!(target: "audit", action = "device.remember", actor = %user_id, resource = %device_id, "User remembered device at login");
+                Some((device_id, token))
+            }
+            Err(e) => {
+                error!(error = %e, "Failed to create trusted device");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
     // Create session
-    let session_token = match sessions::create_session(&state.db, &user_id).await {
+    let session_token = match sessions::create_session(
+        &state.sessions,
+        &user_id,
+        device.as_ref().map(|(id, _)| id.as_str()),
+    )
+    .await
+    {
         Ok(t) => t,
         Err(e) => {
             error!(error = %e, "Failed to create session");
@@ -304,18 +423,23 @@ async fn callback(
     // Clean up auth state
     let _ = delete_auth_state(&state.db, &query.state).await;
 
-    // Set cookie and redirect to portal
+    // Set cookie(s) and redirect to portal
     let cookie = sessions::build_cookie(
         &session_token,
         86400,
         state.config.secure_cookies,
         state.config.cookie_domain.as_deref(),
     );
+    let remember_cookie = device.map(|(_, token)| device_cookie(&state, &token));
 
     let portal_url = format!("{}/portal/", state.config.api_external_url());
     (
-        [("set-cookie", cookie), ("location", portal_url)],
         StatusCode::FOUND,
+        AppendHeaders(
+            std::iter::once(("set-cookie", cookie))
+                .chain(remember_cookie.map(|c| ("set-cookie", c)))
+                .chain(std::iter::once(("location", portal_url))),
+        ),
     )
         .into_response()
 }
@@ -326,17 +450,18 @@ async fn me(State(state): State<Arc<AppState>>, headers: axum::http::HeaderMap)
     // Try bootstrap Basic auth first
     if let Some(auth) = super::try_bootstrap_auth(&headers, &state.config, &state.db).await {
         // Create a session so subsequent requests work via cookie
-        let session_token = match sessions::create_session(&state.db, &auth.user_id).await {
-            Ok(t) => t,
-            Err(e) => {
-                error!(error = %e, "Failed to create session for bootstrap user");
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(serde_json::json!({ "error": "Failed to create session" })),
-                )
-                    .into_response();
-            }
-        };
+        let session_token =
+            match sessions::create_session(&state.sessions, &auth.user_id, None).await {
+                Ok(t) => t,
+                Err(e) => {
+                    error!(error = %e, "Failed to create session for bootstrap user");
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(serde_json::json!({ "error": "Failed to create session" })),
+                    )
+                        .into_response();
+                }
+            };
 
         let cookie = sessions::build_cookie(
             &session_token,
@@ -352,6 +477,7 @@ async fn me(State(state): State<Arc<AppState>>, headers: axum::http::HeaderMap)
                 "email": auth.email,
                 "display_name": auth.display_name,
                 "is_admin": auth.is_admin,
+                "roles": auth.roles,
                 "chat_url": state.config.chat_external_url(),
             })),
         )
@@ -375,25 +501,118 @@ async fn me(State(state): State<Arc<AppState>>, headers: axum::http::HeaderMap)
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
 
-    let session_user = match super::validate_any_session(cookie_header, &state.db).await {
-        Some(u) => u,
-        None => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(serde_json::json!({ "error": "Authentication required" })),
-            )
-                .into_response();
+    if let Some(session_user) =
+        super::validate_any_session(cookie_header, &state.sessions, &state.db).await
+    {
+        return Json(user_json(&state, &session_user)).into_response();
+    }
+
+    // Session expired — a remembered device can mint a new one silently
+    match refresh_from_device(&state, cookie_header).await {
+        Some((session_user, cookies)) => (
+            AppendHeaders(cookies.map(|c| ("set-cookie", c))),
+            Json(user_json(&state, &session_user)),
+        )
+            .into_response(),
+        None => (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({ "error": "Authentication required" })),
+        )
+            .into_response(),
+    }
+}
+
+/// POST /auth/refresh — Exchange the remembered-device cookie for a new session.
+async fn refresh(State(state): State<Arc<AppState>>, headers: axum::http::HeaderMap) -> Response {
+    let cookie_header = headers
+        .get("cookie")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+
+    match refresh_from_device(&state, cookie_header).await {
+        Some((session_user, cookies)) => (
+            AppendHeaders(cookies.map(|c| ("set-cookie", c))),
+            Json(user_json(&state, &session_user)),
+        )
+            .into_response(),
+        None => (
+            StatusCode::UNAUTHORIZED,
+            [(
+                "set-cookie",
+                devices::clear_cookie(
+                    state.config.secure_cookies,
+                    state.config.cookie_domain.as_deref(),
+                ),
+            )],
+            Json(serde_json::json!({ "error": "Device not remembered" })),
+        )
+            .into_response(),
+    }
+}
+
+fn user_json(state: &AppState, user: &sessions::SessionUser) -> serde_json::Value {
+    serde_json::json!({
+        "user_id": user.user_id,
+        "email": user.email,
+        "display_name": user.display_name,
+        "is_admin": user.is_admin,
+        "roles": rbac::Role::parse_list(&user.roles),
+        "chat_url": state.config.chat_external_url(),
+    })
+}
+
+fn device_cookie(state: &AppState, token: &str) -> String {
+    devices::build_cookie(
+        token,
+        state.config.trusted_device_ttl_days * 86400,
+        state.config.secure_cookies,
+        state.config.cookie_domain.as_deref(),
+    )
+}
+
+/// Mint a session from the `se_device` cookie, rotating the device token.
+/// Returns the session user and the session + device Set-Cookie values.
+async fn refresh_from_device(
+    state: &AppState,
+    cookie_header: &str,
+) -> Option<(sessions::SessionUser, [String; 2])> {
+    let token = devices::token_from_cookies(cookie_header)?;
+    let refreshed = match devices::redeem(&state.db, token).await {
+        Ok(r) => r,
+        Err(e) => {
+            info!(error = %e, "Trusted device refresh rejected");
+            return None;
+        }
+    };
+    let session_token = match sessions::create_session(
+        &state.sessions,
+        &refreshed.user_id,
+        Some(&refreshed.device_id),
+    )
+    .await
+    {
+        Ok(t) => t,
+        Err(e) => {
+            error!(error = %e, "Failed to create session from trusted device");
+            return None;
         }
     };
+    let session_user = sessions::validate_session(&state.sessions, &state.db, &session_token)
+        .await
+        .ok()?;
 
-    Json(serde_json::json!({
-        "user_id": session_user.user_id,
-        "email": session_user.email,
-        "display_name": session_user.display_name,
-        "is_admin": session_user.is_admin,
-        "chat_url": state.config.chat_external_url(),
-    }))
-    .into_response()
+    info!(target: "audit", action = "session.device_refresh", actor = %refreshed.user_id, resource = %refreshed.device_id, "Session refreshed from trusted device");
+
+    let cookies = [
+        sessions::build_cookie(
+            &session_token,
+            86400,
+            state.config.secure_cookies,
+            state.config.cookie_domain.as_deref(),
+        ),
+        device_cookie(state, &refreshed.token),
+    ];
+    Some((session_user, cookies))
 }
 
 /// POST /auth/logout — Clear session.
@@ -404,17 +623,22 @@ async fn logout(State(state): State<Arc<AppState>>, headers: axum::http::HeaderM
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
     for token in super::extract_session_tokens(cookie_header) {
-        let _ = sessions::delete_session(&state.db, token).await;
+        let _ = sessions::delete_session(&state.sessions, token).await;
+    }
+    // Logging out also forgets a remembered device
+    if let Some(token) = devices::token_from_cookies(cookie_header) {
+        let _ = devices::revoke_by_token(&state.db, &state.sessions, token).await;
     }
 
-    // Clear the cookie regardless
-    let clear_cookie = sessions::clear_cookie(
-        state.config.secure_cookies,
-        state.config.cookie_domain.as_deref(),
-    );
+    // Clear the cookies regardless
+    let secure = state.config.secure_cookies;
+    let domain = state.config.cookie_domain.as_deref();
 
     (
-        [("set-cookie", clear_cookie)],
+        AppendHeaders([
+            ("set-cookie", sessions::clear_cookie(secure, domain)),
+            ("set-cookie", devices::clear_cookie(secure, domain)),
+        ]),
         Json(serde_json::json!({ "status": "logged_out" })),
     )
         .into_response()
@@ -423,7 +647,7 @@ async fn logout(State(state): State<Arc<AppState>>, headers: axum::http::HeaderM
 // --- Helper functions ---
 
 /// Build a reqwest HTTP client suitable for OIDC operations.
-fn build_http_client() -> openidconnect::reqwest::Client {
+pub(super) fn build_http_client() -> openidconnect::reqwest::Client {
     openidconnect::reqwest::ClientBuilder::new()
         // Disable redirects to prevent SSRF
         .redirect(openidconnect::reqwest::redirect::Policy::none())
@@ -433,7 +657,7 @@ fn build_http_client() -> openidconnect::reqwest::Client {
 
 async fn load_idp(db: &Database, idp_id: &str) -> Result<IdpConfig> {
     sqlx::query_as::<_, IdpConfig>(
-        "SELECT id, name, issuer, client_id, client_secret_enc, scopes, enabled, created_at FROM idp_configs WHERE id = ? AND enabled = 1",
+        "SELECT id, name, issuer, client_id, client_secret_enc, scopes, enabled, created_at, groups_claim, group_mappings FROM idp_configs WHERE id = ? AND enabled = 1",
     )
     .bind(idp_id)
     .fetch_optional(&db.pool)
@@ -478,6 +702,113 @@ struct AuthState {
     idp_id: String,
     nonce: String,
     pkce_verifier: String,
+    user_agent_hash: Option<String>,
+    network_hash: Option<String>,
+    remember_device: bool,
+}
+
+/// How strictly an OIDC callback must match the client that started the
+/// login (`OIDC_STATE_BINDING`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StateBinding {
+    /// Same network prefix and User-Agent.
+    Strict,
+    /// Same User-Agent only — for clients that roam between networks.
+    UserAgent,
+    /// No binding.
+    Off,
+}
+
+impl StateBinding {
+    fn from_config(value: &str) -> Self {
+        match value {
+            "strict" => Self::Strict,
+            "user_agent" => Self::UserAgent,
+            "off" => Self::Off,
+            other => {
+                warn!(value = %other, "Unknown OIDC_STATE_BINDING — using strict");
+                Self::Strict
+            }
+        }
+    }
+}
+
+/// Client identity recorded at login and re-derived at callback. Values are
+/// hashed with the CSRF token as salt, so stored rows reveal nothing reusable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ClientFingerprint {
+    user_agent_hash: String,
+    network_hash: Option<String>,
+}
+
+impl ClientFingerprint {
+    fn new(csrf_token: &str, req: &Request, state: &AppState) -> Self {
+        let user_agent = req
+            .headers()
+            .get("user-agent")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+        let ip = client_ip(
+            req.headers(),
+            req.extensions(),
+            state.config.forwarded_hops(),
+        );
+        Self::from_parts(csrf_token, user_agent, ip)
+    }
+
+    fn from_parts(csrf_token: &str, user_agent: &str, ip: Option<IpAddr>) -> Self {
+        Self {
+            user_agent_hash: salted_hash(csrf_token, user_agent),
+            network_hash: ip.map(|ip| salted_hash(csrf_token, &network_prefix(ip))),
+        }
+    }
+
+    /// Whether this (callback) fingerprint satisfies the stored one. Hashes
+    /// missing from the stored row (pre-binding rows, no peer address) are not
+    /// checked.
+    fn matches(&self, stored: &AuthState, binding: StateBinding) -> bool {
+        if binding == StateBinding::Off {
+            return true;
+        }
+        let user_agent_ok = stored
+            .user_agent_hash
+            .as_deref()
+            .is_none_or(|h| h == self.user_agent_hash);
+        if binding == StateBinding::UserAgent {
+            return user_agent_ok;
+        }
+        let network_ok = stored
+            .network_hash
+            .as_deref()
+            .is_none_or(|h| self.network_hash.as_deref() == Some(h));
+        user_agent_ok && network_ok
+    }
+}
+
+/// Coarse network range of a client: /24 for IPv4, /64 for IPv6, so address
+/// churn within a carrier or DHCP pool doesn't break a login.
+fn network_prefix(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            format!("{a}.{b}.{c}.0/24")
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => network_prefix(IpAddr::V4(v4)),
+            None => {
+                let s = v6.segments();
+                format!("{:x}:{:x}:{:x}:{:x}::/64", s[0], s[1], s[2], s[3])
+            }
+        },
+    }
+}
+
+fn salted_hash(salt: &str, value: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(value.as_bytes());
+    hex::encode(hasher.finalize())
 }
 
 async fn store_auth_state(
@@ -486,14 +817,19 @@ async fn store_auth_state(
     nonce: &str,
     idp_id: &str,
     pkce_verifier: &str,
+    fingerprint: &ClientFingerprint,
+    remember_device: bool,
 ) -> Result<()> {
     sqlx::query(
-        "INSERT INTO oidc_auth_state (csrf_token, nonce, idp_id, pkce_verifier, expires_at) VALUES (?, ?, ?, ?, datetime('now', '+10 minutes'))",
+        "INSERT INTO oidc_auth_state (csrf_token, nonce, idp_id, pkce_verifier, user_agent_hash, network_hash, remember_device, expires_at) VALUES (?, ?, ?, ?, ?, ?, ?, datetime('now', '+10 minutes'))",
     )
     .bind(csrf_token)
     .bind(nonce)
     .bind(idp_id)
     .bind(pkce_verifier)
+    .bind(&fingerprint.user_agent_hash)
+    .bind(&fingerprint.network_hash)
+    .bind(remember_device)
     .execute(&db.pool)
     .await?;
     Ok(())
@@ -501,7 +837,7 @@ async fn store_auth_state(
 
 async fn load_auth_state(db: &Database, csrf_token: &str) -> Result<AuthState> {
     sqlx::query_as::<_, AuthState>(
-        "SELECT idp_id, nonce, pkce_verifier FROM oidc_auth_state WHERE csrf_token = ? AND expires_at > datetime('now')",
+        "SELECT idp_id, nonce, pkce_verifier, user_agent_hash, network_hash, remember_device FROM oidc_auth_state WHERE csrf_token = ? AND expires_at > datetime('now')",
     )
     .bind(csrf_token)
     .fetch_optional(&db.pool)
@@ -569,3 +905,91 @@ async fn upsert_user(
 
     Ok(id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(fp: &ClientFingerprint) -> AuthState {
+        AuthState {
+            idp_id: "idp".into(),
+            nonce: "n".into(),
+            pkce_verifier: "v".into(),
+            user_agent_hash: Some(fp.user_agent_hash.clone()),
+            network_hash: fp.network_hash.clone(),
+            remember_device: false,
+        }
+    }
+
+    fn ip(s: &str) -> Option<IpAddr> {
+        Some(s.parse().unwrap())
+    }
+
+    #[test]
+    fn network_prefix_is_coarse() {
+        assert_eq!(
+            network_prefix("192.0.2.17".parse().unwrap()),
+            "192.0.2.0/24"
+        );
+        assert_eq!(
+            network_prefix("2001:db8:1:2:aa:bb:cc:dd".parse().unwrap()),
+            "2001:db8:1:2::/64"
+        );
+        assert_eq!(
+            network_prefix("::ffff:192.0.2.17".parse().unwrap()),
+            "192.0.2.0/24"
+        );
+    }
+
+    #[test]
+    fn same_client_matches_in_every_mode() {
+        let login = ClientFingerprint::from_parts("csrf", "Firefox", ip("192.0.2.17"));
+        // Address changed within the same /24
+        let callback = ClientFingerprint::from_parts("csrf", "Firefox", ip("192.0.2.99"));
+        for binding in [
+            StateBinding::Strict,
+            StateBinding::UserAgent,
+            StateBinding::Off,
+        ] {
+            assert!(callback.matches(&stored(&login), binding));
+        }
+    }
+
+    #[test]
+    fn strict_rejects_other_network_user_agent_allows_it() {
+        let login = ClientFingerprint::from_parts("csrf", "Firefox", ip("192.0.2.17"));
+        let roamed = ClientFingerprint::from_parts("csrf", "Firefox", ip("198.51.100.4"));
+        assert!(!roamed.matches(&stored(&login), StateBinding::Strict));
+        assert!(roamed.matches(&stored(&login), StateBinding::UserAgent));
+    }
+
+    #[test]
+    fn different_user_agent_rejected_unless_off() {
+        let login = ClientFingerprint::from_parts("csrf", "Firefox", ip("192.0.2.17"));
+        let other = ClientFingerprint::from_parts("csrf", "curl/8.0", ip("192.0.2.17"));
+        assert!(!other.matches(&stored(&login), StateBinding::Strict));
+        assert!(!other.matches(&stored(&login), StateBinding::UserAgent));
+        assert!(other.matches(&stored(&login), StateBinding::Off));
+    }
+
+    #[test]
+    fn hashes_are_salted_per_state() {
+        let a = ClientFingerprint::from_parts("csrf-a", "Firefox", ip("192.0.2.17"));
+        let b = ClientFingerprint::from_parts("csrf-b", "Firefox", ip("192.0.2.17"));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn rows_without_fingerprint_are_not_checked() {
+        let legacy = AuthState {
+            idp_id: "idp".into(),
+            nonce: "n".into(),
+            pkce_verifier: "v".into(),
+            user_agent_hash: None,
+            network_hash: None,
+            remember_device: false,
+        };
+        let callback = ClientFingerprint::from_parts("csrf", "anything", ip("203.0.113.1"));
+        assert!(callback.matches(&legacy, StateBinding::Strict));
+    }
+}
@@ -0,0 +1,270 @@
+//! Per-IP rate limiting for the unauthenticated `/auth/*` routes.
+//!
+//! Each client IP gets a token bucket holding `burst` requests that refills at
+//! `per_minute / 60` requests per second. An empty bucket yields 429 with a
+//! `Retry-After` header. This blunts OIDC state flooding (every `/auth/login`
+//! inserts an `oidc_auth_state` row) and provider/callback enumeration.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use tracing::warn;
+
+use crate::auth::client_ip;
+use crate::config::AppConfig;
+
+/// At this many tracked IPs, the map is pruned before a new IP is added.
+const PRUNE_THRESHOLD: usize = 10_000;
+/// Pruning leaves at most this many IPs, so its pass over the map runs at
+/// most once per `PRUNE_THRESHOLD - PRUNE_TARGET` new IPs.
+const PRUNE_TARGET: usize = PRUNE_THRESHOLD * 3 / 4;
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    level: f64,
+    /// When this IP was last seen.
+    updated: Instant,
+    /// Set once the bucket runs dry, so rejections are logged once per episode.
+    limited: bool,
+}
+
+/// Shared per-IP token buckets.
+#[derive(Clone)]
+pub struct AuthRateLimiter {
+    capacity: f64,
+    refill_per_sec: f64,
+    forwarded_hops: usize,
+    buckets: Arc<Mutex<HashMap<IpAddr, Bucket>>>,
+}
+
+impl AuthRateLimiter {
+    /// Build from config; `None` when `AUTH_RATE_LIMIT_PER_MINUTE` is 0.
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        if config.auth_rate_limit_per_minute == 0 {
+            return None;
+        }
+        Some(Self::new(
+            config.auth_rate_limit_per_minute,
+            config.auth_rate_limit_burst,
+            config.forwarded_hops(),
+        ))
+    }
+
+    pub fn new(per_minute: u32, burst: u32, forwarded_hops: usize) -> Self {
+        Self {
+            capacity: burst.max(1) as f64,
+            refill_per_sec: per_minute as f64 / 60.0,
+            forwarded_hops,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Take one token for `ip`. On rejection, returns how long until a token is
+    /// available and whether this is the first rejection since the bucket ran dry.
+    fn check(&self, ip: IpAddr, now: Instant) -> Result<(), (Duration, bool)> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() >= PRUNE_THRESHOLD && !buckets.contains_key(&ip) {
+            self.prune(&mut buckets, now);
+        }
+
+        let bucket = buckets.entry(ip).or_insert(Bucket {
+            level: self.capacity,
+            updated: now,
+            limited: false,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.level = (bucket.level + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.updated = now;
+
+        if bucket.level >= 1.0 {
+            bucket.level -= 1.0;
+            bucket.limited = false;
+            return Ok(());
+        }
+
+        let first = !bucket.limited;
+        bucket.limited = true;
+        let wait = (1.0 - bucket.level) / self.refill_per_sec;
+        Err((Duration::from_secs_f64(wait), first))
+    }
+
+    /// Drop buckets idle long enough to have refilled completely, then, if
+    /// rotating IPs still keep the map above `PRUNE_TARGET`, the least
+    /// recently seen ones. A dropped IP starts again with a full bucket.
+    fn prune(&self, buckets: &mut HashMap<IpAddr, Bucket>, now: Instant) {
+        let idle = Duration::try_from_secs_f64(self.capacity / self.refill_per_sec)
+            .unwrap_or(Duration::MAX);
+        buckets.retain(|_, b| now.duration_since(b.updated) < idle);
+
+        let excess = buckets.len().saturating_sub(PRUNE_TARGET);
+        if excess == 0 {
+            return;
+        }
+        let mut seen: Vec<(Instant, IpAddr)> =
+            buckets.iter().map(|(ip, b)| (b.updated, *ip)).collect();
+        seen.select_nth_unstable(excess - 1);
+        for (_, ip) in &seen[..excess] {
+            buckets.remove(ip);
+        }
+    }
+}
+
+/// Middleware applied to the `/auth` router.
+pub async fn middleware(
+    State(limiter): State<AuthRateLimiter>,
+    req: Request,
+    next: Next,
+) -> Response {
+    // No connection info (e.g. in-process tests) — nothing to key on
+    let Some(ip) = client_ip(req.headers(), req.extensions(), limiter.forwarded_hops) else {
+        return next.run(req).await;
+    };
+
+    match limiter.check(ip, Instant::now()) {
+        Ok(()) => next.run(req).await,
+        Err((retry_after, first)) => {
+            if first {
+                warn!(
+                    target: "audit",
+                    action = "auth.rate_limited",
+                    ip = %ip,
+                    path = %req.uri().path(),
+                    "Auth endpoint rate limit exceeded"
+                );
+            }
+            let secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            let mut response = (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(serde_json::json!({ "error": "Too many requests" })),
+            )
+                .into_response();
+            if let Ok(v) = HeaderValue::from_str(&secs.to_string()) {
+                response.headers_mut().insert(header::RETRY_AFTER, v);
+            }
+            response
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn allows_burst_then_rejects() {
+        let limiter = AuthRateLimiter::new(60, 3, 0);
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.check(ip("10.0.0.1"), now).is_ok());
+        }
+        let (wait, first) = limiter.check(ip("10.0.0.1"), now).unwrap_err();
+        assert!(first);
+        assert_eq!(wait, Duration::from_secs(1));
+        // Only the first rejection in a run is flagged for logging
+        assert!(!limiter.check(ip("10.0.0.1"), now).unwrap_err().1);
+    }
+
+    #[test]
+    fn buckets_are_per_ip() {
+        let limiter = AuthRateLimiter::new(60, 1, 0);
+        let now = Instant::now();
+        assert!(limiter.check(ip("10.0.0.1"), now).is_ok());
+        assert!(limiter.check(ip("10.0.0.1"), now).is_err());
+        assert!(limiter.check(ip("10.0.0.2"), now).is_ok());
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let limiter = AuthRateLimiter::new(30, 2, 0);
+        let start = Instant::now();
+        assert!(limiter.check(ip("10.0.0.1"), start).is_ok());
+        assert!(limiter.check(ip("10.0.0.1"), start).is_ok());
+        assert!(limiter.check(ip("10.0.0.1"), start).is_err());
+        // 30/min = one token every 2s
+        assert!(limiter
+            .check(ip("10.0.0.1"), start + Duration::from_secs(2))
+            .is_ok());
+        // Refill never exceeds the burst size
+        let later = start + Duration::from_secs(600);
+        assert!(limiter.check(ip("10.0.0.1"), later).is_ok());
+        assert!(limiter.check(ip("10.0.0.1"), later).is_ok());
+        assert!(limiter.check(ip("10.0.0.1"), later).is_err());
+    }
+
+    #[test]
+    fn map_stays_bounded_with_rotating_ips() {
+        let limiter = AuthRateLimiter::new(60, 10, 0);
+        let start = Instant::now();
+        // Every IP takes one token and leaves a partly drained bucket
+        let count = PRUNE_THRESHOLD as u32 + 5_000;
+        for i in 0..count {
+            let ip = IpAddr::from((0x0a00_0000 + i).to_be_bytes());
+            let now = start + Duration::from_millis(i as u64);
+            assert!(limiter.check(ip, now).is_ok());
+            assert!(limiter.buckets.lock().unwrap().len() <= PRUNE_THRESHOLD);
+        }
+        // The most recently seen IPs are kept; the oldest were dropped
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(buckets.contains_key(&IpAddr::from((0x0a00_0000 + count - 1).to_be_bytes())));
+        assert!(!buckets.contains_key(&ip("10.0.0.0")));
+    }
+
+    fn with_peer(addr: &str) -> axum::http::Extensions {
+        let mut ext = axum::http::Extensions::new();
+        ext.insert(axum::extract::ConnectInfo(
+            addr.parse::<std::net::SocketAddr>().unwrap(),
+        ));
+        ext
+    }
+
+    #[test]
+    fn forwarded_for_only_when_trusted() {
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("203.0.113.7, 10.0.0.9"),
+        );
+        let ext = with_peer("10.0.0.9:5000");
+
+        assert_eq!(client_ip(&headers, &ext, 0), Some(ip("10.0.0.9")));
+        assert_eq!(client_ip(&headers, &ext, 2), Some(ip("203.0.113.7")));
+        // A single proxy: its own append is the right-most entry
+        assert_eq!(client_ip(&headers, &ext, 1), Some(ip("10.0.0.9")));
+        // Fewer entries than hops: the left-most is all there is
+        assert_eq!(client_ip(&headers, &ext, 5), Some(ip("203.0.113.7")));
+        // Falls back to the peer when the header is missing
+        assert_eq!(
+            client_ip(&axum::http::HeaderMap::new(), &ext, 1),
+            Some(ip("10.0.0.9"))
+        );
+        // No connection info at all
+        assert_eq!(client_ip(&headers, &axum::http::Extensions::new(), 0), None);
+    }
+
+    #[test]
+    fn spoofed_forwarded_for_does_not_change_key() {
+        let ext = with_peer("10.0.0.1:5000");
+        let mut honest = axum::http::HeaderMap::new();
+        honest.insert("x-forwarded-for", HeaderValue::from_static("198.51.100.4"));
+        let mut spoofed = axum::http::HeaderMap::new();
+        spoofed.insert(
+            "x-forwarded-for",
+            HeaderValue::from_static("192.0.2.99, 198.51.100.4"),
+        );
+
+        assert_eq!(client_ip(&honest, &ext, 1), Some(ip("198.51.100.4")));
+        assert_eq!(client_ip(&spoofed, &ext, 1), Some(ip("198.51.100.4")));
+    }
+}
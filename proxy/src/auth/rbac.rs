@@ -0,0 +1,209 @@
+//! Scoped admin roles.
+//!
+//! `is_admin` grants the whole admin API. A user without it can be given
+//! roles that each open one area: `model_admin` (models, categories,
+//! containers, downloads), `user_admin` (users and their roles),
+//! `reservation_admin` (reservations and approval rules). `viewer` can read
+//! every admin area a role can reach, and so can holders of any other role.
+//! `usage_auditor` is a viewer that also sees who used what when the
+//! `analytics_privacy` setting hides users in usage analytics, full admins
+//! included. IdPs, settings, approvals, system prompts, request logs and
+//! change sets stay with full admins.
+
+use axum::extract::{Request, State};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use serde::Serialize;
+
+use super::SessionAuth;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    ModelAdmin,
+    UserAdmin,
+    ReservationAdmin,
+    Viewer,
+    UsageAuditor,
+}
+
+impl Role {
+    pub const ALL: [Role; 5] = [
+        Role::ModelAdmin,
+        Role::UserAdmin,
+        Role::ReservationAdmin,
+        Role::Viewer,
+        Role::UsageAuditor,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::ModelAdmin => "model_admin",
+            Role::UserAdmin => "user_admin",
+            Role::ReservationAdmin => "reservation_admin",
+            Role::Viewer => "viewer",
+            Role::UsageAuditor => "usage_auditor",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.as_str() == s)
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Role::ModelAdmin => "Manage models, categories, containers and downloads",
+            Role::UserAdmin => "Manage users and their roles",
+            Role::ReservationAdmin => "Manage reservations and auto-approval rules",
+            Role::Viewer => "Read-only access to the admin API",
+            Role::UsageAuditor => {
+                "Read-only access, with per-user usage shown when analytics privacy is on"
+            }
+        }
+    }
+
+    /// Roles from a comma-separated list (as built by `group_concat`).
+    /// Unknown names are ignored.
+    pub fn parse_list(s: &str) -> Vec<Self> {
+        s.split(',').filter_map(|r| Self::parse(r.trim())).collect()
+    }
+
+    /// Whether this role may change users and their roles, and so hand out
+    /// any role. Grants of it go through two-person approval when that's on.
+    pub fn manages_users(&self) -> bool {
+        self.permission() == Some(Permission::Users)
+    }
+
+    /// The area this role may change.
+    fn permission(&self) -> Option<Permission> {
+        match self {
+            Role::ModelAdmin => Some(Permission::Models),
+            Role::UserAdmin => Some(Permission::Users),
+            Role::ReservationAdmin => Some(Permission::Reservations),
+            Role::Viewer | Role::UsageAuditor => None,
+        }
+    }
+}
+
+/// What an admin route requires.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Any role; used for read-only status and analytics routes.
+    View,
+    Models,
+    Users,
+    Reservations,
+    /// Full admins only.
+    Admin,
+}
+
+/// Whether `session` may make a `method` request to a route that requires
+/// `permission`. Any role may read `Models`, `Users` and `Reservations`
+/// routes; changing them takes the matching role.
+pub fn allows(session: &SessionAuth, permission: Permission, method: &Method) -> bool {
+    if session.is_admin {
+        return true;
+    }
+    match permission {
+        Permission::Admin => false,
+        Permission::View => !session.roles.is_empty(),
+        area => {
+            session.roles.iter().any(|r| r.permission() == Some(area))
+                || (*method == Method::GET && !session.roles.is_empty())
+        }
+    }
+}
+
+/// Middleware: let full admins and role holders into `/api/admin` (must be
+/// chained after session_auth_middleware). Routes then check their own
+/// permission with `require_permission`.
+pub async fn admin_area_middleware(req: Request, next: Next) -> Result<Response, Response> {
+    let session = req.extensions().get::<SessionAuth>().ok_or_else(|| {
+        (
+            StatusCode::UNAUTHORIZED,
+            Json(serde_json::json!({ "error": "Authentication required" })),
+        )
+            .into_response()
+    })?;
+
+    if !session.is_admin && session.roles.is_empty() {
+        return Err(forbidden("Admin access required"));
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// Route middleware: require `permission` for the matched route, e.g.
+/// `.route_layer(middleware::from_fn_with_state(Permission::Models, require_permission))`.
+pub async fn require_permission(
+    State(permission): State<Permission>,
+    req: Request,
+    next: Next,
+) -> Result<Response, Response> {
+    let allowed = req
+        .extensions()
+        .get::<SessionAuth>()
+        .is_some_and(|session| allows(session, permission, req.method()));
+    if !allowed {
+        return Err(forbidden(match permission {
+            Permission::Admin => "Admin access required",
+            _ => "Your admin roles don't allow this action",
+        }));
+    }
+    Ok(next.run(req).await)
+}
+
+fn forbidden(message: &str) -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(serde_json::json!({ "error": message })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(is_admin: bool, roles: &[Role]) -> SessionAuth {
+        SessionAuth {
+            user_id: "u1".into(),
+            is_admin,
+            roles: roles.to_vec(),
+            email: None,
+            display_name: None,
+        }
+    }
+
+    #[test]
+    fn roles_round_trip() {
+        assert_eq!(
+            Role::parse_list("viewer,model_admin,bogus"),
+            [Role::Viewer, Role::ModelAdmin]
+        );
+        assert!(Role::parse_list("").is_empty());
+    }
+
+    #[test]
+    fn roles_grant_their_area_only() {
+        let models = session(false, &[Role::ModelAdmin]);
+        assert!(allows(&models, Permission::Models, &Method::POST));
+        assert!(allows(&models, Permission::Reservations, &Method::GET));
+        assert!(!allows(&models, Permission::Reservations, &Method::POST));
+        assert!(!allows(&models, Permission::Admin, &Method::GET));
+
+        let viewer = session(false, &[Role::Viewer]);
+        assert!(allows(&viewer, Permission::View, &Method::GET));
+        assert!(allows(&viewer, Permission::Users, &Method::GET));
+        assert!(!allows(&viewer, Permission::Users, &Method::PUT));
+
+        let nobody = session(false, &[]);
+        assert!(!allows(&nobody, Permission::View, &Method::GET));
+        assert!(!allows(&nobody, Permission::Models, &Method::GET));
+
+        let admin = session(true, &[]);
+        assert!(allows(&admin, Permission::Admin, &Method::DELETE));
+    }
+}
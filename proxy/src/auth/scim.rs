@@ -0,0 +1,644 @@
+//! SCIM 2.0 user provisioning (`/scim/v2`).
+//!
+//! Each IdP can be given a SCIM bearer token (`POST /api/admin/idps/:id/scim-token`);
+//! requests made with it see and change only that IdP's users. The IdP's
+//! `externalId` is the OIDC subject, so users it creates are matched at their
+//! first login. Deactivating a user (`active: false` or `DELETE`) deletes
+//! their sessions and trusted devices and revokes their API tokens. Users
+//! are never deleted, so usage history keeps its owner.
+//!
+//! Only what IdPs use for provisioning is implemented: `eq` filters on
+//! `userName` and `externalId`, and `active`, `userName`, `displayName` and
+//! `emails` on create, replace and patch.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Extension, Json, Router};
+use chrono::{DateTime, Utc};
+use rand::RngExt;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::info;
+use uuid::Uuid;
+
+use super::sessions::SessionStore;
+use super::tokens::hash_token;
+use crate::api::error;
+use crate::AppState;
+
+const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+const LIST_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+const ERROR_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+const PATCH_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:PatchOp";
+
+/// Page size when the request gives no `count`, and the most it may ask for.
+const DEFAULT_COUNT: i64 = 100;
+const MAX_COUNT: i64 = 1000;
+
+/// Generate a SCIM bearer token. Only its hash is stored.
+pub fn generate_token() -> String {
+    let bytes: [u8; 32] = rand::rng().random();
+    format!("se-scim-{}", hex::encode(bytes))
+}
+
+/// Activate or deactivate a user. Deactivating deletes their sessions and
+/// trusted devices and revokes their API tokens; reactivating doesn't bring
+/// those back. Returns `false` if the user was already in that state.
+pub(crate) async fn set_user_active(
+    state: &AppState,
+    actor: &str,
+    user_id: &str,
+    active: bool,
+) -> Result<bool> {
+    let mut tx = state.db.pool.begin().await?;
+    let result = sqlx::query(
+        "UPDATE users SET active = ?, deactivated_at = CASE WHEN ? THEN NULL ELSE datetime('now') END \
+         WHERE id = ? AND active != ?",
+    )
+    .bind(active)
+    .bind(active)
+    .bind(user_id)
+    .bind(active)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    let mut revoked: Vec<String> = Vec::new();
+    if !active {
+        sqlx::query("DELETE FROM trusted_devices WHERE user_id = ?")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+        revoked = sqlx::query_scalar("SELECT id FROM tokens WHERE user_id = ? AND revoked = 0")
+            .bind(user_id)
+            .fetch_all(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE tokens SET revoked = 1 WHERE user_id = ? AND revoked = 0")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    if !active {
+        state.sessions.delete_for_user(user_id).await?;
+    }
+
+    for token_id in &revoked {
+        super::token_changed(state, token_id);
+    }
+    let action = if active {
+        "user.activate"
+    } else {
+        "user.deactivate"
+    };
+    info!(target: "audit", action, actor = %actor, resource = %user_id, revoked_tokens = revoked.len(), "User active state changed");
+    Ok(true)
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/Users", get(list_users).post(create_user))
+        .route(
+            "/Users/{id}",
+            get(get_user)
+                .put(replace_user)
+                .patch(patch_user)
+                .delete(delete_user),
+        )
+        .layer(middleware::from_fn_with_state(state.clone(), scim_auth))
+        .with_state(state)
+}
+
+/// The IdP a SCIM request was authenticated as.
+#[derive(Debug, Clone)]
+struct ScimIdp(String);
+
+impl ScimIdp {
+    fn actor(&self) -> String {
+        format!("scim:{}", self.0)
+    }
+}
+
+/// Middleware: resolve the bearer token to an enabled IdP.
+async fn scim_auth(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, Response> {
+    let token = req
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| scim_error(StatusCode::UNAUTHORIZED, "Bearer token required"))?;
+    let idp_id: Option<String> =
+        sqlx::query_scalar("SELECT id FROM idp_configs WHERE scim_token_hash = ? AND enabled = 1")
+            .bind(hash_token(token))
+            .fetch_optional(&state.db.pool)
+            .await
+            .map_err(|e| error::internal_error("scim_auth", e))?;
+    let idp_id =
+        idp_id.ok_or_else(|| scim_error(StatusCode::UNAUTHORIZED, "Invalid SCIM token"))?;
+    req.extensions_mut().insert(ScimIdp(idp_id));
+    Ok(next.run(req).await)
+}
+
+fn scim_response(status: StatusCode, body: Value) -> Response {
+    (
+        status,
+        [(header::CONTENT_TYPE, "application/scim+json")],
+        body.to_string(),
+    )
+        .into_response()
+}
+
+fn scim_error(status: StatusCode, detail: &str) -> Response {
+    scim_response(
+        status,
+        serde_json::json!({
+            "schemas": [ERROR_SCHEMA],
+            "status": status.as_u16().to_string(),
+            "detail": detail,
+        }),
+    )
+}
+
+fn not_found() -> Response {
+    scim_error(StatusCode::NOT_FOUND, "User not found")
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct UserRow {
+    id: String,
+    subject: String,
+    email: Option<String>,
+    display_name: Option<String>,
+    active: bool,
+    created_at: DateTime<Utc>,
+}
+
+const USER_SELECT: &str =
+    "SELECT id, subject, email, display_name, active, created_at FROM users WHERE idp_id = ?";
+
+impl UserRow {
+    fn resource(&self) -> Value {
+        let emails: Vec<Value> = self
+            .email
+            .iter()
+            .map(|email| serde_json::json!({ "value": email, "primary": true }))
+            .collect();
+        serde_json::json!({
+            "schemas": [USER_SCHEMA],
+            "id": self.id,
+            "externalId": self.subject,
+            "userName": self.email.as_deref().unwrap_or(&self.subject),
+            "displayName": self.display_name,
+            "active": self.active,
+            "emails": emails,
+            "meta": {
+                "resourceType": "User",
+                "created": self.created_at.to_rfc3339(),
+                "location": format!("/scim/v2/Users/{}", self.id),
+            },
+        })
+    }
+}
+
+async fn fetch_user(state: &AppState, idp: &ScimIdp, id: &str) -> Result<Option<UserRow>> {
+    Ok(
+        sqlx::query_as::<_, UserRow>(&format!("{USER_SELECT} AND id = ?"))
+            .bind(&idp.0)
+            .bind(id)
+            .fetch_optional(&state.db.pool)
+            .await?,
+    )
+}
+
+/// Parse `attribute eq "value"`, the only filter IdPs send when provisioning.
+fn parse_filter(filter: &str) -> Option<(String, String)> {
+    let (attribute, rest) = filter.trim().split_once(char::is_whitespace)?;
+    let (op, value) = rest.trim_start().split_once(char::is_whitespace)?;
+    if !op.eq_ignore_ascii_case("eq") {
+        return None;
+    }
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((attribute.to_string(), value.replace("\\\"", "\"")))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListQuery {
+    filter: Option<String>,
+    start_index: Option<i64>,
+    count: Option<i64>,
+}
+
+/// GET /scim/v2/Users — List the IdP's users, optionally filtered.
+async fn list_users(
+    State(state): State<Arc<AppState>>,
+    Extension(idp): Extension<ScimIdp>,
+    Query(query): Query<ListQuery>,
+) -> Response {
+    let (condition, value) = match query.filter.as_deref().map(parse_filter) {
+        None => ("", None),
+        Some(Some((attribute, value))) if attribute.eq_ignore_ascii_case("userName") => {
+            (" AND COALESCE(email, subject) = ?", Some(value))
+        }
+        Some(Some((attribute, value))) if attribute.eq_ignore_ascii_case("externalId") => {
+            (" AND subject = ?", Some(value))
+        }
+        Some(_) => {
+            return scim_error(
+                StatusCode::BAD_REQUEST,
+                "Only 'userName eq' and 'externalId eq' filters are supported",
+            )
+        }
+    };
+    let start_index = query.start_index.unwrap_or(1).max(1);
+    let count = query.count.unwrap_or(DEFAULT_COUNT).clamp(0, MAX_COUNT);
+
+    let count_sql = format!("SELECT COUNT(*) FROM users WHERE idp_id = ?{condition}");
+    let mut total = sqlx::query_scalar::<_, i64>(&count_sql).bind(&idp.0);
+    let list_sql = format!("{USER_SELECT}{condition} ORDER BY created_at, id LIMIT ? OFFSET ?");
+    let mut rows = sqlx::query_as::<_, UserRow>(&list_sql).bind(&idp.0);
+    if let Some(value) = &value {
+        total = total.bind(value);
+        rows = rows.bind(value);
+    }
+    let total = total.fetch_one(&state.db.pool).await;
+    let rows = rows
+        .bind(count)
+        .bind(start_index - 1)
+        .fetch_all(&state.db.pool)
+        .await;
+    let (total, rows) = match (total, rows) {
+        (Ok(total), Ok(rows)) => (total, rows),
+        (Err(e), _) | (_, Err(e)) => return error::internal_error("scim_list_users", e),
+    };
+
+    scim_response(
+        StatusCode::OK,
+        serde_json::json!({
+            "schemas": [LIST_SCHEMA],
+            "totalResults": total,
+            "startIndex": start_index,
+            "itemsPerPage": rows.len(),
+            "Resources": rows.iter().map(UserRow::resource).collect::<Vec<_>>(),
+        }),
+    )
+}
+
+/// GET /scim/v2/Users/:id
+async fn get_user(
+    State(state): State<Arc<AppState>>,
+    Extension(idp): Extension<ScimIdp>,
+    Path(id): Path<String>,
+) -> Response {
+    match fetch_user(&state, &idp, &id).await {
+        Ok(Some(user)) => scim_response(StatusCode::OK, user.resource()),
+        Ok(None) => not_found(),
+        Err(e) => error::internal_error("scim_get_user", e),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ScimEmail {
+    value: String,
+    #[serde(default)]
+    primary: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimUserRequest {
+    user_name: String,
+    external_id: Option<String>,
+    display_name: Option<String>,
+    #[serde(default)]
+    emails: Vec<ScimEmail>,
+    active: Option<bool>,
+}
+
+impl ScimUserRequest {
+    /// The primary email, else the first, else `userName` if it looks like one.
+    fn email(&self) -> Option<String> {
+        self.emails
+            .iter()
+            .find(|e| e.primary)
+            .or(self.emails.first())
+            .map(|e| e.value.clone())
+            .or_else(|| self.user_name.contains('@').then(|| self.user_name.clone()))
+    }
+
+    fn validate(&self) -> Option<Response> {
+        let too_long = self.user_name.len() > error::MAX_NAME
+            || self
+                .external_id
+                .as_deref()
+                .is_some_and(|v| v.len() > error::MAX_NAME)
+            || self
+                .display_name
+                .as_deref()
+                .is_some_and(|v| v.len() > error::MAX_NAME)
+            || self.emails.iter().any(|e| e.value.len() > error::MAX_NAME);
+        too_long.then(|| {
+            scim_error(
+                StatusCode::BAD_REQUEST,
+                &format!(
+                    "Attributes exceed maximum length of {} characters",
+                    error::MAX_NAME
+                ),
+            )
+        })
+    }
+}
+
+/// POST /scim/v2/Users — Provision a user ahead of their first login.
+async fn create_user(
+    State(state): State<Arc<AppState>>,
+    Extension(idp): Extension<ScimIdp>,
+    Json(req): Json<ScimUserRequest>,
+) -> Response {
+    if let Some(r) = req.validate() {
+        return r;
+    }
+    let subject = req.external_id.as_deref().unwrap_or(&req.user_name);
+    let id = Uuid::new_v4().to_string();
+    let active = req.active.unwrap_or(true);
+    let result = sqlx::query(
+        "INSERT INTO users (id, idp_id, subject, email, display_name, active, deactivated_at) \
+         VALUES (?, ?, ?, ?, ?, ?, CASE WHEN ? THEN NULL ELSE datetime('now') END) \
+         ON CONFLICT (idp_id, subject) DO NOTHING",
+    )
+    .bind(&id)
+    .bind(&idp.0)
+    .bind(subject)
+    .bind(req.email())
+    .bind(&req.display_name)
+    .bind(active)
+    .bind(active)
+    .execute(&state.db.pool)
+    .await;
+    match result {
+        Ok(r) if r.rows_affected() == 0 => {
+            return scim_response(
+                StatusCode::CONFLICT,
+                serde_json::json!({
+                    "schemas": [ERROR_SCHEMA],
+                    "status": "409",
+                    "scimType": "uniqueness",
+                    "detail": "A user with this externalId already exists",
+                }),
+            )
+        }
+        Ok(_) => {}
+        Err(e) => return error::internal_error("scim_create_user", e),
+    }
+    info!(target: "audit", action = "user.provision", actor = %idp.actor(), resource = %id, subject, "IdP provisioned user");
+
+    match fetch_user(&state, &idp, &id).await {
+        Ok(Some(user)) => scim_response(StatusCode::CREATED, user.resource()),
+        Ok(None) => not_found(),
+        Err(e) => error::internal_error("scim_create_user:fetch", e),
+    }
+}
+
+/// Attribute changes from a replace or patch.
+#[derive(Debug, Default, PartialEq)]
+struct Changes {
+    email: Option<String>,
+    display_name: Option<String>,
+    active: Option<bool>,
+}
+
+/// Apply the changes, then return the updated user.
+async fn apply_changes(state: &AppState, idp: &ScimIdp, id: &str, changes: Changes) -> Response {
+    let user = match fetch_user(state, idp, id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return not_found(),
+        Err(e) => return error::internal_error("scim_update_user:lookup", e),
+    };
+    if changes.email.is_some() || changes.display_name.is_some() {
+        if let Err(e) = sqlx::query(
+            "UPDATE users SET email = COALESCE(?, email), display_name = COALESCE(?, display_name) WHERE id = ?",
+        )
+        .bind(&changes.email)
+        .bind(&changes.display_name)
+        .bind(&user.id)
+        .execute(&state.db.pool)
+        .await
+        {
+            return error::internal_error("scim_update_user", e);
+        }
+    }
+    if let Some(active) = changes.active {
+        if let Err(e) = set_user_active(state, &idp.actor(), &user.id, active).await {
+            return error::internal_error("scim_update_user:active", e);
+        }
+    }
+    match fetch_user(state, idp, id).await {
+        Ok(Some(user)) => scim_response(StatusCode::OK, user.resource()),
+        Ok(None) => not_found(),
+        Err(e) => error::internal_error("scim_update_user:fetch", e),
+    }
+}
+
+/// PUT /scim/v2/Users/:id — Replace a user's attributes.
+async fn replace_user(
+    State(state): State<Arc<AppState>>,
+    Extension(idp): Extension<ScimIdp>,
+    Path(id): Path<String>,
+    Json(req): Json<ScimUserRequest>,
+) -> Response {
+    if let Some(r) = req.validate() {
+        return r;
+    }
+    let changes = Changes {
+        email: req.email(),
+        display_name: req.display_name.clone(),
+        active: req.active,
+    };
+    apply_changes(&state, &idp, &id, changes).await
+}
+
+#[derive(Debug, Deserialize)]
+struct PatchRequest {
+    #[serde(default)]
+    schemas: Vec<String>,
+    #[serde(rename = "Operations")]
+    operations: Vec<PatchOperation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PatchOperation {
+    op: String,
+    path: Option<String>,
+    #[serde(default)]
+    value: Value,
+}
+
+/// Some IdPs send booleans as strings (`"False"`).
+fn as_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(b) => Some(*b),
+        Value::String(s) => s.to_ascii_lowercase().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Collect the supported attribute changes from patch operations. Removes
+/// and unknown attributes are ignored.
+fn patch_changes(operations: &[PatchOperation]) -> Result<Changes, String> {
+    let mut changes = Changes::default();
+    let mut set = |path: &str, value: &Value| -> Result<(), String> {
+        match path {
+            p if p.eq_ignore_ascii_case("active") => {
+                changes.active = Some(as_bool(value).ok_or("active must be a boolean")?);
+            }
+            p if p.eq_ignore_ascii_case("displayName") => {
+                changes.display_name = value.as_str().map(str::to_string);
+            }
+            p if p.eq_ignore_ascii_case("userName") => {
+                if let Some(name) = value.as_str().filter(|n| n.contains('@')) {
+                    changes.email = Some(name.to_string());
+                }
+            }
+            p if p.to_ascii_lowercase().starts_with("emails") => {
+                let email = match value {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Array(items) => items
+                        .iter()
+                        .find(|e| e["primary"] == true)
+                        .or(items.first())
+                        .and_then(|e| e["value"].as_str())
+                        .map(str::to_string),
+                    _ => None,
+                };
+                if email.is_some() {
+                    changes.email = email;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    };
+    for operation in operations {
+        if !matches!(
+            operation.op.to_ascii_lowercase().as_str(),
+            "add" | "replace"
+        ) {
+            continue;
+        }
+        match (&operation.path, &operation.value) {
+            (Some(path), value) => set(path, value)?,
+            (None, Value::Object(attributes)) => {
+                for (path, value) in attributes {
+                    set(path, value)?;
+                }
+            }
+            (None, _) => return Err("Operations without a path need an object value".into()),
+        }
+    }
+    if changes
+        .email
+        .as_ref()
+        .is_some_and(|e| e.len() > error::MAX_NAME)
+        || changes
+            .display_name
+            .as_ref()
+            .is_some_and(|n| n.len() > error::MAX_NAME)
+    {
+        return Err(format!(
+            "Attributes exceed maximum length of {} characters",
+            error::MAX_NAME
+        ));
+    }
+    Ok(changes)
+}
+
+/// PATCH /scim/v2/Users/:id — Change attributes, usually `active`.
+async fn patch_user(
+    State(state): State<Arc<AppState>>,
+    Extension(idp): Extension<ScimIdp>,
+    Path(id): Path<String>,
+    Json(req): Json<PatchRequest>,
+) -> Response {
+    if !req.schemas.is_empty() && !req.schemas.iter().any(|s| s == PATCH_SCHEMA) {
+        return scim_error(StatusCode::BAD_REQUEST, "Expected a PatchOp request");
+    }
+    match patch_changes(&req.operations) {
+        Ok(changes) => apply_changes(&state, &idp, &id, changes).await,
+        Err(msg) => scim_error(StatusCode::BAD_REQUEST, &msg),
+    }
+}
+
+/// DELETE /scim/v2/Users/:id — Deactivate the user (their rows are kept).
+async fn delete_user(
+    State(state): State<Arc<AppState>>,
+    Extension(idp): Extension<ScimIdp>,
+    Path(id): Path<String>,
+) -> Response {
+    match fetch_user(&state, &idp, &id).await {
+        Ok(Some(user)) => match set_user_active(&state, &idp.actor(), &user.id, false).await {
+            Ok(_) => StatusCode::NO_CONTENT.into_response(),
+            Err(e) => error::internal_error("scim_delete_user", e),
+        },
+        Ok(None) => not_found(),
+        Err(e) => error::internal_error("scim_delete_user:lookup", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn operations(value: Value) -> Vec<PatchOperation> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn parses_eq_filters() {
+        assert_eq!(
+            parse_filter(r#"userName eq "ada@example.com""#),
+            Some(("userName".into(), "ada@example.com".into()))
+        );
+        assert_eq!(
+            parse_filter(r#"externalId EQ "a \"b\"""#),
+            Some(("externalId".into(), r#"a "b""#.into()))
+        );
+        assert!(parse_filter(r#"userName sw "ada""#).is_none());
+        assert!(parse_filter("userName eq ada").is_none());
+    }
+
+    #[test]
+    fn patch_reads_path_and_object_forms() {
+        let changes = patch_changes(&operations(serde_json::json!([
+            { "op": "Replace", "path": "active", "value": "False" },
+            { "op": "replace", "path": "emails[type eq \"work\"].value", "value": "ada@example.com" },
+        ])))
+        .unwrap();
+        assert_eq!(changes.active, Some(false));
+        assert_eq!(changes.email.as_deref(), Some("ada@example.com"));
+
+        let changes = patch_changes(&operations(serde_json::json!([
+            { "op": "replace", "value": { "active": true, "displayName": "Ada" } },
+            { "op": "remove", "path": "active" },
+        ])))
+        .unwrap();
+        assert_eq!(changes.active, Some(true));
+        assert_eq!(changes.display_name.as_deref(), Some("Ada"));
+
+        assert!(patch_changes(&operations(serde_json::json!([
+            { "op": "replace", "path": "active", "value": "maybe" },
+        ])))
+        .is_err());
+    }
+}
@@ -1,11 +1,27 @@
+//! Portal sessions: the `se_session` cookie and where sessions are kept.
+//!
+//! Sessions live in SQLite by default, or in Redis when `SESSION_REDIS_URL`
+//! is set (see `session_redis`); the chosen store is held in `AppState`.
+//! Either way the cookie holds a random token and the store is keyed by its
+//! hash; the user, their roles and their active flag are always read from
+//! SQLite, so deactivation and role changes apply to existing sessions
+//! immediately.
+
+use std::future::Future;
+use std::sync::Arc;
+
 use anyhow::{bail, Context, Result};
 use rand::RngExt;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use tracing::info;
 
+use super::session_redis::RedisSessions;
+use crate::config::AppConfig;
 use crate::db::Database;
 
 const SESSION_COOKIE_NAME: &str = "se_session";
-const SESSION_TTL_HOURS: i64 = 24;
+pub(crate) const SESSION_TTL_HOURS: i64 = 24;
 
 /// Generate a random session token.
 pub fn generate_session_token() -> String {
@@ -13,48 +29,284 @@ pub fn generate_session_token() -> String {
     hex::encode(bytes)
 }
 
-fn hash_session(token: &str) -> String {
+pub(crate) fn hash_session(token: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(token.as_bytes());
     hex::encode(hasher.finalize())
 }
 
-/// Create a new session for a user. Returns the plaintext session token.
-pub async fn create_session(db: &Database, user_id: &str) -> Result<String> {
-    let token = generate_session_token();
-    let token_hash = hash_session(&token);
-    let id = uuid::Uuid::new_v4().to_string();
+/// A stored session, without the token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredSession {
+    pub id: String,
+    pub user_id: String,
+    /// The trusted device that minted the session, if any.
+    pub device_id: Option<String>,
+}
 
-    sqlx::query(
-        "INSERT INTO sessions (id, user_id, token_hash, expires_at) VALUES (?, ?, ?, datetime('now', '+' || ? || ' hours'))",
-    )
-    .bind(&id)
-    .bind(user_id)
-    .bind(&token_hash)
-    .bind(SESSION_TTL_HOURS)
-    .execute(&db.pool)
-    .await
-    .context("Failed to create session")?;
+/// Where sessions are kept, keyed by token hash. Sessions expire
+/// [`SESSION_TTL_HOURS`] after they are created.
+pub trait SessionStore {
+    fn insert(
+        &self,
+        token_hash: &str,
+        session: &StoredSession,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// The unexpired session with this token hash.
+    fn find(&self, token_hash: &str) -> impl Future<Output = Result<Option<StoredSession>>> + Send;
+
+    fn delete(&self, token_hash: &str) -> impl Future<Output = Result<()>> + Send;
+
+    /// Delete all of a user's sessions. Returns how many there were.
+    fn delete_for_user(&self, user_id: &str) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Delete the sessions a user's trusted device minted.
+    fn delete_for_device(
+        &self,
+        user_id: &str,
+        device_id: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// How many unexpired sessions a user has.
+    fn count_for_user(&self, user_id: &str) -> impl Future<Output = Result<u64>> + Send;
+
+    /// Detach the sessions a trusted device minted from it, before the device
+    /// is forgotten. The sessions themselves run until they expire.
+    fn unlink_device(
+        &self,
+        user_id: &str,
+        device_id: &str,
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Remove expired sessions the store doesn't expire by itself. Returns
+    /// how many were removed.
+    fn cleanup_expired(&self) -> impl Future<Output = Result<u64>> + Send;
+}
+
+/// Sessions in the `sessions` table.
+#[derive(Clone)]
+pub struct SqliteSessions(pub Database);
+
+impl SessionStore for SqliteSessions {
+    async fn insert(&self, token_hash: &str, session: &StoredSession) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO sessions (id, user_id, token_hash, device_id, expires_at) VALUES (?, ?, ?, ?, datetime('now', '+' || ? || ' hours'))",
+        )
+        .bind(&session.id)
+        .bind(&session.user_id)
+        .bind(token_hash)
+        .bind(&session.device_id)
+        .bind(SESSION_TTL_HOURS)
+        .execute(&self.0.pool)
+        .await
+        .context("Failed to create session")?;
+        Ok(())
+    }
 
+    async fn find(&self, token_hash: &str) -> Result<Option<StoredSession>> {
+        sqlx::query_as::<_, (String, String, Option<String>)>(
+            "SELECT id, user_id, device_id FROM sessions WHERE token_hash = ? AND expires_at > datetime('now')",
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.0.pool)
+        .await
+        .context("Failed to query session")
+        .map(|row| {
+            row.map(|(id, user_id, device_id)| StoredSession {
+                id,
+                user_id,
+                device_id,
+            })
+        })
+    }
+
+    async fn delete(&self, token_hash: &str) -> Result<()> {
+        sqlx::query("DELETE FROM sessions WHERE token_hash = ?")
+            .bind(token_hash)
+            .execute(&self.0.pool)
+            .await
+            .context("Failed to delete session")?;
+        Ok(())
+    }
+
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM sessions WHERE user_id = ?")
+            .bind(user_id)
+            .execute(&self.0.pool)
+            .await
+            .context("Failed to delete sessions")?;
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_for_device(&self, user_id: &str, device_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM sessions WHERE device_id = ? AND user_id = ?")
+            .bind(device_id)
+            .bind(user_id)
+            .execute(&self.0.pool)
+            .await
+            .context("Failed to delete device sessions")?;
+        Ok(())
+    }
+
+    async fn count_for_user(&self, user_id: &str) -> Result<u64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM sessions WHERE user_id = ? AND expires_at > datetime('now')",
+        )
+        .bind(user_id)
+        .fetch_one(&self.0.pool)
+        .await
+        .context("Failed to count sessions")?;
+        Ok(count as u64)
+    }
+
+    async fn unlink_device(&self, user_id: &str, device_id: &str) -> Result<()> {
+        sqlx::query("UPDATE sessions SET device_id = NULL WHERE device_id = ? AND user_id = ?")
+            .bind(device_id)
+            .bind(user_id)
+            .execute(&self.0.pool)
+            .await
+            .context("Failed to unlink device sessions")?;
+        Ok(())
+    }
+
+    async fn cleanup_expired(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM sessions WHERE expires_at < datetime('now')")
+            .execute(&self.0.pool)
+            .await
+            .context("Failed to clean up sessions")?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// The session store selected by SESSION_REDIS_URL. Cheap to clone.
+#[derive(Clone)]
+pub enum Sessions {
+    Sqlite(SqliteSessions),
+    Redis(Arc<RedisSessions>),
+}
+
+impl Sessions {
+    /// Redis when SESSION_REDIS_URL is set, otherwise the `sessions` table.
+    pub fn from_config(config: &AppConfig, db: &Database) -> Result<Self> {
+        match &config.session_redis_url {
+            Some(url) => {
+                let redis = RedisSessions::from_url(url)?;
+                info!("Session store: Redis");
+                Ok(Self::Redis(Arc::new(redis)))
+            }
+            None => Ok(Self::sqlite(db)),
+        }
+    }
+
+    pub fn sqlite(db: &Database) -> Self {
+        Self::Sqlite(SqliteSessions(db.clone()))
+    }
+}
+
+impl SessionStore for Sessions {
+    async fn insert(&self, token_hash: &str, session: &StoredSession) -> Result<()> {
+        match self {
+            Self::Sqlite(s) => s.insert(token_hash, session).await,
+            Self::Redis(s) => s.insert(token_hash, session).await,
+        }
+    }
+
+    async fn find(&self, token_hash: &str) -> Result<Option<StoredSession>> {
+        match self {
+            Self::Sqlite(s) => s.find(token_hash).await,
+            Self::Redis(s) => s.find(token_hash).await,
+        }
+    }
+
+    async fn delete(&self, token_hash: &str) -> Result<()> {
+        match self {
+            Self::Sqlite(s) => s.delete(token_hash).await,
+            Self::Redis(s) => s.delete(token_hash).await,
+        }
+    }
+
+    async fn delete_for_user(&self, user_id: &str) -> Result<u64> {
+        match self {
+            Self::Sqlite(s) => s.delete_for_user(user_id).await,
+            Self::Redis(s) => s.delete_for_user(user_id).await,
+        }
+    }
+
+    async fn delete_for_device(&self, user_id: &str, device_id: &str) -> Result<()> {
+        match self {
+            Self::Sqlite(s) => s.delete_for_device(user_id, device_id).await,
+            Self::Redis(s) => s.delete_for_device(user_id, device_id).await,
+        }
+    }
+
+    async fn count_for_user(&self, user_id: &str) -> Result<u64> {
+        match self {
+            Self::Sqlite(s) => s.count_for_user(user_id).await,
+            Self::Redis(s) => s.count_for_user(user_id).await,
+        }
+    }
+
+    async fn unlink_device(&self, user_id: &str, device_id: &str) -> Result<()> {
+        match self {
+            Self::Sqlite(s) => s.unlink_device(user_id, device_id).await,
+            Self::Redis(s) => s.unlink_device(user_id, device_id).await,
+        }
+    }
+
+    async fn cleanup_expired(&self) -> Result<u64> {
+        match self {
+            Self::Sqlite(s) => s.cleanup_expired().await,
+            Self::Redis(s) => s.cleanup_expired().await,
+        }
+    }
+}
+
+/// Create a new session for a user, optionally minted by a trusted device.
+/// Returns the plaintext session token.
+pub async fn create_session(
+    sessions: &Sessions,
+    user_id: &str,
+    device_id: Option<&str>,
+) -> Result<String> {
+    let token = generate_session_token();
+    let session = StoredSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: user_id.to_string(),
+        device_id: device_id.map(str::to_string),
+    };
+    sessions.insert(&hash_session(&token), &session).await?;
     Ok(token)
 }
 
 /// Validate a session token, return user_id if valid.
-pub async fn validate_session(db: &Database, token: &str) -> Result<SessionUser> {
-    let token_hash = hash_session(token);
+pub async fn validate_session(
+    sessions: &Sessions,
+    db: &Database,
+    token: &str,
+) -> Result<SessionUser> {
+    let Some(session) = sessions.find(&hash_session(token)).await? else {
+        bail!("Invalid or expired session");
+    };
 
     let row = sqlx::query_as::<_, SessionUser>(
         r#"
-        SELECT s.id as session_id, s.user_id, u.is_admin, u.email, u.display_name
-        FROM sessions s
-        JOIN users u ON u.id = s.user_id
-        WHERE s.token_hash = ? AND s.expires_at > datetime('now')
+        SELECT ? as session_id, u.id as user_id,
+               (u.is_admin OR EXISTS (
+                   SELECT 1 FROM admin_elevations e
+                   WHERE e.user_id = u.id AND e.status = 'active' AND e.expires_at > datetime('now')
+               )) AS is_admin,
+               u.email, u.display_name,
+               COALESCE((SELECT group_concat(r.role) FROM user_roles r WHERE r.user_id = u.id), '') AS roles
+        FROM users u
+        WHERE u.id = ? AND u.active = 1
         "#,
     )
-    .bind(&token_hash)
+    .bind(&session.id)
+    .bind(&session.user_id)
     .fetch_optional(&db.pool)
     .await
-    .context("Failed to query session")?;
+    .context("Failed to query session user")?;
 
     match row {
         Some(r) => Ok(r),
@@ -63,23 +315,24 @@ pub async fn validate_session(db: &Database, token: &str) -> Result<SessionUser>
 }
 
 /// Delete a session (logout).
-pub async fn delete_session(db: &Database, token: &str) -> Result<()> {
-    let token_hash = hash_session(token);
-    sqlx::query("DELETE FROM sessions WHERE token_hash = ?")
-        .bind(&token_hash)
-        .execute(&db.pool)
-        .await
-        .context("Failed to delete session")?;
-    Ok(())
+pub async fn delete_session(sessions: &Sessions, token: &str) -> Result<()> {
+    sessions.delete(&hash_session(token)).await
 }
 
-/// Clean up expired sessions.
-pub async fn cleanup_expired(db: &Database) -> Result<u64> {
-    let result = sqlx::query("DELETE FROM sessions WHERE expires_at < datetime('now')")
+/// Log a user out everywhere: delete all their sessions, and their trusted
+/// devices so a remembered browser can't mint a new session. Returns the
+/// number of sessions deleted.
+pub async fn revoke_user_sessions(
+    sessions: &Sessions,
+    db: &Database,
+    user_id: &str,
+) -> Result<u64> {
+    sqlx::query("DELETE FROM trusted_devices WHERE user_id = ?")
+        .bind(user_id)
         .execute(&db.pool)
         .await
-        .context("Failed to clean up sessions")?;
-    Ok(result.rows_affected())
+        .context("Failed to delete trusted devices")?;
+    sessions.delete_for_user(user_id).await
 }
 
 pub fn cookie_name() -> &'static str {
@@ -117,15 +370,18 @@ pub fn clear_cookie(secure: bool, cookie_domain: Option<&str>) -> String {
     )
 }
 
-/// Session-authenticated user, populated from a JOIN of sessions + users.
+/// Session-authenticated user: the session id and the user's row.
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct SessionUser {
-    #[allow(dead_code)] // populated by sqlx; available for session management
+    #[allow(dead_code)] // available for session management
     pub session_id: String,
     pub user_id: String,
+    /// The user's admin flag, or a live time-boxed elevation.
     pub is_admin: bool,
     pub email: Option<String>,
     pub display_name: Option<String>,
+    /// Comma-separated `user_roles` (see `rbac::Role::parse_list`).
+    pub roles: String,
 }
 
 #[cfg(test)]
@@ -220,4 +476,40 @@ mod tests {
             "should not contain Domain attribute"
         );
     }
+
+    #[tokio::test]
+    async fn sqlite_store_round_trip() {
+        let db = Database::test_db().await;
+        sqlx::query(
+            "INSERT INTO idp_configs (id, name, issuer, client_id, client_secret_enc) \
+             VALUES ('idp', 'test', 'https://test', 'client', 'secret')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO users (id, idp_id, subject) VALUES ('u1', 'idp', 'u1')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let store = Sessions::sqlite(&db);
+        let first = create_session(&store, "u1", None).await.unwrap();
+        let second = create_session(&store, "u1", None).await.unwrap();
+        assert_eq!(store.count_for_user("u1").await.unwrap(), 2);
+        let user = validate_session(&store, &db, &first).await.unwrap();
+        assert_eq!(user.user_id, "u1");
+        assert!(!user.is_admin);
+
+        delete_session(&store, &first).await.unwrap();
+        assert!(validate_session(&store, &db, &first).await.is_err());
+        assert!(validate_session(&store, &db, &second).await.is_ok());
+        assert_eq!(store.count_for_user("u1").await.unwrap(), 1);
+
+        sqlx::query("UPDATE users SET active = 0 WHERE id = 'u1'")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        assert!(validate_session(&store, &db, &second).await.is_err());
+        assert_eq!(revoke_user_sessions(&store, &db, "u1").await.unwrap(), 1);
+    }
 }
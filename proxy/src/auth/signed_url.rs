@@ -0,0 +1,298 @@
+//! Short-lived signed URLs for downloading artifacts.
+//!
+//! A logged-in user asks the API to sign the path of a download
+//! (`POST /api/user/signed-urls`). The URL carries the user id, an expiry and
+//! an HMAC-SHA256 over both plus the path and query, so a browser tab or
+//! download manager can fetch it without a session cookie or bearer token.
+//! [`middleware`] checks the signature and runs the request as that user,
+//! without admin rights or roles. Only GETs of [`SIGNABLE_PATHS`] accept a
+//! signature, and a signed URL can't be revoked before it expires.
+
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use axum::extract::{OriginalUri, Request, State};
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use hmac::{Hmac, Mac};
+use rand::RngExt;
+use sha2::Sha256;
+use tracing::warn;
+
+use crate::auth::SessionAuth;
+use crate::config::AppConfig;
+use crate::AppState;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const USER_PARAM: &str = "uid";
+const EXPIRES_PARAM: &str = "expires";
+const SIGNATURE_PARAM: &str = "signature";
+
+/// Path prefix and suffix of each download that can be signed.
+pub const SIGNABLE_PATHS: &[(&str, &str)] = &[
+    ("/api/user/conversations/", "/export"),
+    ("/api/user/archive/blobs/", ""),
+];
+
+/// Issues and checks signed URLs. Cheap to clone.
+#[derive(Clone)]
+pub struct UrlSigner {
+    key: Arc<Vec<u8>>,
+    max_ttl_secs: i64,
+}
+
+impl UrlSigner {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let key = match &config.signed_url_key {
+            Some(k) => k.as_bytes().to_vec(),
+            None => {
+                let bytes: [u8; 32] = rand::rng().random();
+                bytes.to_vec()
+            }
+        };
+        Self::new(key, config.signed_url_max_ttl_secs as i64)
+    }
+
+    pub fn new(key: Vec<u8>, max_ttl_secs: i64) -> Self {
+        Self {
+            key: Arc::new(key),
+            max_ttl_secs: max_ttl_secs.max(1),
+        }
+    }
+
+    pub fn max_ttl_secs(&self) -> i64 {
+        self.max_ttl_secs
+    }
+
+    /// Sign `path_and_query` (e.g. `/api/user/conversations/x/export?format=markdown`)
+    /// for `user_id`, valid for `ttl_secs` (capped at the configured maximum).
+    /// Returns the signed path and query, and its expiry as a unix time.
+    pub fn sign(
+        &self,
+        path_and_query: &str,
+        user_id: &str,
+        ttl_secs: i64,
+    ) -> Result<(String, i64)> {
+        let (path, query) = path_and_query
+            .split_once('?')
+            .unwrap_or((path_and_query, ""));
+        if !is_signable(path) {
+            bail!("This path can't be signed");
+        }
+        if query
+            .split('&')
+            .any(|pair| is_signature_param(pair.split('=').next().unwrap_or("")))
+        {
+            bail!("The query already has signed URL parameters");
+        }
+        if !user_id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        {
+            bail!("User id can't be carried in a URL");
+        }
+
+        let expires = chrono::Utc::now().timestamp() + ttl_secs.clamp(1, self.max_ttl_secs);
+        let signature = hex::encode(self.mac(path, query, user_id, expires));
+        let separator = if query.is_empty() { "" } else { "&" };
+        Ok((
+            format!(
+                "{path}?{query}{separator}{USER_PARAM}={user_id}&{EXPIRES_PARAM}={expires}&{SIGNATURE_PARAM}={signature}"
+            ),
+            expires,
+        ))
+    }
+
+    /// Check a request's signature and expiry. Returns the signing user, or
+    /// `None` when the request carries no signature.
+    pub fn verify(&self, path: &str, query: &str) -> Result<Option<String>> {
+        let mut user_id = None;
+        let mut expires = None;
+        let mut signature = None;
+        let mut rest = Vec::new();
+        for pair in query.split('&').filter(|p| !p.is_empty()) {
+            match pair.split_once('=') {
+                Some((USER_PARAM, v)) => user_id = Some(v),
+                Some((EXPIRES_PARAM, v)) => expires = Some(v),
+                Some((SIGNATURE_PARAM, v)) => signature = Some(v),
+                _ => rest.push(pair),
+            }
+        }
+        let Some(signature) = signature else {
+            return Ok(None);
+        };
+        if !is_signable(path) {
+            bail!("This path doesn't accept signed URLs");
+        }
+        let user_id = user_id.context("Signed URL has no user")?;
+        let expires: i64 = expires
+            .and_then(|e| e.parse().ok())
+            .context("Signed URL has no valid expiry")?;
+        let signature = hex::decode(signature).context("Malformed signature")?;
+
+        self.mac_for(path, &rest.join("&"), user_id, expires)
+            .verify_slice(&signature)
+            .map_err(|_| anyhow::anyhow!("Invalid signature"))?;
+        if expires <= chrono::Utc::now().timestamp() {
+            bail!("Signed URL has expired");
+        }
+        Ok(Some(user_id.to_string()))
+    }
+
+    fn mac_for(&self, path: &str, query: &str, user_id: &str, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(format!("{path}\n{query}\n{user_id}\n{expires}").as_bytes());
+        mac
+    }
+
+    fn mac(&self, path: &str, query: &str, user_id: &str, expires: i64) -> Vec<u8> {
+        self.mac_for(path, query, user_id, expires)
+            .finalize()
+            .into_bytes()
+            .to_vec()
+    }
+}
+
+fn is_signature_param(name: &str) -> bool {
+    matches!(name, USER_PARAM | EXPIRES_PARAM | SIGNATURE_PARAM)
+}
+
+/// Whether `path` is one of [`SIGNABLE_PATHS`] with a single segment between
+/// prefix and suffix.
+pub fn is_signable(path: &str) -> bool {
+    SIGNABLE_PATHS.iter().any(|(prefix, suffix)| {
+        path.strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(suffix))
+            .is_some_and(|id| !id.is_empty() && !id.contains('/'))
+    })
+}
+
+/// Middleware: authenticate a GET that carries a URL signature as the user
+/// who signed it. Requests without one pass through to session auth.
+pub async fn middleware(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, Response> {
+    if req.method() != Method::GET {
+        return Ok(next.run(req).await);
+    }
+    // Nested routers see the path without its `/api` prefix
+    let uri = req
+        .extensions()
+        .get::<OriginalUri>()
+        .map_or_else(|| req.uri().clone(), |u| u.0.clone());
+    let path = uri.path();
+    let query = uri.query().unwrap_or("");
+    let user_id = match state.signed_urls.verify(path, query) {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => return Ok(next.run(req).await),
+        Err(e) => return Err(forbidden(&e.to_string())),
+    };
+
+    let user: Option<(Option<String>, Option<String>)> =
+        sqlx::query_as("SELECT email, display_name FROM users WHERE id = ? AND active = 1")
+            .bind(&user_id)
+            .fetch_optional(&state.db.pool)
+            .await
+            .map_err(|e| crate::api::error::internal_error("signed_url", e))?;
+    let Some((email, display_name)) = user else {
+        warn!(user = %user_id, path = %path, "Signed URL for unknown or deactivated user");
+        return Err(forbidden("Signed URL is no longer valid"));
+    };
+
+    req.extensions_mut().insert(SessionAuth {
+        user_id,
+        is_admin: false,
+        roles: Vec::new(),
+        email,
+        display_name,
+    });
+    Ok(next.run(req).await)
+}
+
+fn forbidden(message: &str) -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Json(serde_json::json!({ "error": message })),
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signer() -> UrlSigner {
+        UrlSigner::new(b"test-key".to_vec(), 600)
+    }
+
+    fn split(url: &str) -> (&str, &str) {
+        url.split_once('?').unwrap()
+    }
+
+    #[test]
+    fn signed_url_round_trips() {
+        let signer = signer();
+        let (url, _) = signer
+            .sign(
+                "/api/user/conversations/c1/export?format=markdown",
+                "u1",
+                60,
+            )
+            .unwrap();
+        let (path, query) = split(&url);
+        assert_eq!(signer.verify(path, query).unwrap().as_deref(), Some("u1"));
+
+        // No signature means the request falls through to session auth
+        assert_eq!(signer.verify(path, "format=markdown").unwrap(), None);
+    }
+
+    #[test]
+    fn tampered_or_foreign_urls_are_rejected() {
+        let signer = signer();
+        let (url, _) = signer
+            .sign("/api/user/conversations/c1/export?format=json", "u1", 60)
+            .unwrap();
+        let (path, query) = split(&url);
+
+        assert!(signer
+            .verify("/api/user/conversations/c2/export", query)
+            .is_err());
+        assert!(signer
+            .verify(path, &query.replace("format=json", "format=markdown"))
+            .is_err());
+        assert!(signer
+            .verify(path, &query.replace("uid=u1", "uid=u2"))
+            .is_err());
+        assert!(UrlSigner::new(b"other".to_vec(), 600)
+            .verify(path, query)
+            .is_err());
+    }
+
+    #[test]
+    fn only_download_paths_can_be_signed() {
+        let signer = signer();
+        assert!(signer.sign("/api/user/tokens", "u1", 60).is_err());
+        assert!(signer
+            .sign("/api/user/conversations/c1/messages", "u1", 60)
+            .is_err());
+        assert!(signer
+            .sign("/api/user/archive/blobs/abc?uid=u2", "u1", 60)
+            .is_err());
+        assert!(signer.sign("/api/user/archive/blobs/abc", "u1", 60).is_ok());
+    }
+
+    #[test]
+    fn ttl_is_capped() {
+        let signer = signer();
+        let now = chrono::Utc::now().timestamp();
+        let (_, expires) = signer
+            .sign("/api/user/archive/blobs/abc", "u1", 86_400)
+            .unwrap();
+        assert!(expires <= now + 601);
+    }
+}
@@ -0,0 +1,230 @@
+//! In-memory cache of validated bearer tokens.
+//!
+//! Maps token hash → `AuthUser` so a busy client doesn't cost a SQLite query
+//! per request. Entries live for `TOKEN_CACHE_TTL_SECS` (never past the
+//! token's own expiry) and are evicted least-recently-used beyond
+//! `TOKEN_CACHE_MAX_ENTRIES`. Revoking, deleting, or re-scoping a token, or
+//! changing its owner's admin flag, invalidates the affected entries
+//! immediately. Quota-capped tokens are never cached — their quota must be
+//! checked on every request.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::auth::AuthUser;
+use crate::config::AppConfig;
+
+struct Entry {
+    user: AuthUser,
+    valid_until: Instant,
+    /// Position in the recency order (higher = more recently used).
+    tick: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: HashMap<String, Entry>,
+    /// tick → token hash, oldest first.
+    recency: BTreeMap<u64, String>,
+    next_tick: u64,
+}
+
+impl Inner {
+    fn touch(&mut self, hash: &str) {
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        if let Some(entry) = self.entries.get_mut(hash) {
+            self.recency.remove(&entry.tick);
+            entry.tick = tick;
+            self.recency.insert(tick, hash.to_string());
+        }
+    }
+
+    fn remove(&mut self, hash: &str) {
+        if let Some(entry) = self.entries.remove(hash) {
+            self.recency.remove(&entry.tick);
+        }
+    }
+
+    fn retain(&mut self, keep: impl Fn(&AuthUser) -> bool) {
+        let stale: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, e)| !keep(&e.user))
+            .map(|(h, _)| h.clone())
+            .collect();
+        for hash in stale {
+            self.remove(&hash);
+        }
+    }
+}
+
+/// Shared token validation cache. Cheap to clone; disabled when the TTL or
+/// capacity is 0.
+#[derive(Clone)]
+pub struct TokenCache {
+    ttl: Duration,
+    capacity: usize,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl TokenCache {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self::new(
+            Duration::from_secs(config.token_cache_ttl_secs),
+            config.token_cache_max_entries,
+        )
+    }
+
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            inner: Arc::new(Mutex::new(Inner::default())),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        !self.ttl.is_zero() && self.capacity > 0
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Cached user for a token hash, if present and fresh.
+    pub fn get(&self, token_hash: &str) -> Option<AuthUser> {
+        if !self.enabled() {
+            return None;
+        }
+        let mut inner = self.lock();
+        let entry = inner.entries.get(token_hash)?;
+        if entry.valid_until <= Instant::now() {
+            inner.remove(token_hash);
+            return None;
+        }
+        let user = entry.user.clone();
+        inner.touch(token_hash);
+        Some(user)
+    }
+
+    /// Cache a freshly validated token. `expires_in` is the time left before
+    /// the token itself expires, if it has an expiry.
+    pub fn insert(&self, token_hash: &str, user: AuthUser, expires_in: Option<Duration>) {
+        if !self.enabled() {
+            return;
+        }
+        let ttl = expires_in.map_or(self.ttl, |left| left.min(self.ttl));
+        let mut inner = self.lock();
+        inner.remove(token_hash);
+        while inner.entries.len() >= self.capacity {
+            let Some((_, oldest)) = inner.recency.pop_first() else {
+                break;
+            };
+            inner.entries.remove(&oldest);
+        }
+        let tick = inner.next_tick;
+        inner.next_tick += 1;
+        inner.recency.insert(tick, token_hash.to_string());
+        inner.entries.insert(
+            token_hash.to_string(),
+            Entry {
+                user,
+                valid_until: Instant::now() + ttl,
+                tick,
+            },
+        );
+    }
+
+    /// Drop the entry for a token that was revoked, deleted, or re-scoped.
+    pub fn invalidate_token(&self, token_id: &str) {
+        self.lock().retain(|u| u.token_id != token_id);
+    }
+
+    /// Drop every entry belonging to a user (e.g. admin flag changed).
+    pub fn invalidate_user(&self, user_id: &str) {
+        self.lock().retain(|u| u.user_id != user_id);
+    }
+
+    /// Drop everything (bulk token changes).
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.entries.clear();
+        inner.recency.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(user_id: &str, token_id: &str) -> AuthUser {
+        AuthUser {
+            user_id: user_id.into(),
+            token_id: token_id.into(),
+            category_id: None,
+            specific_model_id: None,
+            is_admin: false,
+            is_internal: false,
+            via_jwt: false,
+        }
+    }
+
+    #[test]
+    fn hit_after_insert() {
+        let cache = TokenCache::new(Duration::from_secs(30), 10);
+        cache.insert("h1", user("u1", "t1"), None);
+        assert_eq!(cache.get("h1").unwrap().token_id, "t1");
+        assert!(cache.get("h2").is_none());
+    }
+
+    #[test]
+    fn disabled_cache_stores_nothing() {
+        let cache = TokenCache::new(Duration::ZERO, 10);
+        cache.insert("h1", user("u1", "t1"), None);
+        assert!(cache.get("h1").is_none());
+        assert_eq!(cache.lock().entries.len(), 0);
+    }
+
+    #[test]
+    fn entry_never_outlives_token_expiry() {
+        let cache = TokenCache::new(Duration::from_secs(30), 10);
+        cache.insert("h1", user("u1", "t1"), Some(Duration::ZERO));
+        assert!(cache.get("h1").is_none());
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = TokenCache::new(Duration::from_secs(30), 2);
+        cache.insert("h1", user("u1", "t1"), None);
+        cache.insert("h2", user("u1", "t2"), None);
+        // Touch h1 so h2 becomes the oldest
+        assert!(cache.get("h1").is_some());
+        cache.insert("h3", user("u1", "t3"), None);
+
+        assert_eq!(cache.lock().entries.len(), 2);
+        assert!(cache.get("h1").is_some());
+        assert!(cache.get("h2").is_none());
+        assert!(cache.get("h3").is_some());
+    }
+
+    #[test]
+    fn invalidation_by_token_and_user() {
+        let cache = TokenCache::new(Duration::from_secs(30), 10);
+        cache.insert("h1", user("u1", "t1"), None);
+        cache.insert("h2", user("u1", "t2"), None);
+        cache.insert("h3", user("u2", "t3"), None);
+
+        cache.invalidate_token("t1");
+        assert!(cache.get("h1").is_none());
+        assert!(cache.get("h2").is_some());
+
+        cache.invalidate_user("u1");
+        assert!(cache.get("h2").is_none());
+        assert!(cache.get("h3").is_some());
+
+        cache.clear();
+        assert_eq!(cache.lock().entries.len(), 0);
+    }
+}
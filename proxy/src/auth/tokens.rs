@@ -3,6 +3,7 @@ use sha2::{Digest, Sha256};
 use tracing::info;
 use uuid::Uuid;
 
+use crate::auth::token_cache::TokenCache;
 use crate::auth::AuthUser;
 use crate::config::AppConfig;
 use crate::db::Database;
@@ -51,20 +52,116 @@ pub async fn create_token(
     Ok(token)
 }
 
+/// Lifetime of a quickstart token.
+pub const QUICKSTART_TTL_HOURS: i64 = 24;
+/// Requests a quickstart token may make before it stops working.
+pub const QUICKSTART_REQUEST_QUOTA: i64 = 100;
+
+/// Mint a short-lived, request-capped token for onboarding.
+/// Returns the plaintext token and its expiry (`YYYY-MM-DD HH:MM:SS`, UTC).
+pub async fn create_quickstart_token(db: &Database, user_id: &str) -> Result<(String, String)> {
+    let token = generate_token();
+    let id = Uuid::new_v4().to_string();
+    let expires_at = (chrono::Utc::now() + chrono::Duration::hours(QUICKSTART_TTL_HOURS))
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+
+    sqlx::query(
+        "INSERT INTO tokens (id, user_id, name, token_hash, expires_at, request_quota) VALUES (?, ?, 'Quickstart', ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(user_id)
+    .bind(hash_token(&token))
+    .bind(&expires_at)
+    .bind(QUICKSTART_REQUEST_QUOTA)
+    .execute(&db.pool)
+    .await
+    .context("Failed to create quickstart token")?;
+
+    Ok((token, expires_at))
+}
+
+/// Default lifetime of an exchanged token.
+pub const EXCHANGE_DEFAULT_TTL_SECS: i64 = 900;
+/// Longest lifetime a token exchange may request.
+pub const EXCHANGE_MAX_TTL_SECS: i64 = 3600;
+
+/// Mint a short-lived token on behalf of a session user for an embedded UI
+/// (`client`). Scope narrowing follows [`create_token`]. Returns the plaintext
+/// token and its lifetime in seconds after clamping.
+pub async fn create_exchange_token(
+    db: &Database,
+    user_id: &str,
+    client: &str,
+    category_id: Option<&str>,
+    specific_model_id: Option<&str>,
+    ttl_secs: Option<i64>,
+) -> Result<(String, i64)> {
+    let token = generate_token();
+    let id = Uuid::new_v4().to_string();
+    let ttl = ttl_secs
+        .unwrap_or(EXCHANGE_DEFAULT_TTL_SECS)
+        .clamp(60, EXCHANGE_MAX_TTL_SECS);
+
+    sqlx::query(
+        "INSERT INTO tokens (id, user_id, name, token_hash, category_id, specific_model_id, exchange_client, expires_at) VALUES (?, ?, ?, ?, ?, ?, ?, datetime('now', '+' || ? || ' seconds'))",
+    )
+    .bind(&id)
+    .bind(user_id)
+    .bind(format!("Exchange: {client}"))
+    .bind(hash_token(&token))
+    .bind(category_id)
+    .bind(specific_model_id)
+    .bind(client)
+    .bind(ttl)
+    .execute(&db.pool)
+    .await
+    .context("Failed to create exchange token")?;
+
+    Ok((token, ttl))
+}
+
 /// Validate a Bearer token and return the associated user context.
 pub async fn validate_token(db: &Database, token: &str) -> Result<AuthUser> {
+    Ok(lookup_token(db, &hash_token(token)).await?.user)
+}
+
+/// [`validate_token`] through the in-memory cache. Quota-capped tokens
+/// always go to the DB.
+pub async fn validate_token_cached(
+    db: &Database,
+    cache: &TokenCache,
+    token: &str,
+) -> Result<AuthUser> {
     let token_hash = hash_token(token);
+    if let Some(user) = cache.get(&token_hash) {
+        return Ok(user);
+    }
+    let validated = lookup_token(db, &token_hash).await?;
+    if !validated.quota_capped {
+        cache.insert(&token_hash, validated.user.clone(), validated.expires_in);
+    }
+    Ok(validated.user)
+}
 
+struct ValidatedToken {
+    user: AuthUser,
+    /// Time left before the token expires, if it has an expiry.
+    expires_in: Option<std::time::Duration>,
+    quota_capped: bool,
+}
+
+async fn lookup_token(db: &Database, token_hash: &str) -> Result<ValidatedToken> {
     let row = sqlx::query_as::<_, TokenWithUser>(
         r#"
         SELECT t.id as token_id, t.user_id, t.category_id, t.specific_model_id,
-               t.revoked, t.expires_at, t.internal, u.is_admin
+               t.revoked, t.expires_at, t.internal, t.request_quota, u.is_admin, u.active
         FROM tokens t
         JOIN users u ON u.id = t.user_id
         WHERE t.token_hash = ?
         "#,
     )
-    .bind(&token_hash)
+    .bind(token_hash)
     .fetch_optional(&db.pool)
     .await
     .context("Failed to query token")?;
@@ -78,6 +175,11 @@ pub async fn validate_token(db: &Database, token: &str) -> Result<AuthUser> {
         bail!("Token has been revoked");
     }
 
+    if !row.active {
+        bail!("User is deactivated");
+    }
+
+    let mut expires_in = None;
     if let Some(ref expires_at) = row.expires_at {
         let naive = chrono::NaiveDateTime::parse_from_str(expires_at, "%Y-%m-%d %H:%M:%S")
             .context("Invalid expires_at")?;
@@ -85,15 +187,32 @@ pub async fn validate_token(db: &Database, token: &str) -> Result<AuthUser> {
         if expires < chrono::Utc::now() {
             bail!("Token has expired");
         }
+        expires_in = (expires - chrono::Utc::now()).to_std().ok();
+    }
+
+    if let Some(quota) = row.request_quota {
+        let (used,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM usage_log WHERE token_id = ?")
+            .bind(&row.token_id)
+            .fetch_one(&db.pool)
+            .await
+            .context("Failed to count token usage")?;
+        if used >= quota {
+            bail!("Token request quota exhausted");
+        }
     }
 
-    Ok(AuthUser {
-        user_id: row.user_id,
-        token_id: row.token_id,
-        category_id: row.category_id,
-        specific_model_id: row.specific_model_id,
-        is_admin: row.is_admin,
-        is_internal: row.internal,
+    Ok(ValidatedToken {
+        user: AuthUser {
+            user_id: row.user_id,
+            token_id: row.token_id,
+            category_id: row.category_id,
+            specific_model_id: row.specific_model_id,
+            is_admin: row.is_admin,
+            is_internal: row.internal,
+            via_jwt: false,
+        },
+        expires_in,
+        quota_capped: row.request_quota.is_some(),
     })
 }
 
@@ -122,7 +241,9 @@ struct TokenWithUser {
     revoked: bool,
     expires_at: Option<String>,
     internal: bool,
+    request_quota: Option<i64>,
     is_admin: bool,
+    active: bool,
 }
 
 /// Result of resolving a `user` email to a meta token for usage attribution.
@@ -186,13 +307,14 @@ pub async fn resolve_meta_user(db: &Database, email: &str) -> Result<Option<Meta
 ///
 /// If WEBUI_API_KEY is set, hashes it and ensures a matching token row exists.
 /// If the key changed (hash mismatch), revokes old internal tokens and creates a new one.
-/// The token is owned by the bootstrap admin user.
-pub async fn ensure_internal_token(config: &AppConfig, db: &Database) -> Result<()> {
+/// The token is owned by the bootstrap admin user. Returns the ids of the
+/// internal tokens it revoked, so callers can drop them from the token cache.
+pub async fn ensure_internal_token(config: &AppConfig, db: &Database) -> Result<Vec<String>> {
     let api_key = match &config.webui_api_key {
         Some(k) => k.clone(),
         None => {
             info!("WEBUI_API_KEY not set — skipping internal token provisioning");
-            return Ok(());
+            return Ok(Vec::new());
         }
     };
 
@@ -209,10 +331,15 @@ pub async fn ensure_internal_token(config: &AppConfig, db: &Database) -> Result<
 
     if existing.is_some() {
         info!("Internal API token already registered");
-        return Ok(());
+        return Ok(Vec::new());
     }
 
     // Revoke any previous internal tokens
+    let revoked: Vec<String> =
+        sqlx::query_scalar("SELECT id FROM tokens WHERE internal = 1 AND revoked = 0")
+            .fetch_all(&db.pool)
+            .await
+            .context("Failed to list old internal tokens")?;
     sqlx::query("UPDATE tokens SET revoked = 1 WHERE internal = 1")
         .execute(&db.pool)
         .await
@@ -235,7 +362,7 @@ pub async fn ensure_internal_token(config: &AppConfig, db: &Database) -> Result<
     .context("Failed to create internal token")?;
 
     info!("Internal API token provisioned for Open WebUI");
-    Ok(())
+    Ok(revoked)
 }
 
 #[cfg(test)]
@@ -276,4 +403,107 @@ mod tests {
         let h2 = hash_token("se-token-b");
         assert_ne!(h1, h2);
     }
+
+    async fn db_with_user() -> Database {
+        let db = Database::test_db().await;
+        sqlx::query(
+            "INSERT INTO idp_configs (id, name, issuer, client_id, client_secret_enc)
+             VALUES ('test-idp', 'test', 'https://test', 'client', 'secret')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO users (id, idp_id, subject) VALUES ('u1', 'test-idp', 'u1')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        db
+    }
+
+    #[tokio::test]
+    async fn quickstart_token_stops_at_quota() {
+        let db = db_with_user().await;
+
+        let (token, _) = create_quickstart_token(&db, "u1").await.unwrap();
+        let auth = validate_token(&db, &token).await.unwrap();
+
+        for _ in 0..QUICKSTART_REQUEST_QUOTA {
+            sqlx::query(
+                "INSERT INTO usage_log (id, token_id, user_id, model_id) VALUES (?, ?, 'u1', 'm')",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(&auth.token_id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        }
+
+        let err = validate_token(&db, &token).await.unwrap_err();
+        assert!(err.to_string().contains("quota"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn exchange_token_authenticates_as_user_with_clamped_ttl() {
+        let db = db_with_user().await;
+
+        let (token, ttl) = create_exchange_token(&db, "u1", "notebook", None, None, Some(86400))
+            .await
+            .unwrap();
+        assert_eq!(ttl, EXCHANGE_MAX_TTL_SECS);
+
+        let auth = validate_token(&db, &token).await.unwrap();
+        assert_eq!(auth.user_id, "u1");
+
+        let (client,): (Option<String>,) =
+            sqlx::query_as("SELECT exchange_client FROM tokens WHERE id = ?")
+                .bind(&auth.token_id)
+                .fetch_one(&db.pool)
+                .await
+                .unwrap();
+        assert_eq!(client.as_deref(), Some("notebook"));
+    }
+
+    #[tokio::test]
+    async fn cached_validation_skips_capped_tokens() {
+        let db = db_with_user().await;
+        let cache = TokenCache::new(std::time::Duration::from_secs(30), 100);
+
+        let token = create_token(&db, "u1", "regular", None, None, None)
+            .await
+            .unwrap();
+        validate_token_cached(&db, &cache, &token).await.unwrap();
+        assert!(cache.get(&hash_token(&token)).is_some());
+
+        let (quickstart, _) = create_quickstart_token(&db, "u1").await.unwrap();
+        validate_token_cached(&db, &cache, &quickstart)
+            .await
+            .unwrap();
+        assert!(cache.get(&hash_token(&quickstart)).is_none());
+    }
+
+    #[tokio::test]
+    async fn rotated_internal_key_reports_revoked_token() {
+        let db = Database::test_db().await;
+        let config = |key: &str| AppConfig {
+            webui_api_key: Some(key.to_string()),
+            ..crate::test_harness::test_config()
+        };
+
+        assert!(ensure_internal_token(&config("key-1"), &db)
+            .await
+            .unwrap()
+            .is_empty());
+        let old = validate_token(&db, "key-1").await.unwrap();
+        assert!(old.is_internal);
+        // Same key again: nothing revoked
+        assert!(ensure_internal_token(&config("key-1"), &db)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let revoked = ensure_internal_token(&config("key-2"), &db).await.unwrap();
+        assert_eq!(revoked, vec![old.token_id]);
+        assert!(validate_token(&db, "key-1").await.is_err());
+        assert!(validate_token(&db, "key-2").await.unwrap().is_internal);
+    }
 }
@@ -1,6 +1,11 @@
-use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
 use subtle::ConstantTimeEq;
 
+use crate::secrets;
+
 #[derive(Debug, Clone)]
 pub struct AppConfig {
     /// Bind address (e.g. "0.0.0.0:443")
@@ -37,6 +42,10 @@ pub struct AppConfig {
     /// Static UI files path
     pub ui_path: String,
 
+    /// Directory of static sites that host routes can serve, one
+    /// subdirectory per site (env: SITES_PATH)
+    pub sites_path: String,
+
     /// API subdomain hostname (e.g. "api.example.com", env: API_HOSTNAME)
     pub api_hostname: String,
 
@@ -46,6 +55,11 @@ pub struct AppConfig {
     /// Cookie domain for cross-subdomain sharing (e.g. ".example.com", env: COOKIE_DOMAIN)
     pub cookie_domain: Option<String>,
 
+    /// Keep portal sessions in Redis instead of SQLite, e.g.
+    /// `redis://:password@redis:6379/0`; lets replicas share sessions
+    /// (env: SESSION_REDIS_URL, default: none, sessions live in SQLite)
+    pub session_redis_url: Option<String>,
+
     /// Docker network name for backend containers (internal, isolated)
     pub backend_network: String,
 
@@ -64,6 +78,272 @@ pub struct AppConfig {
     /// Max seconds to hold a queued request before returning 429 (env: QUEUE_TIMEOUT_SECS)
     pub queue_timeout_secs: u64,
 
+    /// Max seconds a graceful container stop waits for in-flight requests
+    /// before stopping anyway (env: GRACEFUL_STOP_TIMEOUT_SECS)
+    pub graceful_stop_timeout_secs: u64,
+
+    /// Max idle keep-alive connections kept per backend (env: BACKEND_POOL_MAX_IDLE)
+    pub backend_pool_max_idle: usize,
+
+    /// Seconds an idle backend connection is kept open (env: BACKEND_POOL_IDLE_TIMEOUT_SECS)
+    pub backend_pool_idle_timeout_secs: u64,
+
+    /// Seconds to wait when opening a backend connection (env: BACKEND_CONNECT_TIMEOUT_SECS)
+    pub backend_connect_timeout_secs: u64,
+
+    /// Upstream HTTP protocol per backend type, e.g. `llamacpp=auto`
+    /// (env: UPSTREAM_PROTOCOLS). Values: http1, http2, auto. Unlisted types use http1.
+    pub upstream_protocols: HashMap<String, String>,
+
+    /// Seconds between SSE keep-alive comments sent while a streaming request
+    /// waits for its first backend chunk; 0 disables (env: SSE_KEEPALIVE_SECS)
+    pub sse_keepalive_secs: u64,
+
+    /// Structured access log file; disabled when unset (env: ACCESS_LOG_PATH)
+    pub access_log_path: Option<String>,
+
+    /// Rotate the access log file at this size (env: ACCESS_LOG_MAX_BYTES)
+    pub access_log_max_bytes: u64,
+
+    /// Rotated access log files to keep (env: ACCESS_LOG_MAX_FILES)
+    pub access_log_max_files: u32,
+
+    /// Webhook receiving access log entries as NDJSON batches (env: ACCESS_LOG_WEBHOOK_URL)
+    pub access_log_webhook_url: Option<String>,
+
+    /// Sustained requests per minute per client IP on `/auth/*`; 0 disables
+    /// (env: AUTH_RATE_LIMIT_PER_MINUTE)
+    pub auth_rate_limit_per_minute: u32,
+
+    /// Requests a client IP may burst on `/auth/*` before throttling kicks in
+    /// (env: AUTH_RATE_LIMIT_BURST)
+    pub auth_rate_limit_burst: u32,
+
+    /// Take the client IP from `X-Forwarded-For` instead of the socket peer.
+    /// Only enable behind a trusted reverse proxy
+    /// (env: TRUST_FORWARDED_FOR, default: false)
+    pub trust_forwarded_for: bool,
+
+    /// Number of trusted proxies in front of the service. The client IP is the
+    /// entry this many places from the right of `X-Forwarded-For`; entries to
+    /// its left are client-supplied and ignored (env: TRUSTED_PROXY_HOPS, default: 1)
+    pub trusted_proxy_hops: usize,
+
+    /// How strictly an OIDC callback must come from the client that started
+    /// the login: `strict` (network prefix + User-Agent), `user_agent` (for
+    /// roaming clients), or `off` (env: OIDC_STATE_BINDING, default: strict)
+    pub oidc_state_binding: String,
+
+    /// Lifetime of a "remember this device" refresh token in days; 0 disables
+    /// remembering (env: TRUSTED_DEVICE_TTL_DAYS)
+    pub trusted_device_ttl_days: i64,
+
+    /// Allow trading opaque API tokens for short-lived JWTs validated without a
+    /// DB query (env: JWT_ACCESS_TOKENS, default: false)
+    pub jwt_access_tokens: bool,
+
+    /// HMAC-SHA256 key for JWT access tokens; random per process when unset
+    /// (env: JWT_SIGNING_KEY)
+    pub jwt_signing_key: Option<String>,
+
+    /// JWT access token lifetime in seconds (env: JWT_TTL_SECS)
+    pub jwt_ttl_secs: u64,
+
+    /// HMAC-SHA256 key for signed download URLs; random per process when
+    /// unset (env: SIGNED_URL_KEY)
+    pub signed_url_key: Option<String>,
+
+    /// Longest lifetime a signed download URL can be given, in seconds
+    /// (env: SIGNED_URL_MAX_TTL_SECS, default: 3600)
+    pub signed_url_max_ttl_secs: u64,
+
+    /// Seconds a validated bearer token stays cached in memory; 0 disables
+    /// the cache (env: TOKEN_CACHE_TTL_SECS, default: 30)
+    pub token_cache_ttl_secs: u64,
+
+    /// Maximum cached tokens before least-recently-used eviction
+    /// (env: TOKEN_CACHE_MAX_ENTRIES, default: 10000)
+    pub token_cache_max_entries: usize,
+
+    /// Retry unknown model names case-insensitively, with prefixes stripped,
+    /// and against repo basenames (env: MODEL_NAME_NORMALIZE, default: true)
+    pub model_name_normalize: bool,
+
+    /// Comma-separated client prefixes stripped from model names
+    /// (env: MODEL_NAME_STRIP_PREFIXES, default: "openai/")
+    pub model_name_strip_prefixes: Vec<String>,
+
+    /// Suggest the closest known name in model-not-found errors
+    /// (env: MODEL_NAME_SUGGESTIONS, default: true)
+    pub model_name_suggestions: bool,
+
+    /// Endpoint receiving hourly usage deltas for billing (env: USAGE_WEBHOOK_URL)
+    pub usage_webhook_url: Option<String>,
+
+    /// HMAC-SHA256 key for signing usage webhooks (env: USAGE_WEBHOOK_SECRET)
+    pub usage_webhook_secret: Option<String>,
+
+    /// Retries per usage webhook delivery before waiting for the next tick
+    /// (env: USAGE_WEBHOOK_MAX_RETRIES, default: 5)
+    pub usage_webhook_max_retries: u32,
+
+    /// Endpoint receiving event bus notifications (env: EVENT_WEBHOOK_URL)
+    pub event_webhook_url: Option<String>,
+
+    /// HMAC-SHA256 key for signing event webhooks (env: EVENT_WEBHOOK_SECRET)
+    pub event_webhook_secret: Option<String>,
+
+    /// Store /v1 completion requests and responses so admins can replay them.
+    /// The default for categories without their own `log_level`
+    /// (env: REQUEST_LOG, default: false)
+    pub request_log: bool,
+
+    /// Seconds a request waits for an autoloaded container to become healthy
+    /// (env: AUTOLOAD_TIMEOUT_SECS, default: 300)
+    pub autoload_timeout_secs: u64,
+
+    /// Seconds between backend watchdog checks of loaded models; 0 turns
+    /// the watchdog off (env: WATCHDOG_INTERVAL_SECS, default: 30)
+    pub watchdog_interval_secs: u64,
+
+    /// Restarts the watchdog attempts for a crashed container before it
+    /// marks the model unloaded (env: WATCHDOG_MAX_RESTARTS, default: 3)
+    pub watchdog_max_restarts: u32,
+
+    /// Send each slot of a newly healthy backend a tiny request, so the first
+    /// real request doesn't pay slot initialization (env: BACKEND_WARMUP,
+    /// default: false)
+    pub backend_warmup: bool,
+
+    /// Archive completion prompts and responses, content-addressed by SHA-256
+    /// (env: PROMPT_ARCHIVE, default: false)
+    pub prompt_archive: bool,
+
+    /// Days archived prompts are kept; 0 keeps them forever
+    /// (env: PROMPT_ARCHIVE_RETENTION_DAYS, default: 90)
+    pub prompt_archive_retention_days: i64,
+
+    /// Record a hash of each completion prompt in the usage log for the
+    /// prompt deduplication statistics (env: PROMPT_DEDUP_STATS, default: false)
+    pub prompt_dedup_stats: bool,
+
+    /// Hold model deletes and admin grants until a second admin approves them
+    /// (env: TWO_PERSON_APPROVAL, default: false)
+    pub two_person_approval: bool,
+
+    /// Minutes a second admin has to approve a held action
+    /// (env: APPROVAL_WINDOW_MINUTES, default: 60)
+    pub approval_window_minutes: i64,
+
+    /// Serve `/v1/tools/execute`, running code in sandbox containers
+    /// (env: SANDBOX_ENABLED, default: false)
+    pub sandbox_enabled: bool,
+
+    /// Image sandboxed Python snippets run in
+    /// (env: SANDBOX_IMAGE, default: python:3.12-alpine)
+    pub sandbox_image: String,
+
+    /// Memory limit per sandbox run, no swap (env: SANDBOX_MEMORY_MB, default: 256)
+    pub sandbox_memory_mb: u64,
+
+    /// CPU limit per sandbox run (env: SANDBOX_CPUS, default: 1.0)
+    pub sandbox_cpus: f64,
+
+    /// Longest a sandbox run may take; requests may ask for less
+    /// (env: SANDBOX_TIMEOUT_SECS, default: 10)
+    pub sandbox_timeout_secs: u64,
+
+    /// Sandbox runs allowed at once; more get 429
+    /// (env: SANDBOX_MAX_CONCURRENT, default: 4)
+    pub sandbox_max_concurrent: usize,
+
+    /// Refuse to start a llama.cpp container whose model file would push host
+    /// memory use past this percentage; 0 disables the check
+    /// (env: HOST_MEMORY_CEILING_PERCENT, default: 0)
+    pub host_memory_ceiling_percent: u64,
+
+    /// Host memory use at which pressure counts as critical
+    /// (env: MEMORY_CRITICAL_PERCENT, default: 95)
+    pub memory_critical_percent: u64,
+
+    /// Turn inference requests away with 503 while memory pressure is critical
+    /// (env: MEMORY_PRESSURE_ADMISSION, default: false)
+    pub memory_pressure_admission: bool,
+
+    /// Serve backend traffic over mutual TLS with per-container certificates
+    /// (env: BACKEND_TLS, default: false)
+    pub backend_tls: bool,
+
+    /// Where the backend CA and container certificates are kept
+    /// (env: BACKEND_TLS_DIR, default: /config/backend-tls)
+    pub backend_tls_dir: String,
+
+    /// Host-side path of BACKEND_TLS_DIR for backend bind mounts
+    /// (env: BACKEND_TLS_HOST_PATH, default: BACKEND_TLS_DIR)
+    pub backend_tls_host_path: String,
+
+    /// Per-download speed cap in MB/s while any model has requests in flight;
+    /// 0 leaves downloads at full speed
+    /// (env: DOWNLOAD_BACKGROUND_MB_PER_SEC, default: 0)
+    pub download_background_mb_per_sec: u64,
+
+    /// Concurrent range requests per downloaded file; 1 downloads each file
+    /// over a single connection (env: HF_DOWNLOAD_CONNECTIONS, default: 1)
+    pub hf_download_connections: usize,
+
+    /// Chunk size in MB for parallel downloads; files under two chunks use
+    /// one connection (env: HF_DOWNLOAD_CHUNK_MB, default: 64)
+    pub hf_download_chunk_mb: u64,
+
+    /// Hours a deleted model's files stay in `<MODEL_PATH>/.trash` before
+    /// they are purged; 0 deletes them immediately
+    /// (env: MODEL_TRASH_HOURS, default: 24)
+    pub model_trash_hours: u64,
+
+    /// Size cap for the model trash in GB; the oldest entries are purged to
+    /// stay under it. 0 means no cap (env: MODEL_TRASH_MAX_GB, default: 200)
+    pub model_trash_max_gb: u64,
+
+    /// Staging directory `POST /api/admin/models/import` may copy models
+    /// from into MODEL_PATH, e.g. a NAS mount (env: MODEL_IMPORT_PATH,
+    /// default: none, only files already under MODEL_PATH can be imported)
+    pub model_import_path: Option<String>,
+
+    /// Keep models in S3-compatible object storage at `s3://bucket/prefix`,
+    /// with MODEL_PATH as a local cache filled on container start
+    /// (env: MODEL_STORE_S3, default: none, models are local only)
+    pub model_store_s3: Option<String>,
+
+    /// Object storage endpoint, e.g. `http://minio:9000`
+    /// (env: S3_ENDPOINT, default: AWS S3 in S3_REGION)
+    pub s3_endpoint: Option<String>,
+
+    /// Region requests are signed for (env: S3_REGION, default: us-east-1)
+    pub s3_region: String,
+
+    /// Object storage credentials (env: S3_ACCESS_KEY_ID,
+    /// S3_SECRET_ACCESS_KEY)
+    pub s3_access_key_id: Option<String>,
+    pub s3_secret_access_key: Option<String>,
+
+    /// Size cap for MODEL_PATH in GB when MODEL_STORE_S3 is set; the least
+    /// recently used unloaded models are evicted from the local cache to
+    /// stay under it. 0 means no cap (env: MODEL_CACHE_MAX_GB, default: 0)
+    pub model_cache_max_gb: u64,
+
+    /// Run a throwaway container per detected GPU type at startup to check
+    /// device passthrough (env: GPU_PROBE, default: true)
+    pub gpu_probe: bool,
+
+    /// What to do with a detected category for a new download that has
+    /// none: `suggest` (an admin accepts it), `assign`, or `off`
+    /// (env: AUTO_CATEGORIZE, default: suggest)
+    pub auto_categorize: String,
+
+    /// Days request log entries are kept; 0 keeps them forever
+    /// (env: REQUEST_LOG_RETENTION_DAYS, default: 30)
+    pub request_log_retention_days: i64,
+
     /// Set Secure flag on session cookies (env: SECURE_COOKIES, default: true).
     /// Set to false for HTTP-only dev instances.
     pub secure_cookies: bool,
@@ -107,9 +387,13 @@ impl AppConfig {
                 std::env::var("MODEL_PATH").unwrap_or_else(|_| "/models".into())
             }),
             ui_path: std::env::var("UI_PATH").unwrap_or_else(|_| "/app/ui".into()),
+            sites_path: std::env::var("SITES_PATH").unwrap_or_else(|_| "/config/sites".into()),
             api_hostname: std::env::var("API_HOSTNAME").unwrap_or_else(|_| "localhost".into()),
             chat_hostname: std::env::var("CHAT_HOSTNAME").unwrap_or_else(|_| "localhost".into()),
             cookie_domain: std::env::var("COOKIE_DOMAIN").ok(),
+            session_redis_url: std::env::var("SESSION_REDIS_URL")
+                .ok()
+                .filter(|v| !v.is_empty()),
             backend_network: std::env::var("BACKEND_NETWORK")
                 .unwrap_or_else(|_| "sovereign-internal".into()),
             acme_contact: std::env::var("ACME_CONTACT").ok(),
@@ -123,6 +407,232 @@ impl AppConfig {
                 .ok()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(30),
+            graceful_stop_timeout_secs: std::env::var("GRACEFUL_STOP_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(120),
+            backend_pool_max_idle: std::env::var("BACKEND_POOL_MAX_IDLE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(32),
+            backend_pool_idle_timeout_secs: std::env::var("BACKEND_POOL_IDLE_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(90),
+            backend_connect_timeout_secs: std::env::var("BACKEND_CONNECT_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+            upstream_protocols: std::env::var("UPSTREAM_PROTOCOLS")
+                .map(|v| parse_key_value_list(&v))
+                .unwrap_or_default(),
+            sse_keepalive_secs: std::env::var("SSE_KEEPALIVE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(15),
+            access_log_path: std::env::var("ACCESS_LOG_PATH").ok(),
+            access_log_max_bytes: std::env::var("ACCESS_LOG_MAX_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100 * 1024 * 1024),
+            access_log_max_files: std::env::var("ACCESS_LOG_MAX_FILES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+            access_log_webhook_url: std::env::var("ACCESS_LOG_WEBHOOK_URL").ok(),
+            auth_rate_limit_per_minute: std::env::var("AUTH_RATE_LIMIT_PER_MINUTE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            auth_rate_limit_burst: std::env::var("AUTH_RATE_LIMIT_BURST")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10),
+            trust_forwarded_for: std::env::var("TRUST_FORWARDED_FOR")
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            trusted_proxy_hops: std::env::var("TRUSTED_PROXY_HOPS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1),
+            oidc_state_binding: std::env::var("OIDC_STATE_BINDING")
+                .unwrap_or_else(|_| "strict".to_string()),
+            trusted_device_ttl_days: std::env::var("TRUSTED_DEVICE_TTL_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            jwt_access_tokens: std::env::var("JWT_ACCESS_TOKENS")
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            jwt_signing_key: std::env::var("JWT_SIGNING_KEY")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            jwt_ttl_secs: std::env::var("JWT_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+            signed_url_key: std::env::var("SIGNED_URL_KEY")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            signed_url_max_ttl_secs: std::env::var("SIGNED_URL_MAX_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3600),
+            token_cache_ttl_secs: std::env::var("TOKEN_CACHE_TTL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            token_cache_max_entries: std::env::var("TOKEN_CACHE_MAX_ENTRIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10_000),
+            model_name_normalize: std::env::var("MODEL_NAME_NORMALIZE")
+                .map(|v| !v.eq_ignore_ascii_case("false"))
+                .unwrap_or(true),
+            model_name_strip_prefixes: std::env::var("MODEL_NAME_STRIP_PREFIXES")
+                .map(|v| {
+                    v.split(',')
+                        .map(|p| p.trim().to_string())
+                        .filter(|p| !p.is_empty())
+                        .collect()
+                })
+                .unwrap_or_else(|_| vec!["openai/".to_string()]),
+            model_name_suggestions: std::env::var("MODEL_NAME_SUGGESTIONS")
+                .map(|v| !v.eq_ignore_ascii_case("false"))
+                .unwrap_or(true),
+            usage_webhook_url: std::env::var("USAGE_WEBHOOK_URL").ok(),
+            usage_webhook_secret: std::env::var("USAGE_WEBHOOK_SECRET").ok(),
+            usage_webhook_max_retries: std::env::var("USAGE_WEBHOOK_MAX_RETRIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(5),
+            event_webhook_url: std::env::var("EVENT_WEBHOOK_URL").ok(),
+            event_webhook_secret: std::env::var("EVENT_WEBHOOK_SECRET").ok(),
+            request_log: std::env::var("REQUEST_LOG")
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            autoload_timeout_secs: std::env::var("AUTOLOAD_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(300),
+            watchdog_interval_secs: std::env::var("WATCHDOG_INTERVAL_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
+            watchdog_max_restarts: std::env::var("WATCHDOG_MAX_RESTARTS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3),
+            backend_warmup: std::env::var("BACKEND_WARMUP")
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            prompt_archive: std::env::var("PROMPT_ARCHIVE")
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            prompt_archive_retention_days: std::env::var("PROMPT_ARCHIVE_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(90),
+            prompt_dedup_stats: std::env::var("PROMPT_DEDUP_STATS")
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            two_person_approval: std::env::var("TWO_PERSON_APPROVAL")
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            approval_window_minutes: std::env::var("APPROVAL_WINDOW_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|m: &i64| *m > 0)
+                .unwrap_or(60),
+            sandbox_enabled: std::env::var("SANDBOX_ENABLED")
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            sandbox_image: std::env::var("SANDBOX_IMAGE")
+                .unwrap_or_else(|_| "python:3.12-alpine".to_string()),
+            sandbox_memory_mb: std::env::var("SANDBOX_MEMORY_MB")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|m: &u64| *m > 0)
+                .unwrap_or(256),
+            sandbox_cpus: std::env::var("SANDBOX_CPUS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|c: &f64| *c > 0.0)
+                .unwrap_or(1.0),
+            sandbox_timeout_secs: std::env::var("SANDBOX_TIMEOUT_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|s: &u64| *s > 0)
+                .unwrap_or(10),
+            sandbox_max_concurrent: std::env::var("SANDBOX_MAX_CONCURRENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|n: &usize| *n > 0)
+                .unwrap_or(4),
+            host_memory_ceiling_percent: std::env::var("HOST_MEMORY_CEILING_PERCENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|p: &u64| *p <= 100)
+                .unwrap_or(0),
+            memory_critical_percent: std::env::var("MEMORY_CRITICAL_PERCENT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|p: &u64| (1..=100).contains(p))
+                .unwrap_or(95),
+            memory_pressure_admission: std::env::var("MEMORY_PRESSURE_ADMISSION")
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            backend_tls: std::env::var("BACKEND_TLS")
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            backend_tls_dir: std::env::var("BACKEND_TLS_DIR")
+                .unwrap_or_else(|_| "/config/backend-tls".into()),
+            backend_tls_host_path: std::env::var("BACKEND_TLS_HOST_PATH").unwrap_or_else(|_| {
+                std::env::var("BACKEND_TLS_DIR").unwrap_or_else(|_| "/config/backend-tls".into())
+            }),
+            download_background_mb_per_sec: std::env::var("DOWNLOAD_BACKGROUND_MB_PER_SEC")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            hf_download_connections: std::env::var("HF_DOWNLOAD_CONNECTIONS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1),
+            hf_download_chunk_mb: std::env::var("HF_DOWNLOAD_CHUNK_MB")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(64),
+            model_trash_hours: std::env::var("MODEL_TRASH_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(24),
+            model_trash_max_gb: std::env::var("MODEL_TRASH_MAX_GB")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(200),
+            model_import_path: std::env::var("MODEL_IMPORT_PATH")
+                .ok()
+                .filter(|v| !v.is_empty()),
+            model_store_s3: std::env::var("MODEL_STORE_S3")
+                .ok()
+                .filter(|v| !v.is_empty()),
+            s3_endpoint: std::env::var("S3_ENDPOINT").ok().filter(|v| !v.is_empty()),
+            s3_region: std::env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".into()),
+            s3_access_key_id: std::env::var("S3_ACCESS_KEY_ID").ok(),
+            s3_secret_access_key: std::env::var("S3_SECRET_ACCESS_KEY").ok(),
+            model_cache_max_gb: std::env::var("MODEL_CACHE_MAX_GB")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0),
+            gpu_probe: std::env::var("GPU_PROBE")
+                .map(|v| !v.eq_ignore_ascii_case("false"))
+                .unwrap_or(true),
+            auto_categorize: std::env::var("AUTO_CATEGORIZE")
+                .unwrap_or_else(|_| "suggest".to_string()),
+            request_log_retention_days: std::env::var("REQUEST_LOG_RETENTION_DAYS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30),
             secure_cookies: std::env::var("SECURE_COOKIES")
                 .map(|v| !v.eq_ignore_ascii_case("false"))
                 .unwrap_or(true),
@@ -179,6 +689,25 @@ impl AppConfig {
         format!("{scheme}://{}", self.chat_hostname)
     }
 
+    /// SSE keep-alive interval, or `None` when disabled.
+    pub fn sse_keepalive(&self) -> Option<Duration> {
+        if self.sse_keepalive_secs > 0 {
+            Some(Duration::from_secs(self.sse_keepalive_secs))
+        } else {
+            None
+        }
+    }
+
+    /// How many `X-Forwarded-For` entries to walk from the right to find the
+    /// client IP, or 0 when the header is not trusted.
+    pub fn forwarded_hops(&self) -> usize {
+        if self.trust_forwarded_for {
+            self.trusted_proxy_hops.max(1)
+        } else {
+            0
+        }
+    }
+
     /// Return ACME config if ACME_CONTACT is set.
     /// Derives domains from api_hostname + chat_hostname.
     pub fn acme_config(&self) -> Result<Option<AcmeSettings>> {
@@ -193,6 +722,103 @@ impl AppConfig {
     }
 }
 
+/// A secret manager that can supply config secrets instead of the
+/// environment (env: SECRET_PROVIDER).
+///
+/// `fetch` returns the secret's key/value pairs. Keys named like the
+/// variables they replace (`DB_ENCRYPTION_KEY`, `DB_ENCRYPTION_KEY_OLD`,
+/// `WEBUI_API_KEY`, `S3_SECRET_ACCESS_KEY`) override the environment;
+/// others are ignored.
+pub trait SecretProvider {
+    /// Short name for logs.
+    fn name(&self) -> &'static str;
+
+    fn fetch(&self) -> impl std::future::Future<Output = Result<HashMap<String, String>>> + Send;
+}
+
+/// The secret manager selected by SECRET_PROVIDER.
+pub enum SecretSource {
+    Vault(secrets::Vault),
+    Aws(secrets::AwsSecretsManager),
+    Sops(secrets::SopsFile),
+}
+
+impl SecretSource {
+    /// Read SECRET_PROVIDER and the chosen provider's settings. None when
+    /// unset or `env`, i.e. secrets come from the environment only.
+    pub fn from_env() -> Result<Option<Self>> {
+        let provider = std::env::var("SECRET_PROVIDER").unwrap_or_default();
+        Ok(Some(match provider.trim() {
+            "" | "env" => return Ok(None),
+            "vault" => Self::Vault(secrets::Vault::from_env()?),
+            "aws" => Self::Aws(secrets::AwsSecretsManager::from_env()?),
+            "sops" => Self::Sops(secrets::SopsFile::from_env()?),
+            other => bail!("Unknown SECRET_PROVIDER '{other}' (expected vault, aws or sops)"),
+        }))
+    }
+}
+
+impl SecretProvider for SecretSource {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Vault(p) => p.name(),
+            Self::Aws(p) => p.name(),
+            Self::Sops(p) => p.name(),
+        }
+    }
+
+    async fn fetch(&self) -> Result<HashMap<String, String>> {
+        match self {
+            Self::Vault(p) => p.fetch().await,
+            Self::Aws(p) => p.fetch().await,
+            Self::Sops(p) => p.fetch().await,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Fetch secrets from `provider` and overwrite the matching fields.
+    /// Returns the names of the secrets it supplied.
+    pub async fn load_secrets(
+        &mut self,
+        provider: &impl SecretProvider,
+    ) -> Result<Vec<&'static str>> {
+        let values = provider
+            .fetch()
+            .await
+            .with_context(|| format!("Failed to fetch secrets from {}", provider.name()))?;
+        Ok(self.apply_secrets(&values))
+    }
+
+    fn apply_secrets(&mut self, values: &HashMap<String, String>) -> Vec<&'static str> {
+        let fields: [(&'static str, &mut Option<String>); 4] = [
+            ("DB_ENCRYPTION_KEY", &mut self.db_encryption_key),
+            ("DB_ENCRYPTION_KEY_OLD", &mut self.db_encryption_key_old),
+            ("WEBUI_API_KEY", &mut self.webui_api_key),
+            ("S3_SECRET_ACCESS_KEY", &mut self.s3_secret_access_key),
+        ];
+        let mut applied = Vec::new();
+        for (key, field) in fields {
+            if let Some(value) = values.get(key).filter(|v| !v.is_empty()) {
+                *field = Some(value.clone());
+                applied.push(key);
+            }
+        }
+        applied
+    }
+}
+
+/// Parse `key=value,key=value` into a map, skipping malformed entries.
+fn parse_key_value_list(s: &str) -> HashMap<String, String> {
+    s.split(',')
+        .filter_map(|pair| {
+            let (k, v) = pair.split_once('=')?;
+            let (k, v) = (k.trim(), v.trim());
+            (!k.is_empty() && !v.is_empty()).then(|| (k.to_string(), v.to_string()))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -212,9 +838,11 @@ mod tests {
             model_path: "/models".into(),
             model_host_path: "/models".into(),
             ui_path: "/app/ui".into(),
+            sites_path: "/config/sites".into(),
             api_hostname: "localhost".into(),
             chat_hostname: "localhost".into(),
             cookie_domain: None,
+            session_redis_url: None,
             backend_network: "sovereign-internal".into(),
             acme_contact: None,
             acme_staging: false,
@@ -224,6 +852,74 @@ mod tests {
             secure_cookies: true,
             db_encryption_key: None,
             db_encryption_key_old: None,
+            graceful_stop_timeout_secs: 120,
+            backend_pool_max_idle: 32,
+            backend_pool_idle_timeout_secs: 90,
+            backend_connect_timeout_secs: 5,
+            upstream_protocols: Default::default(),
+            sse_keepalive_secs: 15,
+            access_log_path: None,
+            access_log_max_bytes: 100 * 1024 * 1024,
+            access_log_max_files: 5,
+            access_log_webhook_url: None,
+            auth_rate_limit_per_minute: 0,
+            auth_rate_limit_burst: 10,
+            trust_forwarded_for: false,
+            trusted_proxy_hops: 1,
+            oidc_state_binding: "strict".to_string(),
+            trusted_device_ttl_days: 30,
+            jwt_access_tokens: false,
+            jwt_signing_key: None,
+            jwt_ttl_secs: 300,
+            token_cache_ttl_secs: 0,
+            token_cache_max_entries: 10_000,
+            model_name_normalize: true,
+            model_name_strip_prefixes: vec!["openai/".to_string()],
+            model_name_suggestions: true,
+            usage_webhook_url: None,
+            usage_webhook_secret: None,
+            usage_webhook_max_retries: 5,
+            event_webhook_url: None,
+            event_webhook_secret: None,
+            request_log: false,
+            autoload_timeout_secs: 300,
+            watchdog_interval_secs: 30,
+            watchdog_max_restarts: 3,
+            backend_warmup: false,
+            prompt_archive: false,
+            prompt_archive_retention_days: 90,
+            prompt_dedup_stats: false,
+            two_person_approval: false,
+            approval_window_minutes: 60,
+            sandbox_enabled: false,
+            sandbox_image: "python:3.12-alpine".to_string(),
+            sandbox_memory_mb: 256,
+            sandbox_cpus: 1.0,
+            sandbox_timeout_secs: 10,
+            sandbox_max_concurrent: 4,
+            backend_tls: false,
+            backend_tls_dir: "/config/backend-tls".to_string(),
+            backend_tls_host_path: "/config/backend-tls".to_string(),
+            host_memory_ceiling_percent: 0,
+            memory_critical_percent: 95,
+            memory_pressure_admission: false,
+            download_background_mb_per_sec: 0,
+            hf_download_connections: 1,
+            hf_download_chunk_mb: 64,
+            model_trash_hours: 24,
+            model_trash_max_gb: 200,
+            model_import_path: None,
+            model_store_s3: None,
+            s3_endpoint: None,
+            s3_region: "us-east-1".into(),
+            s3_access_key_id: None,
+            s3_secret_access_key: None,
+            model_cache_max_gb: 0,
+            gpu_probe: false,
+            auto_categorize: "off".to_string(),
+            request_log_retention_days: 30,
+            signed_url_key: None,
+            signed_url_max_ttl_secs: 3600,
         }
     }
 
@@ -433,4 +1129,56 @@ mod tests {
         };
         assert_eq!(cfg.chat_external_url(), "https://chat.example.com");
     }
+
+    // -----------------------------------------------------------------------
+    // parse_key_value_list
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn parse_key_value_list_trims_and_skips_malformed() {
+        let map = parse_key_value_list(" llamacpp = auto ,bogus,=http2,vllm=http2,");
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["llamacpp"], "auto");
+        assert_eq!(map["vllm"], "http2");
+    }
+
+    // -----------------------------------------------------------------------
+    // sse_keepalive
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn sse_keepalive_zero_disables() {
+        let cfg = AppConfig {
+            sse_keepalive_secs: 0,
+            ..base_config()
+        };
+        assert_eq!(cfg.sse_keepalive(), None);
+    }
+
+    #[test]
+    fn secrets_override_only_known_non_empty_keys() {
+        let mut cfg = AppConfig {
+            webui_api_key: Some("from-env".into()),
+            db_encryption_key: Some("env-key".into()),
+            ..base_config()
+        };
+        let values = HashMap::from([
+            ("WEBUI_API_KEY".to_string(), "from-vault".to_string()),
+            ("DB_ENCRYPTION_KEY".to_string(), String::new()),
+            ("UNRELATED".to_string(), "x".to_string()),
+        ]);
+        assert_eq!(cfg.apply_secrets(&values), vec!["WEBUI_API_KEY"]);
+        assert_eq!(cfg.webui_api_key.as_deref(), Some("from-vault"));
+        assert_eq!(cfg.db_encryption_key.as_deref(), Some("env-key"));
+        assert_eq!(cfg.db_encryption_key_old, None);
+    }
+
+    #[test]
+    fn sse_keepalive_interval() {
+        let cfg = AppConfig {
+            sse_keepalive_secs: 10,
+            ..base_config()
+        };
+        assert_eq!(cfg.sse_keepalive(), Some(Duration::from_secs(10)));
+    }
 }
@@ -3,6 +3,7 @@ use aes_gcm::{AeadCore, Aes256Gcm, Key, KeyInit, Nonce};
 use anyhow::{Context, Result};
 use base64::Engine as _;
 use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
 use sha2::{Digest, Sha256};
 use tracing::info;
 
@@ -31,6 +32,15 @@ fn derive_key_legacy(key_str: &str) -> Key<Aes256Gcm> {
     *Key::<Aes256Gcm>::from_slice(&hash)
 }
 
+/// Identify `key_str` without revealing it: hex HMAC-SHA256 of a fixed label.
+/// Two instances with the same fingerprint can read each other's secrets.
+pub fn key_fingerprint(key_str: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key_str.as_bytes())
+        .expect("HMAC-SHA256 accepts keys of any length");
+    mac.update(b"sovereign-engine-key-fingerprint");
+    hex::encode(mac.finalize().into_bytes())
+}
+
 /// Encrypt plaintext with AES-256-GCM. Returns base64(nonce || ciphertext).
 pub fn encrypt(plaintext: &str, key_str: &str) -> Result<String> {
     let key = derive_key(key_str);
@@ -210,6 +220,13 @@ mod tests {
         assert_eq!(decrypted, plaintext);
     }
 
+    #[test]
+    fn key_fingerprint_identifies_key() {
+        assert_eq!(key_fingerprint(TEST_KEY), key_fingerprint(TEST_KEY));
+        assert_ne!(key_fingerprint(TEST_KEY), key_fingerprint(NEW_KEY));
+        assert_eq!(key_fingerprint(TEST_KEY).len(), 64);
+    }
+
     #[test]
     fn decrypt_with_wrong_key_fails() {
         let encrypted = encrypt("secret", TEST_KEY).unwrap();
@@ -12,6 +12,12 @@ pub struct IdpConfig {
     pub scopes: String,
     pub enabled: bool,
     pub created_at: DateTime<Utc>,
+    /// ID token claim holding the user's groups; `None` disables group sync.
+    #[sqlx(default)]
+    pub groups_claim: Option<String>,
+    /// JSON object of group → `admin` or role names (see `auth::groups`).
+    #[sqlx(default)]
+    pub group_mappings: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -21,6 +27,30 @@ pub struct ModelCategory {
     pub description: String,
     pub preferred_model_id: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// Start any unloaded model in this category when a request needs it.
+    #[sqlx(default)]
+    pub autoload: bool,
+    /// Model kind this category takes in auto-categorization.
+    #[sqlx(default)]
+    pub auto_kind: Option<String>,
+    /// Request log level (`none`, `metadata`, `full`); `None` follows
+    /// `REQUEST_LOG`.
+    #[sqlx(default)]
+    pub log_level: Option<String>,
+    /// Requests in flight across the category's models; `None` = no limit.
+    #[sqlx(default)]
+    pub max_concurrent: Option<i64>,
+    /// Requests queued across the category's models; `None` = no limit.
+    #[sqlx(default)]
+    pub max_queue_depth: Option<i64>,
+    /// Seconds a request waits for a slot; `None` follows the global
+    /// `queue_timeout_secs` setting.
+    #[sqlx(default)]
+    pub queue_timeout_secs: Option<i64>,
+    /// Parallel slots for the category's models when neither the start
+    /// request nor the launch profile sets them.
+    #[sqlx(default)]
+    pub default_parallel: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -48,21 +78,94 @@ pub struct Model {
     pub kv_bytes_per_token_swa: Option<i64>,
     /// Per-model llama-server CLI overrides. Stored as a JSON TEXT column
     /// (defaults to `{}` per migration). Serialized to the API as a nested
-    /// object, not a string — see [`serialize_runtime_overrides`].
-    #[serde(serialize_with = "serialize_runtime_overrides")]
+    /// object, not a string — see [`serialize_json_object`].
+    #[serde(serialize_with = "serialize_json_object")]
     pub runtime_overrides: String,
+    /// New requests are rejected while set (admin drain before unload).
+    #[sqlx(default)]
+    pub draining: bool,
+    /// SHA-256 of the primary file, verified at download time against the
+    /// repo's LFS hash. `None` for registered (not downloaded) models.
+    #[sqlx(default)]
+    pub sha256: Option<String>,
+    /// Minutes idle before the container is stopped; `None` follows the
+    /// `idle_unload_minutes` setting, `Some(0)` never unloads.
+    #[sqlx(default)]
+    pub idle_unload_minutes: Option<i64>,
+    /// Start the container when a request arrives while unloaded.
+    #[sqlx(default)]
+    pub autoload: bool,
+    /// Embedding model, served on `/v1/embeddings`.
+    #[sqlx(default)]
+    pub embedding: bool,
+    /// GGUF `general.architecture` and Hugging Face pipeline tag, as seen
+    /// at download.
+    #[sqlx(default)]
+    pub architecture: Option<String>,
+    #[sqlx(default)]
+    pub pipeline_tag: Option<String>,
+    /// Category proposed by auto-categorization, waiting for an admin.
+    #[sqlx(default)]
+    pub suggested_category_id: Option<String>,
+    #[sqlx(default)]
+    pub category_suggestion_reason: Option<String>,
+    /// Sampling defaults filled into `/v1` completion requests, as JSON
+    /// TEXT (`{}` for none). See `api::sampling_defaults`.
+    #[sqlx(default)]
+    #[serde(serialize_with = "serialize_json_object")]
+    pub default_params: String,
+    /// `reject` or `truncate` prompts longer than the model's context.
+    #[sqlx(default)]
+    pub context_policy: String,
+    /// What the backend answered when last probed after a start, as JSON
+    /// TEXT. See `api::capabilities`.
+    #[sqlx(default)]
+    #[serde(serialize_with = "serialize_optional_json")]
+    pub capabilities: Option<String>,
+    /// Cleared when the files are evicted from disk after going unused
+    /// (the `model_evict_unused_days` setting).
+    #[sqlx(default)]
+    pub downloaded: bool,
+    #[sqlx(default)]
+    pub evicted_at: Option<DateTime<Utc>>,
 }
 
-/// Serialize the `runtime_overrides` JSON column as a nested object so the
-/// wire shape matches the typed `RuntimeOverrides | null` the UI expects.
+/// Serialize a JSON TEXT column (`runtime_overrides`, `group_mappings`) as a
+/// nested object so the wire shape matches the typed objects the UI expects.
 /// Falls back to `{}` if the stored text fails to parse — keeps the API
 /// contract stable even if a row somehow holds invalid JSON.
-fn serialize_runtime_overrides<S: Serializer>(s: &str, ser: S) -> Result<S::Ok, S::Error> {
+fn serialize_json_object<S: Serializer>(s: &str, ser: S) -> Result<S::Ok, S::Error> {
     let value: serde_json::Value =
         serde_json::from_str(s).unwrap_or_else(|_| serde_json::json!({}));
     value.serialize(ser)
 }
 
+/// Serialize an optional JSON TEXT column as a nested value, or `null` when
+/// unset or unparseable.
+fn serialize_optional_json<S: Serializer>(s: &Option<String>, ser: S) -> Result<S::Ok, S::Error> {
+    s.as_deref()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(s).ok())
+        .serialize(ser)
+}
+
+/// Preferred container launch settings for a model. Start requests fall back
+/// to these for omitted fields; `None` means the built-in default.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow)]
+pub struct LaunchProfile {
+    pub backend_type: Option<String>,
+    pub gpu_type: Option<String>,
+    pub gpu_layers: Option<u32>,
+    pub context_size: Option<u32>,
+    pub parallel: Option<u32>,
+    /// CPUs the container may use.
+    #[serde(default)]
+    pub cpu_limit: Option<f64>,
+    #[serde(default)]
+    pub memory_limit_mb: Option<u32>,
+    #[serde(default)]
+    pub pids_limit: Option<u32>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct User {
     pub id: String,
@@ -71,6 +174,10 @@ pub struct User {
     pub email: Option<String>,
     pub display_name: Option<String>,
     pub is_admin: bool,
+    pub active: bool,
+    /// Queue priority tier (`low`, `normal`, `high`).
+    #[sqlx(default)]
+    pub priority_tier: String,
     pub created_at: DateTime<Utc>,
 }
 
@@ -83,6 +190,11 @@ pub struct IdpConfigPublic {
     pub scopes: String,
     pub enabled: bool,
     pub created_at: DateTime<Utc>,
+    pub groups_claim: Option<String>,
+    #[serde(serialize_with = "serialize_json_object")]
+    pub group_mappings: String,
+    /// Whether a SCIM token has been issued (the token itself is never returned).
+    pub scim_enabled: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -94,6 +206,7 @@ pub struct TokenListItem {
     pub specific_model_id: Option<String>,
     pub expires_at: Option<DateTime<Utc>>,
     pub revoked: bool,
+    pub request_quota: Option<i64>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -124,6 +237,20 @@ mod tests {
             kv_bytes_per_token_global: None,
             kv_bytes_per_token_swa: None,
             runtime_overrides: runtime_overrides.into(),
+            draining: false,
+            sha256: None,
+            idle_unload_minutes: None,
+            autoload: false,
+            embedding: false,
+            architecture: None,
+            pipeline_tag: None,
+            suggested_category_id: None,
+            category_suggestion_reason: None,
+            default_params: "{}".into(),
+            context_policy: "reject".into(),
+            capabilities: None,
+            downloaded: true,
+            evicted_at: None,
         }
     }
 
@@ -151,4 +278,13 @@ mod tests {
         let json = serde_json::to_value(&m).expect("serialize");
         assert_eq!(json["runtime_overrides"], serde_json::json!({}));
     }
+
+    #[test]
+    fn capabilities_serialize_as_nested_object_or_null() {
+        let mut m = sample_model("{}");
+        assert!(serde_json::to_value(&m).unwrap()["capabilities"].is_null());
+        m.capabilities = Some(r#"{"endpoints":["chat"],"max_context":8192}"#.into());
+        let json = serde_json::to_value(&m).expect("serialize");
+        assert_eq!(json["capabilities"]["max_context"], 8192);
+    }
 }
@@ -0,0 +1,74 @@
+//! Host CPU architecture and the backend images published for it.
+//!
+//! The llama.cpp CPU image is multi-arch, so Docker pulls the right variant
+//! on its own. The llama.cpp Vulkan and vLLM images are published for amd64
+//! only: on other hosts those backends aren't offered, their GPUs aren't
+//! probed for, and starting them is refused up front rather than failing
+//! with an exec format error inside the container.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HostArch {
+    Amd64,
+    Arm64,
+    /// Anything else; only the llama.cpp CPU backend is attempted.
+    Other,
+}
+
+impl HostArch {
+    /// From a Docker or `uname -m` architecture name.
+    pub fn parse(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "x86_64" | "amd64" => HostArch::Amd64,
+            "aarch64" | "arm64" | "armv8" => HostArch::Arm64,
+            _ => HostArch::Other,
+        }
+    }
+
+    /// The architecture this binary was built for; used when the Docker
+    /// daemon doesn't report one.
+    pub fn native() -> Self {
+        Self::parse(std::env::consts::ARCH)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HostArch::Amd64 => "amd64",
+            HostArch::Arm64 => "arm64",
+            HostArch::Other => "other",
+        }
+    }
+
+    /// Whether the llama.cpp Vulkan image runs here.
+    pub fn has_vulkan_image(&self) -> bool {
+        *self == HostArch::Amd64
+    }
+
+    /// Whether the vLLM image runs here.
+    pub fn has_vllm_image(&self) -> bool {
+        *self == HostArch::Amd64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_docker_and_uname_names() {
+        assert_eq!(HostArch::parse("x86_64"), HostArch::Amd64);
+        assert_eq!(HostArch::parse("aarch64"), HostArch::Arm64);
+        assert_eq!(HostArch::parse("ARM64"), HostArch::Arm64);
+        assert_eq!(HostArch::parse("riscv64"), HostArch::Other);
+    }
+
+    #[test]
+    fn gpu_images_are_amd64_only() {
+        assert!(HostArch::Amd64.has_vulkan_image());
+        assert!(HostArch::Amd64.has_vllm_image());
+        assert!(!HostArch::Arm64.has_vulkan_image());
+        assert!(!HostArch::Arm64.has_vllm_image());
+    }
+}
@@ -0,0 +1,342 @@
+//! GPU passthrough probes.
+//!
+//! A missing NVIDIA Container Toolkit or the wrong permissions on `/dev/dri`
+//! otherwise only show up as a backend that crashes or silently runs on the
+//! CPU. For each detected GPU type a throwaway container is started from the
+//! backend image with the same device passthrough as a real backend, and
+//! asked to list the GPUs it can see. Failures come with a hint at the usual
+//! cause. Probes run in the background at startup and on request from
+//! `POST /api/admin/system/gpu-probe`.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use bollard::container::LogOutput;
+use bollard::models::{ContainerCreateBody, DeviceRequest, HostConfig};
+use bollard::query_parameters::{
+    CreateContainerOptions, LogsOptions, RemoveContainerOptions, StartContainerOptions,
+    WaitContainerOptions,
+};
+use futures::StreamExt;
+use serde::Serialize;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use super::{llamacpp, vllm, DockerManager};
+
+/// Marks probe containers; like sandboxes they aren't `managed-by` backends.
+const LABEL_PROBE: &str = "sovereign-engine.gpu-probe";
+
+/// Probes run as `nobody` plus the GPU device groups, like backends do.
+const PROBE_UID: u32 = 65534;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Output kept for the report; device listings are short.
+const MAX_DETAIL_CHARS: usize = 2000;
+
+/// Result of probing one GPU type.
+#[derive(Debug, Clone, Serialize)]
+pub struct GpuProbe {
+    /// `vulkan` or `nvidia`, as reported by `detect_gpu`.
+    pub gpu_type: String,
+    pub ok: bool,
+    /// GPUs the container saw, one per line.
+    pub devices: Vec<String>,
+    /// What went wrong: the Docker error or the container's output.
+    pub error: Option<String>,
+    /// The likely fix, when the error is a known misconfiguration.
+    pub hint: Option<String>,
+    pub checked_at: String,
+}
+
+impl GpuProbe {
+    fn failed(gpu_type: &str, error: String) -> Self {
+        Self {
+            gpu_type: gpu_type.to_string(),
+            ok: false,
+            devices: Vec::new(),
+            hint: hint_for(gpu_type, &error),
+            error: Some(truncate(&error)),
+            checked_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+fn truncate(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(MAX_DETAIL_CHARS) {
+        Some((i, _)) => format!("{}…", &text[..i]),
+        None => text.to_string(),
+    }
+}
+
+/// The usual cause of a probe failure, from the Docker error or the output.
+fn hint_for(gpu_type: &str, error: &str) -> Option<String> {
+    let lower = error.to_lowercase();
+    let hint = if lower.contains("could not select device driver") {
+        "The NVIDIA Container Toolkit is not installed or not registered with Docker. \
+         Install nvidia-container-toolkit, run `nvidia-ctk runtime configure --runtime=docker` \
+         and restart Docker."
+    } else if lower.contains("error gathering device information") || lower.contains("no such file")
+    {
+        "A GPU device node is missing. Check that the GPU driver is loaded on the host and that \
+         /dev/dri (and /dev/kfd for AMD) is passed through to the proxy container."
+    } else if lower.contains("permission denied") {
+        "The container can't open the GPU device. Backends run as a random unprivileged user and \
+         get the groups that own /dev/dri and /dev/kfd: check those device nodes are owned by a \
+         group such as `render` or `video` (not root) and are group read-writable."
+    } else if lower.contains("no such image") || lower.contains("image not available") {
+        "The backend image isn't available yet. Wait for the pull to finish (or check the pull \
+         error in the logs) and probe again."
+    } else if gpu_type == "nvidia"
+        && (lower.contains("nvml") || lower.contains("driver/library version mismatch"))
+    {
+        "nvidia-smi can't talk to the driver. A driver/library version mismatch usually clears \
+         after a reboot; otherwise reinstall the driver and the container toolkit."
+    } else if gpu_type == "vulkan" && lower.contains("no gpu") {
+        "The Vulkan loader found no GPU. Check the host driver (Mesa RADV for AMD, the NVIDIA \
+         driver for NVIDIA) and that the render nodes are readable by the device group."
+    } else {
+        return None;
+    };
+    Some(hint.to_string())
+}
+
+/// Vulkan devices listed by `llama-server --list-devices` (`Vulkan0: ...`).
+fn parse_vulkan_devices(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("Vulkan"))
+        .map(str::to_string)
+        .collect()
+}
+
+/// GPUs listed by `nvidia-smi -L` (`GPU 0: ...`).
+fn parse_nvidia_devices(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("GPU "))
+        .map(str::to_string)
+        .collect()
+}
+
+impl DockerManager {
+    /// Probe every detected GPU type and keep the results for
+    /// `/api/admin/system`. Failures are logged with their hint.
+    pub async fn probe_gpus(&self) -> Vec<GpuProbe> {
+        let mut probes = Vec::new();
+        for gpu_type in self.detect_gpu().await {
+            let probe = self.probe_gpu(&gpu_type).await;
+            if probe.ok {
+                info!(gpu = %gpu_type, devices = ?probe.devices, "GPU passthrough probe passed");
+            } else {
+                warn!(
+                    gpu = %gpu_type,
+                    error = probe.error.as_deref().unwrap_or("-"),
+                    hint = probe.hint.as_deref().unwrap_or("-"),
+                    "GPU passthrough probe failed"
+                );
+            }
+            probes.push(probe);
+        }
+        *self.gpu_probes.write().await = probes.clone();
+        probes
+    }
+
+    /// Results of the last probe run.
+    pub async fn gpu_probe_results(&self) -> Vec<GpuProbe> {
+        self.gpu_probes.read().await.clone()
+    }
+
+    async fn probe_gpu(&self, gpu_type: &str) -> GpuProbe {
+        let (image, entrypoint, mut host_config) = match gpu_type {
+            "vulkan" => {
+                let groups = llamacpp::gpu_device_gids();
+                (
+                    llamacpp::LLAMACPP_IMAGE_VULKAN,
+                    vec![
+                        "/app/llama-server".to_string(),
+                        "--list-devices".to_string(),
+                    ],
+                    HostConfig {
+                        devices: Some(llamacpp::vulkan_device_mappings(vec![
+                            "/dev/dri".to_string()
+                        ])),
+                        group_add: (!groups.is_empty()).then_some(groups),
+                        ..Default::default()
+                    },
+                )
+            }
+            "nvidia" => (
+                vllm::VLLM_IMAGE,
+                vec!["nvidia-smi".to_string(), "-L".to_string()],
+                HostConfig {
+                    device_requests: Some(vec![DeviceRequest {
+                        driver: Some("nvidia".to_string()),
+                        count: Some(-1),
+                        capabilities: Some(vec![vec!["gpu".to_string()]]),
+                        ..Default::default()
+                    }]),
+                    ..Default::default()
+                },
+            ),
+            other => return GpuProbe::failed(other, format!("No probe for GPU type '{other}'")),
+        };
+
+        // Wait for the background pull rather than fail on a fresh host
+        super::pull_image(&self.docker, image).await;
+        if self.docker.inspect_image(image).await.is_err() {
+            return GpuProbe::failed(gpu_type, format!("Image not available: {image}"));
+        }
+
+        host_config.network_mode = Some("none".to_string());
+        host_config.cap_drop = Some(vec!["ALL".to_string()]);
+        host_config.security_opt = Some(vec!["no-new-privileges".to_string()]);
+        let container_name = format!("sovereign-gpu-probe-{gpu_type}-{}", Uuid::new_v4());
+        let config = ContainerCreateBody {
+            image: Some(image.to_string()),
+            entrypoint: Some(entrypoint),
+            labels: Some(HashMap::from([(
+                LABEL_PROBE.to_string(),
+                gpu_type.to_string(),
+            )])),
+            user: Some(format!("{PROBE_UID}:{PROBE_UID}")),
+            network_disabled: Some(true),
+            host_config: Some(host_config),
+            ..Default::default()
+        };
+
+        let result = self.run_probe(&container_name, config).await;
+        if let Err(e) = self
+            .docker
+            .remove_container(
+                &container_name,
+                Some(RemoveContainerOptions {
+                    force: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+        {
+            // Not created, or already gone
+            debug!(container = %container_name, error = %e, "Probe container not removed");
+        }
+
+        let (exit_code, output) = match result {
+            Ok(r) => r,
+            Err(e) => return GpuProbe::failed(gpu_type, format!("{e:#}")),
+        };
+        let devices = if gpu_type == "vulkan" {
+            parse_vulkan_devices(&output)
+        } else {
+            parse_nvidia_devices(&output)
+        };
+        match exit_code {
+            Some(0) if !devices.is_empty() => GpuProbe {
+                gpu_type: gpu_type.to_string(),
+                ok: true,
+                devices,
+                error: None,
+                hint: None,
+                checked_at: chrono::Utc::now().to_rfc3339(),
+            },
+            Some(0) => GpuProbe::failed(
+                gpu_type,
+                format!("No GPU visible in the container\n{output}"),
+            ),
+            Some(code) => GpuProbe::failed(gpu_type, format!("Probe exited with {code}\n{output}")),
+            None => GpuProbe::failed(gpu_type, "Probe timed out".to_string()),
+        }
+    }
+
+    /// Create and run a probe container; returns its exit code (`None` on
+    /// timeout) and combined output.
+    async fn run_probe(
+        &self,
+        container_name: &str,
+        config: ContainerCreateBody,
+    ) -> Result<(Option<i64>, String)> {
+        self.docker
+            .create_container(
+                Some(CreateContainerOptions {
+                    name: Some(container_name.to_string()),
+                    ..Default::default()
+                }),
+                config,
+            )
+            .await
+            .context("Failed to create probe container")?;
+        self.docker
+            .start_container(container_name, None::<StartContainerOptions>)
+            .await
+            .context("Failed to start probe container")?;
+
+        let mut wait = Box::pin(
+            self.docker
+                .wait_container(container_name, None::<WaitContainerOptions>),
+        );
+        let exit_code = match tokio::time::timeout(PROBE_TIMEOUT, wait.next()).await {
+            Ok(Some(Ok(status))) => Some(status.status_code),
+            Ok(Some(Err(bollard::errors::Error::DockerContainerWaitError { code, .. }))) => {
+                Some(code)
+            }
+            Ok(Some(Err(e))) => return Err(e).context("Failed waiting for probe container"),
+            Ok(None) | Err(_) => None,
+        };
+
+        let mut output = Vec::new();
+        let mut logs = Box::pin(self.docker.logs(
+            container_name,
+            Some(LogsOptions {
+                stdout: true,
+                stderr: true,
+                ..Default::default()
+            }),
+        ));
+        while let Some(Ok(chunk)) = logs.next().await {
+            if let LogOutput::StdOut { message } | LogOutput::StdErr { message } = chunk {
+                output.extend_from_slice(&message);
+            }
+        }
+        Ok((exit_code, String::from_utf8_lossy(&output).into_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_device_listings() {
+        let vulkan = "ggml_vulkan: Found 1 Vulkan devices:\nAvailable devices:\n  Vulkan0: AMD Radeon RX 7900 XTX (RADV NAVI31) (24560 MiB, 24000 MiB free)\n";
+        assert_eq!(
+            parse_vulkan_devices(vulkan),
+            ["Vulkan0: AMD Radeon RX 7900 XTX (RADV NAVI31) (24560 MiB, 24000 MiB free)"]
+        );
+        assert!(parse_vulkan_devices("Available devices:\n").is_empty());
+
+        let nvidia = "GPU 0: NVIDIA L4 (UUID: GPU-1234)\nGPU 1: NVIDIA L4 (UUID: GPU-5678)\n";
+        assert_eq!(parse_nvidia_devices(nvidia).len(), 2);
+    }
+
+    #[test]
+    fn hints_for_common_misconfigurations() {
+        let toolkit = hint_for(
+            "nvidia",
+            "Docker responded with status code 500: could not select device driver \"nvidia\" with capabilities: [[gpu]]",
+        );
+        assert!(toolkit.unwrap().contains("nvidia-container-toolkit"));
+
+        let permissions = hint_for(
+            "vulkan",
+            "MESA: error: Failed to open /dev/dri/renderD128: Permission denied",
+        );
+        assert!(permissions.unwrap().contains("render"));
+
+        assert!(hint_for("vulkan", "something else entirely").is_none());
+    }
+}
@@ -0,0 +1,180 @@
+//! CPU, memory and process limits for backend containers.
+//!
+//! Limits come from the start request or the model's launch profile and are
+//! applied through the container's `HostConfig`. They are also written to the
+//! container's labels, so listings show what a running container was started
+//! with.
+
+use std::collections::HashMap;
+
+use bollard::models::HostConfig;
+use serde::{Deserialize, Serialize};
+
+const LABEL_CPU_LIMIT: &str = "sovereign-engine.cpu-limit";
+const LABEL_MEMORY_LIMIT_MB: &str = "sovereign-engine.memory-limit-mb";
+const LABEL_PIDS_LIMIT: &str = "sovereign-engine.pids-limit";
+
+/// Resource limits for one container; `None` leaves that resource
+/// unconstrained.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceLimits {
+    /// CPUs the container may use, e.g. `2.5`.
+    #[serde(default)]
+    pub cpu_limit: Option<f64>,
+    /// Memory in MB. Swap is not allowed beyond it.
+    #[serde(default)]
+    pub memory_limit_mb: Option<u32>,
+    /// Maximum number of processes and threads.
+    #[serde(default)]
+    pub pids_limit: Option<u32>,
+}
+
+impl ResourceLimits {
+    /// Each limit from `self`, or from `fallback` where `self` has none.
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            cpu_limit: self.cpu_limit.or(fallback.cpu_limit),
+            memory_limit_mb: self.memory_limit_mb.or(fallback.memory_limit_mb),
+            pids_limit: self.pids_limit.or(fallback.pids_limit),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.cpu_limit.is_some_and(|c| !c.is_finite() || c < 0.01) {
+            return Err("cpu_limit must be at least 0.01");
+        }
+        // Docker refuses less than 6 MB; a backend needs far more anyway
+        if self.memory_limit_mb.is_some_and(|m| m < 64) {
+            return Err("memory_limit_mb must be at least 64");
+        }
+        if self.pids_limit == Some(0) {
+            return Err("pids_limit must be positive");
+        }
+        Ok(())
+    }
+
+    /// Set the limits on a container's host config.
+    pub fn apply(&self, host_config: &mut HostConfig) {
+        if let Some(cpus) = self.cpu_limit {
+            host_config.nano_cpus = Some((cpus * 1e9) as i64);
+        }
+        if let Some(mb) = self.memory_limit_mb {
+            let memory = i64::from(mb) * 1024 * 1024;
+            host_config.memory = Some(memory);
+            // Same as memory: no swap
+            host_config.memory_swap = Some(memory);
+        }
+        if let Some(pids) = self.pids_limit {
+            host_config.pids_limit = Some(i64::from(pids));
+        }
+    }
+
+    /// Record the limits in a container's labels.
+    pub fn add_labels(&self, labels: &mut HashMap<String, String>) {
+        if let Some(cpus) = self.cpu_limit {
+            labels.insert(LABEL_CPU_LIMIT.to_string(), cpus.to_string());
+        }
+        if let Some(mb) = self.memory_limit_mb {
+            labels.insert(LABEL_MEMORY_LIMIT_MB.to_string(), mb.to_string());
+        }
+        if let Some(pids) = self.pids_limit {
+            labels.insert(LABEL_PIDS_LIMIT.to_string(), pids.to_string());
+        }
+    }
+
+    /// The limits a container was started with, from its labels.
+    pub fn from_labels(labels: &HashMap<String, String>) -> Self {
+        Self {
+            cpu_limit: labels.get(LABEL_CPU_LIMIT).and_then(|v| v.parse().ok()),
+            memory_limit_mb: labels
+                .get(LABEL_MEMORY_LIMIT_MB)
+                .and_then(|v| v.parse().ok()),
+            pids_limit: labels.get(LABEL_PIDS_LIMIT).and_then(|v| v.parse().ok()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_limits_to_host_config() {
+        let limits = ResourceLimits {
+            cpu_limit: Some(2.5),
+            memory_limit_mb: Some(8192),
+            pids_limit: Some(512),
+        };
+        let mut hc = HostConfig::default();
+        limits.apply(&mut hc);
+        assert_eq!(hc.nano_cpus, Some(2_500_000_000));
+        assert_eq!(hc.memory, Some(8192 * 1024 * 1024));
+        assert_eq!(hc.memory_swap, hc.memory);
+        assert_eq!(hc.pids_limit, Some(512));
+
+        let mut unlimited = HostConfig::default();
+        ResourceLimits::default().apply(&mut unlimited);
+        assert_eq!(unlimited.nano_cpus, None);
+        assert_eq!(unlimited.memory, None);
+    }
+
+    #[test]
+    fn round_trips_through_labels() {
+        let limits = ResourceLimits {
+            cpu_limit: Some(4.0),
+            memory_limit_mb: None,
+            pids_limit: Some(256),
+        };
+        let mut labels = HashMap::new();
+        limits.add_labels(&mut labels);
+        assert_eq!(labels.len(), 2);
+        assert_eq!(ResourceLimits::from_labels(&labels), limits);
+    }
+
+    #[test]
+    fn request_limits_override_profile() {
+        let request = ResourceLimits {
+            cpu_limit: Some(1.0),
+            ..Default::default()
+        };
+        let profile = ResourceLimits {
+            cpu_limit: Some(8.0),
+            memory_limit_mb: Some(4096),
+            pids_limit: None,
+        };
+        let merged = request.or(profile);
+        assert_eq!(merged.cpu_limit, Some(1.0));
+        assert_eq!(merged.memory_limit_mb, Some(4096));
+        assert_eq!(merged.pids_limit, None);
+    }
+
+    #[test]
+    fn rejects_unusable_limits() {
+        let ok = ResourceLimits {
+            cpu_limit: Some(0.5),
+            memory_limit_mb: Some(1024),
+            pids_limit: Some(100),
+        };
+        assert!(ok.validate().is_ok());
+        for bad in [
+            ResourceLimits {
+                cpu_limit: Some(0.0),
+                ..ok
+            },
+            ResourceLimits {
+                cpu_limit: Some(f64::NAN),
+                ..ok
+            },
+            ResourceLimits {
+                memory_limit_mb: Some(16),
+                ..ok
+            },
+            ResourceLimits {
+                pids_limit: Some(0),
+                ..ok
+            },
+        ] {
+            assert!(bad.validate().is_err(), "{bad:?}");
+        }
+    }
+}
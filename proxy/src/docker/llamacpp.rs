@@ -6,11 +6,14 @@ use bollard::models::{
     NetworkingConfig,
 };
 use bollard::query_parameters::{
-    CreateContainerOptions, RemoveContainerOptions, StartContainerOptions, StopContainerOptions,
+    CreateContainerOptions, RemoveContainerOptions, StartContainerOptions,
 };
 use tracing::{error, info, warn};
 
-use super::{DockerManager, LABEL_BACKEND, LABEL_MANAGED_BY, LABEL_MANAGED_VALUE, LABEL_MODEL_ID};
+use super::limits::ResourceLimits;
+use super::{
+    DockerManager, StopMode, LABEL_BACKEND, LABEL_MANAGED_BY, LABEL_MANAGED_VALUE, LABEL_MODEL_ID,
+};
 
 pub(crate) const LLAMACPP_IMAGE_CPU: &str = "ghcr.io/ggml-org/llama.cpp:server";
 pub(crate) const LLAMACPP_IMAGE_VULKAN: &str = "ghcr.io/ggml-org/llama.cpp:server-vulkan";
@@ -53,11 +56,21 @@ pub struct LlamacppConfig {
     pub context_size: u32,
     /// Number of parallel sequences / slots (default 1)
     pub parallel: u32,
+    /// Serve the embeddings endpoint (`--embeddings`) for an embedding model
+    pub embeddings: bool,
+    /// GPU indices the container may use (empty = all). With Vulkan only
+    /// the matching `/dev/dri` render nodes are passed through.
+    pub devices: Vec<u32>,
     pub extra_args: Vec<String>,
     /// Container UID — allocated by DockerManager::allocate_uid()
     pub uid: u32,
     /// API key for backend authentication — passed as --api-key to llama-server
     pub api_key: String,
+    /// Container name; defaults to [`super::container_name`]. Set when
+    /// starting the replacement in a blue/green swap.
+    pub container_name: Option<String>,
+    /// CPU, memory and process limits (none by default)
+    pub limits: ResourceLimits,
 }
 
 impl Default for LlamacppConfig {
@@ -69,9 +82,13 @@ impl Default for LlamacppConfig {
             gpu_layers: 99,
             context_size: 4096,
             parallel: 1,
+            embeddings: false,
+            devices: Vec::new(),
             extra_args: Vec::new(),
             uid: 10000,
             api_key: String::new(),
+            container_name: None,
+            limits: ResourceLimits::default(),
         }
     }
 }
@@ -79,7 +96,10 @@ impl Default for LlamacppConfig {
 impl DockerManager {
     /// Start a llama.cpp container for the given model.
     pub async fn start_llamacpp(&self, config: &LlamacppConfig) -> Result<String> {
-        let container_name = format!("sovereign-llamacpp-{}", config.model_id);
+        let container_name = config
+            .container_name
+            .clone()
+            .unwrap_or_else(|| super::container_name("llamacpp", &config.model_id));
 
         // Check if container already exists
         if let Ok(info) = self.docker.inspect_container(&container_name, None).await {
@@ -129,10 +149,26 @@ impl DockerManager {
             cmd.push(config.parallel.to_string());
         }
 
+        if config.embeddings {
+            cmd.push("--embeddings".to_string());
+        }
+
         // Add API key for backend authentication
         cmd.push("--api-key".to_string());
         cmd.push(config.api_key.clone());
 
+        let mut mounts = vec![Mount {
+            target: Some("/models".to_string()),
+            source: Some(self.model_path.clone()),
+            typ: Some(MountTypeEnum::BIND),
+            read_only: Some(true),
+            ..Default::default()
+        }];
+        if let Some(tls) = &self.backend_tls {
+            mounts.push(tls.issue(&container_name).await?);
+            cmd.extend(super::tls::llamacpp_args());
+        }
+
         cmd.extend(config.extra_args.clone());
 
         let uid = config.uid;
@@ -146,36 +182,25 @@ impl DockerManager {
         );
         labels.insert(LABEL_MODEL_ID.to_string(), config.model_id.clone());
         labels.insert(LABEL_BACKEND.to_string(), "llamacpp".to_string());
+        config.limits.add_labels(&mut labels);
 
         let mut host_config = HostConfig {
             // No port bindings — llama.cpp is only reachable via the internal network
-            mounts: Some(vec![Mount {
-                target: Some("/models".to_string()),
-                source: Some(self.model_path.clone()),
-                typ: Some(MountTypeEnum::BIND),
-                read_only: Some(true),
-                ..Default::default()
-            }]),
+            mounts: Some(mounts),
             ..Default::default()
         };
 
         // GPU configuration
         match config.gpu_type {
             GpuType::Vulkan => {
-                // Vulkan: expose /dev/dri (and /dev/kfd if present for AMD).
-                let mut devices = vec![DeviceMapping {
-                    path_on_host: Some("/dev/dri".to_string()),
-                    path_in_container: Some("/dev/dri".to_string()),
-                    cgroup_permissions: Some("rw".to_string()),
-                }];
-                if std::path::Path::new("/dev/kfd").exists() {
-                    devices.push(DeviceMapping {
-                        path_on_host: Some("/dev/kfd".to_string()),
-                        path_in_container: Some("/dev/kfd".to_string()),
-                        cgroup_permissions: Some("rw".to_string()),
-                    });
-                }
-                host_config.devices = Some(devices);
+                // Vulkan: expose /dev/dri, or only the pinned GPUs' render
+                // nodes (and /dev/kfd if present for AMD).
+                let dri_paths = if config.devices.is_empty() {
+                    vec!["/dev/dri".to_string()]
+                } else {
+                    select_render_nodes(&render_nodes(), &config.devices)?
+                };
+                host_config.devices = Some(vulkan_device_mappings(dri_paths));
                 // Discover the GIDs that own the GPU device files and forward
                 // them to the backend container so its non-root user can access
                 // /dev/dri and /dev/kfd.
@@ -188,6 +213,7 @@ impl DockerManager {
                 // CPU-only: no GPU config needed
             }
         }
+        config.limits.apply(&mut host_config);
 
         // Attach to the internal network so the proxy can reach this container by name
         let mut endpoints_config = HashMap::new();
@@ -213,6 +239,7 @@ impl DockerManager {
             image = %image,
             uid = uid,
             gpu = ?config.gpu_type,
+            devices = ?config.devices,
             "Creating llama.cpp container"
         );
 
@@ -265,58 +292,15 @@ impl DockerManager {
     }
 
     /// Stop a llama.cpp container by model ID.
-    pub async fn stop_llamacpp(&self, model_id: &str) -> Result<()> {
-        let container_name = format!("sovereign-llamacpp-{}", model_id);
-
-        // Check container state first — only attempt stop if actually running
-        let is_running = match self.docker.inspect_container(&container_name, None).await {
-            Ok(info) => {
-                let status = info.state.as_ref().and_then(|s| s.status);
-                info!(model = %model_id, container = %container_name, state = ?status, "Inspected container for stop");
-                info.state.as_ref().and_then(|s| s.running).unwrap_or(false)
-            }
-            Err(e) => {
-                warn!(model = %model_id, container = %container_name, error = %e, "Container not found during stop");
-                return Ok(()); // Container doesn't exist — nothing to stop
-            }
-        };
-
-        if is_running {
-            self.docker
-                .stop_container(
-                    &container_name,
-                    Some(StopContainerOptions {
-                        t: Some(30),
-                        ..Default::default()
-                    }),
-                )
-                .await
-                .context("Failed to stop llama.cpp container")?;
-        }
-
-        self.docker
-            .remove_container(
-                &container_name,
-                Some(RemoveContainerOptions {
-                    force: true,
-                    ..Default::default()
-                }),
-            )
-            .await
-            .context("Failed to remove llama.cpp container")?;
-
-        info!(model = %model_id, container = %container_name, "llama.cpp container stopped and removed");
-        Ok(())
+    pub async fn stop_llamacpp(&self, model_id: &str, mode: StopMode) -> Result<()> {
+        let container_name = super::container_name("llamacpp", model_id);
+        self.stop_and_remove(&container_name, model_id, mode).await
     }
 
     /// Check if a llama.cpp container is healthy and responding.
     pub async fn check_llamacpp_health(&self, model_id: &str) -> Result<bool> {
-        let container_name = format!("sovereign-llamacpp-{}", model_id);
-        let url = format!(
-            "http://{}:{}/health",
-            container_name, LLAMACPP_INTERNAL_PORT
-        );
-        match reqwest::get(&url).await {
+        let url = format!("{}/health", self.llamacpp_base_url(model_id));
+        match self.health_client()?.get(&url).send().await {
             Ok(resp) => Ok(resp.status().is_success()),
             Err(_) => Ok(false),
         }
@@ -324,9 +308,75 @@ impl DockerManager {
 
     /// Get the internal URL for a llama.cpp container on the isolated network.
     pub fn llamacpp_base_url(&self, model_id: &str) -> String {
-        let container_name = format!("sovereign-llamacpp-{}", model_id);
-        format!("http://{}:{}", container_name, LLAMACPP_INTERNAL_PORT)
+        self.llamacpp_url(&super::container_name("llamacpp", model_id))
+    }
+
+    /// Internal URL of the named llama.cpp container.
+    pub(crate) fn llamacpp_url(&self, container_name: &str) -> String {
+        format!(
+            "{}://{}:{}",
+            self.backend_scheme(),
+            container_name,
+            LLAMACPP_INTERNAL_PORT
+        )
+    }
+}
+
+/// Render nodes under /dev/dri, ordered by minor number so index N is the
+/// Nth GPU, matching `GpuStats::device_index`.
+fn render_nodes() -> Vec<String> {
+    let mut minors: Vec<u32> = std::fs::read_dir("/dev/dri")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|e| {
+                    e.file_name()
+                        .to_str()?
+                        .strip_prefix("renderD")?
+                        .parse()
+                        .ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    minors.sort_unstable();
+    minors
+        .into_iter()
+        .map(|m| format!("/dev/dri/renderD{m}"))
+        .collect()
+}
+
+/// The render nodes for the GPU indices in `devices`.
+fn select_render_nodes(nodes: &[String], devices: &[u32]) -> Result<Vec<String>> {
+    devices
+        .iter()
+        .map(|&d| {
+            nodes.get(d as usize).cloned().with_context(|| {
+                format!("GPU {d} not found ({} render nodes present)", nodes.len())
+            })
+        })
+        .collect()
+}
+
+/// Device mappings for a Vulkan container: the given `/dev/dri` paths, plus
+/// `/dev/kfd` if present (AMD).
+pub(super) fn vulkan_device_mappings(dri_paths: Vec<String>) -> Vec<DeviceMapping> {
+    let mut devices: Vec<DeviceMapping> = dri_paths
+        .into_iter()
+        .map(|path| DeviceMapping {
+            path_on_host: Some(path.clone()),
+            path_in_container: Some(path),
+            cgroup_permissions: Some("rw".to_string()),
+        })
+        .collect();
+    if std::path::Path::new("/dev/kfd").exists() {
+        devices.push(DeviceMapping {
+            path_on_host: Some("/dev/kfd".to_string()),
+            path_in_container: Some("/dev/kfd".to_string()),
+            cgroup_permissions: Some("rw".to_string()),
+        });
     }
+    devices
 }
 
 /// Discover the GIDs that own GPU device files (/dev/dri/*, /dev/kfd).
@@ -335,7 +385,7 @@ impl DockerManager {
 /// non-root container user can access the GPU devices. By stat-ing the
 /// actual device files we pick up the correct host GIDs regardless of
 /// group naming or GID changes across reboots.
-fn gpu_device_gids() -> Vec<String> {
+pub(super) fn gpu_device_gids() -> Vec<String> {
     use std::collections::BTreeSet;
     use std::os::unix::fs::MetadataExt;
 
@@ -420,6 +470,8 @@ mod tests {
         assert_eq!(cfg.gpu_layers, 99);
         assert_eq!(cfg.context_size, 4096);
         assert_eq!(cfg.parallel, 1);
+        assert!(!cfg.embeddings);
+        assert!(cfg.devices.is_empty());
         assert_eq!(cfg.uid, 10000);
         assert!(cfg.model_id.is_empty());
         assert!(cfg.gguf_path.is_empty());
@@ -428,6 +480,21 @@ mod tests {
         assert!(matches!(cfg.gpu_type, GpuType::None));
     }
 
+    // -- select_render_nodes -------------------------------------------------
+
+    #[test]
+    fn render_nodes_selected_by_gpu_index() {
+        let nodes = vec![
+            "/dev/dri/renderD128".to_string(),
+            "/dev/dri/renderD129".to_string(),
+        ];
+        assert_eq!(
+            select_render_nodes(&nodes, &[1]).unwrap(),
+            ["/dev/dri/renderD129"]
+        );
+        assert!(select_render_nodes(&nodes, &[2]).is_err());
+    }
+
     // -- DockerManager::llamacpp_base_url ------------------------------------
     // This is a pure function; we can test its output format without Docker.
 
@@ -1,14 +1,25 @@
+pub mod arch;
+pub mod gpu_probe;
+pub mod limits;
 pub mod llamacpp;
 pub mod runtime_overrides;
+pub mod sandbox;
+pub mod tls;
+pub mod vllm;
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
 use anyhow::{Context, Result};
+use bollard::container::LogOutput;
 use bollard::models::NetworkCreateRequest;
-use bollard::query_parameters::{CreateImageOptions, ListContainersOptions};
+use bollard::query_parameters::{
+    CreateImageOptions, ListContainersOptions, LogsOptions, RemoveContainerOptions,
+    StopContainerOptions,
+};
 use bollard::Docker;
-use futures::StreamExt;
+use futures::{Stream, StreamExt};
 use rand::RngExt;
 use tracing::{info, warn};
 
@@ -28,16 +39,121 @@ pub struct GpuStats {
     pub utilization_percent: Option<u64>,
 }
 
+/// VRAM held by one backend container.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContainerVram {
+    pub total_mb: u64,
+    /// Share of `total_mb` per GPU index, for containers pinned to specific
+    /// GPUs. Empty when the container may use every GPU.
+    pub per_device_mb: BTreeMap<u32, u64>,
+}
+
+/// Split `total_mb` across the GPUs a container is pinned to. Backends shard
+/// a model evenly over their devices, and the per-process stats don't say
+/// which GPU holds what, so an even split is the best estimate.
+pub fn attribute_vram(total_mb: u64, devices: &[u32]) -> BTreeMap<u32, u64> {
+    let mut per_device = BTreeMap::new();
+    if devices.is_empty() {
+        return per_device;
+    }
+    let share = total_mb / devices.len() as u64;
+    let remainder = total_mb % devices.len() as u64;
+    for (i, &device) in devices.iter().enumerate() {
+        let extra = if i == 0 { remainder } else { 0 };
+        per_device.insert(device, share + extra);
+    }
+    per_device
+}
+
+/// How a backend container is stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StopMode {
+    /// SIGTERM, then SIGKILL if the container hasn't exited after 30s.
+    Graceful,
+    /// SIGKILL immediately.
+    Force,
+}
+
+impl StopMode {
+    pub fn from_force(force: bool) -> Self {
+        if force {
+            StopMode::Force
+        } else {
+            StopMode::Graceful
+        }
+    }
+
+    /// Seconds Docker waits between SIGTERM and SIGKILL.
+    fn kill_timeout_secs(self) -> i32 {
+        match self {
+            StopMode::Graceful => 30,
+            StopMode::Force => 0,
+        }
+    }
+}
+
+/// Which output of a container a log chunk came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+impl LogStream {
+    pub fn name(self) -> &'static str {
+        match self {
+            LogStream::Stdout => "stdout",
+            LogStream::Stderr => "stderr",
+        }
+    }
+}
+
+/// A log chunk as text, without its trailing newline.
+fn log_text(message: &[u8]) -> String {
+    String::from_utf8_lossy(message)
+        .trim_end_matches(['\r', '\n'])
+        .to_string()
+}
+
+/// Name of the container that serves `model_id` on `backend_type`.
+///
+/// After a blue/green replace the model may be served by the `-green` twin
+/// instead; `container_secrets.container_name` records which.
+pub fn container_name(backend_type: &str, model_id: &str) -> String {
+    format!("sovereign-{backend_type}-{model_id}")
+}
+
+/// Name for a container replacing `current`: whichever of the default name
+/// and its `-green` twin `current` isn't.
+pub fn replacement_container_name(backend_type: &str, model_id: &str, current: &str) -> String {
+    let blue = container_name(backend_type, model_id);
+    if current == blue {
+        format!("{blue}-green")
+    } else {
+        blue
+    }
+}
+
 const LABEL_MANAGED_BY: &str = "managed-by";
 const LABEL_MANAGED_VALUE: &str = "sovereign-engine";
 const LABEL_MODEL_ID: &str = "sovereign-engine.model-id";
 pub(crate) const LABEL_BACKEND: &str = "sovereign-engine.backend";
 
+/// Values accepted in `models.backend_type`.
+pub const BACKEND_TYPES: &[&str] = &["llamacpp", "vllm"];
+
 #[derive(Debug, Clone)]
 pub struct DockerManager {
     pub docker: Docker,
     pub model_path: String,
     pub backend_network: String,
+    /// Set when backend traffic uses mutual TLS (`BACKEND_TLS`).
+    pub backend_tls: Option<tls::BackendTls>,
+    /// Architecture of the Docker host, which decides the backend images.
+    pub arch: arch::HostArch,
+    /// Results of the last GPU passthrough probe run.
+    pub gpu_probes: std::sync::Arc<tokio::sync::RwLock<Vec<gpu_probe::GpuProbe>>>,
 }
 
 impl DockerManager {
@@ -51,6 +167,9 @@ impl DockerManager {
             docker,
             model_path: "/tmp/test-models".to_string(),
             backend_network: "test-network".to_string(),
+            backend_tls: None,
+            arch: arch::HostArch::native(),
+            gpu_probes: Default::default(),
         }
     }
 
@@ -69,6 +188,26 @@ impl DockerManager {
             "Connected to Docker"
         );
 
+        // The daemon's architecture, not ours: the proxy may run emulated.
+        let host_arch = match docker.info().await {
+            Ok(info) => info
+                .architecture
+                .as_deref()
+                .map(arch::HostArch::parse)
+                .unwrap_or_else(arch::HostArch::native),
+            Err(e) => {
+                warn!(error = %e, "Failed to query Docker host info, assuming native architecture");
+                arch::HostArch::native()
+            }
+        };
+        info!(arch = host_arch.as_str(), "Detected host architecture");
+        if !host_arch.has_vulkan_image() {
+            info!(
+                arch = host_arch.as_str(),
+                "No Vulkan or vLLM images for this architecture, only the llama.cpp CPU backend is available"
+            );
+        }
+
         // Ensure the internal backend network exists
         let network_name = &config.backend_network;
         match docker.inspect_network(network_name, None).await {
@@ -89,10 +228,20 @@ impl DockerManager {
             }
         }
 
+        let backend_tls = tls::BackendTls::from_config(config);
+        if let Some(tls) = &backend_tls {
+            tls.ensure_ca()
+                .await
+                .context("Failed to set up backend TLS")?;
+        }
+
         Ok(Self {
             docker,
             model_path: config.model_host_path.clone(),
             backend_network: config.backend_network.clone(),
+            backend_tls,
+            arch: host_arch,
+            gpu_probes: Default::default(),
         })
     }
 
@@ -155,37 +304,225 @@ impl DockerManager {
 
     /// Get the internal base URL for a backend container on the isolated network.
     pub fn backend_base_url(&self, model_id: &str, backend_type: &str) -> String {
+        self.backend_url(&container_name(backend_type, model_id), backend_type)
+    }
+
+    /// Internal base URL of the named backend container.
+    pub fn backend_url(&self, container_name: &str, backend_type: &str) -> String {
         match backend_type {
-            "llamacpp" => self.llamacpp_base_url(model_id),
+            "llamacpp" => self.llamacpp_url(container_name),
+            "vllm" => self.vllm_url(container_name),
             other => panic!("Unknown backend type: {other}"),
         }
     }
 
+    /// Stop and remove the named backend container. A missing container is
+    /// not an error.
+    pub async fn stop_named(
+        &self,
+        container_name: &str,
+        model_id: &str,
+        mode: StopMode,
+    ) -> Result<()> {
+        self.stop_and_remove(container_name, model_id, mode).await
+    }
+
     /// Stop a backend container by model ID.
-    pub async fn stop_backend(&self, model_id: &str, backend_type: &str) -> Result<()> {
+    pub async fn stop_backend(
+        &self,
+        model_id: &str,
+        backend_type: &str,
+        mode: StopMode,
+    ) -> Result<()> {
         match backend_type {
-            "llamacpp" => self.stop_llamacpp(model_id).await,
+            "llamacpp" => self.stop_llamacpp(model_id, mode).await,
+            "vllm" => self.stop_vllm(model_id, mode).await,
             other => anyhow::bail!("Unknown backend type: {other}"),
         }
     }
 
+    /// URL scheme backends are served on.
+    pub(crate) fn backend_scheme(&self) -> &'static str {
+        if self.backend_tls.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+
+    /// Client for health checks, presenting the proxy's certificate and
+    /// trusting only the backend CA when backend TLS is on.
+    pub(crate) fn health_client(&self) -> Result<reqwest::Client> {
+        let builder = reqwest::Client::builder().timeout(std::time::Duration::from_secs(5));
+        let builder = match &self.backend_tls {
+            Some(tls) => {
+                let (ca, identity) = tls.client_material()?;
+                builder
+                    .use_rustls_tls()
+                    .tls_built_in_root_certs(false)
+                    .add_root_certificate(ca)
+                    .identity(identity)
+            }
+            None => builder,
+        };
+        builder
+            .build()
+            .context("Failed to build health check client")
+    }
+
+    /// Check if the named backend container is healthy and responding.
+    ///
+    /// vLLM's `/health` answers 200 only once the model is loaded.
+    pub async fn check_health_at(&self, container_name: &str, backend_type: &str) -> Result<bool> {
+        let url = format!("{}/health", self.backend_url(container_name, backend_type));
+        match self.health_client()?.get(&url).send().await {
+            Ok(resp) => Ok(resp.status().is_success()),
+            Err(_) => Ok(false),
+        }
+    }
+
     /// Check if a backend container is healthy and responding.
     pub async fn check_backend_health(&self, model_id: &str, backend_type: &str) -> Result<bool> {
         match backend_type {
             "llamacpp" => self.check_llamacpp_health(model_id).await,
+            "vllm" => self.check_vllm_health(model_id).await,
             other => anyhow::bail!("Unknown backend type: {other}"),
         }
     }
 
+    /// Why the named container is no longer running — OOM-killed, exited or
+    /// gone — or None while it runs.
+    pub async fn container_exit_reason(&self, container_name: &str) -> Result<Option<String>> {
+        let info = match self.docker.inspect_container(container_name, None).await {
+            Ok(info) => info,
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => return Ok(Some("container missing".to_string())),
+            Err(e) => return Err(e).with_context(|| format!("Failed to inspect {container_name}")),
+        };
+        let state = info.state.unwrap_or_default();
+        if state.running.unwrap_or(false) {
+            return Ok(None);
+        }
+        Ok(Some(if state.oom_killed.unwrap_or(false) {
+            "OOM-killed".to_string()
+        } else {
+            format!("exited with code {}", state.exit_code.unwrap_or(-1))
+        }))
+    }
+
+    /// A container's stdout and stderr, or `None` if there is no such
+    /// container. `tail` limits the output to the last that many lines
+    /// (`None` for all of it) and `since` to lines written after that Unix
+    /// time. With `follow` the stream stays open for new output until the
+    /// container stops.
+    pub async fn container_logs(
+        &self,
+        container_name: &str,
+        follow: bool,
+        tail: Option<u32>,
+        since: Option<i64>,
+    ) -> Result<Option<impl Stream<Item = Result<(LogStream, String)>> + 'static>> {
+        match self.docker.inspect_container(container_name, None).await {
+            Ok(_) => {}
+            Err(bollard::errors::Error::DockerResponseServerError {
+                status_code: 404, ..
+            }) => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to inspect {container_name}")),
+        }
+        let options = LogsOptions {
+            follow,
+            stdout: true,
+            stderr: true,
+            since: since.map_or(0, |t| t.clamp(0, i32::MAX as i64) as i32),
+            tail: tail.map_or_else(|| "all".to_string(), |n| n.to_string()),
+            ..Default::default()
+        };
+        let name = container_name.to_string();
+        let logs = self.docker.logs(container_name, Some(options));
+        Ok(Some(logs.filter_map(move |chunk| {
+            let line = match chunk {
+                // Backends run without a TTY; a TTY container's output
+                // arrives as console output
+                Ok(LogOutput::StdOut { message } | LogOutput::Console { message }) => {
+                    Some(Ok((LogStream::Stdout, log_text(&message))))
+                }
+                Ok(LogOutput::StdErr { message }) => {
+                    Some(Ok((LogStream::Stderr, log_text(&message))))
+                }
+                Ok(LogOutput::StdIn { .. }) => None,
+                Err(e) => Some(Err(
+                    anyhow::Error::new(e).context(format!("Failed to read logs of {name}"))
+                )),
+            };
+            std::future::ready(line)
+        })))
+    }
+
+    /// Stop (if running) and remove a backend container. A missing container
+    /// counts as already stopped.
+    async fn stop_and_remove(
+        &self,
+        container_name: &str,
+        model_id: &str,
+        mode: StopMode,
+    ) -> Result<()> {
+        // Check container state first — only attempt stop if actually running
+        let is_running = match self.docker.inspect_container(container_name, None).await {
+            Ok(info) => {
+                let status = info.state.as_ref().and_then(|s| s.status);
+                info!(model = %model_id, container = %container_name, state = ?status, "Inspected container for stop");
+                info.state.as_ref().and_then(|s| s.running).unwrap_or(false)
+            }
+            Err(e) => {
+                warn!(model = %model_id, container = %container_name, error = %e, "Container not found during stop");
+                return Ok(()); // Container doesn't exist — nothing to stop
+            }
+        };
+
+        if is_running {
+            self.docker
+                .stop_container(
+                    container_name,
+                    Some(StopContainerOptions {
+                        t: Some(mode.kill_timeout_secs()),
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .with_context(|| format!("Failed to stop container {container_name}"))?;
+        }
+
+        self.docker
+            .remove_container(
+                container_name,
+                Some(RemoveContainerOptions {
+                    force: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .with_context(|| format!("Failed to remove container {container_name}"))?;
+
+        info!(model = %model_id, container = %container_name, mode = ?mode, "Backend container stopped and removed");
+        Ok(())
+    }
+
     /// Detect available GPU types by checking Docker runtime capabilities and device nodes.
+    /// GPU types without a backend image for the host architecture are left out.
     pub async fn detect_gpu(&self) -> Vec<String> {
         let mut gpus = Vec::new();
 
         // Vulkan works on any GPU with DRI support (AMD and NVIDIA)
-        if std::path::Path::new("/dev/dri").exists() {
+        if self.arch.has_vulkan_image() && std::path::Path::new("/dev/dri").exists() {
             gpus.push("vulkan".to_string());
         }
 
+        // NVIDIA driver device nodes — required for vLLM
+        if self.arch.has_vllm_image() && std::path::Path::new("/dev/nvidiactl").exists() {
+            gpus.push("nvidia".to_string());
+        }
+
         gpus
     }
 
@@ -203,6 +540,10 @@ impl DockerManager {
             images.push(llamacpp::LLAMACPP_IMAGE_VULKAN);
         }
 
+        if gpus.contains(&"nvidia".to_string()) {
+            images.push(vllm::VLLM_IMAGE);
+        }
+
         info!(images = ?images, "Pulling backend images in background");
 
         for image in images {
@@ -213,9 +554,22 @@ impl DockerManager {
         }
     }
 
+    /// Pull the code-execution sandbox image in the background.
+    pub fn pull_sandbox_image(&self, image: &str) {
+        let docker = self.docker.clone();
+        let image = image.to_string();
+        tokio::spawn(async move {
+            pull_image(&docker, &image).await;
+        });
+    }
+
     /// Determine which backends are available based on detected GPUs.
     pub async fn available_backends(&self) -> Vec<String> {
-        vec!["llamacpp".to_string()]
+        let mut backends = vec!["llamacpp".to_string()];
+        if self.detect_gpu().await.contains(&"nvidia".to_string()) {
+            backends.push("vllm".to_string());
+        }
+        backends
     }
 
     /// Collect GPU stats from all detected GPUs (NVIDIA + AMD).
@@ -229,6 +583,9 @@ impl DockerManager {
 
     /// Query all NVIDIA GPUs via nvidia-smi. Returns one GpuStats per GPU.
     async fn gpu_stats_nvidia() -> Vec<GpuStats> {
+        if !std::path::Path::new("/dev/nvidiactl").exists() {
+            return vec![];
+        }
         let output = match tokio::process::Command::new("nvidia-smi")
             .args([
                 "--query-gpu=memory.total,memory.used,memory.free,utilization.gpu",
@@ -352,10 +709,14 @@ impl DockerManager {
             .collect()
     }
 
-    /// Per-container VRAM usage: model_id → total VRAM MB.
+    /// Per-container VRAM usage: model_id → VRAM MB, in total and per GPU
+    /// for containers pinned to GPUs (`devices`, from `container_secrets`).
     /// Uses DRM fdinfo for AMD GPUs and nvidia-smi for NVIDIA GPUs.
     /// Requires `pid: host` in docker-compose for host PID visibility.
-    pub async fn per_container_vram(&self) -> HashMap<String, u64> {
+    pub async fn per_container_vram(
+        &self,
+        devices: &HashMap<String, Vec<u32>>,
+    ) -> HashMap<String, ContainerVram> {
         let containers = match self.list_managed_containers().await {
             Ok(c) => c,
             Err(_) => return HashMap::new(),
@@ -417,7 +778,15 @@ impl DockerManager {
                 }
             }
             if total_vram_bytes > 0 {
-                result.insert(model_id.clone(), total_vram_bytes / (1024 * 1024));
+                let total_mb = total_vram_bytes / (1024 * 1024);
+                let pinned = devices.get(model_id).map(Vec::as_slice).unwrap_or(&[]);
+                result.insert(
+                    model_id.clone(),
+                    ContainerVram {
+                        total_mb,
+                        per_device_mb: attribute_vram(total_mb, pinned),
+                    },
+                );
             }
         }
 
@@ -427,6 +796,9 @@ impl DockerManager {
     /// Query NVIDIA per-process GPU memory via nvidia-smi.
     /// Returns PID → used memory in MiB.
     async fn nvidia_per_process_vram() -> HashMap<u32, u64> {
+        if !std::path::Path::new("/dev/nvidiactl").exists() {
+            return HashMap::new();
+        }
         let output = match tokio::process::Command::new("nvidia-smi")
             .args([
                 "--query-compute-apps=pid,used_memory",
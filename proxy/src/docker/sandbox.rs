@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use bollard::container::LogOutput;
+use bollard::models::{ContainerCreateBody, HostConfig};
+use bollard::query_parameters::{
+    CreateContainerOptions, ListContainersOptions, LogsOptions, RemoveContainerOptions,
+    StartContainerOptions, WaitContainerOptions,
+};
+use futures::StreamExt;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::DockerManager;
+
+/// Marks code-execution containers. They deliberately don't carry the
+/// `managed-by` label, so they never show up as model backends.
+const LABEL_SANDBOX: &str = "sovereign-engine.sandbox";
+
+/// `nobody`: sandboxed code never runs as root.
+const SANDBOX_UID: u32 = 65534;
+
+/// Bytes of stdout/stderr kept per stream; the rest is dropped.
+pub const SANDBOX_MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// Limits for one sandboxed run.
+#[derive(Debug, Clone)]
+pub struct SandboxLimits {
+    pub image: String,
+    pub memory_mb: u64,
+    pub cpus: f64,
+    pub timeout: Duration,
+}
+
+/// What a sandboxed run produced.
+#[derive(Debug, Clone, Default)]
+pub struct SandboxOutput {
+    /// `None` when the run was killed at the time limit.
+    pub exit_code: Option<i64>,
+    pub stdout: String,
+    pub stderr: String,
+    pub timed_out: bool,
+    /// Output beyond `SANDBOX_MAX_OUTPUT_BYTES` was dropped.
+    pub truncated: bool,
+    pub duration: Duration,
+}
+
+/// Keep at most `SANDBOX_MAX_OUTPUT_BYTES` of `buf`. Returns true if
+/// anything was dropped.
+fn append_capped(buf: &mut Vec<u8>, chunk: &[u8]) -> bool {
+    let room = SANDBOX_MAX_OUTPUT_BYTES.saturating_sub(buf.len());
+    buf.extend_from_slice(&chunk[..chunk.len().min(room)]);
+    chunk.len() > room
+}
+
+/// Host settings for a sandbox container: no network, no capabilities, a
+/// read-only root filesystem with a small `/tmp`, and hard CPU, memory and
+/// process limits.
+fn sandbox_host_config(limits: &SandboxLimits) -> HostConfig {
+    let memory = (limits.memory_mb * 1024 * 1024) as i64;
+    HostConfig {
+        network_mode: Some("none".to_string()),
+        memory: Some(memory),
+        // Same as memory: no swap
+        memory_swap: Some(memory),
+        nano_cpus: Some((limits.cpus * 1e9) as i64),
+        pids_limit: Some(64),
+        readonly_rootfs: Some(true),
+        tmpfs: Some(HashMap::from([(
+            "/tmp".to_string(),
+            "rw,noexec,nosuid,size=16m".to_string(),
+        )])),
+        cap_drop: Some(vec!["ALL".to_string()]),
+        security_opt: Some(vec!["no-new-privileges".to_string()]),
+        ..Default::default()
+    }
+}
+
+impl DockerManager {
+    /// Run `cmd` in a throwaway sandbox container and collect its output.
+    ///
+    /// The container is removed afterwards whatever happens. A run that
+    /// outlives `limits.timeout` is killed and reported as `timed_out`.
+    pub async fn run_sandboxed(
+        &self,
+        limits: &SandboxLimits,
+        cmd: Vec<String>,
+    ) -> Result<SandboxOutput> {
+        let container_name = format!("sovereign-sandbox-{}", Uuid::new_v4());
+        let labels = HashMap::from([(LABEL_SANDBOX.to_string(), "true".to_string())]);
+
+        let container_config = ContainerCreateBody {
+            image: Some(limits.image.clone()),
+            cmd: Some(cmd),
+            labels: Some(labels),
+            user: Some(format!("{SANDBOX_UID}:{SANDBOX_UID}")),
+            working_dir: Some("/tmp".to_string()),
+            network_disabled: Some(true),
+            host_config: Some(sandbox_host_config(limits)),
+            ..Default::default()
+        };
+
+        self.docker
+            .create_container(
+                Some(CreateContainerOptions {
+                    name: Some(container_name.clone()),
+                    ..Default::default()
+                }),
+                container_config,
+            )
+            .await
+            .context("Failed to create sandbox container")?;
+
+        let result = self.run_and_collect(&container_name, limits.timeout).await;
+
+        if let Err(e) = self
+            .docker
+            .remove_container(
+                &container_name,
+                Some(RemoveContainerOptions {
+                    force: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+        {
+            warn!(container = %container_name, error = %e, "Failed to remove sandbox container");
+        }
+
+        result
+    }
+
+    async fn run_and_collect(
+        &self,
+        container_name: &str,
+        timeout: Duration,
+    ) -> Result<SandboxOutput> {
+        let started = Instant::now();
+        self.docker
+            .start_container(container_name, None::<StartContainerOptions>)
+            .await
+            .context("Failed to start sandbox container")?;
+
+        let mut wait = Box::pin(
+            self.docker
+                .wait_container(container_name, None::<WaitContainerOptions>),
+        );
+        let (exit_code, timed_out) = match tokio::time::timeout(timeout, wait.next()).await {
+            Ok(Some(Ok(status))) => (Some(status.status_code), false),
+            // Non-zero exits arrive as an error carrying the code
+            Ok(Some(Err(bollard::errors::Error::DockerContainerWaitError { code, .. }))) => {
+                (Some(code), false)
+            }
+            Ok(Some(Err(e))) => return Err(e).context("Failed waiting for sandbox container"),
+            Ok(None) => (None, false),
+            Err(_) => (None, true),
+        };
+        let duration = started.elapsed();
+
+        let mut output = SandboxOutput {
+            exit_code,
+            timed_out,
+            duration,
+            ..Default::default()
+        };
+        let (mut stdout, mut stderr) = (Vec::new(), Vec::new());
+        let mut logs = Box::pin(self.docker.logs(
+            container_name,
+            Some(LogsOptions {
+                stdout: true,
+                stderr: true,
+                ..Default::default()
+            }),
+        ));
+        while let Some(chunk) = logs.next().await {
+            match chunk {
+                Ok(LogOutput::StdOut { message }) => {
+                    output.truncated |= append_capped(&mut stdout, &message);
+                }
+                Ok(LogOutput::StdErr { message }) => {
+                    output.truncated |= append_capped(&mut stderr, &message);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(container = %container_name, error = %e, "Failed to read sandbox output");
+                    break;
+                }
+            }
+        }
+        output.stdout = String::from_utf8_lossy(&stdout).into_owned();
+        output.stderr = String::from_utf8_lossy(&stderr).into_owned();
+        Ok(output)
+    }
+
+    /// Remove sandbox containers left behind by a crash or restart.
+    pub async fn remove_stale_sandboxes(&self) {
+        let filters = HashMap::from([("label".to_string(), vec![format!("{LABEL_SANDBOX}=true")])]);
+        let containers = match self
+            .docker
+            .list_containers(Some(ListContainersOptions {
+                all: true,
+                filters: Some(filters),
+                ..Default::default()
+            }))
+            .await
+        {
+            Ok(c) => c,
+            Err(e) => {
+                warn!(error = %e, "Failed to list sandbox containers");
+                return;
+            }
+        };
+        for id in containers.into_iter().filter_map(|c| c.id) {
+            let removed = self
+                .docker
+                .remove_container(
+                    &id,
+                    Some(RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await;
+            match removed {
+                Ok(()) => info!(container = %id, "Removed stale sandbox container"),
+                Err(e) => {
+                    warn!(container = %id, error = %e, "Failed to remove stale sandbox container")
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_is_capped() {
+        let mut buf = Vec::new();
+        assert!(!append_capped(&mut buf, &[b'a'; 1000]));
+        assert!(append_capped(
+            &mut buf,
+            &vec![b'b'; SANDBOX_MAX_OUTPUT_BYTES]
+        ));
+        assert_eq!(buf.len(), SANDBOX_MAX_OUTPUT_BYTES);
+        assert!(append_capped(&mut buf, b"c"));
+        assert_eq!(buf.len(), SANDBOX_MAX_OUTPUT_BYTES);
+    }
+
+    #[test]
+    fn host_config_isolates_container() {
+        let hc = sandbox_host_config(&SandboxLimits {
+            image: "python:3.12-alpine".to_string(),
+            memory_mb: 256,
+            cpus: 0.5,
+            timeout: Duration::from_secs(10),
+        });
+        assert_eq!(hc.network_mode.as_deref(), Some("none"));
+        assert_eq!(hc.memory, Some(256 * 1024 * 1024));
+        assert_eq!(hc.memory_swap, hc.memory);
+        assert_eq!(hc.nano_cpus, Some(500_000_000));
+        assert_eq!(hc.readonly_rootfs, Some(true));
+        assert_eq!(hc.cap_drop, Some(vec!["ALL".to_string()]));
+        assert!(hc.mounts.is_none(), "no host paths reach the sandbox");
+    }
+}
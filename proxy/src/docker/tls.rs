@@ -0,0 +1,344 @@
+//! Mutual TLS between the proxy and backend containers.
+//!
+//! With `BACKEND_TLS=true` the engine keeps a private CA in `BACKEND_TLS_DIR`
+//! and issues each backend container its own certificate, named after the
+//! container, every time it starts one. The proxy trusts only that CA and
+//! checks the certificate against the container name it dials, so one
+//! backend can't answer for another. The proxy presents its own client
+//! certificate, which vLLM is started to require. llama-server can't verify
+//! client certificates, so it still relies on its per-container API key to
+//! authenticate the proxy.
+//!
+//! Certificates are generated with the `openssl` CLI.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use bollard::models::{Mount, MountTypeEnum};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rand::RngExt;
+use tokio::process::Command;
+use tracing::info;
+
+use crate::config::AppConfig;
+
+/// Where a container's certificate directory is mounted.
+pub const TLS_MOUNT: &str = "/tls";
+
+const CA_SUBJECT: &str = "/CN=sovereign-engine backend CA";
+/// Common name of the proxy's client certificate.
+const PROXY_CN: &str = "sovereign-engine-proxy";
+const CA_DAYS: u32 = 3650;
+/// Container certificates are reissued on every start.
+const LEAF_DAYS: u32 = 365;
+
+/// A certificate the engine relies on, and how to renew it.
+#[derive(Debug, Clone)]
+pub struct Certificate {
+    pub name: &'static str,
+    pub path: PathBuf,
+    pub renew: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub struct BackendTls {
+    /// Certificate directory as seen by this process.
+    dir: PathBuf,
+    /// The same directory as seen by the Docker host, for bind mounts.
+    host_dir: String,
+}
+
+impl BackendTls {
+    pub fn from_config(config: &AppConfig) -> Option<Self> {
+        config.backend_tls.then(|| Self {
+            dir: PathBuf::from(&config.backend_tls_dir),
+            host_dir: config.backend_tls_host_path.clone(),
+        })
+    }
+
+    fn ca_cert(&self) -> PathBuf {
+        self.dir.join("ca.pem")
+    }
+
+    fn ca_key(&self) -> PathBuf {
+        self.dir.join("ca-key.pem")
+    }
+
+    fn proxy_dir(&self) -> PathBuf {
+        self.dir.join("proxy")
+    }
+
+    /// Create the CA and the proxy's client certificate if they don't exist.
+    pub async fn ensure_ca(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+
+        if !self.ca_cert().exists() || !self.ca_key().exists() {
+            openssl(&[
+                "req",
+                "-x509",
+                "-newkey",
+                "ec",
+                "-pkeyopt",
+                "ec_paramgen_curve:prime256v1",
+                "-nodes",
+                "-keyout",
+                path_str(&self.ca_key())?,
+                "-out",
+                path_str(&self.ca_cert())?,
+                "-days",
+                &CA_DAYS.to_string(),
+                "-subj",
+                CA_SUBJECT,
+                "-addext",
+                "basicConstraints=critical,CA:TRUE",
+                "-addext",
+                "keyUsage=critical,keyCertSign,cRLSign",
+            ])
+            .await
+            .context("Failed to create backend CA")?;
+            set_mode(&self.ca_key(), 0o600)?;
+            info!(dir = %self.dir.display(), "Created backend TLS CA");
+        }
+
+        let proxy_dir = self.proxy_dir();
+        if !proxy_dir.join("cert.pem").exists() {
+            self.sign(&proxy_dir, PROXY_CN, "clientAuth", None)
+                .await
+                .context("Failed to issue proxy client certificate")?;
+            set_mode(&proxy_dir.join("key.pem"), 0o600)?;
+        }
+        Ok(())
+    }
+
+    /// Issue a fresh certificate for `container_name` and return the mount
+    /// that exposes it (with the CA) at `TLS_MOUNT`.
+    pub async fn issue(&self, container_name: &str) -> Result<Mount> {
+        let dir = self.dir.join("containers").join(container_name);
+        self.sign(&dir, container_name, "serverAuth", Some(container_name))
+            .await
+            .with_context(|| format!("Failed to issue certificate for {container_name}"))?;
+        std::fs::copy(self.ca_cert(), dir.join("ca.pem")).context("Failed to copy backend CA")?;
+        // The container runs as a random UID, so it must be able to read its key
+        set_mode(&dir.join("key.pem"), 0o644)?;
+
+        Ok(Mount {
+            target: Some(TLS_MOUNT.to_string()),
+            source: Some(format!(
+                "{}/containers/{}",
+                self.host_dir.trim_end_matches('/'),
+                container_name
+            )),
+            typ: Some(MountTypeEnum::BIND),
+            read_only: Some(true),
+            ..Default::default()
+        })
+    }
+
+    /// The long-lived certificates: the CA and the proxy's client
+    /// certificate. Container certificates are reissued on every start.
+    pub fn certificates(&self) -> Vec<Certificate> {
+        vec![
+            Certificate {
+                name: "backend CA",
+                path: self.ca_cert(),
+                renew: "Delete ca.pem, ca-key.pem and proxy/ in BACKEND_TLS_DIR, restart the \
+                        proxy, then restart the loaded models",
+            },
+            Certificate {
+                name: "proxy client certificate",
+                path: self.proxy_dir().join("cert.pem"),
+                renew: "Delete proxy/ in BACKEND_TLS_DIR and restart the proxy",
+            },
+        ]
+    }
+
+    /// The CA to trust and the identity to present when calling backends.
+    pub fn client_material(&self) -> Result<(reqwest::Certificate, reqwest::Identity)> {
+        let ca = std::fs::read(self.ca_cert()).context("Failed to read backend CA")?;
+        let proxy_dir = self.proxy_dir();
+        let mut identity =
+            std::fs::read(proxy_dir.join("cert.pem")).context("Failed to read proxy cert")?;
+        identity
+            .extend(std::fs::read(proxy_dir.join("key.pem")).context("Failed to read proxy key")?);
+        Ok((
+            reqwest::Certificate::from_pem(&ca).context("Invalid backend CA")?,
+            reqwest::Identity::from_pem(&identity).context("Invalid proxy identity")?,
+        ))
+    }
+
+    /// Create `dir/key.pem` and `dir/cert.pem` signed by the CA.
+    async fn sign(
+        &self,
+        dir: &Path,
+        common_name: &str,
+        usage: &str,
+        dns: Option<&str>,
+    ) -> Result<()> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let key = dir.join("key.pem");
+        let csr = dir.join("req.csr");
+        let ext = dir.join("ext.cnf");
+        let cert = dir.join("cert.pem");
+
+        openssl(&[
+            "req",
+            "-new",
+            "-newkey",
+            "ec",
+            "-pkeyopt",
+            "ec_paramgen_curve:prime256v1",
+            "-nodes",
+            "-keyout",
+            path_str(&key)?,
+            "-out",
+            path_str(&csr)?,
+            "-subj",
+            &format!("/CN={common_name}"),
+        ])
+        .await?;
+        std::fs::write(&ext, extensions(usage, dns)).context("Failed to write extensions")?;
+        let serial = format!("0x{:016x}", rand::rng().random::<u64>());
+        openssl(&[
+            "x509",
+            "-req",
+            "-in",
+            path_str(&csr)?,
+            "-CA",
+            path_str(&self.ca_cert())?,
+            "-CAkey",
+            path_str(&self.ca_key())?,
+            "-set_serial",
+            &serial,
+            "-days",
+            &LEAF_DAYS.to_string(),
+            "-extfile",
+            path_str(&ext)?,
+            "-out",
+            path_str(&cert)?,
+        ])
+        .await?;
+
+        let _ = std::fs::remove_file(&csr);
+        let _ = std::fs::remove_file(&ext);
+        Ok(())
+    }
+}
+
+/// X.509 v3 extensions for a leaf certificate. `usage` is the extended key
+/// usage (`serverAuth` or `clientAuth`); `dns` becomes the only SAN.
+fn extensions(usage: &str, dns: Option<&str>) -> String {
+    let mut ext = format!(
+        "basicConstraints=critical,CA:FALSE\n\
+         keyUsage=critical,digitalSignature\n\
+         extendedKeyUsage={usage}\n"
+    );
+    if let Some(dns) = dns {
+        ext.push_str(&format!("subjectAltName=DNS:{dns}\n"));
+    }
+    ext
+}
+
+/// llama-server arguments to serve HTTPS with the mounted certificate.
+pub fn llamacpp_args() -> Vec<String> {
+    vec![
+        "--ssl-key-file".to_string(),
+        format!("{TLS_MOUNT}/key.pem"),
+        "--ssl-cert-file".to_string(),
+        format!("{TLS_MOUNT}/cert.pem"),
+    ]
+}
+
+/// vLLM arguments to serve HTTPS and require a client certificate signed by
+/// the backend CA (`--ssl-cert-reqs 2` is `ssl.CERT_REQUIRED`).
+pub fn vllm_args() -> Vec<String> {
+    vec![
+        "--ssl-keyfile".to_string(),
+        format!("{TLS_MOUNT}/key.pem"),
+        "--ssl-certfile".to_string(),
+        format!("{TLS_MOUNT}/cert.pem"),
+        "--ssl-ca-certs".to_string(),
+        format!("{TLS_MOUNT}/ca.pem"),
+        "--ssl-cert-reqs".to_string(),
+        "2".to_string(),
+    ]
+}
+
+/// When the PEM certificate at `path` expires.
+pub async fn cert_expiry(path: &Path) -> Result<DateTime<Utc>> {
+    let out = openssl(&["x509", "-noout", "-enddate", "-in", path_str(path)?]).await?;
+    parse_not_after(&out).with_context(|| format!("Unexpected openssl output: {}", out.trim()))
+}
+
+/// Parse openssl's `notAfter=Oct 16 12:00:00 2027 GMT`.
+fn parse_not_after(out: &str) -> Option<DateTime<Utc>> {
+    let date = out.trim().strip_prefix("notAfter=")?;
+    // The day is space-padded
+    let date = date.split_whitespace().collect::<Vec<_>>().join(" ");
+    NaiveDateTime::parse_from_str(&date, "%b %d %H:%M:%S %Y GMT")
+        .ok()
+        .map(|d| d.and_utc())
+}
+
+/// Run openssl and return its standard output.
+async fn openssl(args: &[&str]) -> Result<String> {
+    let output = Command::new("openssl")
+        .args(args)
+        .output()
+        .await
+        .context("Failed to run openssl — is it installed?")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "openssl {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn path_str(path: &Path) -> Result<&str> {
+    path.to_str()
+        .with_context(|| format!("Non-UTF-8 path: {}", path.display()))
+}
+
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+        .with_context(|| format!("Failed to set permissions on {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_extensions_name_the_container() {
+        let ext = extensions("serverAuth", Some("sovereign-vllm-m1"));
+        assert!(ext.contains("extendedKeyUsage=serverAuth\n"));
+        assert!(ext.contains("subjectAltName=DNS:sovereign-vllm-m1\n"));
+        assert!(ext.contains("CA:FALSE"));
+
+        let client = extensions("clientAuth", None);
+        assert!(!client.contains("subjectAltName"));
+    }
+
+    #[test]
+    fn parses_openssl_end_date() {
+        let expiry = parse_not_after("notAfter=Oct 16 12:00:00 2027 GMT\n").unwrap();
+        assert_eq!(expiry.to_rfc3339(), "2027-10-16T12:00:00+00:00");
+        let padded = parse_not_after("notAfter=Mar  5 08:30:00 2030 GMT").unwrap();
+        assert_eq!(padded.to_rfc3339(), "2030-03-05T08:30:00+00:00");
+        assert!(parse_not_after("subject=CN = x").is_none());
+    }
+
+    #[test]
+    fn vllm_requires_client_certificates() {
+        let args = vllm_args();
+        let pos = args.iter().position(|a| a == "--ssl-cert-reqs").unwrap();
+        assert_eq!(args[pos + 1], "2");
+        assert!(args.contains(&"/tls/ca.pem".to_string()));
+        assert!(llamacpp_args().contains(&"/tls/cert.pem".to_string()));
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use bollard::models::{
+    ContainerCreateBody, DeviceRequest, EndpointSettings, HostConfig, Mount, MountTypeEnum,
+    NetworkingConfig,
+};
+use bollard::query_parameters::{
+    CreateContainerOptions, RemoveContainerOptions, StartContainerOptions,
+};
+use bytes::Bytes;
+use tracing::{error, info, warn};
+
+use super::limits::ResourceLimits;
+use super::{
+    DockerManager, StopMode, LABEL_BACKEND, LABEL_MANAGED_BY, LABEL_MANAGED_VALUE, LABEL_MODEL_ID,
+};
+
+pub(crate) const VLLM_IMAGE: &str = "vllm/vllm-openai:latest";
+
+// vLLM only runs on NVIDIA GPUs here (via the NVIDIA container toolkit).
+// GGUF models stay on llama.cpp; vLLM serves safetensors repos.
+const VLLM_INTERNAL_PORT: u16 = 8000;
+
+/// Configuration for launching a vLLM container.
+#[derive(Debug, Clone)]
+pub struct VllmConfig {
+    pub model_id: String,
+    /// Path to the model repo directory relative to the model directory (e.g. "meta-llama--Llama-3.1-8B-Instruct")
+    pub model_dir: String,
+    /// Maximum sequence length (`--max-model-len`)
+    pub context_size: u32,
+    /// Maximum concurrent sequences (`--max-num-seqs`, default 1)
+    pub parallel: u32,
+    /// Fraction of GPU memory vLLM may claim (default 0.90)
+    pub gpu_memory_utilization: f32,
+    /// Number of GPUs to shard the model across (default 1)
+    pub tensor_parallel_size: u32,
+    /// GPU indices the container may use (empty = all)
+    pub devices: Vec<u32>,
+    pub extra_args: Vec<String>,
+    /// Container UID — allocated by DockerManager::allocate_uid()
+    pub uid: u32,
+    /// API key for backend authentication — passed as --api-key to vLLM
+    pub api_key: String,
+    /// Container name; defaults to [`super::container_name`]. Set when
+    /// starting the replacement in a blue/green swap.
+    pub container_name: Option<String>,
+    /// CPU, memory and process limits (none by default)
+    pub limits: ResourceLimits,
+}
+
+impl Default for VllmConfig {
+    fn default() -> Self {
+        Self {
+            model_id: String::new(),
+            model_dir: String::new(),
+            context_size: 4096,
+            parallel: 1,
+            gpu_memory_utilization: 0.90,
+            tensor_parallel_size: 1,
+            devices: Vec::new(),
+            extra_args: Vec::new(),
+            uid: 10000,
+            api_key: String::new(),
+            container_name: None,
+            limits: ResourceLimits::default(),
+        }
+    }
+}
+
+impl VllmConfig {
+    /// vLLM server arguments for this config.
+    fn cmd(&self) -> Vec<String> {
+        let mut cmd = vec![
+            "--model".to_string(),
+            format!("/models/{}", self.model_dir),
+            // vLLM rejects requests whose `model` doesn't match; the proxy
+            // rewrites it to the model ID (see `rewrite_model_field`)
+            "--served-model-name".to_string(),
+            self.model_id.clone(),
+            "--host".to_string(),
+            "0.0.0.0".to_string(),
+            "--port".to_string(),
+            VLLM_INTERNAL_PORT.to_string(),
+            "--max-model-len".to_string(),
+            self.context_size.to_string(),
+            "--max-num-seqs".to_string(),
+            self.parallel.max(1).to_string(),
+            "--gpu-memory-utilization".to_string(),
+            format!("{:.2}", self.gpu_memory_utilization),
+            "--api-key".to_string(),
+            self.api_key.clone(),
+        ];
+
+        if self.tensor_parallel_size > 1 {
+            cmd.push("--tensor-parallel-size".to_string());
+            cmd.push(self.tensor_parallel_size.to_string());
+        }
+
+        cmd.extend(self.extra_args.clone());
+        cmd
+    }
+
+    /// NVIDIA GPUs requested from the container toolkit: all of them, or
+    /// only the pinned indices. The toolkit renumbers the pinned GPUs from
+    /// 0 inside the container, so CUDA_VISIBLE_DEVICES needs no rewriting.
+    fn device_request(&self) -> DeviceRequest {
+        let (count, device_ids) = if self.devices.is_empty() {
+            (Some(-1), None)
+        } else {
+            (
+                None,
+                Some(self.devices.iter().map(|d| d.to_string()).collect()),
+            )
+        };
+        DeviceRequest {
+            driver: Some("nvidia".to_string()),
+            count,
+            device_ids,
+            capabilities: Some(vec![vec!["gpu".to_string()]]),
+            ..Default::default()
+        }
+    }
+}
+
+impl DockerManager {
+    /// Start a vLLM container for the given model.
+    pub async fn start_vllm(&self, config: &VllmConfig) -> Result<String> {
+        let container_name = config
+            .container_name
+            .clone()
+            .unwrap_or_else(|| super::container_name("vllm", &config.model_id));
+
+        // Check if container already exists
+        if let Ok(info) = self.docker.inspect_container(&container_name, None).await {
+            if let Some(state) = &info.state {
+                if state.running.unwrap_or(false) {
+                    info!(model = %config.model_id, "vLLM container already running");
+                    return Ok(container_name);
+                }
+            }
+            // Container exists but not running — remove and recreate
+            warn!(model = %config.model_id, "Removing stopped vLLM container");
+            self.docker
+                .remove_container(
+                    &container_name,
+                    Some(RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .context("Failed to remove existing container")?;
+        }
+
+        let uid = config.uid;
+        let user_str = format!("{}:{}", uid, uid);
+
+        // Labels
+        let mut labels = HashMap::new();
+        labels.insert(
+            LABEL_MANAGED_BY.to_string(),
+            LABEL_MANAGED_VALUE.to_string(),
+        );
+        labels.insert(LABEL_MODEL_ID.to_string(), config.model_id.clone());
+        labels.insert(LABEL_BACKEND.to_string(), "vllm".to_string());
+        config.limits.add_labels(&mut labels);
+
+        let mut cmd = config.cmd();
+        let mut mounts = vec![Mount {
+            target: Some("/models".to_string()),
+            source: Some(self.model_path.clone()),
+            typ: Some(MountTypeEnum::BIND),
+            read_only: Some(true),
+            ..Default::default()
+        }];
+        if let Some(tls) = &self.backend_tls {
+            mounts.push(tls.issue(&container_name).await?);
+            cmd.extend(super::tls::vllm_args());
+        }
+
+        let mut host_config = HostConfig {
+            // No port bindings — vLLM is only reachable via the internal network
+            mounts: Some(mounts),
+            device_requests: Some(vec![config.device_request()]),
+            // PyTorch worker processes share tensors through /dev/shm
+            shm_size: Some(2 * 1024 * 1024 * 1024),
+            ..Default::default()
+        };
+        config.limits.apply(&mut host_config);
+
+        // The container runs as an unprivileged UID with a read-only model
+        // mount: keep caches in /tmp and never reach out to the Hub.
+        let env = vec![
+            "HOME=/tmp".to_string(),
+            "HF_HOME=/tmp/hf".to_string(),
+            "HF_HUB_OFFLINE=1".to_string(),
+            "VLLM_NO_USAGE_STATS=1".to_string(),
+        ];
+
+        // Attach to the internal network so the proxy can reach this container by name
+        let mut endpoints_config = HashMap::new();
+        endpoints_config.insert(self.backend_network.clone(), EndpointSettings::default());
+
+        let networking_config = NetworkingConfig {
+            endpoints_config: Some(endpoints_config),
+        };
+
+        let container_config = ContainerCreateBody {
+            image: Some(VLLM_IMAGE.to_string()),
+            cmd: Some(cmd),
+            env: Some(env),
+            labels: Some(labels),
+            user: Some(user_str),
+            host_config: Some(host_config),
+            networking_config: Some(networking_config),
+            ..Default::default()
+        };
+
+        info!(
+            model = %config.model_id,
+            container = %container_name,
+            image = %VLLM_IMAGE,
+            uid = uid,
+            tensor_parallel = config.tensor_parallel_size,
+            devices = ?config.devices,
+            "Creating vLLM container"
+        );
+
+        self.docker
+            .create_container(
+                Some(CreateContainerOptions {
+                    name: Some(container_name.clone()),
+                    ..Default::default()
+                }),
+                container_config,
+            )
+            .await
+            .context("Failed to create vLLM container")?;
+
+        if let Err(e) = self
+            .docker
+            .start_container(&container_name, None::<StartContainerOptions>)
+            .await
+        {
+            error!(
+                model = %config.model_id,
+                container = %container_name,
+                error = %e,
+                "Failed to start vLLM container — cleaning up"
+            );
+            // Clean up the created-but-not-started container
+            let _ = self
+                .docker
+                .remove_container(
+                    &container_name,
+                    Some(RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await;
+            return Err(e).context("Failed to start vLLM container");
+        }
+
+        info!(
+            model = %config.model_id,
+            container = %container_name,
+            network = %self.backend_network,
+            uid = uid,
+            "vLLM container started on internal network"
+        );
+
+        Ok(container_name)
+    }
+
+    /// Stop a vLLM container by model ID.
+    pub async fn stop_vllm(&self, model_id: &str, mode: StopMode) -> Result<()> {
+        let container_name = super::container_name("vllm", model_id);
+        self.stop_and_remove(&container_name, model_id, mode).await
+    }
+
+    /// Check if a vLLM container is healthy and responding.
+    ///
+    /// vLLM's `/health` answers 200 only once the model is loaded.
+    pub async fn check_vllm_health(&self, model_id: &str) -> Result<bool> {
+        let url = format!("{}/health", self.vllm_base_url(model_id));
+        match self.health_client()?.get(&url).send().await {
+            Ok(resp) => Ok(resp.status().is_success()),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Get the internal URL for a vLLM container on the isolated network.
+    pub fn vllm_base_url(&self, model_id: &str) -> String {
+        self.vllm_url(&super::container_name("vllm", model_id))
+    }
+
+    /// Internal URL of the named vLLM container.
+    pub(crate) fn vllm_url(&self, container_name: &str) -> String {
+        format!(
+            "{}://{}:{}",
+            self.backend_scheme(),
+            container_name,
+            VLLM_INTERNAL_PORT
+        )
+    }
+}
+
+/// Point a request body's `model` field at the name vLLM serves the model
+/// under. Bodies that aren't a JSON object are passed through unchanged.
+pub fn rewrite_model_field(body: Bytes, served_model_name: &str) -> Bytes {
+    let Ok(mut value) = serde_json::from_slice::<serde_json::Value>(&body) else {
+        return body;
+    };
+    match value.as_object_mut() {
+        Some(obj) => {
+            obj.insert(
+                "model".to_string(),
+                serde_json::Value::String(served_model_name.to_string()),
+            );
+        }
+        None => return body,
+    }
+    serde_json::to_vec(&value).map(Bytes::from).unwrap_or(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // -- VllmConfig ----------------------------------------------------------
+
+    #[test]
+    fn vllm_config_defaults() {
+        let cfg = VllmConfig::default();
+        assert_eq!(cfg.context_size, 4096);
+        assert_eq!(cfg.parallel, 1);
+        assert_eq!(cfg.tensor_parallel_size, 1);
+        assert_eq!(cfg.uid, 10000);
+        assert!(cfg.extra_args.is_empty());
+    }
+
+    #[test]
+    fn vllm_device_request_pins_gpus() {
+        let all = VllmConfig::default().device_request();
+        assert_eq!(all.count, Some(-1));
+        assert!(all.device_ids.is_none());
+
+        let pinned = VllmConfig {
+            devices: vec![1, 3],
+            ..Default::default()
+        }
+        .device_request();
+        assert_eq!(pinned.count, None);
+        assert_eq!(
+            pinned.device_ids,
+            Some(vec!["1".to_string(), "3".to_string()])
+        );
+    }
+
+    #[test]
+    fn vllm_cmd_serves_model_under_its_id() {
+        let cfg = VllmConfig {
+            model_id: "abc".into(),
+            model_dir: "org--model".into(),
+            context_size: 8192,
+            parallel: 4,
+            api_key: "key".into(),
+            ..Default::default()
+        };
+        let cmd = cfg.cmd();
+        let arg = |flag: &str| {
+            let i = cmd.iter().position(|a| a == flag).unwrap();
+            cmd[i + 1].clone()
+        };
+        assert_eq!(arg("--model"), "/models/org--model");
+        assert_eq!(arg("--served-model-name"), "abc");
+        assert_eq!(arg("--max-model-len"), "8192");
+        assert_eq!(arg("--max-num-seqs"), "4");
+        assert_eq!(arg("--api-key"), "key");
+        assert!(!cmd.contains(&"--tensor-parallel-size".to_string()));
+    }
+
+    #[test]
+    fn vllm_base_url_format() {
+        let dm = DockerManager::test_dummy();
+        assert_eq!(
+            dm.vllm_base_url("my-model-123"),
+            "http://sovereign-vllm-my-model-123:8000"
+        );
+    }
+
+    // -- rewrite_model_field -------------------------------------------------
+
+    #[test]
+    fn rewrites_model_and_keeps_other_fields() {
+        let body = Bytes::from(r#"{"model":"thinking","messages":[],"stream":true}"#);
+        let out: serde_json::Value =
+            serde_json::from_slice(&rewrite_model_field(body, "abc")).unwrap();
+        assert_eq!(out["model"], "abc");
+        assert_eq!(out["stream"], true);
+    }
+
+    #[test]
+    fn non_object_body_passes_through() {
+        let body = Bytes::from_static(b"not json");
+        assert_eq!(rewrite_model_field(body.clone(), "abc"), body);
+    }
+}
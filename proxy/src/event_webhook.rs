@@ -0,0 +1,184 @@
+//! Event bus notifications pushed to an external endpoint.
+//!
+//! With `EVENT_WEBHOOK_URL` set, a subscriber on the [`EventBus`] POSTs each
+//! notification — model loaded, download finished, reservations changed,
+//! container crashed — as one JSON document: the event's fields and `type`,
+//! plus a `delivery_id` and `occurred_at`. Periodic metrics snapshots and
+//! download progress are not sent.
+//!
+//! Events are delivered one at a time, in order, with the usage webhook's
+//! headers and signing scheme (keyed with `EVENT_WEBHOOK_SECRET`). A failed
+//! POST is retried with backoff up to [`MAX_RETRIES`] times and then dropped;
+//! events published while a slow receiver keeps the dispatcher busy are
+//! skipped once the bus buffer overflows. Nothing is persisted — receivers
+//! that need the full picture should re-fetch on any event.
+
+use std::time::Duration;
+
+use chrono::{SecondsFormat, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::events::{AppEvent, EventBus};
+use crate::usage_webhook::{backoff, sign};
+
+/// Payload schema identifier. Bump the version on any incompatible change.
+pub const SCHEMA: &str = "sovereign-engine.event.v1";
+
+/// Retries per event before it is dropped.
+const MAX_RETRIES: u32 = 3;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One webhook delivery.
+#[derive(Debug, Serialize)]
+struct EventPayload<'a> {
+    schema: &'static str,
+    delivery_id: String,
+    occurred_at: String,
+    #[serde(flatten)]
+    event: &'a AppEvent,
+}
+
+impl<'a> EventPayload<'a> {
+    /// The delivery for `event`, or `None` for periodic updates.
+    fn for_event(event: &'a AppEvent) -> Option<Self> {
+        event.is_notification().then(|| Self {
+            schema: SCHEMA,
+            delivery_id: format!("event-{}", Uuid::new_v4()),
+            occurred_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            event,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct EventWebhook {
+    url: String,
+    secret: Option<String>,
+    client: reqwest::Client,
+}
+
+impl EventWebhook {
+    /// Subscribe to `events` and spawn the dispatcher if a webhook URL is
+    /// configured.
+    pub fn spawn(config: &AppConfig, events: &EventBus) {
+        let Some(url) = config.event_webhook_url.clone() else {
+            return;
+        };
+        if config.event_webhook_secret.is_none() {
+            warn!("EVENT_WEBHOOK_SECRET not set — event webhooks are sent unsigned");
+        }
+        info!(url = %url, "Event webhook enabled");
+
+        let hook = Self {
+            url,
+            secret: config.event_webhook_secret.clone(),
+            client: reqwest::Client::new(),
+        };
+        let mut rx = events.subscribe();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => hook.deliver(&event).await,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(skipped, "Event webhook fell behind; events dropped")
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    async fn deliver(&self, event: &AppEvent) {
+        let Some(payload) = EventPayload::for_event(event) else {
+            return;
+        };
+        let body = match serde_json::to_string(&payload) {
+            Ok(b) => b,
+            Err(e) => {
+                warn!(event = event.name(), error = %e, "Failed to serialize event");
+                return;
+            }
+        };
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match self.post(&payload.delivery_id, &body).await {
+                Ok(()) => return,
+                Err(e) if attempt > MAX_RETRIES => {
+                    warn!(
+                        event = event.name(),
+                        delivery_id = %payload.delivery_id,
+                        attempts = attempt,
+                        error = %e,
+                        "Event webhook unreachable; event dropped"
+                    );
+                    return;
+                }
+                Err(_) => tokio::time::sleep(backoff(attempt)).await,
+            }
+        }
+    }
+
+    async fn post(&self, delivery_id: &str, body: &str) -> Result<(), String> {
+        let timestamp = Utc::now().timestamp().to_string();
+        let mut req = self
+            .client
+            .post(&self.url)
+            .header("content-type", "application/json")
+            .header("x-sovereign-delivery", delivery_id)
+            .header("x-sovereign-timestamp", &timestamp)
+            .timeout(REQUEST_TIMEOUT);
+        if let Some(secret) = &self.secret {
+            req = req.header("x-sovereign-signature", sign(secret, &timestamp, body));
+        }
+
+        match req.body(body.to_string()).send().await {
+            Ok(resp) if resp.status().is_success() => Ok(()),
+            Ok(resp) => Err(format!("HTTP {}", resp.status())),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn payload_carries_the_event_fields() {
+        let event = AppEvent::ContainerCrashed {
+            model_id: "m1".into(),
+            reason: "OOM-killed".into(),
+            gave_up: true,
+        };
+        let payload = EventPayload::for_event(&event).unwrap();
+        let json = serde_json::to_value(&payload).unwrap();
+
+        assert_eq!(json["schema"], SCHEMA);
+        assert_eq!(json["type"], "container_crashed");
+        assert_eq!(json["model_id"], "m1");
+        assert_eq!(json["gave_up"], true);
+        assert!(json["delivery_id"].as_str().unwrap().starts_with("event-"));
+        assert!(json["occurred_at"].is_string());
+    }
+
+    #[test]
+    fn periodic_updates_are_not_sent() {
+        let metrics = AppEvent::Metrics {
+            snapshot: Arc::new(serde_json::json!({ "timestamp": "now" })),
+        };
+        let download = AppEvent::DownloadUpdated {
+            download: serde_json::json!({ "id": "d1" }),
+        };
+        assert!(EventPayload::for_event(&metrics).is_none());
+        assert!(EventPayload::for_event(&download).is_none());
+        assert!(EventPayload::for_event(&AppEvent::ReservationChanged).is_some());
+    }
+}
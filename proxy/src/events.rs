@@ -0,0 +1,168 @@
+//! In-process event bus for notifications between modules.
+//!
+//! Modules publish an [`AppEvent`] when something others may react to
+//! happens — a container starts serving, a download finishes, reservations
+//! change, a backend crashes — and the metrics and download collectors
+//! publish their periodic updates the same way. Subscribers (the
+//! `/api/user/events` and download SSE streams, and the event webhook) each
+//! get their own receiver and pick the events they care about. Publishing
+//! never blocks: with no subscribers the event is dropped, and a subscriber
+//! that falls more than [`CAPACITY`] events behind skips the oldest.
+
+use std::sync::Arc;
+
+use serde::{Serialize, Serializer};
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber.
+const CAPACITY: usize = 256;
+
+/// Something that happened, for whoever is listening.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AppEvent {
+    /// A backend container was started and recorded as serving the model.
+    ModelLoaded {
+        model_id: String,
+        container: String,
+        backend_type: String,
+    },
+    /// A Hugging Face download completed and its models were registered.
+    DownloadFinished {
+        hf_repo: String,
+        model_ids: Vec<String>,
+    },
+    /// Reservations were created, changed state or were deleted. Carries no
+    /// data; subscribers re-fetch what they show.
+    ReservationChanged,
+    /// The watchdog found a loaded model's container stopped. `gave_up` is
+    /// set when it won't be restarted again and the model was unloaded.
+    ContainerCrashed {
+        model_id: String,
+        reason: String,
+        gave_up: bool,
+    },
+    /// A metrics snapshot from the collector, every couple of seconds.
+    /// Carried as JSON so the bus doesn't depend on the collector's types.
+    Metrics {
+        #[serde(serialize_with = "shared")]
+        snapshot: Arc<serde_json::Value>,
+    },
+    /// A download's progress or status changed; sent at most once per
+    /// second per download.
+    DownloadUpdated { download: serde_json::Value },
+}
+
+fn shared<S: Serializer>(value: &Arc<serde_json::Value>, s: S) -> Result<S::Ok, S::Error> {
+    value.serialize(s)
+}
+
+impl AppEvent {
+    /// Event name for SSE and logs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ModelLoaded { .. } => "model_loaded",
+            Self::DownloadFinished { .. } => "download_finished",
+            Self::ReservationChanged => "reservations_changed",
+            Self::ContainerCrashed { .. } => "container_crashed",
+            Self::Metrics { .. } => "metrics",
+            Self::DownloadUpdated { .. } => "download",
+        }
+    }
+
+    /// Whether this is a one-off notification rather than a periodic
+    /// metrics or progress update.
+    pub fn is_notification(&self) -> bool {
+        !matches!(self, Self::Metrics { .. } | Self::DownloadUpdated { .. })
+    }
+}
+
+/// Cheap to clone; clones publish to the same subscribers.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<AppEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (tx, _) = broadcast::channel(CAPACITY);
+        Self { tx }
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive every event published from now on.
+    pub fn subscribe(&self) -> broadcast::Receiver<AppEvent> {
+        self.tx.subscribe()
+    }
+
+    pub fn publish(&self, event: AppEvent) {
+        // Err only means nobody is subscribed
+        let _ = self.tx.send(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subscribers_each_receive_events() {
+        let bus = EventBus::new();
+        bus.publish(AppEvent::ReservationChanged); // nobody listening yet
+
+        let mut a = bus.subscribe();
+        let mut b = bus.clone().subscribe();
+        let crash = AppEvent::ContainerCrashed {
+            model_id: "m1".into(),
+            reason: "OOM-killed".into(),
+            gave_up: false,
+        };
+        bus.publish(crash.clone());
+
+        assert_eq!(a.try_recv().unwrap(), crash);
+        assert_eq!(b.try_recv().unwrap(), crash);
+        assert!(a.try_recv().is_err());
+    }
+
+    #[test]
+    fn serializes_with_type_tag() {
+        let event = AppEvent::DownloadFinished {
+            hf_repo: "org/repo".into(),
+            model_ids: vec!["m1".into()],
+        };
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "type": "download_finished",
+                "hf_repo": "org/repo",
+                "model_ids": ["m1"],
+            })
+        );
+        assert_eq!(event.name(), "download_finished");
+        assert!(event.is_notification());
+    }
+
+    #[test]
+    fn periodic_updates_share_the_bus() {
+        let bus = EventBus::new();
+        let mut rx = bus.subscribe();
+        let snapshot = Arc::new(serde_json::json!({ "timestamp": "now" }));
+        bus.publish(AppEvent::Metrics {
+            snapshot: snapshot.clone(),
+        });
+        bus.publish(AppEvent::ReservationChanged);
+
+        let metrics = rx.try_recv().unwrap();
+        assert!(!metrics.is_notification());
+        assert_eq!(
+            serde_json::to_value(&metrics).unwrap(),
+            serde_json::json!({ "type": "metrics", "snapshot": { "timestamp": "now" } })
+        );
+        assert_eq!(rx.try_recv().unwrap(), AppEvent::ReservationChanged);
+    }
+}
@@ -0,0 +1,166 @@
+//! Feature flags for gradual rollout of proxy changes.
+//!
+//! Flags live in the `feature_flags` table and are cached in memory, so
+//! handlers can check one per request without a query. A flag is on for a
+//! user when it is enabled and either lists the user in `user_ids` or puts
+//! them inside `rollout_percent`. Users are bucketed by a hash of the flag
+//! name and user ID, so a user's answer is stable across requests and
+//! restarts, and raising the percentage only adds users. The admin API
+//! (`api::feature_flags`) reloads the cache after every change.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+
+use crate::db::Database;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct FeatureFlag {
+    pub name: String,
+    pub description: String,
+    /// Off overrides everything else.
+    pub enabled: bool,
+    /// Share of users the flag is on for, 0–100.
+    pub rollout_percent: u8,
+    /// Users the flag is always on for while enabled.
+    pub user_ids: Vec<String>,
+    pub updated_at: String,
+}
+
+impl FeatureFlag {
+    /// Whether the flag is on for `user_id`.
+    pub fn enabled_for(&self, user_id: &str) -> bool {
+        self.enabled
+            && (self.user_ids.iter().any(|u| u == user_id)
+                || rollout_bucket(&self.name, user_id) < self.rollout_percent)
+    }
+}
+
+/// The user's position (0–99) in the flag's rollout order.
+fn rollout_bucket(flag: &str, user_id: &str) -> u8 {
+    let digest = Sha256::new()
+        .chain_update(flag.as_bytes())
+        .chain_update(b":")
+        .chain_update(user_id.as_bytes())
+        .finalize();
+    let n = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+    (n % 100) as u8
+}
+
+/// Cached flags. Cheap to clone; clones share the cache.
+#[derive(Clone, Default)]
+pub struct FeatureFlags {
+    flags: Arc<RwLock<HashMap<String, FeatureFlag>>>,
+}
+
+impl FeatureFlags {
+    /// Whether `name` is on for `user_id`. Unknown flags are off.
+    pub async fn is_enabled(&self, name: &str, user_id: &str) -> bool {
+        self.flags
+            .read()
+            .await
+            .get(name)
+            .is_some_and(|f| f.enabled_for(user_id))
+    }
+
+    /// Replace the cache with the flags in the database.
+    pub async fn reload(&self, db: &Database) -> Result<()> {
+        let flags = load_flags(db).await?;
+        *self.flags.write().await = flags.into_iter().map(|f| (f.name.clone(), f)).collect();
+        Ok(())
+    }
+}
+
+/// All flags, by name.
+pub async fn load_flags(db: &Database) -> Result<Vec<FeatureFlag>> {
+    let rows: Vec<(String, String, bool, i64, String, String)> = sqlx::query_as(
+        "SELECT name, description, enabled, rollout_percent, user_ids, updated_at \
+         FROM feature_flags ORDER BY name",
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(
+            |(name, description, enabled, rollout_percent, user_ids, updated_at)| FeatureFlag {
+                name,
+                description,
+                enabled,
+                rollout_percent: rollout_percent.clamp(0, 100) as u8,
+                user_ids: serde_json::from_str(&user_ids).unwrap_or_default(),
+                updated_at,
+            },
+        )
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(rollout_percent: u8) -> FeatureFlag {
+        FeatureFlag {
+            name: "new_usage_parser".to_string(),
+            description: String::new(),
+            enabled: true,
+            rollout_percent,
+            user_ids: vec!["pinned".to_string()],
+            updated_at: String::new(),
+        }
+    }
+
+    fn share_on(flag: &FeatureFlag) -> usize {
+        (0..1000)
+            .filter(|i| flag.enabled_for(&format!("user-{i}")))
+            .count()
+    }
+
+    #[test]
+    fn rollout_percent_bounds() {
+        assert_eq!(share_on(&flag(0)), 0);
+        assert_eq!(share_on(&flag(100)), 1000);
+        let half = share_on(&flag(50));
+        assert!((400..600).contains(&half), "{half} of 1000 users on at 50%");
+    }
+
+    #[test]
+    fn raising_rollout_only_adds_users() {
+        let (low, high) = (flag(20), flag(60));
+        for i in 0..1000 {
+            let user = format!("user-{i}");
+            assert!(!low.enabled_for(&user) || high.enabled_for(&user));
+        }
+    }
+
+    #[test]
+    fn targeted_users_and_disabled_flag() {
+        let mut f = flag(0);
+        assert!(f.enabled_for("pinned"));
+        f.enabled = false;
+        assert!(!f.enabled_for("pinned"));
+        f.rollout_percent = 100;
+        assert!(!f.enabled_for("anyone"));
+    }
+
+    #[tokio::test]
+    async fn cache_reloads_from_db() {
+        let db = Database::test_db().await;
+        let flags = FeatureFlags::default();
+        assert!(!flags.is_enabled("new_usage_parser", "u1").await);
+
+        sqlx::query(
+            "INSERT INTO feature_flags (name, enabled, rollout_percent, user_ids) \
+             VALUES ('new_usage_parser', 1, 0, '[\"u1\"]')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        flags.reload(&db).await.unwrap();
+        assert!(flags.is_enabled("new_usage_parser", "u1").await);
+        assert!(!flags.is_enabled("new_usage_parser", "u2").await);
+    }
+}
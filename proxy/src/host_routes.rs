@@ -0,0 +1,323 @@
+//! Host-header routing beyond the API and chat hostnames.
+//!
+//! `API_HOSTNAME` and `CHAT_HOSTNAME` are built in. Further hostnames live
+//! in the `host_routes` table, each pointing at a target: the API/portal
+//! app, the chat app, a static site under `SITES_PATH` (a status page,
+//! docs), or a redirect (vanity hosts). The table is cached in memory and
+//! reloaded by the admin API (`api::host_routes`) after every change, so
+//! hosts can be added without a restart. The certificate must cover them;
+//! ACME only requests the two built-in hostnames.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::extract::Request;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::Router;
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+use crate::db::Database;
+
+/// What serves a hostname.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostTarget {
+    /// The API, portal and `/v1` routes (`api`).
+    Api,
+    /// Open WebUI (`chat`).
+    Chat,
+    /// A directory under `SITES_PATH` (`site:<name>`).
+    Site(String),
+    /// Redirect to this URL, keeping the path and query (`redirect:<url>`).
+    Redirect(String),
+}
+
+impl FromStr for HostTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "api" => Ok(Self::Api),
+            None if s == "chat" => Ok(Self::Chat),
+            Some(("site", name)) => {
+                let valid = !name.is_empty()
+                    && !name.starts_with('.')
+                    && name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+                if valid {
+                    Ok(Self::Site(name.to_string()))
+                } else {
+                    Err("Site names may only contain letters, digits, '_', '-' and '.'".into())
+                }
+            }
+            Some(("redirect", url)) => {
+                if (url.starts_with("https://") || url.starts_with("http://"))
+                    && reqwest::Url::parse(url).is_ok()
+                {
+                    Ok(Self::Redirect(url.trim_end_matches('/').to_string()))
+                } else {
+                    Err("Redirect targets must be http(s) URLs".into())
+                }
+            }
+            _ => Err("Target must be api, chat, site:<name> or redirect:<url>".into()),
+        }
+    }
+}
+
+impl fmt::Display for HostTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Api => f.write_str("api"),
+            Self::Chat => f.write_str("chat"),
+            Self::Site(name) => write!(f, "site:{name}"),
+            Self::Redirect(url) => write!(f, "redirect:{url}"),
+        }
+    }
+}
+
+/// A row of the `host_routes` table.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct HostRoute {
+    pub hostname: String,
+    pub target: String,
+    pub description: String,
+    pub created_by: Option<String>,
+    pub updated_at: String,
+}
+
+/// Hostnames are matched lowercased, without a port.
+pub fn normalize_host(host: &str) -> String {
+    host.split(':').next().unwrap_or("").to_ascii_lowercase()
+}
+
+/// Cached routing table. Cheap to clone; clones share the cache.
+#[derive(Clone, Default)]
+pub struct HostRoutes {
+    routes: Arc<RwLock<HashMap<String, HostTarget>>>,
+}
+
+impl HostRoutes {
+    /// The target for `host`, if the table has one.
+    pub async fn lookup(&self, host: &str) -> Option<HostTarget> {
+        self.routes.read().await.get(host).cloned()
+    }
+
+    /// Replace the cache with the routes in the database. Rows with a
+    /// target that no longer parses are left out.
+    pub async fn reload(&self, db: &Database) -> Result<()> {
+        let routes = load_routes(db)
+            .await?
+            .into_iter()
+            .filter_map(|r| Some((normalize_host(&r.hostname), r.target.parse().ok()?)))
+            .collect();
+        *self.routes.write().await = routes;
+        Ok(())
+    }
+}
+
+/// All routes, by hostname.
+pub async fn load_routes(db: &Database) -> Result<Vec<HostRoute>> {
+    Ok(sqlx::query_as(
+        "SELECT hostname, target, description, created_by, updated_at \
+         FROM host_routes ORDER BY hostname",
+    )
+    .fetch_all(&db.pool)
+    .await?)
+}
+
+/// Routes requests to an app by Host header: the built-in hostnames, then
+/// the table. Hosts in neither go to `fallback`, or get 421 without one.
+#[derive(Clone)]
+pub struct HostDispatch {
+    pub api: Router,
+    pub chat: Router,
+    pub api_hostname: String,
+    pub chat_hostname: String,
+    pub routes: HostRoutes,
+    pub sites_path: PathBuf,
+    pub fallback: Option<Router>,
+}
+
+impl HostDispatch {
+    pub async fn serve(self, req: Request) -> Response {
+        let host = normalize_host(
+            req.headers()
+                .get("host")
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or(""),
+        );
+        let target = if host == self.chat_hostname {
+            Some(HostTarget::Chat)
+        } else if host == self.api_hostname {
+            Some(HostTarget::Api)
+        } else {
+            self.routes.lookup(&host).await
+        };
+
+        match target {
+            Some(HostTarget::Api) => self.api.oneshot(req).await.into_response(),
+            Some(HostTarget::Chat) => self.chat.oneshot(req).await.into_response(),
+            Some(HostTarget::Site(name)) => {
+                tower_http::services::ServeDir::new(self.sites_path.join(name))
+                    .oneshot(req)
+                    .await
+                    .into_response()
+            }
+            Some(HostTarget::Redirect(url)) => {
+                let path = req
+                    .uri()
+                    .path_and_query()
+                    .map(|p| p.as_str())
+                    .unwrap_or("/");
+                Redirect::temporary(&format!("{url}{path}")).into_response()
+            }
+            None => match self.fallback {
+                Some(router) => router.oneshot(req).await.into_response(),
+                None => {
+                    (StatusCode::MISDIRECTED_REQUEST, "421 Misdirected Request").into_response()
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+
+    #[test]
+    fn targets_round_trip() {
+        for target in [
+            "api",
+            "chat",
+            "site:status",
+            "redirect:https://example.com/docs",
+        ] {
+            let parsed: HostTarget = target.parse().unwrap();
+            assert_eq!(parsed.to_string(), target);
+        }
+        assert_eq!(
+            "redirect:https://example.com/".parse::<HostTarget>(),
+            Ok(HostTarget::Redirect("https://example.com".into()))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_targets() {
+        for target in [
+            "",
+            "portal",
+            "site:",
+            "site:../etc",
+            "site:a/b",
+            "redirect:ftp://example.com",
+            "redirect:not a url",
+        ] {
+            assert!(target.parse::<HostTarget>().is_err(), "{target}");
+        }
+    }
+
+    fn dispatch(routes: HostRoutes, fallback: Option<Router>) -> HostDispatch {
+        HostDispatch {
+            api: Router::new().route("/", get(|| async { "api" })),
+            chat: Router::new().route("/", get(|| async { "chat" })),
+            api_hostname: "api.example.com".into(),
+            chat_hostname: "chat.example.com".into(),
+            routes,
+            sites_path: PathBuf::from("/tmp/test-sites"),
+            fallback,
+        }
+    }
+
+    async fn get_host(dispatch: &HostDispatch, host: &str, uri: &str) -> Response {
+        let req = Request::builder()
+            .uri(uri)
+            .header("host", host)
+            .body(Body::empty())
+            .unwrap();
+        dispatch.clone().serve(req).await
+    }
+
+    async fn text(resp: Response) -> String {
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn dispatches_by_host() {
+        let routes = HostRoutes::default();
+        routes.routes.write().await.extend([
+            ("llm.acme.test".to_string(), HostTarget::Api),
+            (
+                "docs.example.com".to_string(),
+                HostTarget::Redirect("https://docs.example.org".into()),
+            ),
+        ]);
+        let d = dispatch(routes, None);
+
+        assert_eq!(
+            text(get_host(&d, "API.example.com:443", "/").await).await,
+            "api"
+        );
+        assert_eq!(
+            text(get_host(&d, "chat.example.com", "/").await).await,
+            "chat"
+        );
+        assert_eq!(text(get_host(&d, "llm.acme.test", "/").await).await, "api");
+
+        let resp = get_host(&d, "docs.example.com", "/guide?x=1").await;
+        assert_eq!(resp.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(
+            resp.headers()["location"],
+            "https://docs.example.org/guide?x=1"
+        );
+
+        let resp = get_host(&d, "unknown.example.com", "/").await;
+        assert_eq!(resp.status(), StatusCode::MISDIRECTED_REQUEST);
+
+        let d = dispatch(HostRoutes::default(), Some(d.api.clone()));
+        assert_eq!(
+            text(get_host(&d, "unknown.example.com", "/").await).await,
+            "api"
+        );
+    }
+
+    #[tokio::test]
+    async fn serves_sites_and_reloads_from_db() {
+        let dir = PathBuf::from("/tmp/test-sites/status");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("index.html"), "all systems go").unwrap();
+
+        let db = Database::test_db().await;
+        sqlx::query(
+            "INSERT INTO host_routes (hostname, target) VALUES ('status.example.com', 'site:status')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        let routes = HostRoutes::default();
+        routes.reload(&db).await.unwrap();
+        let d = dispatch(routes, None);
+
+        let resp = get_host(&d, "status.example.com", "/").await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(text(resp).await, "all systems go");
+        assert_eq!(
+            get_host(&d, "status.example.com", "/missing.html")
+                .await
+                .status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+}
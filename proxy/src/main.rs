@@ -1,12 +1,25 @@
+mod access_log;
 mod api;
 mod auth;
 mod config;
 mod db;
 mod docker;
+mod event_webhook;
+mod events;
+mod feature_flags;
+mod host_routes;
 mod metrics;
+mod profiles;
 mod proxy;
 mod scheduler;
+mod secrets;
+mod storage;
+mod tasks;
 mod tls;
+mod ui_integrity;
+mod usage_webhook;
+mod vector_store;
+mod watchdog;
 
 #[cfg(test)]
 mod admin_tests;
@@ -14,18 +27,22 @@ mod admin_tests;
 mod meta_token_tests;
 #[cfg(test)]
 mod reservation_tests;
+#[cfg(test)]
+mod snapshot_tests;
+#[cfg(test)]
+mod test_harness;
 
 use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
 use anyhow::Result;
 use axum::extract::DefaultBodyLimit;
-use axum::http::{HeaderValue, Method, StatusCode};
+use axum::http::{HeaderValue, Method};
 use axum::middleware;
-use axum::response::{IntoResponse, Response};
+use axum::response::Response;
 use axum::Router;
 use base64::Engine;
 use sha2::{Digest, Sha256};
-use tower::ServiceExt;
 use tower_http::compression::CompressionLayer;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
@@ -35,12 +52,17 @@ use tracing::{error, info, warn};
 /// Falls back to a hardcoded hash when the UI bundle is absent (dev mode).
 static CSP_HEADER: OnceLock<String> = OnceLock::new();
 
-use crate::config::AppConfig;
+use crate::config::{AppConfig, SecretProvider};
 use crate::db::Database;
 use crate::docker::DockerManager;
-use crate::metrics::MetricsBroadcaster;
-use crate::scheduler::reservation::ReservationBroadcaster;
+use crate::events::{AppEvent, EventBus};
+use crate::proxy::pool::{BackendClients, PoolSettings};
+use crate::scheduler::resolver::NameMatching;
 use crate::scheduler::Scheduler;
+use crate::tasks::{Task, TaskScheduler};
+
+/// How long shutdown waits for background task runs in progress.
+const TASK_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// Shared application state available to all handlers.
 pub struct AppState {
@@ -48,8 +70,38 @@ pub struct AppState {
     pub db: Database,
     pub docker: DockerManager,
     pub scheduler: Scheduler,
-    pub metrics: MetricsBroadcaster,
-    pub reservations: ReservationBroadcaster,
+    /// Cross-module notifications (see `events`).
+    pub events: EventBus,
+    pub backends: BackendClients,
+    /// Set when JWT access tokens are enabled.
+    pub jwt: Option<auth::jwt::JwtIssuer>,
+    pub signed_urls: auth::signed_url::UrlSigner,
+    pub token_cache: auth::token_cache::TokenCache,
+    /// Portal session store (SQLite, or Redis with SESSION_REDIS_URL).
+    pub sessions: auth::sessions::Sessions,
+    /// Periodic background tasks and their last-run status.
+    pub tasks: TaskScheduler,
+    /// Cached feature flags for gradual rollouts.
+    pub flags: feature_flags::FeatureFlags,
+    /// Cached routing table for hostnames beyond API and chat.
+    pub host_routes: host_routes::HostRoutes,
+    /// Restart bookkeeping and alerts of the backend watchdog.
+    pub watchdog: watchdog::Watchdog,
+    /// Latest backend warm-up per model.
+    pub warmups: api::warmup::Warmups,
+    /// When each running download last made progress.
+    pub download_progress: api::hf::DownloadProgress,
+    /// Models with a blue/green replace in progress.
+    pub replacing: api::container_replace::Replacing,
+    /// Settings the server started with and where each came from; None in
+    /// tests.
+    pub effective_config: Option<profiles::EffectiveConfig>,
+    /// S3 model store and local model directory locks.
+    pub storage: storage::Storage,
+    /// Per-model locks held while an autoload cold-starts a container.
+    pub autoloads: api::common::Autoloads,
+    /// UI bundle hashes from startup and tampering found since.
+    pub ui_integrity: ui_integrity::UiIntegrity,
 }
 
 #[tokio::main]
@@ -57,6 +109,11 @@ async fn main() -> Result<()> {
     // Load .env if present (not required)
     dotenvy::dotenv().ok();
 
+    // Fill in settings the environment leaves unset from CONFIG_PROFILE,
+    // before anything (including the log filter) reads them
+    let profile = profiles::Profile::from_env()?;
+    let from_profile = profile.as_ref().map(|p| p.apply()).unwrap_or_default();
+
     // Initialize tracing
     tracing_subscriber::fmt()
         .with_env_filter(
@@ -66,10 +123,23 @@ async fn main() -> Result<()> {
         .init();
 
     info!("Starting Sovereign Engine v{}", env!("CARGO_PKG_VERSION"));
+    if let Some(profile) = &profile {
+        info!(profile = %profile.name, path = %profile.path.display(), applied = from_profile.len(), "Loaded config profile");
+    }
 
     // Load configuration
-    let config = AppConfig::from_env()?;
+    let mut config = AppConfig::from_env()?;
+    let secret_source = config::SecretSource::from_env()?;
+    let mut from_secrets = Vec::new();
+    if let Some(source) = &secret_source {
+        from_secrets = config.load_secrets(source).await?;
+        info!(provider = source.name(), keys = ?from_secrets, "Loaded secrets from secret manager");
+    }
+    let effective_config =
+        profiles::EffectiveConfig::collect(profile.as_ref(), &from_profile, &from_secrets);
+    effective_config.log_warnings();
     info!(listen_addr = %config.listen_addr, "Configuration loaded");
+    let model_storage = storage::Storage::from_config(&config)?;
 
     // Initialize database
     let db = Database::connect(&config.database_url).await?;
@@ -88,23 +158,59 @@ async fn main() -> Result<()> {
 
     // Pull backend images in the background (non-blocking)
     docker.pull_backend_images().await;
+    if config.gpu_probe {
+        let docker = docker.clone();
+        tokio::spawn(async move {
+            docker.probe_gpus().await;
+        });
+    }
+    if config.sandbox_enabled {
+        docker.remove_stale_sandboxes().await;
+        docker.pull_sandbox_image(&config.sandbox_image);
+    }
 
     // Initialize scheduler and load settings from DB
-    let scheduler = Scheduler::new();
+    let scheduler = Scheduler::new().with_name_matching(NameMatching {
+        normalize: config.model_name_normalize,
+        strip_prefixes: config.model_name_strip_prefixes.clone(),
+        suggestions: config.model_name_suggestions,
+    });
     if let Err(e) = scheduler.reload_settings(&db).await {
         warn!("Failed to load scheduler settings from DB: {e}");
     }
+    if let Err(e) = scheduler.reload_category_limits(&db).await {
+        warn!("Failed to load category limits from DB: {e}");
+    }
 
     // Recover concurrency gate state from DB for any containers still running
     recover_gate_state(&scheduler, &db).await;
 
+    // Requests still queued when the previous process exited were dropped
+    match scheduler::queue_log::recover_interrupted(&db).await {
+        Ok(n) if n > 0 => warn!(count = n, "Marked queued requests interrupted by restart"),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to recover queue log: {e}"),
+    }
+
+    // Downloads running when the previous process exited can be resumed
+    match api::hf::recover_interrupted_downloads(&db).await {
+        Ok(n) if n > 0 => warn!(count = n, "Marked downloads interrupted by restart"),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to recover downloads: {e}"),
+    }
+
     // NOTE: active reservation recovery happens after Arc<AppState> is built (below)
 
-    // Initialize metrics broadcaster
-    let metrics = MetricsBroadcaster::new();
+    let flags = feature_flags::FeatureFlags::default();
+    if let Err(e) = flags.reload(&db).await {
+        warn!("Failed to load feature flags: {e}");
+    }
+    let host_routes = host_routes::HostRoutes::default();
+    if let Err(e) = host_routes.reload(&db).await {
+        warn!("Failed to load host routes: {e}");
+    }
 
-    // Initialize reservation change broadcaster
-    let reservations_broadcaster = ReservationBroadcaster::new();
+    let sessions = auth::sessions::Sessions::from_config(&config, &db)?;
 
     // Build shared state
     let state = Arc::new(AppState {
@@ -112,33 +218,92 @@ async fn main() -> Result<()> {
         db,
         docker,
         scheduler,
-        metrics,
-        reservations: reservations_broadcaster,
+        events: EventBus::new(),
+        backends: BackendClients::new(PoolSettings::from_config(&config)),
+        jwt: auth::jwt::JwtIssuer::from_config(&config),
+        signed_urls: auth::signed_url::UrlSigner::from_config(&config),
+        token_cache: auth::token_cache::TokenCache::from_config(&config),
+        sessions,
+        tasks: TaskScheduler::new(),
+        flags,
+        host_routes,
+        watchdog: watchdog::Watchdog::default(),
+        warmups: api::warmup::Warmups::default(),
+        download_progress: api::hf::DownloadProgress::default(),
+        replacing: api::container_replace::Replacing::default(),
+        effective_config: Some(effective_config),
+        storage: model_storage,
+        autoloads: api::common::Autoloads::default(),
+        ui_integrity: ui_integrity::UiIntegrity::new(&config.ui_path),
     });
+    let tasks = &state.tasks;
 
-    // Start background metrics collection (broadcasts every 2s)
-    state.metrics.spawn_collector(
+    // Start background metrics collection (publishes every 2s)
+    metrics::spawn_collector(
+        state.events.clone(),
         state.docker.clone(),
         state.scheduler.clone(),
         state.config.model_path.clone(),
+        state.db.pool.clone(),
+        state.warmups.clone(),
+        state.config.memory_critical_percent,
     );
 
     // Recover active reservation from DB (if proxy restarted during a reservation)
     scheduler::reservation::recover_active_reservation(&state.db.pool, &state.scheduler).await;
 
-    // Spawn reservation tick task (every 30s)
+    // Refresh queue ETA history (every 60s, first run immediately)
+    {
+        let db = state.db.clone();
+        let sched = state.scheduler.clone();
+        tasks.spawn(
+            Task::every("queue_timings", Duration::from_secs(60)).immediate(),
+            move || {
+                let db = db.clone();
+                let sched = sched.clone();
+                async move { sched.refresh_timings(&db).await }
+            },
+        );
+    }
+
+    // Stop idle containers (every 60s)
+    {
+        let state = state.clone();
+        tasks.spawn(
+            Task::every("idle_unload", Duration::from_secs(60)).with_jitter(Duration::from_secs(5)),
+            move || {
+                let state = state.clone();
+                async move {
+                    api::common::unload_idle_models(&state).await;
+                    Ok(())
+                }
+            },
+        );
+    }
+
+    // Activate and expire reservations (every 30s)
     {
         let pool = state.db.pool.clone();
         let sched = state.scheduler.clone();
-        let res_broadcaster = state.reservations.clone();
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
-            interval.tick().await; // first tick is immediate — skip it
-            loop {
-                interval.tick().await;
-                scheduler::reservation::tick_reservations(&pool, &sched, &res_broadcaster).await;
-            }
-        });
+        let events = state.events.clone();
+        tasks.spawn(
+            Task::every("reservation_tick", Duration::from_secs(30)),
+            move || {
+                let pool = pool.clone();
+                let sched = sched.clone();
+                let events = events.clone();
+                async move {
+                    scheduler::reservation::tick_reservations(&pool, &sched, &events).await;
+                    // Pending requests the tick cancelled may have freed waitlisted slots
+                    let settings = sched.settings().await;
+                    let promoted = api::reservation::promote_waitlist(&pool, &settings).await?;
+                    if !promoted.is_empty() {
+                        events.publish(AppEvent::ReservationChanged);
+                    }
+                    Ok(())
+                }
+            },
+        );
     }
 
     // Warn about insecure bootstrap credential defaults
@@ -168,58 +333,283 @@ async fn main() -> Result<()> {
         warn!("DB_ENCRYPTION_KEY not set — IdP client secrets stored in plaintext");
     }
 
-    // Spawn hourly session/state cleanup
+    // Re-read secrets on SIGHUP
+    if let Some(source) = secret_source {
+        tokio::spawn(watch_secret_rotation(source, state.clone()));
+    }
+
+    // Push hourly usage deltas to the billing webhook (if configured)
+    usage_webhook::UsageWebhook::spawn(&config, state.db.clone(), tasks);
+
+    // Forward event bus notifications to the event webhook (if configured)
+    event_webhook::EventWebhook::spawn(&config, &state.events);
+
+    // Restart crashed backend containers (every WATCHDOG_INTERVAL_SECS)
+    watchdog::spawn(state.clone(), tasks);
+
+    // Hourly session/state cleanup
     {
         let db = state.db.clone();
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
-            interval.tick().await; // first tick is immediate — skip it
-            loop {
-                interval.tick().await;
-                if let Ok(n) = auth::sessions::cleanup_expired(&db).await {
-                    if n > 0 {
-                        info!(deleted = n, "Cleaned up expired sessions");
+        let sessions = state.sessions.clone();
+        let archive_retention_days = config.prompt_archive_retention_days;
+        let request_log_retention_days = config.request_log_retention_days;
+        tasks.spawn(
+            Task::every("cleanup", Duration::from_secs(3600)).with_jitter(Duration::from_secs(60)),
+            move || {
+                let db = db.clone();
+                let sessions = sessions.clone();
+                async move {
+                    hourly_cleanup(
+                        &db,
+                        &sessions,
+                        archive_retention_days,
+                        request_log_retention_days,
+                    )
+                    .await;
+                    Ok(())
+                }
+            },
+        );
+    }
+
+    // Delete models unused for `model_evict_unused_days` from disk (hourly)
+    {
+        let state = state.clone();
+        tasks.spawn(
+            Task::every("model_eviction", Duration::from_secs(3600))
+                .with_jitter(Duration::from_secs(60)),
+            move || {
+                let state = state.clone();
+                async move {
+                    let days = state.scheduler.settings().await.model_evict_unused_days;
+                    if days > 0 {
+                        let model_path = std::path::Path::new(&state.config.model_path);
+                        state
+                            .storage
+                            .evict_unused(&state.db, model_path, days)
+                            .await?;
                     }
+                    Ok(())
                 }
-                // Also clean expired OIDC auth state
-                let _ =
-                    sqlx::query("DELETE FROM oidc_auth_state WHERE expires_at < datetime('now')")
-                        .execute(&db.pool)
-                        .await;
-            }
-        });
+            },
+        );
+    }
+
+    // Upload models the S3 model store doesn't have yet, and keep the
+    // local cache under MODEL_CACHE_MAX_GB (hourly)
+    if state.storage.is_remote() {
+        state.storage.spawn_backfill(state.db.clone());
+        let db = state.db.clone();
+        let storage = state.storage.clone();
+        tasks.spawn(
+            Task::every("model_cache", Duration::from_secs(3600))
+                .with_jitter(Duration::from_secs(60)),
+            move || {
+                let db = db.clone();
+                let storage = storage.clone();
+                async move {
+                    storage.evict(&db).await;
+                    Ok(())
+                }
+            },
+        );
     }
 
     // Compute CSP hashes from built index.html (or fall back to hardcoded)
     init_csp_header(&config.ui_path);
 
+    // Watch the UI bundle hashed at startup for changes while running
+    state
+        .ui_integrity
+        .spawn_watcher(tasks, Duration::from_secs(60));
+
+    // Start the structured access log writer (if configured)
+    let access_log = access_log::AccessLog::spawn(&config).await;
+
     // Build router
-    let app = build_router(state.clone());
+    let app = build_router(state.clone(), access_log);
 
     // Start server
     let addr = config.listen_addr.parse::<std::net::SocketAddr>()?;
 
-    if let Some(acme) = config.acme_config()? {
-        if config.tls_cert_path.is_some() || config.tls_key_path.is_some() {
-            warn!("ACME is enabled — ignoring TLS_CERT_PATH/TLS_KEY_PATH");
+    let acme = config.acme_config()?;
+    let serve = async {
+        if let Some(acme) = acme {
+            if config.tls_cert_path.is_some() || config.tls_key_path.is_some() {
+                warn!("ACME is enabled — ignoring TLS_CERT_PATH/TLS_KEY_PATH");
+            }
+            info!(
+                "Starting HTTPS server on {} with ACME (domains: {:?})",
+                addr, acme.domains
+            );
+            tls::serve_acme(app, addr, &acme.domains, &acme.contact, acme.staging).await
+        } else if config.tls_cert_path.is_some() && config.tls_key_path.is_some() {
+            info!("Starting HTTPS server on {} with manual TLS", addr);
+            tls::serve_tls(app, addr, &config).await
+        } else {
+            info!("Starting HTTP server on {} (no TLS configured)", addr);
+            let listener = tokio::net::TcpListener::bind(addr).await?;
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+            )
+            .await?;
+            Ok(())
+        }
+    };
+
+    let result = tokio::select! {
+        result = serve => result,
+        _ = shutdown_signal() => {
+            info!("Shutdown signal received");
+            Ok(())
+        }
+    };
+    // Let background task runs in progress (cleanup, reservation tick) finish
+    state.tasks.shutdown(TASK_SHUTDOWN_TIMEOUT).await;
+    result
+}
+
+/// Resolves on Ctrl-C, or SIGTERM on Unix.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!(error = %e, "Failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                warn!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Hourly cleanup of expired sessions, auth state and retained history.
+async fn hourly_cleanup(
+    db: &Database,
+    sessions: &auth::sessions::Sessions,
+    archive_retention_days: i64,
+    request_log_retention_days: i64,
+) {
+    if let Ok(n) = sessions.cleanup_expired().await {
+        if n > 0 {
+            info!(deleted = n, "Cleaned up expired sessions");
+        }
+    }
+    if let Ok(n) = auth::devices::cleanup_expired(db, sessions).await {
+        if n > 0 {
+            info!(deleted = n, "Cleaned up expired trusted devices");
+        }
+    }
+    // Also clean expired OIDC auth state
+    let _ = sqlx::query("DELETE FROM oidc_auth_state WHERE expires_at < datetime('now')")
+        .execute(&db.pool)
+        .await;
+    // Keep a week of queue history for wait statistics
+    let _ = scheduler::queue_log::prune(db, 7).await;
+    match api::model_trash::purge_expired(db).await {
+        Ok(n) if n > 0 => info!(purged = n, "Purged expired model trash entries"),
+        Ok(_) => {}
+        Err(e) => warn!(error = %e, "Failed to purge model trash"),
+    }
+    if archive_retention_days > 0 {
+        if let Ok(n) = api::archive::prune(db, archive_retention_days).await {
+            if n > 0 {
+                info!(deleted = n, "Pruned expired prompt archive entries");
+            }
+        }
+    }
+    if request_log_retention_days > 0 {
+        if let Ok(n) = api::request_log::prune(db, request_log_retention_days).await {
+            if n > 0 {
+                info!(deleted = n, "Pruned expired request log entries");
+            }
         }
-        info!(
-            "Starting HTTPS server on {} with ACME (domains: {:?})",
-            addr, acme.domains
-        );
-        tls::serve_acme(app, addr, &acme.domains, &acme.contact, acme.staging).await?;
-    } else if config.tls_cert_path.is_some() && config.tls_key_path.is_some() {
-        info!("Starting HTTPS server on {} with manual TLS", addr);
-        tls::serve_tls(app, addr, &config).await?;
-    } else {
-        info!("Starting HTTP server on {} (no TLS configured)", addr);
-        let listener = tokio::net::TcpListener::bind(addr).await?;
-        axum::serve(listener, app).await?;
     }
+    if let Ok(n) = api::prompt_uploads::prune(db).await {
+        if n > 0 {
+            info!(deleted = n, "Removed expired prompt uploads");
+        }
+    }
+    if let Ok(n) = api::model_trace::prune(db).await {
+        if n > 0 {
+            info!(deleted = n, "Removed finished model traces");
+        }
+    }
+    match api::elevation::expire(db).await {
+        Ok(n) if n > 0 => info!(expired = n, "Expired admin elevations"),
+        Ok(_) => {}
+        Err(e) => warn!(error = %e, "Failed to expire admin elevations"),
+    }
+}
+
+/// Re-fetch secrets from the secret manager on each SIGHUP. A new
+/// WEBUI_API_KEY replaces the internal token straight away; a new
+/// DB_ENCRYPTION_KEY needs a restart, which re-encrypts stored secrets.
+#[cfg(unix)]
+async fn watch_secret_rotation(source: config::SecretSource, state: Arc<AppState>) {
+    use tokio::signal::unix::{signal, SignalKind};
 
-    Ok(())
+    let mut current = state.config.clone();
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            error!(error = %e, "Failed to listen for SIGHUP; secrets won't be re-read");
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        let mut fresh = current.clone();
+        if let Err(e) = fresh.load_secrets(&source).await {
+            error!(error = %e, "Failed to re-read secrets");
+            continue;
+        }
+        if fresh.webui_api_key != current.webui_api_key {
+            match auth::tokens::ensure_internal_token(&fresh, &state.db).await {
+                Ok(revoked) => {
+                    for id in &revoked {
+                        auth::token_changed(&state, id);
+                    }
+                    info!(target: "audit", action = "secrets.rotate", actor = "system", resource = "WEBUI_API_KEY", provider = source.name(), "Rotated the internal API key")
+                }
+                Err(e) => {
+                    error!(error = %e, "Failed to provision the rotated WEBUI_API_KEY");
+                    continue;
+                }
+            }
+        }
+        if fresh.db_encryption_key != current.db_encryption_key {
+            warn!(
+                provider = source.name(),
+                "DB_ENCRYPTION_KEY changed; restart with the previous key as DB_ENCRYPTION_KEY_OLD to re-encrypt stored secrets"
+            );
+            // Keep reporting it until the restart
+            fresh.db_encryption_key = current.db_encryption_key.clone();
+        }
+        info!(provider = source.name(), "Re-read secrets");
+        current = fresh;
+    }
 }
 
+#[cfg(not(unix))]
+async fn watch_secret_rotation(_source: config::SecretSource, _state: Arc<AppState>) {}
+
 /// Backfill GGUF architecture metadata for models that have NULL metadata columns.
 /// Scans GGUF files on disk and updates the DB.
 async fn backfill_gguf_metadata(db: &Database, config: &AppConfig) {
@@ -311,32 +701,55 @@ async fn recover_gate_state(scheduler: &Scheduler, db: &Database) {
     info!(count = rows.len(), "Gate state recovered from DB");
 }
 
-fn build_router(state: Arc<AppState>) -> Router {
-    // OIDC auth routes (no auth required)
-    let auth_routes = auth::oidc::routes(state.clone());
+fn build_router(state: Arc<AppState>, access_log: Option<access_log::AccessLog>) -> Router {
+    // OIDC auth routes (no auth required, so throttled per client IP)
+    let auth_routes = match auth::rate_limit::AuthRateLimiter::from_config(&state.config) {
+        Some(limiter) => auth::oidc::routes(state.clone()).layer(middleware::from_fn_with_state(
+            limiter,
+            auth::rate_limit::middleware,
+        )),
+        None => auth::oidc::routes(state.clone()),
+    };
+
+    // Portal API routes (session auth required). `tag_identity` sits inside
+    // each auth layer so the access log can attribute the request. Signed
+    // download URLs are checked first and stand in for a session.
+    let api_routes = api::routes(state.clone())
+        .layer(middleware::from_fn(access_log::tag_identity))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::session_auth_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::signed_url::middleware,
+        ));
 
-    // Portal API routes (session auth required)
-    let api_routes = api::routes(state.clone()).layer(middleware::from_fn_with_state(
-        state.clone(),
-        auth::session_auth_middleware,
-    ));
+    // SCIM provisioning (authenticated by per-IdP SCIM tokens)
+    let scim_routes = auth::scim::routes(state.clone());
 
     // OpenAI-compatible routes (bearer token auth required)
-    let openai_routes = api::openai::routes(state.clone()).layer(middleware::from_fn_with_state(
-        state.clone(),
-        auth::bearer_auth_middleware,
-    ));
+    let openai_routes = api::openai::routes(state.clone())
+        .layer(middleware::from_fn(access_log::tag_identity))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::bearer_auth_middleware,
+        ));
 
     // Anthropic-compatible routes (bearer token auth required)
-    let anthropic_routes = api::anthropic::routes(state.clone()).layer(
-        middleware::from_fn_with_state(state.clone(), auth::bearer_auth_middleware),
-    );
+    let anthropic_routes = api::anthropic::routes(state.clone())
+        .layer(middleware::from_fn(access_log::tag_identity))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::bearer_auth_middleware,
+        ));
 
     let ui_path = state.config.ui_path.clone();
 
     // Open WebUI reverse proxy (session auth with redirect for browsers).
     let webui_fallback = Router::new()
         .fallback(proxy::webui::webui_proxy_handler)
+        .layer(middleware::from_fn(access_log::tag_identity))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             auth::session_auth_redirect_middleware,
@@ -344,79 +757,77 @@ fn build_router(state: Arc<AppState>) -> Router {
         .with_state(state.clone());
 
     let shared_layers = |router: Router| -> Router {
-        router
-            .layer(DefaultBodyLimit::max(10 * 1024 * 1024)) // 10 MB
+        let router = router
+            .layer(DefaultBodyLimit::max(api::compat::MAX_BODY_BYTES))
             .layer(middleware::from_fn(security_headers))
             .layer(TraceLayer::new_for_http())
             .layer(CompressionLayer::new())
-            .layer(build_cors_layer(&state.config))
+            .layer(build_cors_layer(&state.config));
+        // Outermost, so byte counts are what actually went over the wire
+        match &access_log {
+            Some(log) => router.layer(middleware::from_fn_with_state(
+                log.clone(),
+                access_log::middleware,
+            )),
+            None => router,
+        }
     };
 
-    // When both hostnames are the same (dev mode / unconfigured), build a combined
-    // router that preserves the pre-subdomain layout: API routes + Open WebUI fallback.
-    if state.config.api_hostname == state.config.chat_hostname {
-        return shared_layers(
-            Router::new()
-                .nest("/auth", auth_routes)
-                .nest("/api", api_routes)
-                .nest("/v1", openai_routes)
-                .nest("/v1", anthropic_routes)
-                .nest_service(
-                    "/portal",
-                    tower_http::services::ServeDir::new(&ui_path).fallback(
-                        tower_http::services::ServeFile::new(format!("{}/index.html", ui_path)),
-                    ),
-                )
-                .fallback_service(webui_fallback),
-        );
-    }
-
-    // Subdomain mode: separate API and Chat routers dispatched by Host header.
-    let api_router = Router::new()
-        .route(
-            "/",
-            axum::routing::get(|| async { axum::response::Redirect::permanent("/portal/") }),
+    let portal = || {
+        tower_http::services::ServeDir::new(&ui_path).fallback(
+            tower_http::services::ServeFile::new(format!("{}/index.html", ui_path)),
         )
-        .nest("/auth", auth_routes)
-        .nest("/api", api_routes)
-        .nest("/v1", openai_routes)
-        .nest("/v1", anthropic_routes)
-        .nest_service(
-            "/portal",
-            tower_http::services::ServeDir::new(&ui_path).fallback(
-                tower_http::services::ServeFile::new(format!("{}/index.html", ui_path)),
-            ),
-        );
+    };
 
-    let api_hostname = state.config.api_hostname.clone();
-    let chat_hostname = state.config.chat_hostname.clone();
+    // When both hostnames are the same (dev mode / unconfigured), build a combined
+    // router that preserves the pre-subdomain layout: API routes + Open WebUI fallback.
+    // Hosts in the routing table still get their own target; every other host
+    // gets the combined router.
+    let dispatch = if state.config.api_hostname == state.config.chat_hostname {
+        let combined = Router::new()
+            .nest("/auth", auth_routes)
+            .nest("/api", api_routes)
+            .nest("/scim/v2", scim_routes)
+            .nest("/v1", openai_routes)
+            .nest("/v1", anthropic_routes)
+            .nest_service("/portal", portal())
+            .fallback_service(webui_fallback);
+        host_routes::HostDispatch {
+            api: combined.clone(),
+            chat: combined.clone(),
+            api_hostname: host_routes::normalize_host(&state.config.api_hostname),
+            chat_hostname: host_routes::normalize_host(&state.config.chat_hostname),
+            routes: state.host_routes.clone(),
+            sites_path: state.config.sites_path.clone().into(),
+            fallback: Some(combined),
+        }
+    } else {
+        // Subdomain mode: separate API and Chat routers dispatched by Host header.
+        let api_router = Router::new()
+            .route(
+                "/",
+                axum::routing::get(|| async { axum::response::Redirect::permanent("/portal/") }),
+            )
+            .nest("/auth", auth_routes)
+            .nest("/api", api_routes)
+            .nest("/scim/v2", scim_routes)
+            .nest("/v1", openai_routes)
+            .nest("/v1", anthropic_routes)
+            .nest_service("/portal", portal());
+        host_routes::HostDispatch {
+            api: api_router,
+            chat: webui_fallback,
+            api_hostname: host_routes::normalize_host(&state.config.api_hostname),
+            chat_hostname: host_routes::normalize_host(&state.config.chat_hostname),
+            routes: state.host_routes.clone(),
+            sites_path: state.config.sites_path.clone().into(),
+            fallback: None,
+        }
+    };
 
     shared_layers(
         Router::new()
-            .fallback(move |req: axum::extract::Request| {
-                let api_router = api_router.clone();
-                let chat_router = webui_fallback.clone();
-                let api_host = api_hostname.clone();
-                let chat_host = chat_hostname.clone();
-                async move {
-                    let host = req
-                        .headers()
-                        .get("host")
-                        .and_then(|v| v.to_str().ok())
-                        .unwrap_or("")
-                        .split(':')
-                        .next()
-                        .unwrap_or("");
-
-                    if host == chat_host {
-                        chat_router.oneshot(req).await.into_response()
-                    } else if host == api_host {
-                        api_router.oneshot(req).await.into_response()
-                    } else {
-                        (StatusCode::MISDIRECTED_REQUEST, "421 Misdirected Request").into_response()
-                    }
-                }
-            })
+            .fallback(move |req: axum::extract::Request| dispatch.clone().serve(req))
             .with_state(state.clone()),
     )
 }
@@ -36,8 +36,8 @@ use crate::auth::{self, SessionAuth};
 use crate::config::AppConfig;
 use crate::db::Database;
 use crate::docker::DockerManager;
-use crate::metrics::MetricsBroadcaster;
-use crate::scheduler::reservation::ReservationBroadcaster;
+use crate::events::EventBus;
+use crate::proxy::pool::{BackendClients, PoolSettings};
 use crate::scheduler::Scheduler;
 use crate::AppState;
 
@@ -58,9 +58,11 @@ fn test_config() -> AppConfig {
         model_path: "/tmp/test-models".to_string(),
         model_host_path: "/tmp/test-models".to_string(),
         ui_path: "/tmp/test-ui".to_string(),
+        sites_path: "/tmp/test-sites".to_string(),
         api_hostname: "localhost".to_string(),
         chat_hostname: "localhost".to_string(),
         cookie_domain: None,
+        session_redis_url: None,
         backend_network: "test-network".to_string(),
         acme_contact: None,
         acme_staging: false,
@@ -70,6 +72,74 @@ fn test_config() -> AppConfig {
         secure_cookies: false,
         db_encryption_key: None,
         db_encryption_key_old: None,
+        graceful_stop_timeout_secs: 120,
+        backend_pool_max_idle: 32,
+        backend_pool_idle_timeout_secs: 90,
+        backend_connect_timeout_secs: 5,
+        upstream_protocols: Default::default(),
+        sse_keepalive_secs: 15,
+        access_log_path: None,
+        access_log_max_bytes: 100 * 1024 * 1024,
+        access_log_max_files: 5,
+        access_log_webhook_url: None,
+        auth_rate_limit_per_minute: 0,
+        auth_rate_limit_burst: 10,
+        trust_forwarded_for: false,
+        trusted_proxy_hops: 1,
+        oidc_state_binding: "strict".to_string(),
+        trusted_device_ttl_days: 30,
+        jwt_access_tokens: false,
+        jwt_signing_key: None,
+        jwt_ttl_secs: 300,
+        token_cache_ttl_secs: 0,
+        token_cache_max_entries: 10_000,
+        model_name_normalize: true,
+        model_name_strip_prefixes: vec!["openai/".to_string()],
+        model_name_suggestions: true,
+        usage_webhook_url: None,
+        usage_webhook_secret: None,
+        usage_webhook_max_retries: 5,
+        event_webhook_url: None,
+        event_webhook_secret: None,
+        request_log: false,
+        autoload_timeout_secs: 300,
+        watchdog_interval_secs: 30,
+        watchdog_max_restarts: 3,
+        backend_warmup: false,
+        prompt_archive: false,
+        prompt_archive_retention_days: 90,
+        prompt_dedup_stats: false,
+        two_person_approval: false,
+        approval_window_minutes: 60,
+        sandbox_enabled: false,
+        sandbox_image: "python:3.12-alpine".to_string(),
+        sandbox_memory_mb: 256,
+        sandbox_cpus: 1.0,
+        sandbox_timeout_secs: 10,
+        sandbox_max_concurrent: 4,
+        backend_tls: false,
+        backend_tls_dir: "/config/backend-tls".to_string(),
+        backend_tls_host_path: "/config/backend-tls".to_string(),
+        host_memory_ceiling_percent: 0,
+        memory_critical_percent: 95,
+        memory_pressure_admission: false,
+        download_background_mb_per_sec: 0,
+        hf_download_connections: 1,
+        hf_download_chunk_mb: 64,
+        model_trash_hours: 24,
+        model_trash_max_gb: 200,
+        model_import_path: None,
+        model_store_s3: None,
+        s3_endpoint: None,
+        s3_region: "us-east-1".to_string(),
+        s3_access_key_id: None,
+        s3_secret_access_key: None,
+        model_cache_max_gb: 0,
+        gpu_probe: false,
+        auto_categorize: "off".to_string(),
+        request_log_retention_days: 30,
+        signed_url_key: None,
+        signed_url_max_ttl_secs: 3600,
     }
 }
 
@@ -77,11 +147,26 @@ async fn test_app_state() -> Arc<AppState> {
     let db = Database::test_db().await;
     Arc::new(AppState {
         config: test_config(),
+        sessions: crate::auth::sessions::Sessions::sqlite(&db),
         db,
         docker: DockerManager::test_dummy(),
         scheduler: Scheduler::new(),
-        metrics: MetricsBroadcaster::new(),
-        reservations: ReservationBroadcaster::new(),
+        events: EventBus::new(),
+        backends: BackendClients::new(PoolSettings::from_config(&test_config())),
+        jwt: None,
+        token_cache: crate::auth::token_cache::TokenCache::new(std::time::Duration::ZERO, 0),
+        signed_urls: crate::auth::signed_url::UrlSigner::new(b"test".to_vec(), 3600),
+        tasks: crate::tasks::TaskScheduler::new(),
+        flags: crate::feature_flags::FeatureFlags::default(),
+        host_routes: crate::host_routes::HostRoutes::default(),
+        watchdog: crate::watchdog::Watchdog::default(),
+        warmups: crate::api::warmup::Warmups::default(),
+        download_progress: crate::api::hf::DownloadProgress::default(),
+        replacing: crate::api::container_replace::Replacing::default(),
+        effective_config: None,
+        storage: crate::storage::Storage::default(),
+        autoloads: crate::api::common::Autoloads::default(),
+        ui_integrity: crate::ui_integrity::UiIntegrity::default(),
     })
 }
 
@@ -177,6 +262,7 @@ fn user_api_router(state: Arc<AppState>, user_id: &str) -> Router {
                 req.extensions_mut().insert(SessionAuth {
                     user_id,
                     is_admin: false,
+                    roles: Vec::new(),
                     email: None,
                     display_name: None,
                 });
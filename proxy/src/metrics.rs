@@ -1,11 +1,14 @@
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 
 use serde::Serialize;
-use tokio::sync::broadcast;
-use tracing::warn;
+use sqlx::SqlitePool;
+use tracing::{info, warn};
 
 use crate::api::hf::{get_disk_usage, DiskUsage};
+use crate::api::warmup::{WarmupStatus, Warmups};
 use crate::docker::DockerManager;
+use crate::events::{AppEvent, EventBus};
 use crate::scheduler::gate::GateSnapshot;
 use crate::scheduler::queue::QueueStats;
 use crate::scheduler::Scheduler;
@@ -88,17 +91,71 @@ impl CpuSampler {
     }
 }
 
+// ---- Host memory (Linux /proc/meminfo) ----
+
+/// Host RAM and swap. On unified-memory hosts GTT allocations come out of
+/// the same RAM, so CPU and Vulkan inference both show up here.
+#[derive(Debug, Clone, Serialize)]
+pub struct HostMemoryInfo {
+    pub total_mb: u64,
+    pub available_mb: u64,
+    pub used_mb: u64,
+    pub used_percent: f64,
+    pub swap_total_mb: u64,
+    pub swap_used_mb: u64,
+    /// `used_percent` has reached `MEMORY_CRITICAL_PERCENT`.
+    pub critical: bool,
+}
+
+impl HostMemoryInfo {
+    /// Parse /proc/meminfo. `critical_percent` sets the `critical` flag.
+    pub fn parse(meminfo: &str, critical_percent: u64) -> Option<Self> {
+        let field = |name: &str| -> Option<u64> {
+            meminfo.lines().find_map(|line| {
+                let value = line.strip_prefix(name)?.strip_prefix(':')?;
+                value.split_whitespace().next()?.parse::<u64>().ok()
+            })
+        };
+        let total_kb = field("MemTotal").filter(|t| *t > 0)?;
+        let available_kb = field("MemAvailable")?.min(total_kb);
+        let swap_total_kb = field("SwapTotal").unwrap_or(0);
+        let swap_free_kb = field("SwapFree").unwrap_or(0).min(swap_total_kb);
+
+        let used_kb = total_kb - available_kb;
+        let used_percent = used_kb as f64 / total_kb as f64 * 100.0;
+        Some(Self {
+            total_mb: total_kb / 1024,
+            available_mb: available_kb / 1024,
+            used_mb: used_kb / 1024,
+            used_percent: (used_percent * 10.0).round() / 10.0,
+            swap_total_mb: swap_total_kb / 1024,
+            swap_used_mb: (swap_total_kb - swap_free_kb) / 1024,
+            critical: used_kb * 100 >= total_kb * critical_percent,
+        })
+    }
+
+    /// Current host memory, or `None` off Linux.
+    pub fn read(critical_percent: u64) -> Option<Self> {
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+        Self::parse(&meminfo, critical_percent)
+    }
+
+    /// Whether starting something that needs `needed_mb` more would push
+    /// host memory use past `ceiling_percent`.
+    pub fn exceeds_ceiling(&self, needed_mb: u64, ceiling_percent: u64) -> bool {
+        (self.used_mb + needed_mb) * 100 > self.total_mb * ceiling_percent
+    }
+}
+
 /// How often the collector runs (seconds).
 const COLLECT_INTERVAL_SECS: u64 = 2;
 
-/// Broadcast channel buffer size — enough for a couple of slow readers.
-const BROADCAST_BUFFER: usize = 4;
-
 /// A point-in-time snapshot of system metrics, sent to SSE clients.
 #[derive(Debug, Clone, Serialize)]
 pub struct MetricsSnapshot {
     pub gpu_memory: Vec<GpuMemoryInfo>,
     pub cpu: Option<CpuInfo>,
+    pub memory: Option<HostMemoryInfo>,
     pub containers: Vec<ContainerStatus>,
     pub queues: HashMap<String, QueueStats>,
     pub gates: HashMap<String, GateSnapshot>,
@@ -132,6 +189,12 @@ pub struct ContainerStatus {
     pub healthy: bool,
     pub state: Option<String>,
     pub vram_used_mb: Option<u64>,
+    /// VRAM per GPU index, for containers pinned to specific GPUs.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub vram_by_device_mb: BTreeMap<u32, u64>,
+    /// Latest slot warm-up, when `BACKEND_WARMUP` is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warmup: Option<WarmupStatus>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -151,50 +214,72 @@ impl From<DiskUsage> for DiskInfo {
     }
 }
 
-/// Holds the broadcast sender; cloned into AppState.
-#[derive(Debug, Clone)]
-pub struct MetricsBroadcaster {
-    tx: broadcast::Sender<MetricsSnapshot>,
-}
-
-impl MetricsBroadcaster {
-    pub fn new() -> Self {
-        let (tx, _) = broadcast::channel(BROADCAST_BUFFER);
-        Self { tx }
-    }
-
-    /// Subscribe to the metrics stream (one receiver per SSE client).
-    pub fn subscribe(&self) -> broadcast::Receiver<MetricsSnapshot> {
-        self.tx.subscribe()
-    }
-
-    /// Spawn the background collector task. Call once after AppState is built.
-    pub fn spawn_collector(&self, docker: DockerManager, scheduler: Scheduler, model_path: String) {
-        let tx = self.tx.clone();
-
-        tokio::spawn(async move {
-            let mut interval =
-                tokio::time::interval(std::time::Duration::from_secs(COLLECT_INTERVAL_SECS));
-            let mut cpu_sampler = CpuSampler::new();
-
-            loop {
-                interval.tick().await;
-
-                let snapshot =
-                    collect_snapshot(&docker, &scheduler, &model_path, &mut cpu_sampler).await;
+/// Spawn the background collector task, publishing an
+/// [`AppEvent::Metrics`] snapshot every `COLLECT_INTERVAL_SECS`. Call once
+/// after AppState is built.
+pub fn spawn_collector(
+    events: EventBus,
+    docker: DockerManager,
+    scheduler: Scheduler,
+    model_path: String,
+    pool: SqlitePool,
+    warmups: Warmups,
+    memory_critical_percent: u64,
+) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(COLLECT_INTERVAL_SECS));
+        let mut cpu_sampler = CpuSampler::new();
+        let mut was_critical = false;
+
+        loop {
+            interval.tick().await;
+
+            let snapshot = collect_snapshot(
+                &docker,
+                &scheduler,
+                &model_path,
+                &pool,
+                &warmups,
+                &mut cpu_sampler,
+                memory_critical_percent,
+            )
+            .await;
+
+            if let Some(memory) = &snapshot.memory {
+                if memory.critical && !was_critical {
+                    warn!(
+                        used_percent = memory.used_percent,
+                        swap_used_mb = memory.swap_used_mb,
+                        "Host memory pressure is critical"
+                    );
+                } else if !memory.critical && was_critical {
+                    info!(
+                        used_percent = memory.used_percent,
+                        "Host memory pressure back to normal"
+                    );
+                }
+                was_critical = memory.critical;
+            }
 
-                // If nobody is listening, send() returns Err — that's fine.
-                let _ = tx.send(snapshot);
+            match serde_json::to_value(&snapshot) {
+                Ok(snapshot) => events.publish(AppEvent::Metrics {
+                    snapshot: std::sync::Arc::new(snapshot),
+                }),
+                Err(e) => warn!(error = %e, "Failed to serialize metrics snapshot"),
             }
-        });
-    }
+        }
+    });
 }
 
 async fn collect_snapshot(
     docker: &DockerManager,
     scheduler: &Scheduler,
     model_path: &str,
+    pool: &SqlitePool,
+    warmups: &Warmups,
     cpu_sampler: &mut CpuSampler,
+    memory_critical_percent: u64,
 ) -> MetricsSnapshot {
     // GPU stats (memory + utilization) — all detected GPUs
     let gpu_memory: Vec<GpuMemoryInfo> = DockerManager::gpu_all_info()
@@ -213,12 +298,16 @@ async fn collect_snapshot(
     // CPU utilization (delta-based)
     let cpu = cpu_sampler.sample();
 
+    // Host RAM and swap
+    let memory = HostMemoryInfo::read(memory_critical_percent);
+
     // Per-container VRAM (best-effort, requires pid:host)
-    let vram_map = docker.per_container_vram().await;
+    let devices = crate::api::common::container_devices(pool).await;
+    let vram_map = docker.per_container_vram(&devices).await;
 
     // Container statuses
     let containers = match docker.list_managed_containers().await {
-        Ok(list) => crate::api::common::extract_container_statuses(list, &vram_map),
+        Ok(list) => crate::api::common::extract_container_statuses(list, &vram_map, warmups),
         Err(e) => {
             warn!(error = %e, "Failed to list containers for metrics");
             vec![]
@@ -248,6 +337,7 @@ async fn collect_snapshot(
     MetricsSnapshot {
         gpu_memory,
         cpu,
+        memory,
         containers,
         queues,
         gates,
@@ -256,3 +346,36 @@ async fn collect_snapshot(
         timestamp,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMINFO: &str = "MemTotal:       65536000 kB\n\
+                           MemFree:         1024000 kB\n\
+                           MemAvailable:    6553600 kB\n\
+                           SwapTotal:       8192000 kB\n\
+                           SwapFree:        6144000 kB\n";
+
+    #[test]
+    fn meminfo_is_parsed() {
+        let memory = HostMemoryInfo::parse(MEMINFO, 95).unwrap();
+        assert_eq!(memory.total_mb, 64000);
+        assert_eq!(memory.available_mb, 6400);
+        assert_eq!(memory.used_mb, 57600);
+        assert_eq!(memory.used_percent, 90.0);
+        assert_eq!(memory.swap_total_mb, 8000);
+        assert_eq!(memory.swap_used_mb, 2000);
+        assert!(!memory.critical);
+        assert!(HostMemoryInfo::parse(MEMINFO, 90).unwrap().critical);
+        assert!(HostMemoryInfo::parse("MemFree: 1 kB\n", 95).is_none());
+    }
+
+    #[test]
+    fn ceiling_counts_the_new_allocation() {
+        let memory = HostMemoryInfo::parse(MEMINFO, 95).unwrap();
+        // 57600 + 3200 = 60800 MB is exactly 95% of 64000 MB
+        assert!(!memory.exceeds_ceiling(3200, 95));
+        assert!(memory.exceeds_ceiling(3201, 95));
+    }
+}
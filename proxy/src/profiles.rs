@@ -0,0 +1,395 @@
+//! Named configuration profiles.
+//!
+//! `CONFIG_PROFILE=prod` reads `<CONFIG_PROFILE_DIR>/prod.env` (default
+//! directory `/config/profiles`) at startup: `KEY=VALUE` lines in `.env`
+//! syntax. A variable from the profile applies only when the environment
+//! (including `.env`) doesn't set it, so a profile holds what a tier's hosts
+//! share and each host's environment overrides what differs.
+//!
+//! Profiles are checked before anything starts: a variable the server
+//! doesn't read, or a value of the wrong type, fails startup rather than
+//! falling back to a default. [`EffectiveConfig`] records where each setting
+//! came from for `GET /api/admin/system/config`, with secrets redacted.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use tracing::warn;
+
+const DEFAULT_PROFILE_DIR: &str = "/config/profiles";
+const REDACTED: &str = "[redacted]";
+
+/// What a setting's value must parse as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Text,
+    Bool,
+    Integer,
+    Number,
+    /// Text that is never shown.
+    Secret,
+}
+
+/// Every environment variable the server reads.
+const SETTINGS: &[(&str, Kind)] = &[
+    ("LISTEN_ADDR", Kind::Text),
+    ("DATABASE_URL", Kind::Text),
+    ("TLS_CERT_PATH", Kind::Text),
+    ("TLS_KEY_PATH", Kind::Text),
+    ("BOOTSTRAP_USER", Kind::Text),
+    ("BOOTSTRAP_PASSWORD", Kind::Secret),
+    ("BREAK_GLASS", Kind::Bool),
+    ("DOCKER_HOST", Kind::Text),
+    ("MODEL_PATH", Kind::Text),
+    ("MODEL_HOST_PATH", Kind::Text),
+    ("UI_PATH", Kind::Text),
+    ("SITES_PATH", Kind::Text),
+    ("API_HOSTNAME", Kind::Text),
+    ("CHAT_HOSTNAME", Kind::Text),
+    ("COOKIE_DOMAIN", Kind::Text),
+    ("SESSION_REDIS_URL", Kind::Secret),
+    ("BACKEND_NETWORK", Kind::Text),
+    ("ACME_CONTACT", Kind::Text),
+    ("ACME_STAGING", Kind::Bool),
+    ("WEBUI_BACKEND_URL", Kind::Text),
+    ("WEBUI_API_KEY", Kind::Secret),
+    ("QUEUE_TIMEOUT_SECS", Kind::Integer),
+    ("GRACEFUL_STOP_TIMEOUT_SECS", Kind::Integer),
+    ("BACKEND_POOL_MAX_IDLE", Kind::Integer),
+    ("BACKEND_POOL_IDLE_TIMEOUT_SECS", Kind::Integer),
+    ("BACKEND_CONNECT_TIMEOUT_SECS", Kind::Integer),
+    ("UPSTREAM_PROTOCOLS", Kind::Text),
+    ("SSE_KEEPALIVE_SECS", Kind::Integer),
+    ("ACCESS_LOG_PATH", Kind::Text),
+    ("ACCESS_LOG_MAX_BYTES", Kind::Integer),
+    ("ACCESS_LOG_MAX_FILES", Kind::Integer),
+    ("ACCESS_LOG_WEBHOOK_URL", Kind::Text),
+    ("AUTH_RATE_LIMIT_PER_MINUTE", Kind::Integer),
+    ("AUTH_RATE_LIMIT_BURST", Kind::Integer),
+    ("TRUST_FORWARDED_FOR", Kind::Bool),
+    ("TRUSTED_PROXY_HOPS", Kind::Integer),
+    ("OIDC_STATE_BINDING", Kind::Text),
+    ("TRUSTED_DEVICE_TTL_DAYS", Kind::Integer),
+    ("JWT_ACCESS_TOKENS", Kind::Bool),
+    ("JWT_SIGNING_KEY", Kind::Secret),
+    ("JWT_TTL_SECS", Kind::Integer),
+    ("SIGNED_URL_KEY", Kind::Secret),
+    ("SIGNED_URL_MAX_TTL_SECS", Kind::Integer),
+    ("TOKEN_CACHE_TTL_SECS", Kind::Integer),
+    ("TOKEN_CACHE_MAX_ENTRIES", Kind::Integer),
+    ("MODEL_NAME_NORMALIZE", Kind::Bool),
+    ("MODEL_NAME_STRIP_PREFIXES", Kind::Text),
+    ("MODEL_NAME_SUGGESTIONS", Kind::Bool),
+    ("USAGE_WEBHOOK_URL", Kind::Text),
+    ("USAGE_WEBHOOK_SECRET", Kind::Secret),
+    ("USAGE_WEBHOOK_MAX_RETRIES", Kind::Integer),
+    ("EVENT_WEBHOOK_URL", Kind::Text),
+    ("EVENT_WEBHOOK_SECRET", Kind::Secret),
+    ("REQUEST_LOG", Kind::Bool),
+    ("REQUEST_LOG_RETENTION_DAYS", Kind::Integer),
+    ("AUTOLOAD_TIMEOUT_SECS", Kind::Integer),
+    ("WATCHDOG_INTERVAL_SECS", Kind::Integer),
+    ("WATCHDOG_MAX_RESTARTS", Kind::Integer),
+    ("BACKEND_WARMUP", Kind::Bool),
+    ("PROMPT_ARCHIVE", Kind::Bool),
+    ("PROMPT_ARCHIVE_RETENTION_DAYS", Kind::Integer),
+    ("PROMPT_DEDUP_STATS", Kind::Bool),
+    ("TWO_PERSON_APPROVAL", Kind::Bool),
+    ("APPROVAL_WINDOW_MINUTES", Kind::Integer),
+    ("SANDBOX_ENABLED", Kind::Bool),
+    ("SANDBOX_IMAGE", Kind::Text),
+    ("SANDBOX_MEMORY_MB", Kind::Integer),
+    ("SANDBOX_CPUS", Kind::Number),
+    ("SANDBOX_TIMEOUT_SECS", Kind::Integer),
+    ("SANDBOX_MAX_CONCURRENT", Kind::Integer),
+    ("HOST_MEMORY_CEILING_PERCENT", Kind::Integer),
+    ("MEMORY_CRITICAL_PERCENT", Kind::Integer),
+    ("MEMORY_PRESSURE_ADMISSION", Kind::Bool),
+    ("BACKEND_TLS", Kind::Bool),
+    ("BACKEND_TLS_DIR", Kind::Text),
+    ("BACKEND_TLS_HOST_PATH", Kind::Text),
+    ("DOWNLOAD_BACKGROUND_MB_PER_SEC", Kind::Integer),
+    ("HF_DOWNLOAD_CONNECTIONS", Kind::Integer),
+    ("HF_DOWNLOAD_CHUNK_MB", Kind::Integer),
+    ("MODEL_TRASH_HOURS", Kind::Integer),
+    ("MODEL_TRASH_MAX_GB", Kind::Integer),
+    ("MODEL_IMPORT_PATH", Kind::Text),
+    ("MODEL_STORE_S3", Kind::Text),
+    ("S3_ENDPOINT", Kind::Text),
+    ("S3_REGION", Kind::Text),
+    ("S3_ACCESS_KEY_ID", Kind::Text),
+    ("S3_SECRET_ACCESS_KEY", Kind::Secret),
+    ("MODEL_CACHE_MAX_GB", Kind::Integer),
+    ("GPU_PROBE", Kind::Bool),
+    ("AUTO_CATEGORIZE", Kind::Text),
+    ("SECURE_COOKIES", Kind::Bool),
+    ("DB_ENCRYPTION_KEY", Kind::Secret),
+    ("DB_ENCRYPTION_KEY_OLD", Kind::Secret),
+    ("HF_TOKEN", Kind::Secret),
+    ("SECRET_PROVIDER", Kind::Text),
+    ("VAULT_ADDR", Kind::Text),
+    ("VAULT_SECRET_PATH", Kind::Text),
+    ("VAULT_NAMESPACE", Kind::Text),
+    ("VAULT_TOKEN", Kind::Secret),
+    ("VAULT_TOKEN_FILE", Kind::Text),
+    ("AWS_REGION", Kind::Text),
+    ("AWS_SECRET_ID", Kind::Text),
+    ("AWS_ACCESS_KEY_ID", Kind::Text),
+    ("AWS_SECRET_ACCESS_KEY", Kind::Secret),
+    ("AWS_SESSION_TOKEN", Kind::Secret),
+    ("SOPS_SECRETS_FILE", Kind::Text),
+    ("RUST_LOG", Kind::Text),
+];
+
+fn kind_of(name: &str) -> Option<Kind> {
+    SETTINGS.iter().find(|(n, _)| *n == name).map(|(_, k)| *k)
+}
+
+/// Why `value` isn't valid for `kind`, if it isn't.
+fn type_error(kind: Kind, value: &str) -> Option<&'static str> {
+    let value = value.trim();
+    match kind {
+        Kind::Text | Kind::Secret => None,
+        Kind::Bool => (!value.eq_ignore_ascii_case("true") && !value.eq_ignore_ascii_case("false"))
+            .then_some("expected true or false"),
+        Kind::Integer => value
+            .parse::<i64>()
+            .is_err()
+            .then_some("expected a whole number"),
+        Kind::Number => value.parse::<f64>().is_err().then_some("expected a number"),
+    }
+}
+
+/// A loaded profile file.
+#[derive(Debug)]
+pub struct Profile {
+    pub name: String,
+    pub path: PathBuf,
+    values: Vec<(String, String)>,
+}
+
+impl Profile {
+    /// Load the profile named by `CONFIG_PROFILE`, if set.
+    pub fn from_env() -> Result<Option<Self>> {
+        let Some(name) = std::env::var("CONFIG_PROFILE")
+            .ok()
+            .map(|n| n.trim().to_string())
+            .filter(|n| !n.is_empty())
+        else {
+            return Ok(None);
+        };
+        let dir = std::env::var("CONFIG_PROFILE_DIR")
+            .ok()
+            .filter(|d| !d.is_empty())
+            .unwrap_or_else(|| DEFAULT_PROFILE_DIR.to_string());
+        Self::load(&name, Path::new(&dir)).map(Some)
+    }
+
+    /// Read and check `<dir>/<name>.env`.
+    pub fn load(name: &str, dir: &Path) -> Result<Self> {
+        if name.is_empty()
+            || !name
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        {
+            bail!("CONFIG_PROFILE '{name}' must be letters, digits, '-' or '_'");
+        }
+        let path = dir.join(format!("{name}.env"));
+        let values = dotenvy::from_path_iter(&path)
+            .with_context(|| format!("Failed to open config profile {}", path.display()))?
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Failed to parse config profile {}", path.display()))?;
+        validate(&values).with_context(|| format!("Invalid config profile {}", path.display()))?;
+        Ok(Self {
+            name: name.to_string(),
+            path,
+            values,
+        })
+    }
+
+    /// Set each profile variable the environment doesn't already set.
+    /// Returns the names applied. Call before anything else reads the
+    /// environment.
+    pub fn apply(&self) -> Vec<String> {
+        let mut applied = Vec::new();
+        for (key, value) in &self.values {
+            if std::env::var_os(key).is_none() {
+                std::env::set_var(key, value);
+                applied.push(key.clone());
+            }
+        }
+        applied
+    }
+}
+
+/// Check every profile entry names a known setting and has a valid value.
+fn validate(values: &[(String, String)]) -> Result<()> {
+    let mut errors = Vec::new();
+    for (key, value) in values {
+        if matches!(key.as_str(), "CONFIG_PROFILE" | "CONFIG_PROFILE_DIR") {
+            errors.push(format!("{key} can't be set from a profile"));
+            continue;
+        }
+        match kind_of(key) {
+            None => errors.push(format!("{key} is not a known setting")),
+            Some(kind) => {
+                if let Some(why) = type_error(kind, value) {
+                    errors.push(format!("{key}: {why}"));
+                }
+            }
+        }
+    }
+    if !errors.is_empty() {
+        bail!("{}", errors.join("; "));
+    }
+    Ok(())
+}
+
+/// Where a setting's value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Env,
+    Profile,
+    SecretProvider,
+    Default,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Setting {
+    pub name: &'static str,
+    /// `None` when unset (the built-in default applies). Secrets show as
+    /// `[redacted]`.
+    pub value: Option<String>,
+    pub source: Source,
+}
+
+/// The merged configuration as the server started with it.
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveConfig {
+    pub profile: Option<String>,
+    pub profile_path: Option<PathBuf>,
+    pub settings: Vec<Setting>,
+    /// Environment values of the wrong type, which the server may read as
+    /// unset.
+    pub warnings: Vec<String>,
+}
+
+impl EffectiveConfig {
+    /// Snapshot the environment after `profile` (with `from_profile` the
+    /// names it applied) and the secret manager (`from_secrets`).
+    pub fn collect(
+        profile: Option<&Profile>,
+        from_profile: &[String],
+        from_secrets: &[&str],
+    ) -> Self {
+        let mut settings = Vec::with_capacity(SETTINGS.len());
+        let mut warnings = Vec::new();
+        for &(name, kind) in SETTINGS {
+            let value = std::env::var(name).ok();
+            let source = if from_secrets.contains(&name) {
+                Source::SecretProvider
+            } else if from_profile.iter().any(|k| k == name) {
+                Source::Profile
+            } else if value.is_some() {
+                Source::Env
+            } else {
+                Source::Default
+            };
+            if let Some(why) = value.as_deref().and_then(|v| type_error(kind, v)) {
+                warnings.push(format!("{name}: {why}"));
+            }
+            let value = if kind == Kind::Secret || source == Source::SecretProvider {
+                (value.is_some() || source == Source::SecretProvider).then(|| REDACTED.to_string())
+            } else {
+                value
+            };
+            settings.push(Setting {
+                name,
+                value,
+                source,
+            });
+        }
+        Self {
+            profile: profile.map(|p| p.name.clone()),
+            profile_path: profile.map(|p| p.path.clone()),
+            settings,
+            warnings,
+        }
+    }
+
+    /// Log the values of the wrong type.
+    pub fn log_warnings(&self) {
+        for warning in &self.warnings {
+            warn!("Config setting has the wrong type: {warning}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(entries: &[(&str, &str)]) -> Vec<(String, String)> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn valid_profile_passes() {
+        assert!(validate(&pairs(&[
+            ("QUEUE_TIMEOUT_SECS", "60"),
+            ("SECURE_COOKIES", "false"),
+            ("SANDBOX_CPUS", "0.5"),
+            ("API_HOSTNAME", "api.staging.example.com"),
+        ]))
+        .is_ok());
+    }
+
+    #[test]
+    fn unknown_keys_and_bad_values_are_all_reported() {
+        let err = validate(&pairs(&[
+            ("QUEUE_TIMEOUT_SEC", "60"),
+            ("SECURE_COOKIES", "no"),
+            ("JWT_TTL_SECS", "5m"),
+            ("CONFIG_PROFILE", "prod"),
+        ]))
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("QUEUE_TIMEOUT_SEC is not a known setting"));
+        assert!(err.contains("SECURE_COOKIES: expected true or false"));
+        assert!(err.contains("JWT_TTL_SECS: expected a whole number"));
+        assert!(err.contains("CONFIG_PROFILE can't be set"));
+    }
+
+    #[test]
+    fn profile_loads_from_dir() {
+        let dir = std::env::temp_dir().join(format!("profiles-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("staging.env"),
+            "# shared staging settings\nQUEUE_TIMEOUT_SECS=45\nREQUEST_LOG=true\n",
+        )
+        .unwrap();
+
+        let profile = Profile::load("staging", &dir).unwrap();
+        assert_eq!(profile.values.len(), 2);
+        assert!(Profile::load("../staging", &dir).is_err());
+        assert!(Profile::load("prod", &dir).is_err());
+
+        std::fs::write(dir.join("bad.env"), "QUEUE_TIMEOUT_SECS=soon\n").unwrap();
+        assert!(Profile::load("bad", &dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn every_setting_is_listed_once() {
+        let mut names: Vec<_> = SETTINGS.iter().map(|(n, _)| *n).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), SETTINGS.len());
+    }
+}
@@ -1,2 +1,3 @@
+pub mod pool;
 pub mod streaming;
 pub mod webui;
@@ -0,0 +1,562 @@
+//! Per-backend upstream HTTP clients.
+//!
+//! Each backend container gets its own `reqwest::Client`, so its keep-alive
+//! pool survives across requests instead of being rebuilt (and every
+//! connection re-handshaked) per request. A connector layer counts the
+//! connections each client actually opens, which together with the request
+//! count gives the reuse ratio reported by `GET /api/admin/system/connections`.
+//!
+//! The upstream protocol is chosen per backend type (`UPSTREAM_PROTOCOLS`).
+//! `auto` starts with HTTP/2 (prior knowledge — backends are plain HTTP on the
+//! internal network, so there is no ALPN) and permanently drops that backend
+//! to HTTP/1.1 the first time HTTP/2 can't be set up: a connect failure, or a
+//! protocol error from a peer that answered the HTTP/2 preface in HTTP/1.1.
+//! Any other failure (a timeout, a broken body, the backend resetting a
+//! stream) may come after the backend saw the request, so it is returned
+//! as is and never re-sent.
+//!
+//! With backend TLS on, clients trust only the engine's backend CA and
+//! present the proxy's client certificate (see `docker::tls`).
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use reqwest::{Client, RequestBuilder, Response};
+use serde::Serialize;
+use tokio::sync::RwLock;
+use tracing::warn;
+
+use crate::config::AppConfig;
+use crate::docker::tls::BackendTls;
+
+/// HTTP protocol used to talk to a backend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpstreamProtocol {
+    Http1,
+    Http2,
+    /// Try HTTP/2, fall back to HTTP/1.1 on failure.
+    Auto,
+}
+
+impl UpstreamProtocol {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpstreamProtocol::Http1 => "http1",
+            UpstreamProtocol::Http2 => "http2",
+            UpstreamProtocol::Auto => "auto",
+        }
+    }
+}
+
+impl fmt::Display for UpstreamProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for UpstreamProtocol {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "http1" => Ok(UpstreamProtocol::Http1),
+            "http2" => Ok(UpstreamProtocol::Http2),
+            "auto" => Ok(UpstreamProtocol::Auto),
+            other => Err(format!(
+                "Unknown upstream protocol '{other}' (expected http1, http2, or auto)"
+            )),
+        }
+    }
+}
+
+/// Pool tuning shared by every backend client.
+#[derive(Debug, Clone)]
+pub struct PoolSettings {
+    pub max_idle_per_host: usize,
+    pub idle_timeout: Duration,
+    pub connect_timeout: Duration,
+    /// Protocol per backend type; unlisted types use HTTP/1.1.
+    pub protocols: HashMap<String, UpstreamProtocol>,
+    /// Backend CA and proxy identity, when backend TLS is on.
+    pub tls: Option<BackendTls>,
+}
+
+impl PoolSettings {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let protocols = config
+            .upstream_protocols
+            .iter()
+            .filter_map(|(backend_type, value)| match value.parse() {
+                Ok(protocol) => Some((backend_type.clone(), protocol)),
+                Err(e) => {
+                    warn!(backend = %backend_type, "Ignoring UPSTREAM_PROTOCOLS entry: {e}");
+                    None
+                }
+            })
+            .collect();
+        Self {
+            max_idle_per_host: config.backend_pool_max_idle,
+            idle_timeout: Duration::from_secs(config.backend_pool_idle_timeout_secs),
+            connect_timeout: Duration::from_secs(config.backend_connect_timeout_secs),
+            protocols,
+            tls: BackendTls::from_config(config),
+        }
+    }
+
+    fn protocol_for(&self, backend_type: &str) -> UpstreamProtocol {
+        self.protocols
+            .get(backend_type)
+            .copied()
+            .unwrap_or(UpstreamProtocol::Http1)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    requests: AtomicU64,
+    connections_opened: AtomicU64,
+    streamed_frames: AtomicU64,
+    streamed_bytes: AtomicU64,
+    stream_poll_ns: AtomicU64,
+}
+
+/// Records pass-through streaming work against one backend's counters.
+#[derive(Clone)]
+pub struct StreamMeter {
+    counters: Arc<Counters>,
+}
+
+impl StreamMeter {
+    /// Count time spent polling the upstream body, whether or not it yielded.
+    pub fn record_poll(&self, busy: Duration) {
+        self.counters
+            .stream_poll_ns
+            .fetch_add(busy.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn record_frame(&self, len: usize) {
+        self.counters
+            .streamed_frames
+            .fetch_add(1, Ordering::Relaxed);
+        self.counters
+            .streamed_bytes
+            .fetch_add(len as u64, Ordering::Relaxed);
+    }
+}
+
+struct BackendClient {
+    client: Client,
+    counters: Arc<Counters>,
+    /// Protocol the client currently speaks (never `Auto`).
+    protocol: UpstreamProtocol,
+    /// Whether a failure should drop this backend to HTTP/1.1.
+    can_fall_back: bool,
+}
+
+/// Whether an upstream error means the connection never got as far as
+/// carrying the request: the connect (including the HTTP/2 handshake)
+/// failed, or our HTTP/2 library rejected what the peer sent back, which is
+/// what an HTTP/1.1-only server answering the preface looks like. Timeouts
+/// and body errors never qualify.
+fn is_negotiation_error(e: &reqwest::Error) -> bool {
+    if e.is_timeout() || e.is_body() {
+        return false;
+    }
+    if e.is_connect() {
+        return true;
+    }
+    let mut source = std::error::Error::source(e);
+    while let Some(err) = source {
+        if let Some(h2) = err.downcast_ref::<h2::Error>() {
+            return h2.is_library()
+                && matches!(
+                    h2.reason(),
+                    Some(h2::Reason::PROTOCOL_ERROR | h2::Reason::FRAME_SIZE_ERROR)
+                );
+        }
+        source = err.source();
+    }
+    false
+}
+
+/// Connection reuse statistics for one backend.
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolStats {
+    pub model_id: String,
+    pub protocol: UpstreamProtocol,
+    pub requests: u64,
+    pub connections_opened: u64,
+    /// Requests served on an already-open connection.
+    pub reused: u64,
+    /// `reused / requests`, 0 when no requests have been made.
+    pub reuse_ratio: f64,
+    /// Body frames forwarded on streaming responses (roughly one per token).
+    pub streamed_frames: u64,
+    pub streamed_bytes: u64,
+    /// Mean proxy time spent forwarding each streamed frame, in nanoseconds.
+    pub poll_ns_per_frame: Option<u64>,
+}
+
+/// Registry of upstream clients keyed by model ID.
+#[derive(Clone)]
+pub struct BackendClients {
+    settings: PoolSettings,
+    clients: Arc<RwLock<HashMap<String, BackendClient>>>,
+}
+
+impl BackendClients {
+    pub fn new(settings: PoolSettings) -> Self {
+        Self {
+            settings,
+            clients: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Get (or lazily build) the client for a backend and count the request.
+    ///
+    /// `reqwest::Client` is a cheap `Arc` handle, so the returned clone shares
+    /// the backend's connection pool.
+    pub async fn client(&self, model_id: &str, backend_type: &str) -> Client {
+        if let Some(backend) = self.clients.read().await.get(model_id) {
+            backend.counters.requests.fetch_add(1, Ordering::Relaxed);
+            return backend.client.clone();
+        }
+
+        let mut clients = self.clients.write().await;
+        let backend = clients.entry(model_id.to_string()).or_insert_with(|| {
+            let protocol = self.settings.protocol_for(backend_type);
+            self.build(model_id, protocol, Arc::new(Counters::default()))
+        });
+        backend.counters.requests.fetch_add(1, Ordering::Relaxed);
+        backend.client.clone()
+    }
+
+    /// Send a request to a backend, retrying once over HTTP/1.1 if an
+    /// auto-negotiated HTTP/2 connection couldn't be established (see
+    /// [`is_negotiation_error`]).
+    ///
+    /// `build` is called per attempt, so the body must be cheap to clone.
+    pub async fn send<F>(
+        &self,
+        model_id: &str,
+        backend_type: &str,
+        build: F,
+    ) -> reqwest::Result<Response>
+    where
+        F: Fn(&Client) -> RequestBuilder,
+    {
+        let client = self.client(model_id, backend_type).await;
+        match build(&client).send().await {
+            Err(e) if is_negotiation_error(&e) => match self.fall_back_to_http1(model_id).await {
+                Some(fallback) => {
+                    warn!(model = %model_id, error = %e, "HTTP/2 upstream request failed, falling back to HTTP/1.1");
+                    build(&fallback).send().await
+                }
+                None => Err(e),
+            },
+            ok => ok,
+        }
+    }
+
+    /// Replace an `auto` backend's HTTP/2 client with an HTTP/1.1 one.
+    /// Returns the new client, or `None` if there was nothing to fall back from.
+    async fn fall_back_to_http1(&self, model_id: &str) -> Option<Client> {
+        let mut clients = self.clients.write().await;
+        let backend = clients.get_mut(model_id)?;
+        if !backend.can_fall_back {
+            return None;
+        }
+        let counters = backend.counters.clone();
+        *backend = self.build(model_id, UpstreamProtocol::Http1, counters);
+        Some(backend.client.clone())
+    }
+
+    /// Meter for a streaming response from this backend, if it has a client.
+    pub async fn stream_meter(&self, model_id: &str) -> Option<StreamMeter> {
+        self.clients
+            .read()
+            .await
+            .get(model_id)
+            .map(|backend| StreamMeter {
+                counters: backend.counters.clone(),
+            })
+    }
+
+    /// Drop a backend's client and its idle connections (called on container stop).
+    pub async fn remove(&self, model_id: &str) {
+        self.clients.write().await.remove(model_id);
+    }
+
+    /// Reuse statistics for every backend with a live client, sorted by model ID.
+    pub async fn stats(&self) -> Vec<PoolStats> {
+        let clients = self.clients.read().await;
+        let mut stats: Vec<PoolStats> = clients
+            .iter()
+            .map(|(model_id, backend)| {
+                let requests = backend.counters.requests.load(Ordering::Relaxed);
+                let connections_opened =
+                    backend.counters.connections_opened.load(Ordering::Relaxed);
+                let reused = requests.saturating_sub(connections_opened);
+                let streamed_frames = backend.counters.streamed_frames.load(Ordering::Relaxed);
+                let stream_poll_ns = backend.counters.stream_poll_ns.load(Ordering::Relaxed);
+                PoolStats {
+                    model_id: model_id.clone(),
+                    protocol: backend.protocol,
+                    requests,
+                    connections_opened,
+                    reused,
+                    reuse_ratio: if requests > 0 {
+                        reused as f64 / requests as f64
+                    } else {
+                        0.0
+                    },
+                    streamed_frames,
+                    streamed_bytes: backend.counters.streamed_bytes.load(Ordering::Relaxed),
+                    poll_ns_per_frame: (streamed_frames > 0)
+                        .then(|| stream_poll_ns / streamed_frames),
+                }
+            })
+            .collect();
+        stats.sort_by(|a, b| a.model_id.cmp(&b.model_id));
+        stats
+    }
+
+    fn build(
+        &self,
+        model_id: &str,
+        protocol: UpstreamProtocol,
+        counters: Arc<Counters>,
+    ) -> BackendClient {
+        let layer_counters = counters.clone();
+        let builder = match protocol {
+            UpstreamProtocol::Http1 => Client::builder().http1_only(),
+            UpstreamProtocol::Http2 | UpstreamProtocol::Auto => {
+                Client::builder().http2_prior_knowledge()
+            }
+        };
+        let builder = match &self.settings.tls {
+            Some(tls) => match tls.client_material() {
+                Ok((ca, identity)) => builder
+                    .use_rustls_tls()
+                    .tls_built_in_root_certs(false)
+                    .add_root_certificate(ca)
+                    .identity(identity),
+                Err(e) => {
+                    // Trusting nothing fails every request rather than
+                    // falling back to an unverified connection
+                    warn!(model = %model_id, error = %e, "Failed to load backend TLS material");
+                    builder.use_rustls_tls().tls_built_in_root_certs(false)
+                }
+            },
+            None => builder,
+        };
+        let client = builder
+            .pool_max_idle_per_host(self.settings.max_idle_per_host)
+            .pool_idle_timeout(self.settings.idle_timeout)
+            .connect_timeout(self.settings.connect_timeout)
+            .tcp_keepalive(Duration::from_secs(60))
+            .tcp_nodelay(true)
+            .connector_layer(tower::layer::layer_fn(move |inner| CountConnects {
+                inner,
+                counters: layer_counters.clone(),
+            }))
+            .build()
+            .unwrap_or_else(|e| {
+                warn!(model = %model_id, error = %e, "Failed to build pooled backend client, using defaults");
+                Client::new()
+            });
+        BackendClient {
+            client,
+            counters,
+            protocol: match protocol {
+                UpstreamProtocol::Auto => UpstreamProtocol::Http2,
+                p => p,
+            },
+            can_fall_back: protocol == UpstreamProtocol::Auto,
+        }
+    }
+}
+
+/// Connector middleware that counts connection attempts. The pool only calls
+/// the connector when it has no idle connection to hand out.
+#[derive(Clone)]
+struct CountConnects<S> {
+    inner: S,
+    counters: Arc<Counters>,
+}
+
+impl<S, R> tower::Service<R> for CountConnects<S>
+where
+    S: tower::Service<R>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        self.counters
+            .connections_opened
+            .fetch_add(1, Ordering::Relaxed);
+        self.inner.call(req)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> PoolSettings {
+        PoolSettings {
+            max_idle_per_host: 4,
+            idle_timeout: Duration::from_secs(30),
+            connect_timeout: Duration::from_secs(1),
+            protocols: HashMap::from([("auto-backend".to_string(), UpstreamProtocol::Auto)]),
+            tls: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn client_is_reused_per_backend() {
+        let clients = BackendClients::new(settings());
+        clients.client("m1", "llamacpp").await;
+        clients.client("m1", "llamacpp").await;
+        clients.client("m2", "llamacpp").await;
+
+        let stats = clients.stats().await;
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].model_id, "m1");
+        assert_eq!(stats[0].requests, 2);
+        assert_eq!(stats[1].requests, 1);
+    }
+
+    #[tokio::test]
+    async fn remove_drops_stats() {
+        let clients = BackendClients::new(settings());
+        clients.client("m1", "llamacpp").await;
+        clients.remove("m1").await;
+        assert!(clients.stats().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn requests_without_new_connections_count_as_reused() {
+        let clients = BackendClients::new(settings());
+        clients.client("m1", "llamacpp").await;
+        // Nothing was sent, so the connector was never called
+        let stats = clients.stats().await;
+        assert_eq!(stats[0].connections_opened, 0);
+        assert_eq!(stats[0].reused, 1);
+    }
+
+    #[test]
+    fn protocol_parse_round_trip() {
+        for p in [
+            UpstreamProtocol::Http1,
+            UpstreamProtocol::Http2,
+            UpstreamProtocol::Auto,
+        ] {
+            assert_eq!(p.as_str().parse::<UpstreamProtocol>().unwrap(), p);
+        }
+        assert!("h2c".parse::<UpstreamProtocol>().is_err());
+    }
+
+    #[tokio::test]
+    async fn unlisted_backend_type_uses_http1_without_fallback() {
+        let clients = BackendClients::new(settings());
+        clients.client("m1", "llamacpp").await;
+        assert_eq!(clients.stats().await[0].protocol, UpstreamProtocol::Http1);
+        assert!(clients.fall_back_to_http1("m1").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn auto_starts_on_http2_and_falls_back_once() {
+        let clients = BackendClients::new(settings());
+        clients.client("m1", "auto-backend").await;
+        assert_eq!(clients.stats().await[0].protocol, UpstreamProtocol::Http2);
+
+        assert!(clients.fall_back_to_http1("m1").await.is_some());
+        let stats = clients.stats().await;
+        assert_eq!(stats[0].protocol, UpstreamProtocol::Http1);
+        // Counters survive the client swap
+        assert_eq!(stats[0].requests, 1);
+
+        assert!(clients.fall_back_to_http1("m1").await.is_none());
+    }
+
+    /// Send over HTTP/2 prior knowledge to a server that writes `reply` to
+    /// every connection and then holds it open.
+    async fn h2_error_from(reply: &'static [u8]) -> reqwest::Error {
+        use tokio::io::AsyncWriteExt;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut conn, _)) = listener.accept().await {
+                let _ = conn.write_all(reply).await;
+                tokio::spawn(async move {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    drop(conn);
+                });
+            }
+        });
+        Client::builder()
+            .http2_prior_knowledge()
+            .timeout(Duration::from_millis(300))
+            .build()
+            .unwrap()
+            .post(format!("http://{addr}/v1/chat/completions"))
+            .body("{}")
+            .send()
+            .await
+            .unwrap_err()
+    }
+
+    #[tokio::test]
+    async fn only_negotiation_errors_fall_back() {
+        // Nothing listening
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port()
+        };
+        let refused = Client::new()
+            .get(format!("http://127.0.0.1:{port}/"))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(is_negotiation_error(&refused));
+
+        // An HTTP/1.1-only server answering the preface
+        let http1 = h2_error_from(b"HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\n\r\n").await;
+        assert!(is_negotiation_error(&http1), "{http1:?}");
+
+        // A backend that accepted the request but never answered
+        let timeout = h2_error_from(b"").await;
+        assert!(timeout.is_timeout());
+        assert!(!is_negotiation_error(&timeout));
+    }
+
+    #[tokio::test]
+    async fn stream_meter_reports_per_frame_cost() {
+        let clients = BackendClients::new(settings());
+        assert!(clients.stream_meter("m1").await.is_none());
+        clients.client("m1", "llamacpp").await;
+
+        let meter = clients.stream_meter("m1").await.unwrap();
+        meter.record_poll(Duration::from_nanos(3000));
+        meter.record_frame(100);
+        meter.record_frame(50);
+
+        let stats = clients.stats().await;
+        assert_eq!(stats[0].streamed_frames, 2);
+        assert_eq!(stats[0].streamed_bytes, 150);
+        assert_eq!(stats[0].poll_ns_per_frame, Some(1500));
+    }
+}
@@ -1,37 +1,147 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
 use axum::body::Body;
 use axum::http::{Response, StatusCode};
 use bytes::Bytes;
-use futures::StreamExt;
-use reqwest::Client;
+use futures::Stream;
+use tokio::sync::oneshot;
 use tracing::error;
 
+use super::pool::{BackendClients, StreamMeter};
+
 /// Result of proxying to a backend. For non-streaming responses, includes
 /// the raw body bytes so callers can extract usage data.
 pub struct ProxyResult {
     pub response: Response<Body>,
     /// Raw response body bytes (only populated for non-streaming successful responses)
     pub body_bytes: Option<Bytes>,
+    /// Token counts seen in a streamed response, sent once the client body
+    /// is finished or dropped (only populated for streaming responses)
+    pub stream_usage: Option<oneshot::Receiver<StreamUsage>>,
+}
+
+/// Token counts gathered from an OpenAI-style SSE completion stream.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StreamUsage {
+    /// `usage.prompt_tokens` from the backend's final usage frame, if sent.
+    pub prompt_tokens: Option<i64>,
+    /// `usage.completion_tokens` from the backend's final usage frame, if sent.
+    pub completion_tokens: Option<i64>,
+    /// Chunks that carried generated output. llama.cpp and vLLM stream one
+    /// token per chunk, so this stands in for `completion_tokens` when the
+    /// backend sends no usage frame.
+    pub content_chunks: i64,
+}
+
+/// Incremental SSE parser that picks token usage out of `data:` events.
+/// Frames may split events anywhere; partial lines are buffered.
+#[derive(Debug, Default)]
+pub struct SseUsageParser {
+    pending: Vec<u8>,
+    usage: StreamUsage,
+}
+
+impl SseUsageParser {
+    /// Feed one body frame.
+    pub fn feed(&mut self, frame: &[u8]) {
+        self.pending.extend_from_slice(frame);
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.parse_line(&line);
+        }
+    }
+
+    /// Counts seen so far.
+    pub fn usage(&self) -> StreamUsage {
+        self.usage
+    }
+
+    fn parse_line(&mut self, line: &[u8]) {
+        let Some(data) = line.strip_prefix(b"data:") else {
+            return;
+        };
+        let Ok(event) = serde_json::from_slice::<serde_json::Value>(data.trim_ascii()) else {
+            // `[DONE]` and anything that isn't a JSON chunk
+            return;
+        };
+
+        if let Some(usage) = event.get("usage").filter(|u| u.is_object()) {
+            if let Some(n) = usage.get("prompt_tokens").and_then(|v| v.as_i64()) {
+                self.usage.prompt_tokens = Some(n);
+            }
+            if let Some(n) = usage.get("completion_tokens").and_then(|v| v.as_i64()) {
+                self.usage.completion_tokens = Some(n);
+            }
+        }
+
+        let has_output = event
+            .get("choices")
+            .and_then(|c| c.as_array())
+            .is_some_and(|choices| choices.iter().any(choice_has_output));
+        if has_output {
+            self.usage.content_chunks += 1;
+        }
+    }
+}
+
+/// Whether a streamed choice carries generated text or a tool-call fragment.
+fn choice_has_output(choice: &serde_json::Value) -> bool {
+    let non_empty =
+        |v: Option<&serde_json::Value>| v.and_then(|v| v.as_str()).is_some_and(|s| !s.is_empty());
+    let delta = choice.get("delta");
+    non_empty(choice.get("text"))
+        || non_empty(delta.and_then(|d| d.get("content")))
+        || non_empty(delta.and_then(|d| d.get("reasoning_content")))
+        || delta.and_then(|d| d.get("tool_calls")).is_some()
+}
+
+/// Feeds streamed frames to a parser and reports the result when dropped,
+/// so a client disconnect still accounts for the tokens already generated.
+struct UsageTap {
+    parser: SseUsageParser,
+    tx: Option<oneshot::Sender<StreamUsage>>,
+}
+
+impl Drop for UsageTap {
+    fn drop(&mut self) {
+        if let Some(tx) = self.tx.take() {
+            let _ = tx.send(self.parser.usage());
+        }
+    }
 }
 
 /// Forward a request to an inference backend and stream the response back.
 /// Handles both streaming (SSE) and non-streaming responses transparently.
 /// If `api_key` is provided, sends `Authorization: Bearer <key>` to the backend.
+/// Streaming responses get an SSE comment every `keepalive` until the first
+/// backend chunk arrives.
 pub async fn proxy_to_backend(
-    client: &Client,
+    backends: &BackendClients,
+    model_id: &str,
+    backend_type: &str,
     backend_url: &str,
     body: Bytes,
     is_streaming: bool,
     api_key: Option<&str>,
+    keepalive: Option<Duration>,
 ) -> ProxyResult {
-    let mut request = client
-        .post(backend_url)
-        .header("content-type", "application/json");
+    let sent = backends
+        .send(model_id, backend_type, |client| {
+            let mut request = client
+                .post(backend_url)
+                .header("content-type", "application/json");
 
-    if let Some(key) = api_key {
-        request = request.header("authorization", format!("Bearer {}", key));
-    }
+            if let Some(key) = api_key {
+                request = request.header("authorization", format!("Bearer {}", key));
+            }
+
+            request.body(body.clone())
+        })
+        .await;
 
-    let response = match request.body(body).send().await {
+    let response = match sent {
         Ok(resp) => resp,
         Err(e) => {
             error!(error = %e, "Failed to connect to backend");
@@ -51,21 +161,25 @@ pub async fn proxy_to_backend(
                     ))
                     .unwrap(),
                 body_bytes: None,
+                stream_usage: None,
             };
         }
     };
 
     let status = response.status();
-    let headers = response.headers().clone();
 
     if is_streaming {
-        // Stream SSE events back to the client
-        let stream = response.bytes_stream().map(|chunk| {
-            chunk.map_err(|e| {
-                error!(error = %e, "Error streaming from backend");
-                std::io::Error::other(e)
-            })
-        });
+        // Stream SSE events back to the client frame-for-frame
+        let transfer_encoding = response.headers().get("transfer-encoding").cloned();
+        let (usage_tx, usage_rx) = oneshot::channel();
+        let stream = PassThrough {
+            inner: Box::pin(response.bytes_stream()),
+            meter: backends.stream_meter(model_id).await,
+            usage: Some(UsageTap {
+                parser: SseUsageParser::default(),
+                tx: Some(usage_tx),
+            }),
+        };
 
         let mut builder = Response::builder()
             .status(status.as_u16())
@@ -74,21 +188,25 @@ pub async fn proxy_to_backend(
             .header("connection", "keep-alive");
 
         // Preserve transfer-encoding if present
-        if let Some(te) = headers.get("transfer-encoding") {
+        if let Some(te) = transfer_encoding {
             builder = builder.header("transfer-encoding", te);
         }
 
         ProxyResult {
-            response: builder.body(Body::from_stream(stream)).unwrap(),
+            response: builder
+                .body(Body::from_stream(KeepAlive::new(stream, keepalive)))
+                .unwrap(),
             body_bytes: None,
+            stream_usage: Some(usage_rx),
         }
     } else {
         // Non-streaming: collect full response and forward
+        let content_type = response.headers().get("content-type").cloned();
         match response.bytes().await {
             Ok(body_bytes) => {
                 let mut builder = Response::builder().status(status.as_u16());
 
-                if let Some(ct) = headers.get("content-type") {
+                if let Some(ct) = content_type {
                     builder = builder.header("content-type", ct);
                 } else {
                     builder = builder.header("content-type", "application/json");
@@ -97,6 +215,7 @@ pub async fn proxy_to_backend(
                 ProxyResult {
                     response: builder.body(Body::from(body_bytes.clone())).unwrap(),
                     body_bytes: Some(body_bytes),
+                    stream_usage: None,
                 }
             }
             Err(e) => {
@@ -117,8 +236,173 @@ pub async fn proxy_to_backend(
                         ))
                         .unwrap(),
                     body_bytes: None,
+                    stream_usage: None,
                 }
             }
         }
     }
 }
+
+/// Metering wrapper around the upstream body. Frames are forwarded exactly as
+/// the plain `bytes_stream().map(..)` adapter did; this type exists to count
+/// them and time the poll path for `poll_ns_per_frame`, and to let the usage
+/// tap read token counts off the frames on their way past. The clock is only
+/// read when a meter is attached: two monotonic reads per poll (tens of ns via
+/// vDSO) against a network read and an SSE frame per token.
+struct PassThrough<S> {
+    inner: Pin<Box<S>>,
+    meter: Option<StreamMeter>,
+    usage: Option<UsageTap>,
+}
+
+impl<S> Stream for PassThrough<S>
+where
+    S: Stream<Item = reqwest::Result<Bytes>>,
+{
+    type Item = Result<Bytes, std::io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let started = self.meter.as_ref().map(|_| Instant::now());
+        let polled = self.inner.as_mut().poll_next(cx);
+
+        if let (Some(meter), Some(started)) = (&self.meter, started) {
+            meter.record_poll(started.elapsed());
+            if let Poll::Ready(Some(Ok(frame))) = &polled {
+                meter.record_frame(frame.len());
+            }
+        }
+        if let Poll::Ready(Some(Ok(frame))) = &polled {
+            if let Some(tap) = self.usage.as_mut() {
+                tap.parser.feed(frame);
+            }
+        }
+
+        polled.map(|item| {
+            item.map(|chunk| {
+                chunk.map_err(|e| {
+                    error!(error = %e, "Error streaming from backend");
+                    std::io::Error::other(e)
+                })
+            })
+        })
+    }
+}
+
+/// SSE comment sent to keep idle connections open.
+const KEEPALIVE_COMMENT: &[u8] = b": keep-alive\n\n";
+
+/// Wraps an SSE body and emits a keep-alive comment every `period` until the
+/// inner stream yields its first item. After that it is a plain pass-through,
+/// so pings can never land inside an event.
+struct KeepAlive<S> {
+    inner: S,
+    ticker: Option<tokio::time::Interval>,
+}
+
+impl<S> KeepAlive<S> {
+    fn new(inner: S, period: Option<Duration>) -> Self {
+        let ticker = period.map(|period| {
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            ticker
+        });
+        Self { inner, ticker }
+    }
+}
+
+impl<S> Stream for KeepAlive<S>
+where
+    S: Stream<Item = Result<Bytes, std::io::Error>> + Unpin,
+{
+    type Item = Result<Bytes, std::io::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let polled = Pin::new(&mut self.inner).poll_next(cx);
+        if polled.is_ready() {
+            self.ticker = None;
+            return polled;
+        }
+        if let Some(ticker) = self.ticker.as_mut() {
+            if ticker.poll_tick(cx).is_ready() {
+                return Poll::Ready(Some(Ok(Bytes::from_static(KEEPALIVE_COMMENT))));
+            }
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn keepalive_pings_until_first_chunk() {
+        let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(4);
+        let mut stream = KeepAlive::new(
+            tokio_stream::wrappers::ReceiverStream::new(rx),
+            Some(Duration::from_millis(20)),
+        );
+
+        // Nothing from the backend yet: a ping after each period
+        let first = stream.next().await.unwrap().unwrap();
+        assert_eq!(&first[..], KEEPALIVE_COMMENT);
+
+        tx.send(Ok(Bytes::from_static(b"data: {}\n\n")))
+            .await
+            .unwrap();
+        let chunk = stream.next().await.unwrap().unwrap();
+        assert_eq!(&chunk[..], b"data: {}\n\n");
+
+        drop(tx);
+        assert!(stream.next().await.is_none());
+    }
+
+    #[test]
+    fn sse_usage_reads_final_usage_frame() {
+        let mut parser = SseUsageParser::default();
+        parser.feed(b"data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n");
+        parser.feed(b"data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n");
+        parser.feed(
+            b"data: {\"choices\":[],\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":5}}\n\ndata: [DONE]\n\n",
+        );
+        let usage = parser.usage();
+        assert_eq!(usage.prompt_tokens, Some(12));
+        assert_eq!(usage.completion_tokens, Some(5));
+        assert_eq!(usage.content_chunks, 1);
+    }
+
+    #[test]
+    fn sse_usage_counts_chunks_split_across_frames() {
+        let mut parser = SseUsageParser::default();
+        let stream = b"data: {\"choices\":[{\"text\":\"a\"}]}\r\n\r\ndata: {\"choices\":[{\"delta\":{\"content\":\"b\"}}]}\n\n: keep-alive\n\ndata: {\"choices\":[{\"delta\":{\"content\":\"\"},\"finish_reason\":\"stop\"}]}\n\n";
+        for piece in stream.chunks(7) {
+            parser.feed(piece);
+        }
+        let usage = parser.usage();
+        assert_eq!(usage.prompt_tokens, None);
+        assert_eq!(usage.completion_tokens, None);
+        assert_eq!(usage.content_chunks, 2);
+    }
+
+    #[tokio::test]
+    async fn usage_tap_reports_on_drop() {
+        let (tx, rx) = oneshot::channel();
+        let mut tap = UsageTap {
+            parser: SseUsageParser::default(),
+            tx: Some(tx),
+        };
+        tap.parser
+            .feed(b"data: {\"choices\":[{\"delta\":{\"content\":\"x\"}}]}\n\n");
+        drop(tap);
+        assert_eq!(rx.await.unwrap().content_chunks, 1);
+    }
+
+    #[tokio::test]
+    async fn keepalive_disabled_passes_through() {
+        let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(4);
+        let mut stream = KeepAlive::new(tokio_stream::wrappers::ReceiverStream::new(rx), None);
+        drop(tx);
+        assert!(stream.next().await.is_none());
+    }
+}
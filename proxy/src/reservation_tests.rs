@@ -13,7 +13,14 @@
 //! - **create_bad_time_format / create_not_30min_boundary / create_too_short /
 //!   create_start_in_past** — input validation rejects bad requests.
 //! - **create_overlap_rejected** — overlapping an approved slot returns 409.
+//! - **create_overlap_suggests_free_slots** — the 409 lists the blocking
+//!   window and free slots of the same duration that avoid it.
 //! - **create_adjacent_allowed** — back-to-back slots are fine.
+//! - **create_waitlisted_on_overlap / waitlist_promoted_when_blocker_cancelled /
+//!   waitlist_promotes_oldest_request_first** — `waitlist: true` queues an
+//!   overlapping request, and freeing the slot promotes the oldest one.
+//! - **create_auto_approved_by_policy / create_rejected_over_pending_limit /
+//!   create_rejected_over_max_duration** — booking policy settings apply.
 //! - **list_own_filters_by_user** — a user only sees their own reservations.
 //! - **cancel_own_pending** — users can cancel pending reservations.
 //! - **cancel_own_active_fails** — active reservations cannot be self-cancelled.
@@ -30,6 +37,11 @@
 //!   scheduler cache.
 //! - **admin_force_deactivate** — end an active reservation + verify cache clear.
 //! - **admin_delete_non_active / admin_delete_active_fails** — deletion rules.
+//! - **rule_auto_approves_matching_request** — an auto-approval rule created
+//!   through the admin API approves a matching request, but not one that is
+//!   too long or from a user with a recent rejection.
+//! - **admin_heatmap_sums_reserved_hours / admin_heatmap_rejects_bad_window** —
+//!   weekday/hour heatmap covers all 168 cells and validates `window`.
 //!
 //! ## 3. Enforcement — OpenAI inference (`/v1/chat/completions`, `/v1/completions`)
 //!
@@ -53,6 +65,10 @@
 //!   immediately restores access (no stale state).
 //! - **completions_endpoint_also_blocked** — `/v1/completions` is a separate
 //!   route; confirm it shares the same reservation enforcement.
+//! - **embeddings_endpoint_also_blocked** — `/v1/embeddings` shares the
+//!   reservation gate too.
+//! - **embeddings_reject_non_embedding_model** — models not flagged
+//!   `embedding` get `model_not_embedding` on `/v1/embeddings`.
 //! - **unloaded_model_rejected_before_reservation_check** — the model-loaded
 //!   check at openai.rs:101 fires *before* the reservation check at line 119,
 //!   so an unloaded model returns `model_not_loaded`, not `system_reserved`.
@@ -78,6 +94,12 @@
 //! - **holder_can_stop_model** — full success (dummy Docker's `stop_llamacpp`
 //!   returns Ok when container is absent). Verifies cleanup: `models.loaded` set
 //!   to 0, `container_secrets` row deleted, concurrency gate unregistered.
+//! - **graceful_stop_reports_abandoned_in_flight_after_timeout** — with the
+//!   only slot held and `timeout_secs: 0`, a graceful stop gives up on the
+//!   drain, reports one abandoned in-flight request and clears the `draining`
+//!   flag.
+//! - **force_stop_skips_drain** — `force: true` stops straight away and
+//!   reports mode `force`.
 //!
 //! # Test infrastructure
 //!
@@ -106,14 +128,16 @@ use chrono::{Duration, Timelike, Utc};
 use serde_json::Value;
 use tower::ServiceExt;
 
-use crate::api::{openai, reservation};
+use crate::api::{openai, reservation, reservation_rules};
 use crate::auth::tokens::hash_token;
 use crate::auth::{self, SessionAuth};
 use crate::config::AppConfig;
 use crate::db::Database;
 use crate::docker::DockerManager;
-use crate::metrics::MetricsBroadcaster;
-use crate::scheduler::reservation::{ActiveReservation, ReservationBroadcaster};
+use crate::events::EventBus;
+use crate::proxy::pool::{BackendClients, PoolSettings};
+use crate::scheduler::reservation::ActiveReservation;
+use crate::scheduler::settings::save_setting;
 use crate::scheduler::Scheduler;
 use crate::AppState;
 
@@ -134,9 +158,11 @@ fn test_config() -> AppConfig {
         model_path: "/tmp/test-models".to_string(),
         model_host_path: "/tmp/test-models".to_string(),
         ui_path: "/tmp/test-ui".to_string(),
+        sites_path: "/tmp/test-sites".to_string(),
         api_hostname: "localhost".to_string(),
         chat_hostname: "localhost".to_string(),
         cookie_domain: None,
+        session_redis_url: None,
         backend_network: "test-network".to_string(),
         acme_contact: None,
         acme_staging: false,
@@ -146,6 +172,74 @@ fn test_config() -> AppConfig {
         secure_cookies: false,
         db_encryption_key: None,
         db_encryption_key_old: None,
+        graceful_stop_timeout_secs: 120,
+        backend_pool_max_idle: 32,
+        backend_pool_idle_timeout_secs: 90,
+        backend_connect_timeout_secs: 5,
+        upstream_protocols: Default::default(),
+        sse_keepalive_secs: 15,
+        access_log_path: None,
+        access_log_max_bytes: 100 * 1024 * 1024,
+        access_log_max_files: 5,
+        access_log_webhook_url: None,
+        auth_rate_limit_per_minute: 0,
+        auth_rate_limit_burst: 10,
+        trust_forwarded_for: false,
+        trusted_proxy_hops: 1,
+        oidc_state_binding: "strict".to_string(),
+        trusted_device_ttl_days: 30,
+        jwt_access_tokens: false,
+        jwt_signing_key: None,
+        jwt_ttl_secs: 300,
+        token_cache_ttl_secs: 0,
+        token_cache_max_entries: 10_000,
+        model_name_normalize: true,
+        model_name_strip_prefixes: vec!["openai/".to_string()],
+        model_name_suggestions: true,
+        usage_webhook_url: None,
+        usage_webhook_secret: None,
+        usage_webhook_max_retries: 5,
+        event_webhook_url: None,
+        event_webhook_secret: None,
+        request_log: false,
+        autoload_timeout_secs: 300,
+        watchdog_interval_secs: 30,
+        watchdog_max_restarts: 3,
+        backend_warmup: false,
+        prompt_archive: false,
+        prompt_archive_retention_days: 90,
+        prompt_dedup_stats: false,
+        two_person_approval: false,
+        approval_window_minutes: 60,
+        sandbox_enabled: false,
+        sandbox_image: "python:3.12-alpine".to_string(),
+        sandbox_memory_mb: 256,
+        sandbox_cpus: 1.0,
+        sandbox_timeout_secs: 10,
+        sandbox_max_concurrent: 4,
+        backend_tls: false,
+        backend_tls_dir: "/config/backend-tls".to_string(),
+        backend_tls_host_path: "/config/backend-tls".to_string(),
+        host_memory_ceiling_percent: 0,
+        memory_critical_percent: 95,
+        memory_pressure_admission: false,
+        download_background_mb_per_sec: 0,
+        hf_download_connections: 1,
+        hf_download_chunk_mb: 64,
+        model_trash_hours: 24,
+        model_trash_max_gb: 200,
+        model_import_path: None,
+        model_store_s3: None,
+        s3_endpoint: None,
+        s3_region: "us-east-1".to_string(),
+        s3_access_key_id: None,
+        s3_secret_access_key: None,
+        model_cache_max_gb: 0,
+        gpu_probe: false,
+        auto_categorize: "off".to_string(),
+        request_log_retention_days: 30,
+        signed_url_key: None,
+        signed_url_max_ttl_secs: 3600,
     }
 }
 
@@ -153,11 +247,26 @@ async fn test_app_state() -> Arc<AppState> {
     let db = Database::test_db().await;
     Arc::new(AppState {
         config: test_config(),
+        sessions: crate::auth::sessions::Sessions::sqlite(&db),
         db,
         docker: DockerManager::test_dummy(),
         scheduler: Scheduler::new(),
-        metrics: MetricsBroadcaster::new(),
-        reservations: ReservationBroadcaster::new(),
+        events: EventBus::new(),
+        backends: BackendClients::new(PoolSettings::from_config(&test_config())),
+        jwt: None,
+        token_cache: crate::auth::token_cache::TokenCache::new(std::time::Duration::ZERO, 0),
+        signed_urls: crate::auth::signed_url::UrlSigner::new(b"test".to_vec(), 3600),
+        tasks: crate::tasks::TaskScheduler::new(),
+        flags: crate::feature_flags::FeatureFlags::default(),
+        host_routes: crate::host_routes::HostRoutes::default(),
+        watchdog: crate::watchdog::Watchdog::default(),
+        warmups: crate::api::warmup::Warmups::default(),
+        download_progress: crate::api::hf::DownloadProgress::default(),
+        replacing: crate::api::container_replace::Replacing::default(),
+        effective_config: None,
+        storage: crate::storage::Storage::default(),
+        autoloads: crate::api::common::Autoloads::default(),
+        ui_integrity: crate::ui_integrity::UiIntegrity::default(),
     })
 }
 
@@ -172,6 +281,7 @@ fn test_router(state: Arc<AppState>, user_id: &str, is_admin: bool) -> Router {
                 req.extensions_mut().insert(SessionAuth {
                     user_id,
                     is_admin,
+                    roles: Vec::new(),
                     email: None,
                     display_name: None,
                 });
@@ -181,7 +291,8 @@ fn test_router(state: Arc<AppState>, user_id: &str, is_admin: bool) -> Router {
     );
 
     let user_routes = reservation::user_routes(state.clone());
-    let admin_routes = reservation::admin_routes(state.clone());
+    let admin_routes = reservation::admin_routes(state.clone())
+        .merge(reservation_rules::admin_routes(state.clone()));
 
     Router::new()
         .nest("/user", user_routes)
@@ -234,6 +345,14 @@ async fn insert_reservation(
     id
 }
 
+async fn reservation_status(pool: &sqlx::Pool<sqlx::Sqlite>, id: &str) -> String {
+    sqlx::query_scalar("SELECT status FROM reservations WHERE id = ?")
+        .bind(id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
 /// Generate a future ISO time aligned to 30-min boundary.
 fn future_time(hours: i64) -> String {
     let dt = Utc::now() + Duration::hours(hours);
@@ -424,6 +543,52 @@ async fn create_overlap_rejected() {
     assert!(body["error"].as_str().unwrap().contains("overlap"));
 }
 
+#[tokio::test]
+async fn create_overlap_suggests_free_slots() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "user1").await;
+
+    let start = future_time(4);
+    let end = future_time(6);
+    let blocking = insert_reservation(&state.db.pool, "user1", "approved", &start, &end).await;
+
+    let router = test_router(state, "user1", false);
+    let (status, body) = json_post(
+        &router,
+        "/user/reservations",
+        serde_json::json!({ "start_time": start, "end_time": end }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["conflicts"][0]["id"], blocking.as_str());
+    assert_eq!(body["conflicts"][0]["start_time"], start.as_str());
+
+    let suggestions = body["suggestions"].as_array().unwrap();
+    assert_eq!(suggestions.len(), 3);
+    for slot in suggestions {
+        let s = slot["start_time"].as_str().unwrap();
+        let e = slot["end_time"].as_str().unwrap();
+        // Same 2h duration, and clear of the blocking window
+        let parse =
+            |t: &str| chrono::NaiveDateTime::parse_from_str(t, "%Y-%m-%dT%H:%M:%S").unwrap();
+        assert_eq!(parse(e) - parse(s), Duration::hours(2));
+        assert!(e <= start.as_str() || s >= end.as_str());
+    }
+
+    // Each suggestion can be booked as-is
+    let (status, _) = json_post(
+        &router,
+        "/user/reservations",
+        serde_json::json!({
+            "start_time": suggestions[0]["start_time"],
+            "end_time": suggestions[0]["end_time"],
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+}
+
 #[tokio::test]
 async fn create_adjacent_allowed() {
     let state = test_app_state().await;
@@ -450,6 +615,162 @@ async fn create_adjacent_allowed() {
     assert_eq!(status, StatusCode::CREATED);
 }
 
+#[tokio::test]
+async fn create_waitlisted_on_overlap() {
+    let state = test_app_state().await;
+    let start = future_time(2);
+    let end = future_time(4);
+    let blocking = insert_reservation(&state.db.pool, "user1", "approved", &start, &end).await;
+    ensure_test_user(&state.db.pool, "user2").await;
+
+    let router = test_router(state, "user2", false);
+    let (status, body) = json_post(
+        &router,
+        "/user/reservations",
+        serde_json::json!({ "start_time": start, "end_time": end, "waitlist": true }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["status"], "waitlisted");
+    assert_eq!(body["blocked_by"][0], blocking.as_str());
+}
+
+#[tokio::test]
+async fn waitlist_promoted_when_blocker_cancelled() {
+    let state = test_app_state().await;
+    let start = future_time(2);
+    let end = future_time(4);
+    let blocking = insert_reservation(&state.db.pool, "user1", "approved", &start, &end).await;
+    ensure_test_user(&state.db.pool, "user2").await;
+
+    let (_, body) = json_post(
+        &test_router(state.clone(), "user2", false),
+        "/user/reservations",
+        serde_json::json!({ "start_time": start, "end_time": end, "waitlist": true }),
+    )
+    .await;
+    let waitlisted = body["id"].as_str().unwrap().to_string();
+
+    let mut events = state.events.subscribe();
+    let (status, _) = json_post(
+        &test_router(state.clone(), "user1", false),
+        &format!("/user/reservations/{blocking}/cancel"),
+        serde_json::json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(events.try_recv().is_ok());
+
+    assert_eq!(
+        reservation_status(&state.db.pool, &waitlisted).await,
+        "pending"
+    );
+}
+
+#[tokio::test]
+async fn waitlist_promotes_oldest_request_first() {
+    let state = test_app_state().await;
+    let start = future_time(2);
+    let end = future_time(4);
+    let blocking = insert_reservation(&state.db.pool, "user1", "approved", &start, &end).await;
+    let first = insert_reservation(&state.db.pool, "user2", "waitlisted", &start, &end).await;
+    let second = insert_reservation(&state.db.pool, "user3", "waitlisted", &start, &end).await;
+    sqlx::query("UPDATE reservations SET created_at = datetime('now', '-1 hour') WHERE id = ?")
+        .bind(&first)
+        .execute(&state.db.pool)
+        .await
+        .unwrap();
+    set_booking_policy(&state, &[("reservation_auto_approve", "true")]).await;
+
+    ensure_test_user(&state.db.pool, "admin1").await;
+    let router = test_router(state.clone(), "admin1", true);
+    let (status, _) = json_delete(&router, &format!("/admin/reservations/{blocking}")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The oldest request takes the slot and the policy approves it
+    assert_eq!(reservation_status(&state.db.pool, &first).await, "approved");
+    assert_eq!(
+        reservation_status(&state.db.pool, &second).await,
+        "waitlisted"
+    );
+}
+
+/// Set booking policy keys and refresh the scheduler's cached settings.
+async fn set_booking_policy(state: &Arc<AppState>, pairs: &[(&str, &str)]) {
+    for (key, value) in pairs {
+        save_setting(&state.db, key, value).await.unwrap();
+    }
+    state.scheduler.reload_settings(&state.db).await.unwrap();
+}
+
+#[tokio::test]
+async fn create_auto_approved_by_policy() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "user1").await;
+    set_booking_policy(&state, &[("reservation_auto_approve", "true")]).await;
+
+    let router = test_router(state.clone(), "user1", false);
+    let (status, body) = json_post(
+        &router,
+        "/user/reservations",
+        serde_json::json!({ "start_time": future_time(2), "end_time": future_time(4) }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["status"], "approved");
+    let stored: (String,) = sqlx::query_as("SELECT status FROM reservations WHERE id = ?")
+        .bind(body["id"].as_str().unwrap())
+        .fetch_one(&state.db.pool)
+        .await
+        .unwrap();
+    assert_eq!(stored.0, "approved");
+}
+
+#[tokio::test]
+async fn create_rejected_over_pending_limit() {
+    let state = test_app_state().await;
+    set_booking_policy(&state, &[("reservation_max_pending_per_user", "1")]).await;
+    insert_reservation(
+        &state.db.pool,
+        "user1",
+        "pending",
+        &future_time(2),
+        &future_time(4),
+    )
+    .await;
+
+    let router = test_router(state, "user1", false);
+    let (status, body) = json_post(
+        &router,
+        "/user/reservations",
+        serde_json::json!({ "start_time": future_time(6), "end_time": future_time(8) }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("limit is 1"));
+}
+
+#[tokio::test]
+async fn create_rejected_over_max_duration() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "user1").await;
+    set_booking_policy(&state, &[("reservation_max_minutes", "60")]).await;
+
+    let router = test_router(state, "user1", false);
+    let (status, body) = json_post(
+        &router,
+        "/user/reservations",
+        serde_json::json!({ "start_time": future_time(2), "end_time": future_time(4) }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("Maximum"));
+}
+
 #[tokio::test]
 async fn list_own_filters_by_user() {
     let state = test_app_state().await;
@@ -751,6 +1072,134 @@ async fn admin_delete_active_fails() {
     assert!(body["error"].as_str().unwrap().contains("deactivate"));
 }
 
+#[tokio::test]
+async fn rule_auto_approves_matching_request() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    ensure_test_user(&state.db.pool, "user1").await;
+    ensure_test_user(&state.db.pool, "user2").await;
+    // user2 had a request turned down recently
+    insert_reservation(
+        &state.db.pool,
+        "user2",
+        "rejected",
+        &future_time(30),
+        &future_time(31),
+    )
+    .await;
+
+    let admin = test_router(state.clone(), "admin1", true);
+    let (status, rule) = json_post(
+        &admin,
+        "/admin/reservations/rules",
+        serde_json::json!({
+            "name": "short slots",
+            "max_duration_minutes": 120,
+            "good_standing_days": 90,
+        }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(rule["enabled"], true);
+
+    let user1 = test_router(state.clone(), "user1", false);
+    let (status, body) = json_post(
+        &user1,
+        "/user/reservations",
+        serde_json::json!({ "start_time": future_time(2), "end_time": future_time(4) }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["status"], "approved");
+    let note: (String,) = sqlx::query_as("SELECT admin_note FROM reservations WHERE id = ?")
+        .bind(body["id"].as_str().unwrap())
+        .fetch_one(&state.db.pool)
+        .await
+        .unwrap();
+    assert!(note.0.contains("short slots"));
+
+    // Longer than the rule allows
+    let (_, body) = json_post(
+        &user1,
+        "/user/reservations",
+        serde_json::json!({ "start_time": future_time(6), "end_time": future_time(9) }),
+    )
+    .await;
+    assert_eq!(body["status"], "pending");
+
+    // Not in good standing
+    let user2 = test_router(state, "user2", false);
+    let (_, body) = json_post(
+        &user2,
+        "/user/reservations",
+        serde_json::json!({ "start_time": future_time(12), "end_time": future_time(13) }),
+    )
+    .await;
+    assert_eq!(body["status"], "pending");
+}
+
+#[tokio::test]
+async fn admin_heatmap_sums_reserved_hours() {
+    let state = test_app_state().await;
+    // Two hours of a completed reservation inside the window, plus a pending
+    // and an out-of-window one that must not count
+    insert_reservation(
+        &state.db.pool,
+        "user1",
+        "completed",
+        &future_time(-50),
+        &future_time(-48),
+    )
+    .await;
+    insert_reservation(
+        &state.db.pool,
+        "user1",
+        "pending",
+        &future_time(-30),
+        &future_time(-28),
+    )
+    .await;
+    insert_reservation(
+        &state.db.pool,
+        "user1",
+        "completed",
+        &future_time(-24 * 40),
+        &future_time(-24 * 40 + 2),
+    )
+    .await;
+
+    ensure_test_user(&state.db.pool, "admin1").await;
+    let router = test_router(state, "admin1", true);
+
+    let (status, body) = json_get(&router, "/admin/reservations/heatmap?window=30d").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["window_days"], 30);
+    let cells = body["cells"].as_array().unwrap();
+    assert_eq!(cells.len(), 7 * 24);
+    let total: f64 = cells
+        .iter()
+        .map(|c| c["reserved_hours"].as_f64().unwrap())
+        .sum();
+    assert_eq!(total, 2.0);
+}
+
+#[tokio::test]
+async fn admin_heatmap_rejects_bad_window() {
+    let state = test_app_state().await;
+    ensure_test_user(&state.db.pool, "admin1").await;
+    let router = test_router(state, "admin1", true);
+
+    for window in ["30", "0d", "1000d", "abc"] {
+        let (status, _) = json_get(
+            &router,
+            &format!("/admin/reservations/heatmap?window={window}"),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "window={window}");
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Enforcement helpers — token creation, model setup, OpenAI router
 // ---------------------------------------------------------------------------
@@ -1052,6 +1501,55 @@ async fn completions_endpoint_also_blocked() {
     );
 }
 
+#[tokio::test]
+async fn embeddings_endpoint_also_blocked() {
+    let state = test_app_state().await;
+    let token = create_test_token(&state.db.pool, "user2", false).await;
+    insert_test_model(&state, "embed-model").await;
+    sqlx::query("UPDATE models SET embedding = 1 WHERE id = 'embed-model'")
+        .execute(&state.db.pool)
+        .await
+        .unwrap();
+    set_active(&state, "user1").await;
+    let router = openai_router(state);
+
+    let (status, body) = bearer_post(
+        &router,
+        "/v1/embeddings",
+        &token,
+        serde_json::json!({ "model": "embed-model", "input": "hello" }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        body.pointer("/error/code").and_then(|v| v.as_str()),
+        Some("system_reserved")
+    );
+}
+
+#[tokio::test]
+async fn embeddings_reject_non_embedding_model() {
+    let state = test_app_state().await;
+    let token = create_test_token(&state.db.pool, "user1", false).await;
+    insert_test_model(&state, "test-model").await;
+    let router = openai_router(state);
+
+    let (status, body) = bearer_post(
+        &router,
+        "/v1/embeddings",
+        &token,
+        serde_json::json!({ "model": "test-model", "input": "hello" }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body.pointer("/error/code").and_then(|v| v.as_str()),
+        Some("model_not_embedding")
+    );
+}
+
 #[tokio::test]
 async fn unloaded_model_rejected_before_reservation_check() {
     // The model-not-loaded check (openai.rs:101) runs before the reservation
@@ -1292,3 +1790,80 @@ async fn holder_can_stop_model() {
         "container_secrets should be cleaned up after stop"
     );
 }
+
+#[tokio::test]
+async fn graceful_stop_reports_abandoned_in_flight_after_timeout() {
+    let state = test_app_state().await;
+    let model_id = "glm-4-flash";
+    insert_gguf_model(&state.db.pool, model_id, "unsloth/GLM-4.7-Flash-GGUF").await;
+    sqlx::query("UPDATE models SET loaded = 1 WHERE id = ?")
+        .bind(model_id)
+        .execute(&state.db.pool)
+        .await
+        .unwrap();
+    state.scheduler.gate().register(model_id, 1).await;
+
+    // Hold the only slot so the drain can't complete
+    let settings = state.scheduler.settings().await;
+    let policy = state.scheduler.policy().await;
+    let _slot = state
+        .scheduler
+        .gate()
+        .acquire_with_timeout(
+            model_id,
+            None,
+            "user1",
+            None,
+            &state.db,
+            &settings,
+            policy.as_ref(),
+            state.scheduler.queue(),
+            std::time::Duration::from_secs(1),
+        )
+        .await
+        .unwrap();
+
+    set_active(&state, "user1").await;
+    let router = test_router(state.clone(), "user1", false);
+
+    let (status, body) = json_post(
+        &router,
+        "/user/reservations/containers/stop",
+        serde_json::json!({ "model_id": model_id, "timeout_secs": 0 }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["mode"], "graceful");
+    assert_eq!(body["drained"], false);
+    assert_eq!(body["abandoned_in_flight"], 1);
+
+    let draining: (bool,) = sqlx::query_as("SELECT draining FROM models WHERE id = ?")
+        .bind(model_id)
+        .fetch_one(&state.db.pool)
+        .await
+        .unwrap();
+    assert!(!draining.0, "drain flag should be cleared after stop");
+}
+
+#[tokio::test]
+async fn force_stop_skips_drain() {
+    let state = test_app_state().await;
+    let model_id = "glm-4-flash";
+    insert_gguf_model(&state.db.pool, model_id, "unsloth/GLM-4.7-Flash-GGUF").await;
+    state.scheduler.gate().register(model_id, 1).await;
+
+    set_active(&state, "user1").await;
+    let router = test_router(state, "user1", false);
+
+    let (status, body) = json_post(
+        &router,
+        "/user/reservations/containers/stop",
+        serde_json::json!({ "model_id": model_id, "force": true }),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["mode"], "force");
+    assert_eq!(body["drained"], true);
+}
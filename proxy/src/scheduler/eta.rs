@@ -0,0 +1,218 @@
+//! Queue wait-time prediction.
+//!
+//! Replaces "average time spent waiting so far" with a forward-looking
+//! estimate: simulate the queue draining through the model's slots, using
+//! historical service times from `usage_log` and the `max_tokens` each queued
+//! request asked for.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::db::Database;
+
+/// Historical service-time profile for one model.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelTiming {
+    /// Mean time from slot acquisition to completion (latency minus queue time).
+    pub avg_service_ms: f64,
+    /// Mean generation time per output token, when output tokens were recorded.
+    pub ms_per_output_token: Option<f64>,
+}
+
+impl ModelTiming {
+    /// Expected service time for a request with the given `max_tokens`.
+    ///
+    /// `max_tokens` is an upper bound, so it can only shorten the estimate.
+    fn expected_service_ms(&self, max_tokens: Option<u32>) -> f64 {
+        match (max_tokens, self.ms_per_output_token) {
+            (Some(m), Some(per_token)) => (m as f64 * per_token).min(self.avg_service_ms),
+            _ => self.avg_service_ms,
+        }
+    }
+}
+
+/// Load per-model timings from the last `window_hours` of usage.
+pub async fn load_model_timings(
+    db: &Database,
+    window_hours: i64,
+) -> Result<HashMap<String, ModelTiming>> {
+    let rows: Vec<(String, f64, i64, i64)> = sqlx::query_as(
+        r#"
+        SELECT model_id,
+               AVG(MAX(latency_ms - queued_ms, 0)) as avg_service_ms,
+               COALESCE(SUM(output_tokens), 0) as output_tokens,
+               COALESCE(SUM(MAX(latency_ms - queued_ms, 0)), 0) as service_ms
+        FROM usage_log
+        WHERE created_at >= datetime('now', ?)
+        GROUP BY model_id
+        "#,
+    )
+    .bind(format!("-{window_hours} hours"))
+    .fetch_all(&db.pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(model_id, avg_service_ms, output_tokens, service_ms)| {
+            let ms_per_output_token =
+                (output_tokens > 0).then(|| service_ms as f64 / output_tokens as f64);
+            (
+                model_id,
+                ModelTiming {
+                    avg_service_ms,
+                    ms_per_output_token,
+                },
+            )
+        })
+        .collect())
+}
+
+/// Predict how long a request arriving now would wait for a slot.
+///
+/// * `max_slots` / `in_flight_ages` — gate capacity and how long each running
+///   request has been going. A running request is assumed to finish after
+///   `avg_service_ms` total (or immediately, if it has already overrun).
+/// * `queued` — `max_tokens` of requests already waiting, in dequeue order.
+///
+/// Each queued request takes the earliest free slot; the new request starts
+/// when the next slot frees after all of them.
+pub fn predict_wait_ms(
+    timing: &ModelTiming,
+    max_slots: u32,
+    in_flight_ages: &[Duration],
+    queued: &[Option<u32>],
+) -> i64 {
+    if max_slots == 0 {
+        return 0;
+    }
+
+    // Time (ms from now) at which each slot next becomes free
+    let mut free_at: Vec<f64> = in_flight_ages
+        .iter()
+        .take(max_slots as usize)
+        .map(|age| (timing.avg_service_ms - age.as_millis() as f64).max(0.0))
+        .collect();
+    free_at.resize(max_slots as usize, 0.0);
+
+    for max_tokens in queued {
+        let slot = earliest(&free_at);
+        free_at[slot] += timing.expected_service_ms(*max_tokens);
+    }
+
+    free_at[earliest(&free_at)].round() as i64
+}
+
+fn earliest(free_at: &[f64]) -> usize {
+    free_at
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timing() -> ModelTiming {
+        ModelTiming {
+            avg_service_ms: 1000.0,
+            ms_per_output_token: Some(10.0),
+        }
+    }
+
+    #[test]
+    fn free_slot_means_no_wait() {
+        assert_eq!(predict_wait_ms(&timing(), 2, &[Duration::ZERO], &[]), 0);
+    }
+
+    #[test]
+    fn waits_for_remaining_in_flight_time() {
+        let ages = [Duration::from_millis(400)];
+        assert_eq!(predict_wait_ms(&timing(), 1, &ages, &[]), 600);
+    }
+
+    #[test]
+    fn overrunning_request_counts_as_finishing_now() {
+        let ages = [Duration::from_millis(5000)];
+        assert_eq!(predict_wait_ms(&timing(), 1, &ages, &[]), 0);
+    }
+
+    #[test]
+    fn queued_requests_add_service_time() {
+        let ages = [Duration::ZERO];
+        // 1000 remaining + two queued at full service time
+        assert_eq!(predict_wait_ms(&timing(), 1, &ages, &[None, None]), 3000);
+    }
+
+    #[test]
+    fn small_max_tokens_shortens_estimate() {
+        let ages = [Duration::ZERO];
+        // 1000 remaining + 20 tokens * 10ms
+        assert_eq!(predict_wait_ms(&timing(), 1, &ages, &[Some(20)]), 1200);
+        // max_tokens above the average never lengthens it
+        assert_eq!(predict_wait_ms(&timing(), 1, &ages, &[Some(10_000)]), 2000);
+    }
+
+    #[test]
+    fn queued_work_spreads_across_slots() {
+        let ages = [Duration::ZERO, Duration::ZERO];
+        // Two slots busy for 1000ms, three queued: slot A takes q1+q3, slot B q2
+        // → free at 3000 and 2000; new arrival starts at 2000.
+        assert_eq!(
+            predict_wait_ms(&timing(), 2, &ages, &[None, None, None]),
+            2000
+        );
+    }
+
+    #[test]
+    fn zero_slots_does_not_panic() {
+        assert_eq!(predict_wait_ms(&timing(), 0, &[], &[None]), 0);
+    }
+
+    async fn insert_usage(db: &Database, model: &str, latency: i64, queued: i64, output: i64) {
+        sqlx::query(
+            "INSERT OR IGNORE INTO idp_configs (id, name, issuer, client_id, client_secret_enc)
+             VALUES ('test-idp', 'test', 'https://test', 'client', 'secret')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT OR IGNORE INTO users (id, idp_id, subject, email)
+             VALUES ('u1', 'test-idp', 'u1', 'u1@test.com')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO usage_log (id, user_id, model_id, output_tokens, latency_ms, queued_ms)
+             VALUES (?, 'u1', ?, ?, ?, ?)",
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(model)
+        .bind(output)
+        .bind(latency)
+        .bind(queued)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn load_timings_excludes_queue_time() {
+        let db = Database::test_db().await;
+        insert_usage(&db, "m1", 1500, 500, 50).await;
+        insert_usage(&db, "m1", 3000, 0, 150).await;
+
+        let timings = load_model_timings(&db, 24).await.unwrap();
+        let m1 = timings.get("m1").unwrap();
+        // Service times 1000 and 3000
+        assert!((m1.avg_service_ms - 2000.0).abs() < f64::EPSILON);
+        // 4000ms over 200 tokens
+        assert!((m1.ms_per_output_token.unwrap() - 20.0).abs() < f64::EPSILON);
+    }
+}
@@ -1,9 +1,172 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
 use anyhow::Result;
 use chrono::Utc;
 
-use super::settings::FairnessSettings;
+use super::settings::{FairnessPolicyKind, FairnessSettings};
 use crate::db::Database;
 
+/// Everything a policy may use to score a request at enqueue time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PriorityInput {
+    /// Seconds the request has already waited.
+    pub wait_seconds: f64,
+    /// Tokens (input + output) the user consumed in the rolling window.
+    pub recent_tokens: i64,
+    /// Requests from the same user already waiting in this queue.
+    pub queued_ahead: usize,
+}
+
+/// A user's explicit queue priority (`users.priority_tier`), added on top of
+/// whatever the active policy scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PriorityTier {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl PriorityTier {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "low" => Some(Self::Low),
+            "normal" => Some(Self::Normal),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high",
+        }
+    }
+
+    /// Priority added for this tier: `±fairness_tier_weight`.
+    pub fn bonus(&self, settings: &FairnessSettings) -> f64 {
+        match self {
+            Self::Low => -settings.tier_weight,
+            Self::Normal => 0.0,
+            Self::High => settings.tier_weight,
+        }
+    }
+}
+
+/// Strategy for ordering queued requests. Higher score = dequeued first.
+///
+/// Implementations are swapped at runtime when the `fairness_policy` setting
+/// changes (see `Scheduler::reload_settings`), so they must not assume they
+/// live for the whole process.
+pub trait FairnessPolicy: Send + Sync + std::fmt::Debug {
+    /// The settings value that selects this policy.
+    fn kind(&self) -> FairnessPolicyKind;
+
+    /// Score a request about to be enqueued.
+    fn priority(&self, settings: &FairnessSettings, user_id: &str, input: &PriorityInput) -> f64;
+
+    /// Observe tokens consumed by a completed request. Stateless policies ignore this.
+    fn record_usage(&self, _settings: &FairnessSettings, _user_id: &str, _tokens: i64) {}
+}
+
+/// Build a fresh policy instance for the given kind.
+pub fn build_policy(kind: FairnessPolicyKind) -> Arc<dyn FairnessPolicy> {
+    match kind {
+        FairnessPolicyKind::WeightedUsage => Arc::new(WeightedUsagePolicy),
+        FairnessPolicyKind::RoundRobin => Arc::new(RoundRobinPolicy),
+        FairnessPolicyKind::TokenBucket => Arc::new(TokenBucketPolicy::default()),
+    }
+}
+
+/// The original log-scaled usage formula — see [`calculate_priority`].
+#[derive(Debug, Default)]
+pub struct WeightedUsagePolicy;
+
+impl FairnessPolicy for WeightedUsagePolicy {
+    fn kind(&self) -> FairnessPolicyKind {
+        FairnessPolicyKind::WeightedUsage
+    }
+
+    fn priority(&self, settings: &FairnessSettings, _user_id: &str, input: &PriorityInput) -> f64 {
+        calculate_priority(settings, input.wait_seconds, input.recent_tokens)
+    }
+}
+
+/// Strict round-robin between users, ignoring usage history.
+///
+/// A user's first queued request scores `base_priority`, the second
+/// `base_priority - 1`, and so on. Combined with FIFO tie-breaking in the
+/// queue, users take turns regardless of how many requests each one submits.
+#[derive(Debug, Default)]
+pub struct RoundRobinPolicy;
+
+impl FairnessPolicy for RoundRobinPolicy {
+    fn kind(&self) -> FairnessPolicyKind {
+        FairnessPolicyKind::RoundRobin
+    }
+
+    fn priority(&self, settings: &FairnessSettings, _user_id: &str, input: &PriorityInput) -> f64 {
+        settings.base_priority - input.queued_ahead as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    level: f64,
+    updated: Instant,
+}
+
+/// Per-user token bucket held in memory.
+///
+/// Each user starts with a full bucket of `bucket_capacity` tokens that
+/// refills at `bucket_refill_per_sec`. Completed requests drain it (it may go
+/// negative). Priority is `base_priority + wait_weight * wait +
+/// usage_weight * level / usage_scale`, so users with budget left go first.
+/// Buckets reset when the policy is swapped or the process restarts.
+#[derive(Debug, Default)]
+pub struct TokenBucketPolicy {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl TokenBucketPolicy {
+    /// Refill the user's bucket up to now and apply `delta`, returning the new level.
+    fn adjust(&self, settings: &FairnessSettings, user_id: &str, delta: f64) -> f64 {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        let bucket = buckets.entry(user_id.to_string()).or_insert(Bucket {
+            level: settings.bucket_capacity,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.level = (bucket.level + elapsed * settings.bucket_refill_per_sec)
+            .min(settings.bucket_capacity)
+            + delta;
+        bucket.updated = now;
+        bucket.level
+    }
+}
+
+impl FairnessPolicy for TokenBucketPolicy {
+    fn kind(&self) -> FairnessPolicyKind {
+        FairnessPolicyKind::TokenBucket
+    }
+
+    fn priority(&self, settings: &FairnessSettings, user_id: &str, input: &PriorityInput) -> f64 {
+        let level = self.adjust(settings, user_id, 0.0);
+        settings.base_priority
+            + settings.wait_weight * input.wait_seconds
+            + settings.usage_weight * level / settings.usage_scale
+    }
+
+    fn record_usage(&self, settings: &FairnessSettings, user_id: &str, tokens: i64) {
+        self.adjust(settings, user_id, -(tokens as f64));
+    }
+}
+
 /// Calculate a fair-use priority score for a request.
 ///
 /// Formula: priority = base_priority + (wait_weight * wait_seconds) - (usage_weight * ln(1 + recent_tokens / usage_scale))
@@ -47,15 +210,37 @@ pub async fn get_recent_usage(db: &Database, user_id: &str, window_minutes: i64)
     Ok(row.0)
 }
 
-/// Calculate priority for a user, querying their recent usage from the database.
+/// Look up a user's priority tier. Unknown users and unparseable values are
+/// `normal`.
+pub async fn get_priority_tier(db: &Database, user_id: &str) -> Result<PriorityTier> {
+    let tier: Option<String> = sqlx::query_scalar("SELECT priority_tier FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await?;
+    Ok(tier
+        .as_deref()
+        .and_then(PriorityTier::parse)
+        .unwrap_or_default())
+}
+
+/// Calculate priority for a user under `policy`, querying their recent usage
+/// and priority tier from the database.
 pub async fn calculate_user_priority(
     db: &Database,
     settings: &FairnessSettings,
+    policy: &dyn FairnessPolicy,
     user_id: &str,
     wait_seconds: f64,
+    queued_ahead: usize,
 ) -> Result<f64> {
     let recent_tokens = get_recent_usage(db, user_id, settings.window_minutes).await?;
-    Ok(calculate_priority(settings, wait_seconds, recent_tokens))
+    let input = PriorityInput {
+        wait_seconds,
+        recent_tokens,
+        queued_ahead,
+    };
+    let tier = get_priority_tier(db, user_id).await?;
+    Ok(policy.priority(settings, user_id, &input) + tier.bonus(settings))
 }
 
 #[cfg(test)]
@@ -219,6 +404,80 @@ mod tests {
         );
     }
 
+    // -- Policies ------------------------------------------------------------
+
+    #[test]
+    fn weighted_policy_matches_formula() {
+        let s = default_settings();
+        let input = PriorityInput {
+            wait_seconds: 5.0,
+            recent_tokens: 20_000,
+            queued_ahead: 3,
+        };
+        let p = WeightedUsagePolicy.priority(&s, "u1", &input);
+        assert!((p - calculate_priority(&s, 5.0, 20_000)).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn round_robin_ignores_usage_and_penalises_backlog() {
+        let s = default_settings();
+        let light_first = RoundRobinPolicy.priority(&s, "light", &PriorityInput::default());
+        let heavy_first = RoundRobinPolicy.priority(
+            &s,
+            "heavy",
+            &PriorityInput {
+                recent_tokens: 1_000_000,
+                ..Default::default()
+            },
+        );
+        let heavy_second = RoundRobinPolicy.priority(
+            &s,
+            "heavy",
+            &PriorityInput {
+                queued_ahead: 1,
+                ..Default::default()
+            },
+        );
+        assert!((light_first - heavy_first).abs() < f64::EPSILON);
+        assert!(heavy_second < light_first);
+    }
+
+    #[test]
+    fn token_bucket_drains_with_usage() {
+        let s = default_settings();
+        let policy = TokenBucketPolicy::default();
+        let before = policy.priority(&s, "u1", &PriorityInput::default());
+        policy.record_usage(&s, "u1", 40_000);
+        let after = policy.priority(&s, "u1", &PriorityInput::default());
+        let other = policy.priority(&s, "u2", &PriorityInput::default());
+        assert!(after < before);
+        assert!(other > after, "Untouched user should outrank drained user");
+    }
+
+    #[test]
+    fn token_bucket_never_exceeds_capacity() {
+        let s = FairnessSettings {
+            bucket_refill_per_sec: 1e12,
+            ..default_settings()
+        };
+        let policy = TokenBucketPolicy::default();
+        policy.record_usage(&s, "u1", 10);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        let level = policy.adjust(&s, "u1", 0.0);
+        assert!((level - s.bucket_capacity).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn build_policy_returns_requested_kind() {
+        for kind in [
+            FairnessPolicyKind::WeightedUsage,
+            FairnessPolicyKind::RoundRobin,
+            FairnessPolicyKind::TokenBucket,
+        ] {
+            assert_eq!(build_policy(kind).kind(), kind);
+        }
+    }
+
     // --- DB-dependent tests for get_recent_usage ---
 
     use crate::db::Database;
@@ -309,4 +568,44 @@ mod tests {
         let carol_total = get_recent_usage(&db, "carol", 60).await.unwrap();
         assert_eq!(carol_total, 200); // dave's usage not counted
     }
+
+    #[test]
+    fn priority_tier_round_trips() {
+        for tier in [PriorityTier::Low, PriorityTier::Normal, PriorityTier::High] {
+            assert_eq!(PriorityTier::parse(tier.as_str()), Some(tier));
+        }
+        assert_eq!(PriorityTier::parse("urgent"), None);
+    }
+
+    #[tokio::test]
+    async fn high_tier_outranks_heavy_usage() {
+        let db = Database::test_db().await;
+        let s = default_settings();
+        let policy = build_policy(s.policy);
+        let now = Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+        insert_usage(&db, "instructor", 500_000, 500_000, &now).await;
+        ensure_test_user(&db, "student").await;
+        sqlx::query("UPDATE users SET priority_tier = 'high' WHERE id = 'instructor'")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let instructor = calculate_user_priority(&db, &s, policy.as_ref(), "instructor", 0.0, 0)
+            .await
+            .unwrap();
+        let student = calculate_user_priority(&db, &s, policy.as_ref(), "student", 0.0, 0)
+            .await
+            .unwrap();
+        assert!(instructor > student);
+
+        assert_eq!(
+            get_priority_tier(&db, "student").await.unwrap(),
+            PriorityTier::Normal
+        );
+        assert_eq!(
+            get_priority_tier(&db, "nobody").await.unwrap(),
+            PriorityTier::Normal
+        );
+    }
 }
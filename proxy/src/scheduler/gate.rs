@@ -1,19 +1,56 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use tokio::sync::{oneshot, RwLock};
 use tracing::{debug, warn};
 use uuid::Uuid;
 
-use super::fairness;
+use super::fairness::{self, FairnessPolicy};
 use super::queue::RequestQueue;
+use super::queue_log::{self, QueueOutcome};
 use super::settings::FairnessSettings;
 use crate::db::Database;
 
-/// Error returned when a request times out waiting for a concurrency slot.
-#[derive(Debug)]
-pub struct QueueTimeout;
+/// Why a request didn't get a concurrency slot.
+#[derive(Debug, PartialEq, Eq)]
+pub enum GateError {
+    /// Timed out waiting in the queue.
+    Timeout,
+    /// The model's category already has `max_queue_depth` requests waiting.
+    QueueFull,
+}
+
+/// Handed to a caller that asked to hear when its request joins a queue, so
+/// it can look up the request's place while it waits.
+#[derive(Debug, Clone)]
+pub struct QueueTicket {
+    pub model_id: String,
+    pub request_id: String,
+}
+
+/// Limits shared by all models of a category (`None` = no limit).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CategoryLimits {
+    /// Requests in flight across the category's models.
+    pub max_concurrent: Option<u32>,
+    /// Requests waiting for a slot across the category's models.
+    pub max_queue_depth: Option<u32>,
+    /// How long a request waits for a slot; `None` follows the global
+    /// `queue_timeout_secs` setting.
+    pub queue_timeout_secs: Option<u32>,
+}
+
+/// Snapshot of a category's gate state (for observability).
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CategorySnapshot {
+    pub max_concurrent: Option<u32>,
+    pub max_queue_depth: Option<u32>,
+    pub queue_timeout_secs: Option<u32>,
+    pub in_flight: u32,
+    pub queued: u32,
+}
 
 /// Snapshot of a single model's gate state (for observability).
 #[derive(Debug, Clone, serde::Serialize)]
@@ -26,14 +63,34 @@ pub struct GateSnapshot {
 struct GateState {
     max_slots: u32,
     in_flight: u32,
+    /// Start time of each in-flight request, keyed by slot id.
+    started: HashMap<u64, Instant>,
+    /// Last request start or finish (registration time until the first one).
+    last_active: Instant,
+    /// Category of the latest request, so a freed category slot can wake
+    /// waiters on the category's other models.
+    category_id: Option<String>,
+}
+
+/// Per-category counters. Requests are counted whether or not the category
+/// has limits, so limits set later apply to the right numbers.
+#[derive(Debug, Default)]
+struct CategoryState {
+    limits: CategoryLimits,
+    in_flight: u32,
+    queued: u32,
 }
 
-/// Per-model concurrency limiter with fair-queue wakeup.
+/// Per-model concurrency limiter with fair-queue wakeup, plus optional
+/// limits per model category.
 ///
 /// Cloning is cheap — clones share the same underlying data via Arc.
+/// Locks are always taken models first, then categories.
 #[derive(Debug, Clone)]
 pub struct ConcurrencyGate {
     state: Arc<RwLock<HashMap<String, GateState>>>,
+    categories: Arc<RwLock<HashMap<String, CategoryState>>>,
+    next_slot_id: Arc<AtomicU64>,
 }
 
 impl std::fmt::Debug for GateState {
@@ -52,6 +109,17 @@ pub struct AcquiredSlot {
     gate: ConcurrencyGate,
     queue: RequestQueue,
     model_id: String,
+    category_id: Option<String>,
+    slot_id: u64,
+    queue_position: Option<usize>,
+}
+
+impl AcquiredSlot {
+    /// The request's place in the model's queue when it started waiting, or
+    /// None if it got a slot straight away.
+    pub fn queue_position(&self) -> Option<usize> {
+        self.queue_position
+    }
 }
 
 impl Drop for AcquiredSlot {
@@ -59,13 +127,94 @@ impl Drop for AcquiredSlot {
         let gate = self.gate.clone();
         let queue = self.queue.clone();
         let model_id = self.model_id.clone();
+        let category_id = self.category_id.take();
+        let slot_id = self.slot_id;
         // Spawn release as a task so it doesn't block if drop happens outside async context
         tokio::spawn(async move {
-            gate.release_and_wake(&model_id, &queue).await;
+            gate.release_and_wake(&model_id, category_id.as_deref(), slot_id, &queue)
+                .await;
+        });
+    }
+}
+
+/// Clean-up for a request that entered the queue. [`QueuedWait`] runs it
+/// once the wait ends, or when the waiting future is dropped because the
+/// client went away.
+struct WaitCleanup {
+    gate: ConcurrencyGate,
+    queue: RequestQueue,
+    db: Database,
+    model_id: String,
+    category_id: Option<String>,
+    request_id: String,
+    enqueued_at: chrono::DateTime<chrono::Utc>,
+    /// Whether the request made it into `queue`.
+    enqueued: bool,
+}
+
+impl WaitCleanup {
+    /// Leave the category queue, drop the queue entry and resolve the
+    /// persisted row.
+    async fn run(self, outcome: QueueOutcome) {
+        let removed = self
+            .queue
+            .remove_by_id(&self.model_id, &self.request_id)
+            .await;
+        let category_id = self.category_id.as_deref();
+        if let Some(category_id) = category_id {
+            self.gate.leave_category_queue(category_id).await;
+        }
+        // Woken but not served (the wake-up raced the timeout, or the
+        // client left): hand the wake-up on so the next waiter isn't stuck
+        if self.enqueued && !removed && outcome != QueueOutcome::Served {
+            if let Some(slot_id) = self.gate.try_acquire(&self.model_id, category_id).await {
+                self.gate
+                    .release_and_wake(&self.model_id, category_id, slot_id, &self.queue)
+                    .await;
+            }
+        }
+
+        // Resolve the persisted entry off the hot path
+        let wait_ms = (chrono::Utc::now() - self.enqueued_at).num_milliseconds();
+        let db = self.db;
+        let request_id = self.request_id;
+        tokio::spawn(async move {
+            if let Err(e) = queue_log::record_outcome(&db, &request_id, outcome, wait_ms).await {
+                warn!(error = %e, "Failed to resolve queue entry");
+            }
         });
     }
 }
 
+/// Guard for a request waiting for a slot. [`QueuedWait::finish`] cleans up
+/// with the wait's outcome; dropping the guard unfinished (the handler
+/// future was dropped mid-wait) cleans up in a task with
+/// [`QueueOutcome::Cancelled`], so the category's `queued` count and the
+/// queue never keep abandoned entries.
+struct QueuedWait(Option<WaitCleanup>);
+
+impl QueuedWait {
+    fn mark_enqueued(&mut self) {
+        if let Some(cleanup) = &mut self.0 {
+            cleanup.enqueued = true;
+        }
+    }
+
+    async fn finish(mut self, outcome: QueueOutcome) {
+        if let Some(cleanup) = self.0.take() {
+            cleanup.run(outcome).await;
+        }
+    }
+}
+
+impl Drop for QueuedWait {
+    fn drop(&mut self) {
+        if let Some(cleanup) = self.0.take() {
+            tokio::spawn(cleanup.run(QueueOutcome::Cancelled));
+        }
+    }
+}
+
 impl Default for ConcurrencyGate {
     fn default() -> Self {
         Self::new()
@@ -76,6 +225,8 @@ impl ConcurrencyGate {
     pub fn new() -> Self {
         Self {
             state: Arc::new(RwLock::new(HashMap::new())),
+            categories: Arc::new(RwLock::new(HashMap::new())),
+            next_slot_id: Arc::new(AtomicU64::new(1)),
         }
     }
 
@@ -96,6 +247,51 @@ impl ConcurrencyGate {
             .collect()
     }
 
+    /// Snapshot of every category seen so far, with its limits and counters.
+    pub async fn category_status(&self) -> HashMap<String, CategorySnapshot> {
+        let categories = self.categories.read().await;
+        categories
+            .iter()
+            .map(|(k, cs)| {
+                (
+                    k.clone(),
+                    CategorySnapshot {
+                        max_concurrent: cs.limits.max_concurrent,
+                        max_queue_depth: cs.limits.max_queue_depth,
+                        queue_timeout_secs: cs.limits.queue_timeout_secs,
+                        in_flight: cs.in_flight,
+                        queued: cs.queued,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Replace all category limits. Categories left out lose their limits;
+    /// counters are kept.
+    pub async fn set_category_limits(&self, limits: HashMap<String, CategoryLimits>) {
+        let mut categories = self.categories.write().await;
+        for cs in categories.values_mut() {
+            cs.limits = CategoryLimits::default();
+        }
+        for (category_id, limits) in limits {
+            categories.entry(category_id).or_default().limits = limits;
+        }
+    }
+
+    /// How long a request for a model in `category_id` waits for a slot:
+    /// the category's `queue_timeout_secs`, else `default`.
+    pub async fn queue_timeout(&self, category_id: Option<&str>, default: Duration) -> Duration {
+        let Some(category_id) = category_id else {
+            return default;
+        };
+        let categories = self.categories.read().await;
+        categories
+            .get(category_id)
+            .and_then(|cs| cs.limits.queue_timeout_secs)
+            .map_or(default, |secs| Duration::from_secs(u64::from(secs)))
+    }
+
     /// Register a model with its maximum parallel slots. Called on container start.
     pub async fn register(&self, model_id: &str, max_slots: u32) {
         let mut state = self.state.write().await;
@@ -104,6 +300,9 @@ impl ConcurrencyGate {
             GateState {
                 max_slots,
                 in_flight: 0,
+                started: HashMap::new(),
+                last_active: Instant::now(),
+                category_id: None,
             },
         );
         debug!(model = %model_id, max_slots, "Gate registered");
@@ -116,34 +315,152 @@ impl ConcurrencyGate {
         debug!(model = %model_id, "Gate unregistered");
     }
 
-    /// Non-blocking: try to acquire a slot. Returns true if under the limit.
-    async fn try_acquire(&self, model_id: &str) -> bool {
+    /// Change a model's slot limit, keeping the requests in flight. Used when
+    /// a blue/green replace switches to a container with a different
+    /// `parallel`; queued requests take up extra slots as running ones finish.
+    pub async fn resize(&self, model_id: &str, max_slots: u32) {
         let mut state = self.state.write().await;
         if let Some(gs) = state.get_mut(model_id) {
-            if gs.in_flight < gs.max_slots {
-                gs.in_flight += 1;
-                return true;
+            gs.max_slots = max_slots;
+            debug!(model = %model_id, max_slots, "Gate resized");
+            return;
+        }
+        drop(state);
+        self.register(model_id, max_slots).await;
+    }
+
+    /// Slot limit and in-flight request ages for a registered model (for ETA prediction).
+    pub async fn in_flight_ages(&self, model_id: &str) -> Option<(u32, Vec<Duration>)> {
+        let state = self.state.read().await;
+        state.get(model_id).map(|gs| {
+            let ages = gs.started.values().map(|t| t.elapsed()).collect();
+            (gs.max_slots, ages)
+        })
+    }
+
+    /// Requests in flight across all models.
+    pub async fn total_in_flight(&self) -> u32 {
+        let state = self.state.read().await;
+        state.values().map(|gs| gs.in_flight).sum()
+    }
+
+    /// How long each registered model with nothing in flight has been idle.
+    pub async fn idle_durations(&self) -> HashMap<String, Duration> {
+        let state = self.state.read().await;
+        state
+            .iter()
+            .filter(|(_, gs)| gs.in_flight == 0)
+            .map(|(k, gs)| (k.clone(), gs.last_active.elapsed()))
+            .collect()
+    }
+
+    /// Non-blocking: try to acquire a slot. Returns the slot id if both the
+    /// model and its category are under their limits.
+    async fn try_acquire(&self, model_id: &str, category_id: Option<&str>) -> Option<u64> {
+        let slot_id = self.next_slot_id.fetch_add(1, Ordering::Relaxed);
+        let mut state = self.state.write().await;
+        let mut categories = self.categories.write().await;
+        if let Some(gs) = state.get_mut(model_id) {
+            gs.category_id = category_id.map(str::to_string);
+        }
+        if let Some(category_id) = category_id {
+            let cs = categories.entry(category_id.to_string()).or_default();
+            if cs
+                .limits
+                .max_concurrent
+                .is_some_and(|max| cs.in_flight >= max)
+            {
+                return None;
             }
-        } else {
-            // Model not registered — allow through (no gate configured).
-            // This is a safety net; callers should only gate registered models.
-            return true;
         }
-        false
+        if let Some(gs) = state.get_mut(model_id) {
+            if gs.in_flight >= gs.max_slots {
+                return None;
+            }
+            gs.in_flight += 1;
+            gs.last_active = Instant::now();
+            gs.started.insert(slot_id, gs.last_active);
+        }
+        // An unregistered model is let through (no gate configured). This is
+        // a safety net; callers should only gate registered models.
+        if let Some(cs) = category_id.and_then(|c| categories.get_mut(c)) {
+            cs.in_flight += 1;
+        }
+        Some(slot_id)
     }
 
-    /// Decrement in-flight count and wake the highest-priority queued request.
-    async fn release_and_wake(&self, model_id: &str, queue: &RequestQueue) {
+    /// Take a place in the category's queue, unless `max_queue_depth`
+    /// requests are already waiting.
+    async fn try_enqueue_in_category(&self, category_id: &str) -> bool {
+        let mut categories = self.categories.write().await;
+        let cs = categories.entry(category_id.to_string()).or_default();
+        if cs
+            .limits
+            .max_queue_depth
+            .is_some_and(|max| cs.queued >= max)
         {
+            return false;
+        }
+        cs.queued += 1;
+        true
+    }
+
+    async fn leave_category_queue(&self, category_id: &str) {
+        let mut categories = self.categories.write().await;
+        if let Some(cs) = categories.get_mut(category_id) {
+            cs.queued = cs.queued.saturating_sub(1);
+        }
+    }
+
+    /// Decrement in-flight counts and wake the highest-priority queued request.
+    ///
+    /// With a category concurrency limit the freed category slot may be what
+    /// a request for another of the category's models is waiting for, so the
+    /// best waiter across the category's models with a free model slot wins.
+    async fn release_and_wake(
+        &self,
+        model_id: &str,
+        category_id: Option<&str>,
+        slot_id: u64,
+        queue: &RequestQueue,
+    ) {
+        let category_keys = {
             let mut state = self.state.write().await;
+            let mut categories = self.categories.write().await;
             if let Some(gs) = state.get_mut(model_id) {
                 gs.in_flight = gs.in_flight.saturating_sub(1);
+                gs.started.remove(&slot_id);
+                gs.last_active = Instant::now();
                 debug!(model = %model_id, in_flight = gs.in_flight, "Slot released");
             }
-        }
+            let limited = category_id
+                .and_then(|c| categories.get_mut(c))
+                .map(|cs| {
+                    cs.in_flight = cs.in_flight.saturating_sub(1);
+                    cs.limits.max_concurrent.is_some()
+                })
+                .unwrap_or(false);
+            category_id.filter(|_| limited).map(|category_id| {
+                let mut keys: Vec<String> = state
+                    .iter()
+                    .filter(|(k, gs)| {
+                        k.as_str() != model_id
+                            && gs.category_id.as_deref() == Some(category_id)
+                            && gs.in_flight < gs.max_slots
+                    })
+                    .map(|(k, _)| k.clone())
+                    .collect();
+                keys.push(model_id.to_string());
+                keys
+            })
+        };
 
-        // Wake the highest-priority queued request for this model
-        if let Some(req) = queue.dequeue(model_id).await {
+        // Wake the highest-priority queued request for this model (or category)
+        let next = match category_keys {
+            Some(keys) => queue.dequeue_best(&keys).await,
+            None => queue.dequeue(model_id).await,
+        };
+        if let Some(req) = next {
             debug!(model = %model_id, user = %req.user_id, "Waking queued request");
             // Send on the oneshot — if the receiver was dropped (timeout), this is a no-op
             let _ = req.waker.send(());
@@ -152,30 +469,104 @@ impl ConcurrencyGate {
 
     /// Acquire a concurrency slot, waiting up to `timeout` if all slots are busy.
     ///
-    /// When waiting, the request is enqueued with its fair-use priority so that
-    /// heavy users wait longer than light users.
+    /// When waiting, the request is enqueued with the priority assigned by the
+    /// active fairness `policy`. `category_id` is the model's category, whose
+    /// limits apply on top of the model's own.
     ///
     /// Returns an `AcquiredSlot` RAII guard that auto-releases on drop.
+    #[allow(clippy::too_many_arguments)]
     pub async fn acquire_with_timeout(
         &self,
         model_id: &str,
+        category_id: Option<&str>,
+        user_id: &str,
+        max_tokens: Option<u32>,
+        db: &Database,
+        settings: &FairnessSettings,
+        policy: &dyn FairnessPolicy,
+        queue: &RequestQueue,
+        timeout: Duration,
+    ) -> Result<AcquiredSlot, GateError> {
+        self.acquire_reporting(
+            model_id,
+            category_id,
+            user_id,
+            max_tokens,
+            db,
+            settings,
+            policy,
+            queue,
+            timeout,
+            None,
+        )
+        .await
+    }
+
+    /// [`acquire_with_timeout`](Self::acquire_with_timeout), sending a
+    /// [`QueueTicket`] on `queued` if the request has to wait. The sender is
+    /// dropped unused when a slot is free straight away.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn acquire_reporting(
+        &self,
+        model_id: &str,
+        category_id: Option<&str>,
         user_id: &str,
+        max_tokens: Option<u32>,
         db: &Database,
         settings: &FairnessSettings,
+        policy: &dyn FairnessPolicy,
         queue: &RequestQueue,
         timeout: Duration,
-    ) -> Result<AcquiredSlot, QueueTimeout> {
+        queued: Option<oneshot::Sender<QueueTicket>>,
+    ) -> Result<AcquiredSlot, GateError> {
+        let slot = |slot_id, queue_position| AcquiredSlot {
+            gate: self.clone(),
+            queue: queue.clone(),
+            model_id: model_id.to_string(),
+            category_id: category_id.map(str::to_string),
+            slot_id,
+            queue_position,
+        };
+
         // Fast path: slot available immediately
-        if self.try_acquire(model_id).await {
-            return Ok(AcquiredSlot {
-                gate: self.clone(),
-                queue: queue.clone(),
-                model_id: model_id.to_string(),
-            });
+        if let Some(slot_id) = self.try_acquire(model_id, category_id).await {
+            return Ok(slot(slot_id, None));
+        }
+
+        if let Some(category_id) = category_id {
+            if !self.try_enqueue_in_category(category_id).await {
+                debug!(model = %model_id, category = %category_id, "Category queue full");
+                return Err(GateError::QueueFull);
+            }
         }
 
+        // From here on every exit, including the future being dropped, goes
+        // through the guard's clean-up
+        let request_id = Uuid::new_v4().to_string();
+        let enqueued_at = chrono::Utc::now();
+        let mut wait = QueuedWait(Some(WaitCleanup {
+            gate: self.clone(),
+            queue: queue.clone(),
+            db: db.clone(),
+            model_id: model_id.to_string(),
+            category_id: category_id.map(str::to_string),
+            request_id: request_id.clone(),
+            enqueued_at,
+            enqueued: false,
+        }));
+
         // Slow path: enqueue and wait
-        let priority = match fairness::calculate_user_priority(db, settings, user_id, 0.0).await {
+        let queued_ahead = queue.user_depth(model_id, user_id).await;
+        let priority = match fairness::calculate_user_priority(
+            db,
+            settings,
+            policy,
+            user_id,
+            0.0,
+            queued_ahead,
+        )
+        .await
+        {
             Ok(p) => p,
             Err(e) => {
                 warn!(error = %e, "Failed to calculate priority, using base");
@@ -183,7 +574,12 @@ impl ConcurrencyGate {
             }
         };
 
-        let request_id = Uuid::new_v4().to_string();
+        // Persist the entry so a restart can report it as interrupted
+        if let Err(e) =
+            queue_log::record_enqueued(db, &request_id, user_id, model_id, enqueued_at).await
+        {
+            warn!(error = %e, "Failed to persist queue entry");
+        }
 
         let (tx, rx) = oneshot::channel();
 
@@ -193,47 +589,85 @@ impl ConcurrencyGate {
                 user_id: user_id.to_string(),
                 queue_key: model_id.to_string(),
                 priority,
-                enqueued_at: chrono::Utc::now(),
+                enqueued_at,
+                max_tokens,
                 waker: tx,
             })
             .await;
+        wait.mark_enqueued();
+        let position = queue.position(model_id, &request_id).await;
+        if let Some(queued) = queued {
+            let _ = queued.send(QueueTicket {
+                model_id: model_id.to_string(),
+                request_id: request_id.clone(),
+            });
+        }
 
         // Wait for wakeup or timeout
-        match tokio::time::timeout(timeout, rx).await {
+        let result = match tokio::time::timeout(timeout, rx).await {
             Ok(Ok(())) => {
                 // We were woken — the slot was already accounted for by release_and_wake
                 // We need to actually acquire the slot now
                 // The release_and_wake dequeued us AND released a slot, so re-acquire
-                if self.try_acquire(model_id).await {
-                    Ok(AcquiredSlot {
-                        gate: self.clone(),
-                        queue: queue.clone(),
-                        model_id: model_id.to_string(),
-                    })
+                if let Some(slot_id) = self.try_acquire(model_id, category_id).await {
+                    Ok(slot(slot_id, position))
                 } else {
                     // Race condition — another request grabbed the slot.
                     // This shouldn't happen with the current design but handle gracefully.
                     warn!(model = %model_id, "Woken but slot gone — treating as timeout");
-                    Err(QueueTimeout)
+                    Err(GateError::Timeout)
                 }
             }
             Ok(Err(_)) => {
                 // Sender dropped — gate was unregistered or similar
-                Err(QueueTimeout)
+                Err(GateError::Timeout)
             }
-            Err(_) => {
-                // Timeout — remove from queue and return 429
-                queue.remove_by_id(model_id, &request_id).await;
-                Err(QueueTimeout)
-            }
-        }
+            // Timeout — the guard removes us from the queue; the caller returns 429
+            Err(_) => Err(GateError::Timeout),
+        };
+
+        let outcome = if result.is_ok() {
+            QueueOutcome::Served
+        } else {
+            QueueOutcome::TimedOut
+        };
+        wait.finish(outcome).await;
+
+        result
     }
 }
 
+/// Read the limits of every category that has one.
+pub async fn load_category_limits(
+    db: &Database,
+) -> anyhow::Result<HashMap<String, CategoryLimits>> {
+    let rows: Vec<(String, Option<i64>, Option<i64>, Option<i64>)> = sqlx::query_as(
+        "SELECT id, max_concurrent, max_queue_depth, queue_timeout_secs FROM model_categories \
+         WHERE max_concurrent IS NOT NULL OR max_queue_depth IS NOT NULL \
+         OR queue_timeout_secs IS NOT NULL",
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(
+            |(id, max_concurrent, max_queue_depth, queue_timeout_secs)| {
+                let limits = CategoryLimits {
+                    max_concurrent: max_concurrent.and_then(|n| u32::try_from(n).ok()),
+                    max_queue_depth: max_queue_depth.and_then(|n| u32::try_from(n).ok()),
+                    queue_timeout_secs: queue_timeout_secs.and_then(|n| u32::try_from(n).ok()),
+                };
+                (id, limits)
+            },
+        )
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::db::Database;
+    use crate::scheduler::fairness::WeightedUsagePolicy;
     use crate::scheduler::queue::{QueuedRequest, RequestQueue};
     use crate::scheduler::settings::FairnessSettings;
 
@@ -244,9 +678,33 @@ mod tests {
         let gate = ConcurrencyGate::new();
         gate.register("m1", 2).await;
 
-        assert!(gate.try_acquire("m1").await);
-        assert!(gate.try_acquire("m1").await);
-        assert!(!gate.try_acquire("m1").await); // full
+        assert!(gate.try_acquire("m1", None).await.is_some());
+        assert!(gate.try_acquire("m1", None).await.is_some());
+        assert!(gate.try_acquire("m1", None).await.is_none()); // full
+    }
+
+    #[tokio::test]
+    async fn resize_keeps_in_flight() {
+        let gate = ConcurrencyGate::new();
+        gate.register("m1", 1).await;
+        assert!(gate.try_acquire("m1", None).await.is_some());
+
+        gate.resize("m1", 2).await;
+        assert_eq!(gate.status().await["m1"].in_flight, 1);
+        assert!(gate.try_acquire("m1", None).await.is_some());
+        assert!(gate.try_acquire("m1", None).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn idle_durations_skip_busy_models() {
+        let gate = ConcurrencyGate::new();
+        gate.register("busy", 1).await;
+        gate.register("idle", 1).await;
+        gate.try_acquire("busy", None).await.unwrap();
+
+        let idle = gate.idle_durations().await;
+        assert!(idle.contains_key("idle"));
+        assert!(!idle.contains_key("busy"));
     }
 
     #[tokio::test]
@@ -255,11 +713,11 @@ mod tests {
         let queue = RequestQueue::new();
         gate.register("m1", 1).await;
 
-        assert!(gate.try_acquire("m1").await);
-        assert!(!gate.try_acquire("m1").await); // full
+        assert!(gate.try_acquire("m1", None).await.is_some());
+        assert!(gate.try_acquire("m1", None).await.is_none()); // full
 
-        gate.release_and_wake("m1", &queue).await;
-        assert!(gate.try_acquire("m1").await); // freed
+        gate.release_and_wake("m1", None, 0, &queue).await;
+        assert!(gate.try_acquire("m1", None).await.is_some()); // freed
     }
 
     #[tokio::test]
@@ -276,11 +734,12 @@ mod tests {
                 queue_key: "m1".to_string(),
                 priority: 1.0,
                 enqueued_at: chrono::Utc::now(),
+                max_tokens: None,
                 waker: tx,
             })
             .await;
 
-        gate.release_and_wake("m1", &queue).await;
+        gate.release_and_wake("m1", None, 0, &queue).await;
 
         // The waker should have fired
         assert!(rx.await.is_ok());
@@ -288,23 +747,87 @@ mod tests {
         assert_eq!(queue.depth("m1").await, 0);
     }
 
+    #[tokio::test]
+    async fn category_limit_spans_models() {
+        let gate = ConcurrencyGate::new();
+        let queue = RequestQueue::new();
+        gate.register("m1", 2).await;
+        gate.register("m2", 2).await;
+        gate.set_category_limits(HashMap::from([(
+            "chat".to_string(),
+            CategoryLimits {
+                max_concurrent: Some(2),
+                ..Default::default()
+            },
+        )]))
+        .await;
+
+        let a = gate.try_acquire("m1", Some("chat")).await.unwrap();
+        assert!(gate.try_acquire("m2", Some("chat")).await.is_some());
+        // Both models have free slots, but the category is full
+        assert!(gate.try_acquire("m2", Some("chat")).await.is_none());
+        assert!(gate.try_acquire("m1", Some("chat")).await.is_none());
+        // Other categories are unaffected
+        assert!(gate.try_acquire("m1", Some("code")).await.is_some());
+
+        // Releasing on m1 wakes the waiter queued on m2
+        let (tx, rx) = oneshot::channel();
+        queue
+            .enqueue(QueuedRequest {
+                request_id: "r1".to_string(),
+                user_id: "u1".to_string(),
+                queue_key: "m2".to_string(),
+                priority: 1.0,
+                enqueued_at: chrono::Utc::now(),
+                max_tokens: None,
+                waker: tx,
+            })
+            .await;
+        gate.release_and_wake("m1", Some("chat"), a, &queue).await;
+        assert!(rx.await.is_ok());
+        assert!(gate.try_acquire("m2", Some("chat")).await.is_some());
+
+        let status = gate.category_status().await;
+        assert_eq!(status["chat"].in_flight, 2);
+        assert_eq!(status["code"].in_flight, 1);
+        assert_eq!(status["code"].max_concurrent, None);
+    }
+
     #[tokio::test]
     async fn unregistered_model_allows_through() {
         let gate = ConcurrencyGate::new();
         // No register call — should fail-open
-        assert!(gate.try_acquire("unknown").await);
+        assert!(gate.try_acquire("unknown", None).await.is_some());
     }
 
     #[tokio::test]
     async fn unregister_removes_gate() {
         let gate = ConcurrencyGate::new();
         gate.register("m1", 1).await;
-        assert!(gate.try_acquire("m1").await);
-        assert!(!gate.try_acquire("m1").await); // full
+        assert!(gate.try_acquire("m1", None).await.is_some());
+        assert!(gate.try_acquire("m1", None).await.is_none()); // full
 
         gate.unregister("m1").await;
         // After unregistering, fail-open applies
-        assert!(gate.try_acquire("m1").await);
+        assert!(gate.try_acquire("m1", None).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn in_flight_ages_track_acquire_and_release() {
+        let gate = ConcurrencyGate::new();
+        let queue = RequestQueue::new();
+        gate.register("m1", 2).await;
+        assert!(gate.in_flight_ages("unknown").await.is_none());
+
+        let a = gate.try_acquire("m1", None).await.unwrap();
+        let _b = gate.try_acquire("m1", None).await.unwrap();
+        let (max_slots, ages) = gate.in_flight_ages("m1").await.unwrap();
+        assert_eq!(max_slots, 2);
+        assert_eq!(ages.len(), 2);
+
+        gate.release_and_wake("m1", None, a, &queue).await;
+        let (_, ages) = gate.in_flight_ages("m1").await.unwrap();
+        assert_eq!(ages.len(), 1);
     }
 
     #[tokio::test]
@@ -314,13 +837,13 @@ mod tests {
         gate.register("m1", 2).await;
 
         // Release without any acquire — should not underflow below 0
-        gate.release_and_wake("m1", &queue).await;
-        gate.release_and_wake("m1", &queue).await;
+        gate.release_and_wake("m1", None, 0, &queue).await;
+        gate.release_and_wake("m1", None, 0, &queue).await;
 
         // Should still be able to acquire max_slots times
-        assert!(gate.try_acquire("m1").await);
-        assert!(gate.try_acquire("m1").await);
-        assert!(!gate.try_acquire("m1").await);
+        assert!(gate.try_acquire("m1", None).await.is_some());
+        assert!(gate.try_acquire("m1", None).await.is_some());
+        assert!(gate.try_acquire("m1", None).await.is_none());
     }
 
     // ── Group B: full acquire flow (DB needed) ──
@@ -336,14 +859,17 @@ mod tests {
         let result = gate
             .acquire_with_timeout(
                 "m1",
+                None,
                 "user1",
+                None,
                 &db,
                 &settings,
+                &WeightedUsagePolicy,
                 &queue,
                 Duration::from_secs(1),
             )
             .await;
-        assert!(result.is_ok());
+        assert_eq!(result.unwrap().queue_position(), None);
     }
 
     #[tokio::test]
@@ -358,9 +884,12 @@ mod tests {
         let _slot1 = gate
             .acquire_with_timeout(
                 "m1",
+                None,
                 "user1",
+                None,
                 &db,
                 &settings,
+                &WeightedUsagePolicy,
                 &queue,
                 Duration::from_secs(5),
             )
@@ -376,9 +905,12 @@ mod tests {
             gate2
                 .acquire_with_timeout(
                     "m1",
+                    None,
                     "user2",
+                    None,
                     &db2,
                     &settings2,
+                    &WeightedUsagePolicy,
                     &queue2,
                     Duration::from_secs(5),
                 )
@@ -394,8 +926,57 @@ mod tests {
         // Give the drop's spawned task time to run
         tokio::time::sleep(Duration::from_millis(50)).await;
 
-        let result = handle.await.unwrap();
-        assert!(result.is_ok());
+        let slot = handle.await.unwrap().unwrap();
+        assert_eq!(slot.queue_position(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn reports_ticket_only_when_queued() {
+        let db = Database::test_db().await;
+        let gate = ConcurrencyGate::new();
+        let queue = RequestQueue::new();
+        let settings = FairnessSettings::default();
+        gate.register("m1", 1).await;
+
+        // A free slot: the sender is dropped unused
+        let (tx, rx) = oneshot::channel();
+        let _slot = gate
+            .acquire_reporting(
+                "m1",
+                None,
+                "user1",
+                None,
+                &db,
+                &settings,
+                &WeightedUsagePolicy,
+                &queue,
+                Duration::from_secs(5),
+                Some(tx),
+            )
+            .await
+            .unwrap();
+        assert!(rx.await.is_err());
+
+        // A busy model: the ticket names the queue entry
+        let (tx, rx) = oneshot::channel();
+        let result = gate
+            .acquire_reporting(
+                "m1",
+                None,
+                "user2",
+                None,
+                &db,
+                &settings,
+                &WeightedUsagePolicy,
+                &queue,
+                Duration::from_millis(50),
+                Some(tx),
+            )
+            .await;
+        assert_eq!(result.err(), Some(GateError::Timeout));
+        let ticket = rx.await.unwrap();
+        assert_eq!(ticket.model_id, "m1");
+        assert!(!ticket.request_id.is_empty());
     }
 
     #[tokio::test]
@@ -410,9 +991,12 @@ mod tests {
         let _slot = gate
             .acquire_with_timeout(
                 "m1",
+                None,
                 "user1",
+                None,
                 &db,
                 &settings,
+                &WeightedUsagePolicy,
                 &queue,
                 Duration::from_secs(5),
             )
@@ -423,9 +1007,12 @@ mod tests {
         let result = gate
             .acquire_with_timeout(
                 "m1",
+                None,
                 "user2",
+                None,
                 &db,
                 &settings,
+                &WeightedUsagePolicy,
                 &queue,
                 Duration::from_millis(50),
             )
@@ -445,9 +1032,12 @@ mod tests {
         let _slot = gate
             .acquire_with_timeout(
                 "m1",
+                None,
                 "user1",
+                None,
                 &db,
                 &settings,
+                &WeightedUsagePolicy,
                 &queue,
                 Duration::from_secs(5),
             )
@@ -458,9 +1048,12 @@ mod tests {
         let _ = gate
             .acquire_with_timeout(
                 "m1",
+                None,
                 "user2",
+                None,
                 &db,
                 &settings,
+                &WeightedUsagePolicy,
                 &queue,
                 Duration::from_millis(50),
             )
@@ -470,6 +1063,146 @@ mod tests {
         assert_eq!(queue.depth("m1").await, 0);
     }
 
+    #[tokio::test]
+    async fn queue_timeout_follows_category() {
+        let gate = ConcurrencyGate::new();
+        let default = Duration::from_secs(30);
+        gate.set_category_limits(HashMap::from([(
+            "large".to_string(),
+            CategoryLimits {
+                queue_timeout_secs: Some(300),
+                ..Default::default()
+            },
+        )]))
+        .await;
+
+        assert_eq!(
+            gate.queue_timeout(Some("large"), default).await,
+            Duration::from_secs(300)
+        );
+        assert_eq!(gate.queue_timeout(Some("small"), default).await, default);
+        assert_eq!(gate.queue_timeout(None, default).await, default);
+
+        // Clearing the category's limits goes back to the default
+        gate.set_category_limits(HashMap::new()).await;
+        assert_eq!(gate.queue_timeout(Some("large"), default).await, default);
+    }
+
+    #[tokio::test]
+    async fn category_queue_full_rejects_without_waiting() {
+        let db = Database::test_db().await;
+        let gate = ConcurrencyGate::new();
+        let queue = RequestQueue::new();
+        let settings = FairnessSettings::default();
+        gate.register("m1", 1).await;
+        gate.set_category_limits(HashMap::from([(
+            "chat".to_string(),
+            CategoryLimits {
+                max_queue_depth: Some(1),
+                ..Default::default()
+            },
+        )]))
+        .await;
+
+        let acquire = |user: &'static str, timeout: Duration| {
+            let gate = gate.clone();
+            let queue = queue.clone();
+            let db = db.clone();
+            let settings = settings.clone();
+            async move {
+                gate.acquire_with_timeout(
+                    "m1",
+                    Some("chat"),
+                    user,
+                    None,
+                    &db,
+                    &settings,
+                    &WeightedUsagePolicy,
+                    &queue,
+                    timeout,
+                )
+                .await
+            }
+        };
+
+        let _slot = acquire("user1", Duration::from_secs(1)).await.unwrap();
+        let waiter = tokio::spawn(acquire("user2", Duration::from_millis(200)));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(queue.depth("m1").await, 1);
+
+        // The category's one queue place is taken
+        let start = Instant::now();
+        let err = acquire("user3", Duration::from_secs(5)).await.unwrap_err();
+        assert_eq!(err, GateError::QueueFull);
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // Once the waiter gives up its place is free again
+        assert_eq!(waiter.await.unwrap().unwrap_err(), GateError::Timeout);
+        assert_eq!(gate.category_status().await["chat"].queued, 0);
+    }
+
+    #[tokio::test]
+    async fn dropped_waiter_leaves_queue() {
+        let db = Database::test_db().await;
+        let gate = ConcurrencyGate::new();
+        let queue = RequestQueue::new();
+        let settings = FairnessSettings::default();
+        gate.register("m1", 1).await;
+        gate.set_category_limits(HashMap::from([(
+            "chat".to_string(),
+            CategoryLimits {
+                max_queue_depth: Some(1),
+                ..Default::default()
+            },
+        )]))
+        .await;
+
+        let acquire = |user: &'static str, timeout: Duration| {
+            let gate = gate.clone();
+            let queue = queue.clone();
+            let db = db.clone();
+            let settings = settings.clone();
+            async move {
+                gate.acquire_with_timeout(
+                    "m1",
+                    Some("chat"),
+                    user,
+                    None,
+                    &db,
+                    &settings,
+                    &WeightedUsagePolicy,
+                    &queue,
+                    timeout,
+                )
+                .await
+            }
+        };
+
+        let slot = acquire("user1", Duration::from_secs(1)).await.unwrap();
+        // A client that disconnects mid-wait: its handler future is dropped
+        let abandoned = tokio::spawn(acquire("user2", Duration::from_secs(30)));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(gate.category_status().await["chat"].queued, 1);
+        abandoned.abort();
+        let _ = abandoned.await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(gate.category_status().await["chat"].queued, 0);
+        assert_eq!(queue.depth("m1").await, 0);
+        let (status,): (String,) =
+            sqlx::query_as("SELECT status FROM queue_log WHERE user_id = 'user2'")
+                .fetch_one(&db.pool)
+                .await
+                .unwrap();
+        assert_eq!(status, "cancelled");
+
+        // The freed place and the next slot go to a live waiter
+        let waiter = tokio::spawn(acquire("user3", Duration::from_secs(5)));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        drop(slot);
+        assert!(waiter.await.unwrap().is_ok());
+    }
+
     #[tokio::test]
     async fn raii_guard_releases_on_drop() {
         let db = Database::test_db().await;
@@ -482,9 +1215,12 @@ mod tests {
             let _slot = gate
                 .acquire_with_timeout(
                     "m1",
+                    None,
                     "user1",
+                    None,
                     &db,
                     &settings,
+                    &WeightedUsagePolicy,
                     &queue,
                     Duration::from_secs(1),
                 )
@@ -497,6 +1233,6 @@ mod tests {
         tokio::time::sleep(Duration::from_millis(50)).await;
 
         // Slot should be freed — can acquire again
-        assert!(gate.try_acquire("m1").await);
+        assert!(gate.try_acquire("m1", None).await.is_some());
     }
 }
@@ -1,6 +1,8 @@
+pub mod eta;
 pub mod fairness;
 pub mod gate;
 pub mod queue;
+pub mod queue_log;
 pub mod reservation;
 pub mod resolver;
 pub mod settings;
@@ -9,15 +11,31 @@ pub mod usage;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use tokio::sync::RwLock;
+use tracing::info;
 
 use crate::db::Database;
+use eta::ModelTiming;
+use fairness::FairnessPolicy;
 use gate::ConcurrencyGate;
-use queue::{QueueStats, RequestQueue};
+use queue::{QueuePosition, QueueStats, RequestQueue};
 use reservation::ActiveReservation;
-use resolver::ResolvedModel;
+use resolver::{NameMatching, ResolvedModel};
 use settings::FairnessSettings;
 
+/// A system-wide drain: new inference requests are refused until an admin
+/// resumes.
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemDrain {
+    pub started_at: DateTime<Utc>,
+    pub started_by: String,
+    pub reason: Option<String>,
+    /// Sent to refused clients as `Retry-After`.
+    pub retry_after_secs: u64,
+}
+
 /// The scheduler manages per-model queues, concurrency gating, fair-use priority,
 /// and model resolution.
 ///
@@ -27,7 +45,11 @@ pub struct Scheduler {
     queue: RequestQueue,
     gate: ConcurrencyGate,
     settings: Arc<RwLock<FairnessSettings>>,
+    policy: Arc<RwLock<Arc<dyn FairnessPolicy>>>,
+    timings: Arc<RwLock<HashMap<String, ModelTiming>>>,
     active_reservation: Arc<RwLock<Option<ActiveReservation>>>,
+    drain: Arc<RwLock<Option<SystemDrain>>>,
+    name_matching: Arc<NameMatching>,
 }
 
 impl Default for Scheduler {
@@ -42,10 +64,22 @@ impl Scheduler {
             queue: RequestQueue::new(),
             gate: ConcurrencyGate::new(),
             settings: Arc::new(RwLock::new(FairnessSettings::default())),
+            policy: Arc::new(RwLock::new(fairness::build_policy(
+                FairnessSettings::default().policy,
+            ))),
+            timings: Arc::new(RwLock::new(HashMap::new())),
             active_reservation: Arc::new(RwLock::new(None)),
+            drain: Arc::new(RwLock::new(None)),
+            name_matching: Arc::new(NameMatching::default()),
         }
     }
 
+    /// Replace the model name normalization rules used by `resolve_model`.
+    pub fn with_name_matching(mut self, matching: NameMatching) -> Self {
+        self.name_matching = Arc::new(matching);
+        self
+    }
+
     /// Resolve a model for an inference request.
     pub async fn resolve_model(
         &self,
@@ -54,7 +88,14 @@ impl Scheduler {
         category_id: Option<&str>,
         specific_model_id: Option<&str>,
     ) -> anyhow::Result<ResolvedModel> {
-        resolver::resolve_model(db, model_name, category_id, specific_model_id).await
+        resolver::resolve_model(
+            db,
+            model_name,
+            category_id,
+            specific_model_id,
+            &self.name_matching,
+        )
+        .await
     }
 
     /// Get the queue depth for a specific key.
@@ -67,9 +108,59 @@ impl Scheduler {
         self.queue.all_depths().await
     }
 
-    /// Get stats (depth + avg wait) for all queues.
+    /// Get stats (depth + avg wait + predicted wait) for all queues.
     pub async fn get_queue_stats(&self) -> HashMap<String, QueueStats> {
-        self.queue.all_stats().await
+        let mut stats = self.queue.all_stats().await;
+        for (key, s) in stats.iter_mut() {
+            s.eta_ms = self.estimate_wait_ms(key).await;
+        }
+        stats
+    }
+
+    /// Predict how long a request for `model_id` arriving now would queue.
+    ///
+    /// Returns None until the model has usage history (see `refresh_timings`).
+    pub async fn estimate_wait_ms(&self, model_id: &str) -> Option<i64> {
+        self.estimate_wait_behind(model_id, usize::MAX).await
+    }
+
+    /// Predict the wait for a request with `ahead` queued requests in front
+    /// of it (all of them when `ahead` exceeds the queue depth).
+    async fn estimate_wait_behind(&self, model_id: &str, ahead: usize) -> Option<i64> {
+        let timing = self.timings.read().await.get(model_id).copied()?;
+        let (max_slots, ages) = self.gate.in_flight_ages(model_id).await?;
+        let mut queued = self.queue.pending_max_tokens(model_id).await;
+        queued.truncate(ahead);
+        Some(eta::predict_wait_ms(&timing, max_slots, &ages, &queued))
+    }
+
+    /// The requests `user_id` has waiting for a slot, with their place in
+    /// line and predicted remaining wait.
+    pub async fn user_queue(&self, user_id: &str) -> Vec<QueuePosition> {
+        let mut positions = self.queue.user_positions(user_id).await;
+        for p in positions.iter_mut() {
+            p.estimated_wait_ms = self.estimate_wait_behind(&p.model_id, p.position - 1).await;
+        }
+        positions
+    }
+
+    /// A waiting request's place in its model's queue and predicted
+    /// remaining wait, or `None` once it has left the queue.
+    pub async fn queue_status(
+        &self,
+        model_id: &str,
+        request_id: &str,
+    ) -> Option<(usize, Option<i64>)> {
+        let position = self.queue.position(model_id, request_id).await?;
+        let wait = self.estimate_wait_behind(model_id, position - 1).await;
+        Some((position, wait))
+    }
+
+    /// Reload per-model service-time history used for ETA prediction.
+    pub async fn refresh_timings(&self, db: &Database) -> anyhow::Result<()> {
+        let timings = eta::load_model_timings(db, 24).await?;
+        *self.timings.write().await = timings;
+        Ok(())
     }
 
     /// Access the underlying request queue.
@@ -82,19 +173,52 @@ impl Scheduler {
         &self.gate
     }
 
+    /// Reload per-category concurrency and queue limits and queue timeouts
+    /// from the database.
+    pub async fn reload_category_limits(&self, db: &Database) -> anyhow::Result<()> {
+        let limits = gate::load_category_limits(db).await?;
+        self.gate.set_category_limits(limits).await;
+        Ok(())
+    }
+
     /// Get a read-locked snapshot of the current fairness settings.
     pub async fn settings(&self) -> FairnessSettings {
         self.settings.read().await.clone()
     }
 
     /// Reload settings from the database into the cached Arc.
+    ///
+    /// Swaps in a fresh fairness policy if `fairness_policy` changed; requests
+    /// already queued keep the priority they were enqueued with.
     pub async fn reload_settings(&self, db: &Database) -> anyhow::Result<()> {
         let new_settings = settings::load_settings(db).await?;
+        {
+            let mut policy = self.policy.write().await;
+            if policy.kind() != new_settings.policy {
+                info!(
+                    from = %policy.kind(),
+                    to = %new_settings.policy,
+                    "Switching fairness policy"
+                );
+                *policy = fairness::build_policy(new_settings.policy);
+            }
+        }
         let mut locked = self.settings.write().await;
         *locked = new_settings;
         Ok(())
     }
 
+    /// The currently active fairness policy.
+    pub async fn policy(&self) -> Arc<dyn FairnessPolicy> {
+        self.policy.read().await.clone()
+    }
+
+    /// Feed completed-request token usage to the active fairness policy.
+    pub async fn record_usage(&self, user_id: &str, tokens: i64) {
+        let settings = self.settings().await;
+        self.policy().await.record_usage(&settings, user_id, tokens);
+    }
+
     /// Get the currently active reservation (if any).
     pub async fn active_reservation(&self) -> Option<ActiveReservation> {
         self.active_reservation.read().await.clone()
@@ -105,4 +229,28 @@ impl Scheduler {
         let mut locked = self.active_reservation.write().await;
         *locked = reservation;
     }
+
+    /// The system-wide drain, if one is in effect.
+    pub async fn drain(&self) -> Option<SystemDrain> {
+        self.drain.read().await.clone()
+    }
+
+    /// Start a drain. An existing drain is kept (and returned) so repeated
+    /// calls don't reset its start time.
+    pub async fn start_drain(&self, drain: SystemDrain) -> SystemDrain {
+        self.drain.write().await.get_or_insert(drain).clone()
+    }
+
+    /// End the drain. Returns the drain that was in effect.
+    pub async fn end_drain(&self) -> Option<SystemDrain> {
+        self.drain.write().await.take()
+    }
+
+    /// Requests holding a gate slot plus requests queued for one, across all
+    /// models.
+    pub async fn outstanding_requests(&self) -> (u32, usize) {
+        let in_flight = self.gate.total_in_flight().await;
+        let queued = self.queue.all_depths().await.values().sum();
+        (in_flight, queued)
+    }
 }
@@ -12,6 +12,8 @@ pub struct QueuedRequest {
     pub queue_key: String,
     pub priority: f64,
     pub enqueued_at: DateTime<Utc>,
+    /// Requested output token limit, if the client sent one (used for ETA prediction).
+    pub max_tokens: Option<u32>,
     /// Oneshot sender to wake this request when a slot becomes available.
     pub waker: oneshot::Sender<()>,
 }
@@ -25,6 +27,7 @@ impl std::fmt::Debug for QueuedRequest {
             .field("queue_key", &self.queue_key)
             .field("priority", &self.priority)
             .field("enqueued_at", &self.enqueued_at)
+            .field("max_tokens", &self.max_tokens)
             .finish()
     }
 }
@@ -34,6 +37,35 @@ impl std::fmt::Debug for QueuedRequest {
 pub struct QueueStats {
     pub depth: usize,
     pub avg_wait_ms: i64,
+    /// Predicted wait for a request arriving now (None without timing history).
+    pub eta_ms: Option<i64>,
+}
+
+/// Where one of a user's requests stands in its queue.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct QueuePosition {
+    pub request_id: String,
+    pub model_id: String,
+    /// 1-based place in dequeue order.
+    pub position: usize,
+    /// Requests waiting for the model in total.
+    pub depth: usize,
+    pub enqueued_at: DateTime<Utc>,
+    /// Predicted time until a slot frees up for this request (filled in by
+    /// the scheduler; None without timing history).
+    pub estimated_wait_ms: Option<i64>,
+}
+
+/// The requests in `queue`, highest priority first. The sort is stable, so
+/// ties keep FIFO order, matching `dequeue`.
+fn dequeue_order(queue: &VecDeque<QueuedRequest>) -> Vec<&QueuedRequest> {
+    let mut entries: Vec<&QueuedRequest> = queue.iter().collect();
+    entries.sort_by(|a, b| {
+        b.priority
+            .partial_cmp(&a.priority)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    entries
 }
 
 /// Thread-safe per-key request queue.
@@ -75,10 +107,12 @@ impl RequestQueue {
             return None;
         }
 
-        // Find the index of the highest-priority request
+        // Find the index of the highest-priority request. Iterate in reverse
+        // so that `max_by` (which keeps the last maximum) breaks ties FIFO.
         let best_idx = queue
             .iter()
             .enumerate()
+            .rev()
             .max_by(|(_, a), (_, b)| {
                 a.priority
                     .partial_cmp(&b.priority)
@@ -89,12 +123,34 @@ impl RequestQueue {
         queue.remove(best_idx)
     }
 
-    /// Remove a specific request by ID (used for timeout cleanup).
-    pub async fn remove_by_id(&self, queue_key: &str, request_id: &str) {
+    /// Remove and return the highest-priority request across several queue
+    /// keys. Ties go to the request enqueued first.
+    pub async fn dequeue_best(&self, queue_keys: &[String]) -> Option<QueuedRequest> {
         let mut queues = self.queues.write().await;
-        if let Some(queue) = queues.get_mut(queue_key) {
-            queue.retain(|r| r.request_id != request_id);
-        }
+        let (key, idx) = queue_keys
+            .iter()
+            .filter_map(|key| queues.get(key).map(|queue| (key, queue)))
+            .flat_map(|(key, queue)| queue.iter().enumerate().map(move |(i, r)| (key, i, r)))
+            .max_by(|(_, _, a), (_, _, b)| {
+                a.priority
+                    .partial_cmp(&b.priority)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(b.enqueued_at.cmp(&a.enqueued_at))
+            })
+            .map(|(key, i, _)| (key.clone(), i))?;
+        queues.get_mut(&key)?.remove(idx)
+    }
+
+    /// Remove a specific request by ID (used for timeout cleanup). Returns
+    /// false if it was no longer queued, i.e. it has been woken.
+    pub async fn remove_by_id(&self, queue_key: &str, request_id: &str) -> bool {
+        let mut queues = self.queues.write().await;
+        let Some(queue) = queues.get_mut(queue_key) else {
+            return false;
+        };
+        let before = queue.len();
+        queue.retain(|r| r.request_id != request_id);
+        queue.len() < before
     }
 
     /// Get the depth of a specific queue.
@@ -103,6 +159,60 @@ impl RequestQueue {
         queues.get(queue_key).map_or(0, |q| q.len())
     }
 
+    /// `max_tokens` of each waiting request, in the order they will be dequeued.
+    pub async fn pending_max_tokens(&self, queue_key: &str) -> Vec<Option<u32>> {
+        let queues = self.queues.read().await;
+        let Some(queue) = queues.get(queue_key) else {
+            return Vec::new();
+        };
+        dequeue_order(queue)
+            .into_iter()
+            .map(|r| r.max_tokens)
+            .collect()
+    }
+
+    /// 1-based place of a request in dequeue order, if it is still waiting.
+    pub async fn position(&self, queue_key: &str, request_id: &str) -> Option<usize> {
+        let queues = self.queues.read().await;
+        dequeue_order(queues.get(queue_key)?)
+            .iter()
+            .position(|r| r.request_id == request_id)
+            .map(|i| i + 1)
+    }
+
+    /// Every request `user_id` has waiting, across all queues, oldest first.
+    pub async fn user_positions(&self, user_id: &str) -> Vec<QueuePosition> {
+        let queues = self.queues.read().await;
+        let mut positions: Vec<QueuePosition> = queues
+            .iter()
+            .flat_map(|(key, queue)| {
+                dequeue_order(queue)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, r)| r.user_id == user_id)
+                    .map(|(i, r)| QueuePosition {
+                        request_id: r.request_id.clone(),
+                        model_id: key.clone(),
+                        position: i + 1,
+                        depth: queue.len(),
+                        enqueued_at: r.enqueued_at,
+                        estimated_wait_ms: None,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        positions.sort_by_key(|p| p.enqueued_at);
+        positions
+    }
+
+    /// Count requests from `user_id` currently waiting in a queue.
+    pub async fn user_depth(&self, queue_key: &str, user_id: &str) -> usize {
+        let queues = self.queues.read().await;
+        queues
+            .get(queue_key)
+            .map_or(0, |q| q.iter().filter(|r| r.user_id == user_id).count())
+    }
+
     /// Get depths for all queues.
     pub async fn all_depths(&self) -> HashMap<String, usize> {
         let queues = self.queues.read().await;
@@ -128,7 +238,14 @@ impl RequestQueue {
                     0
                 };
 
-                (key.clone(), QueueStats { depth, avg_wait_ms })
+                (
+                    key.clone(),
+                    QueueStats {
+                        depth,
+                        avg_wait_ms,
+                        eta_ms: None,
+                    },
+                )
             })
             .collect()
     }
@@ -152,6 +269,7 @@ mod tests {
             queue_key: key.to_string(),
             priority,
             enqueued_at: Utc::now(),
+            max_tokens: None,
             waker: tx,
         };
         (req, rx)
@@ -176,6 +294,93 @@ mod tests {
         assert!(q.dequeue("model-a").await.is_none());
     }
 
+    #[tokio::test]
+    async fn dequeue_equal_priority_is_fifo() {
+        let q = RequestQueue::new();
+        let (r1, _rx1) = make_request("r1", "u1", "m", 5.0);
+        let (r2, _rx2) = make_request("r2", "u2", "m", 5.0);
+        let (r3, _rx3) = make_request("r3", "u3", "m", 5.0);
+
+        q.enqueue(r1).await;
+        q.enqueue(r2).await;
+        q.enqueue(r3).await;
+
+        assert_eq!(q.dequeue("m").await.unwrap().request_id, "r1");
+        assert_eq!(q.dequeue("m").await.unwrap().request_id, "r2");
+        assert_eq!(q.dequeue("m").await.unwrap().request_id, "r3");
+    }
+
+    #[tokio::test]
+    async fn pending_max_tokens_in_dequeue_order() {
+        let q = RequestQueue::new();
+        let (mut r1, _rx1) = make_request("r1", "u1", "m", 1.0);
+        let (mut r2, _rx2) = make_request("r2", "u2", "m", 5.0);
+        let (r3, _rx3) = make_request("r3", "u3", "m", 1.0);
+        r1.max_tokens = Some(100);
+        r2.max_tokens = Some(200);
+
+        q.enqueue(r1).await;
+        q.enqueue(r2).await;
+        q.enqueue(r3).await;
+
+        assert_eq!(
+            q.pending_max_tokens("m").await,
+            vec![Some(200), Some(100), None]
+        );
+        assert!(q.pending_max_tokens("other").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn positions_follow_dequeue_order() {
+        let q = RequestQueue::new();
+        let (r1, _rx1) = make_request("r1", "u1", "m", 1.0);
+        let (r2, _rx2) = make_request("r2", "u2", "m", 5.0);
+        let (r3, _rx3) = make_request("r3", "u1", "m", 1.0);
+        let (mut r4, _rx4) = make_request("r4", "u1", "other", 1.0);
+        r4.enqueued_at += chrono::Duration::seconds(1);
+        q.enqueue(r1).await;
+        q.enqueue(r2).await;
+        q.enqueue(r3).await;
+        q.enqueue(r4).await;
+
+        assert_eq!(q.position("m", "r2").await, Some(1));
+        assert_eq!(q.position("m", "r3").await, Some(3));
+        assert_eq!(q.position("m", "gone").await, None);
+
+        let mine = q.user_positions("u1").await;
+        let summary: Vec<(&str, &str, usize, usize)> = mine
+            .iter()
+            .map(|p| {
+                (
+                    p.request_id.as_str(),
+                    p.model_id.as_str(),
+                    p.position,
+                    p.depth,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("r1", "m", 2, 3), ("r3", "m", 3, 3), ("r4", "other", 1, 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn user_depth_counts_only_that_user() {
+        let q = RequestQueue::new();
+        let (r1, _rx1) = make_request("r1", "u1", "m", 1.0);
+        let (r2, _rx2) = make_request("r2", "u1", "m", 1.0);
+        let (r3, _rx3) = make_request("r3", "u2", "m", 1.0);
+
+        q.enqueue(r1).await;
+        q.enqueue(r2).await;
+        q.enqueue(r3).await;
+
+        assert_eq!(q.user_depth("m", "u1").await, 2);
+        assert_eq!(q.user_depth("m", "u2").await, 1);
+        assert_eq!(q.user_depth("other", "u1").await, 0);
+    }
+
     #[tokio::test]
     async fn dequeue_returns_highest_priority() {
         let q = RequestQueue::new();
@@ -292,4 +497,22 @@ mod tests {
         assert_eq!(stats["alpha"].depth, 1);
         assert_eq!(stats["beta"].depth, 2);
     }
+
+    #[tokio::test]
+    async fn dequeue_best_spans_keys() {
+        let q = RequestQueue::new();
+        let (r1, _rx1) = make_request("r1", "u1", "a", 1.0);
+        let (r2, _rx2) = make_request("r2", "u2", "b", 3.0);
+        let (r3, _rx3) = make_request("r3", "u3", "c", 5.0);
+        q.enqueue(r1).await;
+        q.enqueue(r2).await;
+        q.enqueue(r3).await;
+
+        let keys = ["a".to_string(), "b".to_string()];
+        assert_eq!(q.dequeue_best(&keys).await.unwrap().request_id, "r2");
+        assert_eq!(q.dequeue_best(&keys).await.unwrap().request_id, "r1");
+        assert!(q.dequeue_best(&keys).await.is_none());
+        // Keys left out are untouched
+        assert_eq!(q.depth("c").await, 1);
+    }
 }
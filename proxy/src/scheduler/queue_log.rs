@@ -0,0 +1,278 @@
+//! Persistence for queue entries so restarts don't silently lose queue history.
+//!
+//! Only requests that actually wait for a slot are recorded (the fast path
+//! never touches the DB). Timestamps use SQLite's `datetime('now')` format so
+//! they compare correctly against `datetime('now', ?)` offsets.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::db::Database;
+
+const TS_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Final state of a queued request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueOutcome {
+    Served,
+    TimedOut,
+    /// The client went away while waiting.
+    Cancelled,
+}
+
+impl QueueOutcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Served => "served",
+            Self::TimedOut => "timed_out",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// A queue entry that was dropped because the proxy restarted.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct InterruptedEntry {
+    pub request_id: String,
+    pub model_id: String,
+    pub enqueued_at: String,
+    pub resolved_at: Option<String>,
+}
+
+/// Aggregate wait statistics for one model over a time window.
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+pub struct QueueHistoryStats {
+    pub model_id: String,
+    pub queued: i64,
+    pub served: i64,
+    pub timed_out: i64,
+    pub interrupted: i64,
+    pub cancelled: i64,
+    pub avg_wait_ms: i64,
+    pub max_wait_ms: i64,
+}
+
+/// Record that a request has started waiting.
+pub async fn record_enqueued(
+    db: &Database,
+    request_id: &str,
+    user_id: &str,
+    model_id: &str,
+    enqueued_at: DateTime<Utc>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO queue_log (request_id, user_id, model_id, enqueued_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(request_id)
+    .bind(user_id)
+    .bind(model_id)
+    .bind(enqueued_at.format(TS_FORMAT).to_string())
+    .execute(&db.pool)
+    .await
+    .context("Failed to insert queue log entry")?;
+
+    Ok(())
+}
+
+/// Record how a waiting request left the queue.
+pub async fn record_outcome(
+    db: &Database,
+    request_id: &str,
+    outcome: QueueOutcome,
+    wait_ms: i64,
+) -> Result<()> {
+    sqlx::query(
+        "UPDATE queue_log SET status = ?, wait_ms = ?, resolved_at = datetime('now')
+         WHERE request_id = ? AND status = 'waiting'",
+    )
+    .bind(outcome.as_str())
+    .bind(wait_ms)
+    .bind(request_id)
+    .execute(&db.pool)
+    .await
+    .context("Failed to update queue log entry")?;
+
+    Ok(())
+}
+
+/// Mark entries left `waiting` by a previous process as `interrupted`.
+///
+/// Must run at startup before any request can enqueue. Returns the number of
+/// entries recovered.
+pub async fn recover_interrupted(db: &Database) -> Result<u64> {
+    let result = sqlx::query(
+        "UPDATE queue_log
+         SET status = 'interrupted',
+             resolved_at = datetime('now'),
+             wait_ms = CAST((julianday('now') - julianday(enqueued_at)) * 86400000 AS INTEGER)
+         WHERE status = 'waiting'",
+    )
+    .execute(&db.pool)
+    .await
+    .context("Failed to recover interrupted queue entries")?;
+
+    Ok(result.rows_affected())
+}
+
+/// Requests for `user_id` that were interrupted by a restart within `interval`
+/// (a SQLite datetime offset such as `"-1 day"`), newest first.
+pub async fn interrupted_for_user(
+    db: &Database,
+    user_id: &str,
+    interval: &str,
+) -> Result<Vec<InterruptedEntry>> {
+    let rows = sqlx::query_as::<_, InterruptedEntry>(
+        "SELECT request_id, model_id, enqueued_at, resolved_at FROM queue_log
+         WHERE user_id = ? AND status = 'interrupted' AND enqueued_at >= datetime('now', ?)
+         ORDER BY enqueued_at DESC",
+    )
+    .bind(user_id)
+    .bind(interval)
+    .fetch_all(&db.pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Per-model wait statistics for entries enqueued within `interval`.
+///
+/// Averages cover resolved entries only; entries still waiting count towards
+/// `queued` but not the averages.
+pub async fn history_stats(db: &Database, interval: &str) -> Result<Vec<QueueHistoryStats>> {
+    let rows = sqlx::query_as::<_, QueueHistoryStats>(
+        r#"
+        SELECT model_id,
+               COUNT(*) as queued,
+               COALESCE(SUM(status = 'served'), 0) as served,
+               COALESCE(SUM(status = 'timed_out'), 0) as timed_out,
+               COALESCE(SUM(status = 'interrupted'), 0) as interrupted,
+               COALESCE(SUM(status = 'cancelled'), 0) as cancelled,
+               CAST(COALESCE(AVG(wait_ms), 0) AS INTEGER) as avg_wait_ms,
+               COALESCE(MAX(wait_ms), 0) as max_wait_ms
+        FROM queue_log
+        WHERE enqueued_at >= datetime('now', ?)
+        GROUP BY model_id
+        ORDER BY queued DESC
+        "#,
+    )
+    .bind(interval)
+    .fetch_all(&db.pool)
+    .await?;
+
+    Ok(rows)
+}
+
+/// Delete resolved entries older than `days`.
+pub async fn prune(db: &Database, days: i64) -> Result<u64> {
+    let result = sqlx::query(
+        "DELETE FROM queue_log WHERE status != 'waiting' AND enqueued_at < datetime('now', ?)",
+    )
+    .bind(format!("-{days} days"))
+    .execute(&db.pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn status_of(db: &Database, request_id: &str) -> String {
+        let row: (String,) = sqlx::query_as("SELECT status FROM queue_log WHERE request_id = ?")
+            .bind(request_id)
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        row.0
+    }
+
+    #[tokio::test]
+    async fn outcome_resolves_waiting_entry() {
+        let db = Database::test_db().await;
+        record_enqueued(&db, "r1", "u1", "m1", Utc::now())
+            .await
+            .unwrap();
+        record_outcome(&db, "r1", QueueOutcome::Served, 120)
+            .await
+            .unwrap();
+        assert_eq!(status_of(&db, "r1").await, "served");
+
+        // A second outcome must not overwrite the first
+        record_outcome(&db, "r1", QueueOutcome::TimedOut, 999)
+            .await
+            .unwrap();
+        assert_eq!(status_of(&db, "r1").await, "served");
+    }
+
+    #[tokio::test]
+    async fn recover_marks_only_waiting_entries() {
+        let db = Database::test_db().await;
+        record_enqueued(&db, "r1", "u1", "m1", Utc::now())
+            .await
+            .unwrap();
+        record_enqueued(&db, "r2", "u1", "m1", Utc::now())
+            .await
+            .unwrap();
+        record_outcome(&db, "r2", QueueOutcome::TimedOut, 30_000)
+            .await
+            .unwrap();
+
+        assert_eq!(recover_interrupted(&db).await.unwrap(), 1);
+        assert_eq!(status_of(&db, "r1").await, "interrupted");
+        assert_eq!(status_of(&db, "r2").await, "timed_out");
+
+        let mine = interrupted_for_user(&db, "u1", "-1 day").await.unwrap();
+        assert_eq!(mine.len(), 1);
+        assert_eq!(mine[0].request_id, "r1");
+        assert!(interrupted_for_user(&db, "u2", "-1 day")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn history_stats_aggregates_per_model() {
+        let db = Database::test_db().await;
+        for (id, model, outcome, wait) in [
+            ("r1", "m1", QueueOutcome::Served, 100),
+            ("r2", "m1", QueueOutcome::Served, 300),
+            ("r3", "m1", QueueOutcome::TimedOut, 500),
+            ("r4", "m2", QueueOutcome::Served, 50),
+            ("r5", "m1", QueueOutcome::Cancelled, 200),
+        ] {
+            record_enqueued(&db, id, "u1", model, Utc::now())
+                .await
+                .unwrap();
+            record_outcome(&db, id, outcome, wait).await.unwrap();
+        }
+
+        let stats = history_stats(&db, "-1 hour").await.unwrap();
+        let m1 = stats.iter().find(|s| s.model_id == "m1").unwrap();
+        assert_eq!(m1.queued, 4);
+        assert_eq!(m1.served, 2);
+        assert_eq!(m1.timed_out, 1);
+        assert_eq!(m1.cancelled, 1);
+        assert_eq!(m1.avg_wait_ms, 275);
+        assert_eq!(m1.max_wait_ms, 500);
+        let m2 = stats.iter().find(|s| s.model_id == "m2").unwrap();
+        assert_eq!(m2.queued, 1);
+    }
+
+    #[tokio::test]
+    async fn prune_keeps_recent_and_waiting() {
+        let db = Database::test_db().await;
+        let old = Utc::now() - chrono::Duration::days(30);
+        record_enqueued(&db, "old", "u1", "m1", old).await.unwrap();
+        record_outcome(&db, "old", QueueOutcome::Served, 1)
+            .await
+            .unwrap();
+        record_enqueued(&db, "new", "u1", "m1", Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(prune(&db, 7).await.unwrap(), 1);
+        assert_eq!(status_of(&db, "new").await, "waiting");
+    }
+}
@@ -1,41 +1,10 @@
 use chrono::Utc;
 use serde::Serialize;
 use sqlx::{Pool, Sqlite};
-use tokio::sync::broadcast;
 use tracing::{info, warn};
 
 use super::Scheduler;
-
-/// Broadcasts a unit signal whenever reservations change (create, cancel,
-/// approve, reject, activate, deactivate, delete, or tick transitions).
-/// Frontend clients subscribe via SSE and re-fetch their data on receipt.
-#[derive(Debug, Clone)]
-pub struct ReservationBroadcaster {
-    tx: broadcast::Sender<()>,
-}
-
-impl Default for ReservationBroadcaster {
-    fn default() -> Self {
-        let (tx, _) = broadcast::channel(16);
-        Self { tx }
-    }
-}
-
-impl ReservationBroadcaster {
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Subscribe to reservation change notifications.
-    pub fn subscribe(&self) -> broadcast::Receiver<()> {
-        self.tx.subscribe()
-    }
-
-    /// Notify all subscribers that reservations have changed.
-    pub fn notify(&self) {
-        let _ = self.tx.send(());
-    }
-}
+use crate::events::{AppEvent, EventBus};
 
 /// A reservation row from the database.
 #[derive(Debug, Clone, sqlx::FromRow, Serialize)]
@@ -79,12 +48,8 @@ pub struct ActiveReservation {
 }
 
 /// Background tick: activate approved reservations, complete expired active ones,
-/// and cancel stale pending requests.
-pub async fn tick_reservations(
-    pool: &Pool<Sqlite>,
-    scheduler: &Scheduler,
-    broadcaster: &ReservationBroadcaster,
-) {
+/// and cancel stale pending and waitlisted requests.
+pub async fn tick_reservations(pool: &Pool<Sqlite>, scheduler: &Scheduler, events: &EventBus) {
     let now = Utc::now().format("%Y-%m-%dT%H:%M:%S").to_string();
     let mut changed = false;
 
@@ -144,12 +109,12 @@ pub async fn tick_reservations(
         }
     }
 
-    // 3. Auto-cancel pending reservations whose start_time has passed
+    // 3. Auto-cancel pending and waitlisted reservations whose start_time has passed
     let cancelled: Result<sqlx::sqlite::SqliteQueryResult, _> = sqlx::query(
         "UPDATE reservations SET status = 'cancelled', \
          admin_note = 'Auto-cancelled: start time passed without approval', \
          updated_at = datetime('now') \
-         WHERE status = 'pending' AND start_time <= ?",
+         WHERE status IN ('pending', 'waitlisted') AND start_time <= ?",
     )
     .bind(&now)
     .execute(pool)
@@ -166,7 +131,7 @@ pub async fn tick_reservations(
     }
 
     if changed {
-        broadcaster.notify();
+        events.publish(AppEvent::ReservationChanged);
     }
 }
 
@@ -203,11 +168,11 @@ mod tests {
     use super::*;
     use crate::db::Database;
 
-    async fn setup() -> (Database, Scheduler, ReservationBroadcaster) {
+    async fn setup() -> (Database, Scheduler, EventBus) {
         let db = Database::test_db().await;
         let scheduler = Scheduler::new();
-        let broadcaster = ReservationBroadcaster::new();
-        (db, scheduler, broadcaster)
+        let events = EventBus::new();
+        (db, scheduler, events)
     }
 
     async fn ensure_test_user(pool: &Pool<Sqlite>, user_id: &str) {
@@ -266,7 +231,7 @@ mod tests {
 
     #[tokio::test]
     async fn tick_completes_expired_active() {
-        let (db, scheduler, broadcaster) = setup().await;
+        let (db, scheduler, events) = setup().await;
         let past_start = "2020-01-01T00:00:00";
         let past_end = "2020-01-01T01:00:00";
         let id = insert_reservation(&db.pool, "user1", "active", past_start, past_end).await;
@@ -281,7 +246,7 @@ mod tests {
             }))
             .await;
 
-        tick_reservations(&db.pool, &scheduler, &broadcaster).await;
+        tick_reservations(&db.pool, &scheduler, &events).await;
 
         assert_eq!(get_status(&db.pool, &id).await, "completed");
         assert!(scheduler.active_reservation().await.is_none());
@@ -289,12 +254,12 @@ mod tests {
 
     #[tokio::test]
     async fn tick_activates_approved_when_due() {
-        let (db, scheduler, broadcaster) = setup().await;
+        let (db, scheduler, events) = setup().await;
         let past_start = "2020-01-01T00:00:00";
         let future_end = "2099-12-31T23:30:00";
         let id = insert_reservation(&db.pool, "user1", "approved", past_start, future_end).await;
 
-        tick_reservations(&db.pool, &scheduler, &broadcaster).await;
+        tick_reservations(&db.pool, &scheduler, &events).await;
 
         assert_eq!(get_status(&db.pool, &id).await, "active");
         let active = scheduler.active_reservation().await.unwrap();
@@ -303,7 +268,7 @@ mod tests {
 
     #[tokio::test]
     async fn tick_skips_activation_if_already_active() {
-        let (db, scheduler, broadcaster) = setup().await;
+        let (db, scheduler, events) = setup().await;
         let future_end = "2099-12-31T23:30:00";
 
         // One active reservation (still valid)
@@ -334,7 +299,7 @@ mod tests {
         )
         .await;
 
-        tick_reservations(&db.pool, &scheduler, &broadcaster).await;
+        tick_reservations(&db.pool, &scheduler, &events).await;
 
         // Active stays active, approved stays approved
         assert_eq!(get_status(&db.pool, &active_id).await, "active");
@@ -343,7 +308,7 @@ mod tests {
 
     #[tokio::test]
     async fn tick_activates_earliest_of_multiple() {
-        let (db, scheduler, broadcaster) = setup().await;
+        let (db, scheduler, events) = setup().await;
         let future_end = "2099-12-31T23:30:00";
 
         // Two approved, both past start — earlier one should activate
@@ -364,7 +329,7 @@ mod tests {
         )
         .await;
 
-        tick_reservations(&db.pool, &scheduler, &broadcaster).await;
+        tick_reservations(&db.pool, &scheduler, &events).await;
 
         assert_eq!(get_status(&db.pool, &earlier_id).await, "active");
         assert_eq!(get_status(&db.pool, &later_id).await, "approved");
@@ -372,12 +337,12 @@ mod tests {
 
     #[tokio::test]
     async fn tick_cancels_stale_pending() {
-        let (db, scheduler, broadcaster) = setup().await;
+        let (db, scheduler, events) = setup().await;
         let past_start = "2020-01-01T00:00:00";
         let past_end = "2020-01-01T01:00:00";
         let id = insert_reservation(&db.pool, "user1", "pending", past_start, past_end).await;
 
-        tick_reservations(&db.pool, &scheduler, &broadcaster).await;
+        tick_reservations(&db.pool, &scheduler, &events).await;
 
         assert_eq!(get_status(&db.pool, &id).await, "cancelled");
         // Verify admin_note is set
@@ -393,7 +358,7 @@ mod tests {
 
     #[tokio::test]
     async fn tick_cascade_complete_then_activate() {
-        let (db, scheduler, broadcaster) = setup().await;
+        let (db, scheduler, events) = setup().await;
 
         // Active reservation ending now (in the past)
         let active_id = insert_reservation(
@@ -423,7 +388,7 @@ mod tests {
         )
         .await;
 
-        tick_reservations(&db.pool, &scheduler, &broadcaster).await;
+        tick_reservations(&db.pool, &scheduler, &events).await;
 
         assert_eq!(get_status(&db.pool, &active_id).await, "completed");
         assert_eq!(get_status(&db.pool, &approved_id).await, "active");
@@ -433,7 +398,7 @@ mod tests {
 
     #[tokio::test]
     async fn tick_noop_when_nothing_due() {
-        let (db, scheduler, broadcaster) = setup().await;
+        let (db, scheduler, events) = setup().await;
         let future_start = "2099-12-01T00:00:00";
         let future_end = "2099-12-31T23:30:00";
 
@@ -443,9 +408,9 @@ mod tests {
             insert_reservation(&db.pool, "user2", "approved", future_start, future_end).await;
 
         // Subscribe before tick to check for no broadcast
-        let mut rx = broadcaster.subscribe();
+        let mut rx = events.subscribe();
 
-        tick_reservations(&db.pool, &scheduler, &broadcaster).await;
+        tick_reservations(&db.pool, &scheduler, &events).await;
 
         assert_eq!(get_status(&db.pool, &pending_id).await, "pending");
         assert_eq!(get_status(&db.pool, &approved_id).await, "approved");
@@ -456,14 +421,14 @@ mod tests {
 
     #[tokio::test]
     async fn tick_broadcasts_on_change() {
-        let (db, scheduler, broadcaster) = setup().await;
+        let (db, scheduler, events) = setup().await;
         let past_start = "2020-01-01T00:00:00";
         let past_end = "2020-01-01T01:00:00";
         insert_reservation(&db.pool, "user1", "active", past_start, past_end).await;
 
-        let mut rx = broadcaster.subscribe();
+        let mut rx = events.subscribe();
 
-        tick_reservations(&db.pool, &scheduler, &broadcaster).await;
+        tick_reservations(&db.pool, &scheduler, &events).await;
 
         // Should have received a notification
         assert!(rx.try_recv().is_ok());
@@ -1,8 +1,68 @@
+use std::fmt;
+
 use anyhow::{bail, Result};
 use sqlx::FromRow;
 
 use crate::db::Database;
 
+/// How loosely a requested model name may match a known model.
+#[derive(Debug, Clone)]
+pub struct NameMatching {
+    /// Retry failed lookups with prefixes stripped, case-insensitively, and
+    /// against the repo basename (`Llama-3-8B` for `org/Llama-3-8B`).
+    pub normalize: bool,
+    /// Client-side provider prefixes to strip, e.g. `openai/`.
+    pub strip_prefixes: Vec<String>,
+    /// Suggest the closest known name in model-not-found errors.
+    pub suggestions: bool,
+}
+
+impl Default for NameMatching {
+    fn default() -> Self {
+        Self {
+            normalize: true,
+            strip_prefixes: vec!["openai/".to_string()],
+            suggestions: true,
+        }
+    }
+}
+
+/// Returned when no model or category matches the requested name.
+#[derive(Debug)]
+pub struct ModelNotFound {
+    pub model: String,
+    /// Closest known model, category or alias name, when one is near enough.
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for ModelNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Model '{}' not found. Provide a valid model ID, HuggingFace repo name, \
+             category name or alias. Use GET /v1/models to list available models.",
+            self.model
+        )?;
+        if let Some(ref s) = self.suggestion {
+            write!(f, " Did you mean '{s}'?")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ModelNotFound {}
+
+/// Client-facing message for a failed resolution, with the suggestion if any.
+pub fn not_found_message(err: &anyhow::Error, model_name: &str) -> String {
+    match err
+        .downcast_ref::<ModelNotFound>()
+        .and_then(|n| n.suggestion.as_deref())
+    {
+        Some(s) => format!("Model not found: {model_name}. Did you mean '{s}'?"),
+        None => format!("Model not found: {model_name}"),
+    }
+}
+
 #[derive(Debug, Clone, FromRow)]
 pub struct ResolvedModel {
     pub id: String,
@@ -11,6 +71,7 @@ pub struct ResolvedModel {
     pub loaded: bool,
     pub category_id: Option<String>,
     pub backend_type: String,
+    pub draining: bool,
 }
 
 /// Resolve a model for an inference request.
@@ -18,14 +79,22 @@ pub struct ResolvedModel {
 /// Resolution order:
 /// 1. If `specific_model_id` is provided, use that model directly (must exist).
 /// 2. If `category_id` is provided, look up the category's `preferred_model_id`.
-/// 3. If the preferred model isn't loaded, find any loaded model in that category.
-/// 4. If nothing works, try treating `model_name` as a direct model ID/hf_repo.
-/// 5. If still nothing, try treating `model_name` as a category name.
+/// 3. If the preferred model isn't loaded or is draining, find any loaded,
+///    non-draining model in that category.
+/// 4. If `model_name` is an admin-defined alias, resolve its target (see
+///    [`resolve_alias`]).
+/// 5. If nothing works, try treating `model_name` as a direct model ID/hf_repo.
+/// 6. If still nothing, try treating `model_name` as a category name.
+/// 7. If `matching.normalize` is set, retry 5–6 with the name normalized (see
+///    [`resolve_normalized`]).
+///
+/// A miss returns a [`ModelNotFound`] error, with a suggestion when enabled.
 pub async fn resolve_model(
     db: &Database,
     model_name: &str,
     category_id: Option<&str>,
     specific_model_id: Option<&str>,
+    matching: &NameMatching,
 ) -> Result<ResolvedModel> {
     // 1. Specific model override from the token
     if let Some(specific_id) = specific_model_id {
@@ -46,27 +115,202 @@ pub async fn resolve_model(
         );
     }
 
-    // 3. Try the model field from the request as a direct model ID or hf_repo
+    // 3. Aliases come first, so they can stand in for a real model name
+    if let Some(model) = resolve_alias(db, model_name, matching).await? {
+        return Ok(model);
+    }
+
+    // 4. Try the model field from the request as a direct model ID or hf_repo
     if let Some(model) = resolve_by_id_or_repo(db, model_name).await? {
         return Ok(model);
     }
 
-    // 4. Try the model field as a category name
+    // 5. Try the model field as a category name
     if let Some(model) = resolve_from_category_name(db, model_name).await? {
         return Ok(model);
     }
 
-    bail!(
-        "Model '{}' not found. Provide a valid model ID, HuggingFace repo name, \
-         or category name. Use GET /v1/models to list available models.",
-        model_name
+    // 6. Normalized name
+    if matching.normalize {
+        if let Some(model) = resolve_normalized(db, model_name, &matching.strip_prefixes).await? {
+            return Ok(model);
+        }
+    }
+
+    let suggestion = if matching.suggestions {
+        suggest_name(db, model_name, &matching.strip_prefixes).await?
+    } else {
+        None
+    };
+    Err(ModelNotFound {
+        model: model_name.to_string(),
+        suggestion,
+    }
+    .into())
+}
+
+/// Strip the first matching configured prefix (case-insensitive).
+fn strip_prefix<'a>(name: &'a str, prefixes: &[String]) -> &'a str {
+    for prefix in prefixes {
+        if name.len() >= prefix.len()
+            && name.is_char_boundary(prefix.len())
+            && name[..prefix.len()].eq_ignore_ascii_case(prefix)
+        {
+            return &name[prefix.len()..];
+        }
+    }
+    name
+}
+
+/// Resolve an admin-defined alias to the model or category it points at.
+/// Aliases match case-insensitively; when normalizing, configured prefixes
+/// are stripped too (`openai/gpt-4o` finds `gpt-4o`).
+async fn resolve_alias(
+    db: &Database,
+    model_name: &str,
+    matching: &NameMatching,
+) -> Result<Option<ResolvedModel>> {
+    let mut names = vec![model_name];
+    if matching.normalize {
+        let stripped = strip_prefix(model_name, &matching.strip_prefixes);
+        if !stripped.is_empty() && stripped != model_name {
+            names.push(stripped);
+        }
+    }
+    for name in names {
+        let target: Option<(Option<String>, Option<String>)> =
+            sqlx::query_as("SELECT model_id, category_id FROM model_aliases WHERE alias = ?")
+                .bind(name)
+                .fetch_optional(&db.pool)
+                .await?;
+        match target {
+            Some((Some(model_id), _)) => {
+                return resolve_specific_model(db, &model_id).await.map(Some)
+            }
+            Some((None, Some(category_id))) => {
+                return resolve_from_category_id(db, &category_id).await
+            }
+            _ => {}
+        }
+    }
+    Ok(None)
+}
+
+/// Last path segment of an hf_repo (`org/name` → `name`).
+fn basename(repo: &str) -> &str {
+    repo.rsplit('/').next().unwrap_or(repo)
+}
+
+/// Retry the lookup with the name normalized: configured prefixes stripped,
+/// then matched case-insensitively against model IDs, hf_repos, repo
+/// basenames, and category names, in that order. A normalized form that
+/// matches more than one model is ambiguous and resolves to nothing.
+async fn resolve_normalized(
+    db: &Database,
+    model_name: &str,
+    strip_prefixes: &[String],
+) -> Result<Option<ResolvedModel>> {
+    let name = strip_prefix(model_name, strip_prefixes);
+    if name.is_empty() {
+        return Ok(None);
+    }
+
+    let models = sqlx::query_as::<_, ResolvedModel>(
+        "SELECT id, hf_repo, backend_port, loaded, category_id, backend_type, draining FROM models",
     )
+    .fetch_all(&db.pool)
+    .await?;
+
+    let id_or_repo: Vec<&ResolvedModel> = models
+        .iter()
+        .filter(|m| m.id.eq_ignore_ascii_case(name) || m.hf_repo.eq_ignore_ascii_case(name))
+        .collect();
+    if let [m] = id_or_repo.as_slice() {
+        return Ok(Some((*m).clone()));
+    }
+    if id_or_repo.is_empty() {
+        let by_basename: Vec<&ResolvedModel> = models
+            .iter()
+            .filter(|m| basename(&m.hf_repo).eq_ignore_ascii_case(name))
+            .collect();
+        if let [m] = by_basename.as_slice() {
+            return Ok(Some((*m).clone()));
+        }
+    }
+
+    let category: Option<(String,)> =
+        sqlx::query_as("SELECT id FROM model_categories WHERE name = ? COLLATE NOCASE LIMIT 1")
+            .bind(name)
+            .fetch_optional(&db.pool)
+            .await?;
+    match category {
+        Some((cat_id,)) => resolve_from_category_id(db, &cat_id).await,
+        None => Ok(None),
+    }
+}
+
+/// Closest known model ID, hf_repo, category name or alias to `model_name`, if
+/// within a small edit distance.
+async fn suggest_name(
+    db: &Database,
+    model_name: &str,
+    strip_prefixes: &[String],
+) -> Result<Option<String>> {
+    let wanted = strip_prefix(model_name, strip_prefixes).to_lowercase();
+    if wanted.is_empty() {
+        return Ok(None);
+    }
+
+    let mut candidates: Vec<String> =
+        sqlx::query_as::<_, (String, String)>("SELECT id, hf_repo FROM models")
+            .fetch_all(&db.pool)
+            .await?
+            .into_iter()
+            .flat_map(|(id, repo)| [id, repo])
+            .collect();
+    candidates.extend(
+        sqlx::query_as::<_, (String,)>(
+            "SELECT name FROM model_categories UNION ALL SELECT alias FROM model_aliases",
+        )
+        .fetch_all(&db.pool)
+        .await?
+        .into_iter()
+        .map(|(name,)| name),
+    );
+
+    // Allow roughly one typo per four characters, at least two
+    let max_distance = (wanted.chars().count() / 4).max(2);
+    Ok(candidates
+        .into_iter()
+        .filter_map(|c| {
+            let lower = c.to_lowercase();
+            let distance =
+                edit_distance(&wanted, &lower).min(edit_distance(&wanted, basename(&lower)));
+            (distance <= max_distance).then_some((distance, c))
+        })
+        .min_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)))
+        .map(|(_, c)| c))
+}
+
+/// Levenshtein distance over chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let sub = prev[j] + usize::from(ca != *cb);
+            cur[j + 1] = sub.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
 }
 
 /// Resolve a specific model by ID. Fails if the model doesn't exist.
 async fn resolve_specific_model(db: &Database, model_id: &str) -> Result<ResolvedModel> {
     let model = sqlx::query_as::<_, ResolvedModel>(
-        "SELECT id, hf_repo, backend_port, loaded, category_id, backend_type FROM models WHERE id = ?",
+        "SELECT id, hf_repo, backend_port, loaded, category_id, backend_type, draining FROM models WHERE id = ?",
     )
     .bind(model_id)
     .fetch_optional(&db.pool)
@@ -84,7 +328,7 @@ async fn resolve_specific_model(db: &Database, model_id: &str) -> Result<Resolve
 /// Look up a model by direct ID or hf_repo match.
 async fn resolve_by_id_or_repo(db: &Database, model_name: &str) -> Result<Option<ResolvedModel>> {
     let model = sqlx::query_as::<_, ResolvedModel>(
-        "SELECT id, hf_repo, backend_port, loaded, category_id, backend_type FROM models WHERE id = ? OR hf_repo = ?",
+        "SELECT id, hf_repo, backend_port, loaded, category_id, backend_type, draining FROM models WHERE id = ? OR hf_repo = ?",
     )
     .bind(model_name)
     .bind(model_name)
@@ -102,7 +346,7 @@ async fn resolve_from_category_id(
     // Try the category's preferred model
     let preferred = sqlx::query_as::<_, ResolvedModel>(
         r#"
-        SELECT m.id, m.hf_repo, m.backend_port, m.loaded, m.category_id, m.backend_type
+        SELECT m.id, m.hf_repo, m.backend_port, m.loaded, m.category_id, m.backend_type, m.draining
         FROM model_categories c
         JOIN models m ON m.id = c.preferred_model_id
         WHERE c.id = ?
@@ -113,17 +357,17 @@ async fn resolve_from_category_id(
     .await?;
 
     if let Some(ref m) = preferred {
-        if m.loaded {
+        if m.loaded && !m.draining {
             return Ok(preferred);
         }
     }
 
-    // Preferred model not loaded — try any loaded model in this category
+    // Preferred model not loaded (or draining) — try any other loaded model in this category
     let fallback = sqlx::query_as::<_, ResolvedModel>(
         r#"
-        SELECT id, hf_repo, backend_port, loaded, category_id, backend_type
+        SELECT id, hf_repo, backend_port, loaded, category_id, backend_type, draining
         FROM models
-        WHERE category_id = ? AND loaded = 1
+        WHERE category_id = ? AND loaded = 1 AND draining = 0
         ORDER BY last_used_at DESC
         LIMIT 1
         "#,
@@ -157,3 +401,213 @@ async fn resolve_from_category_name(
         None => Ok(None),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn insert_model(
+        db: &Database,
+        id: &str,
+        category_id: &str,
+        loaded: bool,
+        draining: bool,
+    ) {
+        sqlx::query(
+            "INSERT INTO models (id, hf_repo, category_id, loaded, draining) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(format!("org/{id}"))
+        .bind(category_id)
+        .bind(loaded)
+        .bind(draining)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+    }
+
+    async fn insert_category(db: &Database, id: &str, preferred: &str) {
+        sqlx::query(
+            "INSERT INTO model_categories (id, name, description, preferred_model_id) VALUES (?, ?, '', ?)",
+        )
+        .bind(id)
+        .bind(id)
+        .bind(preferred)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn category_skips_draining_preferred_model() {
+        let db = Database::test_db().await;
+        insert_category(&db, "cat", "pref").await;
+        insert_model(&db, "pref", "cat", true, true).await;
+        insert_model(&db, "other", "cat", true, false).await;
+
+        let m = resolve_model(&db, "anything", Some("cat"), None, &NameMatching::default())
+            .await
+            .unwrap();
+        assert_eq!(m.id, "other");
+    }
+
+    #[tokio::test]
+    async fn category_returns_draining_model_when_nothing_else_loaded() {
+        let db = Database::test_db().await;
+        insert_category(&db, "cat", "pref").await;
+        insert_model(&db, "pref", "cat", true, true).await;
+
+        // Caller checks `.draining` and rejects with model_draining
+        let m = resolve_model(&db, "anything", Some("cat"), None, &NameMatching::default())
+            .await
+            .unwrap();
+        assert_eq!(m.id, "pref");
+        assert!(m.draining);
+    }
+
+    #[tokio::test]
+    async fn direct_lookup_reports_draining() {
+        let db = Database::test_db().await;
+        insert_category(&db, "cat", "m1").await;
+        insert_model(&db, "m1", "cat", true, true).await;
+
+        let m = resolve_model(&db, "org/m1", None, None, &NameMatching::default())
+            .await
+            .unwrap();
+        assert!(m.draining);
+    }
+
+    #[test]
+    fn strips_configured_prefixes_case_insensitively() {
+        let prefixes = vec!["openai/".to_string()];
+        assert_eq!(strip_prefix("OpenAI/org/m1", &prefixes), "org/m1");
+        assert_eq!(strip_prefix("org/m1", &prefixes), "org/m1");
+        assert_eq!(strip_prefix("open", &prefixes), "open");
+    }
+
+    #[test]
+    fn edit_distance_counts_single_edits() {
+        assert_eq!(edit_distance("llama", "llama"), 0);
+        assert_eq!(edit_distance("llama", "lama"), 1);
+        assert_eq!(edit_distance("llama", "llamb"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[tokio::test]
+    async fn normalized_lookup_matches_prefix_case_and_basename() {
+        let db = Database::test_db().await;
+        insert_category(&db, "cat", "m1").await;
+        insert_model(&db, "m1", "cat", true, false).await;
+        let matching = NameMatching::default();
+
+        for name in ["openai/org/m1", "ORG/M1", "M1", "openai/M1"] {
+            let m = resolve_model(&db, name, None, None, &matching)
+                .await
+                .unwrap();
+            assert_eq!(m.id, "m1", "{name}");
+        }
+
+        let strict = NameMatching {
+            normalize: false,
+            ..NameMatching::default()
+        };
+        assert!(resolve_model(&db, "ORG/M1", None, None, &strict)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn ambiguous_basename_does_not_resolve() {
+        let db = Database::test_db().await;
+        insert_category(&db, "cat", "a").await;
+        for (id, repo) in [("a", "org-a/shared"), ("b", "org-b/shared")] {
+            sqlx::query("INSERT INTO models (id, hf_repo, category_id) VALUES (?, ?, 'cat')")
+                .bind(id)
+                .bind(repo)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+        }
+
+        assert!(
+            resolve_model(&db, "shared", None, None, &NameMatching::default())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn not_found_suggests_closest_name() {
+        let db = Database::test_db().await;
+        insert_category(&db, "cat", "m1").await;
+        sqlx::query(
+            "INSERT INTO models (id, hf_repo, category_id) VALUES ('m1', 'org/llama-3-8b', 'cat')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        let err = resolve_model(&db, "llama-3-7b", None, None, &NameMatching::default())
+            .await
+            .unwrap_err();
+        let not_found = err.downcast_ref::<ModelNotFound>().unwrap();
+        assert_eq!(not_found.suggestion.as_deref(), Some("org/llama-3-8b"));
+
+        let quiet = NameMatching {
+            suggestions: false,
+            ..NameMatching::default()
+        };
+        let err = resolve_model(&db, "llama-3-7b", None, None, &quiet)
+            .await
+            .unwrap_err();
+        assert!(err
+            .downcast_ref::<ModelNotFound>()
+            .unwrap()
+            .suggestion
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn alias_resolves_before_model_names() {
+        let db = Database::test_db().await;
+        insert_category(&db, "cat", "m1").await;
+        insert_model(&db, "m1", "cat", true, false).await;
+        insert_model(&db, "m2", "cat", true, false).await;
+        for (alias, model_id, category_id) in [
+            ("gpt-4o", Some("m2"), None),
+            ("org/m1", Some("m2"), None),
+            ("gpt-4o-mini", None, Some("cat")),
+        ] {
+            sqlx::query(
+                "INSERT INTO model_aliases (id, alias, model_id, category_id) VALUES (?, ?, ?, ?)",
+            )
+            .bind(alias)
+            .bind(alias)
+            .bind(model_id)
+            .bind(category_id)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        }
+        let matching = NameMatching::default();
+
+        for (name, expected) in [
+            ("gpt-4o", "m2"),
+            ("GPT-4o", "m2"),
+            ("openai/gpt-4o", "m2"),
+            ("org/m1", "m2"),
+            ("gpt-4o-mini", "m1"),
+        ] {
+            let m = resolve_model(&db, name, None, None, &matching)
+                .await
+                .unwrap();
+            assert_eq!(m.id, expected, "{name}");
+        }
+
+        // Scoped tokens never reach the alias lookup
+        let m = resolve_model(&db, "gpt-4o", None, Some("m1"), &matching)
+            .await
+            .unwrap();
+        assert_eq!(m.id, "m1");
+    }
+}
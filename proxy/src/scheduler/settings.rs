@@ -1,8 +1,140 @@
+use std::fmt;
+use std::str::FromStr;
+
 use anyhow::Result;
+use chrono::{Duration, NaiveDateTime, NaiveTime, Timelike};
 
 use crate::db::Database;
 
-/// Runtime-configurable fairness and queue settings.
+/// Which fairness policy orders queued requests (setting: `fairness_policy`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FairnessPolicyKind {
+    /// Log-scaled recent usage penalty plus wait bonus (the original formula).
+    WeightedUsage,
+    /// Strict rotation between users — each user's Nth queued request waits
+    /// behind every other user's (N-1)th.
+    RoundRobin,
+    /// Per-user token bucket — users with tokens left in their bucket go first.
+    TokenBucket,
+}
+
+impl FairnessPolicyKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::WeightedUsage => "weighted_usage",
+            Self::RoundRobin => "round_robin",
+            Self::TokenBucket => "token_bucket",
+        }
+    }
+}
+
+impl fmt::Display for FairnessPolicyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FairnessPolicyKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "weighted_usage" => Ok(Self::WeightedUsage),
+            "round_robin" => Ok(Self::RoundRobin),
+            "token_bucket" => Ok(Self::TokenBucket),
+            other => Err(format!(
+                "unknown fairness policy '{other}' (expected weighted_usage, round_robin or token_bucket)"
+            )),
+        }
+    }
+}
+
+/// How users are labelled in the admin usage analytics (setting:
+/// `analytics_privacy`). Holders of the `usage_auditor` role always see names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalyticsPrivacy {
+    /// Names or emails, as stored.
+    Off,
+    /// A stable pseudonym per user.
+    Pseudonymize,
+    /// Users merged into bands by request count.
+    Bucket,
+}
+
+impl AnalyticsPrivacy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Pseudonymize => "pseudonymize",
+            Self::Bucket => "bucket",
+        }
+    }
+}
+
+impl FromStr for AnalyticsPrivacy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "pseudonymize" => Ok(Self::Pseudonymize),
+            "bucket" => Ok(Self::Bucket),
+            other => Err(format!(
+                "unknown analytics privacy mode '{other}' (expected off, pseudonymize or bucket)"
+            )),
+        }
+    }
+}
+
+/// Daily UTC window in which downloads run at full speed (setting:
+/// `download_full_speed_window`, e.g. `22:00-06:00`). A window whose end is
+/// before its start runs past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl DownloadWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+impl fmt::Display for DownloadWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+impl FromStr for DownloadWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid download window '{s}' (expected HH:MM-HH:MM in UTC)");
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        let time = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").map_err(|_| invalid());
+        let window = Self {
+            start: time(start)?,
+            end: time(end)?,
+        };
+        if window.start == window.end {
+            return Err(format!("download window '{s}' is empty"));
+        }
+        Ok(window)
+    }
+}
+
+/// Runtime-configurable fairness, queue and reservation booking settings.
 ///
 /// Loaded from the `settings` table, with compile-time defaults as fallback.
 #[derive(Debug, Clone)]
@@ -19,6 +151,96 @@ pub struct FairnessSettings {
     pub window_minutes: i64,
     /// Max seconds to hold a queued request before 429.
     pub queue_timeout_secs: u64,
+    /// Active fairness policy.
+    pub policy: FairnessPolicyKind,
+    /// Token-bucket policy: bucket size per user (tokens).
+    pub bucket_capacity: f64,
+    /// Token-bucket policy: refill rate per user (tokens/second).
+    pub bucket_refill_per_sec: f64,
+    /// Priority added for `high`-tier users and taken from `low`-tier ones.
+    pub tier_weight: f64,
+    /// Minutes without requests before a loaded model's container is
+    /// stopped (0 = never). Models can override it.
+    pub idle_unload_minutes: u64,
+    /// Reservation start and end times must fall on multiples of this many
+    /// minutes from midnight UTC.
+    pub reservation_slot_minutes: u32,
+    /// Shortest bookable reservation (minutes).
+    pub reservation_min_minutes: u32,
+    /// Longest bookable reservation (minutes, 0 = no limit).
+    pub reservation_max_minutes: u32,
+    /// How far ahead a reservation may start (days, 0 = no limit).
+    pub reservation_max_advance_days: u32,
+    /// Pending reservations a user may hold at once (0 = no limit).
+    pub reservation_max_pending_per_user: u32,
+    /// Approve conflict-free reservations at creation, without admin review.
+    pub reservation_auto_approve: bool,
+    /// User labelling in the admin usage analytics.
+    pub analytics_privacy: AnalyticsPrivacy,
+    /// Bandwidth shared by all model downloads (MB/s, 0 = no limit).
+    pub download_max_mb_per_sec: u64,
+    /// When set, the download limit is lifted inside this window.
+    pub download_full_speed_window: Option<DownloadWindow>,
+    /// Days without use after which an unloaded model's files are deleted
+    /// from disk, keeping its row (0 = never).
+    pub model_evict_unused_days: u32,
+}
+
+impl FairnessSettings {
+    /// Check a reservation window against the booking policy. Returns the
+    /// reason it is rejected, if it is.
+    pub fn check_booking(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        now: NaiveDateTime,
+    ) -> Result<(), String> {
+        let slot = self.reservation_slot_minutes.max(1);
+        let on_boundary = |t: NaiveDateTime| {
+            t.second() == 0 && t.nanosecond() == 0 && (t.hour() * 60 + t.minute()) % slot == 0
+        };
+        if !on_boundary(start) || !on_boundary(end) {
+            return Err(format!(
+                "Times must be on {slot}-minute boundaries (counted from midnight UTC, seconds 0)"
+            ));
+        }
+
+        let minutes = (end - start).num_minutes();
+        if minutes < i64::from(self.reservation_min_minutes) {
+            return Err(format!(
+                "Minimum reservation duration is {} minutes",
+                self.reservation_min_minutes
+            ));
+        }
+        if self.reservation_max_minutes > 0 && minutes > i64::from(self.reservation_max_minutes) {
+            return Err(format!(
+                "Maximum reservation duration is {} minutes",
+                self.reservation_max_minutes
+            ));
+        }
+        if self.reservation_max_advance_days > 0
+            && start > now + Duration::days(i64::from(self.reservation_max_advance_days))
+        {
+            return Err(format!(
+                "Reservations can start at most {} days ahead",
+                self.reservation_max_advance_days
+            ));
+        }
+        Ok(())
+    }
+
+    /// Bytes per second all downloads may use together at `now` (UTC time
+    /// of day), or `None` for full speed.
+    pub fn download_limit(&self, now: NaiveTime) -> Option<u64> {
+        if self.download_max_mb_per_sec == 0
+            || self
+                .download_full_speed_window
+                .is_some_and(|w| w.contains(now))
+        {
+            return None;
+        }
+        Some(self.download_max_mb_per_sec * 1024 * 1024)
+    }
 }
 
 impl Default for FairnessSettings {
@@ -30,6 +252,21 @@ impl Default for FairnessSettings {
             usage_scale: 1000.0,
             window_minutes: 60,
             queue_timeout_secs: 30,
+            policy: FairnessPolicyKind::WeightedUsage,
+            bucket_capacity: 50_000.0,
+            bucket_refill_per_sec: 100.0,
+            tier_weight: 1000.0,
+            idle_unload_minutes: 0,
+            reservation_slot_minutes: 30,
+            reservation_min_minutes: 30,
+            reservation_max_minutes: 0,
+            reservation_max_advance_days: 0,
+            reservation_max_pending_per_user: 0,
+            reservation_auto_approve: false,
+            analytics_privacy: AnalyticsPrivacy::Off,
+            download_max_mb_per_sec: 0,
+            download_full_speed_window: None,
+            model_evict_unused_days: 0,
         }
     }
 }
@@ -74,6 +311,82 @@ pub async fn load_settings(db: &Database) -> Result<FairnessSettings> {
                     settings.queue_timeout_secs = v;
                 }
             }
+            "fairness_policy" => {
+                if let Ok(v) = value.parse() {
+                    settings.policy = v;
+                }
+            }
+            "fairness_bucket_capacity" => {
+                if let Ok(v) = value.parse() {
+                    settings.bucket_capacity = v;
+                }
+            }
+            "fairness_bucket_refill_per_sec" => {
+                if let Ok(v) = value.parse() {
+                    settings.bucket_refill_per_sec = v;
+                }
+            }
+            "fairness_tier_weight" => {
+                if let Ok(v) = value.parse() {
+                    settings.tier_weight = v;
+                }
+            }
+            "idle_unload_minutes" => {
+                if let Ok(v) = value.parse() {
+                    settings.idle_unload_minutes = v;
+                }
+            }
+            "reservation_slot_minutes" => {
+                if let Ok(v) = value.parse() {
+                    if is_valid_slot_minutes(v) {
+                        settings.reservation_slot_minutes = v;
+                    }
+                }
+            }
+            "reservation_min_minutes" => {
+                if let Ok(v) = value.parse() {
+                    settings.reservation_min_minutes = v;
+                }
+            }
+            "reservation_max_minutes" => {
+                if let Ok(v) = value.parse() {
+                    settings.reservation_max_minutes = v;
+                }
+            }
+            "reservation_max_advance_days" => {
+                if let Ok(v) = value.parse() {
+                    settings.reservation_max_advance_days = v;
+                }
+            }
+            "reservation_max_pending_per_user" => {
+                if let Ok(v) = value.parse() {
+                    settings.reservation_max_pending_per_user = v;
+                }
+            }
+            "reservation_auto_approve" => {
+                if let Ok(v) = value.parse() {
+                    settings.reservation_auto_approve = v;
+                }
+            }
+            "analytics_privacy" => {
+                if let Ok(v) = value.parse() {
+                    settings.analytics_privacy = v;
+                }
+            }
+            "download_max_mb_per_sec" => {
+                if let Ok(v) = value.parse() {
+                    settings.download_max_mb_per_sec = v;
+                }
+            }
+            "download_full_speed_window" => {
+                // Empty clears the window
+                settings.download_full_speed_window = value.parse().ok();
+            }
+            "model_evict_unused_days" => {
+                if let Ok(v) = value.parse() {
+                    settings.model_evict_unused_days = v;
+                }
+            }
             _ => {} // Ignore unknown keys
         }
     }
@@ -81,6 +394,11 @@ pub async fn load_settings(db: &Database) -> Result<FairnessSettings> {
     Ok(settings)
 }
 
+/// Slot lengths must divide a day evenly so every day has the same boundaries.
+pub fn is_valid_slot_minutes(minutes: u32) -> bool {
+    minutes > 0 && 1440 % minutes == 0
+}
+
 /// Persist a single setting to the DB.
 pub async fn save_setting(db: &Database, key: &str, value: &str) -> Result<()> {
     sqlx::query(
@@ -112,6 +430,171 @@ mod tests {
         assert!((s.usage_scale - d.usage_scale).abs() < f64::EPSILON);
         assert_eq!(s.window_minutes, d.window_minutes);
         assert_eq!(s.queue_timeout_secs, d.queue_timeout_secs);
+        assert_eq!(s.policy, d.policy);
+        assert!((s.bucket_capacity - d.bucket_capacity).abs() < f64::EPSILON);
+        assert!((s.bucket_refill_per_sec - d.bucket_refill_per_sec).abs() < f64::EPSILON);
+        assert!((s.tier_weight - d.tier_weight).abs() < f64::EPSILON);
+        assert_eq!(s.idle_unload_minutes, d.idle_unload_minutes);
+        assert_eq!(s.reservation_slot_minutes, d.reservation_slot_minutes);
+        assert_eq!(s.reservation_min_minutes, d.reservation_min_minutes);
+        assert_eq!(s.reservation_max_minutes, d.reservation_max_minutes);
+        assert_eq!(
+            s.reservation_max_advance_days,
+            d.reservation_max_advance_days
+        );
+        assert_eq!(
+            s.reservation_max_pending_per_user,
+            d.reservation_max_pending_per_user
+        );
+        assert_eq!(s.reservation_auto_approve, d.reservation_auto_approve);
+        assert_eq!(s.analytics_privacy, d.analytics_privacy);
+        assert_eq!(s.download_max_mb_per_sec, d.download_max_mb_per_sec);
+        assert_eq!(s.download_full_speed_window, d.download_full_speed_window);
+        assert_eq!(s.model_evict_unused_days, d.model_evict_unused_days);
+    }
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn booking_default_policy_matches_original_rules() {
+        let s = FairnessSettings::default();
+        let now = at("2026-01-01T00:00:00");
+        assert!(s
+            .check_booking(at("2026-01-02T10:30:00"), at("2026-01-02T11:00:00"), now)
+            .is_ok());
+        assert!(s
+            .check_booking(at("2026-01-02T10:15:00"), at("2026-01-02T11:00:00"), now)
+            .is_err());
+        // Far ahead and long are fine without limits
+        assert!(s
+            .check_booking(at("2027-06-01T00:00:00"), at("2027-06-09T00:00:00"), now)
+            .is_ok());
+    }
+
+    #[test]
+    fn booking_limits_enforced() {
+        let s = FairnessSettings {
+            reservation_slot_minutes: 15,
+            reservation_min_minutes: 60,
+            reservation_max_minutes: 240,
+            reservation_max_advance_days: 7,
+            ..Default::default()
+        };
+        let now = at("2026-01-01T00:00:00");
+        assert!(s
+            .check_booking(at("2026-01-02T10:15:00"), at("2026-01-02T11:15:00"), now)
+            .is_ok());
+        // Too short, too long, too far ahead
+        assert!(s
+            .check_booking(at("2026-01-02T10:15:00"), at("2026-01-02T10:45:00"), now)
+            .unwrap_err()
+            .contains("Minimum"));
+        assert!(s
+            .check_booking(at("2026-01-02T10:00:00"), at("2026-01-02T14:15:00"), now)
+            .unwrap_err()
+            .contains("Maximum"));
+        assert!(s
+            .check_booking(at("2026-01-09T00:15:00"), at("2026-01-09T01:15:00"), now)
+            .unwrap_err()
+            .contains("days ahead"));
+    }
+
+    #[test]
+    fn slot_minutes_must_divide_a_day() {
+        assert!(is_valid_slot_minutes(15));
+        assert!(is_valid_slot_minutes(60));
+        assert!(!is_valid_slot_minutes(0));
+        assert!(!is_valid_slot_minutes(45));
+    }
+
+    fn hm(s: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(s, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn download_window_parsing() {
+        let w: DownloadWindow = "22:00-06:00".parse().unwrap();
+        assert_eq!(w.start, hm("22:00"));
+        assert_eq!(w.to_string(), "22:00-06:00");
+        assert!("22:00".parse::<DownloadWindow>().is_err());
+        assert!("25:00-06:00".parse::<DownloadWindow>().is_err());
+        assert!("06:00-06:00".parse::<DownloadWindow>().is_err());
+    }
+
+    #[test]
+    fn download_limit_lifted_inside_window() {
+        let mb = 1024 * 1024;
+        let mut s = FairnessSettings::default();
+        assert_eq!(s.download_limit(hm("12:00")), None);
+
+        s.download_max_mb_per_sec = 20;
+        assert_eq!(s.download_limit(hm("12:00")), Some(20 * mb));
+
+        // Overnight window wraps past midnight
+        s.download_full_speed_window = Some("22:00-06:00".parse().unwrap());
+        assert_eq!(s.download_limit(hm("23:30")), None);
+        assert_eq!(s.download_limit(hm("05:59")), None);
+        assert_eq!(s.download_limit(hm("06:00")), Some(20 * mb));
+        assert_eq!(s.download_limit(hm("21:59")), Some(20 * mb));
+
+        s.download_full_speed_window = Some("01:00-03:00".parse().unwrap());
+        assert_eq!(s.download_limit(hm("02:00")), None);
+        assert_eq!(s.download_limit(hm("04:00")), Some(20 * mb));
+    }
+
+    #[tokio::test]
+    async fn idle_unload_minutes_round_trips_through_db() {
+        let db = Database::test_db().await;
+        save_setting(&db, "idle_unload_minutes", "15")
+            .await
+            .unwrap();
+
+        let s = load_settings(&db).await.unwrap();
+        assert_eq!(s.idle_unload_minutes, 15);
+    }
+
+    #[tokio::test]
+    async fn policy_round_trips_through_db() {
+        let db = Database::test_db().await;
+        save_setting(&db, "fairness_policy", "token_bucket")
+            .await
+            .unwrap();
+
+        let s = load_settings(&db).await.unwrap();
+        assert_eq!(s.policy, FairnessPolicyKind::TokenBucket);
+    }
+
+    #[tokio::test]
+    async fn analytics_privacy_round_trips_through_db() {
+        let db = Database::test_db().await;
+        save_setting(&db, "analytics_privacy", "bucket")
+            .await
+            .unwrap();
+        assert_eq!(
+            load_settings(&db).await.unwrap().analytics_privacy,
+            AnalyticsPrivacy::Bucket
+        );
+
+        save_setting(&db, "analytics_privacy", "blur")
+            .await
+            .unwrap();
+        assert_eq!(
+            load_settings(&db).await.unwrap().analytics_privacy,
+            AnalyticsPrivacy::Off
+        );
+    }
+
+    #[tokio::test]
+    async fn unknown_policy_keeps_default() {
+        let db = Database::test_db().await;
+        save_setting(&db, "fairness_policy", "lottery")
+            .await
+            .unwrap();
+
+        let s = load_settings(&db).await.unwrap();
+        assert_eq!(s.policy, FairnessPolicyKind::WeightedUsage);
     }
 
     #[tokio::test]
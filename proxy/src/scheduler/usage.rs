@@ -5,7 +5,8 @@ use crate::db::Database;
 
 /// A completed inference request to be logged.
 pub struct UsageEntry<'a> {
-    pub token_id: &'a str,
+    /// `None` for admin replays, which run without an API token.
+    pub token_id: Option<&'a str>,
     pub user_id: &'a str,
     pub model_id: &'a str,
     pub category_id: Option<&'a str>,
@@ -13,6 +14,14 @@ pub struct UsageEntry<'a> {
     pub output_tokens: i64,
     pub latency_ms: i64,
     pub queued_ms: i64,
+    /// Set when this request re-ran a logged request (`request_log.id`).
+    pub replay_of: Option<&'a str>,
+    /// Sampling seed the client sent, if any.
+    pub seed: Option<i64>,
+    /// Request hashes for the deduplication statistics, when
+    /// `PROMPT_DEDUP_STATS` is on.
+    pub prompt_hash: Option<&'a str>,
+    pub prompt_norm_hash: Option<&'a str>,
 }
 
 /// Log a completed inference request to the usage_log table.
@@ -22,8 +31,9 @@ pub async fn log_usage(db: &Database, entry: &UsageEntry<'_>) -> Result<()> {
     sqlx::query(
         r#"
         INSERT INTO usage_log (id, token_id, user_id, model_id, category_id,
-                               input_tokens, output_tokens, latency_ms, queued_ms)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                               input_tokens, output_tokens, latency_ms, queued_ms, replay_of,
+                               seed, prompt_hash, prompt_norm_hash)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(&id)
@@ -35,6 +45,10 @@ pub async fn log_usage(db: &Database, entry: &UsageEntry<'_>) -> Result<()> {
     .bind(entry.output_tokens)
     .bind(entry.latency_ms)
     .bind(entry.queued_ms)
+    .bind(entry.replay_of)
+    .bind(entry.seed)
+    .bind(entry.prompt_hash)
+    .bind(entry.prompt_norm_hash)
     .execute(&db.pool)
     .await
     .context("Failed to insert usage log entry")?;
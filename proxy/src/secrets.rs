@@ -0,0 +1,315 @@
+//! Secret managers for config secrets (`SECRET_PROVIDER`).
+//!
+//! Each provider reads one secret holding key/value pairs named like the
+//! environment variables they replace (`DB_ENCRYPTION_KEY`, `WEBUI_API_KEY`,
+//! ...). See `config::SecretProvider` for how the values are applied.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::config::SecretProvider;
+
+fn required_env(name: &str) -> Result<String> {
+    std::env::var(name)
+        .ok()
+        .filter(|v| !v.trim().is_empty())
+        .with_context(|| format!("{name} must be set for this SECRET_PROVIDER"))
+}
+
+/// The string values of a JSON object. Other values are skipped.
+fn string_map(value: &Value) -> Result<HashMap<String, String>> {
+    let Value::Object(object) = value else {
+        bail!("Secret is not a JSON object of key/value pairs");
+    };
+    Ok(object
+        .iter()
+        .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+        .collect())
+}
+
+// ---------------------------------------------------------------------------
+// HashiCorp Vault
+// ---------------------------------------------------------------------------
+
+/// A Vault KV secret (v1 or v2), read with a token.
+pub struct Vault {
+    addr: String,
+    /// API path after `/v1/`, e.g. `secret/data/sovereign-engine` for KV v2.
+    path: String,
+    token: Option<String>,
+    /// Re-read on every fetch, so a Vault agent can renew it.
+    token_file: Option<String>,
+    namespace: Option<String>,
+}
+
+impl Vault {
+    pub fn from_env() -> Result<Self> {
+        let token = std::env::var("VAULT_TOKEN").ok().filter(|v| !v.is_empty());
+        let token_file = std::env::var("VAULT_TOKEN_FILE")
+            .ok()
+            .filter(|v| !v.is_empty());
+        if token.is_none() && token_file.is_none() {
+            bail!("VAULT_TOKEN or VAULT_TOKEN_FILE must be set for SECRET_PROVIDER=vault");
+        }
+        Ok(Self {
+            addr: required_env("VAULT_ADDR")?
+                .trim_end_matches('/')
+                .to_string(),
+            path: required_env("VAULT_SECRET_PATH")?
+                .trim_matches('/')
+                .to_string(),
+            token,
+            token_file,
+            namespace: std::env::var("VAULT_NAMESPACE")
+                .ok()
+                .filter(|v| !v.is_empty()),
+        })
+    }
+
+    async fn token(&self) -> Result<String> {
+        match &self.token_file {
+            Some(path) => Ok(tokio::fs::read_to_string(path)
+                .await
+                .with_context(|| format!("Failed to read VAULT_TOKEN_FILE {path}"))?
+                .trim()
+                .to_string()),
+            None => Ok(self.token.clone().unwrap_or_default()),
+        }
+    }
+}
+
+/// The key/value pairs in a Vault read response: `data.data` for KV v2,
+/// `data` for KV v1.
+fn vault_data(body: &Value) -> Result<HashMap<String, String>> {
+    let data = &body["data"];
+    match &data["data"] {
+        Value::Object(_) => string_map(&data["data"]),
+        _ => string_map(data),
+    }
+}
+
+impl SecretProvider for Vault {
+    fn name(&self) -> &'static str {
+        "vault"
+    }
+
+    async fn fetch(&self) -> Result<HashMap<String, String>> {
+        let mut request = reqwest::Client::new()
+            .get(format!("{}/v1/{}", self.addr, self.path))
+            .header("X-Vault-Token", self.token().await?);
+        if let Some(namespace) = &self.namespace {
+            request = request.header("X-Vault-Namespace", namespace);
+        }
+        let body: Value = request
+            .send()
+            .await
+            .context("Vault request failed")?
+            .error_for_status()
+            .context("Vault refused the secret read")?
+            .json()
+            .await
+            .context("Vault returned invalid JSON")?;
+        vault_data(&body)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// AWS Secrets Manager
+// ---------------------------------------------------------------------------
+
+/// An AWS Secrets Manager secret whose `SecretString` is a JSON object,
+/// read with static credentials from the standard `AWS_*` variables.
+pub struct AwsSecretsManager {
+    region: String,
+    secret_id: String,
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl AwsSecretsManager {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            region: required_env("AWS_REGION")?,
+            secret_id: required_env("AWS_SECRET_ID")?,
+            access_key_id: required_env("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: required_env("AWS_SECRET_ACCESS_KEY")?,
+            session_token: std::env::var("AWS_SESSION_TOKEN")
+                .ok()
+                .filter(|v| !v.is_empty()),
+        })
+    }
+}
+
+pub(crate) fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// SigV4 signing key for `date` (YYYYMMDD).
+pub(crate) fn signing_key(
+    secret_access_key: &str,
+    date: &str,
+    region: &str,
+    service: &str,
+) -> Vec<u8> {
+    let k_date = hmac_sha256(format!("AWS4{secret_access_key}").as_bytes(), date);
+    let k_region = hmac_sha256(&k_date, region);
+    let k_service = hmac_sha256(&k_region, service);
+    hmac_sha256(&k_service, "aws4_request")
+}
+
+impl SecretProvider for AwsSecretsManager {
+    fn name(&self) -> &'static str {
+        "aws"
+    }
+
+    async fn fetch(&self) -> Result<HashMap<String, String>> {
+        const SERVICE: &str = "secretsmanager";
+        const TARGET: &str = "secretsmanager.GetSecretValue";
+        const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+
+        let host = format!("{SERVICE}.{}.amazonaws.com", self.region);
+        let body = serde_json::json!({ "SecretId": self.secret_id }).to_string();
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        // Canonical headers must be sorted by name
+        let mut headers = vec![
+            ("content-type", CONTENT_TYPE.to_string()),
+            ("host", host.clone()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        headers.push(("x-amz-target", TARGET.to_string()));
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{value}\n"))
+            .collect();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let payload_hash = hex::encode(Sha256::digest(body.as_bytes()));
+        let canonical_request =
+            format!("POST\n/\n\n{canonical_headers}\n{signed_headers}\n{payload_hash}");
+        let scope = format!("{date}/{}/{SERVICE}/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let key = signing_key(&self.secret_access_key, &date, &self.region, SERVICE);
+        let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+
+        let mut request = reqwest::Client::new()
+            .post(format!("https://{host}/"))
+            .header("content-type", CONTENT_TYPE)
+            .header("x-amz-date", &amz_date)
+            .header("x-amz-target", TARGET)
+            .header(
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+                    self.access_key_id
+                ),
+            )
+            .body(body);
+        if let Some(token) = &self.session_token {
+            request = request.header("x-amz-security-token", token);
+        }
+        let response: Value = request
+            .send()
+            .await
+            .context("AWS Secrets Manager request failed")?
+            .error_for_status()
+            .context("AWS Secrets Manager refused the secret read")?
+            .json()
+            .await
+            .context("AWS Secrets Manager returned invalid JSON")?;
+        let secret = response["SecretString"]
+            .as_str()
+            .context("Secret has no SecretString")?;
+        string_map(&serde_json::from_str(secret).context("SecretString is not JSON")?)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// SOPS-encrypted file
+// ---------------------------------------------------------------------------
+
+/// A SOPS-encrypted file, decrypted with the `sops` binary and whatever
+/// keys it is configured with (age, PGP, KMS, ...).
+pub struct SopsFile {
+    path: String,
+}
+
+impl SopsFile {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            path: required_env("SOPS_SECRETS_FILE")?,
+        })
+    }
+}
+
+impl SecretProvider for SopsFile {
+    fn name(&self) -> &'static str {
+        "sops"
+    }
+
+    async fn fetch(&self) -> Result<HashMap<String, String>> {
+        let output = tokio::process::Command::new("sops")
+            .args(["--decrypt", "--output-type", "json", &self.path])
+            .output()
+            .await
+            .context("Failed to run sops")?;
+        if !output.status.success() {
+            bail!(
+                "sops could not decrypt {}: {}",
+                self.path,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        string_map(&serde_json::from_slice(&output.stdout).context("sops output is not JSON")?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn vault_reads_kv_v1_and_v2() {
+        let v2 = json!({ "data": { "data": { "WEBUI_API_KEY": "k2" }, "metadata": {} } });
+        let v1 = json!({ "data": { "WEBUI_API_KEY": "k1", "ttl": 60 } });
+        assert_eq!(vault_data(&v2).unwrap()["WEBUI_API_KEY"], "k2");
+        let v1 = vault_data(&v1).unwrap();
+        assert_eq!(v1["WEBUI_API_KEY"], "k1");
+        assert!(!v1.contains_key("ttl"));
+        assert!(vault_data(&json!({ "errors": [] })).is_err());
+    }
+
+    #[test]
+    fn sigv4_signing_key_matches_aws_example() {
+        // From the AWS SigV4 documentation's signing key example
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20150830",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex::encode(key),
+            "c4afb1cc5771d871763a393e44b703571b55cc28424d1a5e86da6ed3c154a4b9"
+        );
+    }
+}
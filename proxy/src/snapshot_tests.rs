@@ -0,0 +1,858 @@
+//! Snapshot tests for the HTTP API.
+//!
+//! Each test drives the full `/api` router through `test_harness::Harness`
+//! and checks responses against golden files in `proxy/tests/snapshots/`
+//! (see `test_harness::assert_snapshot`). Adding a regression test for a new
+//! endpoint is a request plus an `assert_snapshot` call; write the golden
+//! file with `UPDATE_SNAPSHOTS=1` and commit it with the change.
+//!
+//! Every `GET` under `/api/user` and `/api/admin` is covered, except those
+//! listed under "not snapshotted" at the end.
+//!
+//! # Test groups
+//!
+//! ## user tokens — /api/user/tokens
+//!
+//! - **user_token_lifecycle** — empty list, create (plaintext token
+//!   redacted), then the listed token.
+//!
+//! ## user queue — /api/user/queue
+//!
+//! - **user_queue_empty** — no queued requests, and none interrupted by a
+//!   restart.
+//!
+//! ## user devices — /api/user/devices
+//!
+//! - **user_devices_empty** — no remembered devices.
+//!
+//! ## admin area — /api/admin/*
+//!
+//! - **admin_area_forbidden_for_users** — a session without admin rights or
+//!   roles gets 403.
+//!
+//! ## roles — /api/admin/roles
+//!
+//! - **admin_roles_list** — the assignable roles with their descriptions.
+//!
+//! ## approvals — /api/admin/approvals
+//!
+//! - **admin_approvals_empty** — no approval requests.
+//!
+//! ## feature flags — /api/admin/feature-flags
+//!
+//! - **feature_flag_lifecycle** — put, list, delete, and delete again (404).
+//!
+//! ## model files — /api/admin/models/{id}/files
+//!
+//! - **model_files_list_and_prune** — files on disk are listed with sizes;
+//!   deleting one returns what is left and the new `size_bytes`. An unknown
+//!   model is 404.
+//!
+//! ## identity providers — /api/admin/idps
+//!
+//! - **admin_idps_list** — the test IdP, without its client secret.
+//!
+//! ## catalog — /api/admin/{categories,models}, /api/user/{categories,models}
+//!
+//! - **catalog_lists** — one category with one model, as admins and users
+//!   see them.
+//! - **model_launch_profile_and_defaults** — a model without a launch profile
+//!   (404), with one, and its default sampling parameters.
+//! - **model_aliases_list** — a category alias and a model alias with their
+//!   target names.
+//! - **model_trace_lifecycle** — no trace, then a started one.
+//! - **model_trash_list** — a deleted model awaiting purge.
+//!
+//! ## settings and audit — /api/admin/settings, /api/admin/audit/{id}/diff
+//!
+//! - **settings_update_and_diff** — defaults, an update, the recorded change
+//!   set, and an unknown change set (404).
+//!
+//! ## users — /api/admin/users, /api/admin/users/{id}/roles
+//!
+//! - **admin_users_and_roles** — both session users with their roles and
+//!   usage totals.
+//!
+//! ## usage — /api/user/usage*, /api/admin/usage*, /api/admin/queue/history
+//!
+//! - **usage_reports** — two requests in the user and admin summaries and
+//!   timelines.
+//! - **admin_usage_dedup_empty** — prompt repeat stats with nothing recorded.
+//! - **admin_queue_history** — one served and one timed-out queue entry.
+//!
+//! ## system — /api/admin/system*, /api/admin/ui/manifest, /api/admin/issues,
+//! /api/admin/hygiene, /api/admin/export, /api/admin/host-routes
+//!
+//! - **admin_system_views** — system status (host-independent fields),
+//!   connection pool, UI manifest, and the effective config when none was
+//!   loaded.
+//! - **admin_issues_list** — a reservation request pending for over a day.
+//! - **admin_hygiene_report** — an empty category.
+//! - **admin_config_export** — the default configuration bundle.
+//! - **admin_host_routes_list** — built-in routes and one redirect.
+//! - **system_prompt_policies_list** — a category system prompt.
+//!
+//! ## elevations — /api/user/elevation, /api/admin/elevations
+//!
+//! - **elevation_request_listed** — a request, then the user's and the
+//!   admin's pending lists.
+//!
+//! ## reservations — /api/user/reservations*, /api/admin/reservations*
+//!
+//! - **reservation_views** — a pending reservation in the user list,
+//!   calendar and admin list; no active reservation; an empty heatmap.
+//! - **reservation_rules_list** — one auto-approval rule.
+//!
+//! ## request log — /api/admin/requests
+//!
+//! - **request_log_entry** — a fully logged request, listed and with its
+//!   bodies.
+//!
+//! ## archive — /api/user/archive
+//!
+//! - **archive_entry_and_blob** — an archived completion and its prompt
+//!   blob.
+//!
+//! ## conversations — /api/user/conversations
+//!
+//! - **conversation_views** — a saved conversation, listed, fetched and
+//!   exported.
+//!
+//! ## downloads — /api/user/hf/downloads
+//!
+//! - **hf_downloads_list** — a failed download that can be resumed.
+//!
+//! ## harness
+//!
+//! - **redacts_ids_timestamps_and_secrets** — volatile values are replaced
+//!   with placeholders before comparison.
+//! - **missing_snapshot_fails** — a golden file that was never written fails
+//!   the comparison instead of being created.
+//!
+//! ## not snapshotted
+//!
+//! - `/api/user/events`, `/api/user/hf/downloads/stream` — SSE streams that
+//!   do not end.
+//! - `/api/user/hf/{search,files,recommend}` — proxy the live Hugging Face
+//!   API.
+//! - `/api/user/disk` — reports the host's disk.
+//! - `/api/admin/containers`, `/api/admin/containers/{id}/logs` — need a
+//!   Docker daemon.
+//! - The disk, GPU, backend and container fields of `/api/admin/system`,
+//!   for the same reasons.
+
+use axum::http::StatusCode;
+use serde_json::json;
+
+use crate::api::archive::ArchiveEntry;
+use crate::api::request_log::{LogLevel, LoggedRequest};
+use crate::test_harness::{assert_snapshot, check_snapshot, redact, snapshot_path, Harness};
+
+#[tokio::test]
+async fn user_token_lifecycle() {
+    let harness = Harness::new().await;
+    let user = harness.user("snap-user").await;
+
+    let (status, body) = user.get("/api/user/tokens").await;
+    assert_snapshot("user_tokens_empty", status, &body, &[]);
+
+    let (status, body) = user.post("/api/user/tokens", json!({ "name": "ci" })).await;
+    assert_snapshot("user_token_created", status, &body, &["token"]);
+
+    let (status, body) = user.get("/api/user/tokens").await;
+    assert_snapshot("user_tokens_listed", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn user_queue_empty() {
+    let harness = Harness::new().await;
+    let user = harness.user("snap-user").await;
+
+    let (status, body) = user.get("/api/user/queue").await;
+    assert_snapshot("user_queue_empty", status, &body, &[]);
+
+    let (status, body) = user.get("/api/user/queue/interrupted").await;
+    assert_snapshot("user_queue_interrupted_empty", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn user_devices_empty() {
+    let harness = Harness::new().await;
+    let user = harness.user("snap-user").await;
+
+    let (status, body) = user.get("/api/user/devices").await;
+    assert_snapshot("user_devices_empty", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn admin_area_forbidden_for_users() {
+    let harness = Harness::new().await;
+    let user = harness.user("snap-user").await;
+
+    let (status, body) = user.get("/api/admin/feature-flags").await;
+    assert_snapshot("admin_area_forbidden", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn admin_roles_list() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+
+    let (status, body) = admin.get("/api/admin/roles").await;
+    assert_snapshot("admin_roles_list", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn admin_approvals_empty() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+
+    let (status, body) = admin.get("/api/admin/approvals").await;
+    assert_snapshot("admin_approvals_empty", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn feature_flag_lifecycle() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+
+    let (status, body) = admin
+        .put(
+            "/api/admin/feature-flags/new_usage_parser",
+            json!({
+                "description": "Parse usage from the new field",
+                "enabled": true,
+                "rollout_percent": 10,
+                "user_ids": ["snap-user"],
+            }),
+        )
+        .await;
+    assert_snapshot("feature_flag_put", status, &body, &[]);
+
+    let (status, body) = admin.get("/api/admin/feature-flags").await;
+    assert_snapshot("feature_flags_list", status, &body, &[]);
+
+    let (status, body) = admin
+        .delete("/api/admin/feature-flags/new_usage_parser")
+        .await;
+    assert_snapshot("feature_flag_deleted", status, &body, &[]);
+
+    let (status, body) = admin
+        .delete("/api/admin/feature-flags/new_usage_parser")
+        .await;
+    assert_snapshot("feature_flag_delete_missing", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn model_files_list_and_prune() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+
+    let dir = std::path::Path::new(&harness.state.config.model_path).join("snapshot--files-GGUF");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("model-Q4_K_M.gguf"), b"GGUF").unwrap();
+    std::fs::write(dir.join("model-Q8_0.gguf"), b"GGUFGGUF").unwrap();
+    sqlx::query(
+        "INSERT INTO models (id, hf_repo, filename, size_bytes, loaded, backend_type) \
+         VALUES ('snap-model', 'snapshot/files-GGUF', 'model-Q4_K_M.gguf', 12, 0, 'llamacpp')",
+    )
+    .execute(&harness.state.db.pool)
+    .await
+    .unwrap();
+
+    let (status, body) = admin.get("/api/admin/models/snap-model/files").await;
+    assert_snapshot("model_files_list", status, &body, &[]);
+
+    let (status, body) = admin
+        .send(
+            "DELETE",
+            "/api/admin/models/snap-model/files",
+            Some(json!({ "paths": ["model-Q8_0.gguf"] })),
+        )
+        .await;
+    assert_snapshot("model_files_pruned", status, &body, &[]);
+    assert!(!dir.join("model-Q8_0.gguf").exists());
+
+    let (status, body) = admin.get("/api/admin/models/missing/files").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["error"], "Model not found");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// One category with one model in it, shared by the catalog tests.
+async fn seed_catalog(state: &crate::AppState) {
+    let pool = &state.db.pool;
+    sqlx::query(
+        "INSERT INTO model_categories (id, name, description) \
+         VALUES ('snap-cat', 'general', 'Everyday chat')",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"INSERT INTO models (id, hf_repo, filename, size_bytes, category_id, backend_type,
+                              context_length, default_params)
+           VALUES ('snap-model', 'snapshot/chat-GGUF', 'chat-Q4_K_M.gguf', 4096, 'snap-cat',
+                   'llamacpp', 8192, '{"temperature":0.7}')"#,
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+async fn exec(harness: &Harness, sql: &str) {
+    sqlx::query(sql)
+        .execute(&harness.state.db.pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn admin_idps_list() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+
+    let (status, body) = admin.get("/api/admin/idps").await;
+    assert_snapshot("admin_idps_list", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn catalog_lists() {
+    let harness = Harness::new().await;
+    seed_catalog(&harness.state).await;
+    let admin = harness.admin("snap-admin").await;
+    let user = harness.user("snap-user").await;
+
+    let (status, body) = admin.get("/api/admin/categories").await;
+    assert_snapshot("admin_categories_list", status, &body, &[]);
+
+    let (status, body) = user.get("/api/user/categories").await;
+    assert_snapshot("user_categories_list", status, &body, &[]);
+
+    let (status, body) = admin.get("/api/admin/models").await;
+    assert_snapshot("admin_models_list", status, &body, &[]);
+
+    let (status, body) = user.get("/api/user/models").await;
+    assert_snapshot("user_models_list", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn model_launch_profile_and_defaults() {
+    let harness = Harness::new().await;
+    seed_catalog(&harness.state).await;
+    let admin = harness.admin("snap-admin").await;
+
+    let (status, body) = admin
+        .get("/api/admin/models/snap-model/launch-profile")
+        .await;
+    assert_snapshot("model_launch_profile_missing", status, &body, &[]);
+
+    exec(
+        &harness,
+        "INSERT INTO model_launch_profiles (model_id, backend_type, gpu_type, gpu_layers, \
+         context_size, parallel, cpu_limit, memory_limit_mb) \
+         VALUES ('snap-model', 'llamacpp', 'nvidia', 99, 8192, 2, 4.0, 16384)",
+    )
+    .await;
+    let (status, body) = admin
+        .get("/api/admin/models/snap-model/launch-profile")
+        .await;
+    assert_snapshot("model_launch_profile", status, &body, &[]);
+
+    let (status, body) = admin
+        .get("/api/admin/models/snap-model/default-params")
+        .await;
+    assert_snapshot("model_default_params", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn model_aliases_list() {
+    let harness = Harness::new().await;
+    seed_catalog(&harness.state).await;
+    let admin = harness.admin("snap-admin").await;
+    exec(
+        &harness,
+        "INSERT INTO model_aliases (id, alias, model_id, category_id, created_by) VALUES \
+         ('snap-alias-cat', 'fast', NULL, 'snap-cat', 'snap-admin'), \
+         ('snap-alias-model', 'gpt-4o', 'snap-model', NULL, 'snap-admin')",
+    )
+    .await;
+
+    let (status, body) = admin.get("/api/admin/aliases").await;
+    assert_snapshot("admin_aliases_list", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn model_trace_lifecycle() {
+    let harness = Harness::new().await;
+    seed_catalog(&harness.state).await;
+    let admin = harness.admin("snap-admin").await;
+
+    let (status, body) = admin.get("/api/admin/models/snap-model/trace").await;
+    assert_snapshot("model_trace_none", status, &body, &[]);
+
+    let (status, _) = admin
+        .send(
+            "POST",
+            "/api/admin/models/snap-model/trace?count=5&ttl=1h",
+            None,
+        )
+        .await;
+    assert!(status.is_success());
+    let (status, body) = admin.get("/api/admin/models/snap-model/trace").await;
+    assert_snapshot("model_trace_live", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn model_trash_list() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+    exec(
+        &harness,
+        "INSERT INTO model_trash (id, model_id, hf_repo, model_row, size_bytes, deleted_by, \
+         purge_after) \
+         VALUES ('snap-trash', 'old-model', 'snapshot/old-GGUF', '{}', 2048, 'snap-admin', \
+         datetime('now', '+7 days'))",
+    )
+    .await;
+
+    let (status, body) = admin.get("/api/admin/models/trash").await;
+    assert_snapshot("model_trash_list", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn settings_update_and_diff() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+
+    let (status, body) = admin.get("/api/admin/settings").await;
+    assert_snapshot("admin_settings_defaults", status, &body, &[]);
+
+    let (status, body) = admin
+        .put(
+            "/api/admin/settings",
+            json!({ "queue_timeout_secs": 45, "reservation_auto_approve": true }),
+        )
+        .await;
+    assert_snapshot("admin_settings_updated", status, &body, &[]);
+
+    let (change_id,): (String,) =
+        sqlx::query_as("SELECT id FROM config_changes WHERE action = 'settings.update'")
+            .fetch_one(&harness.state.db.pool)
+            .await
+            .unwrap();
+    let (status, body) = admin
+        .get(&format!("/api/admin/audit/{change_id}/diff"))
+        .await;
+    assert_snapshot("audit_settings_diff", status, &body, &[]);
+
+    let (status, body) = admin.get("/api/admin/audit/missing/diff").await;
+    assert_snapshot("audit_diff_missing", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn admin_users_and_roles() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+    harness.user("snap-user").await;
+    exec(
+        &harness,
+        "INSERT INTO user_roles (user_id, role, granted_by) \
+         VALUES ('snap-user', 'viewer', 'snap-admin')",
+    )
+    .await;
+
+    let (status, body) = admin.get("/api/admin/users").await;
+    assert_snapshot("admin_users_list", status, &body, &[]);
+
+    let (status, body) = admin.get("/api/admin/users/snap-user/roles").await;
+    assert_snapshot("admin_user_roles", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn usage_reports() {
+    let harness = Harness::new().await;
+    seed_catalog(&harness.state).await;
+    let admin = harness.admin("snap-admin").await;
+    let user = harness.user("snap-user").await;
+    // One statement, so both rows share a timestamp and timeline bucket
+    exec(
+        &harness,
+        "INSERT INTO usage_log (id, user_id, model_id, category_id, input_tokens, \
+         output_tokens) VALUES \
+         ('u1', 'snap-user', 'snap-model', 'snap-cat', 100, 50), \
+         ('u2', 'snap-user', 'snap-model', 'snap-cat', 200, 25)",
+    )
+    .await;
+
+    let (status, body) = user.get("/api/user/usage").await;
+    assert_snapshot("user_usage", status, &body, &[]);
+
+    let (status, body) = user.get("/api/user/usage/timeline").await;
+    assert_snapshot("user_usage_timeline", status, &body, &[]);
+
+    let (status, body) = admin.get("/api/admin/usage").await;
+    assert_snapshot("admin_usage", status, &body, &[]);
+
+    let (status, body) = admin.get("/api/admin/usage/timeline").await;
+    assert_snapshot("admin_usage_timeline", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn admin_usage_dedup_empty() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+
+    let (status, body) = admin.get("/api/admin/usage/dedup").await;
+    assert_snapshot("admin_usage_dedup_empty", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn admin_queue_history() {
+    let harness = Harness::new().await;
+    seed_catalog(&harness.state).await;
+    let admin = harness.admin("snap-admin").await;
+    harness.user("snap-user").await;
+    exec(
+        &harness,
+        "INSERT INTO queue_log (request_id, user_id, model_id, enqueued_at, status, wait_ms, \
+         resolved_at) VALUES \
+         ('q1', 'snap-user', 'snap-model', datetime('now'), 'served', 120, datetime('now')), \
+         ('q2', 'snap-user', 'snap-model', datetime('now'), 'timed_out', 30000, \
+         datetime('now'))",
+    )
+    .await;
+
+    let (status, body) = admin.get("/api/admin/queue/history").await;
+    assert_snapshot("admin_queue_history", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn admin_system_views() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+
+    // Disk, GPU, architecture, backends and containers depend on the host
+    let (status, body) = admin.get("/api/admin/system").await;
+    let stable = json!({
+        "queues": body["queues"],
+        "gates": body["gates"],
+        "category_gates": body["category_gates"],
+        "drain": body["drain"],
+        "model_eviction": body["model_eviction"],
+        "tasks": body["tasks"],
+        "watchdog": body["watchdog"],
+    });
+    assert_snapshot("admin_system_status", status, &stable, &[]);
+
+    let (status, body) = admin.get("/api/admin/system/connections").await;
+    assert_snapshot("admin_system_connections", status, &body, &[]);
+
+    let (status, body) = admin.get("/api/admin/ui/manifest").await;
+    assert_snapshot("admin_ui_manifest", status, &body, &[]);
+
+    let (status, body) = admin.get("/api/admin/system/config").await;
+    assert_snapshot("admin_system_config", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn admin_issues_list() {
+    let mut config = crate::test_harness::test_config();
+    // No model directory, so no disk-space issue
+    config.model_path = "/nonexistent/snapshot-models".to_string();
+    let harness = Harness::with_config(config).await;
+    let admin = harness.admin("snap-admin").await;
+    harness.user("snap-user").await;
+    exec(
+        &harness,
+        "INSERT INTO reservations (id, user_id, status, start_time, end_time, reason, \
+         created_at) VALUES ('snap-old', 'snap-user', 'pending', '2026-01-06T09:00:00', \
+         '2026-01-06T10:00:00', '', '2026-01-05 09:00:00')",
+    )
+    .await;
+
+    let (status, body) = admin.get("/api/admin/issues").await;
+    assert_snapshot("admin_issues_list", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn admin_hygiene_report() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+    exec(
+        &harness,
+        "INSERT INTO model_categories (id, name) VALUES ('snap-empty', 'empty')",
+    )
+    .await;
+
+    let (status, body) = admin.get("/api/admin/hygiene").await;
+    assert_snapshot("admin_hygiene_report", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn admin_config_export() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+
+    let (status, body) = admin.get("/api/admin/export").await;
+    assert_snapshot("admin_config_export", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn admin_host_routes_list() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+
+    let (status, _) = admin
+        .put(
+            "/api/admin/host-routes/docs.example.com",
+            json!({ "target": "redirect:https://docs.example.org", "description": "Docs" }),
+        )
+        .await;
+    assert!(status.is_success());
+    let (status, body) = admin.get("/api/admin/host-routes").await;
+    assert_snapshot("admin_host_routes_list", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn system_prompt_policies_list() {
+    let harness = Harness::new().await;
+    seed_catalog(&harness.state).await;
+    let admin = harness.admin("snap-admin").await;
+
+    let (status, _) = admin
+        .post(
+            "/api/admin/system-prompts",
+            json!({
+                "name": "house style",
+                "prompt": "Answer in British English.",
+                "category_id": "snap-cat",
+            }),
+        )
+        .await;
+    assert!(status.is_success());
+    let (status, body) = admin.get("/api/admin/system-prompts").await;
+    assert_snapshot("admin_system_prompts_list", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn elevation_request_listed() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+    let user = harness.user("snap-user").await;
+
+    let (status, body) = user
+        .post(
+            "/api/user/elevation",
+            json!({ "reason": "Rotate the IdP secret", "duration_minutes": 60 }),
+        )
+        .await;
+    assert_snapshot("user_elevation_requested", status, &body, &[]);
+
+    let (status, body) = user.get("/api/user/elevation").await;
+    assert_snapshot("user_elevations_list", status, &body, &[]);
+
+    let (status, body) = admin.get("/api/admin/elevations?status=pending").await;
+    assert_snapshot("admin_elevations_pending", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn reservation_views() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+    let user = harness.user("snap-user").await;
+    exec(
+        &harness,
+        "INSERT INTO reservations (id, user_id, status, start_time, end_time, reason) \
+         VALUES ('snap-res', 'snap-user', 'pending', '2030-01-07T09:00:00', \
+         '2030-01-07T11:00:00', 'Fine-tuning eval')",
+    )
+    .await;
+
+    let (status, body) = user.get("/api/user/reservations").await;
+    assert_snapshot("user_reservations_list", status, &body, &[]);
+
+    let (status, body) = user.get("/api/user/reservations/calendar").await;
+    assert_snapshot("user_reservations_calendar", status, &body, &[]);
+
+    let (status, body) = user.get("/api/user/reservations/active").await;
+    assert_snapshot("user_reservation_active", status, &body, &[]);
+
+    let (status, body) = admin.get("/api/admin/reservations").await;
+    assert_snapshot("admin_reservations_list", status, &body, &[]);
+
+    let (status, body) = admin.get("/api/admin/reservations/heatmap").await;
+    assert_snapshot("admin_reservations_heatmap", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn reservation_rules_list() {
+    let harness = Harness::new().await;
+    let admin = harness.admin("snap-admin").await;
+    exec(
+        &harness,
+        "INSERT INTO reservation_rules (id, name, max_duration_minutes, hours_start, \
+         hours_end, weekdays, created_by) \
+         VALUES ('snap-rule', 'Office hours', 120, 8, 18, '0,1,2,3,4', 'snap-admin')",
+    )
+    .await;
+
+    let (status, body) = admin.get("/api/admin/reservations/rules").await;
+    assert_snapshot("admin_reservation_rules_list", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn request_log_entry() {
+    let harness = Harness::new().await;
+    seed_catalog(&harness.state).await;
+    let admin = harness.admin("snap-admin").await;
+    harness.user("snap-user").await;
+    let id = crate::api::request_log::record(
+        &harness.state.db,
+        &LoggedRequest {
+            log_level: LogLevel::Full,
+            user_id: "snap-user",
+            token_id: None,
+            model_id: "snap-model",
+            category_id: Some("snap-cat"),
+            endpoint: "/v1/chat/completions",
+            request_body: br#"{"model":"snap-model","messages":[{"role":"user","content":"Hi"}]}"#,
+            response_status: 200,
+            response_body: Some(
+                br#"{"choices":[{"message":{"role":"assistant","content":"Hello"}}]}"#,
+            ),
+            latency_ms: 420,
+            replay_of: None,
+            seed: Some(7),
+            input_tokens: 5,
+            output_tokens: 2,
+            traced: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    let (status, body) = admin.get("/api/admin/requests").await;
+    assert_snapshot("admin_requests_list", status, &body, &[]);
+
+    let (status, body) = admin.get(&format!("/api/admin/requests/{id}")).await;
+    assert_snapshot("admin_request_entry", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn archive_entry_and_blob() {
+    let harness = Harness::new().await;
+    seed_catalog(&harness.state).await;
+    let user = harness.user("snap-user").await;
+    crate::api::archive::record(
+        &harness.state.db,
+        &ArchiveEntry {
+            user_id: "snap-user",
+            model_id: "snap-model",
+            endpoint: "/v1/chat/completions",
+            request_body: br#"{"model":"snap-model","messages":[{"role":"user","content":"Hi"}],"temperature":0.2,"seed":7}"#,
+            response_body: Some(br#"{"choices":[]}"#),
+        },
+    )
+    .await
+    .unwrap();
+
+    let (status, body) = user.get("/api/user/archive").await;
+    assert_snapshot("user_archive_list", status, &body, &[]);
+
+    let hash = body["entries"][0]["prompt_hash"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let (status, body) = user.get(&format!("/api/user/archive/blobs/{hash}")).await;
+    assert_snapshot("user_archive_blob", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn conversation_views() {
+    let harness = Harness::new().await;
+    let user = harness.user("snap-user").await;
+
+    let (status, body) = user
+        .post(
+            "/api/user/conversations",
+            json!({
+                "title": "Release notes",
+                "model": "snap-model",
+                "messages": [
+                    { "role": "user", "content": "Summarize the release", "prompt_tokens": 12 },
+                    {
+                        "role": "assistant",
+                        "content": "Here is the summary.",
+                        "model": "snap-model",
+                        "completion_tokens": 20,
+                    },
+                ],
+            }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = body["conversation"]["id"].as_str().unwrap().to_string();
+
+    let (status, body) = user.get("/api/user/conversations").await;
+    assert_snapshot("user_conversations_list", status, &body, &[]);
+
+    let (status, body) = user.get(&format!("/api/user/conversations/{id}")).await;
+    assert_snapshot("user_conversation", status, &body, &[]);
+
+    let (status, body) = user
+        .get(&format!("/api/user/conversations/{id}/export"))
+        .await;
+    assert_snapshot("user_conversation_export", status, &body, &[]);
+}
+
+#[tokio::test]
+async fn hf_downloads_list() {
+    let harness = Harness::new().await;
+    let user = harness.user("snap-user").await;
+    exec(
+        &harness,
+        "INSERT INTO downloads (id, hf_repo, status, error, progress_bytes, total_bytes) \
+         VALUES ('snap-dl', 'snapshot/big-GGUF', 'failed', 'Connection reset', 1024, 4096)",
+    )
+    .await;
+
+    let (status, body) = user.get("/api/user/hf/downloads").await;
+    assert_snapshot("user_hf_downloads_list", status, &body, &[]);
+}
+
+#[test]
+fn redacts_ids_timestamps_and_secrets() {
+    let body = json!({
+        "id": "6f1c2a5e-8b7d-4c3e-9f0a-1b2c3d4e5f60",
+        "created_at": "2026-10-16 09:00:00",
+        "expires_at": "2026-10-16T09:00:00Z",
+        "start_time": "2026-10-16T09:00:00",
+        "token": "sk-abc",
+        "missing_token": null,
+        "items": [{ "name": "kept", "count": 3 }],
+    });
+    let redacted = redact(&body, &["token", "missing_token"]);
+    assert_eq!(
+        redacted,
+        json!({
+            "id": "[uuid]",
+            "created_at": "[timestamp]",
+            "expires_at": "[timestamp]",
+            "start_time": "[timestamp]",
+            "token": "[redacted]",
+            "missing_token": null,
+            "items": [{ "name": "kept", "count": 3 }],
+        })
+    );
+}
+
+#[test]
+#[should_panic(expected = "missing snapshot never_committed")]
+fn missing_snapshot_fails() {
+    let path = snapshot_path("never_committed");
+    assert!(!path.exists());
+    check_snapshot("never_committed", &path, "{}\n", false);
+}
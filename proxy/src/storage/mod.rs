@@ -0,0 +1,694 @@
+//! Model storage in S3-compatible object storage (`MODEL_STORE_S3`).
+//!
+//! Without `MODEL_STORE_S3`, models live in `MODEL_PATH` only and nothing
+//! here runs. With it, the bucket holds every model and `MODEL_PATH` is a
+//! cache:
+//!
+//! - A downloaded or imported model is uploaded in the background, and
+//!   `models.stored_remote_at` is set once its directory is in the bucket.
+//!   Models registered before the store was configured are uploaded one at
+//!   a time after startup.
+//! - Before a container starts, files missing from the model's directory
+//!   (or with a different size) are fetched from the bucket.
+//! - When `MODEL_CACHE_MAX_GB` is set and `MODEL_PATH` grows past it, the
+//!   least recently started model directories are removed locally, as long
+//!   as every model in them is unloaded and stored remotely.
+//!
+//! Objects are keyed `<prefix>/<model dir>/<file>`, with the model
+//! directory named as under `MODEL_PATH` (`org--repo`). Deleting a model
+//! doesn't delete its objects.
+//!
+//! Independently of the store, the `model_evict_unused_days` setting
+//! deletes the directories of models nobody has started or sent a request
+//! to for that many days ([`Storage::evict_unused`]). Their rows stay, with
+//! `downloaded = 0`; starting one fetches it from the store if it is there,
+//! and downloading the repo again restores it otherwise.
+
+pub mod s3;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use tokio::sync::OwnedMutexGuard;
+use tracing::{error, info, warn};
+
+use crate::api::admin::list_model_files;
+use crate::config::AppConfig;
+use crate::db::Database;
+
+/// The model store (if configured), directory locks and eviction state,
+/// held in `AppState`. Clones share the same state.
+#[derive(Clone, Default)]
+pub struct Storage {
+    store: Option<Arc<ModelStore>>,
+    dir_locks: DirLocks,
+    last_eviction: Arc<Mutex<Option<EvictionRun>>>,
+}
+
+/// One lock per model directory, held while it is fetched, uploaded or
+/// launched so eviction never removes files in use. An entry is removed
+/// once nothing holds or waits for its lock.
+#[derive(Clone, Default)]
+struct DirLocks(Arc<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>>);
+
+/// Holds a model directory's lock; see [`Storage::prepare_launch`].
+pub struct DirGuard {
+    guard: Option<OwnedMutexGuard<()>>,
+    locks: DirLocks,
+    model_dir: String,
+}
+
+impl DirLocks {
+    fn entry(&self, model_dir: &str) -> Arc<tokio::sync::Mutex<()>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(model_dir.to_string())
+            .or_default()
+            .clone()
+    }
+
+    fn guard(&self, guard: OwnedMutexGuard<()>, model_dir: &str) -> DirGuard {
+        DirGuard {
+            guard: Some(guard),
+            locks: self.clone(),
+            model_dir: model_dir.to_string(),
+        }
+    }
+
+    async fn lock(&self, model_dir: &str) -> DirGuard {
+        let guard = self.entry(model_dir).lock_owned().await;
+        self.guard(guard, model_dir)
+    }
+
+    fn try_lock(&self, model_dir: &str) -> Option<DirGuard> {
+        match self.entry(model_dir).try_lock_owned() {
+            Ok(guard) => Some(self.guard(guard, model_dir)),
+            Err(_) => {
+                self.release(model_dir);
+                None
+            }
+        }
+    }
+
+    /// Drop `model_dir`'s entry if the map holds the only reference to it.
+    fn release(&self, model_dir: &str) {
+        let mut locks = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if locks
+            .get(model_dir)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            locks.remove(model_dir);
+        }
+    }
+}
+
+impl Drop for DirGuard {
+    fn drop(&mut self) {
+        self.guard.take();
+        self.locks.release(&self.model_dir);
+    }
+}
+
+pub struct ModelStore {
+    client: s3::S3Client,
+    /// Key prefix, without a trailing slash (may be empty).
+    prefix: String,
+    model_path: PathBuf,
+    cache_max_bytes: u64,
+}
+
+/// Split `s3://bucket/prefix` into bucket and prefix.
+fn parse_url(url: &str) -> Result<(String, String)> {
+    let rest = url
+        .strip_prefix("s3://")
+        .context("MODEL_STORE_S3 must look like s3://bucket/prefix")?;
+    let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+    if bucket.is_empty() {
+        bail!("MODEL_STORE_S3 has no bucket");
+    }
+    Ok((bucket.to_string(), prefix.trim_matches('/').to_string()))
+}
+
+impl ModelStore {
+    /// The store `MODEL_STORE_S3` describes, or `None` when it is unset.
+    pub fn from_config(config: &AppConfig) -> Result<Option<Self>> {
+        let Some(url) = &config.model_store_s3 else {
+            return Ok(None);
+        };
+        let (bucket, prefix) = parse_url(url)?;
+        let (Some(access_key_id), Some(secret_access_key)) =
+            (&config.s3_access_key_id, &config.s3_secret_access_key)
+        else {
+            bail!("MODEL_STORE_S3 needs S3_ACCESS_KEY_ID and S3_SECRET_ACCESS_KEY");
+        };
+        Ok(Some(Self {
+            client: s3::S3Client::new(
+                config.s3_endpoint.as_deref(),
+                &bucket,
+                &config.s3_region,
+                access_key_id,
+                secret_access_key,
+            )?,
+            prefix,
+            model_path: PathBuf::from(&config.model_path),
+            cache_max_bytes: config.model_cache_max_gb * 1024 * 1024 * 1024,
+        }))
+    }
+
+    /// Key prefix for a model directory, with a trailing slash.
+    fn dir_prefix(&self, model_dir: &str) -> String {
+        if self.prefix.is_empty() {
+            format!("{model_dir}/")
+        } else {
+            format!("{}/{model_dir}/", self.prefix)
+        }
+    }
+
+    /// Download the files of `model_dir` that are missing locally or differ
+    /// in size. Returns the bytes downloaded.
+    async fn fetch(&self, model_dir: &str) -> Result<u64> {
+        let prefix = self.dir_prefix(model_dir);
+        let dir = self.model_path.join(model_dir);
+        let mut fetched = 0;
+        for object in self.client.list(&prefix).await? {
+            let Some(rel) = object.key.strip_prefix(&prefix).and_then(safe_relative) else {
+                warn!(key = %object.key, "Skipping object with an unsafe key");
+                continue;
+            };
+            let dest = dir.join(rel);
+            let local = tokio::fs::metadata(&dest).await.ok().map(|m| m.len());
+            if local == Some(object.size) {
+                continue;
+            }
+            fetched += self.client.download(&object.key, &dest).await?;
+        }
+        Ok(fetched)
+    }
+
+    /// Upload the files of `model_dir` the bucket doesn't have at the same
+    /// size. Returns the bytes uploaded.
+    async fn publish(&self, model_dir: &str) -> Result<u64> {
+        let prefix = self.dir_prefix(model_dir);
+        let remote: HashMap<String, u64> = self
+            .client
+            .list(&prefix)
+            .await?
+            .into_iter()
+            .map(|o| (o.key, o.size))
+            .collect();
+        let dir = self.model_path.join(model_dir);
+        let listed = dir.clone();
+        let files = tokio::task::spawn_blocking(move || list_model_files(&listed)).await?;
+        if files.is_empty() {
+            bail!("{} has no files to upload", dir.display());
+        }
+        let mut uploaded = 0;
+        for file in files.iter().filter(|f| !f.path.ends_with(".part")) {
+            let key = format!("{prefix}{}", file.path);
+            if remote.get(&key) == Some(&file.size_bytes) {
+                continue;
+            }
+            uploaded += self.client.upload(&key, &dir.join(&file.path)).await?;
+        }
+        Ok(uploaded)
+    }
+}
+
+/// `rel` as a path that stays inside the model directory.
+fn safe_relative(rel: &str) -> Option<&Path> {
+    let path = Path::new(rel);
+    (!rel.is_empty()
+        && path
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_))))
+    .then_some(path)
+}
+
+impl Storage {
+    /// Set up the store from the config. Fails on an invalid `MODEL_STORE_S3`.
+    pub fn from_config(config: &AppConfig) -> Result<Self> {
+        let store = ModelStore::from_config(config)?;
+        if store.is_some() {
+            info!(url = ?config.model_store_s3, endpoint = ?config.s3_endpoint, cache_max_gb = config.model_cache_max_gb, "Model store enabled");
+        }
+        Ok(Self {
+            store: store.map(Arc::new),
+            ..Default::default()
+        })
+    }
+
+    /// Whether `MODEL_STORE_S3` is configured.
+    pub fn is_remote(&self) -> bool {
+        self.store.is_some()
+    }
+}
+
+/// Why [`prepare_launch`] couldn't make a model's files ready.
+#[derive(Debug)]
+pub enum PrepareError {
+    /// The files were evicted from disk and the model store doesn't have
+    /// them; downloading the repo again restores the model.
+    Evicted,
+    Failed(anyhow::Error),
+}
+
+impl From<sqlx::Error> for PrepareError {
+    fn from(e: sqlx::Error) -> Self {
+        Self::Failed(e.into())
+    }
+}
+
+impl Storage {
+    /// Make sure `model_dir` is complete locally before a container starts,
+    /// and record the start as a use of the model. The returned guard keeps
+    /// eviction away until it is dropped.
+    ///
+    /// With a model store, missing files of a stored model are fetched first;
+    /// a model not yet in the bucket is used as it is, and when the bucket
+    /// can't be reached but local files exist, they're used with a warning.
+    pub async fn prepare_launch(
+        &self,
+        db: &Database,
+        model_id: &str,
+        model_dir: &str,
+    ) -> Result<DirGuard, PrepareError> {
+        let guard = self.dir_locks.lock(model_dir).await;
+        sqlx::query("UPDATE models SET last_used_at = datetime('now') WHERE id = ?")
+            .bind(model_id)
+            .execute(&db.pool)
+            .await?;
+
+        let (downloaded, stored): (bool, Option<String>) =
+            sqlx::query_as("SELECT downloaded, stored_remote_at FROM models WHERE id = ?")
+                .bind(model_id)
+                .fetch_optional(&db.pool)
+                .await?
+                .unwrap_or((true, None));
+        let Some(store) = self.store.as_ref().filter(|_| stored.is_some()) else {
+            return if downloaded {
+                Ok(guard)
+            } else {
+                Err(PrepareError::Evicted)
+            };
+        };
+
+        match store.fetch(model_dir).await {
+            Ok(0) => {}
+            Ok(bytes) => {
+                info!(model = %model_id, dir = %model_dir, bytes, "Fetched model files from the model store")
+            }
+            Err(e) if downloaded && store.model_path.join(model_dir).is_dir() => {
+                warn!(model = %model_id, error = %e, "Model store unreachable; starting from the local cache")
+            }
+            Err(e) => {
+                return Err(PrepareError::Failed(
+                    e.context("Failed to fetch model from the model store"),
+                ))
+            }
+        }
+        if !downloaded {
+            sqlx::query("UPDATE models SET downloaded = 1, evicted_at = NULL WHERE hf_repo = (SELECT hf_repo FROM models WHERE id = ?)")
+                .bind(model_id)
+                .execute(&db.pool)
+                .await?;
+        }
+        self.evict(db).await;
+        Ok(guard)
+    }
+
+    /// Upload a newly registered model in the background.
+    pub fn spawn_publish(&self, db: Database, model_id: String, model_dir: String) {
+        if self.store.is_none() {
+            return;
+        }
+        let storage = self.clone();
+        tokio::spawn(async move {
+            storage.publish_model(&db, &model_id, &model_dir).await;
+        });
+    }
+
+    async fn publish_model(&self, db: &Database, model_id: &str, model_dir: &str) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let guard = self.dir_locks.lock(model_dir).await;
+        match store.publish(model_dir).await {
+            Ok(bytes) => {
+                info!(model = %model_id, dir = %model_dir, bytes, "Uploaded model to the model store");
+                if let Err(e) =
+                    sqlx::query("UPDATE models SET stored_remote_at = datetime('now') WHERE id = ?")
+                        .bind(model_id)
+                        .execute(&db.pool)
+                        .await
+                {
+                    error!(model = %model_id, error = %e, "Failed to record model upload");
+                }
+            }
+            Err(e) => {
+                warn!(model = %model_id, error = %e, "Model upload to the model store failed")
+            }
+        }
+        drop(guard);
+        self.evict(db).await;
+    }
+
+    /// Upload models registered before the store was configured, one at a time.
+    pub fn spawn_backfill(&self, db: Database) {
+        if self.store.is_none() {
+            return;
+        }
+        let storage = self.clone();
+        tokio::spawn(async move {
+            let models: Vec<(String, String)> = match sqlx::query_as(
+                "SELECT id, hf_repo FROM models WHERE stored_remote_at IS NULL ORDER BY created_at",
+            )
+            .fetch_all(&db.pool)
+            .await
+            {
+                Ok(models) => models,
+                Err(e) => {
+                    warn!(error = %e, "Failed to list models to upload");
+                    return;
+                }
+            };
+            for (model_id, hf_repo) in models {
+                storage
+                    .publish_model(&db, &model_id, &hf_repo.replace('/', "--"))
+                    .await;
+            }
+        });
+    }
+
+    /// Remove least recently used model directories from `MODEL_PATH` until it
+    /// is under `MODEL_CACHE_MAX_GB`. Only directories whose models are all
+    /// unloaded and stored remotely are removed, and none that is locked or
+    /// holds a partial download. Returns the directories removed.
+    pub async fn evict(&self, db: &Database) -> Vec<String> {
+        let Some(store) = self.store.as_ref().filter(|s| s.cache_max_bytes > 0) else {
+            return Vec::new();
+        };
+        match self
+            .evict_to(db, &store.model_path, store.cache_max_bytes)
+            .await
+        {
+            Ok(removed) => removed,
+            Err(e) => {
+                warn!(error = %e, "Model cache eviction failed");
+                Vec::new()
+            }
+        }
+    }
+
+    async fn evict_to(
+        &self,
+        db: &Database,
+        model_path: &Path,
+        max_bytes: u64,
+    ) -> Result<Vec<String>> {
+        let root = model_path.to_path_buf();
+        let mut total = tokio::task::spawn_blocking(move || dir_size(&root).0).await?;
+        let mut removed = Vec::new();
+        if total <= max_bytes {
+            return Ok(removed);
+        }
+
+        let candidates: Vec<String> = sqlx::query_scalar(
+            "SELECT hf_repo FROM models GROUP BY hf_repo \
+             HAVING SUM(loaded) = 0 AND SUM(stored_remote_at IS NULL) = 0 \
+             ORDER BY MAX(COALESCE(last_used_at, created_at))",
+        )
+        .fetch_all(&db.pool)
+        .await?;
+        for hf_repo in candidates {
+            if total <= max_bytes {
+                break;
+            }
+            let model_dir = hf_repo.replace('/', "--");
+            let Some(_guard) = self.dir_locks.try_lock(&model_dir) else {
+                continue;
+            };
+            let dir = model_path.join(&model_dir);
+            let listed = dir.clone();
+            let (size, partial) = tokio::task::spawn_blocking(move || dir_size(&listed)).await?;
+            if size == 0 || partial {
+                continue;
+            }
+            tokio::fs::remove_dir_all(&dir)
+                .await
+                .with_context(|| format!("Failed to remove {}", dir.display()))?;
+            total = total.saturating_sub(size);
+            info!(dir = %model_dir, bytes = size, "Evicted model from the local cache");
+            removed.push(model_dir);
+        }
+        if total > max_bytes {
+            warn!(
+                used_bytes = total,
+                max_bytes, "Model cache is over MODEL_CACHE_MAX_GB with nothing left to evict"
+            );
+        }
+        Ok(removed)
+    }
+
+    /// Delete the directories of models unused for `days`: every model in the
+    /// directory unloaded, and none started or sent a request since. The rows
+    /// are kept with `downloaded = 0`. Directories locked by a launch or upload,
+    /// or holding a partial download, are left for the next run.
+    pub async fn evict_unused(
+        &self,
+        db: &Database,
+        model_path: &Path,
+        days: u32,
+    ) -> Result<EvictionRun> {
+        let candidates: Vec<String> = sqlx::query_scalar(
+            "SELECT m.hf_repo FROM models m GROUP BY m.hf_repo \
+             HAVING SUM(m.loaded) = 0 AND SUM(m.downloaded) > 0 \
+             AND MAX(MAX(m.created_at, COALESCE(m.last_used_at, ''), \
+                 COALESCE((SELECT MAX(u.created_at) FROM usage_log u WHERE u.model_id = m.id), ''))) \
+                 < datetime('now', ?)",
+        )
+        .bind(format!("-{days} days"))
+        .fetch_all(&db.pool)
+        .await?;
+
+        let mut run = EvictionRun {
+            ran_at: chrono::Utc::now().to_rfc3339(),
+            ..Default::default()
+        };
+        for hf_repo in candidates {
+            let model_dir = hf_repo.replace('/', "--");
+            let Some(_guard) = self.dir_locks.try_lock(&model_dir) else {
+                continue;
+            };
+            let dir = model_path.join(&model_dir);
+            let listed = dir.clone();
+            let (size, partial) = tokio::task::spawn_blocking(move || dir_size(&listed)).await?;
+            if partial {
+                continue;
+            }
+            if dir.exists() {
+                tokio::fs::remove_dir_all(&dir)
+                    .await
+                    .with_context(|| format!("Failed to remove {}", dir.display()))?;
+            }
+            sqlx::query(
+                "UPDATE models SET downloaded = 0, evicted_at = datetime('now') WHERE hf_repo = ?",
+            )
+            .bind(&hf_repo)
+            .execute(&db.pool)
+            .await?;
+            info!(target: "audit", action = "model.evict", actor = "system", resource = %hf_repo, bytes = size, unused_days = days, "Evicted unused model from disk");
+            run.freed_bytes += size;
+            run.evicted.push(hf_repo);
+        }
+        *self.last_eviction.lock().unwrap_or_else(|e| e.into_inner()) = Some(run.clone());
+        Ok(run)
+    }
+
+    /// Eviction policy and state for `GET /api/admin/system`.
+    pub async fn eviction_status(&self, db: &Database, unused_days: u32) -> serde_json::Value {
+        let evicted_models: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM models WHERE downloaded = 0")
+                .fetch_one(&db.pool)
+                .await
+                .unwrap_or(0);
+        let last_run = self
+            .last_eviction
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        serde_json::json!({
+            "unused_days": unused_days,
+            "evicted_models": evicted_models,
+            "last_run": last_run,
+        })
+    }
+}
+
+fn dir_size(dir: &Path) -> (u64, bool) {
+    let files = list_model_files(dir);
+    let partial = files.iter().any(|f| f.path.ends_with(".part"));
+    (files.iter().map(|f| f.size_bytes).sum(), partial)
+}
+
+/// Outcome of a [`Storage::evict_unused`] run.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EvictionRun {
+    pub ran_at: String,
+    /// Repos whose files were deleted.
+    pub evicted: Vec<String>,
+    pub freed_bytes: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_store_urls() {
+        assert_eq!(
+            parse_url("s3://models/prod/").unwrap(),
+            ("models".to_string(), "prod".to_string())
+        );
+        assert_eq!(
+            parse_url("s3://models").unwrap(),
+            ("models".to_string(), String::new())
+        );
+        assert!(parse_url("https://models").is_err());
+        assert!(parse_url("s3:///prefix").is_err());
+    }
+
+    #[test]
+    fn rejects_keys_leaving_the_model_dir() {
+        assert!(safe_relative("config.json").is_some());
+        assert!(safe_relative("sub/model.gguf").is_some());
+        assert!(safe_relative("").is_none());
+        assert!(safe_relative("../other/model.gguf").is_none());
+        assert!(safe_relative("/etc/passwd").is_none());
+    }
+
+    #[tokio::test]
+    async fn evicts_least_recently_used_remote_models() {
+        let db = Database::test_db().await;
+        let root = std::env::temp_dir().join(format!("se-cache-{}", uuid::Uuid::new_v4()));
+        for (dir, size) in [("org--old", 600), ("org--new", 600), ("org--local", 600)] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join("model.gguf"), vec![0u8; size]).unwrap();
+        }
+        for (id, repo, used, stored) in [
+            ("m1", "org/old", "2026-01-01 00:00:00", Some("2026-01-01")),
+            ("m2", "org/new", "2026-06-01 00:00:00", Some("2026-01-01")),
+            ("m3", "org/local", "2025-01-01 00:00:00", None),
+        ] {
+            sqlx::query(
+                "INSERT INTO models (id, hf_repo, last_used_at, stored_remote_at) VALUES (?, ?, ?, ?)",
+            )
+            .bind(id)
+            .bind(repo)
+            .bind(used)
+            .bind(stored)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        }
+
+        // 1800 bytes cached; evicting the oldest remote model gets under 1500
+        let storage = Storage::default();
+        let removed = storage.evict_to(&db, &root, 1500).await.unwrap();
+        assert_eq!(removed, vec!["org--old".to_string()]);
+        assert!(!root.join("org--old").exists());
+        assert!(root.join("org--new").exists());
+
+        // The local-only model is never evicted, however old
+        let removed = storage.evict_to(&db, &root, 100).await.unwrap();
+        assert_eq!(removed, vec!["org--new".to_string()]);
+        assert!(root.join("org--local").exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn evicts_models_unused_for_days() {
+        let db = Database::test_db().await;
+        let root = std::env::temp_dir().join(format!("se-evict-{}", uuid::Uuid::new_v4()));
+        for dir in ["org--stale", "org--used", "org--loaded"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join("model.gguf"), vec![0u8; 100]).unwrap();
+        }
+        for (id, repo, loaded) in [
+            ("m1", "org/stale", false),
+            ("m2", "org/used", false),
+            ("m3", "org/loaded", true),
+        ] {
+            sqlx::query(
+                "INSERT INTO models (id, hf_repo, loaded, created_at) \
+                 VALUES (?, ?, ?, datetime('now', '-60 days'))",
+            )
+            .bind(id)
+            .bind(repo)
+            .bind(loaded)
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        }
+        // A recent request keeps org/used on disk
+        for stmt in [
+            "INSERT INTO idp_configs (id, name, issuer, client_id, client_secret_enc) \
+             VALUES ('test-idp', 'test', 'https://test', 'client', 'secret')",
+            "INSERT INTO users (id, idp_id, subject, email) VALUES ('u1', 'test-idp', 'u1', 'u1@test.com')",
+            "INSERT INTO usage_log (id, user_id, model_id, created_at) \
+             VALUES ('l1', 'u1', 'm2', datetime('now', '-1 days'))",
+        ] {
+            sqlx::query(stmt).execute(&db.pool).await.unwrap();
+        }
+
+        let storage = Storage::default();
+        let run = storage.evict_unused(&db, &root, 30).await.unwrap();
+        assert_eq!(run.evicted, vec!["org/stale".to_string()]);
+        assert_eq!(run.freed_bytes, 100);
+        assert!(!root.join("org--stale").exists());
+        assert!(root.join("org--used").exists());
+        assert!(root.join("org--loaded").exists());
+
+        let downloaded: bool = sqlx::query_scalar("SELECT downloaded FROM models WHERE id = 'm1'")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert!(!downloaded);
+        let status = storage.eviction_status(&db, 30).await;
+        assert_eq!(status["evicted_models"], 1);
+        assert_eq!(status["last_run"]["freed_bytes"], 100);
+
+        // An evicted model can't start without the store
+        let err = storage
+            .prepare_launch(&db, "m1", "org--stale")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PrepareError::Evicted));
+
+        // Already evicted models aren't evicted again
+        assert!(storage
+            .evict_unused(&db, &root, 30)
+            .await
+            .unwrap()
+            .evicted
+            .is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dir_locks_are_dropped_once_released() {
+        let locks = DirLocks::default();
+        let held = locks.lock("org--a").await;
+        assert!(locks.try_lock("org--a").is_none());
+        assert!(locks.try_lock("org--b").is_some());
+        assert_eq!(locks.0.lock().unwrap().len(), 1);
+
+        drop(held);
+        assert!(locks.0.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+//! Periodic background tasks.
+//!
+//! Every loop that used to be its own `tokio::spawn` (reservation tick,
+//! cleanup, idle unload, ...) is registered here with a name and an
+//! interval. The scheduler runs it, records each run's outcome for
+//! `GET /api/admin/system`, and on shutdown stops scheduling new runs and
+//! waits for any run in progress to finish.
+//!
+//! A run that fails or panics is recorded and logged; the task keeps its
+//! schedule. Runs of one task never overlap.
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures::FutureExt;
+use rand::RngExt;
+use serde::Serialize;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+/// How a task is scheduled.
+#[derive(Debug, Clone)]
+pub struct Task {
+    name: &'static str,
+    interval: Duration,
+    jitter: Duration,
+    immediate: bool,
+}
+
+impl Task {
+    /// Run every `interval`, first after one interval has passed.
+    pub fn every(name: &'static str, interval: Duration) -> Self {
+        Self {
+            name,
+            interval,
+            jitter: Duration::ZERO,
+            immediate: false,
+        }
+    }
+
+    /// Delay each run by a random amount up to `jitter`, so tasks on the
+    /// same interval don't all hit the database at once.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Run once straight away, then every interval.
+    pub fn immediate(mut self) -> Self {
+        self.immediate = true;
+        self
+    }
+}
+
+/// Status of a registered task.
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskStatus {
+    pub interval_secs: u64,
+    pub jitter_secs: u64,
+    pub running: bool,
+    pub runs: u64,
+    pub failures: u64,
+    pub last_started_at: Option<DateTime<Utc>>,
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<u64>,
+    /// Error of the last run, if it failed.
+    pub last_error: Option<String>,
+    pub next_run_at: Option<DateTime<Utc>>,
+    /// The last run succeeded and the next one isn't overdue.
+    pub healthy: bool,
+}
+
+impl TaskStatus {
+    fn new(task: &Task) -> Self {
+        Self {
+            interval_secs: task.interval.as_secs(),
+            jitter_secs: task.jitter.as_secs(),
+            running: false,
+            runs: 0,
+            failures: 0,
+            last_started_at: None,
+            last_finished_at: None,
+            last_duration_ms: None,
+            last_error: None,
+            next_run_at: None,
+            healthy: true,
+        }
+    }
+
+    /// Healthy unless the last run failed, or a run is late by more than a
+    /// full interval (stuck, or the task died).
+    fn refresh_health(&mut self, now: DateTime<Utc>) {
+        let grace = chrono::Duration::seconds(self.interval_secs.max(1) as i64);
+        let overdue = self.next_run_at.is_some_and(|next| now > next + grace)
+            || (self.running
+                && self
+                    .last_started_at
+                    .is_some_and(|started| now > started + grace + grace));
+        self.healthy = self.last_error.is_none() && !overdue;
+    }
+}
+
+struct Inner {
+    tasks: Mutex<BTreeMap<&'static str, TaskStatus>>,
+    handles: Mutex<Vec<JoinHandle<()>>>,
+    shutdown: watch::Sender<bool>,
+}
+
+/// Runs periodic background tasks. Cheap to clone.
+#[derive(Clone)]
+pub struct TaskScheduler {
+    inner: Arc<Inner>,
+}
+
+impl Default for TaskScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskScheduler {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                tasks: Mutex::new(BTreeMap::new()),
+                handles: Mutex::new(Vec::new()),
+                shutdown: watch::channel(false).0,
+            }),
+        }
+    }
+
+    /// Register and start a task. `run` is called once per tick.
+    pub fn spawn<F, Fut>(&self, task: Task, mut run: F)
+    where
+        F: FnMut() -> Fut + Send + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        if *self.inner.shutdown.borrow() {
+            return;
+        }
+        self.inner
+            .tasks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(task.name, TaskStatus::new(&task));
+
+        let scheduler = self.clone();
+        let mut shutdown = self.inner.shutdown.subscribe();
+        let handle = tokio::spawn(async move {
+            let mut delay = if task.immediate {
+                Duration::ZERO
+            } else {
+                task.interval
+            };
+            loop {
+                let wait = delay + random_jitter(task.jitter);
+                scheduler.update(task.name, |s| {
+                    s.next_run_at = Some(
+                        Utc::now()
+                            + chrono::Duration::from_std(wait).unwrap_or(chrono::Duration::zero()),
+                    );
+                });
+                tokio::select! {
+                    _ = tokio::time::sleep(wait) => {}
+                    _ = shutdown.wait_for(|stop| *stop) => break,
+                }
+
+                let started = tokio::time::Instant::now();
+                scheduler.update(task.name, |s| {
+                    s.running = true;
+                    s.next_run_at = None;
+                    s.last_started_at = Some(Utc::now());
+                });
+                let result = match AssertUnwindSafe(run()).catch_unwind().await {
+                    Ok(result) => result,
+                    Err(_) => Err(anyhow::anyhow!("task panicked")),
+                };
+                let elapsed = started.elapsed();
+                if let Err(e) = &result {
+                    warn!(task = task.name, error = %e, "Background task failed");
+                } else {
+                    debug!(
+                        task = task.name,
+                        elapsed_ms = elapsed.as_millis() as u64,
+                        "Background task ran"
+                    );
+                }
+                scheduler.update(task.name, |s| {
+                    s.running = false;
+                    s.runs += 1;
+                    s.last_finished_at = Some(Utc::now());
+                    s.last_duration_ms = Some(elapsed.as_millis() as u64);
+                    s.last_error = result.as_ref().err().map(|e| e.to_string());
+                    if s.last_error.is_some() {
+                        s.failures += 1;
+                    }
+                });
+
+                // Measured from the start of the run, like tokio's interval
+                delay = task.interval.saturating_sub(elapsed);
+            }
+            scheduler.update(task.name, |s| s.next_run_at = None);
+        });
+        self.inner
+            .handles
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(handle);
+    }
+
+    /// Status of every registered task, by name.
+    pub fn status(&self) -> BTreeMap<&'static str, TaskStatus> {
+        let now = Utc::now();
+        let mut tasks = self
+            .inner
+            .tasks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        for status in tasks.values_mut() {
+            status.refresh_health(now);
+        }
+        tasks
+    }
+
+    /// Stop scheduling runs and wait up to `timeout` for runs in progress to
+    /// finish. Tasks still running after that are aborted.
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.inner.shutdown.send_replace(true);
+        let handles: Vec<_> = self
+            .inner
+            .handles
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain(..)
+            .collect();
+        let aborts: Vec<_> = handles.iter().map(|h| h.abort_handle()).collect();
+        if tokio::time::timeout(timeout, futures::future::join_all(handles))
+            .await
+            .is_err()
+        {
+            warn!(
+                timeout_secs = timeout.as_secs(),
+                "Background tasks still running at shutdown; aborting them"
+            );
+            for abort in aborts {
+                abort.abort();
+            }
+        } else {
+            info!("Background tasks stopped");
+        }
+    }
+
+    fn update(&self, name: &'static str, f: impl FnOnce(&mut TaskStatus)) {
+        let mut tasks = self.inner.tasks.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(status) = tasks.get_mut(name) {
+            f(status);
+        }
+    }
+}
+
+fn random_jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(rand::rng().random_range(0..=max.as_millis() as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Wait until `name` has finished `runs` runs.
+    async fn wait_for_runs(tasks: &TaskScheduler, name: &str, runs: u64) -> TaskStatus {
+        for _ in 0..500 {
+            if let Some(status) = tasks.status().get(name).filter(|s| s.runs >= runs) {
+                return status.clone();
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("{name} did not reach {runs} runs");
+    }
+
+    #[tokio::test]
+    async fn failures_and_panics_are_recorded_and_the_task_continues() {
+        let tasks = TaskScheduler::new();
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        tasks.spawn(
+            Task::every("flaky", Duration::from_millis(20)).immediate(),
+            move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    match n {
+                        0 => anyhow::bail!("first run fails"),
+                        1 => panic!("second run panics"),
+                        _ => Ok(()),
+                    }
+                }
+            },
+        );
+
+        let status = wait_for_runs(&tasks, "flaky", 1).await;
+        assert_eq!(status.failures, 1);
+        assert_eq!(status.last_error.as_deref(), Some("first run fails"));
+        assert!(!status.healthy);
+
+        let status = wait_for_runs(&tasks, "flaky", 2).await;
+        assert_eq!(status.failures, 2);
+        assert_eq!(status.last_error.as_deref(), Some("task panicked"));
+
+        let status = wait_for_runs(&tasks, "flaky", 3).await;
+        assert_eq!(status.failures, 2);
+        assert!(status.last_error.is_none());
+        assert!(status.healthy);
+        tasks.shutdown(Duration::from_secs(1)).await;
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_scheduling() {
+        let tasks = TaskScheduler::new();
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        tasks.spawn(
+            Task::every("tick", Duration::from_millis(20)).with_jitter(Duration::from_millis(5)),
+            move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async { Ok(()) }
+            },
+        );
+
+        wait_for_runs(&tasks, "tick", 1).await;
+        tasks.shutdown(Duration::from_secs(1)).await;
+        let after_shutdown = calls.load(Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(calls.load(Ordering::SeqCst), after_shutdown);
+        assert!(tasks.status()["tick"].next_run_at.is_none());
+
+        // Nothing new starts after shutdown
+        tasks.spawn(Task::every("late", Duration::ZERO).immediate(), || async {
+            Ok(())
+        });
+        assert!(!tasks.status().contains_key("late"));
+    }
+}
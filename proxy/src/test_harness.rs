@@ -0,0 +1,348 @@
+//! Shared harness for HTTP API tests.
+//!
+//! [`Harness`] builds the full `/api` router (`api::routes`) over an
+//! in-memory database, with a fake session-auth layer standing in for
+//! `session_auth_middleware`, so a test can call any admin or user endpoint
+//! as a chosen user. [`assert_snapshot`] compares a response against a golden
+//! file in `proxy/tests/snapshots/`, after replacing values that differ
+//! between runs (UUIDs, timestamps, named secret fields) with placeholders.
+//!
+//! A missing snapshot fails the test. Set `UPDATE_SNAPSHOTS=1` to write new
+//! ones or rewrite the ones a deliberate change broke, then review the diff
+//! before committing.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use axum::middleware;
+use axum::Router;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+use crate::auth::rbac::Role;
+use crate::auth::SessionAuth;
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::docker::DockerManager;
+use crate::events::EventBus;
+use crate::proxy::pool::{BackendClients, PoolSettings};
+use crate::scheduler::Scheduler;
+use crate::AppState;
+
+pub(crate) fn test_config() -> AppConfig {
+    AppConfig {
+        listen_addr: "127.0.0.1:0".to_string(),
+        database_url: "sqlite::memory:".to_string(),
+        tls_cert_path: None,
+        tls_key_path: None,
+        bootstrap_user: None,
+        bootstrap_password: None,
+        break_glass: false,
+        docker_host: "unix:///var/run/docker.sock".to_string(),
+        model_path: "/tmp/test-models-admin-tests".to_string(),
+        model_host_path: "/tmp/test-models-admin-tests".to_string(),
+        ui_path: "/tmp/test-ui".to_string(),
+        sites_path: "/tmp/test-sites".to_string(),
+        api_hostname: "localhost".to_string(),
+        chat_hostname: "localhost".to_string(),
+        cookie_domain: None,
+        session_redis_url: None,
+        backend_network: "test-network".to_string(),
+        acme_contact: None,
+        acme_staging: false,
+        webui_backend_url: "http://localhost:8080".to_string(),
+        webui_api_key: None,
+        queue_timeout_secs: 30,
+        secure_cookies: false,
+        db_encryption_key: None,
+        db_encryption_key_old: None,
+        graceful_stop_timeout_secs: 120,
+        backend_pool_max_idle: 32,
+        backend_pool_idle_timeout_secs: 90,
+        backend_connect_timeout_secs: 5,
+        upstream_protocols: Default::default(),
+        sse_keepalive_secs: 15,
+        access_log_path: None,
+        access_log_max_bytes: 100 * 1024 * 1024,
+        access_log_max_files: 5,
+        access_log_webhook_url: None,
+        auth_rate_limit_per_minute: 0,
+        auth_rate_limit_burst: 10,
+        trust_forwarded_for: false,
+        trusted_proxy_hops: 1,
+        oidc_state_binding: "strict".to_string(),
+        trusted_device_ttl_days: 30,
+        jwt_access_tokens: false,
+        jwt_signing_key: None,
+        jwt_ttl_secs: 300,
+        token_cache_ttl_secs: 0,
+        token_cache_max_entries: 10_000,
+        model_name_normalize: true,
+        model_name_strip_prefixes: vec!["openai/".to_string()],
+        model_name_suggestions: true,
+        usage_webhook_url: None,
+        usage_webhook_secret: None,
+        usage_webhook_max_retries: 5,
+        event_webhook_url: None,
+        event_webhook_secret: None,
+        request_log: false,
+        autoload_timeout_secs: 300,
+        watchdog_interval_secs: 30,
+        watchdog_max_restarts: 3,
+        backend_warmup: false,
+        prompt_archive: false,
+        prompt_archive_retention_days: 90,
+        prompt_dedup_stats: false,
+        two_person_approval: false,
+        approval_window_minutes: 60,
+        sandbox_enabled: false,
+        sandbox_image: "python:3.12-alpine".to_string(),
+        sandbox_memory_mb: 256,
+        sandbox_cpus: 1.0,
+        sandbox_timeout_secs: 10,
+        sandbox_max_concurrent: 4,
+        backend_tls: false,
+        backend_tls_dir: "/config/backend-tls".to_string(),
+        backend_tls_host_path: "/config/backend-tls".to_string(),
+        host_memory_ceiling_percent: 0,
+        memory_critical_percent: 95,
+        memory_pressure_admission: false,
+        download_background_mb_per_sec: 0,
+        hf_download_connections: 1,
+        hf_download_chunk_mb: 64,
+        model_trash_hours: 24,
+        model_trash_max_gb: 200,
+        model_import_path: None,
+        model_store_s3: None,
+        s3_endpoint: None,
+        s3_region: "us-east-1".to_string(),
+        s3_access_key_id: None,
+        s3_secret_access_key: None,
+        model_cache_max_gb: 0,
+        gpu_probe: false,
+        auto_categorize: "off".to_string(),
+        request_log_retention_days: 30,
+        signed_url_key: None,
+        signed_url_max_ttl_secs: 3600,
+    }
+}
+
+pub(crate) async fn test_app_state_with(config: AppConfig) -> Arc<AppState> {
+    let db = Database::test_db().await;
+    Arc::new(AppState {
+        backends: BackendClients::new(PoolSettings::from_config(&config)),
+        config,
+        sessions: crate::auth::sessions::Sessions::sqlite(&db),
+        db,
+        docker: DockerManager::test_dummy(),
+        scheduler: Scheduler::new(),
+        events: EventBus::new(),
+        jwt: None,
+        token_cache: crate::auth::token_cache::TokenCache::new(std::time::Duration::ZERO, 0),
+        signed_urls: crate::auth::signed_url::UrlSigner::new(b"test".to_vec(), 3600),
+        tasks: crate::tasks::TaskScheduler::new(),
+        flags: crate::feature_flags::FeatureFlags::default(),
+        host_routes: crate::host_routes::HostRoutes::default(),
+        watchdog: crate::watchdog::Watchdog::default(),
+        warmups: crate::api::warmup::Warmups::default(),
+        download_progress: crate::api::hf::DownloadProgress::default(),
+        replacing: crate::api::container_replace::Replacing::default(),
+        effective_config: None,
+        storage: crate::storage::Storage::default(),
+        autoloads: crate::api::common::Autoloads::default(),
+        ui_integrity: crate::ui_integrity::UiIntegrity::default(),
+    })
+}
+
+/// Insert the IdP and user rows a session user needs for foreign keys.
+pub(crate) async fn ensure_user(pool: &sqlx::Pool<sqlx::Sqlite>, user_id: &str) {
+    sqlx::query(
+        "INSERT OR IGNORE INTO idp_configs (id, name, issuer, client_id, client_secret_enc) \
+         VALUES ('test-idp', 'test', 'https://test', 'client', 'secret')",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO users (id, idp_id, subject, email) \
+         VALUES (?, 'test-idp', ?, ?)",
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .bind(format!("{}@test.com", user_id))
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+/// The full `/api` router over a fresh in-memory database.
+pub(crate) struct Harness {
+    pub state: Arc<AppState>,
+}
+
+impl Harness {
+    pub async fn new() -> Self {
+        Self::with_config(test_config()).await
+    }
+
+    /// A harness over `config` instead of [`test_config`].
+    pub async fn with_config(config: AppConfig) -> Self {
+        Self {
+            state: test_app_state_with(config).await,
+        }
+    }
+
+    /// A full admin session for `user_id`.
+    pub async fn admin(&self, user_id: &str) -> Client {
+        self.session(user_id, true, &[]).await
+    }
+
+    /// A session without admin rights.
+    pub async fn user(&self, user_id: &str) -> Client {
+        self.session(user_id, false, &[]).await
+    }
+
+    /// A session with the given admin flag and scoped roles. Creates the
+    /// user row if needed.
+    pub async fn session(&self, user_id: &str, is_admin: bool, roles: &[Role]) -> Client {
+        ensure_user(&self.state.db.pool, user_id).await;
+        let user = user_id.to_string();
+        let roles = roles.to_vec();
+        let auth_layer = middleware::from_fn(
+            move |mut req: axum::extract::Request, next: axum::middleware::Next| {
+                let user_id = user.clone();
+                let roles = roles.clone();
+                async move {
+                    req.extensions_mut().insert(SessionAuth {
+                        user_id,
+                        is_admin,
+                        roles,
+                        email: None,
+                        display_name: None,
+                    });
+                    Ok::<_, std::convert::Infallible>(next.run(req).await)
+                }
+            },
+        );
+        Client {
+            router: Router::new()
+                .nest("/api", crate::api::routes(self.state.clone()))
+                .layer(auth_layer),
+        }
+    }
+}
+
+/// Sends requests to the harness router as one session.
+pub(crate) struct Client {
+    router: Router,
+}
+
+impl Client {
+    pub async fn get(&self, uri: &str) -> (StatusCode, Value) {
+        self.send("GET", uri, None).await
+    }
+
+    pub async fn post(&self, uri: &str, body: Value) -> (StatusCode, Value) {
+        self.send("POST", uri, Some(body)).await
+    }
+
+    pub async fn put(&self, uri: &str, body: Value) -> (StatusCode, Value) {
+        self.send("PUT", uri, Some(body)).await
+    }
+
+    pub async fn delete(&self, uri: &str) -> (StatusCode, Value) {
+        self.send("DELETE", uri, None).await
+    }
+
+    /// Send a request; a body that isn't JSON comes back as `Value::Null`.
+    pub async fn send(&self, method: &str, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+        let builder = Request::builder().method(method).uri(uri);
+        let req = match body {
+            Some(body) => builder
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string())),
+            None => builder.body(Body::empty()),
+        }
+        .unwrap();
+
+        let resp = self.router.clone().oneshot(req).await.unwrap();
+        let status = resp.status();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_slice(&body).unwrap_or(Value::Null);
+        (status, json)
+    }
+}
+
+pub(crate) fn snapshot_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.json"))
+}
+
+/// Replace values that change between runs. Fields named in `secret_keys`
+/// become `"[redacted]"`, UUID strings `"[uuid]"`, and RFC 3339, SQLite
+/// (`YYYY-MM-DD HH:MM:SS`) or zone-less ISO (`YYYY-MM-DDTHH:MM:SS`)
+/// timestamps `"[timestamp]"`.
+pub(crate) fn redact(value: &Value, secret_keys: &[&str]) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| {
+                    let v = if secret_keys.contains(&k.as_str()) && !v.is_null() {
+                        json!("[redacted]")
+                    } else {
+                        redact(v, secret_keys)
+                    };
+                    (k.clone(), v)
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.iter().map(|v| redact(v, secret_keys)).collect()),
+        Value::String(s) if uuid::Uuid::try_parse(s).is_ok() => json!("[uuid]"),
+        Value::String(s)
+            if chrono::DateTime::parse_from_rfc3339(s).is_ok()
+                || chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").is_ok()
+                || chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").is_ok() =>
+        {
+            json!("[timestamp]")
+        }
+        other => other.clone(),
+    }
+}
+
+/// Compare a response with the golden file `tests/snapshots/<name>.json`.
+/// `secret_keys` names fields to redact (see [`redact`]).
+pub(crate) fn assert_snapshot(name: &str, status: StatusCode, body: &Value, secret_keys: &[&str]) {
+    let actual = json!({ "status": status.as_u16(), "body": redact(body, secret_keys) });
+    let actual = format!("{}\n", serde_json::to_string_pretty(&actual).unwrap());
+    let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some_and(|v| v != "0");
+    check_snapshot(name, &snapshot_path(name), &actual, update);
+}
+
+/// Compare `actual` with the golden file at `path`, or write it there when
+/// `update` is set. Never writes otherwise, so a snapshot that was not
+/// committed fails instead of passing.
+pub(crate) fn check_snapshot(name: &str, path: &Path, actual: &str, update: bool) {
+    if update {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, actual).unwrap();
+        return;
+    }
+    let Ok(expected) = std::fs::read_to_string(path) else {
+        panic!(
+            "missing snapshot {name} at {}, rerun with UPDATE_SNAPSHOTS=1",
+            path.display()
+        );
+    };
+    assert!(
+        expected == actual,
+        "snapshot {name} does not match {}\n--- expected\n{expected}--- actual\n{actual}\
+         Rerun with UPDATE_SNAPSHOTS=1 if the change is intended.",
+        path.display()
+    );
+}
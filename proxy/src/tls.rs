@@ -19,7 +19,7 @@ pub async fn serve_tls(app: Router, addr: SocketAddr, config: &AppConfig) -> Res
         .context("Failed to load TLS certificates")?;
 
     axum_server::bind_rustls(addr, tls_config)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .context("HTTPS server error")?;
 
@@ -55,7 +55,7 @@ pub async fn serve_acme(
 
     axum_server::bind(addr)
         .acceptor(acceptor)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .context("ACME HTTPS server error")?;
 
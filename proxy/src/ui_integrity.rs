@@ -0,0 +1,280 @@
+//! UI bundle integrity verification.
+//!
+//! Complements the CSP hashes computed from `index.html`: at startup every
+//! JS/CSS file under `UI_PATH` is hashed into a manifest (SHA-256 for change
+//! detection, SHA-384 SRI strings for `integrity=` attributes). A background
+//! task re-hashes the bundle periodically. The bundle is immutable once built,
+//! so any file modified, added or removed while the server runs is logged
+//! loudly as possible tampering and reported by `GET /api/admin/ui/manifest`.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Context;
+use base64::Engine;
+use serde::Serialize;
+use sha2::{Digest, Sha256, Sha384};
+use tracing::{error, info, warn};
+
+use crate::tasks::{Task, TaskScheduler};
+
+/// Hash of one served asset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AssetEntry {
+    /// Path relative to the UI root, `/`-separated.
+    pub path: String,
+    pub size: u64,
+    /// Hex SHA-256 of the file contents.
+    pub sha256: String,
+    /// Subresource Integrity value (`sha384-<base64>`).
+    pub integrity: String,
+}
+
+/// Asset hashes captured at startup.
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetManifest {
+    pub ui_path: String,
+    pub generated_at: String,
+    pub assets: Vec<AssetEntry>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Modified,
+    Added,
+    Removed,
+}
+
+/// A deviation from the startup manifest.
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub detected_at: String,
+}
+
+/// The startup manifest and the changes found since, held in `AppState`.
+/// Cheap to clone; clones share the recorded changes.
+#[derive(Debug, Clone, Default)]
+pub struct UiIntegrity {
+    manifest: Option<Arc<AssetManifest>>,
+    changes: Arc<Mutex<Vec<AssetChange>>>,
+}
+
+impl UiIntegrity {
+    /// Hash the bundle under `ui_path` into the startup manifest.
+    pub fn new(ui_path: &str) -> Self {
+        let assets = hash_assets(Path::new(ui_path));
+        if assets.is_empty() {
+            warn!(path = %ui_path, "No JS/CSS assets found — UI integrity verification disabled");
+        } else {
+            info!(count = assets.len(), "UI asset manifest computed");
+        }
+        Self {
+            manifest: Some(Arc::new(AssetManifest {
+                ui_path: ui_path.to_string(),
+                generated_at: chrono::Utc::now().to_rfc3339(),
+                assets,
+            })),
+            changes: Arc::default(),
+        }
+    }
+
+    /// Startup manifest, unless built with `default()`.
+    pub fn manifest(&self) -> Option<&AssetManifest> {
+        self.manifest.as_deref()
+    }
+
+    /// Changes detected since startup, oldest first.
+    pub fn changes(&self) -> Vec<AssetChange> {
+        self.changes.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    /// Periodically re-hash the bundle and report drift from the manifest.
+    pub fn spawn_watcher(&self, tasks: &TaskScheduler, interval: Duration) {
+        let Some(manifest) = self.manifest.clone() else {
+            return;
+        };
+        if manifest.assets.is_empty() {
+            return;
+        }
+
+        let integrity = self.clone();
+        tasks.spawn(Task::every("ui_integrity", interval), move || {
+            let integrity = integrity.clone();
+            let manifest = manifest.clone();
+            async move {
+                let ui_path = manifest.ui_path.clone();
+                let current = tokio::task::spawn_blocking(move || hash_assets(Path::new(&ui_path)))
+                    .await
+                    .context("UI integrity check failed to run")?;
+                integrity.record_changes(diff(&manifest.assets, &current));
+                Ok(())
+            }
+        });
+    }
+
+    fn record_changes(&self, found: Vec<(String, ChangeKind)>) {
+        let Ok(mut changes) = self.changes.lock() else {
+            return;
+        };
+        for (path, kind) in found {
+            if changes.iter().any(|c| c.path == path && c.kind == kind) {
+                continue;
+            }
+            error!(
+                target: "audit",
+                action = "ui.asset_changed",
+                path = %path,
+                change = ?kind,
+                "UI asset changed on disk while running — possible tampering"
+            );
+            changes.push(AssetChange {
+                path,
+                kind,
+                detected_at: chrono::Utc::now().to_rfc3339(),
+            });
+        }
+    }
+}
+
+/// Compare the current bundle against the baseline. Both must be sorted by path.
+pub fn diff(baseline: &[AssetEntry], current: &[AssetEntry]) -> Vec<(String, ChangeKind)> {
+    let mut out = Vec::new();
+    for entry in baseline {
+        match current.iter().find(|c| c.path == entry.path) {
+            Some(c) if c.sha256 != entry.sha256 => {
+                out.push((entry.path.clone(), ChangeKind::Modified))
+            }
+            Some(_) => {}
+            None => out.push((entry.path.clone(), ChangeKind::Removed)),
+        }
+    }
+    for entry in current {
+        if !baseline.iter().any(|b| b.path == entry.path) {
+            out.push((entry.path.clone(), ChangeKind::Added));
+        }
+    }
+    out
+}
+
+/// Hash every `.js` / `.css` file under `root`, sorted by relative path.
+pub fn hash_assets(root: &Path) -> Vec<AssetEntry> {
+    let mut assets = Vec::new();
+    collect(root, root, &mut assets);
+    assets.sort_by(|a, b| a.path.cmp(&b.path));
+    assets
+}
+
+fn collect(root: &Path, dir: &Path, out: &mut Vec<AssetEntry>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            collect(root, &path, out);
+            continue;
+        }
+        let is_asset = matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("js" | "mjs" | "css")
+        );
+        if !file_type.is_file() || !is_asset {
+            continue;
+        }
+        let Ok(bytes) = std::fs::read(&path) else {
+            continue;
+        };
+        let relative = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        out.push(AssetEntry {
+            path: relative,
+            size: bytes.len() as u64,
+            sha256: hex::encode(Sha256::digest(&bytes)),
+            integrity: format!(
+                "sha384-{}",
+                base64::engine::general_purpose::STANDARD.encode(Sha384::digest(&bytes))
+            ),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_bundle() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("ui-bundle-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("assets")).unwrap();
+        std::fs::write(dir.join("index.html"), "<html></html>").unwrap();
+        std::fs::write(dir.join("assets/app.js"), "console.log(1)").unwrap();
+        std::fs::write(dir.join("assets/app.css"), "body{}").unwrap();
+        dir
+    }
+
+    #[test]
+    fn hashes_only_js_and_css() {
+        let dir = temp_bundle();
+        let assets = hash_assets(&dir);
+        let paths: Vec<&str> = assets.iter().map(|a| a.path.as_str()).collect();
+        assert_eq!(paths, vec!["assets/app.css", "assets/app.js"]);
+        assert!(assets[1].integrity.starts_with("sha384-"));
+        assert_eq!(assets[1].size, 14);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn diff_detects_modified_added_removed() {
+        let dir = temp_bundle();
+        let baseline = hash_assets(&dir);
+
+        std::fs::write(dir.join("assets/app.js"), "console.log(2)").unwrap();
+        std::fs::remove_file(dir.join("assets/app.css")).unwrap();
+        std::fs::write(dir.join("assets/evil.js"), "steal()").unwrap();
+
+        let changes = diff(&baseline, &hash_assets(&dir));
+        assert_eq!(
+            changes,
+            vec![
+                ("assets/app.css".to_string(), ChangeKind::Removed),
+                ("assets/app.js".to_string(), ChangeKind::Modified),
+                ("assets/evil.js".to_string(), ChangeKind::Added),
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn changes_are_recorded_once_per_instance() {
+        let dir = temp_bundle();
+        let integrity = UiIntegrity::new(dir.to_str().unwrap());
+        assert_eq!(integrity.manifest().unwrap().assets.len(), 2);
+
+        let found = vec![("assets/app.js".to_string(), ChangeKind::Modified)];
+        integrity.clone().record_changes(found.clone());
+        integrity.record_changes(found);
+        assert_eq!(integrity.changes().len(), 1);
+        assert!(UiIntegrity::new(dir.to_str().unwrap()).changes().is_empty());
+        assert!(UiIntegrity::default().manifest().is_none());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unchanged_bundle_has_no_diff() {
+        let dir = temp_bundle();
+        let baseline = hash_assets(&dir);
+        assert!(diff(&baseline, &hash_assets(&dir)).is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
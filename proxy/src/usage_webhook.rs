@@ -0,0 +1,411 @@
+//! Hourly usage deltas pushed to an external billing endpoint.
+//!
+//! Once an hour closes, the requests and tokens logged in `usage_log` during
+//! that hour are summed per user, token, model and category and POSTed as one
+//! JSON document to `USAGE_WEBHOOK_URL`. Hours are delivered oldest first and
+//! recorded in `usage_webhook_deliveries`. An hour that still fails after
+//! retries is tried again on the next tick, so a receiver outage delays deltas
+//! rather than losing them (up to [`MAX_CATCHUP_HOURS`]). Empty hours are sent
+//! too, so receivers can tell "no usage" from "not delivered".
+//!
+//! Receivers should deduplicate on `delivery_id`, which is stable across
+//! retries. With `USAGE_WEBHOOK_SECRET` set, each POST carries
+//! `X-Sovereign-Signature: sha256=<hex>`, an HMAC-SHA256 over
+//! `<X-Sovereign-Timestamp>.<body>`.
+
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, DurationRound, SecondsFormat, TimeDelta, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use tracing::{info, warn};
+
+use crate::config::AppConfig;
+use crate::db::Database;
+use crate::tasks::{Task, TaskScheduler};
+
+/// Payload schema identifier. Bump the version on any incompatible change.
+pub const SCHEMA: &str = "sovereign-engine.usage-delta.v1";
+
+/// How often closed hours are checked for delivery.
+const TICK: Duration = Duration::from_secs(300);
+/// Undelivered hours older than this are skipped.
+const MAX_CATCHUP_HOURS: i64 = 168;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// `usage_log.created_at` format (SQLite `datetime('now')`).
+const SQLITE_DATETIME: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Usage for one user/token/model/category combination within an hour.
+#[derive(Debug, Clone, PartialEq, Serialize, sqlx::FromRow)]
+pub struct UsageDelta {
+    pub user_id: String,
+    pub user_email: Option<String>,
+    pub token_id: Option<String>,
+    pub token_name: Option<String>,
+    pub model_id: String,
+    pub category_id: Option<String>,
+    pub requests: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+}
+
+/// One webhook delivery: every delta for `[period_start, period_end)`.
+#[derive(Debug, Serialize)]
+pub struct UsageDeltaPayload {
+    pub schema: &'static str,
+    pub delivery_id: String,
+    pub period_start: String,
+    pub period_end: String,
+    pub deltas: Vec<UsageDelta>,
+}
+
+#[derive(Clone)]
+pub struct UsageWebhook {
+    url: String,
+    secret: Option<String>,
+    max_retries: u32,
+    client: reqwest::Client,
+}
+
+impl UsageWebhook {
+    /// Register the hourly delivery task if a webhook URL is configured.
+    pub fn spawn(config: &AppConfig, db: Database, tasks: &TaskScheduler) {
+        let Some(url) = config.usage_webhook_url.clone() else {
+            return;
+        };
+        if config.usage_webhook_secret.is_none() {
+            warn!("USAGE_WEBHOOK_SECRET not set — usage webhooks are sent unsigned");
+        }
+        info!(url = %url, "Usage webhook enabled");
+
+        let hook = Self {
+            url,
+            secret: config.usage_webhook_secret.clone(),
+            max_retries: config.usage_webhook_max_retries,
+            client: reqwest::Client::new(),
+        };
+        tasks.spawn(Task::every("usage_webhook", TICK).immediate(), move || {
+            let hook = hook.clone();
+            let db = db.clone();
+            async move { hook.deliver_pending(&db, Utc::now()).await }
+        });
+    }
+
+    /// Deliver every closed hour not yet delivered, oldest first. Stops at the
+    /// first hour that can't be delivered so hours arrive in order.
+    async fn deliver_pending(&self, db: &Database, now: DateTime<Utc>) -> Result<()> {
+        let last = last_delivered(db).await?;
+        for hour in pending_hours(last, now) {
+            let payload = build_payload(db, hour).await?;
+            let (attempts, result) = self.post_with_retries(&payload).await;
+            record_attempt(
+                db,
+                &payload.period_start,
+                attempts,
+                result.as_ref().err().map(String::as_str),
+            )
+            .await?;
+            match result {
+                Ok(()) => info!(
+                    period_start = %payload.period_start,
+                    deltas = payload.deltas.len(),
+                    "Usage delta delivered"
+                ),
+                Err(e) => {
+                    warn!(
+                        period_start = %payload.period_start,
+                        attempts,
+                        error = %e,
+                        "Usage webhook unreachable; will retry next tick"
+                    );
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// POST with exponential backoff. Returns the attempts made and the outcome.
+    async fn post_with_retries(&self, payload: &UsageDeltaPayload) -> (u32, Result<(), String>) {
+        let body = match serde_json::to_string(payload) {
+            Ok(b) => b,
+            Err(e) => return (0, Err(format!("serialize: {e}"))),
+        };
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let result = self.post(&payload.delivery_id, &body).await;
+            if result.is_ok() || attempt > self.max_retries {
+                return (attempt, result);
+            }
+            tokio::time::sleep(backoff(attempt)).await;
+        }
+    }
+
+    async fn post(&self, delivery_id: &str, body: &str) -> Result<(), String> {
+        let timestamp = Utc::now().timestamp().to_string();
+        let mut req = self
+            .client
+            .post(&self.url)
+            .header("content-type", "application/json")
+            .header("x-sovereign-delivery", delivery_id)
+            .header("x-sovereign-timestamp", &timestamp)
+            .timeout(REQUEST_TIMEOUT);
+        if let Some(secret) = &self.secret {
+            req = req.header("x-sovereign-signature", sign(secret, &timestamp, body));
+        }
+
+        match req.body(body.to_string()).send().await {
+            Ok(resp) if resp.status().is_success() => Ok(()),
+            Ok(resp) => Err(format!("HTTP {}", resp.status())),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// `sha256=<hex HMAC-SHA256 of "<timestamp>.<body>">`.
+pub(crate) fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// 2s, 4s, 8s … capped at one minute.
+pub(crate) fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs((1u64 << attempt.min(6)).min(60))
+}
+
+/// Closed hours still to deliver. Without a delivery history only the most
+/// recent closed hour is sent — past usage isn't backfilled.
+fn pending_hours(last_delivered: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Vec<DateTime<Utc>> {
+    let Ok(current) = now.duration_trunc(TimeDelta::hours(1)) else {
+        return Vec::new();
+    };
+    let oldest = current - TimeDelta::hours(MAX_CATCHUP_HOURS);
+    let mut hour = last_delivered
+        .map(|h| h + TimeDelta::hours(1))
+        .unwrap_or(current - TimeDelta::hours(1))
+        .max(oldest);
+
+    let mut hours = Vec::new();
+    while hour < current {
+        hours.push(hour);
+        hour += TimeDelta::hours(1);
+    }
+    hours
+}
+
+async fn last_delivered(db: &Database) -> Result<Option<DateTime<Utc>>> {
+    let (last,): (Option<String>,) = sqlx::query_as(
+        "SELECT MAX(period_start) FROM usage_webhook_deliveries WHERE delivered_at IS NOT NULL",
+    )
+    .fetch_one(&db.pool)
+    .await
+    .context("Failed to read usage webhook deliveries")?;
+
+    Ok(last
+        .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+        .map(|d| d.with_timezone(&Utc)))
+}
+
+async fn build_payload(db: &Database, hour: DateTime<Utc>) -> Result<UsageDeltaPayload> {
+    let end = hour + TimeDelta::hours(1);
+    let deltas = sqlx::query_as::<_, UsageDelta>(
+        r#"
+        SELECT ul.user_id, u.email AS user_email, ul.token_id, t.name AS token_name,
+               ul.model_id, ul.category_id,
+               COUNT(*) AS requests,
+               COALESCE(SUM(ul.input_tokens), 0) AS input_tokens,
+               COALESCE(SUM(ul.output_tokens), 0) AS output_tokens
+        FROM usage_log ul
+        LEFT JOIN users u ON u.id = ul.user_id
+        LEFT JOIN tokens t ON t.id = ul.token_id
+        WHERE ul.created_at >= ? AND ul.created_at < ? AND ul.replay_of IS NULL
+        GROUP BY ul.user_id, ul.token_id, ul.model_id, ul.category_id
+        ORDER BY ul.user_id, ul.token_id, ul.model_id, ul.category_id
+        "#,
+    )
+    .bind(hour.format(SQLITE_DATETIME).to_string())
+    .bind(end.format(SQLITE_DATETIME).to_string())
+    .fetch_all(&db.pool)
+    .await
+    .context("Failed to aggregate usage deltas")?;
+
+    let period_start = hour.to_rfc3339_opts(SecondsFormat::Secs, true);
+    Ok(UsageDeltaPayload {
+        schema: SCHEMA,
+        delivery_id: format!("usage-{period_start}"),
+        period_start,
+        period_end: end.to_rfc3339_opts(SecondsFormat::Secs, true),
+        deltas,
+    })
+}
+
+async fn record_attempt(
+    db: &Database,
+    period_start: &str,
+    attempts: u32,
+    error: Option<&str>,
+) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO usage_webhook_deliveries (period_start, attempts, last_error, delivered_at)
+         VALUES (?, ?, ?, CASE WHEN ? IS NULL THEN datetime('now') END)
+         ON CONFLICT(period_start) DO UPDATE SET
+             attempts = attempts + excluded.attempts,
+             last_error = excluded.last_error,
+             delivered_at = excluded.delivered_at",
+    )
+    .bind(period_start)
+    .bind(attempts)
+    .bind(error)
+    .bind(error)
+    .execute(&db.pool)
+    .await
+    .context("Failed to record usage webhook delivery")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn first_run_sends_only_the_last_closed_hour() {
+        let hours = pending_hours(None, at("2026-10-16T13:25:00Z"));
+        assert_eq!(hours, vec![at("2026-10-16T12:00:00Z")]);
+    }
+
+    #[test]
+    fn catches_up_every_hour_since_last_delivery() {
+        let hours = pending_hours(Some(at("2026-10-16T09:00:00Z")), at("2026-10-16T13:00:00Z"));
+        assert_eq!(
+            hours,
+            vec![
+                at("2026-10-16T10:00:00Z"),
+                at("2026-10-16T11:00:00Z"),
+                at("2026-10-16T12:00:00Z"),
+            ]
+        );
+        assert!(
+            pending_hours(Some(at("2026-10-16T12:00:00Z")), at("2026-10-16T13:59:00Z")).is_empty()
+        );
+    }
+
+    #[test]
+    fn catch_up_is_bounded() {
+        let hours = pending_hours(Some(at("2026-01-01T00:00:00Z")), at("2026-10-16T13:00:00Z"));
+        assert_eq!(hours.len() as i64, MAX_CATCHUP_HOURS);
+        assert_eq!(hours.last(), Some(&at("2026-10-16T12:00:00Z")));
+    }
+
+    #[test]
+    fn signature_covers_timestamp_and_body() {
+        let sig = sign("secret", "1700000000", r#"{"a":1}"#);
+        assert!(sig.starts_with("sha256="));
+        assert_eq!(sig.len(), "sha256=".len() + 64);
+        assert_ne!(sig, sign("secret", "1700000001", r#"{"a":1}"#));
+        assert_ne!(sig, sign("other", "1700000000", r#"{"a":1}"#));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert_eq!(backoff(1), Duration::from_secs(2));
+        assert_eq!(backoff(3), Duration::from_secs(8));
+        assert_eq!(backoff(20), Duration::from_secs(60));
+    }
+
+    async fn insert_usage(
+        db: &Database,
+        user_id: &str,
+        model_id: &str,
+        tokens: i64,
+        created_at: &str,
+    ) {
+        sqlx::query(
+            "INSERT OR IGNORE INTO idp_configs (id, name, issuer, client_id, client_secret_enc)
+             VALUES ('test-idp', 'test', 'https://test', 'client', 'secret')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT OR IGNORE INTO users (id, idp_id, subject, email) VALUES (?, 'test-idp', ?, ?)",
+        )
+        .bind(user_id)
+        .bind(user_id)
+        .bind(format!("{user_id}@test.com"))
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO usage_log (id, user_id, model_id, input_tokens, output_tokens, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(user_id)
+        .bind(model_id)
+        .bind(tokens)
+        .bind(tokens * 2)
+        .bind(created_at)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn payload_sums_usage_within_the_hour() {
+        let db = Database::test_db().await;
+        insert_usage(&db, "alice", "m1", 10, "2026-10-16 12:00:00").await;
+        insert_usage(&db, "alice", "m1", 5, "2026-10-16 12:59:59").await;
+        insert_usage(&db, "bob", "m2", 7, "2026-10-16 12:30:00").await;
+        insert_usage(&db, "alice", "m1", 100, "2026-10-16 13:00:00").await; // next hour
+
+        let payload = build_payload(&db, at("2026-10-16T12:00:00Z"))
+            .await
+            .unwrap();
+        assert_eq!(payload.schema, SCHEMA);
+        assert_eq!(payload.delivery_id, "usage-2026-10-16T12:00:00Z");
+        assert_eq!(payload.period_end, "2026-10-16T13:00:00Z");
+        assert_eq!(payload.deltas.len(), 2);
+
+        let alice = &payload.deltas[0];
+        assert_eq!(alice.user_email.as_deref(), Some("alice@test.com"));
+        assert_eq!(
+            (alice.requests, alice.input_tokens, alice.output_tokens),
+            (2, 15, 30)
+        );
+        assert_eq!(payload.deltas[1].user_id, "bob");
+    }
+
+    #[tokio::test]
+    async fn failed_hours_stay_pending_until_delivered() {
+        let db = Database::test_db().await;
+        let hour = "2026-10-16T12:00:00Z";
+
+        record_attempt(&db, hour, 3, Some("HTTP 503"))
+            .await
+            .unwrap();
+        assert_eq!(last_delivered(&db).await.unwrap(), None);
+
+        record_attempt(&db, hour, 1, None).await.unwrap();
+        assert_eq!(last_delivered(&db).await.unwrap(), Some(at(hour)));
+        let (attempts, error): (i64, Option<String>) = sqlx::query_as(
+            "SELECT attempts, last_error FROM usage_webhook_deliveries WHERE period_start = ?",
+        )
+        .bind(hour)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!((attempts, error), (4, None));
+    }
+}
@@ -0,0 +1,287 @@
+//! Built-in vector store: chunking, embedding storage and similarity search.
+//!
+//! Collections live in SQLite (`vector_collections`, `vector_documents`,
+//! `vector_chunks`). Embeddings are stored L2-normalised as little-endian
+//! `f32` blobs, so cosine similarity is a dot product. Each chunk also gets a
+//! random-hyperplane LSH signature (`bucket`) indexed per collection. Small
+//! collections are searched exactly; large ones only score the chunks whose
+//! signature is within `PROBE_RADIUS` bits of the query's, falling back to an
+//! exact scan when that finds too few candidates.
+
+use anyhow::{Context, Result};
+use sqlx::SqlitePool;
+
+/// Bits in an LSH signature.
+const SIGNATURE_BITS: u32 = 12;
+
+/// Signatures within this Hamming distance of the query are probed.
+const PROBE_RADIUS: u32 = 2;
+
+/// Collections with fewer chunks than this are always searched exactly.
+pub const ANN_MIN_CHUNKS: i64 = 5000;
+
+/// Split `text` into chunks of about `size` characters, each starting
+/// `overlap` characters before the previous one ended. Breaks at whitespace
+/// when there is some in the second half of a chunk.
+pub fn chunk_text(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let size = size.max(1);
+    let overlap = overlap.min(size / 2);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+
+    while start < chars.len() {
+        let mut end = (start + size).min(chars.len());
+        if end < chars.len() {
+            if let Some(ws) = chars[start + size / 2..end]
+                .iter()
+                .rposition(|c| c.is_whitespace())
+            {
+                end = start + size / 2 + ws + 1;
+            }
+        }
+        let chunk: String = chars[start..end].iter().collect();
+        let chunk = chunk.trim();
+        if !chunk.is_empty() {
+            chunks.push(chunk.to_string());
+        }
+        if end == chars.len() {
+            break;
+        }
+        start = end.saturating_sub(overlap).max(start + 1);
+    }
+    chunks
+}
+
+/// Scale `v` to unit length. A zero vector is left as is.
+pub fn normalize(v: &mut [f32]) {
+    let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        v.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+pub fn encode(v: &[f32]) -> Vec<u8> {
+    v.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+pub fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect()
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Deterministic pseudo-random value in [-1, 1] (splitmix64), so every
+/// process derives the same hyperplanes without storing them.
+fn plane_component(plane: u32, dim: usize) -> f32 {
+    let mut z = (u64::from(plane) << 32 | dim as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 11) as f32 / (1u64 << 53) as f32 * 2.0 - 1.0
+}
+
+/// LSH signature: bit `i` is set when `v` lies on the positive side of
+/// hyperplane `i`.
+pub fn signature(v: &[f32]) -> i64 {
+    (0..SIGNATURE_BITS).fold(0, |sig, plane| {
+        let side: f32 = v
+            .iter()
+            .enumerate()
+            .map(|(dim, x)| x * plane_component(plane, dim))
+            .sum();
+        if side >= 0.0 {
+            sig | 1 << plane
+        } else {
+            sig
+        }
+    })
+}
+
+/// Every signature within `PROBE_RADIUS` bits of `sig`, including itself.
+fn probes(sig: i64) -> Vec<i64> {
+    let mut out = vec![sig];
+    for i in 0..SIGNATURE_BITS {
+        out.push(sig ^ 1 << i);
+        if PROBE_RADIUS >= 2 {
+            for j in i + 1..SIGNATURE_BITS {
+                out.push(sig ^ 1 << i ^ 1 << j);
+            }
+        }
+    }
+    out
+}
+
+/// A chunk matching a query.
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+pub struct ChunkHit {
+    #[serde(skip)]
+    pub id: i64,
+    pub document_id: String,
+    pub document_name: String,
+    pub chunk_index: i64,
+    pub content: String,
+    #[sqlx(skip)]
+    pub score: f32,
+}
+
+/// The `top_k` chunks of `collection_id` most similar to the normalised
+/// `query` vector, best first.
+pub async fn search(
+    pool: &SqlitePool,
+    collection_id: &str,
+    query: &[f32],
+    top_k: usize,
+) -> Result<Vec<ChunkHit>> {
+    let total: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM vector_chunks WHERE collection_id = ?")
+            .bind(collection_id)
+            .fetch_one(pool)
+            .await
+            .context("Failed to count chunks")?;
+
+    let mut candidates: Vec<(i64, Vec<u8>)> = Vec::new();
+    if total >= ANN_MIN_CHUNKS {
+        let buckets = probes(signature(query));
+        let placeholders = vec!["?"; buckets.len()].join(", ");
+        let sql = format!(
+            "SELECT id, embedding FROM vector_chunks \
+             WHERE collection_id = ? AND bucket IN ({placeholders})"
+        );
+        let mut q = sqlx::query_as::<sqlx::Sqlite, (i64, Vec<u8>)>(&sql).bind(collection_id);
+        for b in &buckets {
+            q = q.bind(b);
+        }
+        candidates = q.fetch_all(pool).await.context("Failed to probe chunks")?;
+    }
+    if candidates.len() < top_k {
+        candidates =
+            sqlx::query_as("SELECT id, embedding FROM vector_chunks WHERE collection_id = ?")
+                .bind(collection_id)
+                .fetch_all(pool)
+                .await
+                .context("Failed to scan chunks")?;
+    }
+
+    let mut scored: Vec<(i64, f32)> = candidates
+        .iter()
+        .map(|(id, blob)| (*id, dot(query, &decode(blob))))
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(top_k);
+
+    let mut hits = Vec::with_capacity(scored.len());
+    for (id, score) in scored {
+        let hit: Option<ChunkHit> = sqlx::query_as(
+            "SELECT c.id, c.document_id, d.name AS document_name, c.chunk_index, c.content \
+             FROM vector_chunks c JOIN vector_documents d ON d.id = c.document_id \
+             WHERE c.id = ?",
+        )
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .context("Failed to load chunk")?;
+        if let Some(hit) = hit {
+            hits.push(ChunkHit { score, ..hit });
+        }
+    }
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_overlap_and_break_at_whitespace() {
+        let text = "alpha beta gamma delta epsilon zeta eta theta";
+        let chunks = chunk_text(text, 20, 6);
+        assert_eq!(chunks[0], "alpha beta gamma");
+        assert!(chunks.iter().all(|c| c.chars().count() <= 20));
+        // Overlap carries the end of one chunk into the next
+        assert!(chunks[1].starts_with("gamma"));
+        assert!(chunks.last().unwrap().ends_with("theta"));
+    }
+
+    #[test]
+    fn chunking_handles_short_and_unbroken_text() {
+        assert_eq!(chunk_text("short", 100, 10), ["short"]);
+        assert!(chunk_text("   ", 100, 10).is_empty());
+        let unbroken = "x".repeat(25);
+        let chunks = chunk_text(&unbroken, 10, 0);
+        assert_eq!(chunks.len(), 3);
+        assert_eq!(chunks[2].len(), 5);
+    }
+
+    #[test]
+    fn embeddings_round_trip_normalised() {
+        let mut v = vec![3.0, 4.0];
+        normalize(&mut v);
+        assert_eq!(decode(&encode(&v)), [0.6, 0.8]);
+    }
+
+    #[test]
+    fn similar_vectors_share_nearby_signatures() {
+        let mut a: Vec<f32> = (0..64).map(|i| (i as f32).sin()).collect();
+        let mut b: Vec<f32> = a.iter().map(|x| x + 0.01).collect();
+        normalize(&mut a);
+        normalize(&mut b);
+        let distance = (signature(&a) ^ signature(&b)).count_ones();
+        assert!(distance <= PROBE_RADIUS);
+        assert!(probes(signature(&a)).contains(&signature(&b)));
+        assert_eq!(probes(0).len(), 1 + 12 + 66);
+    }
+
+    #[tokio::test]
+    async fn search_ranks_by_cosine_similarity() {
+        let db = crate::db::Database::test_db().await;
+        sqlx::query(
+            "INSERT INTO idp_configs (id, name, issuer, client_id, client_secret_enc) \
+             VALUES ('idp', 'test', 'https://test', 'client', 'secret')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO users (id, idp_id, subject) VALUES ('u1', 'idp', 'u1')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO vector_collections (id, user_id, name, model) VALUES ('c1', 'u1', 'docs', 'embed')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO vector_documents (id, collection_id, name) VALUES ('d1', 'c1', 'notes.txt')",
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        for (i, v) in [[1.0f32, 0.0], [0.6, 0.8], [0.0, 1.0]].iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO vector_chunks (collection_id, document_id, chunk_index, content, embedding, bucket) \
+                 VALUES ('c1', 'd1', ?, ?, ?, ?)",
+            )
+            .bind(i as i64)
+            .bind(format!("chunk {i}"))
+            .bind(encode(v))
+            .bind(signature(v))
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        }
+
+        let hits = search(&db.pool, "c1", &[0.0, 1.0], 2).await.unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].content, "chunk 2");
+        assert_eq!(hits[1].content, "chunk 1");
+        assert_eq!(hits[1].document_name, "notes.txt");
+        assert!((hits[1].score - 0.8).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,385 @@
+//! Backend watchdog: restarts model containers that crashed.
+//!
+//! Every `WATCHDOG_INTERVAL_SECS` the backend of each loaded model gets a
+//! health check. One that fails it while its container has stopped —
+//! OOM-killed, exited or removed — has crashed. One that fails while the
+//! container still runs is left alone, since large models take minutes to
+//! load.
+//!
+//! A crashed container is restarted with the model's saved launch
+//! parameters after a backoff: one interval the first time (so a crash seen
+//! mid-way through an admin stop isn't acted on), then doubling per restart
+//! up to [`MAX_BACKOFF`]. When `WATCHDOG_MAX_RESTARTS` restarts haven't kept
+//! it up, the next crash is confirmed one interval later, the model is
+//! marked unloaded and an alert is raised: a `container.watchdog_gave_up`
+//! audit event, an entry under `watchdog` in `GET /api/admin/system` and a
+//! `ContainerCrashed` event with `gave_up` set on the event bus (every
+//! detected crash is published there too). The restart count resets once
+//! the backend has stayed healthy for [`STABLE_AFTER`].
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::api::common::{self, LaunchParams};
+use crate::config::AppConfig;
+use crate::docker::StopMode;
+use crate::events::AppEvent;
+use crate::tasks::{Task, TaskScheduler};
+use crate::AppState;
+
+/// Longest wait before a restart.
+const MAX_BACKOFF: Duration = Duration::from_secs(600);
+
+/// Healthy this long after a restart, a backend gets its full restart
+/// budget back.
+const STABLE_AFTER: Duration = Duration::from_secs(600);
+
+/// Alerts kept for the system status.
+const MAX_ALERTS: usize = 20;
+
+/// Restart bookkeeping for one model.
+#[derive(Debug, Default)]
+struct Watch {
+    /// Restarts since the backend was last stable.
+    restarts: u32,
+    last_restart: Option<Instant>,
+    /// When the current crash may be acted on; None while not crashed.
+    act_at: Option<Instant>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    /// First sighting of a crash; a restart is scheduled.
+    Detected,
+    /// The backoff hasn't passed yet.
+    Wait,
+    Restart,
+    GiveUp,
+}
+
+impl Watch {
+    /// Decide what to do about a crashed backend at `now`.
+    fn on_crash(&mut self, now: Instant, interval: Duration, max_restarts: u32) -> Action {
+        let Some(act_at) = self.act_at else {
+            // Out of restarts: just confirm the crash before giving up
+            let wait = if self.restarts >= max_restarts {
+                interval
+            } else {
+                backoff(interval, self.restarts)
+            };
+            self.act_at = Some(now + wait);
+            return Action::Detected;
+        };
+        if now < act_at {
+            return Action::Wait;
+        }
+        if self.restarts >= max_restarts {
+            return Action::GiveUp;
+        }
+        self.restarts += 1;
+        self.last_restart = Some(now);
+        self.act_at = None;
+        Action::Restart
+    }
+
+    /// Record a healthy check. Returns true once the watch can be dropped.
+    fn on_healthy(&mut self, now: Instant) -> bool {
+        self.act_at = None;
+        self.last_restart
+            .is_none_or(|at| now.duration_since(at) >= STABLE_AFTER)
+    }
+}
+
+/// Wait before restart number `restarts + 1`.
+fn backoff(interval: Duration, restarts: u32) -> Duration {
+    interval
+        .saturating_mul(2u32.saturating_pow(restarts))
+        .min(MAX_BACKOFF)
+}
+
+/// A model the watchdog gave up on.
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub model_id: String,
+    /// How the container last went down, e.g. "OOM-killed".
+    pub reason: String,
+    pub restarts: u32,
+    pub raised_at: DateTime<Utc>,
+}
+
+/// Restart bookkeeping and raised alerts, held in `AppState`.
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    watches: Mutex<HashMap<String, Watch>>,
+    alerts: Mutex<VecDeque<Alert>>,
+}
+
+impl Watchdog {
+    fn watches(&self) -> MutexGuard<'_, HashMap<String, Watch>> {
+        self.watches.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn raise(&self, alert: Alert) {
+        let mut alerts = self.alerts.lock().unwrap_or_else(|e| e.into_inner());
+        if alerts.len() == MAX_ALERTS {
+            alerts.pop_front();
+        }
+        alerts.push_back(alert);
+    }
+
+    /// Models crashed or restarted since they were last stable, with their
+    /// restart count, by model ID.
+    pub fn restarting(&self) -> Vec<(String, u32)> {
+        let mut restarting: Vec<_> = self
+            .watches()
+            .iter()
+            .filter(|(_, w)| w.restarts > 0 || w.act_at.is_some())
+            .map(|(model_id, w)| (model_id.clone(), w.restarts))
+            .collect();
+        restarting.sort();
+        restarting
+    }
+
+    /// Recent alerts, newest first.
+    pub fn alerts(&self) -> Vec<Alert> {
+        self.alerts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
+    }
+
+    /// The watchdog's settings, models it is restarting and recent alerts
+    /// (newest first), for `GET /api/admin/system`.
+    pub fn status(&self, config: &AppConfig) -> serde_json::Value {
+        let restarting: Vec<_> = self
+            .restarting()
+            .into_iter()
+            .map(|(model_id, restarts)| json!({ "model_id": model_id, "restarts": restarts }))
+            .collect();
+        json!({
+            "interval_secs": config.watchdog_interval_secs,
+            "max_restarts": config.watchdog_max_restarts,
+            "restarting": restarting,
+            "alerts": self.alerts(),
+        })
+    }
+}
+
+/// Run the watchdog every `WATCHDOG_INTERVAL_SECS`, unless that is 0.
+pub fn spawn(state: Arc<AppState>, tasks: &TaskScheduler) {
+    let secs = state.config.watchdog_interval_secs;
+    if secs == 0 {
+        info!("Backend watchdog disabled");
+        return;
+    }
+    tasks.spawn(
+        Task::every("watchdog", Duration::from_secs(secs)),
+        move || {
+            let state = state.clone();
+            async move { check_backends(&state).await }
+        },
+    );
+}
+
+/// One watchdog pass over the loaded models.
+async fn check_backends(state: &Arc<AppState>) -> Result<()> {
+    let loaded: Vec<(String, String, Option<String>)> = sqlx::query_as(
+        "SELECT id, backend_type, launch_params FROM models WHERE loaded = 1 AND draining = 0",
+    )
+    .fetch_all(&state.db.pool)
+    .await?;
+    // Models stopped since the last pass start over if loaded again
+    state
+        .watchdog
+        .watches()
+        .retain(|id, _| loaded.iter().any(|(m, _, _)| m == id));
+
+    let interval = Duration::from_secs(state.config.watchdog_interval_secs);
+    for (model_id, backend_type, launch_params) in loaded {
+        let container =
+            common::serving_container_name(&state.db.pool, &model_id, &backend_type).await;
+        let healthy = state
+            .docker
+            .check_health_at(&container, &backend_type)
+            .await;
+        if matches!(healthy, Ok(true)) {
+            let mut watches = state.watchdog.watches();
+            if watches
+                .get_mut(&model_id)
+                .is_some_and(|w| w.on_healthy(Instant::now()))
+            {
+                watches.remove(&model_id);
+            }
+            continue;
+        }
+
+        let reason = match state.docker.container_exit_reason(&container).await {
+            Ok(Some(reason)) => reason,
+            // Still running: loading, or busy enough to miss a check
+            Ok(None) => continue,
+            Err(e) => {
+                warn!(model = %model_id, error = %e, "Watchdog could not inspect container");
+                continue;
+            }
+        };
+
+        let (action, restarts) = {
+            let mut watches = state.watchdog.watches();
+            let watch = watches.entry(model_id.clone()).or_default();
+            let action =
+                watch.on_crash(Instant::now(), interval, state.config.watchdog_max_restarts);
+            (action, watch.restarts)
+        };
+        match action {
+            Action::Detected => {
+                warn!(model = %model_id, container = %container, reason = %reason, restarts, "Backend container crashed; restart scheduled");
+                state.events.publish(AppEvent::ContainerCrashed {
+                    model_id: model_id.clone(),
+                    reason: reason.clone(),
+                    gave_up: false,
+                });
+            }
+            Action::Wait => {}
+            Action::Restart => {
+                if let Some(started) =
+                    restart(state, &model_id, &container, launch_params.as_deref()).await
+                {
+                    info!(target: "audit", action = "container.watchdog_restart", actor = "system", resource = %model_id, container = %started, reason = %reason, attempt = restarts, "Watchdog restarted crashed container");
+                }
+            }
+            Action::GiveUp => give_up(state, &model_id, &container, reason, restarts).await,
+        }
+    }
+    Ok(())
+}
+
+/// Replace the dead container with a new one started the same way, and
+/// return its name. A failed start is left for the next pass, which sees
+/// the crash again.
+async fn restart(
+    state: &Arc<AppState>,
+    model_id: &str,
+    container: &str,
+    launch: Option<&str>,
+) -> Option<String> {
+    if let Err(e) = state
+        .docker
+        .stop_named(container, model_id, StopMode::Force)
+        .await
+    {
+        warn!(model = %model_id, error = %e, "Watchdog failed to remove crashed container");
+    }
+    state.backends.remove(model_id).await;
+    let params = LaunchParams::saved_start(model_id, launch);
+    match common::start_container_core(state, &params).await {
+        Ok((started, _)) => Some(started),
+        Err(response) => {
+            warn!(model = %model_id, status = %response.status(), "Watchdog failed to restart container");
+            None
+        }
+    }
+}
+
+/// Stop trying: remove the container, mark the model unloaded and raise an
+/// alert.
+async fn give_up(
+    state: &Arc<AppState>,
+    model_id: &str,
+    container: &str,
+    reason: String,
+    restarts: u32,
+) {
+    if let Err(e) = state
+        .docker
+        .stop_named(container, model_id, StopMode::Force)
+        .await
+    {
+        warn!(model = %model_id, error = %e, "Watchdog failed to remove crashed container");
+    }
+    common::post_stop_cleanup(state, model_id).await;
+    state.watchdog.watches().remove(model_id);
+
+    warn!(target: "audit", action = "container.watchdog_gave_up", actor = "system", resource = %model_id, reason = %reason, restarts, "Crashed container not restarted again; model marked unloaded");
+    state.events.publish(AppEvent::ContainerCrashed {
+        model_id: model_id.to_string(),
+        reason: reason.clone(),
+        gave_up: true,
+    });
+    state.watchdog.raise(Alert {
+        model_id: model_id.to_string(),
+        reason,
+        restarts,
+        raised_at: Utc::now(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_secs(30);
+
+    #[test]
+    fn backoff_doubles_up_to_cap() {
+        assert_eq!(backoff(INTERVAL, 0), INTERVAL);
+        assert_eq!(backoff(INTERVAL, 1), Duration::from_secs(60));
+        assert_eq!(backoff(INTERVAL, 3), Duration::from_secs(240));
+        assert_eq!(backoff(INTERVAL, 5), MAX_BACKOFF);
+        assert_eq!(backoff(INTERVAL, 100), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn restarts_with_backoff_then_gives_up() {
+        let start = Instant::now();
+        let mut watch = Watch::default();
+
+        assert_eq!(watch.on_crash(start, INTERVAL, 2), Action::Detected);
+        assert_eq!(
+            watch.on_crash(start + Duration::from_secs(10), INTERVAL, 2),
+            Action::Wait
+        );
+        let t = start + INTERVAL;
+        assert_eq!(watch.on_crash(t, INTERVAL, 2), Action::Restart);
+        assert_eq!(watch.restarts, 1);
+
+        // Crashed again: the second restart waits twice as long
+        assert_eq!(watch.on_crash(t, INTERVAL, 2), Action::Detected);
+        assert_eq!(watch.on_crash(t + INTERVAL, INTERVAL, 2), Action::Wait);
+        let t = t + 2 * INTERVAL;
+        assert_eq!(watch.on_crash(t, INTERVAL, 2), Action::Restart);
+
+        assert_eq!(watch.on_crash(t, INTERVAL, 2), Action::Detected);
+        assert_eq!(watch.on_crash(t + INTERVAL, INTERVAL, 2), Action::GiveUp);
+    }
+
+    #[test]
+    fn stable_backend_gets_its_budget_back() {
+        let start = Instant::now();
+        let mut watch = Watch::default();
+        watch.on_crash(start, INTERVAL, 3);
+        assert_eq!(
+            watch.on_crash(start + INTERVAL, INTERVAL, 3),
+            Action::Restart
+        );
+
+        let restarted = start + INTERVAL;
+        assert!(!watch.on_healthy(restarted + Duration::from_secs(60)));
+        assert_eq!(watch.act_at, None);
+        assert!(watch.on_healthy(restarted + STABLE_AFTER));
+
+        // A healthy check in between cancels a pending restart
+        let mut watch = Watch::default();
+        watch.on_crash(start, INTERVAL, 3);
+        assert!(watch.on_healthy(start + Duration::from_secs(5)));
+    }
+}
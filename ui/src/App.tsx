@@ -15,6 +15,7 @@ import Users from './pages/admin/Users';
 import System from './pages/admin/System';
 import AdminReservations from './pages/admin/Reservations';
 import UsageDashboard from './pages/admin/UsageDashboard';
+import Hygiene from './pages/admin/Hygiene';
 import UserGuide from './pages/user/UserGuide';
 import LoadingSpinner from './components/common/LoadingSpinner';
 import ErrorAlert from './components/common/ErrorAlert';
@@ -442,6 +443,8 @@ function UserMenu({ user, onLogout }: Readonly<{ user: AuthUser; onLogout: () =>
 
 function AuthenticatedApp({ user, onLogout }: Readonly<{ user: AuthUser; onLogout: () => void }>) {
   const { colors } = useTheme();
+  // Role holders see the admin pages; the API limits what they can change
+  const hasAdminAccess = user.is_admin || (user.roles ?? []).length > 0;
 
   const handleLogout = async () => {
     try {
@@ -489,17 +492,18 @@ function AuthenticatedApp({ user, onLogout }: Readonly<{ user: AuthUser; onLogou
           <UserMenu user={user} onLogout={handleLogout} />
         </div>
 
-        {user.is_admin && (
+        {hasAdminAccess && (
           <>
             <div style={{ flexBasis: '100%', height: 0, borderTop: `1px solid ${colors.navSeparator}` }} />
             <div style={{ width: 24, marginRight: '-1rem', visibility: 'hidden' }} aria-hidden="true" />
             <strong style={{ fontSize: '1.1rem', marginRight: '0.5rem', visibility: 'hidden' }} aria-hidden="true">Sovereign Engine</strong>
             <NavLink to="/admin/usage">Usage Analytics</NavLink>
-            <NavLink to="/admin/idp">IdP Config</NavLink>
+            {user.is_admin && <NavLink to="/admin/idp">IdP Config</NavLink>}
             <NavLink to="/admin/models">Model Mapping</NavLink>
             <NavLink to="/admin/users">Users</NavLink>
             <NavLink to="/admin/system">System</NavLink>
             <NavLink to="/admin/reservations">Manage Reservations</NavLink>
+            {user.is_admin && <NavLink to="/admin/hygiene">Hygiene</NavLink>}
           </>
         )}
       </nav>
@@ -511,14 +515,15 @@ function AuthenticatedApp({ user, onLogout }: Readonly<{ user: AuthUser; onLogou
           <Route path="/models" element={<Models />} />
           <Route path="/reservations" element={<UserReservations userId={user.user_id} />} />
           <Route path="/guide" element={<UserGuide />} />
-          {user.is_admin && (
+          {hasAdminAccess && (
             <>
               <Route path="/admin/usage" element={<UsageDashboard />} />
-              <Route path="/admin/idp" element={<IdpConfig />} />
+              {user.is_admin && <Route path="/admin/idp" element={<IdpConfig />} />}
               <Route path="/admin/models" element={<ModelMapping />} />
               <Route path="/admin/users" element={<Users />} />
               <Route path="/admin/system" element={<System />} />
               <Route path="/admin/reservations" element={<AdminReservations userId={user.user_id} />} />
+              {user.is_admin && <Route path="/admin/hygiene" element={<Hygiene />} />}
             </>
           )}
         </Routes>
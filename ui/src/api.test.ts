@@ -195,7 +195,7 @@ describe('getUserTokens()', () => {
 
 describe('getUserModels()', () => {
   it('unwraps the models array from /api/user/models', async () => {
-    const models = [{ id: 'm1', hf_repo: 'repo', filename: null, size_bytes: 100, category_id: null, loaded: false, backend_port: null, backend_type: 'vllm', last_used_at: null, created_at: '2025-01-01', context_length: null, n_layers: null, n_heads: null, n_kv_heads: null, embedding_length: null, runtime_overrides: null }];
+    const models = [{ id: 'm1', hf_repo: 'repo', filename: null, size_bytes: 100, category_id: null, loaded: false, backend_port: null, backend_type: 'vllm', last_used_at: null, created_at: '2025-01-01', context_length: null, n_layers: null, n_heads: null, n_kv_heads: null, embedding_length: null, runtime_overrides: null, architecture: null, pipeline_tag: null, suggested_category_id: null, category_suggestion_reason: null }];
     mockFetch.mockResolvedValueOnce(okResponse({ models }));
 
     const result = await getUserModels();
@@ -252,7 +252,7 @@ describe('getCategories()', () => {
 
 describe('getSystemInfo()', () => {
   it('returns system info directly', async () => {
-    const info = { disk: { model_path: '/models', total_bytes: 1000, used_bytes: 500, free_bytes: 500 }, queues: {}, gates: {}, containers: [], gpu: [], gpu_memory: [], available_backends: ['vllm'] };
+    const info = { disk: { model_path: '/models', total_bytes: 1000, used_bytes: 500, free_bytes: 500 }, queues: {}, gates: {}, containers: [], arch: 'amd64', gpu: [], gpu_probes: [], gpu_memory: [], available_backends: ['vllm'] };
     mockFetch.mockResolvedValueOnce(okResponse(info));
 
     const result = await getSystemInfo();
@@ -751,7 +751,7 @@ describe('cancelReservation()', () => {
 
 describe('getAdminModels()', () => {
   it('unwraps models from /api/admin/models', async () => {
-    const models = [{ id: 'm1', hf_repo: 'r', filename: null, size_bytes: 0, category_id: null, loaded: false, backend_port: null, backend_type: 'vllm', last_used_at: null, created_at: '', context_length: null, n_layers: null, n_heads: null, n_kv_heads: null, embedding_length: null, runtime_overrides: { cache_ram_mib: 0, swa_full: true } }];
+    const models = [{ id: 'm1', hf_repo: 'r', filename: null, size_bytes: 0, category_id: null, loaded: false, backend_port: null, backend_type: 'vllm', last_used_at: null, created_at: '', context_length: null, n_layers: null, n_heads: null, n_kv_heads: null, embedding_length: null, runtime_overrides: { cache_ram_mib: 0, swa_full: true }, architecture: null, pipeline_tag: null, suggested_category_id: null, category_suggestion_reason: null }];
     mockFetch.mockResolvedValueOnce(okResponse({ models }));
 
     const result = await getAdminModels();
@@ -762,7 +762,7 @@ describe('getAdminModels()', () => {
 
 describe('getAdminUsers()', () => {
   it('unwraps users from /api/admin/users', async () => {
-    const users = [{ id: 'u1', idp_id: 'i1', email: 'a@b.com', display_name: 'Alice', is_admin: false, created_at: '', usage_summary: { total_requests: 0, total_tokens: 0 } }];
+    const users = [{ id: 'u1', idp_id: 'i1', email: 'a@b.com', display_name: 'Alice', is_admin: false, active: true, priority_tier: 'normal', created_at: '', usage_summary: { total_requests: 0, total_tokens: 0 } }];
     mockFetch.mockResolvedValueOnce(okResponse({ users }));
 
     const result = await getAdminUsers();
@@ -11,9 +11,20 @@ import type {
   Category,
   CategoryCreateRequest,
   AdminModel,
+  CategorizeOutcome,
+  ModelAlias,
+  ModelAliasRequest,
+  ModelTrashEntry,
   RuntimeOverrides,
+  ContextPolicy,
   AdminUser,
+  PriorityTier,
   SystemInfo,
+  GpuProbe,
+  DrainResult,
+  HygieneReport,
+  HygieneCategory,
+  HygieneModelResult,
   OpenAIModel,
   ContainerStartRequest,
   HfSearchResult,
@@ -203,7 +214,7 @@ export async function cancelHfDownload(id: string): Promise<void> {
 export async function getAdminUsage(period: string = 'day'): Promise<AdminUsageResponse> {
   const param = encodeURIComponent(period);
   const [stats, timeline] = await Promise.all([
-    request<{ summary: AdminUsageResponse['summary']; by_user: AdminUsageResponse['by_user'] }>(
+    request<Pick<AdminUsageResponse, 'summary' | 'privacy' | 'by_user'>>(
       `/api/admin/usage?period=${param}`,
     ),
     request<{ timeline: AdminUsageResponse['timeline'] }>(
@@ -240,6 +251,20 @@ export async function deleteIdp(id: string): Promise<void> {
   });
 }
 
+/** Issue a SCIM token for the IdP, replacing any previous one. Shown once. */
+export async function createScimToken(id: string): Promise<string> {
+  const data = await request<{ token: string }>(`/api/admin/idps/${encodeURIComponent(id)}/scim-token`, {
+    method: 'POST',
+  });
+  return data.token;
+}
+
+export async function deleteScimToken(id: string): Promise<void> {
+  await request<{ status: string }>(`/api/admin/idps/${encodeURIComponent(id)}/scim-token`, {
+    method: 'DELETE',
+  });
+}
+
 // ---- Admin: Categories ----
 
 export async function getCategories(): Promise<Category[]> {
@@ -280,6 +305,7 @@ export async function updateModel(
     category_id?: string | null;
     backend_type?: string;
     runtime_overrides?: RuntimeOverrides;
+    context_policy?: ContextPolicy;
   },
 ): Promise<void> {
   await request<{ status: string }>(`/api/admin/models/${encodeURIComponent(id)}`, {
@@ -288,6 +314,27 @@ export async function updateModel(
   });
 }
 
+/** Classify every uncategorized model; `apply` assigns instead of suggesting. */
+export async function categorizeModels(apply: boolean): Promise<CategorizeOutcome[]> {
+  const data = await request<{ models: CategorizeOutcome[] }>('/api/admin/models/categorize', {
+    method: 'POST',
+    body: JSON.stringify({ apply }),
+  });
+  return data.models;
+}
+
+export async function acceptCategorySuggestion(id: string): Promise<{ category_id: string }> {
+  return request<{ category_id: string }>(`/api/admin/models/${encodeURIComponent(id)}/category-suggestion`, {
+    method: 'POST',
+  });
+}
+
+export async function dismissCategorySuggestion(id: string): Promise<void> {
+  await request<{ status: string }>(`/api/admin/models/${encodeURIComponent(id)}/category-suggestion`, {
+    method: 'DELETE',
+  });
+}
+
 /** One row of the `blocking_tokens` array returned with a 409. */
 export interface BlockingToken {
   id: string;
@@ -298,6 +345,8 @@ export interface BlockingToken {
 export interface DeleteModelResult {
   status: string;
   revoked_tokens: number;
+  /** Trash entry the model can be restored from; null when the trash is off. */
+  trash_id: string | null;
 }
 
 /**
@@ -318,6 +367,50 @@ export async function deleteModel(
   );
 }
 
+export async function getModelTrash(): Promise<ModelTrashEntry[]> {
+  const data = await request<{ entries: ModelTrashEntry[] }>('/api/admin/models/trash');
+  return data.entries;
+}
+
+export async function restoreModel(trashId: string): Promise<void> {
+  await request<{ status: string }>(`/api/admin/models/trash/${encodeURIComponent(trashId)}/restore`, {
+    method: 'POST',
+  });
+}
+
+export async function purgeModelTrash(trashId: string): Promise<void> {
+  await request<{ status: string }>(`/api/admin/models/trash/${encodeURIComponent(trashId)}`, {
+    method: 'DELETE',
+  });
+}
+
+// ---- Admin: Model aliases ----
+
+export async function getModelAliases(): Promise<ModelAlias[]> {
+  const data = await request<{ aliases: ModelAlias[] }>('/api/admin/aliases');
+  return data.aliases;
+}
+
+export async function createModelAlias(req: ModelAliasRequest): Promise<ModelAlias> {
+  return request<ModelAlias>('/api/admin/aliases', {
+    method: 'POST',
+    body: JSON.stringify(req),
+  });
+}
+
+export async function updateModelAlias(id: string, req: ModelAliasRequest): Promise<ModelAlias> {
+  return request<ModelAlias>(`/api/admin/aliases/${encodeURIComponent(id)}`, {
+    method: 'PUT',
+    body: JSON.stringify(req),
+  });
+}
+
+export async function deleteModelAlias(id: string): Promise<void> {
+  await request<{ status: string }>(`/api/admin/aliases/${encodeURIComponent(id)}`, {
+    method: 'DELETE',
+  });
+}
+
 // ---- Admin: Users ----
 
 export async function getAdminUsers(): Promise<AdminUser[]> {
@@ -325,19 +418,71 @@ export async function getAdminUsers(): Promise<AdminUser[]> {
   return data.users;
 }
 
-export async function updateUser(id: string, req: { is_admin: boolean }): Promise<void> {
+export async function updateUser(id: string, req: { is_admin?: boolean; active?: boolean; priority_tier?: PriorityTier }): Promise<void> {
   await request<{ status: string }>(`/api/admin/users/${encodeURIComponent(id)}`, {
     method: 'PUT',
     body: JSON.stringify(req),
   });
 }
 
+export async function revokeUserSessions(id: string): Promise<{ revoked_sessions: number }> {
+  return request<{ revoked_sessions: number }>(`/api/admin/users/${encodeURIComponent(id)}/revoke-sessions`, {
+    method: 'POST',
+  });
+}
+
+/** Filters for `revokeTokens`; they combine with AND. */
+export interface TokenRevokeFilter {
+  user_id?: string;
+  created_before?: string;
+  last_used_before?: string;
+  category_id?: string;
+  model_id?: string;
+}
+
+/**
+ * Revoke every active API token matching the filter. With `dryRun` the
+ * matching tokens are only counted.
+ */
+export async function revokeTokens(
+  filter: TokenRevokeFilter,
+  dryRun = false,
+): Promise<{ count: number }> {
+  const data = await request<{ revoked?: number; count?: number }>(
+    `/api/admin/tokens/revoke${dryRun ? '?dry_run=true' : ''}`,
+    {
+      method: 'POST',
+      body: JSON.stringify(filter),
+    },
+  );
+  return { count: data.count ?? data.revoked ?? 0 };
+}
+
 // ---- Admin: System ----
 
 export async function getSystemInfo(): Promise<SystemInfo> {
   return request<SystemInfo>('/api/admin/system');
 }
 
+export async function runGpuProbe(): Promise<{ gpu_probes: GpuProbe[] }> {
+  return request<{ gpu_probes: GpuProbe[] }>('/api/admin/system/gpu-probe', { method: 'POST' });
+}
+
+export async function drainSystem(req: {
+  reason?: string;
+  timeout_secs?: number;
+  stop_containers?: boolean;
+}): Promise<DrainResult> {
+  return request<DrainResult>('/api/admin/system/drain', {
+    method: 'POST',
+    body: JSON.stringify(req),
+  });
+}
+
+export async function resumeSystem(): Promise<void> {
+  await request<{ draining: boolean }>('/api/admin/system/resume', { method: 'POST' });
+}
+
 export async function startContainer(req: ContainerStartRequest): Promise<{ container: string; url: string }> {
   return request<{ container: string; url: string }>('/api/admin/containers/start', {
     method: 'POST',
@@ -359,6 +504,33 @@ export async function stopContainer(modelId: string): Promise<void> {
   });
 }
 
+// ---- Admin: Hygiene ----
+
+export async function getHygieneReport(days: number): Promise<HygieneReport> {
+  return request<HygieneReport>(`/api/admin/hygiene?days=${days}`);
+}
+
+export async function revokeUnusedTokens(days: number): Promise<{ revoked: number }> {
+  return request<{ revoked: number }>(`/api/admin/hygiene/tokens/revoke?days=${days}`, { method: 'POST' });
+}
+
+export async function revokeStaleSessions(): Promise<{ users: number; sessions: number }> {
+  return request<{ users: number; sessions: number }>('/api/admin/hygiene/sessions/revoke', { method: 'POST' });
+}
+
+/** Delete unrequested models; pinned or approval-gated ones are reported by status. */
+export async function deleteUnusedModels(days: number): Promise<{ deleted: number; results: HygieneModelResult[] }> {
+  return request<{ deleted: number; results: HygieneModelResult[] }>(`/api/admin/hygiene/models/delete?days=${days}`, {
+    method: 'POST',
+  });
+}
+
+export async function deleteEmptyCategories(): Promise<{ deleted: HygieneCategory[]; skipped: HygieneCategory[] }> {
+  return request<{ deleted: HygieneCategory[]; skipped: HygieneCategory[] }>('/api/admin/hygiene/categories/delete', {
+    method: 'POST',
+  });
+}
+
 // ---- User: Reservations ----
 
 export async function createReservation(req: CreateReservationRequest): Promise<{ id: string; status: string }> {
@@ -37,6 +37,7 @@ describe('ReservationStatusBadge', () => {
     { status: 'completed', label: 'Completed' },
     { status: 'rejected', label: 'Rejected' },
     { status: 'cancelled', label: 'Cancelled' },
+    { status: 'waitlisted', label: 'Waitlisted' },
   ];
 
   for (const { status, label } of statuses) {
@@ -15,6 +15,7 @@ const STATUS_CONFIG: Record<ReservationStatus, BadgeStyle> = {
   completed: { label: 'Completed', bg: (c) => c.badgeNeutralBg, text: (c) => c.badgeNeutralText },
   rejected:  { label: 'Rejected',  bg: (c) => c.badgeDangerBg,  text: (c) => c.badgeDangerText },
   cancelled: { label: 'Cancelled', bg: (c) => c.badgeNeutralBg, text: (c) => c.badgeNeutralText },
+  waitlisted: { label: 'Waitlisted', bg: (c) => c.badgeWarningBg, text: (c) => c.badgeWarningText },
 };
 
 export default function ReservationStatusBadge({ status }: Readonly<{ status: ReservationStatus }>) {
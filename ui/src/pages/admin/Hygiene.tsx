@@ -0,0 +1,267 @@
+import { useState, useEffect, useCallback } from 'react';
+import {
+  getHygieneReport,
+  revokeUnusedTokens,
+  revokeStaleSessions,
+  deleteUnusedModels,
+  deleteEmptyCategories,
+} from '../../api';
+import type { HygieneReport } from '../../types';
+import { useTheme, tableStyles } from '../../theme';
+import LoadingSpinner from '../../components/common/LoadingSpinner';
+import ErrorAlert from '../../components/common/ErrorAlert';
+import ConfirmDialog from '../../components/common/ConfirmDialog';
+
+type Cleanup = 'tokens' | 'sessions' | 'models' | 'categories';
+
+const CONFIRM: Record<Cleanup, { title: string; message: string; label: string }> = {
+  tokens: {
+    title: 'Revoke Unused Tokens',
+    message: 'Revoke every listed API token? Clients still using them will get 401.',
+    label: 'Revoke',
+  },
+  sessions: {
+    title: 'Sign Out Deactivated Users',
+    message: 'Delete the sessions and trusted devices of every listed deactivated user?',
+    label: 'Sign out',
+  },
+  models: {
+    title: 'Delete Unused Models',
+    message: 'Delete every listed model? Files move to the trash and can be restored until purged.',
+    label: 'Delete',
+  },
+  categories: {
+    title: 'Delete Empty Categories',
+    message: 'Delete every listed category that no token or access mapping refers to?',
+    label: 'Delete',
+  },
+};
+
+export default function Hygiene() {
+  const { colors } = useTheme();
+  const [days, setDays] = useState(90);
+  const [report, setReport] = useState<HygieneReport | null>(null);
+  const [loading, setLoading] = useState(true);
+  const [error, setError] = useState<string | null>(null);
+  const [notice, setNotice] = useState<string | null>(null);
+  const [working, setWorking] = useState<Cleanup | null>(null);
+  const [confirm, setConfirm] = useState<Cleanup | null>(null);
+
+  const { table: tableStyle, th: thStyle, td: tdStyle } = tableStyles(colors);
+
+  const fetchReport = useCallback(async () => {
+    setLoading(true);
+    setError(null);
+    try {
+      setReport(await getHygieneReport(days));
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to load hygiene report');
+    } finally {
+      setLoading(false);
+    }
+  }, [days]);
+
+  useEffect(() => {
+    fetchReport();
+  }, [fetchReport]);
+
+  const runCleanup = async (kind: Cleanup) => {
+    setConfirm(null);
+    setWorking(kind);
+    setNotice(null);
+    try {
+      if (kind === 'tokens') {
+        const { revoked } = await revokeUnusedTokens(days);
+        setNotice(`Revoked ${revoked} token(s).`);
+      } else if (kind === 'sessions') {
+        const { users, sessions } = await revokeStaleSessions();
+        setNotice(`Signed out ${users} user(s), ${sessions} session(s).`);
+      } else if (kind === 'models') {
+        const { deleted, results } = await deleteUnusedModels(days);
+        const pending = results.filter((r) => r.status === 202).length;
+        const blocked = results.filter((r) => r.status !== 200 && r.status !== 202).length;
+        setNotice(
+          `Deleted ${deleted} model(s)` +
+            (pending ? `, ${pending} awaiting approval` : '') +
+            (blocked ? `, ${blocked} not deleted (pinned by tokens or failed)` : '') +
+            '.',
+        );
+      } else {
+        const { deleted, skipped } = await deleteEmptyCategories();
+        setNotice(
+          `Deleted ${deleted.length} categor${deleted.length === 1 ? 'y' : 'ies'}` +
+            (skipped.length ? `, skipped ${skipped.length} still in use` : '') +
+            '.',
+        );
+      }
+      await fetchReport();
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Cleanup failed');
+    } finally {
+      setWorking(null);
+    }
+  };
+
+  const cleanupButton = (kind: Cleanup, count: number) => (
+    <button
+      onClick={() => setConfirm(kind)}
+      disabled={count === 0 || working !== null}
+      style={{
+        padding: '0.3rem 0.7rem',
+        background: colors.buttonDanger,
+        color: '#fff',
+        border: 'none',
+        borderRadius: 4,
+        cursor: count === 0 || working !== null ? 'default' : 'pointer',
+        fontSize: '0.8rem',
+        opacity: count === 0 || working !== null ? 0.5 : 1,
+      }}
+    >
+      {working === kind ? 'Working...' : 'Clean up'}
+    </button>
+  );
+
+  const sectionHeader = (title: string, kind: Cleanup, count: number) => (
+    <div style={{ display: 'flex', alignItems: 'center', gap: '0.75rem', margin: '1.5rem 0 0.75rem' }}>
+      <h2 style={{ margin: 0 }}>
+        {title} ({count})
+      </h2>
+      {cleanupButton(kind, count)}
+    </div>
+  );
+
+  const empty = (text: string) => <p style={{ color: colors.textMuted }}>{text}</p>;
+
+  if (loading && !report) return <LoadingSpinner message="Loading hygiene report..." />;
+  if (error) return <ErrorAlert message={error} onRetry={fetchReport} />;
+  if (!report) return null;
+
+  return (
+    <div>
+      <h1>Hygiene</h1>
+      <label style={{ display: 'flex', alignItems: 'center', gap: '0.5rem', color: colors.textSecondary }}>
+        Unused for
+        <input
+          type="number"
+          min={1}
+          value={days}
+          onChange={(e) => setDays(Math.max(1, Number(e.target.value) || 1))}
+          style={{ width: 80, padding: '0.3rem', background: colors.inputBg, color: colors.textPrimary }}
+        />
+        days
+      </label>
+      {notice && <p style={{ color: colors.successText }}>{notice}</p>}
+
+      {sectionHeader('Unused tokens', 'tokens', report.unused_tokens.length)}
+      {report.unused_tokens.length === 0 ? (
+        empty('No tokens unused for this long.')
+      ) : (
+        <table style={tableStyle}>
+          <thead>
+            <tr>
+              <th style={thStyle}>Name</th>
+              <th style={thStyle}>Owner</th>
+              <th style={thStyle}>Created</th>
+              <th style={thStyle}>Last used</th>
+            </tr>
+          </thead>
+          <tbody>
+            {report.unused_tokens.map((t) => (
+              <tr key={t.id}>
+                <td style={tdStyle}>{t.name}</td>
+                <td style={tdStyle}>{t.user_email || t.user_id}</td>
+                <td style={tdStyle}>{t.created_at}</td>
+                <td style={tdStyle}>{t.last_used_at || 'Never'}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+
+      {sectionHeader('Sessions of deactivated users', 'sessions', report.stale_sessions.length)}
+      {report.stale_sessions.length === 0 ? (
+        empty('No deactivated user is still signed in.')
+      ) : (
+        <table style={tableStyle}>
+          <thead>
+            <tr>
+              <th style={thStyle}>User</th>
+              <th style={thStyle}>Deactivated</th>
+              <th style={thStyle}>Sessions</th>
+              <th style={thStyle}>Trusted devices</th>
+            </tr>
+          </thead>
+          <tbody>
+            {report.stale_sessions.map((s) => (
+              <tr key={s.user_id}>
+                <td style={tdStyle}>{s.email || s.user_id}</td>
+                <td style={tdStyle}>{s.deactivated_at || '—'}</td>
+                <td style={tdStyle}>{s.sessions}</td>
+                <td style={tdStyle}>{s.devices}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+
+      {sectionHeader('Models never requested', 'models', report.unused_models.length)}
+      {report.unused_models.length === 0 ? (
+        empty('Every model older than this has been requested.')
+      ) : (
+        <table style={tableStyle}>
+          <thead>
+            <tr>
+              <th style={thStyle}>Repository</th>
+              <th style={thStyle}>Added</th>
+              <th style={thStyle}>Loaded</th>
+            </tr>
+          </thead>
+          <tbody>
+            {report.unused_models.map((m) => (
+              <tr key={m.id}>
+                <td style={{ ...tdStyle, wordBreak: 'break-all' }}>{m.hf_repo}</td>
+                <td style={tdStyle}>{m.created_at}</td>
+                <td style={tdStyle}>{m.loaded ? 'Yes' : 'No'}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+
+      {sectionHeader('Empty categories', 'categories', report.empty_categories.length)}
+      {report.empty_categories.length === 0 ? (
+        empty('Every category has models.')
+      ) : (
+        <table style={tableStyle}>
+          <thead>
+            <tr>
+              <th style={thStyle}>Name</th>
+              <th style={thStyle}>Scoped tokens</th>
+              <th style={thStyle}>Access mappings</th>
+            </tr>
+          </thead>
+          <tbody>
+            {report.empty_categories.map((c) => (
+              <tr key={c.id}>
+                <td style={tdStyle}>{c.name}</td>
+                <td style={tdStyle}>{c.tokens}</td>
+                <td style={tdStyle}>{c.access_mappings}</td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+
+      {confirm && (
+        <ConfirmDialog
+          title={CONFIRM[confirm].title}
+          message={CONFIRM[confirm].message}
+          confirmLabel={CONFIRM[confirm].label}
+          destructive
+          onConfirm={() => runCleanup(confirm)}
+          onCancel={() => setConfirm(null)}
+        />
+      )}
+    </div>
+  );
+}
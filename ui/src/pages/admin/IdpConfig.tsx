@@ -1,6 +1,6 @@
 import { useState, useEffect, useCallback } from 'react';
-import { getIdps, createIdp, updateIdp, deleteIdp } from '../../api';
-import type { IdP } from '../../types';
+import { getIdps, createIdp, updateIdp, deleteIdp, createScimToken, deleteScimToken } from '../../api';
+import type { IdP, IdPCreateRequest } from '../../types';
 import { useTheme, tableStyles, formStyles } from '../../theme';
 import LoadingSpinner from '../../components/common/LoadingSpinner';
 import ErrorAlert from '../../components/common/ErrorAlert';
@@ -14,6 +14,7 @@ export default function IdpConfig() {
   const [showForm, setShowForm] = useState(false);
   const [editingId, setEditingId] = useState<string | null>(null);
   const [confirmDisable, setConfirmDisable] = useState<string | null>(null);
+  const [scimToken, setScimToken] = useState<{ idp: string; token: string } | null>(null);
 
   // Form state
   const [formName, setFormName] = useState('');
@@ -21,6 +22,8 @@ export default function IdpConfig() {
   const [formClientId, setFormClientId] = useState('');
   const [formClientSecret, setFormClientSecret] = useState('');
   const [formScopes, setFormScopes] = useState('openid email profile');
+  const [formGroupsClaim, setFormGroupsClaim] = useState('');
+  const [formGroupMappings, setFormGroupMappings] = useState('{}');
   const [submitting, setSubmitting] = useState(false);
   const [submitError, setSubmitError] = useState<string | null>(null);
 
@@ -46,15 +49,24 @@ export default function IdpConfig() {
 
   const handleSubmit = async (e: React.SubmitEvent) => {
     e.preventDefault();
+    let groupMappings: Record<string, string[]>;
+    try {
+      groupMappings = JSON.parse(formGroupMappings.trim() || '{}');
+    } catch {
+      setSubmitError('Group mappings must be valid JSON');
+      return;
+    }
     setSubmitting(true);
     setSubmitError(null);
     try {
       if (editingId) {
-        const updates: Record<string, string> = {
+        const updates: Partial<IdPCreateRequest> = {
           name: formName.trim(),
           issuer: formIssuer.trim(),
           client_id: formClientId.trim(),
           scopes: formScopes.trim(),
+          groups_claim: formGroupsClaim.trim(),
+          group_mappings: groupMappings,
         };
         if (formClientSecret.trim()) {
           updates.client_secret = formClientSecret.trim();
@@ -67,6 +79,8 @@ export default function IdpConfig() {
           client_id: formClientId.trim(),
           client_secret: formClientSecret.trim(),
           scopes: formScopes.trim(),
+          groups_claim: formGroupsClaim.trim(),
+          group_mappings: groupMappings,
         });
       }
       setShowForm(false);
@@ -100,6 +114,25 @@ export default function IdpConfig() {
     }
   };
 
+  const handleScimToken = async (idp: IdP) => {
+    try {
+      const token = await createScimToken(idp.id);
+      setScimToken({ idp: idp.name, token });
+      setIdps((prev) => prev.map((i) => (i.id === idp.id ? { ...i, scim_enabled: true } : i)));
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to issue SCIM token');
+    }
+  };
+
+  const handleScimOff = async (id: string) => {
+    try {
+      await deleteScimToken(id);
+      setIdps((prev) => prev.map((i) => (i.id === id ? { ...i, scim_enabled: false } : i)));
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to remove SCIM token');
+    }
+  };
+
   const startEdit = (idp: IdP) => {
     setEditingId(idp.id);
     setFormName(idp.name);
@@ -107,6 +140,8 @@ export default function IdpConfig() {
     setFormClientId(idp.client_id);
     setFormClientSecret('');
     setFormScopes(idp.scopes);
+    setFormGroupsClaim(idp.groups_claim ?? '');
+    setFormGroupMappings(JSON.stringify(idp.group_mappings ?? {}, null, 2));
     setSubmitError(null);
     setShowForm(true);
   };
@@ -117,6 +152,8 @@ export default function IdpConfig() {
     setFormClientId('');
     setFormClientSecret('');
     setFormScopes('openid email profile');
+    setFormGroupsClaim('');
+    setFormGroupMappings('{}');
     setEditingId(null);
     setSubmitError(null);
   };
@@ -173,6 +210,20 @@ export default function IdpConfig() {
               <label htmlFor="idp-scopes" style={labelStyle}>Scopes</label>
               <input id="idp-scopes" type="text" value={formScopes} onChange={(e) => setFormScopes(e.target.value)} style={inputStyle} />
             </div>
+            <div style={{ marginBottom: '1rem' }}>
+              <label htmlFor="idp-groups-claim" style={labelStyle}>Groups claim (synced to admin/roles at every login)</label>
+              <input id="idp-groups-claim" type="text" value={formGroupsClaim} onChange={(e) => setFormGroupsClaim(e.target.value)} style={inputStyle} placeholder="e.g. groups or realm_access.roles" />
+            </div>
+            <div style={{ marginBottom: '1rem' }}>
+              <label htmlFor="idp-group-mappings" style={labelStyle}>Group mappings (JSON)</label>
+              <textarea
+                id="idp-group-mappings"
+                value={formGroupMappings}
+                onChange={(e) => setFormGroupMappings(e.target.value)}
+                style={{ ...inputStyle, fontFamily: 'monospace', minHeight: 80 }}
+                placeholder='{"se-admins": ["admin"], "ml-team": ["model_admin"]}'
+              />
+            </div>
             <button
               type="submit"
               disabled={submitting}
@@ -242,6 +293,37 @@ export default function IdpConfig() {
                   >
                     Edit
                   </button>
+                  <button
+                    onClick={() => handleScimToken(idp)}
+                    title="Issue a token for SCIM user provisioning at /scim/v2"
+                    style={{
+                      padding: '0.3rem 0.7rem',
+                      background: colors.buttonPrimary,
+                      color: '#fff',
+                      border: 'none',
+                      borderRadius: 4,
+                      cursor: 'pointer',
+                      fontSize: '0.8rem',
+                    }}
+                  >
+                    {idp.scim_enabled ? 'New SCIM Token' : 'Enable SCIM'}
+                  </button>
+                  {idp.scim_enabled && (
+                    <button
+                      onClick={() => handleScimOff(idp.id)}
+                      style={{
+                        padding: '0.3rem 0.7rem',
+                        background: colors.buttonDanger,
+                        color: '#fff',
+                        border: 'none',
+                        borderRadius: 4,
+                        cursor: 'pointer',
+                        fontSize: '0.8rem',
+                      }}
+                    >
+                      Disable SCIM
+                    </button>
+                  )}
                   {idp.enabled ? (
                     <button
                       onClick={() => setConfirmDisable(idp.id)}
@@ -280,6 +362,22 @@ export default function IdpConfig() {
         </table>
       )}
 
+      {scimToken && (
+        <div style={{ background: colors.cardBg, border: `1px solid ${colors.cardBorder}`, borderRadius: 8, padding: '1rem', marginTop: '1rem' }}>
+          <strong>SCIM token for {scimToken.idp}</strong>
+          <p style={{ fontSize: '0.85rem', color: colors.textMuted, margin: '0.35rem 0' }}>
+            Configure the IdP to provision to <code>{window.location.origin}/scim/v2</code> with this bearer token. It is only shown once.
+          </p>
+          <code style={{ display: 'block', wordBreak: 'break-all', fontSize: '0.8rem' }}>{scimToken.token}</code>
+          <button
+            onClick={() => setScimToken(null)}
+            style={{ marginTop: '0.5rem', padding: '0.3rem 0.7rem', background: colors.buttonPrimary, color: '#fff', border: 'none', borderRadius: 4, cursor: 'pointer', fontSize: '0.8rem' }}
+          >
+            Done
+          </button>
+        </div>
+      )}
+
       {confirmDisable && (
         <ConfirmDialog
           title="Disable Identity Provider"
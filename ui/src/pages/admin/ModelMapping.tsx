@@ -1,6 +1,20 @@
 import { useState, useEffect, useCallback } from 'react';
-import { getCategories, getAdminModels, createCategory, updateCategory, deleteCategory, updateModel } from '../../api';
-import type { Category, AdminModel, RuntimeOverrides } from '../../types';
+import {
+  getCategories,
+  getAdminModels,
+  createCategory,
+  updateCategory,
+  deleteCategory,
+  updateModel,
+  categorizeModels,
+  acceptCategorySuggestion,
+  dismissCategorySuggestion,
+  getModelAliases,
+  createModelAlias,
+  updateModelAlias,
+  deleteModelAlias,
+} from '../../api';
+import type { Category, AdminModel, ModelAlias, ModelAliasRequest, RequestLogLevel, RuntimeOverrides } from '../../types';
 import { useTheme, tableStyles, formStyles } from '../../theme';
 import LoadingSpinner from '../../components/common/LoadingSpinner';
 import ErrorAlert from '../../components/common/ErrorAlert';
@@ -19,10 +33,25 @@ function countOverrides(o: RuntimeOverrides | null | undefined): number {
   return n;
 }
 
+/** Model kinds a category can claim for auto-categorization. */
+const AUTO_KINDS = ['embedding', 'rerank', 'vision', 'code', 'reasoning', 'chat'];
+
+/** Encode an alias target as a select value: `model:<id>` or `category:<id>`. */
+function aliasTargetValue(alias: ModelAlias): string {
+  return alias.model_id ? `model:${alias.model_id}` : `category:${alias.category_id}`;
+}
+
+/** Turn a select value from `aliasTargetValue` back into request fields. */
+function aliasTargetFields(value: string): Pick<ModelAliasRequest, 'model_id' | 'category_id'> {
+  const id = value.slice(value.indexOf(':') + 1);
+  return value.startsWith('model:') ? { model_id: id, category_id: null } : { model_id: null, category_id: id };
+}
+
 export default function ModelMapping() {
   const { colors } = useTheme();
   const [categories, setCategories] = useState<Category[]>([]);
   const [models, setModels] = useState<AdminModel[]>([]);
+  const [aliases, setAliases] = useState<ModelAlias[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
 
@@ -32,6 +61,12 @@ export default function ModelMapping() {
   const [catName, setCatName] = useState('');
   const [catDescription, setCatDescription] = useState('');
   const [catPreferredModel, setCatPreferredModel] = useState('');
+  const [catAutoKind, setCatAutoKind] = useState('');
+  const [catLogLevel, setCatLogLevel] = useState('');
+  const [catMaxConcurrent, setCatMaxConcurrent] = useState('');
+  const [catMaxQueueDepth, setCatMaxQueueDepth] = useState('');
+  const [catQueueTimeout, setCatQueueTimeout] = useState('');
+  const [catDefaultParallel, setCatDefaultParallel] = useState('');
   const [catSubmitting, setCatSubmitting] = useState(false);
   const [catSubmitError, setCatSubmitError] = useState<string | null>(null);
 
@@ -41,6 +76,15 @@ export default function ModelMapping() {
   // Runtime overrides editor
   const [editingOverridesFor, setEditingOverridesFor] = useState<AdminModel | null>(null);
 
+  // Alias form
+  const [aliasName, setAliasName] = useState('');
+  const [aliasTarget, setAliasTarget] = useState('');
+  const [aliasError, setAliasError] = useState<string | null>(null);
+
+  // Auto-categorization
+  const [categorizing, setCategorizing] = useState(false);
+  const [categorizeResult, setCategorizeResult] = useState<string | null>(null);
+
   const { table: tableStyle, th: thStyle, td: tdStyle } = tableStyles(colors);
   const { input: inputStyle, label: labelStyle } = formStyles(colors);
 
@@ -48,9 +92,10 @@ export default function ModelMapping() {
     setLoading(true);
     setError(null);
     try {
-      const [cats, mods] = await Promise.all([getCategories(), getAdminModels()]);
+      const [cats, mods, als] = await Promise.all([getCategories(), getAdminModels(), getModelAliases()]);
       setCategories(cats);
       setModels(mods);
+      setAliases(als);
     } catch (err) {
       setError(err instanceof Error ? err.message : 'Failed to load data');
     } finally {
@@ -67,6 +112,12 @@ export default function ModelMapping() {
     setCatName('');
     setCatDescription('');
     setCatPreferredModel('');
+    setCatAutoKind('');
+    setCatLogLevel('');
+    setCatMaxConcurrent('');
+    setCatMaxQueueDepth('');
+    setCatQueueTimeout('');
+    setCatDefaultParallel('');
     setCatSubmitError(null);
     setShowCatForm(true);
   };
@@ -76,6 +127,12 @@ export default function ModelMapping() {
     setCatName(cat.name);
     setCatDescription(cat.description);
     setCatPreferredModel(cat.preferred_model_id || '');
+    setCatAutoKind(cat.auto_kind || '');
+    setCatLogLevel(cat.log_level || '');
+    setCatMaxConcurrent(cat.max_concurrent?.toString() ?? '');
+    setCatMaxQueueDepth(cat.max_queue_depth?.toString() ?? '');
+    setCatQueueTimeout(cat.queue_timeout_secs?.toString() ?? '');
+    setCatDefaultParallel(cat.default_parallel?.toString() ?? '');
     setCatSubmitError(null);
     setShowCatForm(true);
   };
@@ -89,6 +146,12 @@ export default function ModelMapping() {
         name: catName.trim(),
         description: catDescription.trim(),
         preferred_model_id: catPreferredModel || null,
+        auto_kind: catAutoKind || null,
+        log_level: (catLogLevel || null) as RequestLogLevel | null,
+        max_concurrent: catMaxConcurrent ? Number(catMaxConcurrent) : null,
+        max_queue_depth: catMaxQueueDepth ? Number(catMaxQueueDepth) : null,
+        queue_timeout_secs: catQueueTimeout ? Number(catQueueTimeout) : null,
+        default_parallel: catDefaultParallel ? Number(catDefaultParallel) : null,
       };
       if (editingCat) {
         await updateCategory(editingCat.id, payload);
@@ -125,6 +188,104 @@ export default function ModelMapping() {
     }
   };
 
+  const handleAliasSubmit = async (e: React.SubmitEvent) => {
+    e.preventDefault();
+    setAliasError(null);
+    try {
+      const created = await createModelAlias({ alias: aliasName.trim(), ...aliasTargetFields(aliasTarget) });
+      setAliases((prev) => [...prev, created].sort((a, b) => a.alias.localeCompare(b.alias)));
+      setAliasName('');
+      setAliasTarget('');
+    } catch (err) {
+      setAliasError(err instanceof Error ? err.message : 'Failed to create alias');
+    }
+  };
+
+  const handleAliasRetarget = async (alias: ModelAlias, target: string) => {
+    setAliasError(null);
+    try {
+      const updated = await updateModelAlias(alias.id, { alias: alias.alias, ...aliasTargetFields(target) });
+      setAliases((prev) => prev.map((a) => (a.id === alias.id ? updated : a)));
+    } catch (err) {
+      setAliasError(err instanceof Error ? err.message : 'Failed to update alias');
+    }
+  };
+
+  const handleAliasDelete = async (id: string) => {
+    setAliasError(null);
+    try {
+      await deleteModelAlias(id);
+      setAliases((prev) => prev.filter((a) => a.id !== id));
+    } catch (err) {
+      setAliasError(err instanceof Error ? err.message : 'Failed to delete alias');
+    }
+  };
+
+  const aliasTargetOptions = (
+    <>
+      <optgroup label="Models">
+        {models.map((m) => (
+          <option key={m.id} value={`model:${m.id}`}>{m.hf_repo}</option>
+        ))}
+      </optgroup>
+      <optgroup label="Categories">
+        {categories.map((c) => (
+          <option key={c.id} value={`category:${c.id}`}>{c.name}</option>
+        ))}
+      </optgroup>
+    </>
+  );
+
+  const handleCategorize = async () => {
+    setCategorizing(true);
+    setCategorizeResult(null);
+    try {
+      const outcomes = await categorizeModels(false);
+      const suggested = outcomes.filter((o) => o.category_id).length;
+      setCategorizeResult(
+        suggested === 0
+          ? 'No uncategorized model matched a category.'
+          : `Suggested a category for ${suggested} model${suggested === 1 ? '' : 's'}.`
+      );
+      setModels(await getAdminModels());
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to categorize models');
+    } finally {
+      setCategorizing(false);
+    }
+  };
+
+  const handleAcceptSuggestion = async (modelId: string) => {
+    try {
+      const { category_id } = await acceptCategorySuggestion(modelId);
+      setModels((prev) =>
+        prev.map((m) =>
+          m.id === modelId
+            ? { ...m, category_id, suggested_category_id: null, category_suggestion_reason: null }
+            : m
+        )
+      );
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to accept suggestion');
+    }
+  };
+
+  const handleDismissSuggestion = async (modelId: string) => {
+    try {
+      await dismissCategorySuggestion(modelId);
+      setModels((prev) =>
+        prev.map((m) =>
+          m.id === modelId ? { ...m, suggested_category_id: null, category_suggestion_reason: null } : m
+        )
+      );
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to dismiss suggestion');
+    }
+  };
+
+  const getCategoryName = (categoryId: string): string =>
+    categories.find((c) => c.id === categoryId)?.name ?? categoryId;
+
   const handleOverridesSaved = (modelId: string, overrides: RuntimeOverrides) => {
     setModels((prev) =>
       prev.map((m) => (m.id === modelId ? { ...m, runtime_overrides: overrides } : m))
@@ -192,6 +353,86 @@ export default function ModelMapping() {
                 ))}
               </select>
             </div>
+            <div style={{ marginBottom: '1rem' }}>
+              <label htmlFor="cat-auto-kind" style={labelStyle}>Auto-categorize</label>
+              <select
+                id="cat-auto-kind"
+                value={catAutoKind}
+                onChange={(e) => setCatAutoKind(e.target.value)}
+                style={{ ...inputStyle, background: colors.inputBg }}
+              >
+                <option value="">Match by name</option>
+                {AUTO_KINDS.map((kind) => (
+                  <option key={kind} value={kind}>{kind} models</option>
+                ))}
+              </select>
+            </div>
+            <div style={{ marginBottom: '1rem' }}>
+              <label htmlFor="cat-log-level" style={labelStyle}>Request logging</label>
+              <select
+                id="cat-log-level"
+                value={catLogLevel}
+                onChange={(e) => setCatLogLevel(e.target.value)}
+                style={{ ...inputStyle, background: colors.inputBg }}
+              >
+                <option value="">Server default</option>
+                <option value="none">None</option>
+                <option value="metadata">Metadata only</option>
+                <option value="full">Full bodies</option>
+              </select>
+            </div>
+            <div style={{ display: 'flex', gap: '1rem', marginBottom: '1rem' }}>
+              <div style={{ flex: 1 }}>
+                <label htmlFor="cat-max-concurrent" style={labelStyle}>Max concurrent requests</label>
+                <input
+                  id="cat-max-concurrent"
+                  type="number"
+                  min={1}
+                  value={catMaxConcurrent}
+                  onChange={(e) => setCatMaxConcurrent(e.target.value)}
+                  placeholder="No limit"
+                  style={inputStyle}
+                />
+              </div>
+              <div style={{ flex: 1 }}>
+                <label htmlFor="cat-max-queue" style={labelStyle}>Max queued requests</label>
+                <input
+                  id="cat-max-queue"
+                  type="number"
+                  min={0}
+                  value={catMaxQueueDepth}
+                  onChange={(e) => setCatMaxQueueDepth(e.target.value)}
+                  placeholder="No limit"
+                  style={inputStyle}
+                />
+              </div>
+            </div>
+            <div style={{ display: 'flex', gap: '1rem', marginBottom: '1rem' }}>
+              <div style={{ flex: 1 }}>
+                <label htmlFor="cat-queue-timeout" style={labelStyle}>Queue timeout (seconds)</label>
+                <input
+                  id="cat-queue-timeout"
+                  type="number"
+                  min={0}
+                  value={catQueueTimeout}
+                  onChange={(e) => setCatQueueTimeout(e.target.value)}
+                  placeholder="Server default"
+                  style={inputStyle}
+                />
+              </div>
+              <div style={{ flex: 1 }}>
+                <label htmlFor="cat-default-parallel" style={labelStyle}>Default parallel slots</label>
+                <input
+                  id="cat-default-parallel"
+                  type="number"
+                  min={1}
+                  value={catDefaultParallel}
+                  onChange={(e) => setCatDefaultParallel(e.target.value)}
+                  placeholder="Server default"
+                  style={inputStyle}
+                />
+              </div>
+            </div>
             <button
               type="submit"
               disabled={catSubmitting}
@@ -268,8 +509,110 @@ export default function ModelMapping() {
         </table>
       )}
 
+      {/* Aliases: client-facing model names routed to a model or category */}
+      <h2 style={{ marginBottom: '0.75rem' }}>Aliases</h2>
+      <p style={{ color: colors.textMuted, margin: '0 0 0.75rem' }}>
+        Requests for an alias go to its model or category, before real model names are matched. Point an alias elsewhere to swap the model behind it without changing clients.
+      </p>
+      {aliasError && <ErrorAlert message={aliasError} />}
+      <form onSubmit={handleAliasSubmit} style={{ display: 'flex', gap: '0.5rem', alignItems: 'center', marginBottom: '0.75rem', maxWidth: 700 }}>
+        <input
+          aria-label="Alias name"
+          type="text"
+          value={aliasName}
+          onChange={(e) => setAliasName(e.target.value)}
+          style={{ ...inputStyle, flex: 1 }}
+          placeholder="e.g. gpt-4o"
+          required
+        />
+        <select
+          aria-label="Alias target"
+          value={aliasTarget}
+          onChange={(e) => setAliasTarget(e.target.value)}
+          style={{ ...inputStyle, flex: 1, background: colors.inputBg }}
+          required
+        >
+          <option value="">Routes to...</option>
+          {aliasTargetOptions}
+        </select>
+        <button
+          type="submit"
+          style={{ padding: '0.5rem 1rem', background: colors.buttonPrimary, color: '#fff', border: 'none', borderRadius: 4, cursor: 'pointer' }}
+        >
+          Add
+        </button>
+      </form>
+      {aliases.length === 0 ? (
+        <p style={{ color: colors.textMuted, marginBottom: '2rem' }}>No aliases defined.</p>
+      ) : (
+        <table style={{ ...tableStyle, marginBottom: '2rem' }}>
+          <thead>
+            <tr>
+              <th style={thStyle}>Alias</th>
+              <th style={thStyle}>Routes to</th>
+              <th style={thStyle}>Actions</th>
+            </tr>
+          </thead>
+          <tbody>
+            {aliases.map((alias) => (
+              <tr key={alias.id}>
+                <td style={{ ...tdStyle, fontWeight: 600, fontFamily: 'monospace' }}>{alias.alias}</td>
+                <td style={tdStyle}>
+                  <select
+                    aria-label={`Target of alias ${alias.alias}`}
+                    value={aliasTargetValue(alias)}
+                    onChange={(e) => handleAliasRetarget(alias, e.target.value)}
+                    style={{ padding: '0.3rem 0.5rem', border: `1px solid ${colors.inputBorder}`, borderRadius: 4, fontSize: '0.85rem', background: colors.inputBg, color: colors.textPrimary }}
+                  >
+                    {aliasTargetOptions}
+                  </select>
+                </td>
+                <td style={tdStyle}>
+                  <button
+                    onClick={() => handleAliasDelete(alias.id)}
+                    aria-label={`Delete alias ${alias.alias}`}
+                    style={{
+                      padding: '0.3rem 0.7rem',
+                      background: colors.buttonDanger,
+                      color: '#fff',
+                      border: 'none',
+                      borderRadius: 4,
+                      cursor: 'pointer',
+                      fontSize: '0.8rem',
+                    }}
+                  >
+                    Delete
+                  </button>
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+
       {/* Models and their category assignment */}
-      <h2 style={{ marginBottom: '0.75rem' }}>Model Category Assignments</h2>
+      <div style={{ display: 'flex', justifyContent: 'space-between', alignItems: 'center', marginBottom: '0.75rem' }}>
+        <h2 style={{ margin: 0 }}>Model Category Assignments</h2>
+        <button
+          onClick={handleCategorize}
+          disabled={categorizing}
+          title="Suggest a category for every uncategorized model from its usage, pipeline tag, architecture and name"
+          style={{
+            padding: '0.4rem 0.9rem',
+            background: categorizing ? colors.buttonPrimaryDisabled : colors.buttonPrimary,
+            color: '#fff',
+            border: 'none',
+            borderRadius: 4,
+            cursor: categorizing ? 'default' : 'pointer',
+            fontSize: '0.85rem',
+          }}
+        >
+          {categorizing ? 'Categorizing...' : 'Suggest categories'}
+        </button>
+      </div>
+      {categorizeResult && (
+        <p style={{ color: colors.textMuted, margin: '0 0 0.75rem' }}>{categorizeResult}</p>
+      )}
       {models.length === 0 ? (
         <p style={{ color: colors.textMuted }}>No models registered.</p>
       ) : (
@@ -331,6 +674,28 @@ export default function ModelMapping() {
                         <option key={cat.id} value={cat.id}>{cat.name}</option>
                       ))}
                     </select>
+                    {!model.category_id && model.suggested_category_id && (
+                      <div
+                        title={model.category_suggestion_reason ?? undefined}
+                        style={{ display: 'flex', alignItems: 'center', gap: '0.4rem', marginTop: '0.35rem', fontSize: '0.8rem', color: colors.textMuted }}
+                      >
+                        <span>Suggested: {getCategoryName(model.suggested_category_id)}</span>
+                        <button
+                          onClick={() => handleAcceptSuggestion(model.id)}
+                          aria-label={`Accept suggested category for ${model.hf_repo}`}
+                          style={{ padding: '0.15rem 0.5rem', background: colors.buttonPrimary, color: '#fff', border: 'none', borderRadius: 4, cursor: 'pointer', fontSize: '0.75rem' }}
+                        >
+                          Accept
+                        </button>
+                        <button
+                          onClick={() => handleDismissSuggestion(model.id)}
+                          aria-label={`Dismiss suggested category for ${model.hf_repo}`}
+                          style={{ padding: '0.15rem 0.5rem', background: colors.buttonDisabled, color: colors.textSecondary, border: 'none', borderRadius: 4, cursor: 'pointer', fontSize: '0.75rem' }}
+                        >
+                          Dismiss
+                        </button>
+                      </div>
+                    )}
                   </td>
                   <td style={tdStyle}>
                     <div style={{ display: 'flex', alignItems: 'center', gap: '0.5rem' }}>
@@ -405,6 +405,7 @@ export default function AdminReservations({ userId }: Readonly<{ userId: string
           <option value="completed">Completed</option>
           <option value="rejected">Rejected</option>
           <option value="cancelled">Cancelled</option>
+          <option value="waitlisted">Waitlisted</option>
         </select>
       </div>
 
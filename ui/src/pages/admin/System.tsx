@@ -1,6 +1,6 @@
 import { useState, useEffect, useCallback, useRef } from 'react';
-import { getSystemInfo, getAdminModels, stopContainer, deleteModel, ApiError, type BlockingToken } from '../../api';
-import type { SystemInfo, AdminModel, SystemContainer, GpuMemory, CpuInfo, GateSnapshot } from '../../types';
+import { getSystemInfo, runGpuProbe, drainSystem, resumeSystem, getAdminModels, stopContainer, deleteModel, getModelTrash, restoreModel, purgeModelTrash, ApiError, type BlockingToken } from '../../api';
+import type { SystemInfo, GpuProbe, AdminModel, ModelTrashEntry, SystemContainer, GpuMemory, CpuInfo, GateSnapshot } from '../../types';
 import { useTheme } from '../../theme';
 import { useEventStream, type ConnectionStatus } from '../../hooks/useEventStream';
 import LoadingSpinner from '../../components/common/LoadingSpinner';
@@ -56,16 +56,21 @@ export default function System() {
   const { colors } = useTheme();
   const [system, setSystem] = useState<SystemInfo | null>(null);
   const [models, setModels] = useState<AdminModel[]>([]);
+  const [trash, setTrash] = useState<ModelTrashEntry[]>([]);
   const [loading, setLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
   const [actionLoading, setActionLoading] = useState<string | null>(null);
   const [confirmStop, setConfirmStop] = useState<string | null>(null);
   const [confirmDelete, setConfirmDelete] = useState<AdminModel | null>(null);
+  const [confirmPurge, setConfirmPurge] = useState<ModelTrashEntry | null>(null);
   const [overrideDelete, setOverrideDelete] = useState<{
     model: AdminModel;
     blockingTokens: BlockingToken[];
   } | null>(null);
   const [startModel, setStartModel] = useState<AdminModel | null>(null);
+  const [confirmDrain, setConfirmDrain] = useState(false);
+  const [drainStopContainers, setDrainStopContainers] = useState(false);
+  const [drainNotice, setDrainNotice] = useState<string | null>(null);
 
   // SSE live metrics
   const { snapshot, status: sseStatus } = useEventStream();
@@ -81,9 +86,10 @@ export default function System() {
     setLoading(true);
     setError(null);
     try {
-      const [sysInfo, modelList] = await Promise.all([getSystemInfo(), getAdminModels()]);
+      const [sysInfo, modelList, trashList] = await Promise.all([getSystemInfo(), getAdminModels(), getModelTrash()]);
       setSystem(sysInfo);
       setModels(modelList);
+      setTrash(trashList);
     } catch (err) {
       setError(err instanceof Error ? err.message : 'Failed to load system info');
     } finally {
@@ -94,7 +100,9 @@ export default function System() {
   const refreshModels = useCallback(async () => {
     setError(null);
     try {
-      setModels(await getAdminModels());
+      const [modelList, trashList] = await Promise.all([getAdminModels(), getModelTrash()]);
+      setModels(modelList);
+      setTrash(trashList);
     } catch (err) {
       setError(err instanceof Error ? err.message : 'Failed to load models');
     }
@@ -147,6 +155,78 @@ export default function System() {
     }
   };
 
+  const handleRestore = async (entry: ModelTrashEntry) => {
+    setActionLoading(entry.id);
+    try {
+      await restoreModel(entry.id);
+      await refreshModels();
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to restore model');
+    } finally {
+      setActionLoading(null);
+    }
+  };
+
+  const handleGpuProbe = async () => {
+    setActionLoading('gpu-probe');
+    try {
+      const { gpu_probes } = await runGpuProbe();
+      setSystem((prev) => (prev ? { ...prev, gpu_probes } : prev));
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to run GPU probe');
+    } finally {
+      setActionLoading(null);
+    }
+  };
+
+  const handleDrain = async () => {
+    setConfirmDrain(false);
+    setActionLoading('drain');
+    setDrainNotice(null);
+    try {
+      const result = await drainSystem({ stop_containers: drainStopContainers });
+      setSystem((prev) => (prev ? { ...prev, drain: result.drain } : prev));
+      if (!result.drained) {
+        setDrainNotice(`Timed out with ${result.in_flight} in-flight and ${result.queued} queued requests.`);
+      } else if (result.stop_errors.length > 0) {
+        setDrainNotice(`Drained, but ${result.stop_errors.length} container(s) failed to stop.`);
+      } else {
+        setDrainNotice(result.stopped.length > 0 ? `Drained and stopped ${result.stopped.length} container(s).` : 'Drained.');
+      }
+      if (result.stopped.length > 0) await refreshModels();
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to drain system');
+    } finally {
+      setActionLoading(null);
+    }
+  };
+
+  const handleResume = async () => {
+    setActionLoading('drain');
+    try {
+      await resumeSystem();
+      setSystem((prev) => (prev ? { ...prev, drain: null } : prev));
+      setDrainNotice(null);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to resume');
+    } finally {
+      setActionLoading(null);
+    }
+  };
+
+  const handlePurge = async (entry: ModelTrashEntry) => {
+    setConfirmPurge(null);
+    setActionLoading(entry.id);
+    try {
+      await purgeModelTrash(entry.id);
+      setTrash((prev) => prev.filter((e) => e.id !== entry.id));
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to purge model');
+    } finally {
+      setActionLoading(null);
+    }
+  };
+
   if (loading) return <LoadingSpinner message="Loading system info..." />;
   if (error && !system) return <ErrorAlert message={error} onRetry={fetchData} />;
   if (!system) return null;
@@ -163,6 +243,11 @@ export default function System() {
     ? (disk.used_bytes / disk.total_bytes) * 100
     : 0;
 
+  const drain = system.drain ?? null;
+  const drainButtonColor = drain ? colors.link : colors.dangerText;
+  const idleDrainLabel = drain ? 'Resume' : 'Drain';
+  const drainButtonLabel = actionLoading === 'drain' ? 'Working...' : idleDrainLabel;
+
   // Derive available GPU types for the start dialog
   const availableGpuTypes: string[] = (() => {
     const types = new Set<string>();
@@ -202,6 +287,55 @@ export default function System() {
 
       {error && <ErrorAlert message={error} />}
 
+      {/* Maintenance drain */}
+      <div
+        style={{
+          ...cardStyle,
+          marginBottom: '1.5rem',
+          ...(drain ? { background: colors.warningBannerBg, border: `1px solid ${colors.warningBannerBorder}` } : {}),
+        }}
+      >
+        <div style={{ display: 'flex', alignItems: 'center', justifyContent: 'space-between', gap: '1rem' }}>
+          <div>
+            <h3 style={{ margin: '0 0 0.25rem' }}>Maintenance</h3>
+            <div style={{ fontSize: '0.85rem', color: drain ? colors.warningBannerText : colors.textMuted }}>
+              {drain ? (
+                <>
+                  Draining since {new Date(drain.started_at).toLocaleString()}
+                  {drain.reason && <> — {drain.reason}</>}. New inference requests are refused.
+                </>
+              ) : (
+                'Serving requests normally.'
+              )}
+              {drainNotice && <div style={{ marginTop: '0.25rem' }}>{drainNotice}</div>}
+            </div>
+          </div>
+          <div style={{ display: 'flex', alignItems: 'center', gap: '0.75rem' }}>
+            {!drain && (
+              <label style={{ display: 'flex', alignItems: 'center', gap: '0.35rem', fontSize: '0.85rem', color: colors.textMuted }}>
+                <input type="checkbox" checked={drainStopContainers} onChange={(e) => setDrainStopContainers(e.target.checked)} />
+                Stop containers
+              </label>
+            )}
+            <button
+              onClick={drain ? handleResume : () => setConfirmDrain(true)}
+              disabled={actionLoading === 'drain'}
+              style={{
+                padding: '0.4rem 1rem',
+                background: 'transparent',
+                color: drainButtonColor,
+                border: `1px solid ${drainButtonColor}`,
+                borderRadius: 4,
+                cursor: 'pointer',
+                whiteSpace: 'nowrap',
+              }}
+            >
+              {drainButtonLabel}
+            </button>
+          </div>
+        </div>
+      </div>
+
       {/* Disk usage */}
       <div style={{ ...cardStyle, marginBottom: '1.5rem' }}>
         <h3 style={{ margin: '0 0 0.75rem' }}>Disk Usage — {system.disk.model_path}</h3>
@@ -249,6 +383,9 @@ export default function System() {
         <div style={{ ...cardStyle, marginBottom: '1.5rem' }}>
           <h3 style={{ margin: '0 0 0.5rem' }}>GPU &amp; Backends</h3>
           <div style={{ fontSize: '0.9rem', color: colors.textPrimary }}>
+            <div style={{ marginBottom: '0.25rem' }}>
+              <strong>Host architecture:</strong> {system.arch}
+            </div>
             <div style={{ marginBottom: '0.25rem' }}>
               <strong>Detected GPUs:</strong>{' '}
               {system.gpu.length > 0 ? system.gpu.map(g => g === 'vulkan' ? 'Vulkan' : g).join(', ') : 'None'}
@@ -258,6 +395,45 @@ export default function System() {
               {system.available_backends.map(b => b === 'llamacpp' ? 'llama.cpp' : b).join(', ')}
             </div>
           </div>
+          {system.gpu.length > 0 && (
+            <div style={{ marginTop: '0.75rem', fontSize: '0.85rem' }}>
+              <div style={{ display: 'flex', alignItems: 'center', gap: '0.5rem', marginBottom: '0.35rem' }}>
+                <strong>Passthrough check</strong>
+                <button
+                  onClick={handleGpuProbe}
+                  disabled={actionLoading === 'gpu-probe'}
+                  style={{
+                    padding: '0.2rem 0.6rem',
+                    background: 'transparent',
+                    color: colors.link,
+                    border: `1px solid ${colors.link}`,
+                    borderRadius: 4,
+                    cursor: 'pointer',
+                    fontSize: '0.8rem',
+                  }}
+                >
+                  {actionLoading === 'gpu-probe' ? 'Probing...' : 'Re-run probe'}
+                </button>
+              </div>
+              {(system.gpu_probes ?? []).length === 0 && (
+                <div style={{ color: colors.textMuted }}>Not checked yet.</div>
+              )}
+              {(system.gpu_probes ?? []).map((p: GpuProbe) => (
+                <div key={p.gpu_type} style={{ marginBottom: '0.35rem' }}>
+                  <span style={{ color: p.ok ? colors.successText : colors.dangerText, fontWeight: 600 }}>
+                    {p.gpu_type === 'vulkan' ? 'Vulkan' : p.gpu_type}: {p.ok ? 'OK' : 'Failed'}
+                  </span>
+                  {p.ok && p.devices.length > 0 && (
+                    <span style={{ color: colors.textMuted }}> — {p.devices.join('; ')}</span>
+                  )}
+                  {p.error && (
+                    <div style={{ color: colors.textMuted, fontFamily: 'monospace', whiteSpace: 'pre-wrap' }}>{p.error}</div>
+                  )}
+                  {p.hint && <div style={{ color: colors.warningText }}>{p.hint}</div>}
+                </div>
+              ))}
+            </div>
+          )}
           {gpuMemory.map((gm) => {
             const vramPercent = gm.total_mb > 0 ? (gm.used_mb / gm.total_mb) * 100 : 0;
             const gpuLabel = gm.gpu_type === 'nvidia' ? `NVIDIA GPU ${gm.device_index}` : `AMD GPU ${gm.device_index}`;
@@ -464,6 +640,118 @@ export default function System() {
         </div>
       )}
 
+      {/* Deleted models that can still be restored */}
+      {trash.length > 0 && (
+        <>
+          <h2 style={{ margin: '1.5rem 0 0.75rem' }}>Recently Deleted</h2>
+          <div style={{ overflowX: 'auto' }}>
+            <table style={{ width: '100%', borderCollapse: 'collapse', fontSize: '0.85rem' }}>
+              <thead>
+                <tr style={{ borderBottom: `2px solid ${colors.cardBorder}`, textAlign: 'left' }}>
+                  <th style={{ padding: '0.5rem' }}>Repository</th>
+                  <th style={{ padding: '0.5rem', textAlign: 'right' }}>Size</th>
+                  <th style={{ padding: '0.5rem' }}>Deleted</th>
+                  <th style={{ padding: '0.5rem' }}>Purged after</th>
+                  <th style={{ padding: '0.5rem', textAlign: 'right' }}>Actions</th>
+                </tr>
+              </thead>
+              <tbody>
+                {trash.map((entry) => {
+                  const busy = actionLoading === entry.id;
+                  return (
+                    <tr key={entry.id} style={{ borderBottom: `1px solid ${colors.tableRowBorder}` }}>
+                      <td style={{ padding: '0.5rem', wordBreak: 'break-all' }}>{entry.hf_repo}</td>
+                      <td style={{ padding: '0.5rem', textAlign: 'right', whiteSpace: 'nowrap' }}>
+                        {entry.has_files ? formatBytes(entry.size_bytes) : <span style={{ color: colors.textMuted }}>-</span>}
+                      </td>
+                      <td style={{ padding: '0.5rem', whiteSpace: 'nowrap' }}>{entry.deleted_at}</td>
+                      <td style={{ padding: '0.5rem', whiteSpace: 'nowrap' }}>{entry.purge_after}</td>
+                      <td style={{ padding: '0.5rem', textAlign: 'right', whiteSpace: 'nowrap' }}>
+                        <div style={{ display: 'flex', gap: '0.35rem', justifyContent: 'flex-end' }}>
+                          <button
+                            onClick={() => handleRestore(entry)}
+                            disabled={busy}
+                            style={{
+                              padding: '0.25rem 0.6rem',
+                              background: colors.buttonPrimary,
+                              color: '#fff',
+                              border: 'none',
+                              borderRadius: 6,
+                              cursor: busy ? 'default' : 'pointer',
+                              fontSize: '0.8rem',
+                              opacity: busy ? 0.5 : 1,
+                            }}
+                          >
+                            {busy ? 'Working...' : 'Restore'}
+                          </button>
+                          <button
+                            onClick={() => setConfirmPurge(entry)}
+                            disabled={busy}
+                            style={{
+                              padding: '0.25rem 0.6rem',
+                              background: colors.buttonDanger,
+                              color: '#fff',
+                              border: 'none',
+                              borderRadius: 6,
+                              cursor: busy ? 'default' : 'pointer',
+                              fontSize: '0.8rem',
+                              opacity: busy ? 0.5 : 1,
+                            }}
+                          >
+                            Purge
+                          </button>
+                        </div>
+                      </td>
+                    </tr>
+                  );
+                })}
+              </tbody>
+            </table>
+          </div>
+        </>
+      )}
+
+      {/* Periodic background tasks */}
+      {Object.keys(system.tasks ?? {}).length > 0 && (
+        <>
+          <h2 style={{ margin: '1.5rem 0 0.75rem' }}>Background Tasks</h2>
+          <div style={{ overflowX: 'auto' }}>
+            <table style={{ width: '100%', borderCollapse: 'collapse', fontSize: '0.85rem' }}>
+              <thead>
+                <tr style={{ borderBottom: `2px solid ${colors.cardBorder}`, textAlign: 'left' }}>
+                  <th style={{ padding: '0.5rem' }}>Task</th>
+                  <th style={{ padding: '0.5rem', textAlign: 'right' }}>Every</th>
+                  <th style={{ padding: '0.5rem', textAlign: 'right' }}>Runs</th>
+                  <th style={{ padding: '0.5rem', textAlign: 'right' }}>Failures</th>
+                  <th style={{ padding: '0.5rem' }}>Last run</th>
+                  <th style={{ padding: '0.5rem' }}>Status</th>
+                </tr>
+              </thead>
+              <tbody>
+                {Object.entries(system.tasks ?? {}).map(([name, task]) => {
+                  const idleStatus = task.healthy ? 'OK' : 'Overdue';
+                  const status = task.last_error ?? (task.running ? 'Running' : idleStatus);
+                  return (
+                    <tr key={name} style={{ borderBottom: `1px solid ${colors.tableRowBorder}` }}>
+                      <td style={{ padding: '0.5rem', fontFamily: 'monospace' }}>{name}</td>
+                      <td style={{ padding: '0.5rem', textAlign: 'right' }}>{task.interval_secs}s</td>
+                      <td style={{ padding: '0.5rem', textAlign: 'right' }}>{task.runs}</td>
+                      <td style={{ padding: '0.5rem', textAlign: 'right' }}>{task.failures}</td>
+                      <td style={{ padding: '0.5rem', whiteSpace: 'nowrap' }}>
+                        {task.last_finished_at ?? <span style={{ color: colors.textMuted }}>-</span>}
+                      </td>
+                      <td style={{ padding: '0.5rem', color: task.healthy ? colors.successText : colors.dangerText }}>
+                        {status}
+                      </td>
+                    </tr>
+                  );
+                })}
+              </tbody>
+            </table>
+          </div>
+        </>
+      )}
+
       {startModel && (
         <StartModelDialog
           model={startModel}
@@ -487,7 +775,7 @@ export default function System() {
       {confirmDelete && (
         <ConfirmDialog
           title="Delete Model"
-          message={`Delete ${confirmDelete.hf_repo}? This will stop any running container and remove all downloaded files.`}
+          message={`Delete ${confirmDelete.hf_repo}? This will stop any running container and move the downloaded files to the trash, where they can be restored until purged.`}
           confirmLabel="Delete"
           destructive
           onConfirm={() => handleDelete(confirmDelete)}
@@ -495,6 +783,28 @@ export default function System() {
         />
       )}
 
+      {confirmDrain && (
+        <ConfirmDialog
+          title="Drain for Maintenance"
+          message={`New inference requests will be refused until you resume. In-flight requests are given time to finish${drainStopContainers ? ', then all containers are stopped' : ''}.`}
+          confirmLabel="Drain"
+          destructive
+          onConfirm={handleDrain}
+          onCancel={() => setConfirmDrain(false)}
+        />
+      )}
+
+      {confirmPurge && (
+        <ConfirmDialog
+          title="Purge Model"
+          message={`Permanently remove ${confirmPurge.hf_repo} from the trash? It can no longer be restored.`}
+          confirmLabel="Purge"
+          destructive
+          onConfirm={() => handlePurge(confirmPurge)}
+          onCancel={() => setConfirmPurge(null)}
+        />
+      )}
+
       {overrideDelete && (
         <OverrideDeleteDialog
           model={overrideDelete.model}
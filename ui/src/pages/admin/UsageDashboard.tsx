@@ -181,7 +181,19 @@ export default function UsageDashboard() {
   return (
     <div>
       <div style={{ display: 'flex', justifyContent: 'space-between', alignItems: 'center', marginBottom: '1.5rem' }}>
-        <h1 style={{ margin: 0 }}>Usage Analytics</h1>
+        <div>
+          <h1 style={{ margin: 0 }}>Usage Analytics</h1>
+          {usage.privacy === 'pseudonymize' && (
+            <p style={{ margin: '0.25rem 0 0', color: colors.textMuted, fontSize: '0.85rem' }}>
+              Users are shown under pseudonyms.
+            </p>
+          )}
+          {usage.privacy === 'bucket' && (
+            <p style={{ margin: '0.25rem 0 0', color: colors.textMuted, fontSize: '0.85rem' }}>
+              Users are grouped by how many requests they made.
+            </p>
+          )}
+        </div>
         <div style={{ display: 'flex', gap: '0.5rem' }}>
           {(['hour', 'day', 'week', 'month'] as Period[]).map((p) => (
             <button
@@ -243,7 +255,9 @@ export default function UsageDashboard() {
       {/* Per-user table */}
       {usage.by_user.length > 0 && (
         <div style={{ background: colors.cardBg, border: `1px solid ${colors.cardBorder}`, borderRadius: 8, padding: '1rem' }}>
-          <h3 style={{ margin: '0 0 0.75rem', fontSize: '1rem' }}>Per-User Breakdown</h3>
+          <h3 style={{ margin: '0 0 0.75rem', fontSize: '1rem' }}>
+            {usage.privacy === 'bucket' ? 'Breakdown by Activity' : 'Per-User Breakdown'}
+          </h3>
           <table style={{ width: '100%', borderCollapse: 'collapse' }}>
             <thead>
               <tr>
@@ -257,7 +271,10 @@ export default function UsageDashboard() {
             <tbody>
               {usage.by_user.map((u) => (
                 <tr key={u.user_label}>
-                  <td style={tdStyle}>{u.user_label}</td>
+                  <td style={tdStyle}>
+                    {u.user_label}
+                    {u.users !== undefined && ` (${u.users} user${u.users === 1 ? '' : 's'})`}
+                  </td>
                   <td style={{ ...tdStyle, textAlign: 'right' }}>{formatNumber(u.requests)}</td>
                   <td style={{ ...tdStyle, textAlign: 'right' }}>{formatNumber(u.input_tokens)}</td>
                   <td style={{ ...tdStyle, textAlign: 'right' }}>{formatNumber(u.output_tokens)}</td>
@@ -1,6 +1,6 @@
 import { useState, useEffect, useCallback } from 'react';
-import { getAdminUsers, updateUser } from '../../api';
-import type { AdminUser } from '../../types';
+import { getAdminUsers, updateUser, revokeUserSessions, revokeTokens } from '../../api';
+import type { AdminUser, PriorityTier } from '../../types';
 import { useTheme, tableStyles } from '../../theme';
 import LoadingSpinner from '../../components/common/LoadingSpinner';
 import ErrorAlert from '../../components/common/ErrorAlert';
@@ -19,6 +19,9 @@ export default function Users() {
   const [error, setError] = useState<string | null>(null);
   const [toggling, setToggling] = useState<string | null>(null);
   const [confirmToggle, setConfirmToggle] = useState<AdminUser | null>(null);
+  const [confirmActive, setConfirmActive] = useState<AdminUser | null>(null);
+  const [confirmSignOut, setConfirmSignOut] = useState<AdminUser | null>(null);
+  const [confirmRevokeTokens, setConfirmRevokeTokens] = useState<{ user: AdminUser; count: number } | null>(null);
 
   const { table: tableStyle, th: thStyle, td: tdStyle } = tableStyles(colors);
 
@@ -54,6 +57,71 @@ export default function Users() {
     }
   };
 
+  const handleToggleActive = async (user: AdminUser) => {
+    setConfirmActive(null);
+    setToggling(user.id);
+    try {
+      await updateUser(user.id, { active: !user.active });
+      setUsers((prev) =>
+        prev.map((u) => (u.id === user.id ? { ...u, active: !u.active } : u))
+      );
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to update user');
+    } finally {
+      setToggling(null);
+    }
+  };
+
+  const handleTierChange = async (user: AdminUser, tier: PriorityTier) => {
+    setToggling(user.id);
+    try {
+      await updateUser(user.id, { priority_tier: tier });
+      setUsers((prev) =>
+        prev.map((u) => (u.id === user.id ? { ...u, priority_tier: tier } : u))
+      );
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to update user');
+    } finally {
+      setToggling(null);
+    }
+  };
+
+  const handleSignOut = async (user: AdminUser) => {
+    setConfirmSignOut(null);
+    setToggling(user.id);
+    try {
+      await revokeUserSessions(user.id);
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to sign out user');
+    } finally {
+      setToggling(null);
+    }
+  };
+
+  const handleCountTokens = async (user: AdminUser) => {
+    setToggling(user.id);
+    try {
+      const { count } = await revokeTokens({ user_id: user.id }, true);
+      setConfirmRevokeTokens({ user, count });
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to count tokens');
+    } finally {
+      setToggling(null);
+    }
+  };
+
+  const handleRevokeTokens = async (user: AdminUser) => {
+    setConfirmRevokeTokens(null);
+    setToggling(user.id);
+    try {
+      await revokeTokens({ user_id: user.id });
+    } catch (err) {
+      setError(err instanceof Error ? err.message : 'Failed to revoke tokens');
+    } finally {
+      setToggling(null);
+    }
+  };
+
   if (loading) return <LoadingSpinner message="Loading users..." />;
   if (error) return <ErrorAlert message={error} onRetry={fetchUsers} />;
 
@@ -70,6 +138,7 @@ export default function Users() {
               <th style={thStyle}>Email</th>
               <th style={thStyle}>Display Name</th>
               <th style={thStyle}>Admin</th>
+              <th style={thStyle}>Priority</th>
               <th style={thStyle}>Requests</th>
               <th style={thStyle}>Total Tokens</th>
               <th style={thStyle}>Actions</th>
@@ -94,6 +163,35 @@ export default function Users() {
                   >
                     {user.is_admin ? 'Admin' : 'User'}
                   </span>
+                  {!user.active && (
+                    <span
+                      style={{
+                        display: 'inline-block',
+                        marginLeft: '0.35rem',
+                        padding: '0.2rem 0.6rem',
+                        borderRadius: 12,
+                        fontSize: '0.8rem',
+                        fontWeight: 600,
+                        background: colors.badgeDangerBg,
+                        color: colors.badgeDangerText,
+                      }}
+                    >
+                      Deactivated
+                    </span>
+                  )}
+                </td>
+                <td style={tdStyle}>
+                  <select
+                    aria-label="Priority tier"
+                    value={user.priority_tier}
+                    disabled={toggling === user.id}
+                    onChange={(e) => handleTierChange(user, e.target.value as PriorityTier)}
+                    style={{ background: colors.inputBg, color: colors.textPrimary, borderRadius: 4, padding: '0.2rem 0.4rem' }}
+                  >
+                    <option value="low">Low</option>
+                    <option value="normal">Normal</option>
+                    <option value="high">High</option>
+                  </select>
                 </td>
                 <td style={tdStyle}>{formatNumber(user.usage_summary.total_requests)}</td>
                 <td style={tdStyle}>{formatNumber(user.usage_summary.total_tokens)}</td>
@@ -117,6 +215,61 @@ export default function Users() {
                       return user.is_admin ? 'Remove Admin' : 'Make Admin';
                     })()}
                   </button>
+                  <button
+                    onClick={() => setConfirmActive(user)}
+                    disabled={toggling === user.id}
+                    style={{
+                      marginLeft: '0.4rem',
+                      padding: '0.3rem 0.7rem',
+                      background: user.active ? colors.buttonDanger : colors.successText,
+                      color: '#fff',
+                      border: 'none',
+                      borderRadius: 4,
+                      cursor: toggling === user.id ? 'default' : 'pointer',
+                      fontSize: '0.8rem',
+                      opacity: toggling === user.id ? 0.5 : 1,
+                    }}
+                  >
+                    {user.active ? 'Deactivate' : 'Reactivate'}
+                  </button>
+                  {user.active && (
+                    <button
+                      onClick={() => setConfirmSignOut(user)}
+                      disabled={toggling === user.id}
+                      style={{
+                        marginLeft: '0.4rem',
+                        padding: '0.3rem 0.7rem',
+                        background: 'transparent',
+                        color: colors.dangerText,
+                        border: `1px solid ${colors.dangerText}`,
+                        borderRadius: 4,
+                        cursor: toggling === user.id ? 'default' : 'pointer',
+                        fontSize: '0.8rem',
+                        opacity: toggling === user.id ? 0.5 : 1,
+                      }}
+                    >
+                      Sign Out
+                    </button>
+                  )}
+                  {user.active && (
+                    <button
+                      onClick={() => handleCountTokens(user)}
+                      disabled={toggling === user.id}
+                      style={{
+                        marginLeft: '0.4rem',
+                        padding: '0.3rem 0.7rem',
+                        background: 'transparent',
+                        color: colors.dangerText,
+                        border: `1px solid ${colors.dangerText}`,
+                        borderRadius: 4,
+                        cursor: toggling === user.id ? 'default' : 'pointer',
+                        fontSize: '0.8rem',
+                        opacity: toggling === user.id ? 0.5 : 1,
+                      }}
+                    >
+                      Revoke Tokens
+                    </button>
+                  )}
                 </td>
               </tr>
             ))}
@@ -138,6 +291,47 @@ export default function Users() {
           onCancel={() => setConfirmToggle(null)}
         />
       )}
+
+      {confirmActive && (
+        <ConfirmDialog
+          title={confirmActive.active ? 'Deactivate User' : 'Reactivate User'}
+          message={
+            confirmActive.active
+              ? `Deactivate ${confirmActive.email || confirmActive.display_name || 'this user'}? They are signed out, their API tokens are revoked and they can't sign in again until reactivated.`
+              : `Reactivate ${confirmActive.email || confirmActive.display_name || 'this user'}? Revoked API tokens stay revoked.`
+          }
+          confirmLabel={confirmActive.active ? 'Deactivate' : 'Reactivate'}
+          destructive={confirmActive.active}
+          onConfirm={() => handleToggleActive(confirmActive)}
+          onCancel={() => setConfirmActive(null)}
+        />
+      )}
+
+      {confirmSignOut && (
+        <ConfirmDialog
+          title="Sign Out Everywhere"
+          message={`Sign ${confirmSignOut.email || confirmSignOut.display_name || 'this user'} out of every browser and forget their remembered devices? Their API tokens keep working.`}
+          confirmLabel="Sign Out"
+          destructive
+          onConfirm={() => handleSignOut(confirmSignOut)}
+          onCancel={() => setConfirmSignOut(null)}
+        />
+      )}
+
+      {confirmRevokeTokens && (
+        <ConfirmDialog
+          title="Revoke API Tokens"
+          message={
+            confirmRevokeTokens.count === 0
+              ? `${confirmRevokeTokens.user.email || confirmRevokeTokens.user.display_name || 'This user'} has no active API tokens.`
+              : `Revoke ${confirmRevokeTokens.count} active API token${confirmRevokeTokens.count === 1 ? '' : 's'} of ${confirmRevokeTokens.user.email || confirmRevokeTokens.user.display_name || 'this user'}? Clients using them stop working immediately.`
+          }
+          confirmLabel="Revoke"
+          destructive
+          onConfirm={() => handleRevokeTokens(confirmRevokeTokens.user)}
+          onCancel={() => setConfirmRevokeTokens(null)}
+        />
+      )}
     </div>
   );
 }
@@ -121,7 +121,7 @@ function UserMyReservationsDialog({
                       {r.admin_note || '-'}
                     </td>
                     <td style={tdStyle}>
-                      {(r.status === 'pending' || r.status === 'approved') && (
+                      {(r.status === 'pending' || r.status === 'approved' || r.status === 'waitlisted') && (
                         <button
                           onClick={() => onCancelReservation(r.id)}
                           style={{
@@ -5,6 +5,8 @@ export interface AuthUser {
   email: string | null;
   display_name: string | null;
   is_admin: boolean;
+  /** Scoped admin roles, e.g. `model_admin`; empty for full admins. */
+  roles: string[];
   chat_url: string;
 }
 
@@ -89,11 +91,16 @@ export interface UsageResponse {
 
 // ---- Admin: Usage Analytics ----
 
+/** How users are labelled: names, pseudonyms or request-count bands. */
+export type AnalyticsPrivacy = 'off' | 'pseudonymize' | 'bucket';
+
 export interface AdminUsageByUser {
   user_label: string;
   requests: number;
   input_tokens: number;
   output_tokens: number;
+  /** Users merged into this band (bucket mode only). */
+  users?: number;
 }
 
 export interface AdminUsageTimelinePoint {
@@ -102,14 +109,45 @@ export interface AdminUsageTimelinePoint {
   requests: number;
   input_tokens: number;
   output_tokens: number;
+  users?: number;
 }
 
 export interface AdminUsageResponse {
   summary: UsageSummary;
+  privacy?: AnalyticsPrivacy;
   by_user: AdminUsageByUser[];
   timeline: AdminUsageTimelinePoint[];
 }
 
+export interface PromptDedupTtl {
+  ttl_secs: number;
+  hits: number;
+  hit_rate: number;
+  input_tokens_saved: number;
+  output_tokens_saved: number;
+}
+
+export interface PromptDedupRepeats {
+  repeats: number;
+  repeat_rate: number;
+  distinct: number;
+  input_tokens_saved: number;
+  output_tokens_saved: number;
+  by_ttl: PromptDedupTtl[];
+}
+
+export interface PromptDedupStats {
+  period: string;
+  /** Whether PROMPT_DEDUP_STATS is on; older hashes are still counted. */
+  recording: boolean;
+  requests: number;
+  /** By the full request (minus stream/user fields). */
+  exact: PromptDedupRepeats;
+  /** By the case- and whitespace-folded prompt text. */
+  near: PromptDedupRepeats;
+  by_model: { model_id: string; requests: number; repeats: number; near_repeats: number }[];
+}
+
 // ---- Admin: IdPs ----
 
 export interface IdP {
@@ -120,6 +158,12 @@ export interface IdP {
   scopes: string;
   enabled: boolean;
   created_at: string;
+  /** ID token claim synced to admin/roles at login; null when off. */
+  groups_claim: string | null;
+  /** Group → grants (`admin` or role names). */
+  group_mappings: Record<string, string[]>;
+  /** Whether a SCIM provisioning token has been issued. */
+  scim_enabled: boolean;
 }
 
 export interface IdPCreateRequest {
@@ -128,6 +172,9 @@ export interface IdPCreateRequest {
   client_id: string;
   client_secret: string;
   scopes: string;
+  /** Empty string turns group sync off on update. */
+  groups_claim?: string;
+  group_mappings?: Record<string, string[]>;
 }
 
 // ---- Admin: Categories ----
@@ -137,6 +184,18 @@ export interface Category {
   name: string;
   description: string;
   preferred_model_id: string | null;
+  /** Model kind this category claims for auto-categorization, if any. */
+  auto_kind?: string | null;
+  /** Request log level; null follows the REQUEST_LOG setting. */
+  log_level?: RequestLogLevel | null;
+  /** Requests in flight across the category's models; null = no limit. */
+  max_concurrent?: number | null;
+  /** Requests queued across the category's models; null = no limit. */
+  max_queue_depth?: number | null;
+  /** Seconds a request waits for a slot; null follows queue_timeout_secs. */
+  queue_timeout_secs?: number | null;
+  /** Parallel slots for the category's models when a start doesn't set them. */
+  default_parallel?: number | null;
   created_at: string;
 }
 
@@ -144,8 +203,16 @@ export interface CategoryCreateRequest {
   name: string;
   description: string;
   preferred_model_id: string | null;
+  auto_kind?: string | null;
+  log_level?: RequestLogLevel | null;
+  max_concurrent?: number | null;
+  max_queue_depth?: number | null;
+  queue_timeout_secs?: number | null;
+  default_parallel?: number | null;
 }
 
+export type RequestLogLevel = 'none' | 'metadata' | 'full';
+
 // ---- Admin: Models ----
 
 /**
@@ -167,6 +234,24 @@ export interface RuntimeOverrides {
   extra?: string[];
 }
 
+export type ContextPolicy = 'reject' | 'truncate';
+
+/** Per-model sampling defaults. `{}` means none. */
+export interface SamplingDefaults {
+  temperature?: number;
+  top_p?: number;
+  min_p?: number;
+  repeat_penalty?: number;
+}
+
+/** What a model's backend answered when probed after its last start. */
+export interface ModelCapabilities {
+  endpoints: ('models' | 'chat' | 'completions' | 'embeddings')[];
+  max_context: number | null;
+  embedding_dim: number | null;
+  probed_at: string;
+}
+
 export interface AdminModel {
   id: string;
   hf_repo: string;
@@ -184,16 +269,80 @@ export interface AdminModel {
   n_kv_heads: number | null;
   embedding_length: number | null;
   runtime_overrides: RuntimeOverrides | null;
+  /** Sampling defaults filled into `/v1` completions that don't set them. */
+  default_params?: SamplingDefaults | null;
+  /** What happens to prompts longer than the model's context. */
+  context_policy?: ContextPolicy;
+  architecture: string | null;
+  pipeline_tag: string | null;
+  /** Category auto-categorization suggests for this uncategorized model. */
+  suggested_category_id: string | null;
+  category_suggestion_reason: string | null;
+  /** `null` until the model has been started and probed. */
+  capabilities?: ModelCapabilities | null;
+  /** `false` once the files were evicted from disk for going unused. */
+  downloaded?: boolean;
+  evicted_at?: string | null;
+}
+
+/** An admin-defined model name routed to a model or a category. */
+export interface ModelAlias {
+  id: string;
+  alias: string;
+  model_id: string | null;
+  category_id: string | null;
+  /** The target model's hf_repo or the category's name. */
+  target_name: string | null;
+  created_by: string | null;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface ModelAliasRequest {
+  alias: string;
+  model_id?: string | null;
+  category_id?: string | null;
+}
+
+/** One model classified by auto-categorization. */
+export interface CategorizeOutcome {
+  model_id: string;
+  hf_repo: string;
+  kind: string;
+  reason: string;
+  /** Null when no category claims the detected kind. */
+  category_id: string | null;
+  category_name: string | null;
+  applied: boolean;
+}
+
+/** A deleted model that can still be restored from the trash. */
+export interface ModelTrashEntry {
+  id: string;
+  model_id: string;
+  hf_repo: string;
+  /** False for registered models that had no files on disk. */
+  has_files: boolean;
+  size_bytes: number;
+  deleted_by: string | null;
+  deleted_at: string;
+  purge_after: string;
 }
 
 // ---- Admin: Users ----
 
+export type PriorityTier = 'low' | 'normal' | 'high';
+
 export interface AdminUser {
   id: string;
   idp_id: string;
   email: string | null;
   display_name: string | null;
   is_admin: boolean;
+  /** False once deprovisioned; the user can't sign in or use tokens. */
+  active: boolean;
+  /** Queue priority during contention; `high` outranks everyone on `normal`. */
+  priority_tier: PriorityTier;
   created_at: string;
   usage_summary: {
     total_requests: number;
@@ -203,7 +352,7 @@ export interface AdminUser {
 
 // ---- Reservations ----
 
-export type ReservationStatus = 'pending' | 'approved' | 'active' | 'completed' | 'rejected' | 'cancelled';
+export type ReservationStatus = 'pending' | 'approved' | 'active' | 'completed' | 'rejected' | 'cancelled' | 'waitlisted';
 
 export interface Reservation {
   id: string;
@@ -243,9 +392,24 @@ export interface GateSnapshot {
   in_flight: number;
 }
 
+export interface CategoryGateSnapshot {
+  max_concurrent: number | null;
+  max_queue_depth: number | null;
+  queue_timeout_secs: number | null;
+  in_flight: number;
+  queued: number;
+}
+
+/** Admin-only `/api/user/events` events from the server's event bus. */
+export type BusEvent =
+  | { type: 'model_loaded'; model_id: string; container: string; backend_type: string }
+  | { type: 'download_finished'; hf_repo: string; model_ids: string[] }
+  | { type: 'container_crashed'; model_id: string; reason: string; gave_up: boolean };
+
 export interface MetricsSnapshot {
   gpu_memory: GpuMemory[];
   cpu: CpuInfo | null;
+  memory: HostMemoryInfo | null;
   containers: SystemContainer[];
   queues: Record<string, { depth: number; avg_wait_ms: number }>;
   gates: Record<string, GateSnapshot>;
@@ -259,6 +423,16 @@ export interface CpuInfo {
   num_cores: number;
 }
 
+export interface HostMemoryInfo {
+  total_mb: number;
+  available_mb: number;
+  used_mb: number;
+  used_percent: number;
+  swap_total_mb: number;
+  swap_used_mb: number;
+  critical: boolean;
+}
+
 // ---- Admin: System ----
 
 export interface GpuMemory {
@@ -279,8 +453,24 @@ export interface VramEstimate {
   gpu_used_mb: number;
   gpu_free_mb: number;
   fits: boolean;
+  devices?: VramEstimateDevice[];
+  placement?: VramPlacement;
+}
+
+export interface VramEstimateDevice {
+  device_index: number;
+  gpu_type: string;
+  total_mb: number;
+  used_mb: number;
+  free_mb: number;
+  fits: boolean;
 }
 
+export type VramPlacement =
+  | { strategy: 'single_gpu'; device_index: number }
+  | { strategy: 'tensor_split'; device_indices: number[] }
+  | { strategy: 'does_not_fit' };
+
 export interface SystemInfo {
   disk: {
     model_path: string;
@@ -290,10 +480,74 @@ export interface SystemInfo {
   };
   queues: Record<string, { depth: number; avg_wait_ms: number }>;
   gates: Record<string, GateSnapshot>;
+  category_gates?: Record<string, CategoryGateSnapshot>;
   containers: SystemContainer[];
+  arch: 'amd64' | 'arm64' | 'other';
   gpu: string[];
+  gpu_probes: GpuProbe[];
   gpu_memory: GpuMemory[];
   available_backends: string[];
+  drain?: SystemDrain | null;
+  model_eviction?: ModelEviction;
+  watchdog?: BackendWatchdog;
+  tasks?: Record<string, BackgroundTask>;
+}
+
+/** The `model_evict_unused_days` policy and its last run. */
+export interface ModelEviction {
+  unused_days: number;
+  evicted_models: number;
+  last_run: { ran_at: string; evicted: string[]; freed_bytes: number } | null;
+}
+
+/** The backend watchdog: models it is restarting and models it gave up on. */
+export interface BackendWatchdog {
+  interval_secs: number;
+  max_restarts: number;
+  restarting: { model_id: string; restarts: number }[];
+  alerts: { model_id: string; reason: string; restarts: number; raised_at: string }[];
+}
+
+/** A periodic background task (cleanup, reservation tick, ...) and its last run. */
+export interface BackgroundTask {
+  interval_secs: number;
+  jitter_secs: number;
+  running: boolean;
+  runs: number;
+  failures: number;
+  last_started_at: string | null;
+  last_finished_at: string | null;
+  last_duration_ms: number | null;
+  last_error: string | null;
+  next_run_at: string | null;
+  healthy: boolean;
+}
+
+/** System-wide maintenance drain: new inference requests get a 503. */
+export interface SystemDrain {
+  started_at: string;
+  started_by: string;
+  reason: string | null;
+  retry_after_secs: number;
+}
+
+export interface DrainResult {
+  draining: boolean;
+  drain: SystemDrain;
+  drained: boolean;
+  in_flight: number;
+  queued: number;
+  stopped: string[];
+  stop_errors: { model_id: string; error: string }[];
+}
+
+export interface GpuProbe {
+  gpu_type: string;
+  ok: boolean;
+  devices: string[];
+  error: string | null;
+  hint: string | null;
+  checked_at: string;
 }
 
 export interface SystemContainer {
@@ -302,10 +556,53 @@ export interface SystemContainer {
   healthy: boolean;
   state: string;
   vram_used_mb: number | null;
+  warmup?: BackendWarmup;
+}
+
+export interface BackendWarmup {
+  state: 'pending' | 'warming' | 'warm' | 'failed';
+  slots: number;
+  slots_warmed: number;
+  duration_ms: number | null;
+  error: string | null;
 }
 
 // ---- Admin: Containers ----
 
+export interface ResourceLimits {
+  cpu_limit: number | null;
+  memory_limit_mb: number | null;
+  pids_limit: number | null;
+}
+
+export type IssueSeverity = 'critical' | 'warning' | 'info';
+
+export interface OperatorIssue {
+  kind:
+    | 'disk_full'
+    | 'container_crashed'
+    | 'watchdog_gave_up'
+    | 'repeated_health_failures'
+    | 'cert_expiring'
+    | 'download_stalled'
+    | 'reservations_pending';
+  severity: IssueSeverity;
+  /** Model ID, download ID, certificate path or MODEL_PATH. */
+  resource: string;
+  title: string;
+  action: string;
+  /** Portal path, including the /portal prefix. */
+  link: string;
+  api: string | null;
+}
+
+export interface OperatorIssues {
+  checked_at: string;
+  counts: Record<IssueSeverity, number>;
+  /** Most severe first. */
+  issues: OperatorIssue[];
+}
+
 export interface Container {
   id: string;
   names: string[];
@@ -313,6 +610,7 @@ export interface Container {
   state: string;
   status: string;
   port: number;
+  limits: ResourceLimits;
 }
 
 export interface ContainerStartRequest {
@@ -321,6 +619,59 @@ export interface ContainerStartRequest {
   gpu_type?: string;
   gpu_layers?: number;
   parallel?: number;
+  cpu_limit?: number;
+  memory_limit_mb?: number;
+  pids_limit?: number;
+}
+
+// ---- Admin: Hygiene ----
+
+export interface HygieneToken {
+  id: string;
+  name: string;
+  user_id: string;
+  user_email: string | null;
+  created_at: string;
+  last_used_at: string | null;
+}
+
+export interface HygieneSessions {
+  user_id: string;
+  email: string | null;
+  deactivated_at: string | null;
+  sessions: number;
+  devices: number;
+}
+
+export interface HygieneModel {
+  id: string;
+  hf_repo: string;
+  category_id: string | null;
+  loaded: boolean;
+  created_at: string;
+}
+
+export interface HygieneCategory {
+  id: string;
+  name: string;
+  /** Tokens scoped to the category; it can't be deleted while any exist. */
+  tokens: number;
+  access_mappings: number;
+}
+
+/** Outcome of deleting one model: 200 deleted, 202 awaiting approval, 409 pinned by a token. */
+export interface HygieneModelResult {
+  model_id: string;
+  hf_repo: string;
+  status: number;
+}
+
+export interface HygieneReport {
+  days: number;
+  unused_tokens: HygieneToken[];
+  stale_sessions: HygieneSessions[];
+  unused_models: HygieneModel[];
+  empty_categories: HygieneCategory[];
 }
 
 // ---- OpenAI-compatible Models ----